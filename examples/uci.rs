@@ -0,0 +1,14 @@
+/*****************************
+*  UCI ENGINE BINARY         *
+*  AUTHOR: alviny            *
+*****************************/
+
+// Run with `cargo run --example uci`, or point your GUI at the built binary in target/debug/examples/uci.
+
+use std::io::{stdin, stdout, BufReader};
+
+use alviny_task_3::uci;
+
+fn main() {
+    uci::run(BufReader::new(stdin()), &mut stdout());
+}
@@ -0,0 +1,14 @@
+/*****************************
+*  XBOARD ENGINE BINARY      *
+*  AUTHOR: alviny            *
+*****************************/
+
+// Run with `cargo run --example xboard`, or point your GUI at the built binary in target/debug/examples/xboard.
+
+use std::io::{stdin, stdout};
+
+use alviny_task_3::xboard;
+
+fn main() {
+    xboard::run(stdin().lock(), &mut stdout());
+}
@@ -0,0 +1,64 @@
+"""Tests for the Python bindings. Build them with `maturin develop` first, then run `pytest python/tests`."""
+
+import pytest
+
+import alviny_task_3
+from alviny_task_3 import Game, IllegalMoveError, InvalidFenError
+
+# The Opera Game, Morphy against the Duke of Brunswick and Count Isouard, Paris 1858
+OPERA_GAME = [
+    "e2e4", "e7e5", "g1f3", "d7d6", "d2d4", "c8g4", "d4e5", "g4f3", "d1f3", "d6e5", "f1c4", "g8f6",
+    "f3b3", "d8e7", "b1c3", "c7c6", "c1g5", "b7b5", "c3b5", "c6b5", "c4b5", "b8d7", "e1c1", "a8d8",
+    "d1d7", "d8d7", "h1d1", "e7e6", "b5d7", "f6d7", "b3b8", "d7b8", "d1d8",
+]
+
+
+def test_full_game():
+    game = Game()
+    assert game.status == "InProgress"
+    for ply, uci_move in enumerate(OPERA_GAME):
+        source, target = uci_move[:2], uci_move[2:4]
+        assert target in game.legal_moves()[source], (ply, uci_move)
+        game.make_move(uci_move)
+    assert game.is_check()
+    assert game.status == "WhiteWinsByCheckmate"
+    assert game.legal_moves() == {}
+    assert game.fen == "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+    assert game.board.piece_at("d8") == "R"
+    assert game.board.piece_at("b8") == "n"
+    assert game.board.piece_at("d7") is None
+    with pytest.raises(IllegalMoveError):
+        game.make_move("e8d8")
+
+
+def test_starting_position():
+    game = Game()
+    moves = game.legal_moves()
+    assert sum(len(targets) for targets in moves.values()) == 20
+    assert moves["g1"] == ["f3", "h3"]
+    assert not game.is_check()
+    board = game.board
+    game.make_move("e2e4")
+    assert board.piece_at("e4") is None  # Boards are copies
+    assert game.board.piece_at("e4") == "P"
+
+
+def test_errors():
+    game = Game()
+    with pytest.raises(IllegalMoveError):
+        game.make_move("e2e5")
+    with pytest.raises(ValueError):
+        game.make_move("not a move")
+    with pytest.raises(ValueError):
+        game.board.piece_at("z9")
+    with pytest.raises(InvalidFenError):
+        Game.from_fen("8/8/8 w - - 0 1")
+    assert game.fen == "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    assert issubclass(alviny_task_3.InvalidFenError, ValueError)
+
+
+def test_from_fen_and_promotion():
+    game = Game.from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1")
+    assert game.legal_moves()["a7"] == ["a8"]
+    game.make_move("a7a8n")
+    assert game.board.piece_at("a8") == "N"
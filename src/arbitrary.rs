@@ -0,0 +1,159 @@
+/*****************************
+*  ARBITRARY POSITIONS       *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Random positions for property-based testing with [proptest](https://docs.rs/proptest), with the `proptest` feature.
+
+Board implements proptest's Arbitrary, so `any::<Board>()` generates positions that pass Board::validate(): each side
+has one king, the kings aren't next to each other, no pawn stands on the first or last rank, the side that is not to move
+isn't in check, and castling rights are only given where the king and rook are on their starting squares.
+
+```
+# #[cfg(feature = "proptest")] {
+# use alviny_task_3::{generate_fen, parse_fen, Board};
+# use proptest::prelude::*;
+# use proptest::test_runner::TestRunner;
+let mut runner = TestRunner::default();
+runner.run(&any::<Board>(), |board| {
+    prop_assert_eq!(parse_fen(&generate_fen(&board)), board); // FENs round trip
+    Ok(())
+}).unwrap();
+# }
+```
+*/
+
+use proptest::prelude::*;
+
+use crate::position::{BoardBuilder, CASTLING_SQUARES};
+use crate::{Board, Square};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn square_strategy() -> impl Strategy<Value = u8> {
+    prop_oneof![4 => 0u8..64, 1 => prop::sample::select(vec![0u8, 4, 7, 56, 60, 63])]
+} // Any square, with extra weight on the starting squares of the kings and rooks so that castling comes up
+
+fn build_board(white_king: u8, black_king: u8, pieces: Vec<(u8, char)>, side_to_move: char, castling: String) -> Option<Board> {
+    let (white_king, black_king) = (Square::from_index(white_king)?, Square::from_index(black_king)?);
+    if white_king.distance(black_king) <= 1 {
+        return None;
+    }
+    let mut builder = BoardBuilder::empty();
+    for (index, piece) in pieces {
+        let square = Square::from_index(index)?;
+        let is_back_rank = !(8..56).contains(&index);
+        if square != white_king && square != black_king && !(piece.eq_ignore_ascii_case(&'p') && is_back_rank) {
+            builder.piece(&square.to_algebraic(), piece);
+        }
+    }
+    builder.piece(&white_king.to_algebraic(), 'K').piece(&black_king.to_algebraic(), 'k').side_to_move(side_to_move);
+    let board = builder.build().ok()?;
+    let mut board = board;
+    for (right, king_index, rook_index, king, rook) in CASTLING_SQUARES {
+        let fits = board.pieces().any(|(square, piece)| square.index() == king_index && piece.to_fen_char() == king)
+            && board.pieces().any(|(square, piece)| square.index() == rook_index && piece.to_fen_char() == rook);
+        board.castling_rights.set(right, castling.contains(right) && fits);
+    }
+    Some(board)
+} // Puts the pieces on an empty board, or returns None if the position isn't valid
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Arbitrary for Board {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Board>;
+
+    fn arbitrary_with(_parameters: ()) -> Self::Strategy {
+        let piece = prop::sample::select("PNBRQpnbrq".chars().collect::<Vec<char>>());
+        (
+            square_strategy(),
+            square_strategy(),
+            prop::collection::vec((square_strategy(), piece), 0..20),
+            prop::sample::select(vec!['w', 'b']),
+            "[KQkq]{0,4}",
+        )
+            .prop_filter_map("the position must be valid", |(white_king, black_king, pieces, side_to_move, castling)| {
+                build_board(white_king, black_king, pieces, side_to_move, castling)
+            })
+            .boxed()
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_available_moves, get_legal_move_list, parse_fen, player_is_in_check, try_parse_fen, Game, Move, PieceKind};
+
+    const FEN_SHAPED: &str = "[pnbrqkPNBRQK1-9xX]{0,10}(/[pnbrqkPNBRQK1-9xX]{0,10}){0,8} [wbx-]{0,2} (-|[KQkqx]{1,5}) (-|[a-i][0-9]) [0-9-]{1,4} [0-9-]{1,4}";
+
+    proptest! {
+        #[test]
+        fn test_fen_parsing_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..100)) {
+            let fen = String::from_utf8_lossy(&bytes);
+            let _ = try_parse_fen(&fen);
+            let _ = Game::try_from_fen(&fen);
+            let _ = parse_fen(&fen);
+        }
+        #[test]
+        fn test_fen_shaped_strings_never_panic(fen in FEN_SHAPED) {
+            if let Ok(game) = Game::try_from_fen(&fen) {
+                prop_assert!(game.board.validate().is_ok());
+            }
+            let mut game = Game::new_from_fen(fen.clone()); // Malformed parts are replaced, so this has to cope with anything
+            for legal_move in game.legal_moves().into_iter().take(3) {
+                game.make_uci_move(&legal_move.to_uci());
+            }
+        }
+        #[test]
+        fn test_move_input_never_panics(source in ".{0,4}", target in ".{0,4}", uci_move in ".{0,6}") {
+            let mut game = Game::new();
+            let _ = Square::from_algebraic(&source);
+            let _ = Move::from_uci(&uci_move);
+            let _ = game.explain_move(&source, &target);
+            prop_assert!(!game.make_move(source.clone(), target.clone()) || game.history.len() == 1);
+            let _ = game.make_uci_move(&uci_move);
+            let _ = game.make_move_str(&format!("{}{}", source, uci_move)).map_err(|parse_error| parse_error.to_string());
+        }
+        #[test]
+        fn test_generated_positions_are_valid(board in any::<Board>()) {
+            prop_assert!(board.validate().is_ok());
+            prop_assert_eq!(try_parse_fen(&crate::generate_fen(&board)), Ok(board));
+        }
+        #[test]
+        fn test_no_move_captures_a_king(board in any::<Board>()) {
+            for (color, force_no_check) in [('w', false), ('w', true), ('b', false), ('b', true)] {
+                for (source, targets) in get_available_moves(board.clone(), color, force_no_check) {
+                    for target in targets {
+                        let target_square = Square::from_algebraic(&target).unwrap();
+                        let captured = board.pieces().find(|(square, _piece)| *square == target_square);
+                        prop_assert!(
+                            captured.is_none_or(|(_square, piece)| piece.kind != PieceKind::King),
+                            "{} takes the king on {} in {:?}", source, target, board
+                        );
+                    }
+                }
+            }
+        }
+        #[test]
+        fn test_legal_moves_never_leave_the_king_in_check(board in any::<Board>()) {
+            for (source, target, promotion) in get_legal_move_list(&board) {
+                let mut next_board = board.clone();
+                next_board.play_move(source.clone(), target.clone(), promotion);
+                prop_assert!(!player_is_in_check(&next_board, board.active_player), "{:?} {:?} in {:?}", source, target, board);
+            }
+        }
+    }
+}
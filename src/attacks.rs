@@ -0,0 +1,312 @@
+/*****************************
+*  ATTACKS                   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Attack detection, i.e. whether a square could be captured on by a given side. This is much cheaper than generating
+every move of the enemy and looking for one that lands on the square, which is how check used to be detected.
+
+Attacks ignore pins and whose turn it is: a pinned piece still attacks the squares it could capture on if it were free to move.
+
+Exchange calculations also need the cheapest piece that can capture on a square, and the sliders lined up behind a
+blocker (x-rays), which become attackers once the blocker has captured and left the line.
+*/
+
+use crate::{Board, Piece, Square};
+
+/// The (file, rank) steps of a knight.
+const KNIGHT_STEPS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+/// The (file, rank) steps of a king. The first four are the rook directions, the last four the bishop directions.
+const KING_STEPS: [(i8, i8); 8] = [(0, 1), (1, 0), (0, -1), (-1, 0), (1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+impl Board {
+    /// A function to check whether a square is attacked by one side.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square that might be attacked.
+    /// by_color: char, // The attacking side, 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if any piece of `by_color` attacks the square. Pins are ignored.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.board.is_square_attacked(Square::from_algebraic("f3").unwrap(), 'w')); // by the g1 knight and the g2 pawn
+    /// assert!(!example_game.board.is_square_attacked(Square::from_algebraic("e4").unwrap(), 'w'));
+    /// ```
+    pub fn is_square_attacked(&self, square: Square, by_color: char) -> bool {
+        let is_attacker = |piece: char, kinds: &str| {
+            (by_color == 'w') == piece.is_ascii_uppercase() && kinds.contains(piece.to_ascii_lowercase())
+        };
+        let pawn_rank_step = if by_color == 'w' { -1 } else { 1 }; // White pawns attack upwards, so they stand one rank below
+        for file_step in [-1, 1] {
+            if let Some(piece) = self.piece_at_offset(square, file_step, pawn_rank_step) && is_attacker(piece, "p") {
+                return true;
+            }
+        }
+        for (file_step, rank_step) in KNIGHT_STEPS {
+            if let Some(piece) = self.piece_at_offset(square, file_step, rank_step) && is_attacker(piece, "n") {
+                return true;
+            }
+        }
+        for (direction, (file_step, rank_step)) in KING_STEPS.into_iter().enumerate() {
+            if let Some(piece) = self.piece_at_offset(square, file_step, rank_step) && is_attacker(piece, "k") {
+                return true;
+            }
+            let sliders = if direction < 4 { "rq" } else { "bq" };
+            if let Some((_square, piece)) = self.first_piece_on_ray(square, file_step, rank_step) && is_attacker(piece, sliders) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A function to find every piece of one side that attacks a square.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square that might be attacked.
+    /// by_color: char, // The attacking side, 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the squares of the attacking pieces. Pins are ignored, and a slider standing behind another
+    /// attacker on the same line is not included.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let attackers = example_game.board.attackers_of(Square::from_algebraic("f3").unwrap(), 'w');
+    /// assert_eq!(attackers.len(), 3); // The e2 and g2 pawns and the g1 knight
+    /// ```
+    pub fn attackers_of(&self, square: Square, by_color: char) -> Vec<Square> {
+        let is_attacker = |piece: char, kinds: &str| {
+            (by_color == 'w') == piece.is_ascii_uppercase() && kinds.contains(piece.to_ascii_lowercase())
+        };
+        let mut attackers = vec![];
+        let pawn_rank_step = if by_color == 'w' { -1 } else { 1 };
+        let steps = [(-1, pawn_rank_step, "p"), (1, pawn_rank_step, "p")].into_iter()
+            .chain(KNIGHT_STEPS.into_iter().map(|(file_step, rank_step)| (file_step, rank_step, "n")))
+            .chain(KING_STEPS.into_iter().map(|(file_step, rank_step)| (file_step, rank_step, "k")));
+        for (file_step, rank_step, kinds) in steps {
+            if let Some(attacker) = offset_square(square, file_step, rank_step)
+                && is_attacker(self.piece_on(attacker), kinds) {
+                attackers.push(attacker);
+            }
+        }
+        for (direction, (file_step, rank_step)) in KING_STEPS.into_iter().enumerate() {
+            let sliders = if direction < 4 { "rq" } else { "bq" };
+            if let Some((attacker, piece)) = self.first_piece_on_ray(square, file_step, rank_step) && is_attacker(piece, sliders) {
+                attackers.push(attacker);
+            }
+        }
+        attackers
+    }
+
+    /// A function to find the least valuable piece of one side that attacks a square, i.e. the one to capture with
+    /// first in an exchange. Pins are ignored.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square that might be attacked.
+    /// by_color: char, // The attacking side, 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the square and the piece of the attacker, or None if the square isn't attacked. Pieces
+    /// are valued pawn, knight, bishop, rook, queen, king, from least to most.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, PieceKind, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let (attacker, piece) = example_game.board.least_valuable_attacker(Square::from_algebraic("f3").unwrap(), 'w').unwrap();
+    /// assert_eq!((attacker.to_string(), piece.kind), ("e2".to_string(), PieceKind::Pawn));
+    /// ```
+    pub fn least_valuable_attacker(&self, square: Square, by_color: char) -> Option<(Square, Piece)> {
+        self.attackers_of(square, by_color)
+            .into_iter()
+            .filter_map(|attacker| Piece::from_fen_char(self.piece_on(attacker)).map(|piece| (attacker, piece)))
+            .min_by_key(|(_attacker, piece)| piece.kind as u8)
+    }
+
+    /// A function to find the sliders of one side that attack a square through exactly one blocker, of either color.
+    /// They attack the square as soon as the blocker leaves the line, e.g. a rook doubled behind another rook. Pins
+    /// are ignored.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square that might be attacked.
+    /// by_color: char, // The attacking side, 'w' or 'b'.
+    /// removed: &[Square], // Squares to treat as empty, e.g. the pieces that have already captured in an exchange.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the squares of the sliders. The direct attackers (see docs for attackers_of) are not
+    /// included, unless a removed square stands between them and the square.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new_from_fen("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1".to_string());
+    /// let d5 = Square::from_algebraic("d5").unwrap();
+    /// assert_eq!(example_game.board.xray_attackers(d5, 'w', &[]), [Square::from_algebraic("d1").unwrap()]);
+    /// let d2 = Square::from_algebraic("d2").unwrap();
+    /// assert!(example_game.board.xray_attackers(d5, 'w', &[d2]).is_empty()); // d1 attacks directly once d2 is gone
+    /// ```
+    pub fn xray_attackers(&self, square: Square, by_color: char, removed: &[Square]) -> Vec<Square> {
+        let mut attackers = vec![];
+        for (direction, (file_step, rank_step)) in KING_STEPS.into_iter().enumerate() {
+            let sliders = if direction < 4 { "rq" } else { "bq" };
+            let Some((blocker, _piece)) = self.first_piece_on_ray_skipping(square, file_step, rank_step, removed) else {
+                continue;
+            };
+            if let Some((attacker, piece)) = self.first_piece_on_ray_skipping(blocker, file_step, rank_step, removed)
+                && (by_color == 'w') == piece.is_ascii_uppercase()
+                && sliders.contains(piece.to_ascii_lowercase()) {
+                attackers.push(attacker);
+            }
+        }
+        attackers
+    }
+
+    pub(crate) fn pinned_by(&self, square: Square) -> Option<Square> {
+        let piece = self.piece_on(square);
+        let color = if piece.is_ascii_uppercase() { 'w' } else { 'b' };
+        let king = self.king_square(color).filter(|&king| king != square && piece != '*')?;
+        let (file_distance, rank_distance) = (square.file().index() as i8 - king.file().index() as i8, square.rank().index() as i8 - king.rank().index() as i8);
+        if file_distance != 0 && rank_distance != 0 && file_distance.abs() != rank_distance.abs() {
+            return None;
+        }
+        let (file_step, rank_step) = (file_distance.signum(), rank_distance.signum());
+        let sliders = if file_step == 0 || rank_step == 0 { "rq" } else { "bq" };
+        let (blocker, _piece) = self.first_piece_on_ray(king, file_step, rank_step)?;
+        let (pinner, pinner_piece) = self.first_piece_on_ray(square, file_step, rank_step)?;
+        (blocker == square && pinner_piece.is_ascii_uppercase() != (color == 'w') && sliders.contains(pinner_piece.to_ascii_lowercase())).then_some(pinner)
+    } // The enemy slider that pins the piece on the square to its own king, or None if it isn't absolutely pinned
+
+    fn piece_at_offset(&self, square: Square, file_step: i8, rank_step: i8) -> Option<char> {
+        offset_square(square, file_step, rank_step).map(|square| self.piece_on(square))
+    } // Returns the content of the square (file_step, rank_step) away, or None if that is off the board.
+
+    fn first_piece_on_ray(&self, square: Square, file_step: i8, rank_step: i8) -> Option<(Square, char)> {
+        self.first_piece_on_ray_skipping(square, file_step, rank_step, &[])
+    } // Walks from the square in one direction and returns the first piece found and its square, or None if the ray reaches the edge.
+
+    fn first_piece_on_ray_skipping(&self, square: Square, file_step: i8, rank_step: i8, removed: &[Square]) -> Option<(Square, char)> {
+        let mut distance = 1;
+        while let Some(next) = offset_square(square, file_step * distance, rank_step * distance) {
+            let piece = self.piece_on(next);
+            if piece != '*' && !removed.contains(&next) {
+                return Some((next, piece));
+            }
+            distance += 1;
+        }
+        None
+    } // Like first_piece_on_ray(), treating the removed squares as empty.
+}
+
+fn offset_square(square: Square, file_step: i8, rank_step: i8) -> Option<Square> {
+    let file = square.file().index() as i8 + file_step;
+    let rank = square.rank().index() as i8 + rank_step;
+    if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+        return None;
+    }
+    Square::from_index((rank * 8 + file) as u8)
+} // Returns the square (file_step, rank_step) away, or None if that is off the board.
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_fen, Square};
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_pawn_attacks() {
+        let board = parse_fen("4k3/8/8/3p4/8/8/1P6/4K3 w - - 0 1");
+        assert!(board.is_square_attacked(square("a3"), 'w')); // Captures onto the a-file count too
+        assert!(board.is_square_attacked(square("c3"), 'w'));
+        assert!(!board.is_square_attacked(square("b3"), 'w')); // Pawns don't attack straight ahead
+        assert!(board.is_square_attacked(square("c4"), 'b'));
+        assert!(board.is_square_attacked(square("e4"), 'b'));
+        assert!(!board.is_square_attacked(square("c6"), 'b')); // Pawns don't attack backwards
+    }
+    #[test]
+    fn test_slider_attacks_are_blocked() {
+        let board = parse_fen("4k3/8/8/8/R2p3q/8/8/4K3 w - - 0 1");
+        assert!(board.is_square_attacked(square("d4"), 'w'));
+        assert!(!board.is_square_attacked(square("e4"), 'w')); // Behind the d4 pawn
+        assert!(board.is_square_attacked(square("a8"), 'w'));
+        assert!(board.is_square_attacked(square("e1"), 'b')); // Queen on the h4-e1 diagonal
+        assert!(!board.is_square_attacked(square("c4"), 'b')); // Behind the d4 pawn from the queen's side
+    }
+    #[test]
+    fn test_knight_and_king_attacks() {
+        let board = parse_fen("7k/8/8/8/8/8/8/N6K w - - 0 1");
+        assert!(board.is_square_attacked(square("b3"), 'w'));
+        assert!(board.is_square_attacked(square("c2"), 'w'));
+        assert!(!board.is_square_attacked(square("h3"), 'w')); // No wrapping around the board edge
+        assert!(board.is_square_attacked(square("g7"), 'b'));
+        assert!(!board.is_square_attacked(square("f8"), 'b'));
+    }
+    #[test]
+    fn test_attackers_of() {
+        let board = parse_fen("4k3/8/8/3p4/2P5/1N3B2/8/3RK3 w - - 0 1");
+        let mut attackers = board.attackers_of(square("d5"), 'w');
+        attackers.sort();
+        assert_eq!(attackers, [square("d1"), square("f3"), square("c4")]); // The b3 knight only covers d4
+        assert_eq!(board.attackers_of(square("c4"), 'b'), [square("d5")]);
+        assert!(board.attackers_of(square("h8"), 'w').is_empty());
+    }
+    #[test]
+    fn test_least_valuable_attacker() {
+        let board = parse_fen("4k3/8/8/1N6/Q2p4/2P5/8/4K3 w - - 0 1");
+        assert_eq!(board.attackers_of(square("d4"), 'w').len(), 3);
+        let (attacker, piece) = board.least_valuable_attacker(square("d4"), 'w').unwrap();
+        assert_eq!((attacker, piece.to_fen_char()), (square("c3"), 'P'));
+        let (attacker, piece) = board.least_valuable_attacker(square("a3"), 'w').unwrap(); // The knight and not the queen
+        assert_eq!((attacker, piece.to_fen_char()), (square("b5"), 'N'));
+        assert_eq!(board.least_valuable_attacker(square("e3"), 'b').map(|(attacker, _piece)| attacker), Some(square("d4")));
+        assert_eq!(board.least_valuable_attacker(square("h5"), 'w'), None);
+    }
+    #[test]
+    fn test_xray_attackers() {
+        let board = parse_fen("4k3/8/8/3p4/8/B7/3R4/3R1K2 w - - 0 1");
+        assert_eq!(board.attackers_of(square("d5"), 'w'), [square("d2")]);
+        assert_eq!(board.xray_attackers(square("d5"), 'w', &[]), [square("d1")]); // Only through the front rook
+        assert_eq!(board.xray_attackers(square("d5"), 'w', &[square("d2")]), Vec::<Square>::new());
+        assert_eq!(board.xray_attackers(square("d8"), 'w', &[]), [square("d2")]); // Through the d5 pawn
+        assert_eq!(board.xray_attackers(square("d8"), 'w', &[square("d5")]), [square("d1")]);
+        assert!(board.xray_attackers(square("f8"), 'w', &[]).is_empty()); // The a3 bishop attacks it directly
+        assert!(board.xray_attackers(square("d5"), 'b', &[]).is_empty());
+    }
+    #[test]
+    fn test_pins() {
+        let board = parse_fen("2r1k3/8/8/7b/8/2P5/4N3/2K5 w - - 0 1");
+        assert_eq!(board.pinned_by(square("c3")), Some(square("c8")));
+        assert_eq!(board.pinned_by(square("e2")), None); // The h5 bishop is on the e2 diagonal, but the king isn't
+        assert_eq!(board.pinned_by(square("c1")), None); // The king itself
+        assert_eq!(board.pinned_by(square("d4")), None); // An empty square
+        let shielded = parse_fen("2r1k3/8/2P5/8/8/2N5/8/2K5 w - - 0 1");
+        assert_eq!(shielded.pinned_by(square("c3")), None); // The c6 pawn is in the way
+        let bishop_on_file = parse_fen("2b1k3/8/8/8/8/2N5/8/2K5 w - - 0 1");
+        assert_eq!(bishop_on_file.pinned_by(square("c3")), None); // A bishop can't pin along a file
+    }
+}
@@ -0,0 +1,301 @@
+/*****************************
+*  BINARY GAME FORMAT        *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A compact binary format for storing many games, at two bytes per move instead of the five or more of PGN.
+
+A game is written as:
+```text
+version, // 1 byte, BINARY_VERSION. Readers reject versions they don't know.
+flags, // 1 byte. Bit 0 is set if the game starts from the variant's starting position.
+variant, // 1 byte: 0 for standard chess, 1 for King of the Hill, 2 for Atomic and 3 for Racing Kings (see docs for Variant).
+start FEN, // Only without bit 0: its length in bytes (2 bytes, little-endian), then the FEN in ASCII.
+moves, // 2 bytes per move, little-endian, until the end of the data.
+```
+Each move holds the source square in bits 0-5 and the target square in bits 6-11 (see docs for Square::index, a1 is
+0 and h8 is 63). Bit 14 is set for promotions, with the piece in bits 12-13: 0 for a knight, 1 for a bishop, 2 for a
+rook and 3 for a queen. Castling is the king's move, e.g. e1g1. Bit 15 is reserved and always 0.
+
+The moves are replayed by the rules of the variant when a game is read, so corrupted data is reported rather than
+loaded. Only the variant, the starting position and the moves are kept: annotations and the clock are not. A custom
+variant's rules can't be stored, so its games are written with the variant byte 255, which from_bytes() rejects.
+Version 1 of the format had no variant byte, and its games are still read, as standard chess.
+
+```
+# use alviny_task_3::Game;
+let example_game = Game::from_moves(&["e4", "e5", "Nf3"]).unwrap();
+let bytes = example_game.to_bytes();
+assert_eq!(bytes.len(), 3 + 3 * 2);
+assert_eq!(Game::from_bytes(&bytes).unwrap(), example_game);
+```
+*/
+
+use std::fmt;
+
+use crate::replay::ReplayError;
+use crate::{try_parse_fen, FenError, Game, Move, Square, Variant};
+
+/// The version of the format written by Game::to_bytes().
+pub const BINARY_VERSION: u8 = 2;
+
+const STARTPOS_FLAG: u8 = 1;
+const CUSTOM_VARIANT: u8 = 255;
+const PROMOTION_FLAG: u16 = 1 << 14;
+const PROMOTION_PIECES: [char; 4] = ['n', 'b', 'r', 'q'];
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why binary data can't be read as a game (see docs for Game::from_bytes).
+///
+/// ```text
+/// UnsupportedVersion(u8), // The data was written by a version of the format this crate doesn't know.
+/// UnsupportedVariant(u8), // The variant byte isn't a built-in variant, e.g. a custom variant's 255.
+/// Truncated, // The data ends in the middle of the header or of a move.
+/// InvalidFen(FenError), // The starting position isn't a valid FEN.
+/// InvalidMove(usize), // The move at this ply has a reserved bit set, or a promotion piece without the promotion bit.
+/// IllegalMove(ReplayError), // A move can't be played in its position.
+/// ```
+pub enum BinaryError {
+    UnsupportedVersion(u8),
+    UnsupportedVariant(u8),
+    Truncated,
+    InvalidFen(FenError),
+    InvalidMove(usize),
+    IllegalMove(ReplayError),
+}
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinaryError::UnsupportedVersion(version) => write!(f, "unsupported version {} of the binary format", version),
+            BinaryError::UnsupportedVariant(variant) => write!(f, "unsupported variant {} in the binary format", variant),
+            BinaryError::Truncated => write!(f, "the data ends too early"),
+            BinaryError::InvalidFen(fen_error) => write!(f, "invalid starting position: {}", fen_error),
+            BinaryError::InvalidMove(ply) => write!(f, "the move at ply {} can't be decoded", ply),
+            BinaryError::IllegalMove(replay_error) => write!(f, "{}", replay_error),
+        }
+    }
+}
+impl std::error::Error for BinaryError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn encode_move(chess_move: &Move) -> u16 {
+    let index = |square: &str| Square::from_algebraic(square).map_or(0, |square| square.index() as u16);
+    let mut code = index(&chess_move.source) | (index(&chess_move.target) << 6);
+    if let Some(piece) = chess_move.promotion {
+        let piece_index = PROMOTION_PIECES.iter().position(|promotion| *promotion == piece.to_ascii_lowercase()).unwrap_or(3);
+        code |= PROMOTION_FLAG | ((piece_index as u16) << 12);
+    }
+    code
+} // Packs a move into 16 bits (see module docs)
+
+fn decode_move(code: u16, ply: usize) -> Result<String, BinaryError> {
+    if code & (1 << 15) != 0 || (code & PROMOTION_FLAG == 0 && code & (3 << 12) != 0) {
+        return Err(BinaryError::InvalidMove(ply));
+    }
+    let square = |index: u16| Square::from_index(index as u8).map(|square| square.to_algebraic()).unwrap_or_default();
+    let mut uci_move = format!("{}{}", square(code & 63), square((code >> 6) & 63));
+    if code & PROMOTION_FLAG != 0 {
+        uci_move.push(PROMOTION_PIECES[((code >> 12) & 3) as usize]);
+    }
+    Ok(uci_move)
+} // Unpacks a move into UCI notation
+
+fn encode_variant(variant: &Variant) -> u8 {
+    match variant {
+        Variant::Standard => 0,
+        Variant::KingOfTheHill => 1,
+        Variant::Atomic => 2,
+        Variant::RacingKings => 3,
+        Variant::Custom(_) => CUSTOM_VARIANT,
+    }
+} // The variant byte of the header (see module docs)
+
+fn decode_variant(code: u8) -> Result<Variant, BinaryError> {
+    match code {
+        0 => Ok(Variant::Standard),
+        1 => Ok(Variant::KingOfTheHill),
+        2 => Ok(Variant::Atomic),
+        3 => Ok(Variant::RacingKings),
+        _ => Err(BinaryError::UnsupportedVariant(code)),
+    }
+} // Reads the variant byte of the header
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to write the game in the compact binary format (see module docs).
+    ///
+    /// ## Returns
+    /// This function returns the bytes: three for the header, the starting FEN if it isn't the variant's starting
+    /// position, and two per move.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![BINARY_VERSION];
+        if self.start_fen == Game::new_with_variant(self.variant.clone()).fen {
+            bytes.extend([STARTPOS_FLAG, encode_variant(&self.variant)]);
+        } else {
+            bytes.extend([0, encode_variant(&self.variant)]);
+            bytes.extend((self.start_fen.len() as u16).to_le_bytes());
+            bytes.extend(self.start_fen.as_bytes());
+        }
+        for chess_move in &self.history {
+            bytes.extend(encode_move(chess_move).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// A function to read a game written by to_bytes(), checking every move on the way.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// bytes: &[u8], // One game in the binary format (see module docs).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game after its last move, played by the rules of its variant, or a BinaryError (see
+    /// docs for BinaryError) if the data is corrupted. It never panics, whatever the bytes are.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::binary::BinaryError;
+    /// # use alviny_task_3::Variant;
+    /// assert_eq!(Game::from_bytes(&[2, 1, 0, 12, 7]).unwrap().history.len(), 1); // 1. e4
+    /// assert_eq!(Game::from_bytes(&[2, 1, 2, 12, 7]).unwrap().variant, Variant::Atomic);
+    /// assert_eq!(Game::from_bytes(&[1, 1, 12, 7]).unwrap().history.len(), 1); // Version 1, without the variant byte
+    /// assert_eq!(Game::from_bytes(&[9, 1, 0]), Err(BinaryError::UnsupportedVersion(9)));
+    /// assert_eq!(Game::from_bytes(&[2, 1, 0, 12]), Err(BinaryError::Truncated));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, BinaryError> {
+        let (&version, rest) = bytes.split_first().ok_or(BinaryError::Truncated)?;
+        if version != 1 && version != BINARY_VERSION {
+            return Err(BinaryError::UnsupportedVersion(version));
+        }
+        let (&flags, mut rest) = rest.split_first().ok_or(BinaryError::Truncated)?;
+        let variant = match version {
+            1 => Variant::Standard,
+            _ => {
+                let (&code, after_variant) = rest.split_first().ok_or(BinaryError::Truncated)?;
+                rest = after_variant;
+                decode_variant(code)?
+            }
+        };
+        let mut game = if flags & STARTPOS_FLAG != 0 {
+            Game::new_with_variant(variant)
+        } else {
+            let (length, fen_and_moves) = rest.split_first_chunk::<2>().ok_or(BinaryError::Truncated)?;
+            let (fen, moves) = fen_and_moves.split_at_checked(u16::from_le_bytes(*length) as usize).ok_or(BinaryError::Truncated)?;
+            rest = moves;
+            let fen = String::from_utf8_lossy(fen);
+            try_parse_fen(&fen).map_err(BinaryError::InvalidFen)?.validate().map_err(|error| BinaryError::InvalidFen(FenError::IllegalPosition(error)))?;
+            Game::new_from_fen_with_variant(fen.into_owned(), variant)
+        };
+        let (moves, remainder) = rest.as_chunks::<2>();
+        if !remainder.is_empty() {
+            return Err(BinaryError::Truncated);
+        }
+        for (ply, code) in moves.iter().enumerate() {
+            let uci_move = decode_move(u16::from_le_bytes(*code), ply)?;
+            game.play_uci_moves(&uci_move).map_err(BinaryError::IllegalMove)?;
+        }
+        Ok(game)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn round_trip(game: &Game) {
+        let bytes = game.to_bytes();
+        let read = Game::from_bytes(&bytes).unwrap();
+        assert_eq!(read.history, game.history);
+        assert_eq!(read.start_fen, game.start_fen);
+        assert_eq!(read.fen, game.fen);
+        assert_eq!(read.variant, game.variant);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let castling_and_en_passant = Game::from_uci_moves("e2e4 g8f6 e4e5 d7d5 e5d6 e7d6 g1f3 f8e7 f1c4 e8g8 e1g1").unwrap();
+        assert_eq!(castling_and_en_passant.to_bytes().len(), 3 + 11 * 2);
+        round_trip(&castling_and_en_passant);
+
+        let mut promotions = Game::new_from_fen("1n2k3/P1P5/8/8/8/8/5p1p/K5N1 w - - 0 40".to_string());
+        promotions.play_uci_moves("c7c8n e8f7 a7b8q f2g1r a1a2 h2h1b").unwrap();
+        let bytes = promotions.to_bytes();
+        assert_eq!(bytes[1], 0); // Not the starting position
+        assert_eq!(&bytes[5..5 + promotions.start_fen.len()], promotions.start_fen.as_bytes());
+        round_trip(&promotions);
+
+        round_trip(&Game::new());
+        assert_eq!(Game::new().to_bytes(), [BINARY_VERSION, 1, 0]);
+    }
+    #[test]
+    fn test_variants() {
+        let mut atomic = Game::new_with_variant(Variant::Atomic);
+        atomic.play_uci_moves("g1f3 e7e6 f3g5 d8g5").unwrap();
+        assert_eq!(&atomic.to_bytes()[..3], [BINARY_VERSION, STARTPOS_FLAG, 2]);
+        round_trip(&atomic);
+        assert!(Game::from_bytes(&atomic.to_bytes()).unwrap().fen.starts_with("rnb1kbnr/pppp1ppp/4p3/8/8/8/")); // No queen on g5
+
+        let mut racing_kings = Game::new_with_variant(Variant::RacingKings);
+        racing_kings.play_uci_moves("h2h3").unwrap();
+        round_trip(&racing_kings);
+        let mut hill = Game::new_from_fen_with_variant("4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string(), Variant::KingOfTheHill);
+        hill.play_uci_moves("e1e2 e8e7 e2e3 e7e6 e3e4").unwrap();
+        round_trip(&hill);
+        assert!(Game::from_bytes(&hill.to_bytes()).unwrap().game_status.is_game_over());
+
+        let version_1 = [1, STARTPOS_FLAG, 12, 7]; // 1. e4, written before the variant byte
+        assert_eq!(Game::from_bytes(&version_1).unwrap().fen, Game::from_uci_moves("e2e4").unwrap().fen);
+        assert_eq!(Game::from_bytes(&[BINARY_VERSION, STARTPOS_FLAG, CUSTOM_VARIANT]), Err(BinaryError::UnsupportedVariant(255)));
+        assert_eq!(Game::from_bytes(&[BINARY_VERSION, STARTPOS_FLAG]), Err(BinaryError::Truncated));
+    }
+    #[test]
+    fn test_corrupted_data() {
+        let mut bytes = Game::from_uci_moves("e2e4 e7e5 g1f3").unwrap().to_bytes();
+        bytes[5] = 0; // 2... a1a1
+        assert!(matches!(Game::from_bytes(&bytes), Err(BinaryError::IllegalMove(error)) if error.ply == 1));
+        bytes[6] = 0x80; // The reserved bit
+        assert_eq!(Game::from_bytes(&bytes), Err(BinaryError::InvalidMove(1)));
+        assert_eq!(Game::from_bytes(&bytes[..8]), Err(BinaryError::Truncated));
+        assert_eq!(Game::from_bytes(&[]), Err(BinaryError::Truncated));
+        assert_eq!(Game::from_bytes(&[BINARY_VERSION, 0, 0, 200, 0, b'8']), Err(BinaryError::Truncated)); // A FEN longer than the data
+        assert!(matches!(Game::from_bytes(&[BINARY_VERSION, 0, 0, 1, 0, b'8']), Err(BinaryError::InvalidFen(_))));
+    }
+
+    proptest! {
+        #[test]
+        fn test_random_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok(game) = Game::from_bytes(&bytes) {
+                prop_assert_eq!(Game::from_bytes(&game.to_bytes()).map(|read| read.history), Ok(game.history));
+            }
+        }
+        #[test]
+        fn test_random_moves_never_panic(codes in prop::collection::vec(any::<u16>(), 0..16)) {
+            let mut bytes = vec![BINARY_VERSION, STARTPOS_FLAG, 0];
+            bytes.extend(codes.iter().flat_map(|code| code.to_le_bytes()));
+            let _ = Game::from_bytes(&bytes);
+        }
+    }
+}
@@ -0,0 +1,592 @@
+/*****************************
+*  CASTLING RIGHTS           *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Which castling moves the players are still allowed to make, and whether they have already castled.
+
+A right is lost for good when the king or the rook it belongs to moves, or when the rook is captured. Having the right
+doesn't mean the castling move is legal right now: the squares in between can be occupied or attacked.
+
+Whether a player has castled can't be read from a FEN: a king on g1 without castling rights may have castled or walked
+there. A Game only knows it for the moves it has seen, so a player without castling rights in the starting FEN is
+CastledStatus::Unknown for the whole game.
+
+Chess960 is supported: each right remembers the file of its rook, and castling puts the king and rook on g1 and f1 (c1
+and d1 for the queen side) from wherever they start. In FENs the rights are written in X-FEN: "K" and "Q" for the
+outermost rook on that side of the king, or the file of the rook ("B" for a white rook on b1) if there is another rook
+behind it. Shredder-FEN, which always writes the files ("HAha"), is read too. Castling is written as the king's
+two-square move when the king starts on the e-file and the rook in the corner, as in standard chess, and as the king
+moving onto its own rook otherwise, since the king may move a single square or not at all.
+
+```
+# use alviny_task_3::{Color, Game};
+# use alviny_task_3::castling::{CastleSide, CastledStatus};
+let example_game = Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6 f1c4 f8c5 e1g1").unwrap();
+assert_eq!(example_game.castled(Color::White), CastledStatus::Castled(CastleSide::Kingside));
+assert_eq!(example_game.castled(Color::Black), CastledStatus::NotCastled);
+```
+*/
+
+use std::fmt;
+
+use crate::{get_piece, Board, Color, Move, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent the castling rights of both players. The Default has no rights at all.
+///
+/// ## Attributes
+/// ```text
+/// white_kingside: bool, // 'K' in FEN. White may castle with the rook on the king's right, usually on h1.
+/// white_queenside: bool, // 'Q' in FEN. White may castle with the rook on the king's left, usually on a1.
+/// black_kingside: bool, // 'k' in FEN. Black may castle with the rook on the king's right, usually on h8.
+/// black_queenside: bool, // 'q' in FEN. Black may castle with the rook on the king's left, usually on a8.
+/// rook_files, // The file of each right's rook (see rook_file()). Private, so that it always fits the rights.
+/// ```
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+    rook_files: [u8; 4],
+}
+impl Default for CastlingRights {
+    fn default() -> CastlingRights {
+        CastlingRights { white_kingside: false, white_queenside: false, black_kingside: false, black_queenside: false, rook_files: CORNER_FILES }
+    }
+}
+impl CastlingRights {
+    /// A function to return the rights at the start of a game, where both players may castle to either side.
+    pub fn all() -> CastlingRights {
+        CastlingRights { white_kingside: true, white_queenside: true, black_kingside: true, black_queenside: true, rook_files: CORNER_FILES }
+    }
+
+    /// A function to read castling rights from the castling field of a FEN, with the rooks in the corners. The files of
+    /// Chess960 rights depend on the pieces, so FENs with them are read by parse_fen() and try_parse_fen().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// field: &str, // "-", or some of the letters K, Q, k and q, each at most once, e.g. "KQkq" or "Kq".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(CastlingRights), or None if the field is malformed.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::castling::CastlingRights;
+    /// let rights = CastlingRights::from_fen("Kq").unwrap();
+    /// assert!(rights.white_kingside && rights.black_queenside && !rights.white_queenside);
+    /// assert_eq!(CastlingRights::from_fen("KK"), None);
+    /// ```
+    pub fn from_fen(field: &str) -> Option<CastlingRights> {
+        let mut rights = CastlingRights::default();
+        if field == "-" {
+            return Some(rights);
+        }
+        if field.is_empty() {
+            return None;
+        }
+        for right in field.chars() {
+            if !"KQkq".contains(right) || rights.has(right) {
+                return None;
+            }
+            rights.set(right, true);
+        }
+        Some(rights)
+    }
+
+    /// A function to write the castling field of a FEN without looking at the pieces. Inverse function for from_fen().
+    ///
+    /// ## Returns
+    /// This function returns the rights in the order "KQkq", or "-" if there are none. A right whose rook isn't in the
+    /// corner is written as the file of the rook, as in Shredder-FEN. generate_fen() uses to_fen_for() instead.
+    pub fn to_fen(&self) -> String {
+        let rights = "KQkq"
+            .chars()
+            .filter_map(|right| self.rook_file(right).map(|file| if file == CORNER_FILES[slot(right)] { right } else { file_letter(right, file) }))
+            .collect::<String>();
+        if rights.is_empty() { "-".to_string() } else { rights }
+    }
+
+    /// A function to write the castling field of a FEN for a board, in X-FEN (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The board the rights belong to, to tell which rooks are the outermost.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the rights in the order "KQkq", or "-" if there are none.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{parse_fen, Board};
+    /// let start = Board::chess960_start(0).unwrap(); // BBQNNRKR, with the queen side rooks on f1 and f8
+    /// assert_eq!(start.castling_rights.to_fen_for(&start), "KQkq");
+    /// assert_eq!(start.castling_rights.to_fen(), "KFkf");
+    /// let board = parse_fen("1r2k1r1/8/8/8/8/8/8/RR2K3 w Bg - 0 1");
+    /// assert_eq!(board.castling_rights.to_fen_for(&board), "Bk"); // The a1 rook is outermost, so b1 needs its file
+    /// ```
+    pub fn to_fen_for(&self, board: &Board) -> String {
+        let rights = "KQkq"
+            .chars()
+            .filter_map(|right| {
+                let file = self.rook_file(right)?;
+                Some(if rook_for_letter(right, &board.board_state) == Some((right, file)) { right } else { file_letter(right, file) })
+            })
+            .collect::<String>();
+        if rights.is_empty() { "-".to_string() } else { rights }
+    }
+
+    /// A function to return the file of the rook a right castles with.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// right: char, // 'K', 'Q', 'k' or 'q'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(file) from 0 (the a-file) to 7 (the h-file), or None if the player doesn't have the right.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Board;
+    /// let board = Board::chess960_start(959).unwrap(); // RKRNNQBB
+    /// assert_eq!(board.castling_rights.rook_file('K'), Some(2));
+    /// assert_eq!(board.castling_rights.rook_file('q'), Some(0));
+    /// ```
+    pub fn rook_file(&self, right: char) -> Option<u8> {
+        self.has(right).then(|| self.rook_files[slot(right)])
+    }
+
+    /// A function to check one right, given by its FEN letter.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// right: char, // 'K', 'Q', 'k' or 'q'. Any other character is never a right.
+    ///
+    /// ```
+    pub fn has(&self, right: char) -> bool {
+        match right {
+            'K' => self.white_kingside,
+            'Q' => self.white_queenside,
+            'k' => self.black_kingside,
+            'q' => self.black_queenside,
+            _ => false,
+        }
+    }
+
+    /// A function to give or take away one right, given by its FEN letter. A right that is taken away forgets its
+    /// rook, so a right that is given without one castles with the rook in the corner.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// right: char, // 'K', 'Q', 'k' or 'q'. Any other character is ignored.
+    /// allowed: bool, // Whether the right is given (true) or taken away (false).
+    ///
+    /// ```
+    pub fn set(&mut self, right: char, allowed: bool) {
+        match right {
+            'K' => self.white_kingside = allowed,
+            'Q' => self.white_queenside = allowed,
+            'k' => self.black_kingside = allowed,
+            'q' => self.black_queenside = allowed,
+            _ => return,
+        }
+        if !allowed {
+            self.rook_files[slot(right)] = CORNER_FILES[slot(right)];
+        }
+    }
+
+    pub(crate) fn set_with_rook(&mut self, right: char, file: u8) {
+        self.set(right, true);
+        if self.has(right) {
+            self.rook_files[slot(right)] = file;
+        }
+    } // Gives a right that castles with the rook on a file
+
+    pub(crate) fn from_fen_on_board(field: &str, board_state: &[Vec<char>]) -> Option<CastlingRights> {
+        let mut rights = CastlingRights::default();
+        if field == "-" {
+            return Some(rights);
+        }
+        if field.is_empty() {
+            return None;
+        }
+        for letter in field.chars() {
+            let (right, file) = rook_for_letter(letter, board_state)?;
+            if rights.has(right) {
+                return None;
+            }
+            rights.set_with_rook(right, file);
+        }
+        Some(rights)
+    } // Reads the castling field of a FEN in X-FEN or Shredder-FEN (see module docs), or None if it is malformed
+
+    /// A function to check whether a player may still castle to either side.
+    pub fn can_castle(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside || self.white_queenside,
+            Color::Black => self.black_kingside || self.black_queenside,
+        }
+    }
+
+    /// A function to check whether neither player may castle any more.
+    pub fn is_empty(&self) -> bool {
+        *self == CastlingRights::default()
+    }
+
+    /// A function to return the rights with the colors swapped, so that e.g. "Kq" becomes "Qk".
+    pub fn swapped(&self) -> CastlingRights {
+        let [white_king_rook, white_queen_rook, black_king_rook, black_queen_rook] = self.rook_files;
+        CastlingRights {
+            white_kingside: self.black_kingside,
+            white_queenside: self.black_queenside,
+            black_kingside: self.white_kingside,
+            black_queenside: self.white_queenside,
+            rook_files: [black_king_rook, black_queen_rook, white_king_rook, white_queen_rook],
+        }
+    }
+
+    pub(crate) fn revoke_for_square(&mut self, coords: &[i32], piece: char) {
+        for right in RIGHTS {
+            let (king, row) = if right.is_ascii_uppercase() { ('K', 7) } else { ('k', 0) };
+            if piece == king || self.rook_file(right).is_some_and(|file| coords == [row, file as i32]) {
+                self.set(right, false);
+            }
+        }
+    } // Takes away the rights that depend on the piece on a square, for a move that leaves or lands on it
+
+    pub(crate) fn revoke_unplaced(board: &mut Board) {
+        for right in RIGHTS {
+            if board.castling_rights.has(right) && CastlingMove::for_right(board, right).is_none() {
+                board.castling_rights.set(right, false);
+            }
+        }
+    } // Takes away every right whose king or rook is no longer where the right needs it, e.g. after an atomic explosion
+}
+impl fmt::Display for CastlingRights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent the side a player castled to.
+///
+/// ```text
+/// Kingside, // O-O, with the rook on the h-file.
+/// Queenside, // O-O-O, with the rook on the a-file.
+/// ```
+pub enum CastleSide {
+    Kingside,
+    Queenside,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent whether a player has castled (see docs for Game::castled).
+///
+/// ```text
+/// Castled(CastleSide), // The player castled during the game.
+/// NotCastled, // The player hasn't castled. Known because they still had castling rights at the start of the game.
+/// Unknown, // The game started from a FEN where the player had no castling rights, and they may have castled before it.
+/// ```
+pub enum CastledStatus {
+    Castled(CastleSide),
+    NotCastled,
+    Unknown,
+}
+impl CastledStatus {
+    pub(crate) fn from_rights(rights: &CastlingRights) -> [CastledStatus; 2] {
+        [Color::White, Color::Black].map(|color| if rights.can_castle(color) { CastledStatus::NotCastled } else { CastledStatus::Unknown })
+    } // What a game starting with these rights knows about each player, White first: nothing if they have no rights left
+
+    pub(crate) fn castle_side(board: &Board, chess_move: &Move) -> Option<CastleSide> {
+        let (source, target) = (Square::from_algebraic(&chess_move.source)?, Square::from_algebraic(&chess_move.target)?);
+        CastlingMove::from_move(board, &source.coords(), &target.coords()).map(|castling| castling.side)
+    } // The side a move castles to, if it is a castling move on this board (see docs for CastlingMove::from_move)
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const RIGHTS: [char; 4] = ['K', 'Q', 'k', 'q'];
+const CORNER_FILES: [u8; 4] = [7, 0, 7, 0]; // The files of the rooks of standard chess, in the order of RIGHTS
+
+fn slot(right: char) -> usize {
+    RIGHTS.iter().position(|known| *known == right).unwrap_or(0)
+} // Where a right is kept in CastlingRights.rook_files
+
+fn file_letter(right: char, file: u8) -> char {
+    let letter = (b'a' + file) as char;
+    if right.is_ascii_uppercase() { letter.to_ascii_uppercase() } else { letter }
+} // The Shredder-FEN letter of a right: the file of its rook, uppercase for White
+
+pub(crate) fn rook_for_letter(letter: char, board_state: &[Vec<char>]) -> Option<(char, u8)> {
+    let (king, rook, row) = if letter.is_ascii_uppercase() { ('K', 'R', 7) } else { ('k', 'r', 0) };
+    let home_row = board_state.get(row)?;
+    let king_file = home_row.iter().position(|piece| *piece == king).unwrap_or(4) as u8;
+    let rook_files = (0..8u8).filter(|file| home_row.get(*file as usize) == Some(&rook));
+    let (kingside, queenside) = if letter.is_ascii_uppercase() { ('K', 'Q') } else { ('k', 'q') };
+    match letter.to_ascii_uppercase() {
+        'K' => Some((kingside, rook_files.filter(|file| *file > king_file).max().unwrap_or(7))),
+        'Q' => Some((queenside, rook_files.filter(|file| *file < king_file).min().unwrap_or(0))),
+        file_name @ 'A'..='H' => {
+            let file = file_name as u8 - b'A';
+            match file.cmp(&king_file) {
+                std::cmp::Ordering::Greater => Some((kingside, file)),
+                std::cmp::Ordering::Less => Some((queenside, file)),
+                std::cmp::Ordering::Equal => None,
+            }
+        }
+        _ => None,
+    }
+} // The right and rook file a letter of the castling field stands for. "K" and "Q" are the outermost rook on that side
+// of the king, or the corner if there is none; a file letter names the rook, and its side of the king names the right
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CastlingMove {
+    pub(crate) right: char,
+    pub(crate) side: CastleSide,
+    pub(crate) king: [i32; 2],
+    pub(crate) rook: [i32; 2],
+    pub(crate) king_target: [i32; 2],
+    pub(crate) rook_target: [i32; 2],
+} // Where the king and rook of a castling right stand (as board coords), and where castling puts them: the king on the
+// g-file and the rook on the f-file, or the king on the c-file and the rook on the d-file, whatever the start
+impl CastlingMove {
+    pub(crate) fn for_right(board: &Board, right: char) -> Option<CastlingMove> {
+        let rook_file = board.castling_rights.rook_file(right)? as i32;
+        let (king, rook, row) = if right.is_ascii_uppercase() { ('K', 'R', 7) } else { ('k', 'r', 0) };
+        let king_file = board.board_state.get(row as usize)?.iter().position(|piece| *piece == king)? as i32;
+        let side = if right.eq_ignore_ascii_case(&'k') { CastleSide::Kingside } else { CastleSide::Queenside };
+        let (king_target, rook_target, on_its_side) = match side {
+            CastleSide::Kingside => (6, 5, rook_file > king_file),
+            CastleSide::Queenside => (2, 3, rook_file < king_file),
+        };
+        if !on_its_side || get_piece(board, &[row, rook_file]) != rook {
+            return None;
+        }
+        Some(CastlingMove { right, side, king: [row, king_file], rook: [row, rook_file], king_target: [row, king_target], rook_target: [row, rook_target] })
+    } // The castling a right allows, if the king and its rook are where the right needs them. Nothing else is checked
+
+    pub(crate) fn from_move(board: &Board, source: &[i32], target: &[i32]) -> Option<CastlingMove> {
+        let rights = match get_piece(board, source) {
+            'K' => ['K', 'Q'],
+            'k' => ['k', 'q'],
+            _ => return None,
+        };
+        rights.into_iter().filter_map(|right| CastlingMove::for_right(board, right)).find(|castling| {
+            castling.king.as_slice() == source
+                && (castling.rook.as_slice() == target || (castling.king_target.as_slice() == target && source[1].abs_diff(target[1]) >= 2))
+        })
+    } // The castling a king move stands for: the king moving onto its own rook, or onto the square castling puts it on
+    // when that is too far for a king's step. Legality is left to the caller
+
+    pub(crate) fn encoded_target(&self) -> [i32; 2] {
+        if self.king[1] == 4 && matches!(self.rook[1], 0 | 7) { self.king_target } else { self.rook }
+    } // The target of the move that stands for this castling (see module docs)
+
+    pub(crate) fn squares_to_clear(&self) -> Vec<[i32; 2]> {
+        let span = |from: i32, to: i32| from.min(to)..=from.max(to);
+        let mut files = span(self.king[1], self.king_target[1])
+            .chain(span(self.rook[1], self.rook_target[1]))
+            .filter(|file| *file != self.king[1] && *file != self.rook[1])
+            .collect::<Vec<i32>>();
+        files.sort_by_key(|file| (file.abs_diff(self.king[1]), *file));
+        files.dedup();
+        files.into_iter().map(|file| [self.king[0], file]).collect()
+    } // The squares that must be empty, apart from the king and rook themselves, closest to the king first
+
+    pub(crate) fn passed_squares(&self) -> Vec<[i32; 2]> {
+        let step = (self.king_target[1] - self.king[1]).signum();
+        (1..self.king[1].abs_diff(self.king_target[1]) as i32).map(|distance| [self.king[0], self.king[1] + step * distance]).collect()
+    } // The squares the king passes over on the way to its target, closest to the king first
+
+    pub(crate) fn passes_attacked_square(&self, board: &mut Board, in_check: impl Fn(&Board) -> bool) -> bool {
+        let king = get_piece(board, &self.king);
+        board.set_piece(&self.king, '*');
+        let attacked = self.passed_squares().into_iter().any(|square| {
+            let previous = get_piece(board, &square);
+            board.set_piece(&square, king);
+            let attacked = in_check(board);
+            board.set_piece(&square, previous);
+            attacked
+        });
+        board.set_piece(&self.king, king);
+        attacked
+    } // Whether the king would be in check on a square it passes over, with the castling rook still in place
+
+    pub(crate) fn play(&self, board: &mut Board) {
+        let (king, rook) = (get_piece(board, &self.king), get_piece(board, &self.rook));
+        board.set_piece(&self.king, '*');
+        board.set_piece(&self.rook, '*');
+        board.set_piece(&self.king_target, king);
+        board.set_piece(&self.rook_target, rook);
+        board.castling_rights.revoke_for_square(&self.king, king);
+        board.en_passant_square = "-".to_string();
+        board.halfmove_counter += 1;
+    } // Moves the king and rook, and does the bookkeeping of a quiet king move
+}
+
+impl Board {
+    pub(crate) fn castling_move(&self, side: CastleSide) -> Option<Move> {
+        let right = match (self.active_player, side) {
+            ('w', CastleSide::Kingside) => 'K',
+            ('w', CastleSide::Queenside) => 'Q',
+            (_, CastleSide::Kingside) => 'k',
+            (_, CastleSide::Queenside) => 'q',
+        };
+        let castling = CastlingMove::for_right(self, right)?;
+        Some(Move {
+            source: Square::from_coords(&castling.king).to_algebraic(),
+            target: Square::from_coords(&castling.encoded_target()).to_algebraic(),
+            promotion: None,
+        })
+    } // The move that castles to a side for the player to move, if they have the right. Whether it is legal isn't checked
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::MoveDiagnosis;
+    use crate::Game;
+
+    #[test]
+    fn test_fen_round_trip() {
+        for field in ["KQkq", "KQ", "kq", "Kq", "Qk", "K", "q", "-"] {
+            assert_eq!(CastlingRights::from_fen(field).unwrap().to_fen(), field);
+        }
+        assert_eq!(CastlingRights::from_fen("qkQK").unwrap().to_fen(), "KQkq");
+        for field in ["", "KQkqK", "X", "K-"] {
+            assert_eq!(CastlingRights::from_fen(field), None, "{}", field);
+        }
+        assert_eq!(CastlingRights::from_fen("Kq").unwrap().swapped().to_fen(), "Qk");
+    }
+    #[test]
+    fn test_rights_follow_the_moves() {
+        let mut test_game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        assert_eq!(test_game.castling_rights(), CastlingRights::all());
+        assert!(test_game.make_uci_move("h1h7")); // The rook leaves h1
+        assert_eq!(test_game.castling_rights().to_fen(), "Qkq");
+        assert!(test_game.make_uci_move("a8a1")); // Both queen side rooks are gone: one moved, one was captured
+        assert_eq!(test_game.castling_rights().to_fen(), "k");
+        assert!(!test_game.castling_rights().can_castle(Color::White));
+        assert!(test_game.make_uci_move("e1d2"));
+        assert!(test_game.make_uci_move("e8d8")); // The king moves
+        assert!(test_game.castling_rights().is_empty());
+        assert!(test_game.fen.contains(" w - - "));
+
+        let mut capture = Game::new_from_fen("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1".to_string());
+        assert!(capture.make_uci_move("g2h1")); // The bishop takes the rook on h1
+        assert_eq!(capture.castling_rights().to_fen(), "Qkq");
+        assert!(!capture.make_uci_move("e1g1"));
+    }
+    #[test]
+    fn test_castled_through_undo() {
+        let mut test_game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        assert_eq!(test_game.castled(Color::White), CastledStatus::NotCastled);
+        assert!(test_game.make_uci_move("e1g1"));
+        assert!(test_game.make_uci_move("e8c8"));
+        assert_eq!(test_game.castled(Color::White), CastledStatus::Castled(CastleSide::Kingside));
+        assert_eq!(test_game.castled(Color::Black), CastledStatus::Castled(CastleSide::Queenside));
+        test_game.undo_move();
+        assert_eq!(test_game.castled(Color::Black), CastledStatus::NotCastled);
+        assert_eq!(test_game.castled(Color::White), CastledStatus::Castled(CastleSide::Kingside));
+        assert!(test_game.make_uci_move("e8e7")); // A king move isn't castling, and ends the chance to
+        assert_eq!(test_game.castled(Color::Black), CastledStatus::NotCastled);
+        test_game.undo_move();
+        test_game.undo_move();
+        assert_eq!(test_game.castled(Color::White), CastledStatus::NotCastled);
+
+        let mut successor = test_game.clone();
+        assert!(successor.make_uci_move("e1c1"));
+        let successor = successor.with_move_using(&Move::from_uci("a8a1").unwrap(), crate::successor::SuccessorOptions { keep_history: false, update_status: true }).unwrap();
+        assert_eq!(successor.castled(Color::White), CastledStatus::Castled(CastleSide::Queenside)); // Not forgotten with the history
+
+        let mid_game = Game::new_from_fen("r4rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1".to_string());
+        assert_eq!(mid_game.castled(Color::Black), CastledStatus::Unknown); // Castled or walked, the FEN can't tell
+        assert_eq!(mid_game.castled(Color::White), CastledStatus::NotCastled);
+        assert_eq!(Game::new().castled(Color::Black), CastledStatus::NotCastled);
+    }
+    #[test]
+    fn test_chess960_rights_in_fens() {
+        for (fen, x_fen) in [
+            ("rk2r3/8/8/8/8/8/8/RK2R3 w EAea - 0 1", "KQkq"), // Shredder-FEN, with the king on b1
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1", "KQkq"),
+            ("1r2k1r1/8/8/8/8/8/8/RR2K3 w Bg - 0 1", "Bk"), // The a1 rook is outermost, so b1 is written by its file
+            ("4k3/8/8/8/8/8/8/1R2K2R w KQ - 0 1", "KQ"),
+        ] {
+            let board = crate::try_parse_fen(fen).unwrap();
+            assert_eq!(board.validate(), Ok(()), "{}", fen);
+            assert_eq!(crate::generate_fen(&board).split(' ').nth(2), Some(x_fen), "{}", fen);
+            assert_eq!(crate::try_parse_fen(&crate::generate_fen(&board)), Ok(board.clone()));
+        }
+        let board = crate::parse_fen("4k3/8/8/8/8/8/8/1R2K2R w KQ - 0 1");
+        assert_eq!((board.castling_rights.rook_file('K'), board.castling_rights.rook_file('Q')), (Some(7), Some(1)));
+        assert_eq!(board.castling_rights.to_fen(), "KB");
+        for fen in ["4k3/8/8/8/8/8/8/R3K2R w E - 0 1", "4k3/8/8/8/8/8/8/R3K2R w HH - 0 1", "4k3/8/8/8/8/8/8/R3K2R w KX - 0 1"] {
+            assert!(crate::try_parse_fen(fen).is_err(), "{}", fen); // The king's own file, a right twice, and not a right
+        }
+        let mut rights = CastlingRights::default();
+        rights.set_with_rook('K', 5);
+        assert_eq!(rights.swapped().rook_file('k'), Some(5));
+        rights.set('K', false);
+        assert_eq!(rights, CastlingRights::default()); // The rook is forgotten with the right
+    }
+    #[test]
+    fn test_chess960_castling() {
+        let mut queen_side = Game::new_from_fen("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BB3RKR w KQkq - 0 1".to_string()); // Start 0
+        let castling = Move::from_uci("g1f1").unwrap();
+        assert!(queen_side.legal_moves().contains(&castling)); // The king takes its own rook
+        assert!(!queen_side.legal_moves().contains(&Move::from_uci("g1h1").unwrap())); // O-O needs f1, where the other rook is
+        assert_eq!(queen_side.to_san(&castling), Some("O-O-O".to_string()));
+        let before = queen_side.clone();
+        assert!(queen_side.make_uci_move("g1c1")); // Dropping the king where castling puts it works too
+        assert_eq!(queen_side.board.board_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBKR3R");
+        assert_eq!(queen_side.history.last().map(|played| played.to_uci()), Some("g1f1".to_string()));
+        assert_eq!(queen_side.castled(Color::White), CastledStatus::Castled(CastleSide::Queenside));
+        assert!(queen_side.fen.contains(" b kq - 1 1"));
+        queen_side.undo_move();
+        assert_eq!((queen_side.fen.clone(), queen_side.castled(Color::White)), (before.fen, CastledStatus::NotCastled));
+
+        let mut king_side = Game::new_from_fen("1r2r1k1/8/8/8/8/8/8/1R4KR w KQ - 0 1".to_string());
+        let mut undoable = king_side.board.clone();
+        let undo = undoable.make_move_undoable(&Move::from_uci("g1h1").unwrap());
+        assert_eq!(undoable.board_fen(), "1r2r1k1/8/8/8/8/8/8/1R3RK1"); // The king doesn't move at all
+        assert_eq!(undo.captured(), None);
+        undoable.unmake(undo);
+        assert_eq!(undoable, king_side.board);
+        assert_eq!(
+            king_side.explain_move("g1", "b1"),
+            MoveDiagnosis::CastlingThroughCheck { square: Square::from_algebraic("e1").unwrap(), attacker: Square::from_algebraic("e8").unwrap(), attacker_kind: crate::PieceKind::Rook }
+        );
+        assert_eq!(king_side.make_move_str("O-O-O"), Ok(false));
+        assert_eq!(king_side.make_move_str("O-O"), Ok(true));
+        assert_eq!(king_side.board.board_fen(), "1r2r1k1/8/8/8/8/8/8/1R3RK1");
+
+        let mut e_file = Game::new_from_fen("4k3/8/8/8/8/8/8/1R2K2R w KQ - 0 1".to_string());
+        let castling_moves = e_file.legal_moves().into_iter().filter(|legal_move| CastledStatus::castle_side(&e_file.board, legal_move).is_some());
+        assert_eq!(castling_moves.map(|legal_move| legal_move.to_uci()).collect::<Vec<String>>(), ["e1g1", "e1b1"]);
+        assert_eq!(e_file.parse_san("O-O-O"), Ok(Move::from_uci("e1b1").unwrap()));
+        assert!(e_file.make_uci_move("e1c1"));
+        assert_eq!(e_file.board.board_fen(), "4k3/8/8/8/8/8/8/2KR3R");
+    }
+}
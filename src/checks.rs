@@ -0,0 +1,207 @@
+/*****************************
+*  CHECKS                    *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Whether a move gives check, decided before the move is made, e.g. for move ordering in a search or for the "+" of
+SAN, and what kind of check it is: given by the moved piece, uncovered by it, or both.
+
+The move is played on a scratch copy of the Board, which handles castling, en passant and promotions the way
+make_move() does, and the enemy king is then looked up in the attack tables. Nothing else is computed: no legal move
+lists, no FEN and no game status, so this is much cheaper than Game::with_move().
+
+```
+# use alviny_task_3::{Game, Move};
+let example_game = Game::from_uci_moves("e2e4 e7e5 f1c4 b8c6 d1h5 g8f6").unwrap();
+assert!(example_game.gives_check(&Move::from_uci("h5f7").unwrap())); // Scholar's mate
+assert!(!example_game.gives_check(&Move::from_uci("h5h4").unwrap()));
+```
+*/
+
+use crate::{get_board_coords, Board, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent the kind of check a move gives (see docs for Game::check_kind).
+///
+/// ```text
+/// None, // The move doesn't give check.
+/// Direct, // The moved piece gives check, e.g. a queen moving next to the king or a castled rook.
+/// Discovered, // Another piece gives check, uncovered by the move.
+/// Double, // Two pieces give check at once. Only a king move gets out of it.
+/// ```
+pub enum CheckKind {
+    None,
+    Direct,
+    Discovered,
+    Double,
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+pub(crate) fn scratch_board(board: &Board, chess_move: &Move) -> Option<Board> {
+    let source = get_board_coords(chess_move.source.clone());
+    let target = get_board_coords(chess_move.target.clone());
+    if [&source, &target].iter().any(|coords| coords.iter().any(|coord| !(0..8).contains(coord))) {
+        return None;
+    }
+    let piece = board.board_state[source[0] as usize][source[1] as usize];
+    if piece == '*' || piece.is_ascii_uppercase() != (board.active_player == 'w') {
+        return None;
+    }
+    let mut next_board = board.clone();
+    next_board.play_move(source, target, chess_move.promotion);
+    Some(next_board)
+} // Plays a move of the side to move on a copy of the board, without checking that it is legal. None if the squares
+// aren't on the board or the source has no piece of the side to move
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to check whether a move would put the opponent in check, without making it.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move, assumed to be legal. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the opponent's king is attacked after the move, directly by the moved piece or by
+    /// a piece the move uncovers, e.g. the rook of a castling move or a slider behind a pawn captured en passant.
+    /// Returns false if the source square has no piece of the side to move. Legality is not checked, so the answer
+    /// for an illegal move is meaningless.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::new_from_fen("3k4/8/8/8/8/8/3B4/3RK3 w - - 0 1".to_string());
+    /// assert!(example_game.gives_check(&Move::from_uci("d2g5").unwrap())); // Discovered by the rook
+    /// ```
+    pub fn gives_check(&self, chess_move: &Move) -> bool {
+        let Some(next_board) = scratch_board(&self.board, chess_move) else {
+            return false;
+        };
+        let (mover, opponent) = if self.board.active_player == 'w' { ('w', 'b') } else { ('b', 'w') };
+        next_board.king_square(opponent).is_some_and(|king| next_board.is_square_attacked(king, mover))
+    }
+
+    /// A function to classify the check a move gives, without making it, by the pieces that attack the opponent's king
+    /// after the move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move, assumed to be legal (see docs for gives_check).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns CheckKind::Double if two pieces give check, CheckKind::Direct if one does and it is a
+    /// piece the move put on its square (the moved piece, the promoted piece or the castled rook),
+    /// CheckKind::Discovered if one does and it is any other piece, and CheckKind::None otherwise.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// # use alviny_task_3::checks::CheckKind;
+    /// let example_game = Game::new_from_fen("4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1".to_string());
+    /// assert_eq!(example_game.check_kind(&Move::from_uci("e2d3").unwrap()), CheckKind::Discovered);
+    /// assert_eq!(example_game.check_kind(&Move::from_uci("e2b5").unwrap()), CheckKind::Double);
+    /// assert_eq!(example_game.check_kind(&Move::from_uci("g1h1").unwrap()), CheckKind::None); // The bishop still blocks the rook
+    /// ```
+    pub fn check_kind(&self, chess_move: &Move) -> CheckKind {
+        let Some(next_board) = scratch_board(&self.board, chess_move) else {
+            return CheckKind::None;
+        };
+        let (mover, opponent) = if self.board.active_player == 'w' { ('w', 'b') } else { ('b', 'w') };
+        let Some(king) = next_board.king_square(opponent) else {
+            return CheckKind::None;
+        };
+        let placed = self.board.diff(&next_board).into_iter().filter(|change| change.after.is_some()).map(|change| change.square).collect::<Vec<_>>();
+        match next_board.attackers_of(king, mover)[..] {
+            [] => CheckKind::None,
+            [attacker] if placed.contains(&attacker) => CheckKind::Direct,
+            [_attacker] => CheckKind::Discovered,
+            _ => CheckKind::Double,
+        }
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gives_check() {
+        let cases = [
+            // (FEN, move, gives check)
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", true), // Direct
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a7", false),
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "e2c3", true), // Discovered by the rook
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "e1f1", false),
+            ("8/8/8/1k1pP2R/8/8/8/4K3 w - d6 0 1", "e5d6", true), // En passant uncovers the h5 rook
+            ("8/8/8/k2pP3/8/8/8/4K2Q w - d6 0 1", "e5d6", false),
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", true), // The castled rook checks on f1
+            ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", "e1c1", true), // On d1
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", false),
+            ("3k4/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", true), // Promotion
+            ("3k4/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n", false),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n", false),
+            ("2k5/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8r", true),
+            ("7k/8/8/8/8/8/6p1/K7 b - - 0 1", "g2g1q", true), // Black promotes with check along the first rank
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "e8e7", false), // Not White's piece
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "c3c8", false), // Empty square
+        ];
+        for (fen, uci_move, gives_check) in cases {
+            let game = Game::new_from_fen(fen.to_string());
+            let chess_move = Move::from_uci(uci_move).unwrap();
+            assert_eq!(game.gives_check(&chess_move), gives_check, "{} in {}", uci_move, fen);
+            if let Ok(next_game) = game.with_move(&chess_move) {
+                assert_eq!(next_game.checks.contains(&true), gives_check, "{} in {}", uci_move, fen); // Agrees with make_move()
+            }
+        }
+    }
+    #[test]
+    fn test_check_kinds() {
+        let cases = [
+            // (FEN, move, kind)
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", CheckKind::Direct),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a7", CheckKind::None),
+            // Réti–Tartakower, Vienna 1910: 10. Bg5++, and mate follows
+            ("rnbk1b1r/pp3ppp/2p5/4q3/4n3/8/PPPB1PPP/2KR1BNR w - - 0 10", "d2g5", CheckKind::Double),
+            ("rnbk1b1r/pp3ppp/2p5/4q3/4n3/8/PPPB1PPP/2KR1BNR w - - 0 10", "d2f4", CheckKind::Discovered),
+            ("rnbk1b1r/pp3ppp/2p5/4q3/4n3/8/PPPB1PPP/2KR1BNR w - - 0 10", "d2b4", CheckKind::Discovered),
+            ("rnbk1b1r/pp3ppp/2p5/4q3/4n3/8/PPPB1PPP/2KR1BNR w - - 0 10", "d1e1", CheckKind::None),
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "e2d4", CheckKind::Discovered),
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "e2f4", CheckKind::Discovered),
+            ("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1", "e4d6", CheckKind::Double), // Discovered and direct
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", CheckKind::Direct), // The castled rook
+            ("8/8/8/1k1pP2R/8/8/8/4K3 w - d6 0 1", "e5d6", CheckKind::Discovered), // En passant
+            ("2k5/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8r", CheckKind::Direct), // The promoted rook
+        ];
+        for (fen, uci_move, kind) in cases {
+            let game = Game::new_from_fen(fen.to_string());
+            let chess_move = Move::from_uci(uci_move).unwrap();
+            assert_eq!(game.check_kind(&chess_move), kind, "{} in {}", uci_move, fen);
+            assert_eq!(game.gives_check(&chess_move), kind != CheckKind::None, "{} in {}", uci_move, fen);
+        }
+        let reti = Game::from_uci_moves("e2e4 c7c6 d2d4 d7d5 b1c3 d5e4 c3e4 g8f6 d1d3 e7e5 d4e5 d8a5 c1d2 a5e5 e1c1 f6e4 d3d8 e8d8").unwrap();
+        assert_eq!(reti.check_kind(&Move::from_uci("d2g5").unwrap()), CheckKind::Double); // The same position, reached by the game
+    }
+}
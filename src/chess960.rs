@@ -0,0 +1,199 @@
+/*****************************
+*  CHESS960 STARTS           *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The 960 starting positions of Chess960 (Fischer Random Chess), numbered 0 to 959 with the standard scheme, so that
+position 518 is the classical setup.
+
+The pieces of White's back rank are placed in a fixed order, each on a square chosen by the index:
+```text
+index % 4, // The light-squared bishop: b1, d1, f1 or h1.
+index / 4 % 4, // The dark-squared bishop: a1, c1, e1 or g1.
+index / 16 % 6, // The queen, on one of the 6 squares left.
+index / 96, // The two knights, on one of the 10 pairs of the 5 squares left.
+```
+The rook, king and rook then fill the last three squares from left to right, so the king is always between the rooks.
+Black mirrors White, and the pawns stand on their usual squares.
+
+Every start comes with all four castling rights, each on the file of its rook, and castling puts the king and rook on
+g1 and f1 or c1 and d1 as in standard chess (see the castling module docs for how the moves and rights are written).
+
+```
+# use alviny_task_3::{Game, Move};
+let mut example_game = Game::new_from_fen("rk5r/8/8/8/8/8/8/RK5R w KQkq - 0 1".to_string());
+let castling = Move::from_uci("b1a1").unwrap(); // The king moves onto its own rook
+assert_eq!(example_game.to_san(&castling), Some("O-O-O".to_string()));
+assert!(example_game.make_uci_move("b1a1"));
+assert_eq!(example_game.board.board_fen(), "rk5r/8/8/8/8/8/8/2KR3R"); // The king one square right, the rook over it
+```
+*/
+
+use crate::position::{BoardMeta, PositionError};
+use crate::{Board, Piece};
+
+/// The number of Chess960 starting positions.
+pub const CHESS960_POSITIONS: u16 = 960;
+/// The index of the classical starting position.
+pub const CLASSICAL_INDEX: u16 = 518;
+
+const KNIGHT_PAIRS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn back_rank(index: u16) -> [char; 8] {
+    let mut rank = ['*'; 8];
+    let index = index as usize;
+    rank[index % 4 * 2 + 1] = 'B';
+    rank[index / 4 % 4 * 2] = 'B';
+    let mut place = |piece: char, nth_empty: usize| {
+        let file = (0..8).filter(|file| rank[*file] == '*').nth(nth_empty).expect("there are enough empty squares");
+        rank[file] = piece;
+    };
+    place('Q', index / 16 % 6);
+    let (first_knight, second_knight) = KNIGHT_PAIRS[index / 96];
+    place('N', second_knight); // The second first, so that the first knight doesn't shift it
+    place('N', first_knight);
+    for piece in ['R', 'K', 'R'] {
+        place(piece, 0);
+    }
+    rank
+} // White's back rank from a1 to h1 for an index below 960 (see module docs)
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to set up a Chess960 starting position by its index (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// index: u16, // The number of the position, from 0 to 959.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Board with White to move and every castling right, or PositionError::InvalidChess960Index
+    /// if the index is 960 or more.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Board};
+    /// assert_eq!(generate_fen(&Board::chess960_start(518).unwrap()), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    /// let start = Board::chess960_start(0).unwrap();
+    /// assert_eq!(generate_fen(&start), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+    /// assert_eq!(start.castling_rights.rook_file('Q'), Some(5)); // The rook on f1
+    /// assert!(Board::chess960_start(960).is_err());
+    /// ```
+    pub fn chess960_start(index: u16) -> Result<Board, PositionError> {
+        if index >= CHESS960_POSITIONS {
+            return Err(PositionError::InvalidChess960Index(index));
+        }
+        let white_pieces = back_rank(index).map(Piece::from_fen_char);
+        let mut array = [[None; 8]; 8];
+        array[0] = white_pieces.map(|piece| piece.map(|piece| Piece { color: 'b', ..piece }));
+        array[1] = [Piece::from_fen_char('p'); 8];
+        array[6] = [Piece::from_fen_char('P'); 8];
+        array[7] = white_pieces;
+        let mut board = Board::from_array(array, BoardMeta::default());
+        let rook_files = (0..8u8).filter(|file| back_rank(index)[*file as usize] == 'R').collect::<Vec<u8>>();
+        for (kingside, queenside) in [('K', 'Q'), ('k', 'q')] {
+            board.castling_rights.set_with_rook(kingside, rook_files[1]);
+            board.castling_rights.set_with_rook(queenside, rook_files[0]);
+        }
+        Ok(board)
+    }
+
+    /// A function to recognize which Chess960 starting position the pieces stand in, if any. The side to move,
+    /// castling rights and counters are not compared.
+    ///
+    /// ## Returns
+    /// This function returns Some(index) (see docs for Board::chess960_start), or None if the pieces are not in a
+    /// Chess960 starting position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.chess960_index(), Some(518));
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert_eq!(example_game.board.chess960_index(), None);
+    /// ```
+    pub fn chess960_index(&self) -> Option<u16> {
+        let rows = &self.board_state;
+        let rank_of = |row: usize| -> Option<[char; 8]> { rows.get(row)?.as_slice().try_into().ok() };
+        let white_rank = rank_of(7)?;
+        if rank_of(0)? != white_rank.map(|piece| piece.to_ascii_lowercase())
+            || rank_of(1)? != ['p'; 8]
+            || rank_of(6)? != ['P'; 8]
+            || (2..6).any(|row| rank_of(row) != Some(['*'; 8]))
+        {
+            return None;
+        }
+        (0..CHESS960_POSITIONS).find(|index| back_rank(*index) == white_rank)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_fen, Game};
+
+    #[test]
+    fn test_published_starts() {
+        for (index, rank) in [(0, "BBQNNRKR"), (1, "BQNBNRKR"), (95, "NNRKRQBB"), (518, "RNBQKBNR"), (959, "RKRNNQBB")] {
+            assert_eq!(back_rank(index).iter().collect::<String>(), rank, "{}", index);
+            let mut board = Game::new().board;
+            board.board_state[7] = rank.chars().collect();
+            board.board_state[0] = rank.to_lowercase().chars().collect();
+            assert_eq!(board.chess960_index(), Some(index));
+        }
+        assert_eq!(Board::chess960_start(CLASSICAL_INDEX).unwrap(), Game::new().board);
+        assert_eq!(Board::chess960_start(1000), Err(PositionError::InvalidChess960Index(1000)));
+        let mut classical = Game::new_from_fen(generate_fen(&Board::chess960_start(CLASSICAL_INDEX).unwrap()));
+        for uci_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "e1g1"] {
+            assert!(classical.make_uci_move(uci_move), "{}", uci_move);
+        }
+        assert_eq!(classical.board.board_fen(), "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1");
+
+        let mut swap = Game::new_from_fen(generate_fen(&Board::chess960_start(3).unwrap())); // BQNNRKRB
+        assert_eq!(swap.to_san(&crate::Move::from_uci("f1g1").unwrap()), Some("O-O".to_string())); // Nothing stands in the way
+        assert!(swap.make_uci_move("f1g1"));
+        assert_eq!(swap.board.board_fen(), "bqnnrkrb/pppppppp/8/8/8/8/PPPPPPPP/BQNNRRKB");
+        assert_eq!(swap.board.castling_rights.to_fen(), "ge"); // Without the board, rooks off the corners are written by their files
+        assert_eq!(generate_fen(&swap.board), "bqnnrkrb/pppppppp/8/8/8/8/PPPPPPPP/BQNNRRKB b kq - 1 1");
+    }
+    #[test]
+    fn test_every_start() {
+        let mut ranks = std::collections::HashSet::new();
+        for index in 0..CHESS960_POSITIONS {
+            let rank = back_rank(index);
+            let files = |piece: char| (0..8).filter(|file| rank[*file] == piece).collect::<Vec<_>>();
+            let (bishops, rooks, king) = (files('B'), files('R'), files('K'));
+            assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", index);
+            assert!(rooks[0] < king[0] && king[0] < rooks[1], "{}", index);
+            assert!(ranks.insert(rank));
+            let board = Board::chess960_start(index).unwrap();
+            assert_eq!(board.validate(), Ok(()));
+            assert_eq!(board.chess960_index(), Some(index));
+            for (right, file) in [('K', rooks[1]), ('Q', rooks[0]), ('k', rooks[1]), ('q', rooks[0])] {
+                assert_eq!(board.castling_rights.rook_file(right), Some(file as u8), "{} {}", index, right);
+            }
+            let fen = generate_fen(&board);
+            assert!(fen.ends_with(" w KQkq - 0 1"), "{}", fen); // X-FEN, since the rooks are the outermost
+            assert_eq!(crate::try_parse_fen(&fen), Ok(board.clone()));
+        }
+    }
+}
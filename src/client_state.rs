@@ -0,0 +1,223 @@
+/*****************************
+*  CLIENT STATE              *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Everything a GUI needs to draw a position and let the player move, gathered in one struct so that a frontend doesn't
+have to assemble it from a dozen calls. With the serde feature, it serializes to one JSON object per position.
+
+The schema is stable: fields may be added, but the existing ones keep their names, types and meaning. Squares are in
+algebraic notation, and maps are ordered by square name, so the JSON of a position is always the same.
+```text
+{
+  "pieces": { "e1": { "type": "king", "color": "white" }, ... }, // Every occupied square.
+  "side_to_move": "white", // "white" or "black".
+  "castling": "KQkq", // The castling rights as in a FEN.
+  "en_passant": "e3", // null if there is no en passant square.
+  "checks": { "white": false, "black": false }, // Whether each king is in check.
+  "status": "InProgress", // The name of the GameStatus.
+  "last_move": "e2e4", // In UCI notation, null at the start of the game.
+  "legal_moves": { "g1": ["f3", "h3"], ... } // The target squares of every piece that can move, sorted.
+}
+```
+*/
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Color, Game, PieceKind};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A struct to represent a piece as a GUI sees it.
+///
+/// ## Attributes
+/// ```text
+/// kind: String, // "pawn", "knight", "bishop", "rook", "queen" or "king". Serialized as "type".
+/// color: String, // "white" or "black".
+/// ```
+pub struct ClientPiece {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub kind: String,
+    pub color: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A struct to represent whether each king is in check.
+///
+/// ## Attributes
+/// ```text
+/// white: bool, // Whether White's king is in check.
+/// black: bool, // Whether Black's king is in check.
+/// ```
+pub struct ClientChecks {
+    pub white: bool,
+    pub black: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A struct to represent a position for a GUI (see module docs for the schema).
+///
+/// ## Attributes
+/// ```text
+/// pieces: BTreeMap<String, ClientPiece>, // The piece on every occupied square.
+/// side_to_move: String, // "white" or "black".
+/// castling: String, // The castling rights as in a FEN, e.g. "KQkq" or "-".
+/// en_passant: Option<String>, // The en passant square, if any.
+/// checks: ClientChecks, // Whether each king is in check.
+/// status: String, // The name of the GameStatus, e.g. "InProgress" or "WhiteWinsByCheckmate".
+/// last_move: Option<String>, // The last move in UCI notation, if any move has been made.
+/// legal_moves: BTreeMap<String, Vec<String>>, // The squares every piece that can move can move to, sorted. A promotion is listed once.
+/// ```
+pub struct ClientState {
+    pub pieces: BTreeMap<String, ClientPiece>,
+    pub side_to_move: String,
+    pub castling: String,
+    pub en_passant: Option<String>,
+    pub checks: ClientChecks,
+    pub status: String,
+    pub last_move: Option<String>,
+    pub legal_moves: BTreeMap<String, Vec<String>>,
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn color_name(color: Color) -> String {
+    match color {
+        Color::White => "white".to_string(),
+        Color::Black => "black".to_string(),
+    }
+}
+
+fn kind_name(kind: PieceKind) -> String {
+    match kind {
+        PieceKind::Pawn => "pawn",
+        PieceKind::Knight => "knight",
+        PieceKind::Bishop => "bishop",
+        PieceKind::Rook => "rook",
+        PieceKind::Queen => "queen",
+        PieceKind::King => "king",
+    }
+    .to_string()
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to gather everything a GUI needs about the current position (see module docs).
+    ///
+    /// ## Returns
+    /// This function returns a ClientState, which serializes to JSON with the serde feature.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// let state = example_game.to_client_state();
+    /// assert_eq!(state.pieces["e4"].kind, "pawn");
+    /// assert_eq!(state.side_to_move, "black");
+    /// assert_eq!(state.last_move.as_deref(), Some("e2e4"));
+    /// assert_eq!(state.legal_moves["g8"], ["f6", "h6"]);
+    /// ```
+    pub fn to_client_state(&self) -> ClientState {
+        let pieces = self
+            .board
+            .pieces()
+            .map(|(square, piece)| {
+                let color = Color::from_char(piece.color).unwrap_or(Color::White);
+                (square.to_algebraic(), ClientPiece { kind: kind_name(piece.kind), color: color_name(color) })
+            })
+            .collect();
+        let mut legal_moves: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for legal_move in self.legal_moves() {
+            let targets = legal_moves.entry(legal_move.source).or_default();
+            if !targets.contains(&legal_move.target) {
+                targets.push(legal_move.target);
+            }
+        }
+        legal_moves.values_mut().for_each(|targets| targets.sort());
+        ClientState {
+            pieces,
+            side_to_move: color_name(if self.board.active_player == 'b' { Color::Black } else { Color::White }),
+            castling: self.board.castling_rights.to_fen_for(&self.board),
+            en_passant: Some(self.board.en_passant_square.clone()).filter(|square| square != "-"),
+            checks: ClientChecks { white: self.checks[0], black: self.checks[1] },
+            status: format!("{:?}", self.game_status),
+            last_move: self.history.last().map(|last_move| last_move.to_uci()),
+            legal_moves,
+        }
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::Game;
+
+    #[test]
+    fn test_client_state() {
+        let mut test_game = Game::new_from_fen("2r1k3/1P6/8/3pP3/8/8/8/4K2R w K d6 0 30".to_string());
+        let state = test_game.to_client_state();
+        assert_eq!(state.pieces.len(), 7);
+        assert_eq!(state.en_passant.as_deref(), Some("d6"));
+        assert_eq!(state.legal_moves["e5"], ["d6", "e6"]); // En passant
+        assert_eq!(state.legal_moves["b7"], ["b8", "c8"]); // Each promotion square once
+        assert!(state.legal_moves["e1"].contains(&"g1".to_string()));
+        assert_eq!(state.last_move, None);
+
+        assert!(test_game.make_uci_move("b7c8q"));
+        let state = test_game.to_client_state();
+        assert!(state.checks.black && !state.checks.white);
+        assert_eq!(state.pieces["c8"].kind, "queen");
+        assert_eq!(state.last_move.as_deref(), Some("b7c8q"));
+        assert_eq!(state.castling, "K");
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_client_state_json() {
+        let mut test_game = Game::new();
+        for uci_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5c6", "d7c6", "e1g1", "f7f6", "d2d4", "c8g4", "d4e5", "d8d1", "f1d1", "f6e5", "h2h3"] {
+            assert!(test_game.make_uci_move(uci_move), "{}", uci_move);
+        }
+        let json = serde_json::to_string(&test_game.to_client_state()).unwrap();
+        assert_eq!(json, concat!(
+            r#"{"pieces":{"a1":{"type":"rook","color":"white"},"a2":{"type":"pawn","color":"white"},"a6":{"type":"pawn","color":"black"},"#,
+            r#""a8":{"type":"rook","color":"black"},"b1":{"type":"knight","color":"white"},"b2":{"type":"pawn","color":"white"},"#,
+            r#""b7":{"type":"pawn","color":"black"},"c1":{"type":"bishop","color":"white"},"c2":{"type":"pawn","color":"white"},"#,
+            r#""c6":{"type":"pawn","color":"black"},"c7":{"type":"pawn","color":"black"},"d1":{"type":"rook","color":"white"},"#,
+            r#""e4":{"type":"pawn","color":"white"},"e5":{"type":"pawn","color":"black"},"e8":{"type":"king","color":"black"},"#,
+            r#""f2":{"type":"pawn","color":"white"},"f3":{"type":"knight","color":"white"},"f8":{"type":"bishop","color":"black"},"#,
+            r#""g1":{"type":"king","color":"white"},"g2":{"type":"pawn","color":"white"},"g4":{"type":"bishop","color":"black"},"#,
+            r#""g7":{"type":"pawn","color":"black"},"g8":{"type":"knight","color":"black"},"h3":{"type":"pawn","color":"white"},"#,
+            r#""h7":{"type":"pawn","color":"black"},"h8":{"type":"rook","color":"black"}},"#,
+            r#""side_to_move":"black","castling":"kq","en_passant":null,"checks":{"white":false,"black":false},"#,
+            r#""status":"InProgress","last_move":"h2h3","#,
+            r#""legal_moves":{"a6":["a5"],"a8":["a7","b8","c8","d8"],"b7":["b5","b6"],"c6":["c5"],"e8":["e7","f7"],"#,
+            r#""f8":["a3","b4","c5","d6","e7"],"g4":["c8","d7","e6","f3","f5","h3","h5"],"g7":["g5","g6"],"#,
+            r#""g8":["e7","f6","h6"],"h7":["h5","h6"]}}"#,
+        )); // Black can't castle long, since the rook on d1 covers d8
+        assert_eq!(serde_json::from_str::<super::ClientState>(&json).unwrap(), test_game.to_client_state());
+    }
+}
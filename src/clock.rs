@@ -0,0 +1,496 @@
+/*****************************
+*  CLOCK                     *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A chess clock that can be attached to a Game, for servers that need time handling next to the rules.
+
+The clock never reads the system time. The caller measures how long each move took and passes it in,
+which keeps the clock testable and lets a server decide how to account for network lag.
+
+Clocks follow a TimeControl, which can be read from and written to the TimeControl tag of a PGN file.
+*/
+
+use std::time::Duration;
+
+use crate::{Board, Color, Game, GameStatus, Move, PieceKind};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn has_mating_material(board: &Board, color: Color) -> bool {
+    let mut minor_pieces = 0;
+    for (_square, piece) in board.pieces_of(color.to_char()) {
+        match piece.kind {
+            PieceKind::King => {}
+            PieceKind::Knight | PieceKind::Bishop => minor_pieces += 1,
+            PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return true,
+        }
+    }
+    minor_pieces >= 2
+} // A lone king, or a king and a single bishop or knight, can't force mate.
+// Helpmates where the other side blocks its own king are not considered.
+
+fn time_forfeit_status(board: &Board, flagged: Color) -> GameStatus {
+    match flagged.opposite() {
+        winner if !has_mating_material(board, winner) => GameStatus::TimeForfeitDraw,
+        Color::White => GameStatus::WhiteWinsByTimeForfeit,
+        Color::Black => GameStatus::BlackWinsByTimeForfeit,
+    }
+} // FIDE 6.9: running out of time loses, unless the opponent couldn't have checkmated
+
+fn parse_period(period: &str) -> Option<TimePeriod> {
+    let (moves, rest) = match period.split_once('/') {
+        Some((moves, rest)) => (Some(moves.parse::<u32>().ok().filter(|moves| *moves > 0)?), rest),
+        None => (None, period),
+    };
+    let seconds = |text: &str| text.parse::<u64>().ok().map(Duration::from_secs);
+    let (time, bonus) = if let Some((time, increment)) = rest.split_once('+') {
+        (seconds(time)?, TimeBonus::Increment(seconds(increment)?))
+    } else if let Some((time, delay)) = rest.split_once('d') {
+        (seconds(time)?, TimeBonus::Delay(seconds(delay)?))
+    } else {
+        (seconds(rest)?, TimeBonus::None)
+    };
+    Some(TimePeriod { moves, time, bonus })
+} // Parses one period of a PGN TimeControl tag, e.g. "40/5400+30". "?", "-" and sandclock periods don't parse.
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An enum to represent the time a player gets back for each move.
+///
+/// ```text
+/// None, // No time back.
+/// Increment(Duration), // Fischer increment: the time is added after every move.
+/// Delay(Duration), // Bronstein delay: the time used on the move is given back, up to the delay.
+/// ```
+pub enum TimeBonus {
+    None,
+    Increment(Duration),
+    Delay(Duration),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to represent one period of a time control, e.g. "40 moves in 90 minutes".
+///
+/// ## Attributes
+/// ```text
+/// moves: Option<u32>, // The number of moves to make in the period, or None if the period lasts for the rest of the game.
+/// time: Duration, // The time added to the clock when the period starts.
+/// bonus: TimeBonus, // The time given back for each move in the period (see docs for TimeBonus).
+/// ```
+pub struct TimePeriod {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: TimeBonus,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent a time control, i.e. the periods a game is played in. Time left over from one period
+/// carries over to the next. If the last period has a move count, it repeats until the game ends.
+///
+/// ## Attributes
+/// ```text
+/// periods: Vec<TimePeriod>, // The periods, in order. There is always at least one.
+/// ```
+pub struct TimeControl {
+    pub periods: Vec<TimePeriod>,
+}
+impl TimeControl {
+    /// A function to create a time control of a single period without a move count, e.g. "5 minutes + 3 seconds per move".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// time: Duration, // The time each side gets for the whole game.
+    /// bonus: TimeBonus, // The time given back for each move (see docs for TimeBonus).
+    ///
+    /// ```
+    pub fn new(time: Duration, bonus: TimeBonus) -> TimeControl {
+        TimeControl { periods: vec![TimePeriod { moves: None, time, bonus }] }
+    }
+
+    /// A function to read a time control from the syntax of the PGN TimeControl tag, e.g. "300+3" or "40/5400+30:1800+30".
+    ///
+    /// Periods are separated by ':'. Each period is a time in seconds, optionally preceded by a move count and '/',
+    /// and optionally followed by '+' and a Fischer increment in seconds. Bronstein delay isn't part of the PGN standard,
+    /// and is written with a 'd' in place of the '+', e.g. "300d5".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// tag: &str, // The value of the TimeControl tag.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(TimeControl), or None if the tag can't be parsed. The tag values "?" (unknown), "-" (no time control)
+    /// and sandclock periods such as "*180" are not time controls a Clock can run, so they return None too.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use alviny_task_3::clock::{TimeBonus, TimeControl};
+    /// let classical = TimeControl::from_pgn_tag("40/5400+30:1800+30").unwrap();
+    /// assert_eq!(classical.periods[0].moves, Some(40));
+    /// assert_eq!(classical.periods[1].bonus, TimeBonus::Increment(Duration::from_secs(30)));
+    /// ```
+    pub fn from_pgn_tag(tag: &str) -> Option<TimeControl> {
+        let periods = tag.split(':').map(parse_period).collect::<Option<Vec<TimePeriod>>>()?;
+        Some(TimeControl { periods })
+    }
+
+    /// A function to write the time control in the syntax of the PGN TimeControl tag. Inverse function for from_pgn_tag(),
+    /// for times in whole seconds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use alviny_task_3::clock::{TimeBonus, TimeControl};
+    /// let blitz = TimeControl::new(Duration::from_secs(180), TimeBonus::Increment(Duration::from_secs(2)));
+    /// assert_eq!(blitz.to_pgn_tag(), "180+2");
+    /// ```
+    pub fn to_pgn_tag(&self) -> String {
+        self.periods
+            .iter()
+            .map(|period| {
+                let moves = period.moves.map(|moves| format!("{}/", moves)).unwrap_or_default();
+                let bonus = match period.bonus {
+                    TimeBonus::None => String::new(),
+                    TimeBonus::Increment(increment) => format!("+{}", increment.as_secs()),
+                    TimeBonus::Delay(delay) => format!("d{}", delay.as_secs()),
+                };
+                format!("{}{}{}", moves, period.time.as_secs(), bonus)
+            })
+            .collect::<Vec<String>>()
+            .join(":")
+    }
+
+    fn period(&self, index: usize) -> TimePeriod {
+        match self.periods.get(index).or(self.periods.last()) {
+            Some(period) => *period,
+            None => TimePeriod { moves: None, time: Duration::ZERO, bonus: TimeBonus::None },
+        }
+    } // Periods past the last one repeat the last one
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent a chess clock.
+///
+/// ## Attributes
+/// ```text
+/// time_control: TimeControl, // The time control the clock follows (see docs for TimeControl).
+/// white_remaining: Duration, // White's remaining time.
+/// black_remaining: Duration, // Black's remaining time.
+/// moves_made: [u32; 2], // The number of moves each side has made, White first.
+/// running: Option<Color>, // The side whose time is running, or None before the first move.
+/// ```
+pub struct Clock {
+    time_control: TimeControl,
+    white_remaining: Duration,
+    black_remaining: Duration,
+    moves_made: [u32; 2],
+    running: Option<Color>,
+}
+impl Clock {
+    /// A function to create a clock that gives both sides the same time, without any time back for moves.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// base_time: Duration, // The time each side starts with.
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use alviny_task_3::Color;
+    /// # use alviny_task_3::clock::Clock;
+    /// let clock = Clock::new(Duration::from_secs(300)); // 5 minutes each
+    /// assert_eq!(clock.remaining(Color::Black), Duration::from_secs(300));
+    /// ```
+    pub fn new(base_time: Duration) -> Clock {
+        Clock::from_time_control(TimeControl::new(base_time, TimeBonus::None))
+    }
+
+    /// A function to create a clock that gives each side its own time, e.g. for time odds, without any time back for moves.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// white_time: Duration, // The time White starts with.
+    /// black_time: Duration, // The time Black starts with.
+    ///
+    /// ```
+    pub fn with_times(white_time: Duration, black_time: Duration) -> Clock {
+        let mut clock = Clock::new(white_time);
+        clock.black_remaining = black_time;
+        clock
+    }
+
+    /// A function to create a clock that follows a time control (see docs for TimeControl).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use alviny_task_3::Color;
+    /// # use alviny_task_3::clock::{Clock, TimeControl};
+    /// let clock = Clock::from_time_control(TimeControl::from_pgn_tag("300+3").unwrap());
+    /// assert_eq!(clock.remaining(Color::White), Duration::from_secs(300));
+    /// ```
+    pub fn from_time_control(time_control: TimeControl) -> Clock {
+        let start_time = time_control.period(0).time;
+        Clock { time_control, white_remaining: start_time, black_remaining: start_time, moves_made: [0, 0], running: None }
+    }
+
+    /// A function to return the time control the clock follows.
+    pub fn time_control(&self) -> &TimeControl {
+        &self.time_control
+    }
+
+    /// A function to return the remaining time of one side.
+    pub fn remaining(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.white_remaining,
+            Color::Black => self.black_remaining,
+        }
+    }
+
+    /// A function to return the side whose time is running.
+    ///
+    /// ## Returns
+    /// This function returns Some(Color), or None if the clock hasn't been started by the first move yet.
+    pub fn running(&self) -> Option<Color> {
+        self.running
+    }
+
+    pub(crate) fn moves_made(&self) -> [u32; 2] {
+        self.moves_made
+    } // The number of moves each side has made, White first
+
+    pub(crate) fn restore(time_control: TimeControl, remaining: [Duration; 2], moves_made: [u32; 2], running: Option<Color>) -> Clock {
+        Clock { time_control, white_remaining: remaining[0], black_remaining: remaining[1], moves_made, running }
+    } // A clock in the middle of a game, e.g. one read from a saved game
+
+    fn spend(&mut self, color: Color, elapsed: Duration) -> bool {
+        let remaining = match color {
+            Color::White => &mut self.white_remaining,
+            Color::Black => &mut self.black_remaining,
+        };
+        *remaining = remaining.saturating_sub(elapsed);
+        !remaining.is_zero()
+    } // Deducts the time from one side. Returns false if that side has run out of time.
+
+    fn complete_move(&mut self, color: Color, elapsed: Duration) {
+        let side = if color == Color::White { 0 } else { 1 };
+        let (period_index, moves_in_period) = self.period_of_move(self.moves_made[side]);
+        let period = self.time_control.period(period_index);
+        let mut bonus = match period.bonus {
+            TimeBonus::None => Duration::ZERO,
+            TimeBonus::Increment(increment) => increment,
+            TimeBonus::Delay(delay) => elapsed.min(delay),
+        };
+        if period.moves == Some(moves_in_period + 1) {
+            bonus += self.time_control.period(period_index + 1).time;
+        } // That was the last move of the period, so the time of the next period is added
+        self.moves_made[side] += 1;
+        match color {
+            Color::White => self.white_remaining += bonus,
+            Color::Black => self.black_remaining += bonus,
+        }
+        self.running = Some(color.opposite());
+    } // Gives back the time for a completed move, moves on to the next period if due, and starts the other side's time
+
+    fn period_of_move(&self, mut move_index: u32) -> (usize, u32) {
+        let mut period_index = 0;
+        while let Some(moves) = self.time_control.period(period_index).moves && moves > 0 && move_index >= moves {
+            move_index -= moves;
+            period_index += 1;
+        }
+        (period_index, move_index)
+    } // Returns the period a move (counted from 0) is made in, and how many moves of that period came before it
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to attach a clock to the Game (see docs for Clock). Replaces any clock already attached.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    /// A function to return the remaining time of one side.
+    ///
+    /// ## Returns
+    /// This function returns Some(Duration), or None if the Game has no clock.
+    pub fn remaining_time(&self, color: Color) -> Option<Duration> {
+        self.clock.as_ref().map(|clock| clock.remaining(color))
+    }
+
+    /// A function to make a move given in long algebraic (UCI) notation, and charge the time it took to the mover's clock.
+    /// Without a clock this is the same as make_uci_move(). make_uci_move() and make_move() don't touch the clock.
+    ///
+    /// The clock starts with the first move, so the first move of the game is free. After that, the time is deducted
+    /// from the side that moved, and the other side's time starts running. Increments, delays and new periods of the
+    /// time control are applied after every completed move, including the first. If the time runs out, the move isn't made
+    /// and the game ends with a time forfeit: a loss for the side that ran out, or a draw if the opponent
+    /// couldn't have checkmated (see docs for GameStatus).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// uci_move: &str, // The move in long algebraic notation.
+    /// elapsed: Duration, // How long the mover took, measured by the caller.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the move was made, false if it was illegal, the game is over, or the mover ran out of time.
+    /// Illegal moves don't cost any time.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use alviny_task_3::{Color, Game};
+    /// # use alviny_task_3::clock::Clock;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.set_clock(Clock::new(Duration::from_secs(60)));
+    /// assert!(example_game.make_timed_move("e2e4", Duration::from_secs(5))); // Starts the clock
+    /// assert!(example_game.make_timed_move("e7e5", Duration::from_secs(5)));
+    /// assert_eq!(example_game.remaining_time(Color::Black), Some(Duration::from_secs(55)));
+    /// ```
+    pub fn make_timed_move(&mut self, uci_move: &str, elapsed: Duration) -> bool {
+        if self.game_status.is_off_the_board() {
+            return false;
+        }
+        let Some(played_move) = Move::from_uci(uci_move) else {
+            return false;
+        };
+        if !self.legal_moves().iter().any(|legal_move| legal_move.source == played_move.source && legal_move.target == played_move.target) {
+            return false;
+        }
+        let mover = if self.board.active_player == 'w' { Color::White } else { Color::Black };
+        if let Some(clock) = &mut self.clock {
+            if clock.running == Some(mover) && !clock.spend(mover, elapsed) {
+                self.game_status = time_forfeit_status(&self.board, mover);
+                return false;
+            }
+            clock.complete_move(mover, elapsed);
+        }
+        self.make_uci_move(uci_move)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_counts_down() {
+        let mut test_game = Game::new();
+        test_game.set_clock(Clock::new(Duration::from_secs(60)));
+        assert_eq!(test_game.clock.as_ref().unwrap().running(), None);
+        assert!(test_game.make_timed_move("e2e4", Duration::from_secs(10))); // The first move is free
+        assert_eq!(test_game.remaining_time(Color::White), Some(Duration::from_secs(60)));
+        assert_eq!(test_game.clock.as_ref().unwrap().running(), Some(Color::Black));
+        assert!(!test_game.make_timed_move("e2e4", Duration::from_secs(10))); // Illegal moves cost nothing
+        assert!(test_game.make_timed_move("e7e5", Duration::from_secs(15)));
+        assert!(test_game.make_timed_move("g1f3", Duration::from_millis(2500)));
+        assert_eq!(test_game.remaining_time(Color::Black), Some(Duration::from_secs(45)));
+        assert_eq!(test_game.remaining_time(Color::White), Some(Duration::from_millis(57500)));
+        assert_eq!(Game::new().remaining_time(Color::White), None);
+    }
+    #[test]
+    fn test_time_forfeit() {
+        let mut test_game = Game::new();
+        test_game.set_clock(Clock::new(Duration::from_secs(30)));
+        assert!(test_game.make_timed_move("e2e4", Duration::ZERO));
+        assert!(test_game.make_timed_move("e7e5", Duration::from_secs(29)));
+        assert!(test_game.make_timed_move("g1f3", Duration::from_secs(1)));
+        assert!(!test_game.make_timed_move("b8c6", Duration::from_secs(1))); // Black had 1 second left
+        assert_eq!(test_game.game_status, GameStatus::WhiteWinsByTimeForfeit);
+        assert_eq!(test_game.remaining_time(Color::Black), Some(Duration::ZERO));
+        assert_eq!(test_game.result_string(), "1-0");
+        assert!(!test_game.make_timed_move("b8c6", Duration::ZERO));
+        assert!(!test_game.make_uci_move("b8c6"));
+    }
+    #[test]
+    fn test_time_forfeit_against_insufficient_material() {
+        let mut test_game = Game::new_from_fen("4k3/8/8/8/8/8/4P3/1N2K3 b - - 0 1".to_string());
+        test_game.set_clock(Clock::with_times(Duration::from_secs(60), Duration::from_secs(5)));
+        assert!(test_game.make_timed_move("e8d8", Duration::ZERO));
+        assert!(test_game.make_timed_move("b1c3", Duration::from_secs(1)));
+        assert!(!test_game.make_timed_move("d8e8", Duration::from_secs(10)));
+        assert_eq!(test_game.game_status, GameStatus::WhiteWinsByTimeForfeit); // White has a pawn, so it could still mate
+
+        let mut lone_knight = Game::new_from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1".to_string());
+        lone_knight.set_clock(Clock::new(Duration::from_secs(5)));
+        assert!(lone_knight.make_timed_move("b1c3", Duration::ZERO));
+        assert!(!lone_knight.make_timed_move("e8d8", Duration::from_secs(5)));
+        assert_eq!(lone_knight.game_status, GameStatus::TimeForfeitDraw);
+        assert!(lone_knight.game_status.is_draw());
+    }
+    #[test]
+    fn test_time_control_tags() {
+        for tag in ["300", "180+2", "300d5", "40/5400+30:1800+30", "40/7200:20/3600:900+30"] {
+            assert_eq!(TimeControl::from_pgn_tag(tag).unwrap().to_pgn_tag(), tag);
+        }
+        assert_eq!(
+            TimeControl::from_pgn_tag("180+2"),
+            Some(TimeControl::new(Duration::from_secs(180), TimeBonus::Increment(Duration::from_secs(2))))
+        );
+        for tag in ["?", "-", "*180", "", "0/300", "40/", "5+", "abc"] {
+            assert_eq!(TimeControl::from_pgn_tag(tag), None, "{}", tag);
+        }
+    }
+    #[test]
+    fn test_fischer_increment() {
+        let mut test_game = Game::new();
+        test_game.set_clock(Clock::from_time_control(TimeControl::from_pgn_tag("180+2").unwrap()));
+        assert!(test_game.make_timed_move("e2e4", Duration::ZERO));
+        assert!(test_game.make_timed_move("e7e5", Duration::from_secs(5)));
+        assert_eq!(test_game.remaining_time(Color::White), Some(Duration::from_secs(182)));
+        assert_eq!(test_game.remaining_time(Color::Black), Some(Duration::from_secs(177)));
+    }
+    #[test]
+    fn test_bronstein_delay() {
+        let mut test_game = Game::new();
+        test_game.set_clock(Clock::from_time_control(TimeControl::from_pgn_tag("60d5").unwrap()));
+        assert!(test_game.make_timed_move("e2e4", Duration::ZERO));
+        assert!(test_game.make_timed_move("e7e5", Duration::from_secs(3))); // Used less than the delay, so it all comes back
+        assert!(test_game.make_timed_move("g1f3", Duration::from_secs(8))); // Used more, so only the delay comes back
+        assert_eq!(test_game.remaining_time(Color::Black), Some(Duration::from_secs(60)));
+        assert_eq!(test_game.remaining_time(Color::White), Some(Duration::from_secs(57)));
+    }
+    #[test]
+    fn test_period_boundary() {
+        let mut clock = Clock::from_time_control(TimeControl::from_pgn_tag("2/60:30+5").unwrap());
+        clock.complete_move(Color::White, Duration::ZERO);
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(60)); // Move 1 of 2 in the first period
+        assert!(clock.spend(Color::White, Duration::from_secs(10)));
+        clock.complete_move(Color::White, Duration::from_secs(10));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(80)); // Move 2 ends the period, and adds 30 seconds
+        assert!(clock.spend(Color::White, Duration::from_secs(10)));
+        clock.complete_move(Color::White, Duration::from_secs(10));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(75)); // Move 3 is in the second period, with increment
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+
+        let mut repeating = Clock::from_time_control(TimeControl::from_pgn_tag("1/10").unwrap());
+        repeating.complete_move(Color::Black, Duration::ZERO);
+        repeating.complete_move(Color::Black, Duration::ZERO);
+        assert_eq!(repeating.remaining(Color::Black), Duration::from_secs(30)); // The last period repeats
+    }
+}
@@ -0,0 +1,218 @@
+/*****************************
+*  DEAD POSITIONS            *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Dead positions, where neither player can checkmate by any series of legal moves, even with the opponent's help. The
+game is then drawn at once (FIDE 5.2.2).
+
+Deciding this in general takes a search, so only two cases that can be read off the board are recognised, and
+everything else is assumed to be alive:
+```text
+insufficient material, // No pawns, rooks or queens, and either a single knight or only bishops on squares of one color.
+a frozen position, // Only kings, bishops and pawns, every pawn blocked by an enemy pawn and unable to ever capture,
+                   // the kings walled off from the enemy pawns and from each other, and every square a king can
+                   // reach has a flight square that the opponent can never cover.
+```
+A position reported dead is dead. A position reported alive may still be dead, e.g. a fortress that a rook can't
+break, or a frozen position where a bishop could capture a pawn that nothing defends but that it can't reach.
+
+```
+# use alviny_task_3::Game;
+assert!(Game::new_from_fen("8/8/4k3/8/8/2B5/4K3/8 w - - 0 1".to_string()).is_dead_position()); // King and bishop against king
+assert!(!Game::new().is_dead_position());
+```
+*/
+
+use crate::{Board, Game, PieceKind, Square};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn is_light(square: Square) -> bool {
+    (square.file().index() + square.rank().index()) % 2 == 1
+}
+
+fn is_insufficient_material(board: &Board) -> bool {
+    let (mut knights, mut light_bishops, mut dark_bishops) = (0, 0, 0);
+    for (square, piece) in board.pieces() {
+        match piece.kind {
+            PieceKind::King => {}
+            PieceKind::Knight => knights += 1,
+            PieceKind::Bishop if is_light(square) => light_bishops += 1,
+            PieceKind::Bishop => dark_bishops += 1,
+            PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return false,
+        }
+    }
+    match knights {
+        0 => light_bishops == 0 || dark_bishops == 0,
+        1 => light_bishops + dark_bishops == 0,
+        _ => false,
+    }
+} // Kings with at most one minor piece, or with bishops that all stand on one color, whoever they belong to.
+// Two knights, or a knight and a bishop, can mate if the other side helps
+
+fn pawn_attacks(square: Square, color: char) -> Vec<Square> {
+    let rank = square.rank().index() as i8 + if color == 'w' { 1 } else { -1 };
+    [-1, 1]
+        .into_iter()
+        .map(|file_step| square.file().index() as i8 + file_step)
+        .filter(|file| (0..8).contains(file) && (0..8).contains(&rank))
+        .filter_map(|file| Square::from_index((rank * 8 + file) as u8))
+        .collect()
+} // The squares a pawn of a color on a square attacks
+
+fn is_attacked_by_pawn(board: &Board, square: Square, by_color: char) -> bool {
+    let pawn = if by_color == 'w' { 'P' } else { 'p' };
+    Square::all().any(|other| board.piece_on(other) == pawn && pawn_attacks(other, by_color).contains(&square))
+} // Whether a pawn of a color attacks the square
+
+fn king_region(board: &Board, color: char) -> Option<Vec<Square>> {
+    let enemy = if color == 'w' { 'b' } else { 'w' };
+    let mut region = vec![board.king_square(color)?];
+    let mut next = 0;
+    while next < region.len() {
+        let current = region[next];
+        for square in Square::all().filter(|square| square.distance(current) == 1) {
+            let piece = board.piece_on(square);
+            if region.contains(&square) || is_attacked_by_pawn(board, square, enemy) {
+                continue;
+            }
+            if piece.eq_ignore_ascii_case(&'p') {
+                if piece.is_ascii_uppercase() != (color == 'w') {
+                    return None; // An enemy pawn that nothing protects can be taken
+                }
+                continue;
+            }
+            region.push(square);
+        }
+        next += 1;
+    }
+    Some(region)
+} // Every square the king of a color can ever reach past the frozen pawns, or None if it can take a pawn
+
+fn is_frozen(board: &Board) -> bool {
+    let mut bishops = vec![];
+    let mut pawns = vec![];
+    for (square, piece) in board.pieces() {
+        match piece.kind {
+            PieceKind::King => {}
+            PieceKind::Bishop => bishops.push((square, piece.color)),
+            PieceKind::Pawn => pawns.push((square, piece.color)),
+            PieceKind::Knight | PieceKind::Rook | PieceKind::Queen => return false,
+        }
+    }
+    for &(square, color) in &pawns {
+        let (enemy_pawn, front_rank) = if color == 'w' { ('p', square.rank().index() as i8 + 1) } else { ('P', square.rank().index() as i8 - 1) };
+        let front = Square::from_index((front_rank * 8 + square.file().index() as i8) as u8).filter(|_| (0..8).contains(&front_rank));
+        if front.is_none_or(|front| board.piece_on(front) != enemy_pawn) {
+            return false;
+        } // Not blocked by an enemy pawn, so it might move
+        for target in pawn_attacks(square, color) {
+            let enemy_on_target = board.piece_on(target) != '*' && board.piece_on(target).is_ascii_uppercase() != (color == 'w');
+            if enemy_on_target || bishops.iter().any(|&(bishop, bishop_color)| bishop_color != color && is_light(bishop) == is_light(target)) {
+                return false;
+            }
+        } // Something could be captured
+    }
+    if bishops.iter().any(|&(bishop, color)| pawns.iter().any(|&(pawn, pawn_color)| pawn_color != color && is_light(pawn) == is_light(bishop))) {
+        return false;
+    } // A bishop could take a pawn
+
+    let (Some(white_region), Some(black_region)) = (king_region(board, 'w'), king_region(board, 'b')) else {
+        return false;
+    };
+    if white_region.iter().any(|&white| black_region.iter().any(|&black| white.distance(black) <= 2)) {
+        return false;
+    } // The kings could meet, or guard squares next to each other
+    for (defender, region) in [('w', &white_region), ('b', &black_region)] {
+        for &square in region {
+            let light = is_light(square);
+            let can_be_checked = bishops.iter().any(|&(bishop, color)| color != defender && is_light(bishop) == light);
+            let blockers = bishops.iter().filter(|&&(bishop, color)| color == defender && is_light(bishop) != light).count();
+            let flights = region.iter().filter(|&&other| other.distance(square) == 1 && is_light(other) != light).count();
+            if can_be_checked && flights <= blockers {
+                return false;
+            }
+        } // Bishops only ever cover one color, so a king next to a free square of the other color can't be mated.
+        // Its own bishops could help by standing on those squares, one each
+    }
+    true
+} // Whether the position is frozen (see module docs)
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to check whether neither player can checkmate any more, by any series of legal moves (see module docs).
+    ///
+    /// ## Returns
+    /// This function returns true if the position is recognised as dead: insufficient material, or a frozen pawn
+    /// structure with only bishops left that can't break through. It is conservative, so false means "not known to be
+    /// dead" rather than "alive". The game status isn't changed.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let locked = Game::new_from_fen("4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1".to_string());
+    /// assert!(locked.is_dead_position()); // Neither king can get through the pawns
+    /// ```
+    pub fn is_dead_position(&self) -> bool {
+        is_insufficient_material(&self.board) || is_frozen(&self.board)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_dead(fen: &str) -> bool {
+        Game::new_from_fen(fen.to_string()).is_dead_position()
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        assert!(is_dead("8/8/4k3/8/8/8/4K3/8 w - - 0 1"));
+        assert!(is_dead("8/8/4k3/8/8/8/4K3/1N6 w - - 0 1"));
+        assert!(is_dead("8/8/4k3/8/8/8/4K3/2B5 b - - 0 1"));
+        assert!(is_dead("8/8/4k3/8/3b4/8/4K3/2B5 w - - 0 1")); // Bishops on the same color, on different sides
+        assert!(is_dead("8/8/4k3/8/8/8/1B2K3/2B5 w - - 0 1")); // Both on dark squares
+        assert!(!is_dead("8/8/4k3/8/8/3b4/4K3/2B5 w - - 0 1")); // Opposite colors: a helpmate is possible
+        assert!(!is_dead("8/8/4k3/8/8/8/4K3/1N4n1 w - - 0 1")); // A knight each
+        assert!(!is_dead("8/8/4k3/8/8/8/4K3/1NN5 w - - 0 1"));
+        assert!(!is_dead("8/8/4k3/8/8/8/4K3/1N3B2 w - - 0 1"));
+        assert!(!is_dead("8/8/4k3/8/8/8/4KP2/8 w - - 0 1"));
+    }
+    #[test]
+    fn test_frozen_pawns() {
+        // White's pawns stand on dark squares and Black's on light ones, so each side's pawns only attack one color
+        let chain = "4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1";
+        assert!(is_dead(chain));
+        assert!(is_dead("4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/2B1K3 w - - 0 1")); // A dark bishop can't touch Black's pawns
+        assert!(is_dead("2b1k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/2B1K3 w - - 0 1")); // Nor a light one White's
+        assert!(!is_dead("4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/3BK3 w - - 0 1")); // A light bishop can take the b5 pawn
+        assert!(!is_dead("4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K1b1 w - - 0 1")); // A dark bishop can be taken on b4's squares
+        assert!(!is_dead("4k3/8/8/1p1p1p2/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1")); // h4 can advance
+        assert!(!is_dead("4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K2R w - - 0 1")); // A rook
+        assert!(is_dead("4k3/8/8/1p1p1p1p/1P1P1P1P/8/8/4K3 w - - 0 1")); // The kings can't cross the fourth and fifth ranks
+        assert!(!is_dead("4k3/8/8/1p1p1p1p/1PpPpPpP/p1P1P1P1/8/4K3 w - - 0 1")); // The a3 pawn isn't blocked
+    }
+    #[test]
+    fn test_superficially_blocked() {
+        // All pawns are blocked, but the kings can walk around the open a-file
+        assert!(!is_dead("4k3/8/8/2p1p1p1/2P1P1P1/8/8/4K3 w - - 0 1"));
+        // The same wall, but a light-squared bishop can take the b5 pawn and open it
+        assert!(!is_dead("4k3/8/8/1p1p1p1p/1P1P1P1P/8/8/4KB2 w - - 0 1"));
+    }
+}
@@ -0,0 +1,223 @@
+/*****************************
+*  MOVE DESCRIPTIONS         *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Moves described in plain English, e.g. "knight from g1 to f3" or "queen takes rook on d8, check", for screen readers
+and voice interfaces.
+
+A description is put together from a few parts: the piece that moves, what it does (moves, takes, captures in passing,
+castles), what it promotes to, and whether it gives check or mate. Each part has its own words, so that other languages
+can be added by swapping the words rather than rewriting the sentences.
+
+```
+# use alviny_task_3::{Game, Move};
+let example_game = Game::new(); // Create a new game at the starting position
+assert_eq!(example_game.describe_move(&Move::from_uci("g1f3").unwrap()), "knight from g1 to f3");
+```
+*/
+
+use crate::castling::{CastleSide, CastledStatus};
+use crate::{Game, Move, Piece, PieceKind, Square};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+enum Action {
+    Moves { from: String, to: String },
+    Takes { captured: PieceKind, on: String },
+    TakesInPassing { on: String },
+    CastlesShort,
+    CastlesLong,
+}
+
+enum Ending {
+    Check,
+    Checkmate,
+}
+
+struct Description {
+    piece: PieceKind,
+    action: Action,
+    promotion: Option<PieceKind>,
+    ending: Option<Ending>,
+}
+
+fn piece_name(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::Pawn => "pawn",
+        PieceKind::Knight => "knight",
+        PieceKind::Bishop => "bishop",
+        PieceKind::Rook => "rook",
+        PieceKind::Queen => "queen",
+        PieceKind::King => "king",
+    }
+} // The English name of a piece
+
+fn action_words(action: &Action, promotion: Option<PieceKind>) -> String {
+    match (action, promotion) {
+        (Action::Moves { to, .. }, Some(promotion)) => format!("promotes to {} on {}", piece_name(promotion), to),
+        (Action::Moves { from, to }, None) => format!("from {} to {}", from, to),
+        (Action::Takes { captured, on }, Some(promotion)) => {
+            format!("takes {} on {} and promotes to {}", piece_name(*captured), on, piece_name(promotion))
+        }
+        (Action::Takes { captured, on }, None) => format!("takes {} on {}", piece_name(*captured), on),
+        (Action::TakesInPassing { on }, _) => format!("captures the pawn on {} in passing", on),
+        (Action::CastlesShort, _) => "castles short".to_string(),
+        (Action::CastlesLong, _) => "castles long".to_string(),
+    }
+} // What the piece does, in English. Promotions are part of the action, since "pawn from b2 to b1, promotes to queen"
+// reads worse than "pawn promotes to queen on b1"
+
+fn ending_words(ending: &Ending) -> &'static str {
+    match ending {
+        Ending::Check => "check",
+        Ending::Checkmate => "checkmate",
+    }
+} // The English name of a check or mate
+
+impl Description {
+    fn to_english(&self) -> String {
+        let mut sentence = format!("{} {}", piece_name(self.piece), action_words(&self.action, self.promotion));
+        if let Some(ending) = &self.ending {
+            sentence.push_str(", ");
+            sentence.push_str(ending_words(ending));
+        }
+        sentence
+    } // Puts the parts together: piece, action, and the check or mate after a comma
+}
+
+fn piece_at(game: &Game, square: &str) -> Option<Piece> {
+    let square = Square::from_algebraic(square)?;
+    game.board.pieces().find(|(occupied, _piece)| *occupied == square).map(|(_square, piece)| piece)
+} // The piece on a square, or None if it is empty or the text isn't a square
+
+fn describe(game: &Game, chess_move: &Move) -> Option<Description> {
+    let piece = piece_at(game, &chess_move.source)?;
+    let promotion = chess_move.promotion.unwrap_or(game.board.promotion_selection).to_ascii_lowercase();
+    let legal_move = game.legal_moves().into_iter().find(|legal_move| {
+        legal_move.source == chess_move.source
+            && legal_move.target == chess_move.target
+            && legal_move.promotion.is_none_or(|legal_promotion| legal_promotion == promotion)
+    });
+    let chess_move = legal_move.as_ref().unwrap_or(chess_move);
+    let file_distance = chess_move.source.as_bytes()[0].abs_diff(chess_move.target.as_bytes()[0]);
+    let action = match (CastledStatus::castle_side(&game.board, chess_move), piece.kind, piece_at(game, &chess_move.target)) {
+        (Some(CastleSide::Kingside), _, _) => Action::CastlesShort,
+        (Some(CastleSide::Queenside), _, _) => Action::CastlesLong,
+        (None, _, Some(captured)) => Action::Takes { captured: captured.kind, on: chess_move.target.clone() },
+        (None, PieceKind::Pawn, None) if file_distance == 1 => {
+            Action::TakesInPassing { on: format!("{}{}", &chess_move.target[0..1], &chess_move.source[1..2]) }
+        }
+        (None, _, None) => Action::Moves { from: chess_move.source.clone(), to: chess_move.target.clone() },
+    };
+    let promotion = chess_move.promotion.and_then(Piece::from_fen_char).map(|promotion| promotion.kind);
+    let ending = legal_move.as_ref().and_then(|legal_move| {
+        let mut next_game = game.clone();
+        next_game.make_uci_move(&legal_move.to_uci());
+        if next_game.is_checkmate() {
+            Some(Ending::Checkmate)
+        } else if next_game.checks.contains(&true) {
+            Some(Ending::Check)
+        } else {
+            None
+        }
+    });
+    Some(Description { piece: piece.kind, action, promotion: promotion.filter(|_| piece.kind == PieceKind::Pawn), ending })
+} // Works out the parts of the description in the position before the move
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to describe a move in plain English, in the current position (before the move is made).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the description, e.g. "knight from g1 to f3", "queen takes rook on d8, check",
+    /// "pawn captures the pawn on d5 in passing", "king castles short" or "pawn promotes to queen on b1, checkmate".
+    /// An illegal move is described as it is written, without check or mate. A move from an empty square is
+    /// described as "no piece on <square>".
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::from_uci_moves("f2f3 e7e5 g2g4").unwrap();
+    /// assert_eq!(example_game.describe_move(&Move::from_uci("d8h4").unwrap()), "queen from d8 to h4, checkmate");
+    /// ```
+    pub fn describe_move(&self, chess_move: &Move) -> String {
+        match describe(self, chess_move) {
+            Some(description) => description.to_english(),
+            None => format!("no piece on {}", chess_move.source),
+        }
+    }
+
+    /// A function to describe the last move of the game in plain English (see docs for describe_move).
+    ///
+    /// ## Returns
+    /// This function returns the description, or None if no moves have been made.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::from_uci_moves("e2e4 d7d5 e4d5").unwrap();
+    /// assert_eq!(example_game.describe_last_move(), Some("pawn takes pawn on d5".to_string()));
+    /// ```
+    pub fn describe_last_move(&self) -> Option<String> {
+        let mut previous_game = self.clone();
+        let last_move = previous_game.undo_move()?;
+        Some(previous_game.describe_move(&last_move))
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptions() {
+        let cases = [
+            // (FEN, move, description)
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "g1f3", "knight from g1 to f3"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", "pawn from e2 to e4"),
+            ("3rk3/8/8/8/8/8/8/3QK3 w - - 0 1", "d1d8", "queen takes rook on d8, check"),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3", "e5d6", "pawn captures the pawn on d5 in passing"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "king castles short"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "king castles long"),
+            ("4k3/8/8/8/8/8/1p6/K7 b - - 0 1", "b2b1q", "pawn promotes to queen on b1, check"),
+            ("k7/8/1K6/8/8/8/1p6/8 b - - 0 1", "b2b1n", "pawn promotes to knight on b1"),
+            ("4k3/8/8/8/8/8/6p1/4K2R b - - 0 1", "g2h1q", "pawn takes rook on h1 and promotes to queen, check"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "rook from a1 to a8, checkmate"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e5", "pawn from e2 to e5"), // Illegal
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e4e5", "no piece on e4"),
+        ];
+        for (fen, uci_move, description) in cases {
+            let game = Game::new_from_fen(fen.to_string());
+            assert_eq!(game.describe_move(&Move::from_uci(uci_move).unwrap()), description, "{} in {}", uci_move, fen);
+        }
+    }
+    #[test]
+    fn test_describe_last_move() {
+        assert_eq!(Game::new().describe_last_move(), None);
+        let game = Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1").unwrap();
+        assert_eq!(game.describe_last_move(), Some("king castles short".to_string()));
+        let game = Game::from_uci_moves("e2e4 f7f5 e4f5 g7g5 f5g6").unwrap();
+        assert_eq!(game.describe_last_move(), Some("pawn captures the pawn on g5 in passing".to_string()));
+    }
+}
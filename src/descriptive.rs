@@ -0,0 +1,345 @@
+/*****************************
+*  DESCRIPTIVE NOTATION      *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Reading moves in English descriptive notation, the notation of most chess books in English until the 1980s, e.g.
+"P-K4", "N-KB3", "QxP ch" or "O-O".
+
+Descriptive notation names squares from the point of view of the side that moves: the files are named after the
+pieces that start on them (QR, QN, QB, Q, K, KB, KN, KR) and the ranks are counted from the mover's own side, so
+White's "K4" is e4 and Black's "K4" is e5. Captures name the piece that is taken rather than its square ("NxB").
+Writers left out whatever the position made clear, so "N-B3" may mean QB3 or KB3, and "PxP" may be any pawn taking
+any pawn. This parser does the same as a reader would: it lists every legal move that fits the text, and the text only
+has to fit one of them.
+
+Accepted: "Kt" for the knight, "ch", "dis ch", "dbl ch", "+", "mate" and "#" after the move, "e.p.", annotations
+("!", "?"), "O-O", "O-O-O" and "Castles", the side of a piece ("KR-Q1") or the file of a pawn ("KBP-B4"), the square
+a piece comes from ("R(Q1)-Q2" or "R/Q1-Q2"), the square of a capture ("PxP/Q4") and promotions ("P-K8(Q)" or "P-K8=Q").
+
+```
+# use alviny_task_3::Game;
+let mut example_game = Game::new();
+for move_text in ["P-K4", "P-K4", "N-KB3", "N-QB3"] {
+    example_game.make_descriptive_move(move_text).unwrap();
+}
+assert_eq!(example_game.fen, "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+```
+*/
+
+use std::fmt;
+
+use crate::castling::{CastleSide, CastledStatus};
+use crate::{Game, Move, Piece, PieceKind, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why a move in descriptive notation couldn't be made.
+///
+/// ```text
+/// Unreadable, // The text isn't descriptive notation.
+/// Illegal, // No legal move fits the text.
+/// Ambiguous(Vec<Move>), // More than one legal move fits the text. Holds every one of them.
+/// ```
+pub enum DescriptiveError {
+    Unreadable,
+    Illegal,
+    Ambiguous(Vec<Move>),
+}
+impl fmt::Display for DescriptiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DescriptiveError::Unreadable => write!(f, "not a move in descriptive notation"),
+            DescriptiveError::Illegal => write!(f, "no legal move fits"),
+            DescriptiveError::Ambiguous(candidates) => {
+                let candidates = candidates.iter().map(Move::to_uci).collect::<Vec<String>>();
+                write!(f, "more than one legal move fits: {}", candidates.join(", "))
+            }
+        }
+    }
+}
+impl std::error::Error for DescriptiveError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const SUFFIXES: [&str; 9] = ["dblch", "disch", "mate", "ch", "e.p.", "ep", "+", "#", "!"];
+
+// A piece as descriptive notation names it: its kind, and the files it may stand on
+struct Designation {
+    kind: PieceKind,
+    files: Vec<u8>,
+}
+
+fn file_name(text: &str) -> Option<Vec<u8>> {
+    let files = match text {
+        "QR" => vec![0],
+        "QN" => vec![1],
+        "QB" => vec![2],
+        "Q" => vec![3],
+        "K" => vec![4],
+        "KB" => vec![5],
+        "KN" => vec![6],
+        "KR" => vec![7],
+        "R" => vec![0, 7],
+        "N" => vec![1, 6],
+        "B" => vec![2, 5],
+        _ => return None,
+    };
+    Some(files)
+} // The files (0 is the a-file) a file name may mean. Without a side, "B" may be the QB or the KB file
+
+fn parse_squares(text: &str, color: char) -> Option<Vec<Square>> {
+    let (name, rank) = text.split_at(text.len().checked_sub(1)?);
+    let rank = rank.parse::<u8>().ok().filter(|rank| (1..=8).contains(rank))?;
+    let rank = if color == 'w' { rank - 1 } else { 8 - rank };
+    Some(file_name(name)?.into_iter().filter_map(|file| Square::from_index(rank * 8 + file)).collect())
+} // The squares a square name like "KB3" may mean, counting the ranks from the given side
+
+fn parse_designation(text: &str) -> Option<Designation> {
+    let (prefix, letter) = text.split_at(text.len().checked_sub(1)?);
+    let kind = Piece::from_fen_char(letter.chars().next()?)?.kind;
+    let files = match (kind, prefix) {
+        (_, "") => (0..8).collect(),
+        (PieceKind::Pawn, file) => file_name(file)?,
+        (PieceKind::Rook | PieceKind::Knight | PieceKind::Bishop, "Q") => vec![0, 1, 2, 3],
+        (PieceKind::Rook | PieceKind::Knight | PieceKind::Bishop, "K") => vec![4, 5, 6, 7],
+        _ => return None,
+    };
+    Some(Designation { kind, files })
+} // Reads a piece like "N", "KR" (the rook on the king's side) or "QBP" (the pawn on the QB file)
+
+fn parse_promotion(text: &str) -> Option<(&str, Option<char>)> {
+    let promotion = |letter: &str| match letter {
+        "Q" | "R" | "B" | "N" => letter.chars().next().map(|letter| letter.to_ascii_lowercase()),
+        _ => None,
+    };
+    if let Some(inner) = text.strip_suffix(')') {
+        let (rest, letter) = inner.rsplit_once('(')?;
+        return Some((rest, Some(promotion(letter)?)));
+    }
+    for separator in ["=", "/"] {
+        if let Some((rest, letter)) = text.rsplit_once(separator) && let Some(promotion) = promotion(letter) {
+            return Some((rest, Some(promotion)));
+        }
+    }
+    Some((text, None))
+} // Splits a promotion like "(Q)", "=Q" or "/Q" off the end of a move
+
+fn split_location(text: &str, color: char) -> Option<(&str, Option<Vec<Square>>)> {
+    if let Some(inner) = text.strip_suffix(')') {
+        let (designation, location) = inner.split_once('(')?;
+        return Some((designation, Some(parse_location(location, color)?)));
+    }
+    match text.split_once('/') {
+        Some((designation, location)) => Some((designation, Some(parse_location(location, color)?))),
+        None => Some((text, None)),
+    }
+} // Splits the square a piece stands on off a designation like "R(Q1)" or "R/Q1"
+
+fn parse_location(text: &str, color: char) -> Option<Vec<Square>> {
+    match text.parse::<u8>() {
+        Ok(rank) if (1..=8).contains(&rank) => {
+            let rank = if color == 'w' { rank - 1 } else { 8 - rank };
+            Some((0..8).filter_map(|file| Square::from_index(rank * 8 + file)).collect())
+        }
+        _ => parse_squares(text, color),
+    }
+} // The squares a location may mean: a square like "Q1", or just a rank like "1"
+
+fn normalize(text: &str) -> String {
+    let mut text = text.to_uppercase().replace(' ', "").replace("KT", "N");
+    text = text.trim_end_matches(['!', '?']).to_string();
+    while let Some(suffix) = SUFFIXES.iter().find(|suffix| text.ends_with(&suffix.to_uppercase())) {
+        text.truncate(text.len() - suffix.len());
+        text = text.trim_end_matches(['!', '?', ',']).to_string();
+    }
+    text
+} // Uppercases the move and takes off the spaces, checks, mates, "e.p." and annotations, and spells the knight "N"
+
+fn candidates(game: &Game, text: &str) -> Result<Vec<Move>, DescriptiveError> {
+    let color = game.board.active_player;
+    let legal_moves = game.legal_moves();
+    let piece_at = |square: &str| {
+        let square = Square::from_algebraic(square)?;
+        game.board.pieces().find(|(occupied, _piece)| *occupied == square).map(|(_square, piece)| piece)
+    };
+    let text = normalize(text);
+    let castling_sides: Option<&[CastleSide]> = match text.replace('0', "O").as_str() {
+        "O-O" | "CASTLESKR" | "CASTLESK" => Some(&[CastleSide::Kingside]),
+        "O-O-O" | "CASTLESQR" | "CASTLESQ" => Some(&[CastleSide::Queenside]),
+        "CASTLES" => Some(&[CastleSide::Kingside, CastleSide::Queenside]),
+        _ => None,
+    };
+    if let Some(sides) = castling_sides {
+        return Ok(legal_moves
+            .into_iter()
+            .filter(|legal_move| CastledStatus::castle_side(&game.board, legal_move).is_some_and(|side| sides.contains(&side)))
+            .collect());
+    }
+
+    let (text, promotion) = parse_promotion(&text).ok_or(DescriptiveError::Unreadable)?;
+    let (mover, target, is_capture) = match text.split_once('X') {
+        Some((mover, target)) => (mover, target, true),
+        None => text.split_once('-').map(|(mover, target)| (mover, target, false)).ok_or(DescriptiveError::Unreadable)?,
+    };
+    let (mover, sources) = split_location(mover, color).ok_or(DescriptiveError::Unreadable)?;
+    let mover = parse_designation(mover).ok_or(DescriptiveError::Unreadable)?;
+    let (captured, targets) = if is_capture {
+        match split_location(target, color).and_then(|(captured, squares)| Some((parse_designation(captured)?, squares))) {
+            Some((captured, squares)) => (Some(captured), squares),
+            None => (None, Some(parse_squares(target, color).ok_or(DescriptiveError::Unreadable)?)),
+        }
+    } else {
+        (None, Some(parse_squares(target, color).ok_or(DescriptiveError::Unreadable)?))
+    };
+    let selection = game.board.promotion_selection.to_ascii_lowercase();
+
+    Ok(legal_moves
+        .into_iter()
+        .filter(|legal_move| {
+            let (Some(source), Some(target)) = (Square::from_algebraic(&legal_move.source), Square::from_algebraic(&legal_move.target)) else {
+                return false;
+            };
+            let Some(moving_piece) = piece_at(&legal_move.source) else {
+                return false;
+            };
+            let en_passant = moving_piece.kind == PieceKind::Pawn && source.file() != target.file() && piece_at(&legal_move.target).is_none();
+            let taken_kind = piece_at(&legal_move.target).map(|taken_piece| taken_piece.kind).or(en_passant.then_some(PieceKind::Pawn));
+            moving_piece.kind == mover.kind
+                && mover.files.contains(&source.file().index())
+                && sources.as_ref().is_none_or(|sources| sources.contains(&source))
+                && targets.as_ref().is_none_or(|targets| targets.contains(&target))
+                && taken_kind.is_some() == is_capture
+                && captured.as_ref().is_none_or(|captured| taken_kind == Some(captured.kind) && captured.files.contains(&target.file().index()))
+                && match legal_move.promotion {
+                    Some(legal_promotion) => legal_promotion == promotion.unwrap_or(selection),
+                    None => promotion.is_none(),
+                }
+        })
+        .collect())
+} // Every legal move the text fits
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to find the move a text in descriptive notation means, in the current position.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// move_text: &str, // The move in descriptive notation, e.g. "P-K4", "NxB" or "QxP ch" (see the module docs).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the legal Move the text describes, or a DescriptiveError (see docs for DescriptiveError).
+    /// The move is not made. A promotion without a piece promotes to Board.promotion_selection.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// # use alviny_task_3::descriptive::DescriptiveError;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.parse_descriptive("Kt-KB3"), Ok(Move::from_uci("g1f3").unwrap()));
+    /// let both_knights = vec![Move::from_uci("b1c3").unwrap(), Move::from_uci("g1f3").unwrap()];
+    /// assert_eq!(example_game.parse_descriptive("N-B3"), Err(DescriptiveError::Ambiguous(both_knights)));
+    /// ```
+    pub fn parse_descriptive(&self, move_text: &str) -> Result<Move, DescriptiveError> {
+        let mut candidates = candidates(self, move_text)?;
+        candidates.sort_by_key(Move::to_uci);
+        match candidates.len() {
+            0 => Err(DescriptiveError::Illegal),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(DescriptiveError::Ambiguous(candidates)),
+        }
+    }
+
+    /// A function to make a move given in descriptive notation (see docs for parse_descriptive).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// move_text: &str, // The move in descriptive notation, e.g. "P-K4", "NxB" or "QxP ch".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Move that was made, or a DescriptiveError, in which case the game is unchanged.
+    pub fn make_descriptive_move(&mut self, move_text: &str) -> Result<Move, DescriptiveError> {
+        let chess_move = self.parse_descriptive(move_text)?;
+        if !self.make_uci_move(&chess_move.to_uci()) {
+            return Err(DescriptiveError::Illegal);
+        }
+        Ok(chess_move)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opera_game() {
+        // Morphy against the Duke of Brunswick and Count Isouard, Paris 1858, as the books of the time gave it
+        let descriptive_score = [
+            "P-K4", "P-K4", "Kt-KB3", "P-Q3", "P-Q4", "B-N5", "PxP", "BxKt", "QxB", "PxP", "B-QB4", "Kt-KB3",
+            "Q-QKt3", "Q-K2", "Kt-B3", "P-B3", "B-KN5", "P-Kt4?", "KtxP!", "PxKt",
+        ];
+        let mut descriptive_game = Game::new();
+        for move_text in descriptive_score {
+            descriptive_game.make_descriptive_move(move_text).unwrap_or_else(|error| panic!("{}: {}", move_text, error));
+        }
+        let algebraic_game = Game::from_moves(&[
+            "e4", "e5", "Nf3", "d6", "d4", "Bg4", "dxe5", "Bxf3", "Qxf3", "dxe5", "Bc4", "Nf6",
+            "Qb3", "Qe7", "Nc3", "c6", "Bg5", "b5", "Nxb5", "cxb5",
+        ])
+        .unwrap();
+        assert_eq!(descriptive_game.history, algebraic_game.history);
+    }
+    #[test]
+    fn test_descriptive_details() {
+        let parse = |fen: &str, move_text: &str| Game::new_from_fen(fen.to_string()).parse_descriptive(move_text).map(|chess_move| chess_move.to_uci());
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(parse(start, "P-K5"), Err(DescriptiveError::Illegal));
+        assert_eq!(parse(start, "P to K4"), Err(DescriptiveError::Unreadable));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", "P-QB4"), Ok("c7c5".to_string()));
+
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(parse(castling, "O-O-O"), Ok("e8c8".to_string()));
+        assert_eq!(parse(castling, "Castles"), Err(DescriptiveError::Ambiguous(vec![Move::from_uci("e8c8").unwrap(), Move::from_uci("e8g8").unwrap()])));
+        assert_eq!(parse(castling, "KR-KB1"), Ok("h8f8".to_string()));
+        assert_eq!(parse(castling, "R-Q1"), Ok("a8d8".to_string()));
+        assert_eq!(parse(castling, "RxR ch"), Err(DescriptiveError::Ambiguous(vec![Move::from_uci("a8a1").unwrap(), Move::from_uci("h8h1").unwrap()])));
+        assert_eq!(parse(castling, "QRxR ch"), Ok("a8a1".to_string()));
+        assert_eq!(parse(castling, "RxR/KR8 ch"), Ok("h8h1".to_string()));
+
+        let queen_takes = "3rk3/8/8/8/8/8/3p4/3QK3 w - - 0 1";
+        assert_eq!(parse(queen_takes, "QxR ch"), Err(DescriptiveError::Illegal)); // The pawn on d2 is in the way
+        assert_eq!(parse(queen_takes, "QxP"), Ok("d1d2".to_string()));
+        assert_eq!(parse(queen_takes, "KxP"), Err(DescriptiveError::Illegal)); // The rook guards the pawn
+
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2";
+        assert_eq!(parse(en_passant, "PxP e.p."), Ok("e5d6".to_string()));
+        let promotion = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(parse(promotion, "PxR(Kt) ch"), Ok("a7b8n".to_string()));
+        assert_eq!(parse(promotion, "PxR=Q mate"), Ok("a7b8q".to_string()));
+        assert_eq!(parse(promotion, "P-R8"), Ok("a7a8q".to_string()));
+        let two_rooks = "4k3/8/8/R7/8/8/4K3/R7 w - - 0 1";
+        assert_eq!(parse(two_rooks, "R-R3"), Err(DescriptiveError::Ambiguous(vec![Move::from_uci("a1a3").unwrap(), Move::from_uci("a5a3").unwrap()])));
+        assert_eq!(parse(two_rooks, "R(1)-R3"), Ok("a1a3".to_string()));
+        assert_eq!(parse(two_rooks, "R/QR5-QR3"), Ok("a5a3".to_string()));
+    }
+}
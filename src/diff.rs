@@ -0,0 +1,131 @@
+/*****************************
+*  BOARD DIFFS               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The squares that differ between two boards, e.g. for a GUI to animate a move, or a jump of several moves after an
+undo or redo, without redrawing the whole board.
+
+A diff only compares pieces: whose turn it is, castling rights and the en passant square are not squares, and are left
+out. A normal move changes two squares, castling four, an en passant capture three, and a promotion two, with a pawn
+before and another piece after.
+
+```
+# use alviny_task_3::{Game, Piece, PieceKind, Square};
+let before = Game::new().board;
+let after = Game::from_uci_moves("g1f3").unwrap().board;
+let changes = before.diff(&after);
+assert_eq!(changes.len(), 2);
+assert_eq!(changes[0].square, Square::from_algebraic("g1").unwrap()); // From a1 to h8
+assert_eq!(changes[0].before.map(|piece| piece.kind), Some(PieceKind::Knight));
+assert_eq!(changes[0].after, None);
+```
+*/
+
+use crate::{Board, Piece, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to represent a square whose contents differ between two boards.
+///
+/// ## Attributes
+/// ```text
+/// square: Square, // The square.
+/// before: Option<Piece>, // The piece on the square on the first board, or None if it was empty.
+/// after: Option<Piece>, // The piece on the square on the second board, or None if it is empty.
+/// ```
+pub struct SquareChange {
+    pub square: Square,
+    pub before: Option<Piece>,
+    pub after: Option<Piece>,
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to list the squares whose contents differ between this board and another.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// other: &Board, // The board to compare with, e.g. the board after a move.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the changed squares, ordered from a1 to h8 (a1, b1, ..., h1, a2, ...). A piece that
+    /// moves away and comes back between the two boards is not a change.
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        (0..64)
+            .filter_map(Square::from_index)
+            .filter_map(|square| {
+                let (row, col) = (7 - square.rank().index() as usize, square.file().index() as usize);
+                let before = Piece::from_fen_char(self.board_state[row][col]);
+                let after = Piece::from_fen_char(other.board_state[row][col]);
+                (before != after).then_some(SquareChange { square, before, after })
+            })
+            .collect()
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, PieceKind};
+
+    fn diff_after(fen: &str, uci_move: &str) -> Vec<(String, Option<char>, Option<char>)> {
+        let mut game = Game::new_from_fen(fen.to_string());
+        let before = game.board.clone();
+        assert!(game.make_uci_move(uci_move), "{}", uci_move);
+        before
+            .diff(&game.board)
+            .into_iter()
+            .map(|change| (change.square.to_string(), change.before.map(|piece| piece.to_fen_char()), change.after.map(|piece| piece.to_fen_char())))
+            .collect()
+    } // The changes a move makes, as (square, FEN letter before, FEN letter after)
+
+    #[test]
+    fn test_special_moves() {
+        let castling = diff_after("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1");
+        let expected = [("e1", Some('K'), None), ("f1", None, Some('R')), ("g1", None, Some('K')), ("h1", Some('R'), None)];
+        assert_eq!(castling, expected.map(|(square, before, after)| (square.to_string(), before, after)));
+        let long_castling = diff_after("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8");
+        let expected = [("a8", Some('r'), None), ("c8", None, Some('k')), ("d8", None, Some('r')), ("e8", Some('k'), None)];
+        assert_eq!(long_castling, expected.map(|(square, before, after)| (square.to_string(), before, after)));
+
+        let en_passant = diff_after("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6");
+        let expected = [("d5", Some('p'), None), ("e5", Some('P'), None), ("d6", None, Some('P'))];
+        assert_eq!(en_passant, expected.map(|(square, before, after)| (square.to_string(), before, after)));
+
+        let promotion = diff_after("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n");
+        let expected = [("a7", Some('P'), None), ("b8", Some('r'), Some('N'))];
+        assert_eq!(promotion, expected.map(|(square, before, after)| (square.to_string(), before, after)));
+        let quiet_promotion = diff_after("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q");
+        assert_eq!(quiet_promotion[1], ("a8".to_string(), None, Some('Q')));
+    }
+    #[test]
+    fn test_jumps() {
+        let start = Game::new().board;
+        let game = Game::from_moves(&["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6"]).unwrap();
+        let changes = start.diff(&game.board);
+        let squares = changes.iter().map(|change| change.square.to_string()).collect::<Vec<String>>();
+        assert_eq!(squares, ["f1", "g1", "e2", "f3", "e4", "e5", "a6", "c6", "a7", "e7", "b8"]);
+        let c6 = changes.iter().find(|change| change.square.to_string() == "c6").unwrap();
+        assert_eq!((c6.before, c6.after.map(|piece| piece.kind)), (None, Some(PieceKind::Bishop)));
+        assert_eq!(game.board.diff(&start).len(), changes.len()); // The undo is the same squares the other way around
+        assert!(game.board.diff(&start).iter().zip(&changes).all(|(undo, redo)| undo.before == redo.after && undo.after == redo.before));
+
+        let there_and_back = Game::from_moves(&["Nf3", "Nf6", "Ng1", "Ng8"]).unwrap();
+        assert!(start.diff(&there_and_back.board).is_empty());
+    }
+}
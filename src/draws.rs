@@ -0,0 +1,273 @@
+/*****************************
+*  DRAW CLAIMS               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Draws that a player has to claim, rather than ones that end the game by themselves.
+
+The fifty-move rule already ends the game on its own once the halfmove clock reaches 100 (see GameStatus::FiftyMoveDraw),
+so claiming it is mostly useful together with the move that reaches 100. Threefold repetition only ends the game
+when it is claimed. Positions count as the same when the same pieces stand on the same squares, with the same side to
+move, the same castling rights and the same en passant capture available, as FIDE's rules require.
+
+As FIDE's rules allow, a claim can also be made with the move the player intends to play, if that move would
+produce the condition. The move is then played, and the game is drawn.
+*/
+
+use std::collections::HashMap;
+use std::mem;
+
+use crate::{Game, GameStatus, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent a draw that can be claimed, and why.
+///
+/// ```text
+/// FiftyMoveRule, // No pawn has moved and nothing has been captured in the last fifty moves by each side.
+/// ThreefoldRepetition { occurrences: u8 }, // The position has occurred this many times (at least 3).
+/// ```
+pub enum DrawClaim {
+    FiftyMoveRule,
+    ThreefoldRepetition { occurrences: u8 },
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+pub(crate) fn forget_position(game: &mut Game) {
+    let key = game.board.zobrist_key();
+    if let Some(occurrences) = game.position_occurrences.get_mut(&key) {
+        *occurrences -= 1;
+        if *occurrences == 0 {
+            game.position_occurrences.remove(&key);
+        }
+    }
+} // Takes one occurrence of the current position off the count, before a move is taken back
+
+fn claims_in(game: &Game) -> Vec<DrawClaim> {
+    let mut claims = vec![];
+    if game.board.halfmove_counter >= 100 {
+        claims.push(DrawClaim::FiftyMoveRule);
+    }
+    let occurrences = game.repetition_count();
+    if occurrences >= 3 {
+        claims.push(DrawClaim::ThreefoldRepetition { occurrences });
+    }
+    claims
+} // Every draw that the current position allows to claim, whether or not the game is over
+
+fn game_after(game: &Game, intended_move: &Move) -> Option<Game> {
+    let mut next_game = game.clone();
+    next_game.make_uci_move(&intended_move.to_uci()).then_some(next_game)
+} // The game after a move, or None if the move is illegal
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to return how often the current position has occurred since the position the game started from,
+    /// counting the current occurrence (see module docs for when positions are the same).
+    ///
+    /// ## Returns
+    /// This function returns at least 1. From 3, a draw can be claimed (see docs for claimable_draws). The count is kept
+    /// up to date as moves are made and taken back, so this doesn't replay the game.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// for uci_move in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+    ///     example_game.make_uci_move(uci_move);
+    /// }
+    /// assert_eq!(example_game.repetition_count(), 2); // One more, and a draw can be claimed
+    /// ```
+    pub fn repetition_count(&self) -> u8 {
+        self.position_occurrences.get(&self.board.zobrist_key()).copied().unwrap_or(1)
+    }
+
+    /// A function to return how often every position of the game has occurred since the position the game started from.
+    ///
+    /// ## Returns
+    /// This function returns a map from the Zobrist key of each position (see docs for Board::zobrist_key) to the
+    /// number of times it has occurred.
+    pub fn position_occurrences(&self) -> &HashMap<u64, u8> {
+        &self.position_occurrences
+    }
+
+    /// A function to list the draws that the active player can claim in the current position.
+    ///
+    /// ## Returns
+    /// This function returns a Vec of DrawClaims (see docs for DrawClaim), which is empty if no draw can be claimed
+    /// or the game is already over.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::draws::DrawClaim;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// for uci_move in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+    ///     example_game.make_uci_move(uci_move);
+    /// }
+    /// assert_eq!(example_game.claimable_draws(), vec![DrawClaim::ThreefoldRepetition { occurrences: 3 }]);
+    /// ```
+    pub fn claimable_draws(&self) -> Vec<DrawClaim> {
+        if self.game_status.is_game_over() {
+            return vec![];
+        }
+        claims_in(self)
+    }
+
+    /// A function to list the draws that the active player could claim together with the move they intend to play.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// intended_move: &Move, // The move the player intends to play. It is not played.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a Vec of DrawClaims, which is empty if the move is illegal or the game is already over.
+    /// Claims that are already valid without the move are not included, unless the move keeps them valid.
+    pub fn claimable_draws_after(&self, intended_move: &Move) -> Vec<DrawClaim> {
+        if self.game_status.is_game_over() {
+            return vec![];
+        }
+        game_after(self, intended_move).map(|next_game| claims_in(&next_game)).unwrap_or_default()
+    }
+
+    /// A function to claim a draw, which ends the game if the claim is valid.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// claim: DrawClaim, // The draw to claim. The number of occurrences of a repetition claim is not checked.
+    /// intended_move: Option<Move>, // The move the claim depends on, if any. It is played when the claim is valid.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the game was drawn, or false if the claim was invalid (the game is then unchanged).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, GameStatus, Move};
+    /// # use alviny_task_3::draws::DrawClaim;
+    /// let mut example_game = Game::new_from_fen("8/8/4k3/8/8/4K3/4R3/8 w - - 99 80".to_string());
+    /// assert!(example_game.claimable_draws().is_empty());
+    /// assert!(example_game.claim_draw(DrawClaim::FiftyMoveRule, Move::from_uci("e2a2"))); // The hundredth halfmove
+    /// assert_eq!(example_game.game_status, GameStatus::FiftyMoveDraw);
+    /// ```
+    pub fn claim_draw(&mut self, claim: DrawClaim, intended_move: Option<Move>) -> bool {
+        if self.game_status.is_game_over() {
+            return false;
+        }
+        let next_game = match &intended_move {
+            Some(intended_move) => match game_after(self, intended_move) {
+                Some(next_game) => next_game,
+                None => return false,
+            },
+            None => self.clone(),
+        };
+        let is_valid = claims_in(&next_game)
+            .iter()
+            .any(|valid_claim| mem::discriminant(valid_claim) == mem::discriminant(&claim));
+        if !is_valid {
+            return false;
+        }
+        if let Some(intended_move) = intended_move {
+            self.make_uci_move(&intended_move.to_uci()); // Played on self as well, so the observer hears about it
+        }
+        self.game_status = match claim {
+            DrawClaim::FiftyMoveRule => GameStatus::FiftyMoveDraw,
+            DrawClaim::ThreefoldRepetition { .. } => GameStatus::DrawByRepetition,
+        };
+        true
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, uci_moves: &[&str]) {
+        for uci_move in uci_moves {
+            assert!(game.make_uci_move(uci_move), "{}", uci_move);
+        }
+    }
+
+    #[test]
+    fn test_repetition_needs_the_intended_move() {
+        let mut test_game = Game::new();
+        play(&mut test_game, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"]);
+        let back_home = Move::from_uci("f6g8").unwrap(); // Reaches the starting position for the third time
+        let claim = DrawClaim::ThreefoldRepetition { occurrences: 3 };
+        assert!(test_game.claimable_draws().is_empty());
+        assert_eq!(test_game.claimable_draws_after(&back_home), vec![claim]);
+        assert!(test_game.claimable_draws_after(&Move::from_uci("b8c6").unwrap()).is_empty());
+        assert!(!test_game.claim_draw(claim, None));
+        assert!(!test_game.claim_draw(claim, Move::from_uci("f6f5"))); // Not a legal move
+        assert!(!test_game.claim_draw(DrawClaim::FiftyMoveRule, Some(back_home.clone())));
+        assert_eq!(test_game.history.len(), 7); // Failed claims don't play the move
+        assert!(test_game.claim_draw(claim, Some(back_home)));
+        assert_eq!(test_game.history.len(), 8);
+        assert_eq!(test_game.game_status, GameStatus::DrawByRepetition);
+        assert!(test_game.is_draw());
+        assert!(test_game.legal_moves().is_empty());
+    }
+    #[test]
+    fn test_repetition_count_at_each_ply() {
+        let mut test_game = Game::new();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"];
+        let expected_counts = [1, 1, 1, 2, 2, 2, 2, 3];
+        assert_eq!(test_game.repetition_count(), 1);
+        for (uci_move, expected_count) in shuffle.iter().zip(expected_counts) {
+            play(&mut test_game, &[uci_move]);
+            assert_eq!(test_game.repetition_count(), expected_count, "after {}", uci_move);
+        }
+        assert_eq!(test_game.position_occurrences().len(), 4);
+        assert_eq!(test_game.position_occurrences().values().sum::<u8>(), 9);
+        test_game.undo_move();
+        assert_eq!(test_game.repetition_count(), 2);
+        test_game.undo_move();
+        assert_eq!(test_game.repetition_count(), 2);
+        assert_eq!(test_game.position_occurrences().values().sum::<u8>(), 7);
+    }
+    #[test]
+    fn test_repetition_respects_castling_and_en_passant() {
+        let mut test_game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        play(&mut test_game, &["e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert_eq!(test_game.repetition_count(), 1); // The same pieces, but castling rights were lost
+        play(&mut test_game, &["e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert_eq!(test_game.repetition_count(), 2);
+
+        let mut test_game = Game::new_from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1".to_string());
+        play(&mut test_game, &["e2e4", "e8d8", "e1d1", "d8e8", "d1e1"]);
+        assert_eq!(test_game.repetition_count(), 1); // e3 can't be captured on any more
+        play(&mut test_game, &["e8d8", "e1d1", "d8e8", "d1e1"]);
+        assert_eq!(test_game.repetition_count(), 2);
+    }
+    #[test]
+    fn test_fifty_move_claims() {
+        let test_game = Game::new_from_fen("8/8/4k3/8/8/4K3/4R3/8 w - - 99 80".to_string());
+        assert_eq!(test_game.claimable_draws_after(&Move::from_uci("e2a2").unwrap()), vec![DrawClaim::FiftyMoveRule]);
+        let with_pawn = Game::new_from_fen("8/8/4k3/8/8/4K3/P3R3/8 w - - 99 80".to_string());
+        assert!(with_pawn.claimable_draws_after(&Move::from_uci("a2a3").unwrap()).is_empty()); // Pawn moves reset the clock
+        let automatic = Game::new_from_fen("8/8/4k3/8/8/4K3/4R3/8 w - - 100 80".to_string());
+        assert!(automatic.claimable_draws().is_empty()); // Already drawn
+    }
+}
@@ -0,0 +1,275 @@
+/*****************************
+*  ECO CLASSIFICATION        *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Classification of openings by the Encyclopaedia of Chess Openings (ECO), e.g. "B20 Sicilian Defence". Only available
+with the `eco` feature, which embeds the table of opening lines.
+
+The lines are keyed by the position they reach (see docs for Board::zobrist_key), not by their moves, so a game that
+transposes into an opening is classified as that opening: 1. c4 e6 2. Nc3 Nf6 3. d4 Bb4 is a Nimzo-Indian Defence. Of
+all the lines whose position occurs in the game, the longest one wins, so a game is classified by the deepest line
+it follows.
+
+The table covers the main openings and their best-known variations rather than all 500 codes. To add a line, add an
+entry to ECO_LINES.
+
+```
+# use alviny_task_3::Game;
+let example_game = Game::from_moves(&["e4", "c5", "Nf3", "d6"]).unwrap();
+let (code, name) = example_game.opening().unwrap();
+assert_eq!((code.to_string().as_str(), name), ("B50", "Sicilian Defence: Modern Variations"));
+```
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{Board, Game};
+
+/// The opening lines: the ECO code, the name, and the moves in SAN from the starting position.
+const ECO_LINES: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A00", "Grob Opening", "g4"),
+    ("A00", "Van 't Kruijs Opening", "e3"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird's Opening", "f4"),
+    ("A02", "Bird's Opening: From's Gambit", "f4 e5"),
+    ("A03", "Bird's Opening: Dutch Variation", "f4 d5"),
+    ("A04", "Réti Opening", "Nf3"),
+    ("A05", "Réti Opening", "Nf3 Nf6"),
+    ("A06", "Réti Opening", "Nf3 d5"),
+    ("A09", "Réti Opening", "Nf3 d5 c4"),
+    ("A10", "English Opening", "c4"),
+    ("A15", "English Opening: Anglo-Indian Defence", "c4 Nf6"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Old Benoni Defence", "d4 c5"),
+    ("A45", "Indian Defence", "d4 Nf6"),
+    ("A46", "Indian Defence", "d4 Nf6 Nf3"),
+    ("A50", "Indian Defence", "d4 Nf6 c4"),
+    ("A51", "Budapest Gambit", "d4 Nf6 c4 e5"),
+    ("A56", "Benoni Defence", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A60", "Modern Benoni", "d4 Nf6 c4 c5 d5 e6"),
+    ("A80", "Dutch Defence", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B00", "Nimzowitsch Defence", "e4 Nc6"),
+    ("B01", "Scandinavian Defence", "e4 d5"),
+    ("B01", "Scandinavian Defence: Mieses-Kotroc Variation", "e4 d5 exd5 Qxd5"),
+    ("B02", "Alekhine's Defence", "e4 Nf6"),
+    ("B06", "Modern Defence", "e4 g6"),
+    ("B07", "Pirc Defence", "e4 d6 d4 Nf6"),
+    ("B10", "Caro-Kann Defence", "e4 c6"),
+    ("B12", "Caro-Kann Defence: Advance Variation", "e4 c6 d4 d5 e5"),
+    ("B13", "Caro-Kann Defence: Exchange Variation", "e4 c6 d4 d5 exd5"),
+    ("B15", "Caro-Kann Defence", "e4 c6 d4 d5 Nc3"),
+    ("B20", "Sicilian Defence", "e4 c5"),
+    ("B21", "Sicilian Defence: Smith-Morra Gambit", "e4 c5 d4 cxd4 c3"),
+    ("B22", "Sicilian Defence: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defence: Closed", "e4 c5 Nc3"),
+    ("B27", "Sicilian Defence", "e4 c5 Nf3"),
+    ("B30", "Sicilian Defence: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B32", "Sicilian Defence: Open", "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4"),
+    ("B40", "Sicilian Defence: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defence: Modern Variations", "e4 c5 Nf3 d6"),
+    ("B54", "Sicilian Defence: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    ("B70", "Sicilian Defence: Dragon Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6"),
+    ("B90", "Sicilian Defence: Najdorf Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6"),
+    ("C00", "French Defence", "e4 e6"),
+    ("C01", "French Defence: Exchange Variation", "e4 e6 d4 d5 exd5"),
+    ("C02", "French Defence: Advance Variation", "e4 e6 d4 d5 e5"),
+    ("C03", "French Defence: Tarrasch Variation", "e4 e6 d4 d5 Nd2"),
+    ("C10", "French Defence", "e4 e6 d4 d5 Nc3"),
+    ("C11", "French Defence: Classical Variation", "e4 e6 d4 d5 Nc3 Nf6"),
+    ("C15", "French Defence: Winawer Variation", "e4 e6 d4 d5 Nc3 Bb4"),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C31", "King's Gambit Declined: Falkbeer Countergambit", "e4 e5 f4 d5"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defence", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defence", "e4 e5 Nf3 Nf6"),
+    ("C44", "King's Knight Opening: Normal Variation", "e4 e5 Nf3 Nc6"),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    ("C51", "Italian Game: Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    ("C53", "Italian Game: Classical Variation", "e4 e5 Nf3 Nc6 Bc4 Bc5 c3"),
+    ("C55", "Italian Game: Two Knights Defence", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    ("C57", "Italian Game: Two Knights Defence, Knight Attack", "e4 e5 Nf3 Nc6 Bc4 Nf6 Ng5"),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defence", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    ("C68", "Ruy Lopez: Exchange Variation", "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6"),
+    ("C70", "Ruy Lopez: Morphy Defence", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4"),
+    ("C78", "Ruy Lopez: Morphy Defence", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O"),
+    ("C84", "Ruy Lopez: Closed", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7"),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D02", "Queen's Pawn Game", "d4 d5 Nf3"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D07", "Queen's Gambit Declined: Chigorin Defence", "d4 d5 c4 Nc6"),
+    ("D08", "Queen's Gambit Declined: Albin Countergambit", "d4 d5 c4 e5"),
+    ("D10", "Slav Defence", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D35", "Queen's Gambit Declined", "d4 d5 c4 e6 Nc3 Nf6"),
+    ("D43", "Semi-Slav Defence", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D80", "Grünfeld Defence", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Indian Defence", "d4 Nf6 c4 e6"),
+    ("E11", "Bogo-Indian Defence", "d4 Nf6 c4 e6 Nf3 Bb4+"),
+    ("E12", "Queen's Indian Defence", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defence", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defence", "d4 Nf6 c4 g6"),
+];
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A struct to represent an ECO code, e.g. B20. Display writes it as the volume letter and two digits.
+///
+/// ## Attributes
+/// ```text
+/// volume: char, // 'A' (flank openings) to 'E' (Indian defences).
+/// number: u8, // 0 to 99.
+/// ```
+pub struct EcoCode {
+    pub volume: char,
+    pub number: u8,
+}
+impl EcoCode {
+    /// A function to read an ECO code.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// code: &str, // A volume letter from A to E and two digits, e.g. "B20".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(EcoCode), or None if the code is malformed.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::eco::EcoCode;
+    /// assert_eq!(EcoCode::from_code("C42"), Some(EcoCode { volume: 'C', number: 42 }));
+    /// assert_eq!(EcoCode::from_code("F00"), None);
+    /// ```
+    pub fn from_code(code: &str) -> Option<EcoCode> {
+        let mut characters = code.chars();
+        let volume = characters.next().filter(|volume| ('A'..='E').contains(volume))?;
+        let digits = characters.as_str();
+        if digits.len() != 2 || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+        Some(EcoCode { volume, number: digits.parse().ok()? })
+    }
+}
+impl fmt::Display for EcoCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{:02}", self.volume, self.number)
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn line_positions() -> &'static HashMap<u64, (usize, usize)> {
+    static POSITIONS: OnceLock<HashMap<u64, (usize, usize)>> = OnceLock::new();
+    POSITIONS.get_or_init(|| {
+        let mut positions = HashMap::new();
+        for (index, (_code, _name, moves)) in ECO_LINES.iter().enumerate() {
+            let mut game = Game::new();
+            let moves = moves.split_whitespace().collect::<Vec<&str>>();
+            game.play_moves(&moves).expect("the ECO lines are legal");
+            positions.insert(game.board.zobrist_key(), (moves.len(), index));
+        }
+        positions
+    })
+} // The position at the end of every line in ECO_LINES, with the length and index of the line, built on first use
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to classify the game by its opening (see module docs).
+    ///
+    /// ## Returns
+    /// This function returns the ECO code and name of the longest opening line whose position occurs in the game, or
+    /// None if there is none, e.g. for a game without moves or one that doesn't start from the starting position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::from_moves(&["d4", "Nf6", "c4", "e6", "Nc3", "Bb4"]).unwrap();
+    /// let (code, name) = example_game.opening().unwrap();
+    /// assert_eq!((code.to_string().as_str(), name), ("E20", "Nimzo-Indian Defence"));
+    /// assert_eq!(Game::new().opening(), None);
+    /// ```
+    pub fn opening(&self) -> Option<(EcoCode, &'static str)> {
+        let positions = line_positions();
+        let mut board: Board = Game::try_from_fen(&self.start_fen).ok()?.board;
+        let mut longest: Option<(usize, usize)> = None;
+        for played in &self.history {
+            board = board.after_move(played)?;
+            if let Some(&(length, index)) = positions.get(&board.zobrist_key())
+                && longest.is_none_or(|(longest_length, _index)| length >= longest_length)
+            {
+                longest = Some((length, index));
+            }
+        }
+        let (code, name, _moves) = ECO_LINES[longest?.1];
+        Some((EcoCode::from_code(code)?, name))
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opening_of(moves: &[&str]) -> Option<(String, &'static str)> {
+        Game::from_moves(moves).unwrap().opening().map(|(code, name)| (code.to_string(), name))
+    }
+
+    #[test]
+    fn test_classification() {
+        assert_eq!(opening_of(&["e4", "c5"]), Some(("B20".to_string(), "Sicilian Defence")));
+        assert_eq!(opening_of(&["d4", "Nf6", "c4", "e6", "Nc3", "Bb4"]), Some(("E20".to_string(), "Nimzo-Indian Defence")));
+        assert_eq!(opening_of(&["e4", "c5", "Nf3", "d6", "d4", "cxd4", "Nxd4", "Nf6", "Nc3", "a6", "Be3"]).unwrap().1, "Sicilian Defence: Najdorf Variation");
+        assert_eq!(opening_of(&["e4", "c5", "Nf3", "d6", "d4", "cxd4", "Qxd4"]).unwrap().0, "B50"); // Leaves the book after 2... d6
+        assert_eq!(opening_of(&["a3"]), None);
+    }
+    #[test]
+    fn test_transpositions() {
+        assert_eq!(opening_of(&["c4", "e6", "Nc3", "Nf6", "d4", "Bb4"]).unwrap().1, "Nimzo-Indian Defence");
+        assert_eq!(opening_of(&["Nf3", "Nc6", "e4", "e5", "Bb5"]).unwrap().1, "Ruy Lopez");
+        assert_eq!(opening_of(&["Nf3", "Nf6", "d4"]).unwrap(), ("A46".to_string(), "Indian Defence")); // The en passant square of d4 doesn't matter
+    }
+    #[test]
+    fn test_table() {
+        assert_eq!(line_positions().len(), ECO_LINES.len()); // Every line reaches its own position
+        for (code, name, _moves) in ECO_LINES {
+            assert!(EcoCode::from_code(code).is_some(), "{} {}", code, name);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+/*****************************
+*  ENDGAMES                  *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Recognition of the common endgames by their material, e.g. to hand a position over to specialised knowledge instead
+of the general evaluation.
+
+An endgame is named after the material of the stronger side, then the weaker side, with the kings included: KRvK is a
+king and rook against a bare king, whichever color has the rook. The colors are normalised away, and the side with
+the extra material is reported separately.
+
+To recognise another endgame, add a variant to EndgameKind and a line to the ENDGAMES table.
+
+```
+# use alviny_task_3::{Color, Game};
+# use alviny_task_3::endgame::EndgameKind;
+let example_game = Game::new_from_fen("8/8/4k3/8/8/8/4K3/r7 w - - 0 1".to_string());
+let class = example_game.board.endgame_class().unwrap();
+assert_eq!((class.kind, class.strong_side), (EndgameKind::KRvK, Some(Color::Black)));
+```
+*/
+
+pub mod kpk;
+
+use std::fmt;
+
+use crate::evaluation::MaterialCount;
+use crate::{Board, Color};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent an endgame by its material, stronger side first (see module docs). Display gives the name,
+/// e.g. "KBNvK".
+///
+/// ```text
+/// KvK, // Bare kings.
+/// KNvK, KBvK, // A lone minor piece, which can't mate.
+/// KNNvK, // Two knights, which can't force mate.
+/// KBNvK, KBBvK, KRvK, KQvK, // The basic mates.
+/// KPvK, // King and pawn against king.
+/// KPvKP, // A pawn each.
+/// KRvKP, KQvKP, // A piece against a pawn.
+/// KRvKN, KRvKB, // Rook against a minor piece, usually a draw.
+/// KQvKR, // Queen against rook.
+/// KRvKR, KQvKQ, // Equal pieces.
+/// KRPvKR, // Rook and pawn against rook.
+/// KBPvKB, // Bishop and pawn against bishop.
+/// ```
+pub enum EndgameKind {
+    KvK,
+    KNvK,
+    KBvK,
+    KNNvK,
+    KBNvK,
+    KBBvK,
+    KRvK,
+    KQvK,
+    KPvK,
+    KPvKP,
+    KRvKP,
+    KQvKP,
+    KRvKN,
+    KRvKB,
+    KQvKR,
+    KRvKR,
+    KQvKQ,
+    KRPvKR,
+    KBPvKB,
+}
+impl fmt::Display for EndgameKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (strong, weak, _kind) = ENDGAMES.iter().find(|(_strong, _weak, kind)| kind == self).expect("every endgame is in the table");
+        write!(f, "{}v{}", strong, weak)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent the endgame on a board (see docs for Board::endgame_class).
+///
+/// ## Attributes
+/// ```text
+/// kind: EndgameKind, // The endgame, with the colors normalised away.
+/// strong_side: Option<Color>, // The color with the first material of the name, or None if both sides have the same material.
+/// ```
+pub struct EndgameClass {
+    pub kind: EndgameKind,
+    pub strong_side: Option<Color>,
+}
+
+/// The recognised endgames, as (material of the stronger side, material of the weaker side, kind). Pieces are written
+/// K, Q, R, B, N, P, in that order.
+pub const ENDGAMES: [(&str, &str, EndgameKind); 19] = [
+    ("K", "K", EndgameKind::KvK),
+    ("KN", "K", EndgameKind::KNvK),
+    ("KB", "K", EndgameKind::KBvK),
+    ("KNN", "K", EndgameKind::KNNvK),
+    ("KBN", "K", EndgameKind::KBNvK),
+    ("KBB", "K", EndgameKind::KBBvK),
+    ("KR", "K", EndgameKind::KRvK),
+    ("KQ", "K", EndgameKind::KQvK),
+    ("KP", "K", EndgameKind::KPvK),
+    ("KP", "KP", EndgameKind::KPvKP),
+    ("KR", "KP", EndgameKind::KRvKP),
+    ("KQ", "KP", EndgameKind::KQvKP),
+    ("KR", "KN", EndgameKind::KRvKN),
+    ("KR", "KB", EndgameKind::KRvKB),
+    ("KQ", "KR", EndgameKind::KQvKR),
+    ("KR", "KR", EndgameKind::KRvKR),
+    ("KQ", "KQ", EndgameKind::KQvKQ),
+    ("KRP", "KR", EndgameKind::KRPvKR),
+    ("KBP", "KB", EndgameKind::KBPvKB),
+];
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn signature(material: MaterialCount) -> String {
+    let pieces = [('Q', material.queens), ('R', material.rooks), ('B', material.bishops), ('N', material.knights), ('P', material.pawns)];
+    let mut signature = String::from("K");
+    for (letter, count) in pieces {
+        signature.extend(std::iter::repeat_n(letter, count as usize));
+    }
+    signature
+} // The material of one side as written in an endgame name, e.g. "KRP"
+
+fn strength(material: MaterialCount) -> (i32, u32, u32, u32, u32, u32) {
+    (material.value(), material.queens, material.rooks, material.bishops, material.knights, material.pawns)
+} // Orders material for material_signature(): by value, then by the heaviest pieces
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to write the material on the board as a signature, e.g. "KRPvKR" for a rook and pawn against a
+    /// rook, as used to route positions to endgame knowledge or tablebases. The stronger side comes first, so the
+    /// signature is the same whichever color has the extra material, and matches the names of EndgameKind.
+    ///
+    /// The stronger side is the one with the higher material value (see docs for MaterialCount::value). If the values
+    /// are equal, it is the side with more queens, then more rooks, bishops, knights and pawns, in that order, so KBvKN
+    /// has the bishop first. If both sides have the same material, the order doesn't matter.
+    ///
+    /// ## Returns
+    /// This function returns the signature: each side's pieces in the order K, Q, R, B, N, P, joined by 'v'.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("3r4/8/4k3/3p4/8/8/4K3/R7 w - - 0 1".to_string());
+    /// assert_eq!(example_game.board.material_signature(), "KRPvKR"); // Black has the pawn
+    /// assert_eq!(example_game.board.material_signature_colored(), "KRvKRP");
+    /// ```
+    pub fn material_signature(&self) -> String {
+        let (white, black) = (self.material('w'), self.material('b'));
+        let (strong, weak) = if strength(black) > strength(white) { (black, white) } else { (white, black) };
+        format!("{}v{}", signature(strong), signature(weak))
+    }
+
+    /// A function to write the material on the board as a signature with White's pieces first, e.g. "KRvKRP" when
+    /// Black has the extra pawn (see docs for material_signature).
+    ///
+    /// ## Returns
+    /// This function returns White's pieces and Black's pieces, each in the order K, Q, R, B, N, P, joined by 'v'.
+    pub fn material_signature_colored(&self) -> String {
+        format!("{}v{}", signature(self.material('w')), signature(self.material('b')))
+    }
+
+    /// A function to recognise the endgame on the board by its material (see module docs).
+    ///
+    /// ## Returns
+    /// This function returns Some(EndgameClass) if the material is one of the ENDGAMES, or None otherwise, e.g. with
+    /// more material on the board. Only the material counts: where the pieces stand, and whose turn it is, doesn't.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game};
+    /// # use alviny_task_3::endgame::EndgameKind;
+    /// let example_game = Game::new_from_fen("8/8/4k3/8/3P4/8/4K3/8 w - - 0 1".to_string());
+    /// let class = example_game.board.endgame_class().unwrap();
+    /// assert_eq!((class.kind, class.strong_side), (EndgameKind::KPvK, Some(Color::White)));
+    /// assert_eq!(class.kind.to_string(), "KPvK");
+    /// assert_eq!(Game::new().board.endgame_class(), None);
+    /// ```
+    pub fn endgame_class(&self) -> Option<EndgameClass> {
+
+        let (white, black) = (signature(self.material('w')), signature(self.material('b')));
+        ENDGAMES.iter().find_map(|(strong, weak, kind)| {
+            let strong_side = match (white == *strong && black == *weak, black == *strong && white == *weak) {
+                (true, true) => None,
+                (true, false) => Some(Color::White),
+                (false, true) => Some(Color::Black),
+                (false, false) => return None,
+            };
+            Some(EndgameClass { kind: *kind, strong_side })
+        })
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    #[test]
+    fn test_endgame_classes() {
+        let cases = [
+            // (FEN, kind, strong side)
+            ("8/8/4k3/8/8/8/4K3/8 w - - 0 1", EndgameKind::KvK, None),
+            ("8/8/4k3/8/3P4/8/4K3/8 w - - 0 1", EndgameKind::KPvK, Some(Color::White)),
+            ("8/8/4k3/3p4/8/8/4K3/8 b - - 0 1", EndgameKind::KPvK, Some(Color::Black)),
+            ("8/8/4k3/8/8/8/4K3/R7 w - - 0 1", EndgameKind::KRvK, Some(Color::White)),
+            ("8/8/4k3/8/8/8/4K3/r7 w - - 0 1", EndgameKind::KRvK, Some(Color::Black)),
+            ("8/8/4k3/8/8/8/4K3/q7 w - - 0 1", EndgameKind::KQvK, Some(Color::Black)),
+            ("8/8/4k3/8/8/8/4K3/2BN4 w - - 0 1", EndgameKind::KBNvK, Some(Color::White)),
+            ("8/8/4k3/8/8/8/4K3/2NB4 w - - 0 1", EndgameKind::KBNvK, Some(Color::White)), // The order on the board doesn't matter
+            ("8/8/4k3/8/8/8/4K3/1NN5 w - - 0 1", EndgameKind::KNNvK, Some(Color::White)),
+            ("8/8/4k3/8/8/8/4K3/2bb4 w - - 0 1", EndgameKind::KBBvK, Some(Color::Black)),
+            ("3r4/8/4k3/8/3P4/8/4K3/R7 w - - 0 1", EndgameKind::KRPvKR, Some(Color::White)),
+            ("3r4/8/4k3/3p4/8/8/4K3/R7 w - - 0 1", EndgameKind::KRPvKR, Some(Color::Black)),
+            ("3r4/8/4k3/8/8/8/4K3/R7 w - - 0 1", EndgameKind::KRvKR, None),
+            ("8/8/4k3/3p4/3P4/8/4K3/8 w - - 0 1", EndgameKind::KPvKP, None),
+            ("3q4/8/4k3/8/8/8/4K3/R7 w - - 0 1", EndgameKind::KQvKR, Some(Color::Black)),
+            ("8/8/4k3/8/8/8/4Kb2/R7 w - - 0 1", EndgameKind::KRvKB, Some(Color::White)),
+        ];
+        for (fen, kind, strong_side) in cases {
+            let board = Game::new_from_fen(fen.to_string()).board;
+            assert_eq!(board.endgame_class(), Some(EndgameClass { kind, strong_side }), "{}", fen);
+            assert_eq!(board.material_signature(), kind.to_string(), "{}", fen);
+        }
+    }
+    #[test]
+    fn test_material_signatures() {
+        let cases = [
+            // (FEN, signature, colored signature)
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP", "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP"),
+            ("8/8/4k3/8/8/2q5/4K3/R7 w - - 0 1", "KQvKR", "KRvKQ"), // Black is stronger, so it goes first
+            ("8/5n2/4k3/8/8/8/4K3/2B5 w - - 0 1", "KBvKN", "KBvKN"),
+            ("8/5b2/4k3/8/8/8/4K3/2N5 w - - 0 1", "KBvKN", "KNvKB"), // Equal value: the bishop outranks the knight
+            ("8/5bp1/4k3/8/8/8/4K3/R7 b - - 0 1", "KRvKBP", "KRvKBP"),
+            ("8/5pp1/4k3/8/8/8/4K3/3N4 w - - 0 1", "KNvKPP", "KNvKPP"),
+            ("8/3pppp1/4k3/8/8/8/4K3/3N4 w - - 0 1", "KPPPPvKN", "KNvKPPPP"),
+            ("8/4ppp1/4k3/8/8/8/4K3/3N4 w - - 0 1", "KNvKPPP", "KNvKPPP"), // Equal value: pieces before pawns
+        ];
+        for (fen, signature, colored) in cases {
+            let board = Game::new_from_fen(fen.to_string()).board;
+            assert_eq!((board.material_signature().as_str(), board.material_signature_colored().as_str()), (signature, colored), "{}", fen);
+        }
+    }
+    #[test]
+    fn test_unknown_material() {
+        for fen in [
+            "8/8/4k3/8/3PP3/8/4K3/R7 w - - 0 1", // KRPP v K: extra pawns
+            "3r4/8/4k3/8/3P4/8/4KP2/R7 w - - 0 1", // KRPP v KR
+            "8/8/4k3/8/8/8/4K3/RR6 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ] {
+            assert_eq!(Game::new_from_fen(fen.to_string()).board.endgame_class(), None, "{}", fen);
+        }
+    }
+    #[test]
+    fn test_table_is_consistent() {
+        for (index, (strong, weak, kind)) in ENDGAMES.iter().enumerate() {
+            assert_eq!(kind.to_string(), format!("{}v{}", strong, weak));
+            assert_eq!(format!("{:?}", kind), kind.to_string()); // The variant is named after the endgame
+            assert!(ENDGAMES[..index].iter().all(|(other_strong, other_weak, _kind)| (other_strong, other_weak) != (strong, weak) && (other_strong, other_weak) != (weak, strong)));
+        }
+    }
+}
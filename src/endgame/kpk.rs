@@ -0,0 +1,288 @@
+/*****************************
+*  KPK BITBASE               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+An exact solution of king and pawn against king, with White as the side with the pawn.
+
+KPK is hard to get right by search alone: whether the pawn queens often depends on who has the opposition, i.e. on
+zugzwang many moves ahead. But there are only a few hundred thousand positions, so all of them are solved once, the
+first time a position is probed, by retrograde analysis:
+
+1. Positions that are decided on the spot are marked: White wins if the pawn can promote without the new queen being
+   taken, and it is a draw if Black can take the pawn or is stalemated.
+2. Then, over and over until nothing changes, a position with White to move is a win if some move leads to a win,
+   and a position with Black to move is a draw if some move leads to a draw. A position where every move leads to a
+   draw (for White) or a win (for Black) is decided too.
+3. Everything still undecided is a draw: White can't force anything.
+
+Only files a to d are stored; a pawn on files e to h is the mirror image. Promotion is always to a queen, which is
+exact for this endgame up to the rare position where a queen stalemates and a rook would have won.
+
+```
+# use alviny_task_3::{Color, Square};
+# use alviny_task_3::endgame::kpk::{probe, KpkResult};
+let square = |name| Square::from_algebraic(name).unwrap();
+assert_eq!(probe(square("e4"), square("e3"), square("e6"), Color::White), KpkResult::Draw); // Black has the opposition
+assert_eq!(probe(square("e4"), square("e3"), square("e6"), Color::Black), KpkResult::Win); // Black must give it up
+```
+*/
+
+use std::sync::OnceLock;
+
+use crate::{Color, Square};
+
+/// The number of pawn squares stored: ranks 2 to 7 on files a to d.
+const PAWN_SQUARES: usize = 24;
+/// The number of positions in the bitbase: side to move, white king, black king and pawn.
+const POSITIONS: usize = 2 * 64 * 64 * PAWN_SQUARES;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent the outcome of a KPK position with best play (see docs for probe).
+///
+/// ```text
+/// Win, // The side with the pawn wins.
+/// Draw, // The side without the pawn draws.
+/// ```
+pub enum KpkResult {
+    Win,
+    Draw,
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+fn file(square: u8) -> i8 {
+    (square % 8) as i8
+}
+
+fn rank(square: u8) -> i8 {
+    (square / 8) as i8
+}
+
+fn distance(first: u8, second: u8) -> i8 {
+    (file(first) - file(second)).abs().max((rank(first) - rank(second)).abs())
+} // The number of king moves between two squares
+
+fn king_steps(square: u8) -> impl Iterator<Item = u8> {
+    (-1..=1)
+        .flat_map(|file_step| (-1..=1).map(move |rank_step| (file_step, rank_step)))
+        .filter(|&step| step != (0, 0))
+        .map(move |(file_step, rank_step)| (file(square) + file_step, rank(square) + rank_step))
+        .filter(|(file, rank)| (0..8).contains(file) && (0..8).contains(rank))
+        .map(|(file, rank)| (rank * 8 + file) as u8)
+} // The squares next to a square
+
+fn pawn_attacks(pawn: u8, square: u8) -> bool {
+    rank(square) == rank(pawn) + 1 && (file(square) - file(pawn)).abs() == 1
+} // Whether a White pawn attacks a square
+
+fn pawn_square(pawn_index: usize) -> u8 {
+    ((pawn_index / 4 + 1) * 8 + pawn_index % 4) as u8
+}
+
+fn pawn_index(pawn: u8) -> usize {
+    (rank(pawn) as usize - 1) * 4 + file(pawn) as usize
+} // The inverse of pawn_square(), for a pawn on ranks 2 to 7 and files a to d
+
+fn index(white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> usize {
+    ((usize::from(white_to_move) * 64 + white_king as usize) * 64 + black_king as usize) * PAWN_SQUARES + pawn_index(pawn)
+}
+
+fn initial_entry(white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> Entry {
+    if white_king == black_king || distance(white_king, black_king) <= 1 || pawn == white_king || pawn == black_king
+        || (white_to_move && pawn_attacks(pawn, black_king)) {
+        return Entry::Invalid;
+    }
+    if white_to_move {
+        let promotion = pawn + 8;
+        if rank(pawn) == 6 && promotion != white_king && promotion != black_king
+            && (distance(black_king, promotion) > 1 || distance(white_king, promotion) == 1) {
+            return Entry::Win;
+        }
+        return Entry::Unknown;
+    }
+    if distance(black_king, pawn) == 1 && distance(white_king, pawn) > 1 {
+        return Entry::Draw; // The pawn is taken
+    }
+    let escapes = king_steps(black_king).filter(|&square| distance(square, white_king) > 1 && !pawn_attacks(pawn, square)).count();
+    match (escapes, pawn_attacks(pawn, black_king)) {
+        (0, true) => Entry::Win, // Mated by the pawn
+        (0, false) => Entry::Draw, // Stalemate
+        _ => Entry::Unknown,
+    }
+} // What a position is before looking at any moves
+
+fn successors(white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> Vec<usize> {
+    if !white_to_move {
+        return king_steps(black_king)
+            .filter(|&square| square != pawn && distance(square, white_king) > 1 && !pawn_attacks(pawn, square))
+            .map(|square| index(true, white_king, square, pawn))
+            .collect();
+    } // Taking the pawn is only possible in positions that initial_entry() has decided already
+    let mut next = king_steps(white_king)
+        .filter(|&square| square != pawn && distance(square, black_king) > 1)
+        .map(|square| index(false, square, black_king, pawn))
+        .collect::<Vec<usize>>();
+    let free = |square: u8| square != white_king && square != black_king;
+    if rank(pawn) < 6 && free(pawn + 8) {
+        next.push(index(false, white_king, black_king, pawn + 8));
+        if rank(pawn) == 1 && free(pawn + 16) {
+            next.push(index(false, white_king, black_king, pawn + 16));
+        }
+    } // A promotion that didn't win in initial_entry() loses the queen, and can be left out
+    next
+} // The positions after every legal move, Black's captures and White's promotions excepted
+
+fn generate() -> Vec<u64> {
+    let positions = || {
+        [true, false].into_iter().flat_map(|white_to_move| {
+            (0..64).flat_map(move |white_king| (0..64).flat_map(move |black_king| (0..PAWN_SQUARES).map(move |pawn| (white_to_move, white_king, black_king, pawn_square(pawn)))))
+        })
+    };
+    let mut entries = vec![Entry::Invalid; POSITIONS];
+    for (white_to_move, white_king, black_king, pawn) in positions() {
+        entries[index(white_to_move, white_king, black_king, pawn)] = initial_entry(white_to_move, white_king, black_king, pawn);
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (white_to_move, white_king, black_king, pawn) in positions() {
+            let position = index(white_to_move, white_king, black_king, pawn);
+            if entries[position] != Entry::Unknown {
+                continue;
+            }
+            let next = successors(white_to_move, white_king, black_king, pawn).into_iter().map(|successor| entries[successor]).collect::<Vec<Entry>>();
+            let (good, bad) = if white_to_move { (Entry::Win, Entry::Draw) } else { (Entry::Draw, Entry::Win) };
+            if next.contains(&good) {
+                entries[position] = good;
+                changed = true;
+            } else if next.iter().all(|&entry| entry == bad) {
+                entries[position] = bad;
+                changed = true;
+            }
+        }
+    }
+    let mut bits = vec![0u64; POSITIONS.div_ceil(64)];
+    for (position, entry) in entries.into_iter().enumerate() {
+        if entry == Entry::Win {
+            bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+    bits
+} // Solves every position (see module docs) and keeps one bit per position: whether White wins
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to look up the outcome of a king and pawn against king position, with White as the side with the pawn.
+/// The first call solves the whole endgame, which takes a moment. Later calls are a lookup.
+///
+/// ## Arguments
+/// ```text
+/// white_king: Square, // The square of the king of the side with the pawn.
+/// pawn: Square, // The square of the pawn, which moves up the board.
+/// black_king: Square, // The square of the lone king.
+/// side_to_move: Color, // Whose turn it is.
+///
+/// ```
+/// ## Returns
+/// This function returns KpkResult::Win if White wins with best play, or KpkResult::Draw if Black draws. Positions
+/// that can't occur, e.g. with the kings next to each other or the pawn on the first or last rank, return
+/// KpkResult::Draw. For a position where Black has the pawn, flip the board first.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::{Color, Square};
+/// # use alviny_task_3::endgame::kpk::{probe, KpkResult};
+/// let square = |name| Square::from_algebraic(name).unwrap();
+/// assert_eq!(probe(square("b5"), square("a5"), square("a8"), Color::White), KpkResult::Draw); // The wrong rook pawn
+/// ```
+pub fn probe(white_king: Square, pawn: Square, black_king: Square, side_to_move: Color) -> KpkResult {
+    static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+    let (mut white_king, mut pawn, mut black_king) = (white_king.index(), pawn.index(), black_king.index());
+    if !(1..=6).contains(&rank(pawn)) {
+        return KpkResult::Draw;
+    }
+    if file(pawn) >= 4 {
+        (white_king, pawn, black_king) = (white_king ^ 7, pawn ^ 7, black_king ^ 7);
+    } // Mirror the board so that the pawn is on files a to d
+    if initial_entry(side_to_move == Color::White, white_king, black_king, pawn) == Entry::Invalid {
+        return KpkResult::Draw;
+    }
+    let position = index(side_to_move == Color::White, white_king, black_king, pawn);
+    if BITBASE.get_or_init(generate)[position / 64] & (1 << (position % 64)) != 0 { KpkResult::Win } else { KpkResult::Draw }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe_names(white_king: &str, pawn: &str, black_king: &str, side_to_move: Color) -> KpkResult {
+        let square = |name| Square::from_algebraic(name).unwrap();
+        probe(square(white_king), square(pawn), square(black_king), side_to_move)
+    }
+
+    #[test]
+    fn test_wrong_rook_pawn() {
+        for side_to_move in [Color::White, Color::Black] {
+            assert_eq!(probe_names("b5", "a5", "a8", side_to_move), KpkResult::Draw);
+            assert_eq!(probe_names("g6", "h5", "h8", side_to_move), KpkResult::Draw);
+        }
+        assert_eq!(probe_names("b7", "a5", "d7", Color::White), KpkResult::Win); // The king is cut off from the corner
+    }
+    #[test]
+    fn test_key_squares() {
+        for side_to_move in [Color::White, Color::Black] {
+            assert_eq!(probe_names("d6", "e4", "g7", side_to_move), KpkResult::Win); // d6 is a key square of the e4 pawn
+            assert_eq!(probe_names("f6", "e4", "e8", side_to_move), KpkResult::Win);
+            assert_eq!(probe_names("e6", "e5", "e8", side_to_move), KpkResult::Win); // With the pawn on the fifth, in front is enough
+        }
+        // The opposition: in front of a pawn on the third rank, the side to move loses it
+        assert_eq!(probe_names("e4", "e3", "e6", Color::White), KpkResult::Draw);
+        assert_eq!(probe_names("e4", "e3", "e6", Color::Black), KpkResult::Win);
+    }
+    #[test]
+    fn test_square_of_the_pawn() {
+        assert_eq!(probe_names("h1", "c5", "h6", Color::Black), KpkResult::Win); // Too far away to catch it
+        assert_eq!(probe_names("h1", "c5", "e6", Color::Black), KpkResult::Draw);
+        assert_eq!(probe_names("h1", "c5", "e6", Color::White), KpkResult::Draw);
+    }
+    #[test]
+    fn test_stalemate_traps() {
+        assert_eq!(probe_names("e6", "e7", "e8", Color::Black), KpkResult::Draw); // Stalemate
+        assert_eq!(probe_names("b6", "a7", "a8", Color::Black), KpkResult::Draw); // Stalemate in the corner
+        assert_eq!(probe_names("e6", "e7", "e8", Color::White), KpkResult::Win); // Kd6 Kf7 Kd7 and e8
+        assert_eq!(probe_names("f6", "e7", "e8", Color::White), KpkResult::Draw); // Ke6 stalemates, and anything else drops the pawn
+    }
+    #[test]
+    fn test_impossible_positions() {
+        assert_eq!(probe_names("e4", "e3", "e5", Color::White), KpkResult::Draw); // Kings next to each other
+        assert_eq!(probe_names("e4", "e8", "a1", Color::White), KpkResult::Draw); // Pawn on the last rank
+        assert_eq!(probe_names("e4", "d6", "e7", Color::White), KpkResult::Draw); // Black in check with White to move
+    }
+}
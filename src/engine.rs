@@ -0,0 +1,209 @@
+/*****************************
+*  ENGINE SESSION            *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The state an engine keeps while it is being driven by a GUI, shared by the protocol front-ends (see the uci and xboard
+modules), so that they only differ in how they read commands and write answers.
+
+A session is the current position, the options every search starts from, and a transposition table that is kept
+between searches (see docs for TranspositionTable), so that the search of the next move starts out knowing what the
+search of the last one found.
+*/
+
+use std::time::Duration;
+
+use crate::search::{InfoSink, Search, SearchHandle, SearchOptions, SearchResult, TranspositionTable, DEFAULT_HASH_SIZE};
+use crate::Game;
+
+/// How long the engine thinks when the GUI gives it no limits.
+pub const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+/// How much time the engine keeps in reserve for the GUI and the connection, unless set_move_overhead() says otherwise.
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+/// The largest move overhead set_move_overhead() accepts.
+pub const MAX_MOVE_OVERHEAD: Duration = Duration::from_secs(5);
+/// The largest transposition table set_hash_size() accepts, in megabytes.
+pub const MAX_HASH_SIZE: usize = 1024;
+/// The most lines set_multipv() accepts.
+pub const MAX_MULTIPV: usize = 64;
+/// How many more moves the engine expects to play when it divides up what is left on its clock.
+const EXPECTED_MOVES_LEFT: u32 = 30;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+/// A struct to represent an engine session.
+///
+/// ## Attributes
+/// ```text
+/// game: Game, // The position the GUI has set up.
+/// search_options: SearchOptions, // The options every search starts from (see docs for SearchOptions).
+/// move_overhead: Duration, // Taken off every time limit, for the GUI and the connection.
+/// table: TranspositionTable, // Kept between searches and cleared by new_game().
+/// ```
+pub struct EngineSession {
+    pub game: Game,
+    pub search_options: SearchOptions,
+    pub move_overhead: Duration,
+    pub table: TranspositionTable,
+}
+impl EngineSession {
+    /// A function to create a new session at the starting position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::engine::EngineSession;
+    /// let session = EngineSession::new();
+    /// assert_eq!(session.table.size_mb(), session.search_options.hash_size);
+    /// ```
+    pub fn new() -> EngineSession {
+        EngineSession {
+            game: Game::new(),
+            search_options: SearchOptions::default(),
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            table: TranspositionTable::new(DEFAULT_HASH_SIZE),
+        }
+    }
+
+    /// A function to start a new game at the starting position. What the table knows about the last game is forgotten.
+    pub fn new_game(&mut self) {
+        self.game = Game::new();
+        self.table.clear();
+    }
+
+    /// A function to change the size of the transposition table.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// megabytes: usize, // The new size, clamped between 1 and MAX_HASH_SIZE.
+    ///
+    /// ```
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.search_options.hash_size = megabytes.clamp(1, MAX_HASH_SIZE);
+        self.table.resize(self.search_options.hash_size);
+    }
+
+    /// A function to change how many lines every search returns.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// lines: usize, // The number of lines, clamped between 1 and MAX_MULTIPV.
+    ///
+    /// ```
+    pub fn set_multipv(&mut self, lines: usize) {
+        self.search_options.multipv = lines.clamp(1, MAX_MULTIPV);
+    }
+
+    /// A function to change how much time is kept in reserve for the GUI and the connection.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// overhead: Duration, // The new overhead, capped at MAX_MOVE_OVERHEAD.
+    ///
+    /// ```
+    pub fn set_move_overhead(&mut self, overhead: Duration) {
+        self.move_overhead = overhead.min(MAX_MOVE_OVERHEAD);
+    }
+
+    /// A function to turn a time for the move into a time limit for the search, by taking off the move overhead.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// movetime: Duration, // How long the GUI allows the move to take.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the time limit, at least 1 millisecond.
+    pub fn time_limit(&self, movetime: Duration) -> Duration {
+        movetime.saturating_sub(self.move_overhead).max(Duration::from_millis(1))
+    }
+
+    /// A function to decide how long to think when the GUI only says how much time is left on the engine's clock.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// remaining: Duration, // The time left on the engine's clock.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the time limit for the search: an even share of the remaining time over the moves the
+    /// engine still expects to play, minus the move overhead.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::engine::EngineSession;
+    /// # use std::time::Duration;
+    /// let session = EngineSession::new();
+    /// assert_eq!(session.time_for_move(Duration::from_secs(60)), Duration::from_millis(1990));
+    /// ```
+    pub fn time_for_move(&self, remaining: Duration) -> Duration {
+        self.time_limit(remaining / EXPECTED_MOVES_LEFT)
+    }
+
+    /// A function to search the current position with the session's transposition table (see docs for Game::search_with_table).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &SearchOptions, // The limits for this search, usually search_options with a depth or time limit set.
+    /// sink: &mut dyn InfoSink, // Where to report the progress (see docs for InfoSink).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the SearchResult (see docs for SearchResult).
+    pub fn think(&mut self, options: &SearchOptions, sink: &mut dyn InfoSink) -> SearchResult {
+        self.game.search_with_table(options, &mut self.table, sink)
+    }
+
+    /// A function to start a search of the current position on a worker thread, like think() (see docs for Search::spawn).
+    /// The transposition table goes with the search, so until finish_think() brings it back the session's table is empty.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: SearchOptions, // The limits for this search.
+    /// sink: impl InfoSink + Send + 'static, // Where to report the progress. Called from the worker thread.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the SearchHandle, to stop the search and to give to finish_think().
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::engine::EngineSession;
+    /// # use alviny_task_3::search::{SearchInfo, SearchOptions};
+    /// let mut session = EngineSession::new();
+    /// let handle = session.spawn_think(SearchOptions { depth: 2, ..SearchOptions::default() }, |_info: &SearchInfo| {});
+    /// let result = session.finish_think(handle);
+    /// assert!(result.best_move.is_some());
+    /// assert_eq!(session.table.size_mb(), session.search_options.hash_size);
+    /// ```
+    pub fn spawn_think(&mut self, options: SearchOptions, sink: impl InfoSink + Send + 'static) -> SearchHandle {
+        let table = std::mem::replace(&mut self.table, TranspositionTable::placeholder());
+        Search::spawn_with_table(self.game.clone(), options, table, sink)
+    }
+
+    /// A function to wait for a search started by spawn_think() and put its transposition table back in the session.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// handle: SearchHandle, // The handle spawn_think() returned. Call stop() on it first to cut the search short.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the SearchResult (see docs for SearchResult).
+    pub fn finish_think(&mut self, handle: SearchHandle) -> SearchResult {
+        let (result, table) = handle.join_with_table();
+        self.table = table;
+        result
+    }
+}
+impl Default for EngineSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,655 @@
+/*****************************
+*  STATIC EVALUATION         *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Static evaluation of chess positions. All scores are in centipawns (1/100 of a pawn) from White's perspective,
+so a positive score means White is better and a negative score means Black is better.
+*/
+
+use std::collections::HashMap;
+
+use crate::endgame::kpk::{self, KpkResult};
+use crate::endgame::EndgameKind;
+use crate::{get_available_moves_internal, get_game_status, get_piece, Board, Color, Game, Square};
+
+/// The score of a position where Black is checkmated. A position where White is checkmated scores -MATE_SCORE.
+pub const MATE_SCORE: i32 = 100_000;
+/// The bonus per extra pseudo-legal move compared to the opponent.
+pub const MOBILITY_WEIGHT: i32 = 2;
+/// The weight of king safety in percent: the difference of Board::king_safety() between White and Black is scaled by
+/// this and by the game phase, since a bare king matters less as the attacking pieces come off.
+pub const KING_SAFETY_WEIGHT: i32 = 50;
+/// The score of a won king and pawn against king ending (see docs for endgame::kpk), plus 20 for each rank the pawn
+/// has advanced. Less than a queen, so that promoting still scores better.
+pub const KPK_WIN_SCORE: i32 = 600;
+/// The bonus for being the side to move.
+pub const TEMPO_BONUS: i32 = 10;
+/// The game phase with all pieces on the board (see docs for Board::phase). Phase 0 is a pure endgame.
+pub const MAX_PHASE: i32 = 24;
+
+// The tables are bonuses in centipawns for a White piece on each square, laid out the way a board is printed:
+// the first row is rank 8 and the last row is rank 1. Black pieces use the same tables, mirrored vertically.
+
+#[rustfmt::skip]
+/// The piece-square table for pawns. Pawns are encouraged to take the center and to advance.
+pub const PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+/// The piece-square table for knights. A knight on the rim is dim.
+pub const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+/// The piece-square table for bishops, which prefer long diagonals and stay away from the corners.
+pub const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+/// The piece-square table for rooks, which like the seventh rank and the center files.
+pub const ROOK_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+/// The piece-square table for queens, a mild nudge towards the center.
+pub const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+/// The piece-square table for the king while there is plenty of material left: stay behind the pawns, preferably castled.
+pub const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+/// The piece-square table for the king in the endgame, where it is a fighting piece and belongs in the center.
+pub const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+fn table_index(square: Square, white: bool) -> usize {
+    let [row, column] = square.coords()[..] else { unreachable!() };
+    let row = if white { row } else { 7 - row };
+    (row * 8 + column) as usize
+} // Finds a square in the tables above. Black pieces look at the mirrored square, so one table serves both sides
+
+fn phase_weight(piece: char) -> i32 {
+    match piece.to_ascii_lowercase() {
+        'n' | 'b' => 1,
+        'r' => 2,
+        'q' => 4,
+        _ => 0,
+    }
+} // How much each piece counts towards the game phase. All pieces on the board add up to MAX_PHASE
+
+/// A function to return the material value of a piece in centipawns. Case-insensitive.
+///
+/// ## Arguments
+/// ```text
+/// piece: char, // A piece in FEN notation.
+///
+/// ```
+/// ## Returns
+/// This function returns the value of the piece (P=100, N=320, B=330, R=500, Q=900). Kings and empty squares are worth 0.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::evaluation::piece_value;
+/// assert_eq!(piece_value('N'), 320);
+/// assert_eq!(piece_value('q'), 900);
+/// ```
+pub fn piece_value(piece: char) -> i32 {
+    match piece.to_ascii_lowercase() {
+        'p' => 100,
+        'n' => 320,
+        'b' => 330,
+        'r' => 500,
+        'q' => 900,
+        _ => 0,
+    }
+}
+
+/// A function to look up the piece-square bonus of a piece. The king's bonus is blended between KING_MIDDLEGAME_TABLE
+/// and KING_ENDGAME_TABLE according to the phase.
+///
+/// ## Arguments
+/// ```text
+/// piece: char, // A piece in FEN notation. Uppercase is White and lowercase is Black.
+/// square: Square, // The square the piece stands on.
+/// phase: i32, // The game phase, from 0 (endgame) to MAX_PHASE (see docs for Board::phase).
+///
+/// ```
+/// ## Returns
+/// This function returns the bonus in centipawns from the perspective of the piece's own side, so a Black knight on e4
+/// scores the same as a White knight on e5. Empty squares return 0.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Square;
+/// # use alviny_task_3::evaluation::{piece_square_value, MAX_PHASE};
+/// let e4 = Square::from_algebraic("e4").unwrap();
+/// let a1 = Square::from_algebraic("a1").unwrap();
+/// assert!(piece_square_value('N', e4, MAX_PHASE) > piece_square_value('N', a1, MAX_PHASE));
+/// assert_eq!(piece_square_value('n', e4, MAX_PHASE), piece_square_value('N', Square::from_algebraic("e5").unwrap(), MAX_PHASE));
+/// ```
+pub fn piece_square_value(piece: char, square: Square, phase: i32) -> i32 {
+    let index = table_index(square, piece.is_ascii_uppercase());
+    match piece.to_ascii_lowercase() {
+        'p' => PAWN_TABLE[index],
+        'n' => KNIGHT_TABLE[index],
+        'b' => BISHOP_TABLE[index],
+        'r' => ROOK_TABLE[index],
+        'q' => QUEEN_TABLE[index],
+        'k' => {
+            let phase = phase.clamp(0, MAX_PHASE);
+            (KING_MIDDLEGAME_TABLE[index] * phase + KING_ENDGAME_TABLE[index] * (MAX_PHASE - phase)) / MAX_PHASE
+        }
+        _ => 0,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct to represent the material of one side. Kings are not counted.
+///
+/// ## Attributes
+/// ```text
+/// pawns: u32, // The number of pawns.
+/// knights: u32, // The number of knights.
+/// bishops: u32, // The number of bishops.
+/// rooks: u32, // The number of rooks.
+/// queens: u32, // The number of queens, including promoted ones.
+/// ```
+pub struct MaterialCount {
+    pub pawns: u32,
+    pub knights: u32,
+    pub bishops: u32,
+    pub rooks: u32,
+    pub queens: u32,
+}
+impl MaterialCount {
+    /// A function to return the total value of the material in pawns, using the traditional values (P=1, N=3, B=3, R=5, Q=9).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.material('w').value(), 39);
+    /// ```
+    pub fn value(&self) -> i32 {
+        (self.pawns + 3 * self.knights + 3 * self.bishops + 5 * self.rooks + 9 * self.queens) as i32
+    }
+}
+
+impl Board {
+    /// A function to count the material of one side.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a MaterialCount with the number of each piece type. Kings are not counted.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.material('b').pawns, 8);
+    /// ```
+    pub fn material(&self, color: char) -> MaterialCount {
+        let mut count = MaterialCount::default();
+        for piece in self.board_state.iter().flatten() {
+            if (color == 'w') != piece.is_ascii_uppercase() {
+                continue;
+            }
+            match piece.to_ascii_lowercase() {
+                'p' => count.pawns += 1,
+                'n' => count.knights += 1,
+                'b' => count.bishops += 1,
+                'r' => count.rooks += 1,
+                'q' => count.queens += 1,
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// A function to calculate the material balance of the board.
+    ///
+    /// ## Returns
+    /// This function returns White's material minus Black's material, in pawns (see docs for MaterialCount::value).
+    /// A positive value means White is ahead.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1".to_string()); // White is missing a rook
+    /// assert_eq!(example_game.board.material_balance(), -5);
+    /// ```
+    pub fn material_balance(&self) -> i32 {
+        self.material('w').value() - self.material('b').value()
+    }
+
+    /// A function to calculate the static exchange evaluation (SEE) of a capture: the material won or lost if both sides
+    /// keep recapturing on the target square with their least valuable attacker, each stopping as soon as that is better.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// source: Square, // The square of the capturing piece.
+    /// target: Square, // The square it captures on.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the material balance of the exchange in centipawns, from the perspective of the side making
+    /// the first capture. Pins, checks and promotions are ignored, so this is an estimate and not a search.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new_from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1".to_string());
+    /// let d1 = Square::from_algebraic("d1").unwrap();
+    /// let d5 = Square::from_algebraic("d5").unwrap();
+    /// assert_eq!(example_game.board.static_exchange(d1, d5), -800); // Qxd5 exd5 wins a pawn for the queen
+    /// ```
+    pub fn static_exchange(&self, source: Square, target: Square) -> i32 {
+        let exchange_value = |piece: char| if piece.eq_ignore_ascii_case(&'k') { MATE_SCORE } else { piece_value(piece) };
+        let target_coords = target.coords();
+        let mut board = self.clone();
+        let mut on_target = get_piece(self, &source.coords());
+        let first_victim = match get_piece(self, &target_coords) {
+            '*' if on_target.eq_ignore_ascii_case(&'p') && source.file() != target.file() => 100, // En passant
+            victim => exchange_value(victim),
+        };
+        let mut gains = vec![first_victim];
+        board.set_piece(&source.coords(), '*');
+        board.set_piece(&target_coords, on_target);
+        let mut side = if on_target.is_ascii_uppercase() { 'b' } else { 'w' };
+        while let Some((attacker, _piece)) = board.least_valuable_attacker(target, side) {
+            gains.push(exchange_value(on_target) - gains[gains.len() - 1]);
+            on_target = get_piece(&board, &attacker.coords());
+            board.set_piece(&attacker.coords(), '*');
+            board.set_piece(&target_coords, on_target);
+            side = if side == 'w' { 'b' } else { 'w' };
+        } // Play out the whole exchange. Removing each capturer from the board uncovers the sliders behind it
+        // Then go back through the exchange, letting each side stop recapturing when that is better
+        gains.into_iter().rev().reduce(|gain, previous| -(-previous).max(gain)).unwrap_or_default()
+    }
+
+    /// A function to calculate the game phase from the pieces left on the board. Knights and bishops count 1,
+    /// rooks 2 and queens 4; pawns and kings don't count.
+    ///
+    /// ## Returns
+    /// This function returns a value from 0 (only kings and pawns left) to MAX_PHASE (all pieces still on the board).
+    /// Extra pieces from promotions can't push the phase above MAX_PHASE.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::evaluation::MAX_PHASE;
+    /// assert_eq!(Game::new().board.phase(), MAX_PHASE);
+    /// let rook_endgame = Game::new_from_fen("4k3/pp3r2/8/8/8/8/PP3R2/4K3 w - - 0 1".to_string());
+    /// assert_eq!(rook_endgame.board.phase(), 4);
+    /// ```
+    pub fn phase(&self) -> i32 {
+        self.board_state.iter().flatten().map(|piece| phase_weight(*piece)).sum::<i32>().min(MAX_PHASE)
+    }
+
+    /// A function to statically evaluate the board.
+    ///
+    /// ## Returns
+    /// This function returns a score in centipawns from White's perspective, made up of material, piece-square tables
+    /// (see docs for piece_square_value), a small mobility term, king safety (see docs for KING_SAFETY_WEIGHT) and a bonus for the side to move.
+    /// King and pawn against king is looked up in a bitbase instead (see docs for KPK_WIN_SCORE), so a drawn one scores 0. Finished games return MATE_SCORE (Black is checkmated), -MATE_SCORE (White is checkmated)
+    /// or 0 (stalemate or draw by the 50 move rule).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()); // Black is missing the queen
+    /// assert!(example_game.board.evaluate() > 800);
+    /// ```
+    pub fn evaluate(&self) -> i32 {
+        let status = get_game_status(self);
+        match status.winner() {
+            Some(Color::White) => return MATE_SCORE,
+            Some(Color::Black) => return -MATE_SCORE,
+            None if status.is_game_over() => return 0,
+            None => {}
+        }
+        evaluate_ongoing(self)
+    }
+}
+
+/// A trait for evaluating positions, so that the search can use an evaluation other than the built-in one
+/// (see docs for SearchOptions).
+///
+/// The search scores checkmates, stalemates and draws by itself, so evaluate() is only asked about positions where the
+/// game goes on. Scores are in centipawns from White's perspective, like Board::evaluate(), and the search clamps them
+/// to stay clear of the mate scores.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::{Board, Game};
+/// # use alviny_task_3::evaluation::Evaluator;
+/// struct MaterialOnly;
+/// impl Evaluator for MaterialOnly {
+///     fn evaluate(&self, board: &Board) -> i32 {
+///         100 * board.material_balance()
+///     }
+/// }
+/// assert_eq!(MaterialOnly.evaluate(&Game::new().board), 0);
+/// ```
+pub trait Evaluator {
+    fn evaluate(&self, board: &Board) -> i32;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct for the built-in evaluation: material, piece-square tables, mobility and a bonus for the side to move
+/// (see docs for Board::evaluate).
+pub struct StandardEvaluator;
+impl Evaluator for StandardEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        evaluate_ongoing(board)
+    }
+}
+
+impl Game {
+    /// A function to count the legal moves of one side, whether or not it is that side's turn.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the number of legal moves. A promotion counts as one move, regardless of the number of pieces it can promote to.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.mobility('b'), 20); // Even though it is White's turn
+    /// ```
+    pub fn mobility(&self, color: char) -> usize {
+        count_moves(&self.board, color, false)
+    }
+
+    /// A function to count the pseudo-legal moves of one side. Cheaper than mobility(), since moves that leave the king in check aren't filtered out.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the number of pseudo-legal moves.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.mobility_pseudo('w'), 20);
+    /// ```
+    pub fn mobility_pseudo(&self, color: char) -> usize {
+        count_moves(&self.board, color, true)
+    }
+
+    /// A function to count the legal moves of each piece of one side.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a HashMap, where
+    /// key: the square of each piece of that color,
+    /// value: the number of legal moves the piece has. Pieces that can't move are included with 0.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let mobility = example_game.mobility_by_square('w');
+    /// assert_eq!(mobility[&Square::from_algebraic("g1").unwrap()], 2);
+    /// assert_eq!(mobility[&Square::from_algebraic("a1").unwrap()], 0);
+    /// ```
+    pub fn mobility_by_square(&self, color: char) -> HashMap<Square, usize> {
+        let mut mobility = self.board.pieces_of(color).map(|(square, _piece)| (square, 0)).collect::<HashMap<Square, usize>>();
+        for (coords, moves) in get_available_moves_internal(self.board.clone(), color, false) {
+            mobility.insert(Square::from_coords(&coords), moves.len());
+        }
+        mobility
+    }
+}
+
+fn evaluate_kpk(board: &Board) -> Option<i32> {
+    if board.board_state.iter().flatten().filter(|piece| **piece != '*').count() != 3 {
+        return None;
+    } // Cheap enough to do on every evaluation, unlike endgame_class()
+    let class = board.endgame_class().filter(|class| class.kind == EndgameKind::KPvK)?;
+    let strong = class.strong_side?;
+    let pawn_char = if strong == Color::White { 'P' } else { 'p' };
+    let relative = |square: Square| if strong == Color::White { square } else { Square::from_index(square.index() ^ 56).unwrap_or(square) };
+    let pawn = relative(Square::all().find(|&square| board.piece_on(square) == pawn_char)?);
+    let strong_king = relative(board.king_square(strong.to_char())?);
+    let weak_king = relative(board.king_square(strong.opposite().to_char())?);
+    let side_to_move = Color::from_char(board.active_player)?;
+    let side_to_move = if strong == Color::White { side_to_move } else { side_to_move.opposite() };
+    let score = match kpk::probe(strong_king, pawn, weak_king, side_to_move) {
+        KpkResult::Win => KPK_WIN_SCORE + 20 * (pawn.rank().index() as i32 - 1),
+        KpkResult::Draw => 0,
+    };
+    Some(if strong == Color::White { score } else { -score })
+} // The exact result of a king and pawn against king ending from the bitbase, or None for any other position.
+// A Black pawn is handled by flipping the board, so that it moves up like a White one
+
+fn evaluate_ongoing(board: &Board) -> i32 {
+    if let Some(score) = evaluate_kpk(board) {
+        return score;
+    }
+    let phase = board.phase();
+    let mut score = 0;
+    for (square, piece) in Square::all().map(|square| (square, get_piece(board, &square.coords()))) {
+        if piece.is_ascii_uppercase() {
+            score += piece_value(piece) + piece_square_value(piece, square, phase);
+        } else if piece.is_ascii_lowercase() {
+            score -= piece_value(piece) + piece_square_value(piece, square, phase);
+        }
+    } // Material and piece-square tables
+
+    score += MOBILITY_WEIGHT * (count_moves(board, 'w', true) as i32 - count_moves(board, 'b', true) as i32);
+    score += KING_SAFETY_WEIGHT * (board.king_safety('w') - board.king_safety('b')) * phase / (100 * MAX_PHASE);
+
+    if board.active_player == 'w' {
+        score += TEMPO_BONUS;
+    } else {
+        score -= TEMPO_BONUS;
+    }
+    score
+} // The built-in evaluation of a position where the game goes on.
+
+fn count_moves(board: &Board, color: char, force_no_check: bool) -> usize {
+    get_available_moves_internal(board.clone(), color, force_no_check)
+        .values()
+        .map(|moves| moves.len())
+        .sum()
+} // Counts the moves of a color. With force_no_check, moves that leave the king in check are counted too.
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, GameStatus};
+
+    #[test]
+    fn test_starting_position_is_equal() {
+        let test_position = Game::new();
+        println!("{}", test_position.evaluate());
+        assert!(test_position.evaluate().abs() < 50);
+    }
+    #[test]
+    fn test_queen_up() {
+        let white_up = Game::new_from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        let black_up = Game::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1".to_string());
+        println!("{} {}", white_up.evaluate(), black_up.evaluate());
+        assert!((850..=950).contains(&white_up.evaluate()));
+        assert!((-950..=-850).contains(&black_up.evaluate()));
+    }
+    #[test]
+    fn test_material_balance() {
+        let mut test_game = Game::new_from_fen("4k3/8/8/8/8/8/r7/R3K3 w - - 0 1".to_string());
+        assert_eq!(Game::new().material_balance(), 0);
+        assert_eq!(test_game.material_balance(), 0);
+        assert!(test_game.make_uci_move("a1a2")); // Rxa2
+        assert_eq!(test_game.material_balance(), 5);
+        assert_eq!(test_game.material('b'), MaterialCount::default());
+    }
+    #[test]
+    fn test_material_with_promoted_queens() {
+        let test_game = Game::new_from_fen("QQQ1k3/8/8/8/8/8/7p/4K3 w - - 0 1".to_string());
+        let white = test_game.material('w');
+        assert_eq!(white.queens, 3);
+        assert_eq!(white.pawns, 0);
+        assert_eq!(test_game.material_balance(), 26);
+    }
+    #[test]
+    fn test_mobility() {
+        let start = Game::new();
+        assert_eq!(start.mobility('w'), 20);
+        assert_eq!(start.mobility('b'), 20);
+        assert_eq!(start.mobility_pseudo('b'), 20);
+        assert_eq!(start.mobility_by_square('w').len(), 16);
+        let smothered = Game::new_from_fen("6rk/5Npp/8/8/8/8/8/6K1 b - - 0 1".to_string());
+        assert_eq!(smothered.mobility_by_square('b')[&Square::from_algebraic("h8").unwrap()], 0);
+        assert_eq!(smothered.mobility('b'), 0);
+        assert!(smothered.mobility_pseudo('b') > 0); // The rook and pawns can still move if check is ignored
+    }
+    #[test]
+    fn test_mate_sentinels() {
+        let white_mated = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        let black_mated = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string());
+        assert_eq!(white_mated.evaluate(), -MATE_SCORE);
+        assert_eq!(black_mated.evaluate(), MATE_SCORE);
+    }
+    #[test]
+    fn test_centralized_knight_scores_better() {
+        let e5 = Square::from_algebraic("e5").unwrap();
+        let a1 = Square::from_algebraic("a1").unwrap();
+        assert!(piece_square_value('N', e5, MAX_PHASE) > piece_square_value('N', a1, MAX_PHASE));
+        let center = Game::new_from_fen("4k3/8/8/4N3/8/8/8/4K3 w - - 0 1".to_string());
+        let corner = Game::new_from_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1".to_string());
+        assert!(center.evaluate() > corner.evaluate());
+    }
+    #[test]
+    fn test_king_tables() {
+        let g1 = Square::from_algebraic("g1").unwrap();
+        let e4 = Square::from_algebraic("e4").unwrap();
+        assert!(piece_square_value('K', g1, MAX_PHASE) > piece_square_value('K', e4, MAX_PHASE)); // Hide in the corner
+        assert!(piece_square_value('K', g1, 0) < piece_square_value('K', e4, 0)); // Come out to the center
+        let g8 = Square::from_algebraic("g8").unwrap();
+        assert_eq!(piece_square_value('k', g8, MAX_PHASE), piece_square_value('K', g1, MAX_PHASE)); // Mirrored for Black
+        assert_eq!(piece_square_value('K', g1, MAX_PHASE / 2), (KING_MIDDLEGAME_TABLE[62] + KING_ENDGAME_TABLE[62]) / 2);
+    }
+    #[test]
+    fn test_phase() {
+        assert_eq!(Game::new().board.phase(), MAX_PHASE);
+        assert_eq!(Game::new_from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1".to_string()).board.phase(), 0);
+        assert_eq!(Game::new_from_fen("QQQQk3/8/8/8/8/8/8/QQQQK3 w - - 0 1".to_string()).board.phase(), MAX_PHASE);
+    }
+    #[test]
+    fn test_static_exchange() {
+        let square = |name: &str| Square::from_algebraic(name).unwrap();
+        let defended = Game::new_from_fen("4k3/8/2p1p3/3p4/4P3/8/8/3QK3 w - - 0 1".to_string());
+        assert_eq!(defended.board.static_exchange(square("e4"), square("d5")), 0); // exd5 exd5, and the queen shouldn't recapture
+        assert_eq!(defended.board.static_exchange(square("d1"), square("d5")), -800);
+        let battery = Game::new_from_fen("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1".to_string());
+        assert_eq!(battery.board.static_exchange(square("d2"), square("d5")), -400); // Rxd5 Rxd5 Rxd5 Rxd5, with the rooks behind joining in
+        let free = Game::new_from_fen("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1".to_string());
+        assert_eq!(free.board.static_exchange(square("d1"), square("d5")), 320);
+    }
+    #[test]
+    fn test_stalemate_is_zero() {
+        let stalemate = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string());
+        assert_eq!(stalemate.game_status, GameStatus::Stalemate);
+        assert_eq!(stalemate.evaluate(), 0);
+    }
+    #[test]
+    fn test_kpk_uses_the_bitbase() {
+        let drawn = Game::new_from_fen("k7/8/1K6/P7/8/8/8/8 w - - 0 1".to_string()); // The wrong rook pawn
+        assert_eq!(drawn.evaluate(), 0);
+        let won = Game::new_from_fen("8/6k1/3K4/8/4P3/8/8/8 b - - 0 1".to_string()); // The king is on a key square
+        assert_eq!(won.evaluate(), KPK_WIN_SCORE + 40);
+        let black_wins = Game::new_from_fen("8/8/8/4p3/8/3k4/6K1/8 w - - 0 1".to_string()); // The same, flipped
+        assert_eq!(black_wins.evaluate(), -won.evaluate());
+        let opposition = Game::new_from_fen("8/8/4k3/8/4K3/4P3/8/8 w - - 0 1".to_string());
+        assert_eq!(opposition.evaluate(), 0);
+    }
+}
@@ -0,0 +1,493 @@
+/*****************************
+*  MOVE EXPLANATIONS         *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Explanations of why a move is illegal, for giving feedback that is more useful than "illegal move".
+
+The explanation comes from its own analysis of the move rather than from the move generator, so that it can
+point at the square that causes the problem, such as the piece blocking the path or the piece giving check.
+It is only used to explain: whether a move is legal is still decided by the rules of the game's Variant.
+*/
+
+use std::fmt;
+
+use crate::castling::CastlingMove;
+use crate::{translate_castling_gesture, Board, Game, Piece, PieceKind, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent the verdict on a move, and if it is illegal, why. Display gives a sentence for the player.
+///
+/// ```text
+/// Legal,
+/// GameOver, // The game has ended, so no move is legal.
+/// InvalidSquare(String), // The string isn't a square in algebraic notation.
+/// EmptySource { square }, // There is no piece on the source square.
+/// WrongColor { square }, // The piece on the source square belongs to the player who isn't to move.
+/// ImpossibleForPiece { piece }, // The piece can't move that way, even on an empty board.
+/// OwnPieceOnTarget { square }, // The target square holds a piece of the same color.
+/// Blocked { square, blocker, own }, // Another piece stands in the way. own is true if it has the mover's color.
+/// NothingToCapture { square }, // A pawn moves diagonally to a square with nothing to capture.
+/// Pinned { piece, attacker, attacker_kind }, // Moving the piece would expose the king to the attacker.
+/// DoesNotAddressCheck { attacker, attacker_kind }, // The player is in check, and stays in check from the attacker.
+/// KingWouldBeInCheck { attacker, attacker_kind }, // The king would move onto a square the attacker covers.
+/// CastlingRightsLost { king_moved }, // The king (if king_moved) or that rook has moved before.
+/// CastlingBlocked { square }, // A piece stands between the king and the rook.
+/// CastlingOutOfCheck { attacker, attacker_kind }, // The king is in check.
+/// CastlingThroughCheck { square, attacker, attacker_kind }, // The king would pass over an attacked square.
+/// CastlingIntoCheck { attacker, attacker_kind }, // The king would end up in check.
+/// Forbidden, // The move breaks a rule not covered above, such as a rule of the Variant.
+/// ```
+pub enum MoveDiagnosis {
+    Legal,
+    GameOver,
+    InvalidSquare(String),
+    EmptySource { square: Square },
+    WrongColor { square: Square },
+    ImpossibleForPiece { piece: PieceKind },
+    OwnPieceOnTarget { square: Square },
+    Blocked { square: Square, blocker: PieceKind, own: bool },
+    NothingToCapture { square: Square },
+    Pinned { piece: PieceKind, attacker: Square, attacker_kind: PieceKind },
+    DoesNotAddressCheck { attacker: Square, attacker_kind: PieceKind },
+    KingWouldBeInCheck { attacker: Square, attacker_kind: PieceKind },
+    CastlingRightsLost { king_moved: bool },
+    CastlingBlocked { square: Square },
+    CastlingOutOfCheck { attacker: Square, attacker_kind: PieceKind },
+    CastlingThroughCheck { square: Square, attacker: Square, attacker_kind: PieceKind },
+    CastlingIntoCheck { attacker: Square, attacker_kind: PieceKind },
+    Forbidden,
+}
+impl MoveDiagnosis {
+    /// A function to check whether the diagnosis is that the move is legal.
+    pub fn is_legal(&self) -> bool {
+        *self == MoveDiagnosis::Legal
+    }
+}
+impl fmt::Display for MoveDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveDiagnosis::Legal => write!(f, "the move is legal"),
+            MoveDiagnosis::GameOver => write!(f, "the game is over"),
+            MoveDiagnosis::InvalidSquare(square) => write!(f, "\"{}\" is not a square", square),
+            MoveDiagnosis::EmptySource { square } => write!(f, "there is no piece on {}", square),
+            MoveDiagnosis::WrongColor { square } => write!(f, "the piece on {} belongs to your opponent", square),
+            MoveDiagnosis::ImpossibleForPiece { piece } => write!(f, "a {} can't move like that", kind_name(*piece)),
+            MoveDiagnosis::OwnPieceOnTarget { square } => write!(f, "your own piece is standing on {}", square),
+            MoveDiagnosis::Blocked { square, blocker, own } => write!(
+                f,
+                "the path is blocked by {} {} on {}",
+                if *own { "your own" } else { "the enemy" },
+                kind_name(*blocker),
+                square
+            ),
+            MoveDiagnosis::NothingToCapture { square } => {
+                write!(f, "pawns only move diagonally to capture, and there is nothing to capture on {}", square)
+            }
+            MoveDiagnosis::Pinned { piece, attacker, attacker_kind } => {
+                write!(f, "that {} is pinned by the {} on {}", kind_name(*piece), kind_name(*attacker_kind), attacker)
+            }
+            MoveDiagnosis::DoesNotAddressCheck { attacker, attacker_kind } => write!(
+                f,
+                "you are in check from the {} on {}, and this move doesn't address it",
+                kind_name(*attacker_kind),
+                attacker
+            ),
+            MoveDiagnosis::KingWouldBeInCheck { attacker, attacker_kind } => {
+                write!(f, "the king would be in check from the {} on {}", kind_name(*attacker_kind), attacker)
+            }
+            MoveDiagnosis::CastlingRightsLost { king_moved: true } => {
+                write!(f, "castling is unavailable because the king has moved")
+            }
+            MoveDiagnosis::CastlingRightsLost { king_moved: false } => {
+                write!(f, "castling is unavailable because that rook has moved")
+            }
+            MoveDiagnosis::CastlingBlocked { square } => {
+                write!(f, "castling is blocked by the piece on {}", square)
+            }
+            MoveDiagnosis::CastlingOutOfCheck { attacker, attacker_kind } => {
+                write!(f, "you can't castle out of check from the {} on {}", kind_name(*attacker_kind), attacker)
+            }
+            MoveDiagnosis::CastlingThroughCheck { square, attacker, attacker_kind } => write!(
+                f,
+                "the king would pass through {}, which is attacked by the {} on {}",
+                square,
+                kind_name(*attacker_kind),
+                attacker
+            ),
+            MoveDiagnosis::CastlingIntoCheck { attacker, attacker_kind } => {
+                write!(f, "the king would castle into check from the {} on {}", kind_name(*attacker_kind), attacker)
+            }
+            MoveDiagnosis::Forbidden => write!(f, "the rules of the game don't allow this move"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why a move was rejected. Display gives a sentence for the player.
+///
+/// ```text
+/// Illegal(MoveDiagnosis), // The move is illegal, for the reason in the MoveDiagnosis (never MoveDiagnosis::Legal).
+/// InvalidPromotion(char), // The promotion piece isn't 'q', 'r', 'b' or 'n'.
+/// Unreadable, // The text isn't a move in the notation it was expected in.
+/// Ambiguous, // More than one legal move fits the text, e.g. "Nd2" when both knights can go there.
+/// ```
+pub enum MoveError {
+    Illegal(MoveDiagnosis),
+    InvalidPromotion(char),
+    Unreadable,
+    Ambiguous,
+}
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::Illegal(diagnosis) => write!(f, "{}", diagnosis),
+            MoveError::InvalidPromotion(piece) => write!(f, "a pawn can't promote to '{}'", piece),
+            MoveError::Unreadable => write!(f, "the move can't be read"),
+            MoveError::Ambiguous => write!(f, "more than one legal move fits"),
+        }
+    }
+}
+impl std::error::Error for MoveError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+enum Path {
+    Clear,
+    Blocked(Square),
+    Impossible,
+} // Whether a piece can get from one square to another, and if not, why
+
+fn kind_name(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::Pawn => "pawn",
+        PieceKind::Knight => "knight",
+        PieceKind::Bishop => "bishop",
+        PieceKind::Rook => "rook",
+        PieceKind::Queen => "queen",
+        PieceKind::King => "king",
+    }
+} // The name of a piece kind, for the explanations
+
+fn piece_on(board: &Board, square: Square) -> Option<Piece> {
+    Piece::from_fen_char(board.board_state[7 - square.rank().index() as usize][square.file().index() as usize])
+} // The piece on a square, or None if it is empty
+
+fn offset(from: Square, to: Square) -> (i8, i8) {
+    (
+        to.file().index() as i8 - from.file().index() as i8,
+        to.rank().index() as i8 - from.rank().index() as i8,
+    )
+} // The (file, rank) steps from one square to another
+
+fn line_path(board: &Board, from: Square, to: Square) -> Path {
+    let (file_step, rank_step) = offset(from, to);
+    let distance = file_step.abs().max(rank_step.abs());
+    for step in 1..distance {
+        let file = from.file().index() as i8 + file_step / distance * step;
+        let rank = from.rank().index() as i8 + rank_step / distance * step;
+        let square = Square::from_index((rank * 8 + file) as u8).unwrap();
+        if piece_on(board, square).is_some() {
+            return Path::Blocked(square);
+        }
+    }
+    Path::Clear
+} // Walks the straight line between two squares, which must be on the same rank, file or diagonal
+
+fn path(board: &Board, from: Square, to: Square, piece: Piece) -> Path {
+    let (file_step, rank_step) = offset(from, to);
+    let is_straight = file_step == 0 || rank_step == 0;
+    let is_diagonal = file_step.abs() == rank_step.abs();
+    if from == to {
+        return Path::Impossible;
+    }
+    match piece.kind {
+        PieceKind::Knight if (file_step.abs(), rank_step.abs()) == (1, 2) || (file_step.abs(), rank_step.abs()) == (2, 1) => {
+            Path::Clear
+        }
+        PieceKind::King if from.distance(to) == 1 => Path::Clear,
+        PieceKind::Bishop if is_diagonal => line_path(board, from, to),
+        PieceKind::Rook if is_straight => line_path(board, from, to),
+        PieceKind::Queen if is_straight || is_diagonal => line_path(board, from, to),
+        PieceKind::Pawn => {
+            let forward = if piece.color == 'w' { 1 } else { -1 };
+            let start_rank = if piece.color == 'w' { 1 } else { 6 };
+            match (file_step, rank_step) {
+                (-1 | 1, rank_step) if rank_step == forward => Path::Clear,
+                (0, rank_step) if rank_step == forward => match piece_on(board, to) {
+                    Some(_) => Path::Blocked(to),
+                    None => Path::Clear,
+                },
+                (0, rank_step) if rank_step == 2 * forward && from.rank().index() == start_rank => {
+                    match line_path(board, from, to) {
+                        Path::Clear if piece_on(board, to).is_some() => Path::Blocked(to),
+                        other => other,
+                    }
+                }
+                _ => Path::Impossible,
+            }
+        }
+        _ => Path::Impossible,
+    }
+} // Whether a piece could move from one square to another if only the pieces on the way mattered. Pawns can't capture straight ahead
+
+fn attacks(board: &Board, from: Square, to: Square, piece: Piece) -> bool {
+    match piece.kind {
+        PieceKind::Pawn => {
+            let (file_step, rank_step) = offset(from, to);
+            file_step.abs() == 1 && rank_step == if piece.color == 'w' { 1 } else { -1 }
+        }
+        _ => matches!(path(board, from, to, piece), Path::Clear),
+    }
+} // Whether a piece attacks a square, i.e. could capture on it
+
+fn attacker_of(board: &Board, square: Square, by_color: char) -> Option<(Square, PieceKind)> {
+    board
+        .pieces_of(by_color)
+        .find(|(from, piece)| attacks(board, *from, square, *piece))
+        .map(|(from, piece)| (from, piece.kind))
+} // The first piece of a side that attacks a square
+
+fn opponent(color: char) -> char {
+    if color == 'w' { 'b' } else { 'w' }
+} // The other side, as 'w' or 'b'
+
+fn castling_right(color: char, is_king_side: bool) -> char {
+    match (color, is_king_side) {
+        ('w', true) => 'K',
+        ('w', false) => 'Q',
+        (_, true) => 'k',
+        (_, false) => 'q',
+    }
+} // The castling right of a side towards the king side or the queen side
+
+fn explain_castling(board: &Board, from: Square, to: Square) -> MoveDiagnosis {
+    let color = board.active_player;
+    let (king_side_right, queen_side_right) = (castling_right(color, true), castling_right(color, false));
+    let right = castling_right(color, to.file().index() > from.file().index());
+    if !board.castling_rights.has(right) {
+        let king_moved = !board.castling_rights.has(king_side_right) && !board.castling_rights.has(queen_side_right);
+        return MoveDiagnosis::CastlingRightsLost { king_moved };
+    }
+    let Some(castling) = CastlingMove::for_right(board, right) else {
+        return MoveDiagnosis::Forbidden; // The right doesn't fit the pieces, which only happens in a broken position
+    };
+    for square in castling.squares_to_clear().iter().map(|coords| Square::from_coords(coords)) {
+        if piece_on(board, square).is_some() {
+            return MoveDiagnosis::CastlingBlocked { square };
+        }
+    }
+    if let Some((attacker, attacker_kind)) = attacker_of(board, from, opponent(color)) {
+        return MoveDiagnosis::CastlingOutOfCheck { attacker, attacker_kind };
+    }
+    for passed_square in castling.passed_squares().iter().map(|coords| Square::from_coords(coords)) {
+        if let Some((attacker, attacker_kind)) = attacker_of(board, passed_square, opponent(color)) {
+            return MoveDiagnosis::CastlingThroughCheck { square: passed_square, attacker, attacker_kind };
+        }
+    }
+    let mut next_board = board.clone();
+    next_board.play_move(castling.king.to_vec(), castling.encoded_target().to_vec(), None);
+    let king_target = Square::from_coords(&castling.king_target);
+    if let Some((attacker, attacker_kind)) = attacker_of(&next_board, king_target, opponent(color)) {
+        return MoveDiagnosis::CastlingIntoCheck { attacker, attacker_kind };
+    }
+    MoveDiagnosis::Forbidden
+} // Checks the castling preconditions in the order a player would: rights, empty squares, then the attacked squares.
+// The squares are those of the rook the right castles with, so Chess960 castling is explained too
+
+fn explain(board: &Board, from: Square, to: Square) -> MoveDiagnosis {
+    let color = board.active_player;
+    let Some(piece) = piece_on(board, from) else {
+        return MoveDiagnosis::EmptySource { square: from };
+    };
+    if piece.color != color {
+        return MoveDiagnosis::WrongColor { square: from };
+    }
+    let home_rank = if color == 'w' { 0 } else { 7 };
+    let (file_step, rank_step) = offset(from, to);
+    let onto_own_rook = piece_on(board, to).is_some_and(|target_piece| target_piece.color == color && target_piece.kind == PieceKind::Rook);
+    let lost_right = !board.castling_rights.has(castling_right(color, file_step > 0));
+    if piece.kind == PieceKind::King && rank_step == 0 && from.rank().index() == home_rank
+        && ((file_step.abs() == 2 && from.file().index() == 4)
+            || (onto_own_rook && lost_right)
+            || CastlingMove::from_move(board, &from.coords(), &to.coords()).is_some())
+    {
+        return explain_castling(board, from, to);
+    } // The king's two-square move from e1, or a castling gesture (see docs for Game::make_move), with or without the right
+    if let Some(target_piece) = piece_on(board, to) && target_piece.color == color {
+        return MoveDiagnosis::OwnPieceOnTarget { square: to };
+    }
+    match path(board, from, to, piece) {
+        Path::Impossible => return MoveDiagnosis::ImpossibleForPiece { piece: piece.kind },
+        Path::Blocked(square) => {
+            let blocker = piece_on(board, square).unwrap();
+            return MoveDiagnosis::Blocked { square, blocker: blocker.kind, own: blocker.color == color };
+        }
+        Path::Clear => {}
+    }
+    if piece.kind == PieceKind::Pawn && file_step != 0 && piece_on(board, to).is_none()
+        && board.en_passant_square != to.to_algebraic()
+    {
+        return MoveDiagnosis::NothingToCapture { square: to };
+    }
+
+    let mut next_board = board.clone();
+    next_board.play_move(from.coords(), to.coords(), None);
+    let Some(king_square) = next_board.king_square(color) else {
+        return MoveDiagnosis::Forbidden;
+    };
+    if let Some((attacker, attacker_kind)) = attacker_of(&next_board, king_square, opponent(color)) {
+        if piece.kind == PieceKind::King {
+            return MoveDiagnosis::KingWouldBeInCheck { attacker, attacker_kind };
+        }
+        let was_in_check = board.king_square(color).is_some_and(|king| attacker_of(board, king, opponent(color)).is_some());
+        if was_in_check {
+            return MoveDiagnosis::DoesNotAddressCheck { attacker, attacker_kind };
+        }
+        return MoveDiagnosis::Pinned { piece: piece.kind, attacker, attacker_kind };
+    }
+    MoveDiagnosis::Forbidden
+} // Looks for the first reason a move is illegal, assuming it is. Returns Forbidden if none of the standard reasons apply
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to explain why a move is illegal. The game is not changed.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// from: &str, // The square where the piece to move stands, in algebraic notation.
+    /// to: &str, // The square to which to move the piece, in algebraic notation.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a MoveDiagnosis (see docs for MoveDiagnosis), which is MoveDiagnosis::Legal if make_move()
+    /// would accept the move.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1".to_string());
+    /// assert_eq!(example_game.explain_move("e2", "d3").to_string(), "that bishop is pinned by the rook on e8");
+    /// assert!(example_game.explain_move("e1", "d1").is_legal());
+    /// ```
+    pub fn explain_move(&self, from: &str, to: &str) -> MoveDiagnosis {
+        let Some(from_square) = Square::from_algebraic(from) else {
+            return MoveDiagnosis::InvalidSquare(from.to_string());
+        };
+        let Some(to_square) = Square::from_algebraic(to) else {
+            return MoveDiagnosis::InvalidSquare(to.to_string());
+        };
+        let legal_moves = self.legal_moves();
+        let to = Square::from_coords(&translate_castling_gesture(&self.board, from_square.coords(), to_square.coords())).to_algebraic();
+        if legal_moves.iter().any(|legal_move| legal_move.source == from && legal_move.target == to) {
+            return MoveDiagnosis::Legal;
+        }
+        if legal_moves.is_empty() && self.game_status.is_game_over() {
+            return MoveDiagnosis::GameOver;
+        }
+        explain(&self.board, from_square, to_square)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_diagnoses() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let table = [
+            (start, "e2", "e4", MoveDiagnosis::Legal),
+            (start, "e9", "e4", MoveDiagnosis::InvalidSquare("e9".to_string())),
+            (start, "e4", "e5", MoveDiagnosis::EmptySource { square: square("e4") }),
+            (start, "e7", "e5", MoveDiagnosis::WrongColor { square: square("e7") }),
+            (start, "g1", "g3", MoveDiagnosis::ImpossibleForPiece { piece: PieceKind::Knight }),
+            (start, "e2", "e5", MoveDiagnosis::ImpossibleForPiece { piece: PieceKind::Pawn }),
+            (start, "d1", "d2", MoveDiagnosis::OwnPieceOnTarget { square: square("d2") }),
+            (start, "c1", "e3", MoveDiagnosis::Blocked { square: square("d2"), blocker: PieceKind::Pawn, own: true }),
+            (
+                "rnbqkbnr/pppp1ppp/8/8/4p3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "e2", "e4",
+                MoveDiagnosis::Blocked { square: square("e4"), blocker: PieceKind::Pawn, own: false },
+            ),
+            (start, "e2", "d3", MoveDiagnosis::NothingToCapture { square: square("d3") }),
+            (
+                "4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1",
+                "e2", "d3",
+                MoveDiagnosis::Pinned { piece: PieceKind::Bishop, attacker: square("e8"), attacker_kind: PieceKind::Rook },
+            ),
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/7q/5P2/PPPPP1PP/RNBQKBNR w KQkq - 1 3",
+                "a2", "a3",
+                MoveDiagnosis::DoesNotAddressCheck { attacker: square("h4"), attacker_kind: PieceKind::Queen },
+            ),
+            (
+                "4r1k1/8/8/8/8/8/8/3K4 w - - 0 1",
+                "d1", "e1",
+                MoveDiagnosis::KingWouldBeInCheck { attacker: square("e8"), attacker_kind: PieceKind::Rook },
+            ),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1", "e1", "g1", MoveDiagnosis::CastlingRightsLost { king_moved: true }),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1", "e1", "g1", MoveDiagnosis::CastlingRightsLost { king_moved: false }),
+            ("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1", "e1", "c1", MoveDiagnosis::CastlingBlocked { square: square("b1") }),
+            (
+                "r3k2r/4r3/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                "e1", "g1",
+                MoveDiagnosis::CastlingOutOfCheck { attacker: square("e7"), attacker_kind: PieceKind::Rook },
+            ),
+            (
+                "r3k2r/8/8/8/2b5/8/8/R3K2R w KQkq - 0 1",
+                "e1", "g1",
+                MoveDiagnosis::CastlingThroughCheck { square: square("f1"), attacker: square("c4"), attacker_kind: PieceKind::Bishop },
+            ),
+            (
+                "r3k2r/8/8/7b/8/8/8/R3K2R w KQkq - 0 1",
+                "e1", "c1",
+                MoveDiagnosis::CastlingThroughCheck { square: square("d1"), attacker: square("h5"), attacker_kind: PieceKind::Bishop },
+            ),
+            (
+                "r3k1r1/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                "e1", "g1",
+                MoveDiagnosis::CastlingIntoCheck { attacker: square("g8"), attacker_kind: PieceKind::Rook },
+            ),
+            (castling, "e1", "c1", MoveDiagnosis::Legal),
+            ("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", "a2", "a3", MoveDiagnosis::GameOver),
+        ];
+        for (fen, from, to, expected) in table {
+            let game = Game::new_from_fen(fen.to_string());
+            assert_eq!(game.explain_move(from, to), expected, "{} {}{}", fen, from, to);
+        }
+    }
+    #[test]
+    fn test_explanations_read_well() {
+        let game = Game::new();
+        assert_eq!(game.explain_move("c1", "e3").to_string(), "the path is blocked by your own pawn on d2");
+        let rights_lost = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1".to_string());
+        assert_eq!(rights_lost.explain_move("e1", "g1").to_string(), "castling is unavailable because the king has moved");
+        let mated = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert_eq!(mated.explain_move("e1", "f2").to_string(), "the game is over");
+    }
+}
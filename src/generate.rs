@@ -0,0 +1,265 @@
+/*****************************
+*  RANDOM POSITIONS          *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Random legal positions with a given material, for fuzzing and for mining puzzles. Only available with the `rand`
+feature.
+
+Positions are drawn by rejection sampling: the kings are put on two squares that aren't next to each other, the pawns
+on the second to seventh ranks, and the other pieces anywhere that is left, and the position is thrown away until it
+passes Board::is_legal_position() and Board::validate(). The constraints are checked up front, so material that no
+legal position has (e.g. nine pawns) fails at once instead of sampling forever. With reasonable material almost every
+draw is accepted. Positions never have castling rights or an en passant square.
+
+```
+# use alviny_task_3::generate::{random_position, PositionConstraints};
+# use rand::SeedableRng;
+let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+let constraints = PositionConstraints::from_material("KRPvKR").unwrap();
+let board = random_position(&mut rng, &constraints).unwrap();
+assert_eq!(board.material('w').rooks, 1);
+assert!(board.is_legal_position().is_ok());
+```
+*/
+
+use std::fmt;
+
+use rand::Rng;
+
+use crate::evaluation::MaterialCount;
+use crate::position::BoardMeta;
+use crate::{player_is_in_check, Board, Color, Piece, PieceKind};
+
+/// How many positions random_position() draws before giving up.
+pub const MAX_ATTEMPTS: u32 = 10_000;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why no position can be generated.
+///
+/// ```text
+/// InvalidMaterial(String), // The material string can't be read. Holds the string.
+/// ImpossibleMaterial(Color), // No legal position has this material for this side, e.g. nine pawns or three queens and eight pawns.
+/// NoPositionFound, // MAX_ATTEMPTS positions were drawn and none passed, e.g. because nine queens always give check.
+/// ```
+pub enum GenerateError {
+    InvalidMaterial(String),
+    ImpossibleMaterial(Color),
+    NoPositionFound,
+}
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::InvalidMaterial(material) => write!(f, "{} is not a material string such as KRPvKR", material),
+            GenerateError::ImpossibleMaterial(color) => write!(f, "no legal position has this material for {:?}", color),
+            GenerateError::NoPositionFound => write!(f, "no legal position found in {} attempts", MAX_ATTEMPTS),
+        }
+    }
+}
+impl std::error::Error for GenerateError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to represent what a random position must look like. Both sides always have a king.
+///
+/// ## Attributes
+/// ```text
+/// white: MaterialCount, // White's pieces apart from the king.
+/// black: MaterialCount, // Black's pieces apart from the king.
+/// side_to_move: Option<Color>, // The side to move, or None to pick one at random.
+/// allow_check: bool, // Whether the side to move may be in check. The side not to move never is.
+/// ```
+pub struct PositionConstraints {
+    pub white: MaterialCount,
+    pub black: MaterialCount,
+    pub side_to_move: Option<Color>,
+    pub allow_check: bool,
+}
+impl PositionConstraints {
+    /// A function to create constraints with the given material, either side to move, and checks allowed.
+    pub fn new(white: MaterialCount, black: MaterialCount) -> PositionConstraints {
+        PositionConstraints { white, black, side_to_move: None, allow_check: true }
+    }
+
+    /// A function to read the material from a string in the style of endgame names, White first (see docs for
+    /// EndgameKind).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// material: &str, // The pieces of White and Black, each starting with K and separated by 'v', e.g. "KRPvKR".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the PositionConstraints with either side to move and checks allowed, or
+    /// GenerateError::InvalidMaterial if the string can't be read.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::generate::PositionConstraints;
+    /// let constraints = PositionConstraints::from_material("KQvKPP").unwrap();
+    /// assert_eq!((constraints.white.queens, constraints.black.pawns), (1, 2));
+    /// assert!(PositionConstraints::from_material("KQ").is_err());
+    /// ```
+    pub fn from_material(material: &str) -> Result<PositionConstraints, GenerateError> {
+        let invalid = || GenerateError::InvalidMaterial(material.to_string());
+        let (white, black) = material.split_once('v').ok_or_else(invalid)?;
+        Ok(PositionConstraints::new(parse_side(white).ok_or_else(invalid)?, parse_side(black).ok_or_else(invalid)?))
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn parse_side(pieces: &str) -> Option<MaterialCount> {
+    let mut material = MaterialCount::default();
+    for letter in pieces.strip_prefix('K')?.chars() {
+        let count = match letter {
+            'P' => &mut material.pawns,
+            'N' => &mut material.knights,
+            'B' => &mut material.bishops,
+            'R' => &mut material.rooks,
+            'Q' => &mut material.queens,
+            _ => return None,
+        };
+        *count += 1;
+    }
+    Some(material)
+} // The material of one side of a string like "KRPvKR", without the king
+
+fn is_possible(material: &MaterialCount) -> bool {
+    let promoted = material.queens.saturating_sub(1) + material.rooks.saturating_sub(2) + material.bishops.saturating_sub(2) + material.knights.saturating_sub(2);
+    material.pawns <= 8 && promoted <= 8 - material.pawns
+} // The material rules of Board::is_legal_position()
+
+fn pieces_of(material: &MaterialCount, color: char) -> Vec<Piece> {
+    let counts = [(PieceKind::Pawn, material.pawns), (PieceKind::Knight, material.knights), (PieceKind::Bishop, material.bishops), (PieceKind::Rook, material.rooks), (PieceKind::Queen, material.queens)];
+    counts.into_iter().flat_map(|(kind, count)| std::iter::repeat_n(Piece { kind, color }, count as usize)).collect()
+} // The pieces of one side apart from the king, in a fixed order so that seeded runs repeat
+
+fn draw_position(rng: &mut impl Rng, constraints: &PositionConstraints) -> Board {
+    let mut array: [[Option<Piece>; 8]; 8] = [[None; 8]; 8];
+    let white_king = (rng.random_range(0..8), rng.random_range(0..8));
+    let black_king = loop {
+        let square: (usize, usize) = (rng.random_range(0..8), rng.random_range(0..8));
+        if square.0.abs_diff(white_king.0) > 1 || square.1.abs_diff(white_king.1) > 1 {
+            break square;
+        }
+    };
+    array[white_king.0][white_king.1] = Some(Piece { kind: PieceKind::King, color: 'w' });
+    array[black_king.0][black_king.1] = Some(Piece { kind: PieceKind::King, color: 'b' });
+
+    let mut pieces = pieces_of(&constraints.white, 'w');
+    pieces.extend(pieces_of(&constraints.black, 'b'));
+    for piece in pieces {
+        let rows = if piece.kind == PieceKind::Pawn { 1..7 } else { 0..8 };
+        let empty: Vec<(usize, usize)> = rows.flat_map(|row| (0..8).map(move |column| (row, column))).filter(|(row, column)| array[*row][*column].is_none()).collect();
+        let (row, column) = empty[rng.random_range(0..empty.len())];
+        array[row][column] = Some(piece);
+    }
+
+    let side_to_move = constraints.side_to_move.unwrap_or(if rng.random_bool(0.5) { Color::White } else { Color::Black });
+    Board::from_array(array, BoardMeta { side_to_move, ..BoardMeta::default() })
+} // One candidate position: right material, kings apart, pawns off the back ranks. Might still be illegal
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to generate a random legal position (see module docs).
+///
+/// ## Arguments
+/// ```text
+/// rng: &mut impl Rng, // The random number generator to draw from. Seed it to reproduce a position.
+/// constraints: &PositionConstraints, // The material, side to move and whether checks are allowed.
+///
+/// ```
+/// ## Returns
+/// This function returns a Board that passes Board::is_legal_position() and Board::validate(), or a GenerateError
+/// (see docs for GenerateError) if no such position has the material or none was found.
+pub fn random_position(rng: &mut impl Rng, constraints: &PositionConstraints) -> Result<Board, GenerateError> {
+    for (material, color) in [(&constraints.white, Color::White), (&constraints.black, Color::Black)] {
+        if !is_possible(material) {
+            return Err(GenerateError::ImpossibleMaterial(color));
+        }
+    }
+    for _ in 0..MAX_ATTEMPTS {
+        let board = draw_position(rng, constraints);
+        if board.is_legal_position().is_err() || board.validate().is_err() {
+            continue;
+        }
+        if !constraints.allow_check && player_is_in_check(&board, board.active_player) {
+            continue;
+        }
+        return Ok(board);
+    }
+    Err(GenerateError::NoPositionFound)
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_thousand_positions() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2024);
+        let random_material = |rng: &mut rand::rngs::StdRng| MaterialCount {
+            pawns: rng.random_range(0..=8),
+            knights: rng.random_range(0..=2),
+            bishops: rng.random_range(0..=2),
+            rooks: rng.random_range(0..=2),
+            queens: rng.random_range(0..=1),
+        };
+        let mut terminal = 0;
+        for index in 0..1000 {
+            let mut constraints = PositionConstraints::new(random_material(&mut rng), random_material(&mut rng));
+            constraints.allow_check = index % 2 == 0;
+            constraints.side_to_move = [None, Some(Color::White), Some(Color::Black)][index % 3];
+            let board = random_position(&mut rng, &constraints).unwrap();
+            assert_eq!(board.is_legal_position(), Ok(()));
+            assert_eq!(board.validate(), Ok(()));
+            assert_eq!((board.material('w'), board.material('b')), (constraints.white, constraints.black));
+            if let Some(color) = constraints.side_to_move {
+                assert_eq!(board.active_player, color.to_char());
+            }
+            if !constraints.allow_check {
+                assert!(!player_is_in_check(&board, board.active_player));
+            }
+            let game = Game::from_board(board);
+            if game.legal_moves().is_empty() {
+                assert!(game.is_game_over(), "{}", game.fen);
+                terminal += 1;
+            }
+        }
+        assert!(terminal < 100);
+    }
+    #[test]
+    fn test_impossible_constraints() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let nine_pawns = PositionConstraints::from_material("KPPPPPPPPPvK").unwrap();
+        assert_eq!(random_position(&mut rng, &nine_pawns), Err(GenerateError::ImpossibleMaterial(Color::White)));
+        let three_queens = PositionConstraints::from_material("KvKQQQPPPPPPP").unwrap();
+        assert_eq!(random_position(&mut rng, &three_queens), Err(GenerateError::ImpossibleMaterial(Color::Black)));
+        assert_eq!(PositionConstraints::from_material("KRvQ"), Err(GenerateError::InvalidMaterial("KRvQ".to_string())));
+
+        let mut bare_kings = PositionConstraints::from_material("KvK").unwrap();
+        bare_kings.allow_check = false;
+        let first = random_position(&mut rand::rngs::StdRng::seed_from_u64(5), &bare_kings).unwrap();
+        assert_eq!(first, random_position(&mut rand::rngs::StdRng::seed_from_u64(5), &bare_kings).unwrap()); // Seeded runs repeat
+    }
+}
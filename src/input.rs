@@ -0,0 +1,224 @@
+/*****************************
+*  MOVE INPUT                *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A lenient parser for moves typed by people, who rarely stick to one format.
+
+All of these are read as the same move: "e2e4", "e2-e4", "e2 e4", "E2E4", "e2xe4" and "e2e4+".
+Castling can be written "O-O", "0-0" or "o-o" (and "O-O-O" for the queen side), promotions as "e7e8q", "e7e8Q" or "e7e8=Q",
+and a trailing "+" or "#" is ignored.
+*/
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::castling::CastleSide;
+use crate::{Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent typed input that couldn't be read as a move. Display shows the input with the offending
+/// token underlined.
+///
+/// ## Attributes
+/// ```text
+/// input: String, // The input, as typed.
+/// span: Range<usize>, // The byte range of the offending token in input. It is empty if something is missing at the end.
+/// reason: String, // What is wrong with the token.
+/// ```
+pub struct ParseError {
+    pub input: String,
+    pub span: Range<usize>,
+    pub reason: String,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let padding = " ".repeat(self.input[..self.span.start].chars().count());
+        let underline = "^".repeat(self.input[self.span.clone()].chars().count().max(1));
+        write!(f, "{}\n{}\n{}{}", self.reason, self.input, padding, underline)
+    }
+}
+impl std::error::Error for ParseError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const SEPARATORS: [char; 4] = ['-', ' ', 'x', 'X'];
+
+fn error(input: &str, span: Range<usize>, reason: String) -> ParseError {
+    ParseError { input: input.to_string(), span, reason }
+}
+
+fn read_square(input: &str, start: usize, end: usize, name: &str) -> Result<(String, usize), ParseError> {
+    let token = input[start..end].chars().take(2).collect::<String>();
+    let token_end = start + token.len();
+    let chars = token.chars().map(|character| character.to_ascii_lowercase()).collect::<Vec<char>>();
+    match chars[..] {
+        [] => Err(error(input, start..start, format!("the {} square is missing", name))),
+        [file, rank] if ('a'..='h').contains(&file) && ('1'..='8').contains(&rank) => Ok((format!("{}{}", file, rank), token_end)),
+        _ => Err(error(input, start..token_end, format!("\"{}\" is not a square", token))),
+    }
+} // Reads a square such as "e2" or "E2" starting at byte start, and returns it in lowercase with the byte after it
+
+fn read_promotion(input: &str, start: usize, end: usize) -> Result<Option<char>, ParseError> {
+    let rest = &input[start..end];
+    let piece = rest.strip_prefix('=').unwrap_or(rest);
+    let mut chars = piece.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) if rest.is_empty() => Ok(None),
+        (Some(piece), None) if "qrbn".contains(piece.to_ascii_lowercase()) => Ok(Some(piece.to_ascii_lowercase())),
+        _ => Err(error(input, start..end, format!("\"{}\" is not a promotion piece (q, r, b or n)", rest))),
+    }
+} // Reads an optional promotion suffix such as "q", "Q" or "=Q"
+
+fn move_text(input: &str) -> Range<usize> {
+    let start = input.len() - input.trim_start().len();
+    let end = input.trim_end().trim_end_matches(['+', '#']).trim_end().len().max(start);
+    start..end
+} // Where the move is in the input, without surrounding whitespace or a check sign
+
+fn castling_side(notation: &str) -> Option<CastleSide> {
+    match notation.replace('0', "O").to_ascii_uppercase().as_str() {
+        "O-O" | "OO" => Some(CastleSide::Kingside),
+        "O-O-O" | "OOO" => Some(CastleSide::Queenside),
+        _ => None,
+    }
+} // Reads castling notation
+
+fn castling_move(notation: &str, active_player: char) -> Option<Move> {
+    let target_file = if castling_side(notation)? == CastleSide::Kingside { 'g' } else { 'c' };
+    let rank = if active_player == 'w' { '1' } else { '8' };
+    Some(Move { source: format!("e{}", rank), target: format!("{}{}", target_file, rank), promotion: None })
+} // Reads castling notation as the king's two-square move, which is how standard chess castles without a board to look at
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to read a move typed by a person, in any of the formats listed in the module docs.
+///
+/// ## Arguments
+/// ```text
+/// input: &str, // The typed move.
+/// active_player: char, // 'w' or 'b', the side that castles if the input is castling notation.
+///
+/// ```
+/// ## Returns
+/// This function returns the Move in long algebraic notation, or a ParseError (see docs for ParseError). Whether the move
+/// is legal isn't checked. Without a board, castling notation is read as the king's two-square move from the e-file;
+/// Game::make_move_str() castles with the rooks of the game instead, which also works in Chess960.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Move;
+/// # use alviny_task_3::input::parse_move_input;
+/// assert_eq!(parse_move_input("E2-E4+", 'w'), Ok(Move::from_uci("e2e4").unwrap()));
+/// assert_eq!(parse_move_input("0-0-0", 'b'), Ok(Move::from_uci("e8c8").unwrap()));
+/// assert_eq!(parse_move_input("e9e4", 'w').unwrap_err().to_string(), "\"e9\" is not a square\ne9e4\n^^");
+/// ```
+pub fn parse_move_input(input: &str, active_player: char) -> Result<Move, ParseError> {
+    let Range { start, end } = move_text(input);
+    if let Some(castling) = castling_move(&input[start..end], active_player) {
+        return Ok(castling);
+    }
+    let (source, after_source) = read_square(input, start, end, "source")?;
+    let target_start = after_source + input[after_source..end].len() - input[after_source..end].trim_start_matches(SEPARATORS).len();
+    let (target, after_target) = read_square(input, target_start, end, "target")?;
+    let promotion = read_promotion(input, after_target, end)?;
+    Ok(Move { source, target, promotion })
+}
+
+impl Game {
+    /// A function to make a move typed by a person, in any of the formats listed in the docs for the input module.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// input: &str, // The typed move, e.g. "e2-e4", "E2 E4", "O-O" or "e7e8=Q".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Ok(true) if the move was made, Ok(false) if it was read but is illegal, or a ParseError
+    /// (see docs for ParseError) if it couldn't be read.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.make_move_str("E2-E4"), Ok(true)); // 1. e4
+    /// assert_eq!(example_game.make_move_str("e2 e4"), Ok(false)); // There is no longer a pawn on e2
+    /// assert!(example_game.make_move_str("castle").is_err());
+    /// ```
+    pub fn make_move_str(&mut self, input: &str) -> Result<bool, ParseError> {
+        let parsed_move = parse_move_input(input, self.board.active_player)?;
+        let castling = castling_side(&input[move_text(input)]).and_then(|side| self.board.castling_move(side)); // Also in Chess960
+        Ok(self.make_uci_move(&castling.unwrap_or(parsed_move).to_uci()))
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(fen: &str, input: &str) -> Game {
+        let mut game = Game::new_from_fen(fen.to_string());
+        assert_eq!(game.make_move_str(input), Ok(true), "{}", input);
+        game
+    }
+
+    #[test]
+    fn test_variants_of_the_same_move() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let expected = after(start, "e2e4");
+        let variants = [
+            "e2-e4", "e2 e4", "E2E4", "E2 E4", "E2-e4", "e2xe4", "e2e4+", "e2e4#", "e2-e4+", "  e2e4  ", "e2  -  e4", "e2e4 +",
+        ];
+        for variant in variants {
+            assert_eq!(after(start, variant), expected, "{}", variant);
+        }
+
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let expected = after(castling, "e1g1");
+        for variant in ["O-O", "0-0", "o-o", "O-O+", "OO", "e1-g1"] {
+            assert_eq!(after(castling, variant), expected, "{}", variant);
+        }
+        assert_eq!(after(castling, "0-0-0"), after(castling, "e1c1"));
+
+        let promotion = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        let expected = after(promotion, "a7a8n");
+        for variant in ["a7a8N", "a7a8=n", "a7a8=N", "A7-A8=N", "a7a8=N+"] {
+            assert_eq!(after(promotion, variant), expected, "{}", variant);
+        }
+    }
+    #[test]
+    fn test_errors_point_at_the_token() {
+        let cases = [
+            ("e9e4", 0..2, "\"e9\" is not a square"),
+            ("e2-z4", 3..5, "\"z4\" is not a square"),
+            ("e2", 2..2, "the target square is missing"),
+            ("", 0..0, "the source square is missing"),
+            ("e7e8=K", 4..6, "\"=K\" is not a promotion piece (q, r, b or n)"),
+            ("Nf3", 0..2, "\"Nf\" is not a square"),
+        ];
+        for (input, span, reason) in cases {
+            let parse_error = parse_move_input(input, 'w').unwrap_err();
+            assert_eq!((parse_error.span, parse_error.reason.as_str()), (span, reason), "{}", input);
+        }
+        assert_eq!(parse_move_input("e2-z4", 'w').unwrap_err().to_string(), "\"z4\" is not a square\ne2-z4\n   ^^");
+    }
+}
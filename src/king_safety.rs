@@ -0,0 +1,223 @@
+/*****************************
+*  KING SAFETY               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+How well a king is sheltered, for the evaluation and for UIs that want to show why a king is in danger.
+
+Three things are looked at, on the king's own file and the files on either side of it:
+```text
+pawn shield, // Pawns of the king's color at most two ranks in front of it, e.g. f2, g3 and h2 for a fianchettoed king on g1.
+open files, // Files without pawns, along which enemy rooks and queens can reach the king.
+semi-open files, // Files with only enemy pawns, which are nearly as bad.
+```
+and the enemy pieces that attack the squares around the king, weighted by how dangerous they are there.
+
+```
+# use alviny_task_3::Game;
+let castled = Game::new_from_fen("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1".to_string());
+let exposed = Game::new_from_fen("4k3/8/8/8/8/8/8/PPP3K1 w - - 0 1".to_string());
+assert!(castled.board.king_safety('w') > exposed.board.king_safety('w'));
+```
+*/
+
+use crate::{Board, Piece, PieceKind, Square};
+
+/// The bonus for each pawn in the shield in front of the king.
+pub const SHIELD_PAWN_BONUS: i32 = 15;
+/// The penalty for each file near the king without pawns.
+pub const OPEN_FILE_PENALTY: i32 = 40;
+/// The penalty for each file near the king with only enemy pawns.
+pub const SEMI_OPEN_FILE_PENALTY: i32 = 25;
+/// The penalty per unit of attack weight (see docs for attack_weight).
+pub const ATTACK_PENALTY: i32 = 10;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A struct to represent what makes a king safe or unsafe (see docs for Board::king_safety_report). The Default is
+/// an empty report, for a side without a king.
+///
+/// ## Attributes
+/// ```text
+/// king: Option<Square>, // The square of the king, or None if the side has no king.
+/// shield_pawns: Vec<Square>, // The pawns of the king's color on the three files around it, at most two ranks in front of it, in FEN order.
+/// open_files: u8, // The number of files around the king without pawns.
+/// semi_open_files: u8, // The number of files around the king with enemy pawns but none of the king's color.
+/// attackers: Vec<Square>, // The enemy pieces that attack the king or a square next to it, each counted once.
+/// attack_weight: i32, // The sum of attack_weight() of the attackers.
+/// ```
+pub struct KingSafety {
+    pub king: Option<Square>,
+    pub shield_pawns: Vec<Square>,
+    pub open_files: u8,
+    pub semi_open_files: u8,
+    pub attackers: Vec<Square>,
+    pub attack_weight: i32,
+}
+impl KingSafety {
+    /// A function to combine the components into one score, in centipawns. Higher is safer.
+    ///
+    /// ## Returns
+    /// This function returns SHIELD_PAWN_BONUS for every shield pawn, minus OPEN_FILE_PENALTY and SEMI_OPEN_FILE_PENALTY
+    /// for every open and semi-open file, minus ATTACK_PENALTY times the attack weight.
+    pub fn score(&self) -> i32 {
+        SHIELD_PAWN_BONUS * self.shield_pawns.len() as i32
+            - OPEN_FILE_PENALTY * self.open_files as i32
+            - SEMI_OPEN_FILE_PENALTY * self.semi_open_files as i32
+            - ATTACK_PENALTY * self.attack_weight
+    }
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to return how dangerous a piece is when it attacks the squares around the enemy king.
+///
+/// ## Arguments
+/// ```text
+/// piece: Piece, // The attacking piece.
+///
+/// ```
+/// ## Returns
+/// This function returns 1 for a pawn, 2 for a knight or bishop, 3 for a rook, 5 for a queen and 0 for a king, which
+/// can't give check.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Piece;
+/// # use alviny_task_3::king_safety::attack_weight;
+/// assert_eq!(attack_weight(Piece::from_fen_char('q').unwrap()), 5);
+/// ```
+pub fn attack_weight(piece: Piece) -> i32 {
+    match piece.kind {
+        PieceKind::Pawn => 1,
+        PieceKind::Knight | PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 5,
+        PieceKind::King => 0,
+    }
+}
+
+impl Board {
+    /// A function to list what makes a king safe or unsafe (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // The side whose king is looked at, 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a KingSafety with the shield pawns, open and semi-open files and attackers of the king,
+    /// or KingSafety::default() if the side has no king.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let report = example_game.board.king_safety_report('w');
+    /// assert_eq!(report.shield_pawns.len(), 3); // d2, e2 and f2
+    /// assert!(report.attackers.is_empty());
+    /// ```
+    pub fn king_safety_report(&self, color: char) -> KingSafety {
+        let Some(king) = self.king_square(color) else {
+            return KingSafety::default();
+        };
+        let enemy = if color == 'w' { 'b' } else { 'w' };
+        let forward: i8 = if color == 'w' { 1 } else { -1 };
+        let (king_file, king_rank) = (king.file().index() as i8, king.rank().index() as i8);
+        let mut report = KingSafety { king: Some(king), ..KingSafety::default() };
+        for (square, piece) in self.pieces() {
+            let (file, rank) = (square.file().index() as i8, square.rank().index() as i8);
+            if piece.kind == PieceKind::Pawn && piece.color == color && file.abs_diff(king_file) <= 1 && (1..=2).contains(&((rank - king_rank) * forward)) {
+                report.shield_pawns.push(square);
+            }
+        }
+        for file in (king_file - 1).max(0)..=(king_file + 1).min(7) {
+            let pawns_on_file = self.pieces().filter(|(square, piece)| piece.kind == PieceKind::Pawn && square.file().index() as i8 == file).collect::<Vec<_>>();
+            if pawns_on_file.is_empty() {
+                report.open_files += 1;
+            } else if pawns_on_file.iter().all(|(_square, piece)| piece.color != color) {
+                report.semi_open_files += 1;
+            }
+        }
+        for square in Square::all().filter(|square| square.distance(king) <= 1) {
+            for attacker in self.attackers_of(square, enemy) {
+                if !report.attackers.contains(&attacker) {
+                    report.attackers.push(attacker);
+                }
+            }
+        }
+        report.attack_weight = report.attackers.iter().filter_map(|&attacker| Piece::from_fen_char(self.piece_on(attacker))).map(attack_weight).sum();
+        report
+    }
+
+    /// A function to score how safe a king is (see docs for KingSafety::score). Alias to
+    /// ```text
+    /// your_board.king_safety_report(color).score()
+    /// ```
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // The side whose king is looked at, 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a score in centipawns from the perspective of `color`, where higher is safer, or 0 if the
+    /// side has no king.
+    pub fn king_safety(&self, color: char) -> i32 {
+        self.king_safety_report(color).score()
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::Game;
+
+    #[test]
+    fn test_fianchetto_against_exposed_king() {
+        let fianchetto = Game::new_from_fen("r1bq1rk1/ppp1ppbp/2np1np1/8/8/2NP1NP1/PPP1PPBP/R1BQ1RK1 w - - 0 8".to_string());
+        let report = fianchetto.board.king_safety_report('w');
+        let shield = report.shield_pawns.iter().map(|square| square.to_algebraic()).collect::<Vec<String>>();
+        assert_eq!(shield, ["g3", "f2", "h2"]); // In FEN order
+        assert_eq!((report.open_files, report.semi_open_files), (0, 0));
+        assert!(report.attackers.is_empty());
+        assert_eq!(report.score(), 45);
+
+        let exposed = Game::new_from_fen("r1b1k2r/ppp2ppp/8/8/8/7q/8/6K1 w kq - 0 1".to_string());
+        let report = exposed.board.king_safety_report('w');
+        assert!(report.shield_pawns.is_empty());
+        assert_eq!((report.open_files, report.semi_open_files), (0, 3)); // Black's f, g and h pawns point at the king
+        assert_eq!(report.attackers.iter().map(|square| square.to_algebraic()).collect::<Vec<String>>(), ["h3"]);
+        assert_eq!(report.attack_weight, 5);
+        assert!(exposed.board.king_safety('w') < fianchetto.board.king_safety('w') - 100);
+
+        let bare = Game::new_from_fen("4k3/8/8/8/8/8/8/6K1 w - - 0 1".to_string()).board.king_safety_report('w');
+        assert_eq!((bare.open_files, bare.semi_open_files), (3, 0));
+    }
+    #[test]
+    fn test_colors_and_edges() {
+        let game = Game::new_from_fen("1k6/ppp5/8/8/8/8/8/K7 w - - 0 1".to_string()); // b8 king with a full shield, a1 king in the corner
+        let black = game.board.king_safety_report('b');
+        assert_eq!(black.shield_pawns.len(), 3);
+        assert_eq!(game.board.king_safety_report('w').semi_open_files, 2); // Only the a- and b-files are next to a1
+        assert_eq!(game.board.king_safety_report('w').attackers.len(), 0);
+        assert_eq!(game.board.flip_colors().king_safety('w'), game.board.king_safety('b'));
+
+        let no_king = Game::new_from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1".to_string()); // Broken, but mustn't panic
+        assert_eq!(no_king.board.king_safety('w'), 0);
+    }
+}
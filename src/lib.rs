@@ -10,12 +10,97 @@ All board locations in this library uses algebraic notation. Read more here:
 This library (optionally) uses FEN notation. Read more here:
 <https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation>
 
-This library castles by moving the king 2 squares to either direction.
+This library castles by moving the king 2 squares to either direction, or in Chess960, by moving the king onto its own
+rook (see the castling module).
+
+With the `tracing` feature, the library emits debug-level events through the `tracing` crate when a move is made or rejected,
+and when the game status changes. The library never prints anything itself.
 */
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self};
-use std::cmp::{min, max};
+use std::str::FromStr;
+use std::cmp::min;
+
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::debug!($($arg)*);
+        }
+    };
+} // Emits a tracing event with the `tracing` feature, and does nothing without it
+
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod attacks;
+pub mod binary;
+pub mod castling;
+pub mod checks;
+pub mod chess960;
+pub mod client_state;
+pub mod clock;
+pub mod dead_position;
+pub mod describe;
+pub mod descriptive;
+pub mod diff;
+pub mod draws;
+#[cfg(feature = "eco")]
+pub mod eco;
+pub mod endgame;
+pub mod engine;
+pub mod evaluation;
+pub mod explain;
+#[cfg(feature = "rand")]
+pub mod generate;
+pub mod input;
+pub mod king_safety;
+pub mod matchrunner;
+pub mod mate;
+pub mod observer;
+pub mod opening_tree;
+pub mod pawns;
+pub mod pgn;
+pub mod piece;
+#[cfg(feature = "book")]
+pub mod polyglot;
+pub mod position;
+pub mod puzzle;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod render;
+pub mod replay;
+pub mod rules;
+pub mod san;
+pub mod save;
+pub mod search;
+pub mod session;
+pub mod square;
+pub mod study;
+pub mod successor;
+#[cfg(feature = "tablebase")]
+pub mod tablebase;
+pub mod tactics;
+pub mod transform;
+pub mod uci;
+#[cfg(feature = "uci-client")]
+pub mod uci_client;
+pub mod undo;
+pub mod variant;
+pub mod xboard;
+pub mod zobrist;
+
+use clock::Clock;
+use observer::{MoveEventKind, Observer};
+use evaluation::MaterialCount;
+use pgn::MoveAnnotation;
+pub use castling::CastlingRights;
+use castling::{CastledStatus, CastlingMove};
+pub use piece::{Color, Piece, PieceKind};
+pub use square::Square;
+pub use variant::Variant;
 
 /*****************************
 *   PRIVATE HELPER FUNCTIONS *
@@ -24,66 +109,145 @@ use std::cmp::{min, max};
 /// A function to parse a FEN string into a Board struct. Inverse function for generate_fen().
 ///
 /// ## Arguments
-/// ```
+/// ```text
 /// fen: &str // A valid FEN string in standard format
 /// 
 /// ```
 /// ## Returns
-/// This function returns a Board struct (see docs for Board).
+/// This function returns a Board struct (see docs for Board). It never panics: parts of the FEN that are missing or malformed
+/// are replaced by empty squares and default values (White to move, no castling or en passant, counters 0 and 1).
+/// Use try_parse_fen() to reject malformed FENs instead.
 /// 
 /// ## Example
 ///
 /// ```
-/// let example_board = parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
-/// example_board = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+/// # use alviny_task_3::parse_fen;
+/// let mut example_board = parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+/// example_board = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
 /// ```
 pub fn parse_fen(fen: &str) -> Board {
-    let fen_vec = fen.split(' ').collect::<Vec<&str>>();
+    let fen_vec = fen.split_whitespace().collect::<Vec<&str>>();
+    let field = |index: usize| fen_vec.get(index).copied();
     //Split the FEN into its constituent parts
 
-    let board_state_vec = fen_vec[0].split('/').collect::<Vec<&str>>();
-    let mut row = vec![];
     let mut board_state = vec![];
-    for single_row in board_state_vec {
+    for single_row in field(0).unwrap_or("").split('/').take(8) {
+        let mut row = vec![];
         for character in single_row.chars() {
-            //assuming valid FEN (only characters and numbers)
-            const RADIX: u32 = 10;
-            if character.is_numeric() {
-                for _i in 0..character
-                    .to_digit(RADIX)
-                    .expect("Could not convert char to int")
-                {
-                    row.push('*');
-                }
-            } else {
-                row.push(character);
+            match character.to_digit(10) {
+                Some(empty_squares) => row.extend(std::iter::repeat_n('*', empty_squares as usize)),
+                None if "pnbrqkPNBRQK".contains(character) => row.push(character),
+                None => row.push('*'), // not a piece
             }
         }
-        board_state.push(row.clone());
-        row.retain(|_x| false); // empty the vector
+        row.resize(8, '*');
+        board_state.push(row);
     }
-    //Parse the board state part of the FEN into a 2d nested Vec
+    board_state.resize(8, vec!['*'; 8]);
+    //Parse the board state part of the FEN into a 8x8 nested Vec, padding or cutting off anything that doesn't fit
 
+    let mut castling_rights = CastlingRights::default();
+    for letter in field(2).unwrap_or("").chars() {
+        if let Some((right, file)) = castling::rook_for_letter(letter, &board_state) && !castling_rights.has(right) {
+            castling_rights.set_with_rook(right, file);
+        }
+    }
     Board {
         board_state,
-        active_player: fen_vec[1].chars().next().expect("string is empty"),
-        castling_availability: fen_vec[2].to_string(),
-        en_passant_square: fen_vec[3].to_string(),
-        halfmove_counter: fen_vec[4]
-            .parse::<i32>()
-            .expect("I'm afraid it is not possible to convert this value to i32, my good sir."),
-        turn_counter: fen_vec[5]
-            .parse::<u64>()
-            .expect("I'm afraid it is not possible to convert this value to u64, my good sir."),
+        active_player: if field(1) == Some("b") { 'b' } else { 'w' },
+        castling_rights,
+        en_passant_square: field(3).filter(|square| en_passant_rank_fits(square)).unwrap_or("-").to_string(),
+        halfmove_counter: field(4).and_then(|counter| counter.parse().ok()).unwrap_or(0),
+        turn_counter: field(5).and_then(|counter| counter.parse().ok()).unwrap_or(1),
         promotion_selection: 'q',
     }
     //Then feed the rest directly into the cosntructor
 } // Creates a Board struct from any given FEN. Inverse function to generate_fen()
 
-/// A function to parse a Board struct into an FEN. Inverse function for parse_fen().
+/// A function to parse a FEN string into a Board struct, rejecting malformed FENs. Unlike parse_fen(), nothing is guessed.
 ///
 /// ## Arguments
+/// ```text
+/// fen: &str // A FEN string in standard format
+/// 
+/// ```
+/// ## Returns
+/// This function returns the Board, or a FenError (see docs for FenError) for the first part of the FEN that is malformed.
+/// Only the syntax is checked: see Board::validate() for whether the position can be played.
+/// 
+/// ## Example
+///
 /// ```
+/// # use alviny_task_3::{try_parse_fen, FenError};
+/// assert!(try_parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_ok());
+/// assert_eq!(try_parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0"), Err(FenError::WrongFieldCount(5)));
+/// assert_eq!(try_parse_fen("rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), Err(FenError::InvalidRank("ppppXppp".to_string())));
+/// ```
+pub fn try_parse_fen(fen: &str) -> Result<Board, FenError> {
+    let fen_vec = fen.split_whitespace().collect::<Vec<&str>>();
+    if fen_vec.len() != 6 {
+        return Err(FenError::WrongFieldCount(fen_vec.len()));
+    }
+    let ranks = fen_vec[0].split('/').collect::<Vec<&str>>();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+    for rank in &ranks {
+        let mut squares = 0;
+        for character in rank.chars() {
+            match character.to_digit(10) {
+                Some(empty_squares @ 1..=8) => squares += empty_squares,
+                None if "pnbrqkPNBRQK".contains(character) => squares += 1,
+                _ => return Err(FenError::InvalidRank(rank.to_string())),
+            }
+        }
+        if squares != 8 {
+            return Err(FenError::InvalidRank(rank.to_string()));
+        }
+    }
+    if fen_vec[1] != "w" && fen_vec[1] != "b" {
+        return Err(FenError::InvalidSideToMove(fen_vec[1].to_string()));
+    }
+    let board = parse_fen(fen);
+    if CastlingRights::from_fen_on_board(fen_vec[2], &board.board_state).is_none() {
+        return Err(FenError::InvalidCastling(fen_vec[2].to_string()));
+    }
+    if fen_vec[3] != "-" && !en_passant_rank_fits(fen_vec[3]) {
+        return Err(FenError::InvalidEnPassant(fen_vec[3].to_string()));
+    }
+    if fen_vec[4].parse::<u32>().is_err() {
+        return Err(FenError::InvalidCounter(fen_vec[4].to_string()));
+    }
+    if fen_vec[5].parse::<u64>().is_err() {
+        return Err(FenError::InvalidCounter(fen_vec[5].to_string()));
+    }
+    Ok(board)
+}
+
+fn en_passant_rank_fits(square: &str) -> bool {
+    Square::from_algebraic(square).is_some_and(|square| matches!(square.rank().to_char(), '3' | '6'))
+} // En passant squares are always on the third or sixth rank, behind the pawn that made the double push
+
+fn looks_like_movetext(token: &str) -> bool {
+    let move_number = token.trim_start_matches(|character: char| character.is_ascii_digit());
+    if move_number.len() < token.len() && move_number.starts_with('.') {
+        return true;
+    } // "1." or "12...", or glued to the move like "1.e4"
+    let san = token.trim_end_matches(['+', '#', '!', '?']);
+    let san = san.split_once('=').map_or(san, |(before_promotion, _piece)| before_promotion);
+    if matches!(san, "O-O" | "O-O-O") {
+        return true;
+    }
+    let square = san.get(san.len().saturating_sub(2)..).unwrap_or("");
+    Square::from_algebraic(square).is_some()
+        && san[..san.len() - 2].chars().all(|character| "KQRBNabcdefgh12345678x".contains(character))
+        && san.len() <= 6
+} // Whether the first token of an input is a move number or a SAN move, so the input is probably PGN movetext
+
+/// A function to parse a Board struct into an FEN. Inverse function for parse_fen().
+///
+/// ## Arguments
+/// ```text
 /// board: &Board // A valid FEN string in standard format
 /// 
 /// ```
@@ -93,14 +257,40 @@ pub fn parse_fen(fen: &str) -> Board {
 /// ## Example
 ///
 /// ```
-/// let example_board = parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
-/// let example_fen = generate_fen(example_board)
+/// # use alviny_task_3::{parse_fen, generate_fen};
+/// let example_board = parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+/// let example_fen = generate_fen(&example_board);
 /// ```
 pub fn generate_fen(board: &Board) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        board.board_fen(),
+        board.active_player,
+        board.castling_rights.to_fen_for(board),
+        board.en_passant_square,
+        board.halfmove_counter,
+        board.turn_counter
+    )
+} // Creates a FEN from any given Board struct. Inverse function for parse_fen().
+
+impl Board {
+    /// A function to return only the piece placement of the board, the first field of its FEN.
+    ///
+    /// ## Returns
+    /// This function returns the ranks from 8 to 1 separated by '/', e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR".
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.board_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+    /// ```
+    pub fn board_fen(&self) -> String {
     let mut fen = String::new();
     let mut fen_row = String::new();
     let mut empty_squares: i32 = 0;
-    for row in board.board_state.clone() {
+    for row in self.board_state.clone() {
         for char in row {
             if char == '*' {
                 empty_squares += 1;
@@ -122,22 +312,81 @@ pub fn generate_fen(board: &Board) -> String {
         }
         fen_row = "".to_string();
     }
-    format!(
-        "{} {} {} {} {} {}",
-        fen,
-        board.active_player,
-        board.castling_availability,
-        board.en_passant_square,
-        board.halfmove_counter,
-        board.turn_counter
-    )
-} // Creates a FEN from any given Board struct. Inverse function for parse_fen().
+        fen
+    }
+
+    /// A function to create a Board from only the piece placement of a FEN, for tools that exchange nothing else.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board_fen: &str, // The first field of a FEN, e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR".
+    /// side_to_move: Color, // The player to move, which the piece placement can't tell.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Board, or a FenError if the piece placement is malformed (see docs for try_parse_fen).
+    /// The rest is filled in with defaults: no en passant square, halfmove clock 0 and fullmove number 1. Castling
+    /// rights are inferred from the pieces: a side may castle to a side if its king and that rook are both still on
+    /// their starting squares (e1 and h1 for White's kingside, e1 and a1 for its queenside, e8, h8 and a8 for Black's),
+    /// since they might not have moved.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Board, Color};
+    /// let example_board = Board::from_board_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR", Color::Black).unwrap();
+    /// assert_eq!(example_board.castling_rights.to_fen(), "KQkq");
+    /// assert_eq!(example_board.active_player, 'b');
+    /// ```
+    pub fn from_board_fen(board_fen: &str, side_to_move: Color) -> Result<Board, FenError> {
+        let fields = board_fen.split_whitespace().count();
+        if fields != 1 {
+            return Err(FenError::WrongFieldCount(fields));
+        }
+        let mut board = try_parse_fen(&format!("{} {} - - 0 1", board_fen, side_to_move.to_char()))?;
+        board.castling_rights = position::inferred_castling_rights(&board);
+        Ok(board)
+    }
+
+    /// A function to parse a FEN that may be missing its last fields, as many sources leave out the counters, or even
+    /// everything after the side to move. Unlike try_parse_fen(), which rejects such FENs, the missing fields are filled in.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// fen: &str, // A FEN with between 1 and 6 fields, separated by any amount of whitespace.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Board, or a FenError for the first field that is malformed. Missing fields default to
+    /// White to move, castling rights inferred from the pieces (see docs for Board::from_board_fen), no en passant
+    /// square, halfmove clock 0 and fullmove number 1. A complete FEN gives the same Board as try_parse_fen().
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Board};
+    /// let example_board = Board::from_fen_lenient("8/8/8/8/8/8/8/K6k b").unwrap();
+    /// assert_eq!(generate_fen(&example_board), "8/8/8/8/8/8/8/K6k b - - 0 1");
+    /// ```
+    pub fn from_fen_lenient(fen: &str) -> Result<Board, FenError> {
+        let fields = fen.split_whitespace().collect::<Vec<&str>>();
+        if fields.is_empty() || fields.len() > 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+        let side_to_move = fields.get(1).copied().unwrap_or("w");
+        let color = Color::from_char(side_to_move.chars().next().unwrap_or(' ')).filter(|_| side_to_move.len() == 1);
+        let inferred_castling = Board::from_board_fen(fields[0], color.ok_or_else(|| FenError::InvalidSideToMove(side_to_move.to_string()))?)?.castling_rights.to_fen();
+        let defaults = [fields[0], side_to_move, &inferred_castling, "-", "0", "1"];
+        let completed = (0..6).map(|index| fields.get(index).copied().unwrap_or(defaults[index])).collect::<Vec<&str>>();
+        try_parse_fen(&completed.join(" "))
+    }
+}
 
 fn get_board_coords(algebraic_notation: String) -> Vec<i32> {
     let col_names = "abcdefgh".to_string();
     let col_number_index = col_names
         .chars()
-        .position(|y| y == algebraic_notation.chars().nth(0).unwrap())
+        .position(|y| y == algebraic_notation.chars().next().unwrap())
         .expect("Unable to find col number");
 
     let col_number = i32::try_from(col_number_index).expect("Column number index too large");
@@ -153,40 +402,125 @@ fn get_algebraic_notation(coords: Vec<i32>) -> String {
     format!("{}{}", col_name, 8 - coords[0])
 } // Generates algebraic notation from Board.game_state coords. Inverse to get_board_coords.
 
-fn get_piece(board: &Board, coords: &Vec<i32>) -> char {
+fn get_piece(board: &Board, coords: &[i32]) -> char {
     board.board_state[coords[0] as usize][coords[1] as usize]
 } // Returns the piece on a given coordinate on the board.
 
+fn get_captured_piece(board: &Board, source: &[i32], target: &[i32]) -> Option<Piece> {
+    let mover = get_piece(board, source);
+    match get_piece(board, target) {
+        '*' if mover.eq_ignore_ascii_case(&'p') && source[1] != target[1] => Piece::from_fen_char(if mover == 'P' { 'p' } else { 'P' }),
+        captured if captured.is_ascii_uppercase() == mover.is_ascii_uppercase() => None, // Chess960 castling onto the own rook
+        captured => Piece::from_fen_char(captured),
+    }
+} // Returns the piece a move captures, if any. A pawn moving to an empty square on another file captures en passant
+
+fn translate_castling_gesture(board: &Board, source: Vec<i32>, target: Vec<i32>) -> Vec<i32> {
+    match CastlingMove::from_move(board, &source, &target) {
+        Some(castling) if castling.right.is_ascii_uppercase() == (board.active_player == 'w') => castling.encoded_target().to_vec(),
+        _ => target,
+    }
+} // Many GUIs castle by dropping the king on its own rook, and Chess960 ones by dropping it where castling puts it. Both
+// gestures are turned into the move that represents castling everywhere else (see the castling module docs). Whether
+// castling is legal is left to the caller.
+
 
 fn is_enemy_piece(active_player: char, piece: char) -> bool {
     (active_player == 'w' && piece.is_ascii_lowercase()) || (active_player == 'b' && piece.is_ascii_uppercase())
 }
-fn is_friendly_piece(active_player: char, piece: char) -> bool {
-    (active_player == 'w' && piece.is_ascii_uppercase()) || (active_player == 'b' && piece.is_ascii_lowercase())
-}
 //REMEMBER! x_pos = col number, y_pos = row number !!!!!!!!!!!!!!!!
 
 fn check_for_checks(board: &Board) -> Vec<bool> {
     vec![player_is_in_check(board, 'w'), player_is_in_check(board, 'b')]
 }
 fn player_is_in_check(board: &Board, player: char) -> bool {
-    let mut enemy = 'n';
-    if player == 'w' {
-        enemy = 'b';
-    } else if player == 'b' {
-        enemy = 'w';
-    }
-    // For each move in the enemy pieces, check for a threatened king. If such is the case, return true.
-    for (_key, value) in get_available_moves_internal(board.clone(), enemy, true) {
-        for legal_move in value {
-            // println!("Legal move for {} at {:?}: {}", board.board_state[_key[0] as usize][_key[1] as usize], _key, board.board_state[legal_move[0] as usize][legal_move[1] as usize]);
-            if board.board_state[legal_move[0] as usize][legal_move[1] as usize].eq_ignore_ascii_case(&'k') {
-                return true;
+    let enemy = if player == 'w' { 'b' } else { 'w' };
+    match board.king_square(player) {
+        Some(king_square) => board.is_square_attacked(king_square, enemy),
+        None => false, // No king (or more than one), so there is nothing to check
+    }
+} // Returns true if the player is in check
+
+fn get_legal_move_list(board: &Board) -> Vec<(Vec<i32>, Vec<i32>, Option<char>)> {
+    flatten_move_map(board, get_available_moves_internal(board.clone(), board.active_player, false))
+} // Lists the legal moves of the active player as (source, target, promotion) triples.
+
+fn get_legal_capture_list(board: &Board) -> Vec<(Vec<i32>, Vec<i32>, Option<char>)> {
+    let mut captures = get_legal_move_list(board);
+    captures.retain(|(source, target, _promotion)| get_captured_piece(board, source, target).is_some());
+    captures
+} // Lists the legal captures of the active player, including en passant, as (source, target, promotion) triples.
+
+fn is_safe_move(board: &mut Board, color: char, source: &[i32], target: &[i32]) -> bool {
+    if let Some(castling) = CastlingMove::from_move(board, source, target) {
+        if player_is_in_check(board, color) {
+            return false; // No castling out of check
+        }
+        if castling.passes_attacked_square(board, |board| player_is_in_check(board, color)) {
+            return false;
+        }
+    }
+    let undo = board.move_piece_undoable(source, target);
+    let in_check = player_is_in_check(board, color);
+    board.unmake(undo);
+    !in_check
+} // Returns true if a move from get_piece_movements doesn't leave the mover's king in check, or castle out of or through check
+
+fn has_legal_move(board: &Board) -> bool {
+    let color = board.active_player;
+    let mut scratch = board.clone();
+    for (y_pos, row) in board.board_state.iter().enumerate() {
+        for (x_pos, piece) in row.iter().enumerate() {
+            if !((color == 'w' && piece.is_ascii_uppercase()) || (color == 'b' && piece.is_ascii_lowercase())) {
+                continue;
+            }
+            let coords = vec![y_pos as i32, x_pos as i32];
+            let movements = scratch.get_piece_movements(&coords, piece, &color);
+            for target in movements {
+                if !get_piece(board, &target).eq_ignore_ascii_case(&'k') && is_safe_move(&mut scratch, color, &coords, &target) {
+                    return true;
+                }
             }
         }
     }
-    false // Check if any opposing piece threatens the king, if yes, return true, else return false
-} // Returns true if the player is in check
+    false
+} // Same as !get_legal_move_list(board).is_empty(), but stops at the first legal move instead of listing them all
+
+fn flatten_move_map(board: &Board, move_map: BTreeMap<Vec<i32>, Vec<Vec<i32>>>) -> Vec<(Vec<i32>, Vec<i32>, Option<char>)> {
+    let mut moves = vec![];
+    for (source, targets) in move_map {
+        let piece = get_piece(board, &source);
+        for target in targets {
+            if piece.eq_ignore_ascii_case(&'p') && (target[0] == 0 || target[0] == 7) {
+                for promotion in ['q', 'r', 'b', 'n'] {
+                    moves.push((source.clone(), target.clone(), Some(promotion)));
+                }
+            } else {
+                moves.push((source.clone(), target, None));
+            }
+        }
+    }
+    moves
+} // Flattens the output of get_available_moves_internal into (source, target, promotion) triples.
+// Pawn moves to the last rank are listed once per promotion piece.
+
+fn get_game_status(board: &Board) -> GameStatus {
+    let player = board.active_player;
+    //check for checkmate
+    if !has_legal_move(board) {
+        if !player_is_in_check(board, player) {
+            return GameStatus::Stalemate;
+        } else if player == 'w' {
+            return GameStatus::BlackWinsByCheckmate;
+        } else {
+            return GameStatus::WhiteWinsByCheckmate;
+        }
+    }
+    if board.halfmove_counter >= 100 {
+        return GameStatus::FiftyMoveDraw;
+    }
+    GameStatus::InProgress
+} // Returns the game status (see docs for GameStatus) of a board. Only the active player can be checkmated or stalemated.
 
 
 /*****************************
@@ -196,7 +530,7 @@ fn player_is_in_check(board: &Board, player: char) -> bool {
 /// A function to return available moves for a given color on a given board. 
 ///
 /// ## Arguments
-/// ```
+/// ```text
 /// mut board: Board, // The Board to look at. Usually your_game.board.
 /// color: char, // the color to return moves for. usually your_game.board.active_player. 
 /// force_no_check: bool // Whether or not to remove moves that would not take the color's king out of check.
@@ -206,15 +540,17 @@ fn player_is_in_check(board: &Board, player: char) -> bool {
 /// This function returns a HashMap, where
 /// key: piece location,
 /// value: each square that the piece can move to.
+/// A square with a king on it is never included, even with force_no_check: kings are checked, not captured.
 ///
 /// ## Example
 ///
 /// ```
+/// # use alviny_task_3::{Game, get_available_moves};
 /// let example_game = Game::new(); // Create a new game at the starting position
-/// let moves = get_available_moves(example_game.board, example_game.board.active_player, false)
-/// assert_eq!(moves["e2".to_string()], ["e3", "e4"]) // Available moves for the e2 pawn
+/// let moves = get_available_moves(example_game.board.clone(), example_game.board.active_player, false);
+/// assert_eq!(moves["e2"], ["e3", "e4"]); // Available moves for the e2 pawn
 /// ```
-pub fn get_available_moves(mut board: Board,
+pub fn get_available_moves(board: Board,
     color: char,
     force_no_check: bool) -> HashMap<String, Vec<String> > {
         let mut temp_keys: Vec<String> = vec![];
@@ -235,138 +571,27 @@ fn get_available_moves_internal (
     mut board: Board,
     color: char,
     force_no_check: bool
-) -> HashMap< Vec<i32>, Vec<Vec<i32>> > {
-    let mut output = HashMap::new();
+) -> BTreeMap< Vec<i32>, Vec<Vec<i32>> > {
+    let mut output = BTreeMap::new(); // ordered by source square, so that the moves always come out in the same order
     for (y_pos, row) in board.clone().board_state.iter().enumerate() {
         for (x_pos, piece) in row.iter().enumerate() {
-            if color == 'w' && piece.is_ascii_uppercase() {
-                // WHITE pieces are represented by UPPERCASE letters
-                let coords: Vec<i32> = vec![i32::try_from(y_pos).unwrap(), i32::try_from(x_pos).unwrap()];
-                let movements = board.get_piece_movements(&coords, &piece, &color);
-                if !movements.is_empty() {
-                    output.insert(coords, movements);
-                }
-                    
-            } else if color == 'b' && piece.is_ascii_lowercase() {
-                // black pieces are represented by lowercase letters
+            if (color == 'w' && piece.is_ascii_uppercase()) || (color == 'b' && piece.is_ascii_lowercase()) {
+                // WHITE pieces are represented by UPPERCASE letters, black pieces by lowercase letters
                 let coords: Vec<i32> = vec![i32::try_from(y_pos).unwrap(), i32::try_from(x_pos).unwrap()];
-                let movements = board.get_piece_movements(&coords, &piece, &color);
+                let mut movements = board.get_piece_movements(&coords, piece, &color);
+                movements.retain(|target| !get_piece(&board, target).eq_ignore_ascii_case(&'k')); // Kings are checked, never captured
                 if !movements.is_empty() {
                     output.insert(coords, movements);
                 }
-                    
             }
         }
     }
     if !force_no_check {//DO NOT RUN THIS IF CALLED BY FN PLAYER IS IN CHECK
-        if player_is_in_check(&board, color) {
-            for (key, value) in output.iter_mut() {
-                value.retain(|legal_move| -> bool { // retain all moves where x isnt in check
-                    let mut test_board = board.clone();
-                    test_board.move_piece(key.clone(), legal_move.to_vec());
-                    !player_is_in_check(&test_board, color)
-                });
-            }
-            //Remove castling moves if player is in check
-            if color == 'w' {
-                if board.board_state[7][4] == 'K' {
-                    if output[&vec![7,4]].contains(&vec![7,6]) {
-                        let index = output[&vec![7,4]].iter().position(|x| *x == vec![7,6]).unwrap();
-                        output.get_mut(&vec![7,4]).unwrap().remove(index);
-                    }
-                    if output[&vec![7,4]].contains(&vec![7,2]) {
-                        let index = output[&vec![7,4]].iter().position(|x| *x == vec![7,2]).unwrap();
-                        output.get_mut(&vec![7,4]).unwrap().remove(index);
-                    }
-                }
-            } else if color == 'b' {
-                if board.board_state[0][4] == 'k' {
-                    if output[&vec![0,4]].contains(&vec![0,6]) {
-                        let index = output[&vec![0,4]].iter().position(|x| *x == vec![0,6]).unwrap();
-                        output.get_mut(&vec![0,4]).unwrap().remove(index);
-                    }
-                    if output[&vec![0,4]].contains(&vec![0,2]) {
-                        let index = output[&vec![0,4]].iter().position(|x| *x == vec![0,2]).unwrap();
-                        output.get_mut(&vec![0,4]).unwrap().remove(index);
-                    }
-                }
-            }
-
-        }
-        // Prevent castling through check
-        let mut test_board = board.clone();
-
-        if color == 'w' {
-            if board.board_state[7][4] == 'K' {
-                if output.contains_key(&vec![7,4]) {
-                    if output[&vec![7,4]].contains(&vec![7,6]) {
-                        test_board.move_piece(vec![7,4], vec![7,5]);
-                        if player_is_in_check(&test_board, color) {
-                            let index = output[&vec![7,4]].iter().position(|x| *x == vec![7,6]).unwrap();
-                            output.get_mut(&vec![7,4]).unwrap().remove(index);
-                        }
-                        test_board = board.clone();
-                    }
-                    if output[&vec![7,4]].contains(&vec![7,2]) {
-                        test_board.move_piece(vec![7,4], vec![7,2]);
-                        if player_is_in_check(&test_board, color) {
-                            let index = output[&vec![7,4]].iter().position(|x| *x == vec![7,2]).unwrap();
-                            output.get_mut(&vec![7,4]).unwrap().remove(index);
-                        }
-                        test_board = board.clone();
-                    }
-                }
-            }
-        } else if color == 'b' {
-            if board.board_state[0][4] == 'k' {
-                if output.contains_key(&vec![0,4]) {
-                    if output[&vec![0,4]].contains(&vec![0,6]) {
-                        test_board.move_piece(vec![0,4], vec![0,5]);
-                        if player_is_in_check(&test_board, color) {
-                            let index = output[&vec![0,4]].iter().position(|x| *x == vec![0,6]).unwrap();
-                            output.get_mut(&vec![0,4]).unwrap().remove(index);
-                        }
-                        test_board = board.clone();
-                    }
-                    if output[&vec![0,4]].contains(&vec![0,2]) {
-                        test_board.move_piece(vec![0,4], vec![0,3]);
-                        if player_is_in_check(&test_board, color) {
-                            let index = output[&vec![0,4]].iter().position(|x| *x == vec![0,2]).unwrap();
-                            output.get_mut(&vec![0,4]).unwrap().remove(index);
-                        }
-                        test_board = board.clone();
-                    }
-                }
-            }
-        }
-        
-    } 
-
-    // Remove pieces with no moves
-    let mut to_remove = Vec::new();   
-    for (key, value) in output.iter_mut() {
-        if value.is_empty() {
-            to_remove.push(key.to_owned());
-            
-        } else { // Remove moves that would put the player in check
-            if !force_no_check { //The function ignores this if it's told to pretend check doesn't exist.
-                let mut elements_to_remove = vec![];
-                for coord in value.clone() {
-                    let mut test_board = board.clone();
-                    test_board.move_piece(key.clone(), coord.clone());
-                    if player_is_in_check(&test_board, color) {
-                        let immut_coord = coord.clone();
-                        elements_to_remove.push(immut_coord)
-                    }
-                }
-                value.retain(|x| !elements_to_remove.contains(x));
-            }
+        for (key, value) in output.iter_mut() {
+            value.retain(|legal_move| is_safe_move(&mut board, color, key, legal_move)); // Covers pins, checks and castling
         }
     }
-    for key in to_remove.iter() {
-        output.remove(key);
-    }// println!("{:?}", output);
-
+    output.retain(|_key, value| !value.is_empty()); // Remove pieces with no moves
 
     output
 } // For any given color, finds pieces of that color. Returns a 
@@ -377,143 +602,976 @@ fn get_available_moves_internal (
 *  BEGIN HERE                *
 *****************************/
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent the reasons a FEN string can't be read (see docs for try_parse_fen).
+pub enum FenError {
+    WrongFieldCount(usize), // A FEN has 6 fields separated by spaces
+    WrongRankCount(usize), // The board has 8 ranks separated by '/'
+    InvalidRank(String), // A rank has a character that isn't a piece or a digit from 1 to 8, or doesn't add up to 8 squares
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidCounter(String),
+    IllegalPosition(position::PositionError), // The FEN can be read, but the position can't be played (see docs for Board::validate)
+}
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "a FEN has 6 fields, not {}", count),
+            FenError::WrongRankCount(count) => write!(f, "a FEN board has 8 ranks, not {}", count),
+            FenError::InvalidRank(rank) => write!(f, "{} is not a valid rank", rank),
+            FenError::InvalidSideToMove(side) => write!(f, "{} is not a side to move", side),
+            FenError::InvalidCastling(rights) => write!(f, "{} are not valid castling rights", rights),
+            FenError::InvalidEnPassant(square) => write!(f, "{} is not a valid en passant square", square),
+            FenError::InvalidCounter(counter) => write!(f, "{} is not a valid move counter", counter),
+            FenError::IllegalPosition(error) => write!(f, "illegal position: {}", error),
+        }
+    }
+}
+impl std::error::Error for FenError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why an input can't be parsed into a Game (see docs for Game::from_str).
+///
+/// ```text
+/// Fen(FenError), // The input looks like a FEN or a board FEN, but isn't a valid one.
+/// Pgn(PgnError), // The input looks like PGN, but isn't a valid game.
+/// Unrecognized(String), // The input doesn't look like any of them. Holds the input.
+/// ```
+pub enum ParseGameError {
+    Fen(FenError),
+    Pgn(pgn::PgnError),
+    Unrecognized(String),
+}
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseGameError::Fen(fen_error) => write!(f, "invalid FEN: {}", fen_error),
+            ParseGameError::Pgn(pgn_error) => write!(f, "invalid PGN: {}", pgn_error),
+            ParseGameError::Unrecognized(input) => write!(f, "{:?} is neither a FEN nor a PGN game", input),
+        }
+    }
+}
+impl std::error::Error for ParseGameError {}
+
+impl FromStr for Game {
+    type Err = ParseGameError;
+
+    /// A function to create a Game from a FEN, a board FEN or a PGN game, telling them apart by how the input starts:
+    /// ```text
+    /// '[', // PGN tag pairs (see docs for Game::from_pgn).
+    /// a first field with a '/', // A FEN, which may leave out its last fields (see docs for Board::from_fen_lenient). A board FEN is a FEN
+    ///                           // with only its first field, so White is to move.
+    /// a move number or a SAN move, // PGN movetext without tag pairs, e.g. "1. e4 e5" or "e4 e5".
+    /// ```
+    /// Anything else is rejected rather than guessed at. Since only the start is looked at, an input that starts like
+    /// one kind is reported as an invalid input of that kind, e.g. "e4 is my favourite move" as invalid PGN, and a
+    /// FEN-like position isn't tried as movetext.
+    ///
+    /// ## Returns
+    /// This function returns the Game, or a ParseGameError with the FenError or PgnError of the parser the input was
+    /// handed to. FENs whose positions can't be played (see docs for Board::validate) are rejected.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let from_fen: Game = "8/8/4k3/8/8/4K3/4R3/8 b - - 0 1".parse().unwrap();
+    /// let from_pgn: Game = "1. e4 e5 2. Nf3".parse().unwrap();
+    /// assert_eq!(from_pgn.history.len(), 3);
+    /// assert!("Hello, world!".parse::<Game>().is_err());
+    /// ```
+    fn from_str(input: &str) -> Result<Game, ParseGameError> {
+        let input = input.trim();
+        let first_token = input.split_whitespace().next().unwrap_or("");
+        if input.starts_with('[') || looks_like_movetext(first_token) {
+            Game::from_pgn(input).map_err(ParseGameError::Pgn)
+        } else if first_token.contains('/') {
+            let board = Board::from_fen_lenient(input).map_err(ParseGameError::Fen)?;
+            board.validate().map_err(|error| ParseGameError::Fen(FenError::IllegalPosition(error)))?;
+            Ok(Game::from_board(board))
+        } else {
+            Err(ParseGameError::Unrecognized(input.to_string()))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent whether a game is in progress, and if not, how it ended.
+pub enum GameStatus {
+    InProgress,
+    WhiteWinsByCheckmate,
+    BlackWinsByCheckmate,
+    Stalemate,
+    FiftyMoveDraw,
+    WhiteWinsByKingOfTheHill, // Only in Variant::KingOfTheHill
+    BlackWinsByKingOfTheHill,
+    WhiteWinsByExplosion, // Only in Variant::Atomic
+    BlackWinsByExplosion,
+    WhiteWinsByRace, // Only in Variant::RacingKings
+    BlackWinsByRace,
+    RaceDraw,
+    WhiteWinsByTimeForfeit, // Only with a clock (see docs for Clock)
+    BlackWinsByTimeForfeit,
+    TimeForfeitDraw, // The side that ran out of time loses, unless the opponent couldn't have checkmated
+    WhiteWinsByResignation,
+    BlackWinsByResignation,
+    DrawByAgreement,
+    DrawByRepetition, // Only when claimed (see docs for Game::claim_draw)
+    WhiteWinsByAdjudication, // Only with the `tablebase` feature (see docs for Game::adjudicate_with_tablebase)
+    BlackWinsByAdjudication,
+    DrawByAdjudication,
+}
+impl GameStatus {
+    /// A function to check whether the status is a checkmate, for either side.
+    pub fn is_checkmate(&self) -> bool {
+        matches!(self, GameStatus::WhiteWinsByCheckmate | GameStatus::BlackWinsByCheckmate)
+    }
+
+    /// A function to check whether the status is a draw of any kind (stalemate included).
+    pub fn is_draw(&self) -> bool {
+        matches!(
+            self,
+            GameStatus::Stalemate | GameStatus::FiftyMoveDraw | GameStatus::RaceDraw | GameStatus::TimeForfeitDraw
+            | GameStatus::DrawByAgreement | GameStatus::DrawByRepetition | GameStatus::DrawByAdjudication
+        )
+    }
+
+    /// A function to check whether the game ended because a side ran out of time, whether that was a loss or a draw.
+    pub fn is_time_forfeit(&self) -> bool {
+        matches!(self, GameStatus::WhiteWinsByTimeForfeit | GameStatus::BlackWinsByTimeForfeit | GameStatus::TimeForfeitDraw)
+    }
+
+    /// A function to check whether the game was decided off the board, by a time forfeit, a resignation, a draw agreement,
+    /// a claimed repetition or an adjudication. No more moves can be made in such a game.
+    pub fn is_off_the_board(&self) -> bool {
+        self.is_time_forfeit()
+            || matches!(
+                self,
+                GameStatus::WhiteWinsByResignation | GameStatus::BlackWinsByResignation | GameStatus::DrawByAgreement
+                | GameStatus::DrawByRepetition | GameStatus::WhiteWinsByAdjudication | GameStatus::BlackWinsByAdjudication
+                | GameStatus::DrawByAdjudication
+            )
+    }
+
+    /// A function to check whether the game has ended, for any reason.
+    pub fn is_game_over(&self) -> bool {
+        *self != GameStatus::InProgress
+    }
+
+    /// A function to return the winner of the game.
+    ///
+    /// ## Returns
+    /// This function returns Some(Color) if one side has won, or None for draws and games in progress.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameStatus::WhiteWinsByCheckmate | GameStatus::WhiteWinsByKingOfTheHill | GameStatus::WhiteWinsByExplosion
+            | GameStatus::WhiteWinsByRace | GameStatus::WhiteWinsByTimeForfeit | GameStatus::WhiteWinsByResignation
+            | GameStatus::WhiteWinsByAdjudication => Some(Color::White),
+            GameStatus::BlackWinsByCheckmate | GameStatus::BlackWinsByKingOfTheHill | GameStatus::BlackWinsByExplosion
+            | GameStatus::BlackWinsByRace | GameStatus::BlackWinsByTimeForfeit | GameStatus::BlackWinsByResignation
+            | GameStatus::BlackWinsByAdjudication => Some(Color::Black),
+            GameStatus::InProgress | GameStatus::Stalemate | GameStatus::FiftyMoveDraw | GameStatus::RaceDraw
+            | GameStatus::TimeForfeitDraw | GameStatus::DrawByAgreement | GameStatus::DrawByRepetition
+            | GameStatus::DrawByAdjudication => None,
+        }
+    }
+
+    /// A function to return the result in the form used by PGN.
+    ///
+    /// ## Returns
+    /// This function returns "1-0" if White has won, "0-1" if Black has won, "1/2-1/2" for draws and "*" for games in progress.
+    pub fn result_string(&self) -> &'static str {
+        match (self.winner(), self.is_game_over()) {
+            (Some(Color::White), _) => "1-0",
+            (Some(Color::Black), _) => "0-1",
+            (None, true) => "1/2-1/2",
+            (None, false) => "*",
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 /// A struct to represent the chess game itself.
 ///
 /// ## Attributes
-/// ```
+/// ```text
 /// fen: String, // the FEN string that represents the current position.
 /// board: Board, // A representation of the FEN string that is easier to work with.
 /// checks: Vec<bool>, // index 0 is white's check status, index 1 is black's check status
-/// game_status: u8, // 0: Game in progress, 1: Checkmate (White wins), 2: Checkmate (Black wins), 3: Stalemate, 4: Draw by 50 move rule
+/// game_status: GameStatus, // Whether the game is in progress, and if not, how it ended (see docs for GameStatus).
+/// start_fen: String, // The FEN of the position the game started from.
+/// history: Vec<Move>, // Every move made since start_fen, in order.
+/// variant: Variant, // The rules the game is played by (see docs for Variant).
+/// clock: Option<Clock>, // The chess clock, if the game is timed (see docs for Clock).
+/// captured, // The pieces each side has captured, in order (see captured_by()). Private, and kept in step with history.
+/// annotations, // The clock, evaluation and comment of every move (see annotation()). Private, and kept in step with history.
+/// observer, // The callback registered with set_observer(), if any. Private, and not cloned with the Game.
+/// has_castled, // Whether each player has castled (see castled()). Private, since a FEN can't say.
+/// position_occurrences, // How often each position has occurred since start_fen (see position_occurrences()). Private, and kept in step with history.
+/// tags, // The PGN tag pairs of the game, e.g. ("White", "Morphy, Paul") (see tag()). Private, since Result and FEN come from the game itself.
+/// draw_offer, // The player whose draw offer is pending, if any (see draw_offer()). Private, since moves and undos withdraw it.
 /// 
 /// ```
 pub struct Game {
     pub fen: String,
     pub board: Board, 
     pub checks: Vec<bool>, // index 0 is white's check status, index 1 is black's check status
-    pub game_status: u8, // 0: Game in progress, 1: Checkmate (White wins), 2: Checkmate (Black wins), 3: Stalemate, 4: Draw by 50 move rule
-    
+    pub game_status: GameStatus,
+    pub start_fen: String,
+    pub history: Vec<Move>,
+    pub variant: Variant,
+    pub clock: Option<Clock>,
+    pub(crate) captured: [Vec<Piece>; 2],
+    pub(crate) annotations: Vec<MoveAnnotation>,
+    pub(crate) observer: Observer,
+    pub(crate) has_castled: [CastledStatus; 2],
+    pub(crate) position_occurrences: HashMap<u64, u8>,
+    pub(crate) tags: Vec<(String, String)>,
+    pub(crate) draw_offer: Option<Color>,
 }
 impl Game {
     /// A function to create a new Game object from a given FEN.
     ///
     /// ## Arguments
-    /// ```
+    /// ```text
     /// fen: String, // A valid FEN string.
     /// 
     /// ```
     /// ## Returns
-    /// This function returns a Game object. This function will not error, but it will exhibit undocumented behaviour if the inputted FEN string is invalid.
+    /// This function returns a Game object. This function will not error, but it will exhibit undocumented behaviour if the inputted FEN string is invalid.
+    /// Positions that Board::validate() rejects are still accepted, and never cause a panic: for example, a pawn on the
+    /// last rank it moves towards simply has no moves. Use try_from_fen() to reject such positions instead.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string()); // Create a new game at the final position of the Immortal Game. This Game will have status GameStatus::WhiteWinsByCheckmate, since White is victorious.
+    /// ```
+    pub fn new_from_fen(fen: String) -> Game {
+        let board = parse_fen(&fen);
+        let checks = check_for_checks(&board);
+        let has_castled = CastledStatus::from_rights(&board.castling_rights);
+        let position_occurrences = HashMap::from([(board.zobrist_key(), 1)]);
+        let mut temp_game = Game { start_fen: fen.clone(), fen, board, checks, game_status: GameStatus::InProgress, history: vec![], variant: Variant::Standard, clock: None, captured: Default::default(), annotations: vec![], observer: Observer::default(), has_castled, position_occurrences, tags: vec![], draw_offer: None };
+        temp_game.update_game_status();
+        temp_game
+    }
+    /// A function to create a new Game from a FEN that might be malformed, such as user input.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// fen: &str, // A FEN string.
+    /// 
+    /// ```
+    /// ## Returns
+    /// This function returns the Game, or a FenError if the FEN is malformed (see docs for try_parse_fen) or the position
+    /// can't be played (see docs for Board::validate).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{FenError, Game};
+    /// assert!(Game::try_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23").is_ok());
+    /// assert!(matches!(Game::try_from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::IllegalPosition(_)))); // No white king
+    /// ```
+    pub fn try_from_fen(fen: &str) -> Result<Game, FenError> {
+        try_parse_fen(fen)?.validate().map_err(FenError::IllegalPosition)?;
+        Ok(Game::new_from_fen(fen.to_string()))
+    }
+    /// A function to create a new Game at the starting position. Alias to 
+    /// ```text
+    /// Game::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string())
+    /// ```
+    ///
+    /// ## Returns
+    /// This function returns a valid in-progress Game object. 
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// ```
+    pub fn new() -> Game {
+        Self::new_from_fen(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+        )
+    }
+    /// A function to make a move in the Game. Automatically detects whose turn it is based on `Game.board`.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// source: String, // The square where the piece to move stands, in algebraic notation.
+    /// target: String, // The square to which to move the piece, in algebraic notation.
+    /// 
+    /// ```
+    /// ## Returns
+    /// This function returns true if the move was successfully made, false otherwist,
+    /// Castling is the king's two-square move, e.g. "e1" to "g1", but moving the king onto its own rook ("e1" to "h1")
+    /// is accepted too, as long as castling with that rook is legal. In Chess960, castling is the king moving onto its own
+    /// rook, and moving the king straight to where castling puts it is accepted when that isn't a single step.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_move("f2".to_string(), "f3".to_string()); // 1. f3
+    /// example_game.make_move("e7".to_string(), "e5".to_string()); // 1... e5
+    /// example_game.make_move("g2".to_string(), "g4".to_string()); // 2. g4
+    /// example_game.make_move("d8".to_string(), "h4".to_string()); // 2... Qh4#
+    /// ```
+    pub fn make_move(&mut self, source: String, target: String) -> bool { //Returns true if a valid move has been made
+        //Assuming both square and target are valid algebraic notation.
+        if self.game_status.is_off_the_board() {
+            debug_event!(source = %source, target = %target, reason = "the game is over", "move rejected");
+            return false;
+        }
+        if Square::from_algebraic(&source).is_none() || Square::from_algebraic(&target).is_none() {
+            debug_event!(source = %source, target = %target, reason = "not a square", "move rejected");
+            return false;
+        }
+        let source_coords = get_board_coords(source);
+        let target_coords = translate_castling_gesture(&self.board, source_coords.clone(), get_board_coords(target));
+        let available_moves = self.variant.rules().legal_moves(&self.board);
+        if available_moves.iter().any(|legal_move| get_board_coords(legal_move.source.clone()) == source_coords
+            && get_board_coords(legal_move.target.clone()) == target_coords) {
+                // hopefully error free way of checking if the move is a valid move as dictated by get_available_moves_internal()
+                let promotion = (get_piece(&self.board, &source_coords).eq_ignore_ascii_case(&'p') && (target_coords[0] == 0 || target_coords[0] == 7))
+                    .then(|| self.board.promotion_selection.to_ascii_lowercase());
+                let played_move = Move {
+                    source: get_algebraic_notation(source_coords.clone()),
+                    target: get_algebraic_notation(target_coords.clone()),
+                    promotion,
+                };
+                let is_capture = observer::is_capture(self, &played_move);
+                self.apply_move(played_move.clone(), true);
+                debug_event!(chess_move = %played_move, fen = %self.fen, "move applied");
+                self.notify(MoveEventKind::Move, Some(played_move), is_capture);
+        } else {
+            debug_event!(
+                source = %get_algebraic_notation(source_coords),
+                target = %get_algebraic_notation(target_coords),
+                reason = "not a legal move",
+                "move rejected"
+            );
+            return false;
+        }
+        
+        true
+    } // TODO Make move if move is available for the active player, then switch active player, then check for checks
+
+    /// A function to make a move given in long algebraic (UCI) notation, e.g. "e2e4" or "e7e8q".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// uci_move: &str, // The move in long algebraic notation. The optional fifth character is the promotion piece.
+    /// 
+    /// ```
+    /// ## Returns
+    /// This function returns true if the move was successfully made, false otherwise (including when the string can't be parsed).
+    /// Moves to the last rank without a promotion character promote to the current `Board.promotion_selection`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.make_uci_move("e2e4")); // 1. e4
+    /// assert!(!example_game.make_uci_move("e2e4")); // There is no longer a pawn on e2
+    /// ```
+    pub fn make_uci_move(&mut self, uci_move: &str) -> bool {
+        let parsed_move = match Move::from_uci(uci_move) {
+            Some(parsed_move) => parsed_move,
+            None => {
+                debug_event!(uci_move, reason = "not a UCI move", "move rejected");
+                return false;
+            }
+        };
+        let previous_selection = self.board.promotion_selection;
+        if let Some(promotion) = parsed_move.promotion {
+            self.board.set_promotion(promotion);
+        }
+        let result = self.make_move(parsed_move.source, parsed_move.target);
+        self.board.promotion_selection = previous_selection;
+        result
+    }
+
+    /// A function to make a move, asking for the promotion piece only if the move turns out to be a promotion,
+    /// e.g. by showing a piece picker. This saves setting Board.promotion_selection before every pawn move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// source: String, // The square where the piece to move stands, in algebraic notation.
+    /// target: String, // The square to which to move the piece, in algebraic notation.
+    /// chooser: impl FnOnce(&Game) -> PieceKind, // Called with the position before the move, only for legal promotions.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the move was made, and false if it is illegal or the chooser picked a pawn or a king.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, PieceKind};
+    /// let mut example_game = Game::new_from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+    /// assert!(example_game.make_move_with("a7".to_string(), "a8".to_string(), |_game| PieceKind::Knight));
+    /// assert_eq!(example_game.fen, "N3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    /// ```
+    pub fn make_move_with(&mut self, source: String, target: String, chooser: impl FnOnce(&Game) -> PieceKind) -> bool {
+        let is_promotion = self.legal_moves().iter().any(|legal_move| {
+            legal_move.source == source && legal_move.target == target && legal_move.promotion.is_some()
+        });
+        if !is_promotion {
+            return self.make_move(source, target);
+        }
+        let promotion = match chooser(self) {
+            PieceKind::Queen => 'q',
+            PieceKind::Rook => 'r',
+            PieceKind::Bishop => 'b',
+            PieceKind::Knight => 'n',
+            PieceKind::Pawn | PieceKind::King => return false,
+        };
+        self.make_uci_move(&format!("{}{}{}", source, target, promotion))
+    }
+
+    /// A function to take back the last move. The position is rebuilt by replaying the history from start_fen,
+    /// so the game status is recomputed, but the clock (if any) is left as it is.
+    ///
+    /// ## Returns
+    /// This function returns the move that was taken back, or None if no move has been made.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert_eq!(example_game.undo_move(), Move::from_uci("e2e4"));
+    /// assert_eq!(example_game, Game::new());
+    /// assert_eq!(example_game.undo_move(), None);
+    /// ```
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let undone_move = self.history.pop()?;
+        let mut replay = Game::new_from_fen_with_variant(self.start_fen.clone(), self.variant.clone());
+        for chess_move in &self.history {
+            replay.make_uci_move(&chess_move.to_uci());
+        }
+        let is_capture = observer::is_capture(&replay, &undone_move);
+        if CastledStatus::castle_side(&replay.board, &undone_move).is_some() {
+            self.has_castled[if replay.board.active_player == 'w' { 0 } else { 1 }] = CastledStatus::NotCastled;
+        }
+        draws::forget_position(self);
+        self.fen = replay.fen;
+        self.board = replay.board;
+        self.checks = replay.checks;
+        self.game_status = replay.game_status;
+        self.captured = replay.captured;
+        self.annotations.truncate(self.history.len());
+        self.draw_offer = None;
+        self.notify(MoveEventKind::Undo, Some(undone_move.clone()), is_capture);
+        Some(undone_move)
+    }
+
+    /// A function for a player to resign, which ends the game in the opponent's favour.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The color of the player who resigns.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the player resigned, or false if the game was already over.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game, GameStatus};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.resign(Color::Black));
+    /// assert_eq!(example_game.game_status, GameStatus::WhiteWinsByResignation);
+    /// ```
+    pub fn resign(&mut self, color: Color) -> bool {
+        if self.game_status.is_game_over() {
+            return false;
+        }
+        self.game_status = match color {
+            Color::White => GameStatus::BlackWinsByResignation,
+            Color::Black => GameStatus::WhiteWinsByResignation,
+        };
+        self.notify(MoveEventKind::Resignation(color), None, false);
+        true
+    }
+
+    /// A function to end the game in a draw, because the players agreed to one.
+    ///
+    /// ## Returns
+    /// This function returns true if the game was drawn, or false if the game was already over.
+    pub fn agree_draw(&mut self) -> bool {
+        if self.game_status.is_game_over() {
+            return false;
+        }
+        self.game_status = GameStatus::DrawByAgreement;
+        self.notify(MoveEventKind::DrawAgreement, None, false);
+        true
+    }
+
+    /// A function for a player to offer a draw. The offer stands until the opponent accepts it with accept_draw(),
+    /// declines it with decline_draw() or makes a move, or until a move is undone.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The color of the player who offers the draw.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the draw was offered, or false if the game is over or the opponent's offer is
+    /// pending (accept it instead).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game, GameStatus};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert!(example_game.offer_draw(Color::White));
+    /// example_game.make_uci_move("e7e5"); // 1... e5, which declines the offer
+    /// assert_eq!(example_game.draw_offer(), None);
+    /// assert!(example_game.offer_draw(Color::White));
+    /// assert!(example_game.accept_draw(Color::Black));
+    /// assert_eq!(example_game.game_status, GameStatus::DrawByAgreement);
+    /// ```
+    pub fn offer_draw(&mut self, color: Color) -> bool {
+        if self.game_status.is_game_over() || self.draw_offer == Some(color.opposite()) {
+            return false;
+        }
+        self.draw_offer = Some(color);
+        true
+    }
+
+    /// A function to return the player whose draw offer is pending (see docs for offer_draw).
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// A function for a player to accept the opponent's draw offer, which ends the game in a draw by agreement.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The color of the player who accepts.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the game was drawn, or false if the opponent hasn't offered a draw.
+    pub fn accept_draw(&mut self, color: Color) -> bool {
+        if self.draw_offer != Some(color.opposite()) {
+            return false;
+        }
+        self.draw_offer = None;
+        self.agree_draw()
+    }
+
+    /// A function for a player to decline the opponent's draw offer without making a move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The color of the player who declines.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the offer was declined, or false if the opponent hasn't offered a draw.
+    pub fn decline_draw(&mut self, color: Color) -> bool {
+        if self.draw_offer != Some(color.opposite()) {
+            return false;
+        }
+        self.draw_offer = None;
+        true
+    }
+
+    /// A function to list every legal move for the active player.
+    ///
+    /// ## Returns
+    /// This function returns a Vec of Moves. Pawn moves to the last rank are listed once per promotion piece (q, r, b, n).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.legal_moves().len(), 20);
+    /// ```
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.game_status.is_off_the_board() {
+            return vec![];
+        }
+        self.variant.rules().legal_moves(&self.board)
+    }
+
+    /// A function to list the legal moves of the active player that capture a piece, e.g. for a quiescence search or
+    /// for highlighting the captures on offer.
+    ///
+    /// ## Returns
+    /// This function returns the moves of legal_moves() that capture, including en passant. A capture that promotes is
+    /// listed once per promotion piece.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.legal_captures().is_empty());
+    /// for uci_move in ["e2e4", "d7d5"] {
+    ///     example_game.make_uci_move(uci_move);
+    /// }
+    /// assert_eq!(example_game.legal_captures().len(), 1); // exd5
+    /// ```
+    pub fn legal_captures(&self) -> Vec<Move> {
+        let mut captures = self.legal_moves();
+        captures.retain(|legal_move| observer::is_capture(self, legal_move));
+        captures
+    }
+
+    /// A function to list every legal move for the active player in a fixed order, e.g. for snapshot tests or
+    /// for breaking ties the same way every time.
+    ///
+    /// ## Returns
+    /// This function returns the same moves as legal_moves(), sorted by source square, then target square, then promotion
+    /// piece. Squares are ordered from a1 to h8 as in Square::all() (a1, b1, ..., h1, a2, ...), and promotions in the order
+    /// q, r, b, n.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let first_moves: Vec<String> = example_game.legal_moves_sorted().iter().take(3).map(|legal_move| legal_move.to_uci()).collect();
+    /// assert_eq!(first_moves, ["b1a3", "b1c3", "g1f3"]);
+    /// ```
+    pub fn legal_moves_sorted(&self) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.sort_by_key(|legal_move| {
+            let square_index = |square: &str| Square::from_algebraic(square).map(|square| square.index());
+            let promotion_index = legal_move.promotion.and_then(|piece| "qrbn".find(piece.to_ascii_lowercase()));
+            (square_index(&legal_move.source), square_index(&legal_move.target), promotion_index)
+        });
+        moves
+    }
+
+    /// A function to return the pieces a player has captured, e.g. for the tray of captured pieces next to the board.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The player who made the captures.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the opponent's pieces in the order they were captured, including pawns taken en passant and
+    /// pieces taken by a pawn as it promoted. Only the piece on the target square counts, so in Variant::Atomic the pieces
+    /// that explode around it are not included.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game, Piece};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// for uci_move in ["e2e4", "d7d5", "e4d5", "d8d5"] {
+    ///     example_game.make_uci_move(uci_move);
+    /// }
+    /// assert_eq!(example_game.captured_by(Color::White), [Piece::from_fen_char('p').unwrap()]);
+    /// assert_eq!(example_game.captured_by(Color::Black), [Piece::from_fen_char('P').unwrap()]);
+    /// ```
+    pub fn captured_by(&self, color: Color) -> &[Piece] {
+        &self.captured[if color == Color::White { 0 } else { 1 }]
+    }
+
+    /// A function to return which castling moves the players are still allowed to make (see docs for CastlingRights).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("g1f3"); // 1. Nf3
+    /// example_game.make_uci_move("e7e5"); // 1... e5
+    /// example_game.make_uci_move("h1g1"); // 2. Rg1
+    /// assert!(!example_game.castling_rights().white_kingside && example_game.castling_rights().white_queenside);
+    /// ```
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.board.castling_rights
+    }
+
+    /// A function to return whether a player has castled in this game (see docs for CastledStatus).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The player.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns CastledStatus::Castled with the side the player castled to, CastledStatus::NotCastled if
+    /// they haven't, or CastledStatus::Unknown if the game started from a FEN where they had no castling rights left,
+    /// since the FEN doesn't say whether they castled before it. Undoing the castling move makes it NotCastled again.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game};
+    /// # use alviny_task_3::castling::{CastleSide, CastledStatus};
+    /// let mut example_game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQ - 0 1".to_string());
+    /// example_game.make_uci_move("e1c1"); // 1. O-O-O
+    /// assert_eq!(example_game.castled(Color::White), CastledStatus::Castled(CastleSide::Queenside));
+    /// assert_eq!(example_game.castled(Color::Black), CastledStatus::Unknown); // Black has no rights in the FEN
+    /// ```
+    pub fn castled(&self, color: Color) -> CastledStatus {
+        self.has_castled[if color == Color::White { 0 } else { 1 }]
+    }
+
+    /// A function to return the square a pawn can be captured on en passant, e.g. to draw a hint on it.
+    ///
+    /// ## Returns
+    /// This function returns Some(Square) right after a double pawn push, but only if the active player has a pawn next to
+    /// the pushed pawn that could capture it, and None otherwise. This is the same rule Zobrist keys are hashed by.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert_eq!(example_game.en_passant_square(), None); // No black pawn can capture on e3
+    /// ```
+    pub fn en_passant_square(&self) -> Option<Square> {
+        zobrist::en_passant_file(&self.board)?;
+        Square::from_algebraic(&self.board.en_passant_square)
+    }
+
+    /// A function to check whether the active player has been checkmated.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string()); // The final position of the Immortal Game
+    /// assert!(example_game.is_checkmate());
+    /// ```
+    pub fn is_checkmate(&self) -> bool {
+        self.game_status.is_checkmate()
+    }
+
+    /// A function to check whether the active player has been stalemated.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string());
+    /// assert!(example_game.is_stalemate());
+    /// ```
+    pub fn is_stalemate(&self) -> bool {
+        self.game_status == GameStatus::Stalemate
+    }
+
+    /// A function to check whether the game has ended in a draw, by stalemate or by the 50 move rule.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("6k1/5p1p/8/6p1/2P1p1P1/4P2P/1r6/2K5 w - - 100 47".to_string());
+    /// assert!(example_game.is_draw());
+    /// ```
+    pub fn is_draw(&self) -> bool {
+        self.game_status.is_draw()
+    }
+
+    /// A function to check whether the game has ended, for any reason.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(!example_game.is_game_over());
+    /// ```
+    pub fn is_game_over(&self) -> bool {
+        self.game_status.is_game_over()
+    }
+
+    /// A function to return the winner of the game.
+    ///
+    /// ## Returns
+    /// This function returns Some(Color) if one side has won, or None for draws and games in progress.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game};
+    /// let example_game = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string()); // The final position of the Immortal Game
+    /// assert_eq!(example_game.winner(), Some(Color::White));
+    /// ```
+    pub fn winner(&self) -> Option<Color> {
+        self.game_status.winner()
+    }
+
+    /// A function to return the result of the game in the form used by PGN.
+    ///
+    /// ## Returns
+    /// This function returns "1-0", "0-1", "1/2-1/2", or "*" if the game is still in progress.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.result_string(), "*");
+    /// ```
+    pub fn result_string(&self) -> &'static str {
+        self.game_status.result_string()
+    }
+
+    /// A function to return the halfmove clock, i.e. the number of halfmoves since the last capture or pawn move.
+    ///
+    /// ## Returns
+    /// This function returns the clock. The game is drawn by the 50 move rule when it reaches 100.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("g1f3"); // 1. Nf3
+    /// assert_eq!(example_game.halfmove_clock(), 1);
+    /// ```
+    pub fn halfmove_clock(&self) -> u32 {
+        self.board.halfmove_counter
+    }
+
+    /// A function to return the fullmove number, which starts at 1 and increases after every move by Black.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// example_game.make_uci_move("e7e5"); // 1... e5
+    /// assert_eq!(example_game.fullmove_number(), 2);
+    /// ```
+    pub fn fullmove_number(&self) -> u64 {
+        self.board.turn_counter
+    }
+
+    /// A function to return the number of halfmoves (plies) made since the position the game started from.
+    ///
+    /// ## Returns
+    /// This function returns the length of the move history. Unlike fullmove_number(), this is 0 for a game created from a mid-game FEN.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new_from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 40".to_string());
+    /// example_game.make_uci_move("h1h8"); // 40. Rh8+
+    /// assert_eq!(example_game.ply(), 1);
+    /// ```
+    pub fn ply(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    pub(crate) fn apply_move(&mut self, played_move: Move, update_status: bool) {
+        let (source_coords, target_coords) = (get_board_coords(played_move.source.clone()), get_board_coords(played_move.target.clone()));
+        if let Some(captured_piece) = get_captured_piece(&self.board, &source_coords, &target_coords) {
+            self.captured[if self.board.active_player == 'w' { 0 } else { 1 }].push(captured_piece);
+        }
+        if let Some(side) = CastledStatus::castle_side(&self.board, &played_move) {
+            self.has_castled[if self.board.active_player == 'w' { 0 } else { 1 }] = CastledStatus::Castled(side);
+        }
+        if self.draw_offer.is_some_and(|offered_by| Color::from_char(self.board.active_player) != Some(offered_by)) {
+            self.draw_offer = None; // Moving declines the opponent's offer
+        }
+        self.variant.rules().play_move(&mut self.board, &played_move);
+        let occurrences = self.position_occurrences.entry(self.board.zobrist_key()).or_insert(0);
+        *occurrences = occurrences.saturating_add(1);
+        self.history.push(played_move);
+        self.annotations.push(MoveAnnotation::default());
+        if update_status {
+            self.checks = self.variant.checks(&self.board);
+            self.update_game_status();
+        }
+        self.fen = self.variant.rules().extend_fen(&self.board, generate_fen(&self.board));
+    } // Plays a legal move and does the bookkeeping: captured pieces, castling, draw offers, repetitions, history, annotations, checks, game status and FEN.
+    // The observer isn't notified. Without update_status, checks and game_status are left as they were
+
+    fn update_game_status(&mut self) {
+        let previous_status = self.game_status;
+        self.game_status = self.variant.rules().game_status(&self.board);
+        if self.game_status != previous_status {
+            debug_event!(from = ?previous_status, to = ?self.game_status, "status changed");
+        }
+    }
+
+    /// A function to evaluate the current position. Alias to
+    /// ```text
+    /// your_game.board.evaluate()
+    /// ```
+    ///
+    /// ## Returns
+    /// This function returns a score in centipawns from White's perspective (see docs for Board::evaluate).
     ///
     /// ## Example
     ///
     /// ```
-    /// let example_game = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string()); // Create a new game at the final position of the Immortal Game. This Game will have status 1, since White is victorious.
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.evaluate().abs() < 50); // The starting position is roughly equal
     /// ```
-    pub fn new_from_fen(fen: String) -> Game {
-        let board = parse_fen(&fen);
-        let checks = check_for_checks(&board);
-        let mut temp_game = Game { fen, board, checks, game_status: 0};
-        temp_game.update_game_status();
-        temp_game
+    pub fn evaluate(&self) -> i32 {
+        self.board.evaluate()
     }
-    /// A function to create a new Game at the starting position. Alias to 
-    /// ```
-    /// Game::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string())
+
+    /// A function to count the material of one side. Alias to
+    /// ```text
+    /// your_game.board.material(color)
     /// ```
     ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
     /// ## Returns
-    /// This function returns a valid in-progress Game object. 
+    /// This function returns a MaterialCount (see docs for MaterialCount).
     ///
     /// ## Example
     ///
     /// ```
+    /// # use alviny_task_3::Game;
     /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.material('w').knights, 2);
     /// ```
-    pub fn new() -> Game {
-        Self::new_from_fen(
-            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-        )
+    pub fn material(&self, color: char) -> MaterialCount {
+        self.board.material(color)
     }
-    /// A function to make a move in the Game. Automatically detects whose turn it is based on `Game.board`.
-    ///
-    /// ## Arguments
-    /// ```
-    /// source: String, // The square where the piece to move stands, in algebraic notation.
-    /// target: String, // The square to which to move the piece, in algebraic notation.
-    /// 
+
+    /// A function to calculate the material balance of the game. Alias to
+    /// ```text
+    /// your_game.board.material_balance()
     /// ```
+    ///
     /// ## Returns
-    /// This function returns true if the move was successfully made, false otherwist,
+    /// This function returns White's material minus Black's material, in pawns.
     ///
     /// ## Example
     ///
     /// ```
+    /// # use alviny_task_3::Game;
     /// let example_game = Game::new(); // Create a new game at the starting position
-    /// example_game.make_move("f2".to_string(), "f3".to_string()); // 1. f3
-    /// example_game.make_move("e7".to_string(), "e5".to_string()); // 1... e5
-    /// example_game.make_move("g2".to_string(), "g4".to_string()); // 2. g4
-    /// example_game.make_move("d8".to_string(), "h4".to_string()); // 2... Qh4#
+    /// assert_eq!(example_game.material_balance(), 0);
     /// ```
-    pub fn make_move(&mut self, source: String, target: String) -> bool { //Returns true if a valid move has been made
-        //Assuming both square and target are valid algebraic notation.
-        let source_coords = get_board_coords(source);
-        let target_coords = get_board_coords(target);
-        let available_moves =
-            get_available_moves_internal(self.board.clone(), self.board.active_player, false);
-        if available_moves.contains_key(&source_coords) &&
-             available_moves[&source_coords].contains(&target_coords) {
-                // hopefully error free way of checking if the move is a valid move as dictated by get_available_moves_internal()
-                println!("Source coords: {:?}, Target coords: {:?}", &source_coords, &target_coords);
-                self.board.move_piece(source_coords, target_coords);
-                //self.fen = generate_fen(self.board.clone());
-        } else {
-            return false;
-        }
-
-        if self.board.active_player == 'w' {
-            self.board.active_player = 'b';
-        } else if self.board.active_player == 'b' {
-            self.board.turn_counter += 1;
-            self.board.active_player = 'w';
-        }
-
-        self.checks = check_for_checks(&self.board);
-
-        self.update_game_status();
-
-        self.fen = generate_fen(&self.board);
-        
-        true
-    } // TODO Make move if move is available for the active player, then switch active player, then check for checks
-
-    fn update_game_status(&mut self) {
-        self.game_status = 0;
-        if self.board.halfmove_counter >= 100 {
-            self.game_status = 4;
-        }
-        //check for checkmate
-        if get_available_moves_internal(self.board.clone(), 'w', false).keys().len() == 0 {
-            if self.checks[0] {
-                self.game_status = 2;
-                return;
-            } else {
-                self.game_status = 3;
-                return;
-            }
-        }
-        if get_available_moves_internal(self.board.clone(), 'b', false)
-        .keys()
-        .len() == 0 {
-            if self.checks[1] {
-                self.game_status = 1;
-                return;
-            } else {
-                self.game_status = 3;
-                return;
-            }
-        }
+    pub fn material_balance(&self) -> i32 {
+        self.board.material_balance()
     }
     
 }
@@ -535,22 +1593,20 @@ impl fmt::Debug for Game {
 /// A struct to represent the chessboard.
 ///
 /// ## Attributes
-/// ```
+/// ```text
 /// board_state: Vec<Vec<char>>,
 /// // Represents the board. Pieces are represented by their FEN notation (capital for white, lowercase for black)
 /// // Blank squares are represented by "*"
 /// active_player: char, // 'w' or 'b'. Will produce undocumented behaviour if set to anything else.
 /// 
-/// castling_availability: String,
-/// //This value is in the power set of string "KQkq" and
-/// // represents which castling moves are available. When no castling moves are available, the value will be "-".
-/// // Castling not implemented yet.
+/// castling_rights: CastlingRights,
+/// // Represents which castling moves are still allowed (see docs for CastlingRights).
 /// en_passant_square: String,
 /// // This value represents whether or not en passant is available,
 /// // and if so, the square to which the capturing pawn will move. Otherwise,
 /// // the value will be "-".
 /// // En passant not implemented yet.
-/// halfmove_counter: i32,
+/// halfmove_counter: u32,
 /// // This counter increments for every move made without a capture
 /// // or a pawn move. Otherwise, it resets.
 ///  // When it reaches 100, the game is a draw.
@@ -564,16 +1620,14 @@ pub struct Board {
     // Represents the board. Pieces are represented by their FEN notation (capital for white, lowercase for black)
     // Blank squares are represented by "*"
     pub active_player: char, // "w" or "b"
-    pub castling_availability: String,
-    //This value is in the power set of string "KQkq" and
-    // represents which castling moves are available. When no castling moves are available, the value will be "-".
-    // Castling not implemented yet.
+    pub castling_rights: CastlingRights,
+    // Represents which castling moves are still allowed.
     pub en_passant_square: String,
     // This value represents whether or not en passant is available,
     // and if so, the square to which the capturing pawn will move. Otherwise,
     // the value will be "-".
     // En passant not implemented yet.
-    pub halfmove_counter: i32,
+    pub halfmove_counter: u32,
     // This counter increments for every move made without a capture
     // or a pawn move. Otherwise, it resets.
     // When it reaches 100, the game is a draw.
@@ -583,7 +1637,7 @@ pub struct Board {
     // Selected piece that a pawn promotes to. Defaults to q on each parse_fen call.
 }
 impl Board {
-    fn get_piece_movements(&mut self, coords: &Vec<i32>, piece: &char, color: &char) -> Vec<Vec<i32>> {
+    fn get_piece_movements(&mut self, coords: &[i32], piece: &char, color: &char) -> Vec<Vec<i32>> {
         
         let mut move_list = vec![];
         let x_pos = coords[1];
@@ -592,36 +1646,35 @@ impl Board {
                 // println!("{:?}", board);
         match piece.to_ascii_lowercase() {
             'p' => {
+                if (color == &'w' && y_pos == 0) || (color == &'b' && y_pos == 7) {
+                    return move_list; // a pawn on the last rank (only possible in a broken position) has nowhere to go
+                }
                 if color == &'w' {
                     // White pawns move in -y
                     if self.board_state[(y_pos-1) as usize][(x_pos) as usize] == '*' {
                         move_list.push(vec![y_pos-1, x_pos]);
                     }   
                     // pawns can take diagonally.
-                    if x_pos - 1 > 0 {
-                        if is_enemy_piece('w', self.board_state[(y_pos-1) as usize][(x_pos-1) as usize]) {
-                            move_list.push(vec![y_pos-1, x_pos-1]);
-                        }
+                    if x_pos > 0
+                        && is_enemy_piece('w', self.board_state[(y_pos-1) as usize][(x_pos-1) as usize]) {
+                        move_list.push(vec![y_pos-1, x_pos-1]);
                     }
-                    if x_pos + 1 < 8 {
-                        if is_enemy_piece('w', self.board_state[(y_pos-1) as usize][(x_pos+1) as usize]) {
-                            move_list.push(vec![y_pos-1, x_pos+1]);
-                        }
+                    if x_pos + 1 < 8
+                        && is_enemy_piece('w', self.board_state[(y_pos-1) as usize][(x_pos+1) as usize]) {
+                        move_list.push(vec![y_pos-1, x_pos+1]);
                     }
                     //Pawn First Move Advance
                     if (y_pos == 6) && self.board_state[(y_pos-2) as usize][(x_pos) as usize] == '*' && self.board_state[(y_pos-1) as usize][(x_pos) as usize] == '*'{
                         move_list.push(vec![y_pos-2, x_pos]);
                     }
                     if self.en_passant_square != "-" { // en passant is available
-                        if x_pos - 1 > 0 {
-                            if (get_board_coords(self.en_passant_square.clone()) == vec![y_pos-1, x_pos-1]) {
-                                move_list.push(vec![y_pos-1, x_pos-1]);
-                            }
+                        if x_pos > 0
+                            && get_board_coords(self.en_passant_square.clone()) == vec![y_pos-1, x_pos-1] {
+                            move_list.push(vec![y_pos-1, x_pos-1]);
                         }
-                        if x_pos + 1 < 8 {
-                            if (get_board_coords(self.en_passant_square.clone()) == vec![y_pos-1, x_pos+1]) {
-                                move_list.push(vec![y_pos-1, x_pos+1]);
-                            }
+                        if x_pos + 1 < 8
+                            && get_board_coords(self.en_passant_square.clone()) == vec![y_pos-1, x_pos+1] {
+                            move_list.push(vec![y_pos-1, x_pos+1]);
                         }
                     }
                 } else if color == &'b' {
@@ -630,30 +1683,26 @@ impl Board {
                         move_list.push(vec![y_pos+1, x_pos]);
                     }
                     // pawns can take diagonally.
-                    if x_pos - 1 > 0 {
-                        if is_enemy_piece('b', self.board_state[(y_pos+1) as usize][(x_pos-1) as usize]) {
-                            move_list.push(vec![y_pos+1, x_pos-1]);
-                        }
+                    if x_pos > 0
+                        && is_enemy_piece('b', self.board_state[(y_pos+1) as usize][(x_pos-1) as usize]) {
+                        move_list.push(vec![y_pos+1, x_pos-1]);
                     }
-                    if x_pos + 1 < 8 {
-                        if is_enemy_piece('b', self.board_state[(y_pos+1) as usize][(x_pos+1) as usize]) {
-                            move_list.push(vec![y_pos+1, x_pos+1]);
-                        }
+                    if x_pos + 1 < 8
+                        && is_enemy_piece('b', self.board_state[(y_pos+1) as usize][(x_pos+1) as usize]) {
+                        move_list.push(vec![y_pos+1, x_pos+1]);
                     }
                     //Pawn First Move Advance
                     if (y_pos == 1) && self.board_state[(y_pos+2) as usize][(x_pos) as usize] == '*' && self.board_state[(y_pos+1) as usize][(x_pos) as usize] == '*' {
                         move_list.push(vec![y_pos+2, x_pos]);
                     }
                     if self.en_passant_square != "-" { // en passant is available
-                        if x_pos - 1 > 0 {
-                            if (get_board_coords(self.en_passant_square.clone()) == vec![y_pos+1, x_pos-1]) {
-                                move_list.push(vec![y_pos+1, x_pos-1]);
-                            }
+                        if x_pos > 0
+                            && get_board_coords(self.en_passant_square.clone()) == vec![y_pos+1, x_pos-1] {
+                            move_list.push(vec![y_pos+1, x_pos-1]);
                         }
-                        if x_pos + 1 < 8 {
-                            if (get_board_coords(self.en_passant_square.clone()) == vec![y_pos+1, x_pos+1]) {
-                                move_list.push(vec![y_pos+1, x_pos+1]);
-                            }
+                        if x_pos + 1 < 8
+                            && get_board_coords(self.en_passant_square.clone()) == vec![y_pos+1, x_pos+1] {
+                            move_list.push(vec![y_pos+1, x_pos+1]);
                         }
                     }
                 }
@@ -661,23 +1710,20 @@ impl Board {
             }, // TODO The pawn moves straight forward (y+1) if it's not a capture, moves diagonally ([x+1, y+1], [x+1, y-1]) if it's a capture, and can en passant. On its first move, it can move two squares forward (y+2).
             'b' => {
                 for coordinate_modifier in 1..min(8-y_pos, 8-x_pos) { //Iterates until the x or y coordinate reaches 7, whichever happens first
-                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase() {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase())
                     {
                         break;
                     }
 
                     move_list.push(vec![y_pos + coordinate_modifier, x_pos + coordinate_modifier]);
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase()
-                    {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase())
                     {
                         break;
                     }
@@ -685,23 +1731,20 @@ impl Board {
                 } // Checks in +y, +x for available moves
 
                 for coordinate_modifier in 1..min(8-y_pos, x_pos+1) {
-                                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase() {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase()
+                                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase())
                     {
                         break;
                     }
 
                     move_list.push(vec![y_pos + coordinate_modifier, x_pos - coordinate_modifier]);
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase()
-                    {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos + coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase())
                     {
                         break;
                     }
@@ -709,46 +1752,40 @@ impl Board {
 
                 for coordinate_modifier in 1..min(x_pos+1, y_pos+1) {
                     
-                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase() {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase())
                     {
                         break;
                     }
 
                     move_list.push(vec![y_pos - coordinate_modifier, x_pos - coordinate_modifier]);
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase()
-                    {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos - coordinate_modifier) as usize].is_ascii_uppercase())
                     {
                         break;
                     }
                 } // checks in -y, -x for available moves
                 for coordinate_modifier in 1..min(y_pos+1, 8-x_pos) {
                     
-                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase() {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase())
                     {
                         break;
                     }
 
                     move_list.push(vec![y_pos - coordinate_modifier, x_pos + coordinate_modifier]);
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase()
-                    {
-                        break;
-                    } else if color == &'b'
-                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[(y_pos - coordinate_modifier) as usize][(x_pos + coordinate_modifier) as usize].is_ascii_uppercase())
                     {
                         break;
                     }
@@ -756,54 +1793,45 @@ impl Board {
                 move_list
             }, // The bishop moves along diagonals [+x, +y], [-x, +y], [-x, -y] and [+x, -y], until it hits a piece.
             'n' => {
-                if y_pos + 2 < 8 && x_pos + 1 < 8 { 
-                    if (is_enemy_piece(*color, self.board_state[(y_pos+2) as usize][(x_pos+1) as usize]) ||
-                    (self.board_state[(y_pos+2) as usize][(x_pos+1) as usize] == '*'))  {
-                        move_list.push(vec![y_pos+2, x_pos+1]);
-                    
-                    }
+                if y_pos + 2 < 8 && x_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+2) as usize][(x_pos+1) as usize]) ||
+                    self.board_state[(y_pos+2) as usize][(x_pos+1) as usize] == '*') {
+                    move_list.push(vec![y_pos+2, x_pos+1]);
                 }
-                if y_pos + 2 < 8 && x_pos - 1 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos+2) as usize][(x_pos-1) as usize]) ||
-                    (self.board_state[(y_pos+2) as usize][(x_pos-1) as usize] == '*'){
-                        move_list.push(vec![y_pos+2, x_pos-1]);
-                    }
+                if y_pos + 2 < 8 && x_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+2) as usize][(x_pos-1) as usize]) ||
+                    self.board_state[(y_pos+2) as usize][(x_pos-1) as usize] == '*') {
+                    move_list.push(vec![y_pos+2, x_pos-1]);
                 }
-                if y_pos - 2 >= 0 && x_pos + 1 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-2) as usize][(x_pos+1) as usize]) ||
-                    (self.board_state[(y_pos-2) as usize][(x_pos+1) as usize] == '*'){
-                        move_list.push(vec![y_pos-2, x_pos+1]);
-                    }
+                if y_pos - 2 >= 0 && x_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-2) as usize][(x_pos+1) as usize]) ||
+                    self.board_state[(y_pos-2) as usize][(x_pos+1) as usize] == '*') {
+                    move_list.push(vec![y_pos-2, x_pos+1]);
                 }
-                if y_pos - 2 >= 0 && x_pos - 1 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-2) as usize][(x_pos-1) as usize]) ||
-                        (self.board_state[(y_pos-2) as usize][(x_pos-1) as usize] == '*'){
-                        move_list.push(vec![y_pos-2, x_pos-1]);
-                    }
+                if y_pos - 2 >= 0 && x_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-2) as usize][(x_pos-1) as usize]) ||
+                    self.board_state[(y_pos-2) as usize][(x_pos-1) as usize] == '*') {
+                    move_list.push(vec![y_pos-2, x_pos-1]);
                 }
-                if y_pos + 1 < 8 && x_pos + 2 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos+2) as usize]) ||
-                    (self.board_state[(y_pos+1) as usize][(x_pos+2) as usize] == '*'){
-                        move_list.push(vec![y_pos+1, x_pos+2]);
-                    }
+                if y_pos + 1 < 8 && x_pos + 2 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos+2) as usize]) ||
+                    self.board_state[(y_pos+1) as usize][(x_pos+2) as usize] == '*') {
+                    move_list.push(vec![y_pos+1, x_pos+2]);
                 }
-                if y_pos - 1 >= 0 && x_pos + 2 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos+2) as usize]) ||
-                    (self.board_state[(y_pos-1) as usize][(x_pos+2) as usize] == '*'){
-                        move_list.push(vec![y_pos-1, x_pos+2]);
-                    }
+                if y_pos >= 1 && x_pos + 2 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos+2) as usize]) ||
+                    self.board_state[(y_pos-1) as usize][(x_pos+2) as usize] == '*') {
+                    move_list.push(vec![y_pos-1, x_pos+2]);
                 }
-                if y_pos + 1 < 8 && x_pos - 2 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos-2) as usize]) ||
-                    (self.board_state[(y_pos+1) as usize][(x_pos-2) as usize] == '*'){
-                        move_list.push(vec![y_pos+1, x_pos-2]);
-                    }
+                if y_pos + 1 < 8 && x_pos - 2 >= 0
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos-2) as usize]) ||
+                    self.board_state[(y_pos+1) as usize][(x_pos-2) as usize] == '*') {
+                    move_list.push(vec![y_pos+1, x_pos-2]);
                 }
-                if y_pos - 1 >= 0 && x_pos - 2 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos-2) as usize]) ||
-                    (self.board_state[(y_pos-1) as usize][(x_pos-2) as usize] == '*'){
-                        move_list.push(vec![y_pos-1, x_pos-2]);
-                    }
+                if y_pos >= 1 && x_pos - 2 >= 0
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos-2) as usize]) ||
+                    self.board_state[(y_pos-1) as usize][(x_pos-2) as usize] == '*') {
+                    move_list.push(vec![y_pos-1, x_pos-2]);
                 }
                 move_list
             }, // the knight teleports to specific relative coordinates [x+-2, y+-1], [x+-1, y+-2]
@@ -846,57 +1874,45 @@ impl Board {
 
                     move_list.push(vec![y_pos, new_x]); // adds the current coordinate to the move list
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[y_pos as usize][new_x as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[y_pos as usize][new_x as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[y_pos as usize][new_x as usize].is_ascii_uppercase())
                     {
                         //println!("Loop break detected: collision with enemy piece at +x");
                         break '_loop;
-                    } else if color == &'b'
-                        && self.board_state[y_pos as usize][new_x as usize].is_ascii_uppercase()
-                    {
-                        // println!("Loop break detected: collision with enemy piece at +x");
-                        break '_loop;
                     }
                 } // checks for available moves in +x until we hit a friendly piece (exclusive) or until we hit an enemy piece (inclusive)
 
                 '_loop: for new_y in { 0..y_pos }.rev() {
                     //println!("x coord: {}, y coord: {}, content: {}", x_pos, new_y, board.board_state[new_y as usize][x_pos as usize]);
-                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase())
                     {
                         //println!("Loop break detected: collision with friendly piece at -y");
                         break '_loop;
-                    } else if color == &'b'
-                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase()
-                    {
-                        // println!("Loop break detected: collision with friendly piece at -y");
-                        break '_loop;
                     }
 
                     move_list.push(vec![new_y, x_pos]); // adds the current coordinate to the move list (doesn't proc if the loop is broken in the block before)
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase())
                     {
                         // println!("Loop break detected: collision with enemy piece at -y");
                         break '_loop;
-                    } else if color == &'b'
-                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase()
-                    {
-                        //println!("Loop break detected: collision with enemy piece at -y");
-                        break '_loop;
                     }
                 } // checks for available moves in -y until we hit a friendly piece (exclusive) or until we hit an enemy piece (inclusive)
 
                 '_loop: for new_y in (y_pos + 1)..8 {
                     //println!("x coord: {}, y coord: {}, content: {}", x_pos, new_y, board.board_state[new_y as usize][x_pos as usize]);
-                    if color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
-                    self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase()
-                    {
-                        //println!("Loop break detected: collision with friendly piece at +y");
-                        break  '_loop;
-                    } else if color == &'b'
-                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase()
+                    if (color == &'w' && // breaks at friendly pieces before adding the associated coordinate to the piece's move list
+                    self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase())
+                    || (color == &'b'
+                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase())
                     {
                         //println!("Loop break detected: collision with friendly piece at +y");
                         break '_loop;
@@ -904,13 +1920,10 @@ impl Board {
 
                     move_list.push(vec![new_y, x_pos]); // adds the current coordinate to the move list (doesn't proc if the loop is broken in the block before)
 
-                    if color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
-                    self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase()
-                    {
-                        //println!("Loop break detected: collision with enemy piece at +y");
-                        break  '_loop;
-                    } else if color == &'b'
-                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase()
+                    if (color == &'w' && // breaks at enemy pieces after adding the associated coordinate to the piece's move list
+                    self.board_state[new_y as usize][x_pos as usize].is_ascii_lowercase())
+                    || (color == &'b'
+                        && self.board_state[new_y as usize][x_pos as usize].is_ascii_uppercase())
                     {
                         //println!("Loop break detected: collision with enemy piece at +y");
                         break '_loop;
@@ -929,76 +1942,59 @@ impl Board {
             move_list
             }, //the queen moves in rows and cols [+-x], [+-y], and along diagonals [+x, +y], [-x, +y], [-x, -y] and [+x, -y], until it hits a piece.
             'k' => {
-                if y_pos + 1 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos) as usize]) ||
-                    (self.board_state[(y_pos+1) as usize][(x_pos) as usize] == '*')  {
-                        move_list.push(vec![y_pos+1, x_pos]);
-                    }
+                if y_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos) as usize]) ||
+                    self.board_state[(y_pos+1) as usize][(x_pos) as usize] == '*') {
+                    move_list.push(vec![y_pos+1, x_pos]);
                 }
-                if y_pos -1 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos) as usize]) ||
-                    (self.board_state[(y_pos-1) as usize][(x_pos) as usize] == '*'){
-                        move_list.push(vec![y_pos-1, x_pos]);
-                    }
+                if y_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos) as usize]) ||
+                    self.board_state[(y_pos-1) as usize][(x_pos) as usize] == '*') {
+                    move_list.push(vec![y_pos-1, x_pos]);
                 }
 
-                if x_pos + 1 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos) as usize][(x_pos+1) as usize]) ||
-                    (self.board_state[(y_pos) as usize][(x_pos+1) as usize] == '*'){
-                        move_list.push(vec![y_pos, x_pos+1]);
-                    }
+                if x_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos) as usize][(x_pos+1) as usize]) ||
+                    self.board_state[(y_pos) as usize][(x_pos+1) as usize] == '*') {
+                    move_list.push(vec![y_pos, x_pos+1]);
                 }
-                if x_pos - 1 >= 0 { 
-                    if is_enemy_piece(*color, self.board_state[(y_pos) as usize][(x_pos-1) as usize]) ||
-                    (self.board_state[(y_pos) as usize][(x_pos-1) as usize] == '*'){
-                        move_list.push(vec![y_pos, x_pos-1]);
-                    }
+                if x_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos) as usize][(x_pos-1) as usize]) ||
+                    self.board_state[(y_pos) as usize][(x_pos-1) as usize] == '*') {
+                    move_list.push(vec![y_pos, x_pos-1]);
                 }
-                if y_pos + 1 < 8 && x_pos + 1 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos+1) as usize]) ||
-                    (self.board_state[(y_pos+1) as usize][(x_pos+1) as usize] == '*'){
-                        move_list.push(vec![y_pos+1, x_pos+1]);
-                    }
+                if y_pos + 1 < 8 && x_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos+1) as usize]) ||
+                    self.board_state[(y_pos+1) as usize][(x_pos+1) as usize] == '*') {
+                    move_list.push(vec![y_pos+1, x_pos+1]);
                 }
-                if y_pos + 1 < 8 && x_pos - 1 >= 0 { 
-                    if is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos-1) as usize]) ||
-                    (self.board_state[(y_pos+1) as usize][(x_pos-1) as usize] == '*'){
-                        move_list.push(vec![y_pos+1, x_pos-1]);
-                    }
+                if y_pos + 1 < 8 && x_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos+1) as usize][(x_pos-1) as usize]) ||
+                    self.board_state[(y_pos+1) as usize][(x_pos-1) as usize] == '*') {
+                    move_list.push(vec![y_pos+1, x_pos-1]);
                 }
-                if y_pos - 1 >= 0 && x_pos + 1 < 8 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos+1) as usize]) ||
-                    (self.board_state[(y_pos-1) as usize][(x_pos+1) as usize] == '*'){
-                        move_list.push(vec![y_pos-1, x_pos+1]);
-                    }
+                if y_pos >= 1 && x_pos + 1 < 8
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos+1) as usize]) ||
+                    self.board_state[(y_pos-1) as usize][(x_pos+1) as usize] == '*') {
+                    move_list.push(vec![y_pos-1, x_pos+1]);
                 }
-                if y_pos - 1 >= 0 && x_pos -1 >= 0 {
-                    if is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos-1) as usize]) ||
-                    (self.board_state[(y_pos-1) as usize][(x_pos-1) as usize] == '*'){
-                        move_list.push(vec![y_pos-1, x_pos-1]);
-                    }
+                if y_pos >= 1 && x_pos >= 1
+                    && (is_enemy_piece(*color, self.board_state[(y_pos-1) as usize][(x_pos-1) as usize]) ||
+                    self.board_state[(y_pos-1) as usize][(x_pos-1) as usize] == '*') {
+                    move_list.push(vec![y_pos-1, x_pos-1]);
                 }
-                //println!("{} King's castling square 2: {}", color, self.board_state[y_pos as usize][(x_pos+2) as usize]);
-                if color == &'w' {
-                    if self.castling_availability.contains('K') && self.board_state[y_pos as usize][(x_pos+1) as usize] == '*' && self.board_state[y_pos as usize][(x_pos+2) as usize] == '*' {
-                        move_list.push(vec![y_pos, x_pos+2])
-                    }
-                    if self.castling_availability.contains('Q') && self.board_state[y_pos as usize][(x_pos-1) as usize] == '*' && self.board_state[y_pos as usize][(x_pos-2) as usize] == '*' && self.board_state[y_pos as usize][(x_pos-3) as usize] == '*' {
-                        move_list.push(vec![y_pos, x_pos-2])
-                    }
-                } else
-                if color == &'b' {
-                    if self.castling_availability.contains('k') && self.board_state[y_pos as usize][(x_pos+1) as usize] == '*' && self.board_state[y_pos as usize][(x_pos+2) as usize] == '*' {
-                        move_list.push(vec![y_pos, x_pos+2])
-                    }
-                    if self.castling_availability.contains('q') && self.board_state[y_pos as usize][(x_pos-1) as usize] == '*' && self.board_state[y_pos as usize][(x_pos-2) as usize] == '*' {
-                        move_list.push(vec![y_pos, x_pos-2])
+                let rights = if color == &'w' { ['K', 'Q'] } else { ['k', 'q'] };
+                for right in rights {
+                    if let Some(castling) = CastlingMove::for_right(self, right)
+                        && castling.king.as_slice() == coords // castling rights without the king on its home rank (only possible in a broken position) can't be used
+                        && castling.squares_to_clear().iter().all(|square| get_piece(self, square) == '*') {
+                        move_list.push(castling.encoded_target().to_vec()) // the king castles with the rook of each right, over empty squares
                     }
                 }
                 move_list
             }, // the king teleports to surrounding squares. [x+-1, y+-1].
             '*' => vec![],  // the empty square can't move.
-            _ => panic!("By God! A non-filled square on board! PANIC!"),
+            _ => vec![], // neither can anything that isn't a piece
         }
     } //For a given piece on a given coordinate, return a Vec of coordinates that this piece can move to. Does not process game flag statuses.
     // giant match-case statement which returns a set of moves for each piece
@@ -1012,43 +2008,33 @@ impl Board {
         let mut increment_halfmove_counter = true;
         let piece = get_piece(self, &source_coords);
 
-        //Castling counter updates: Rook move => that side castling is disabled
-        if piece == 'R' && source_coords[1] == 0 {
-            self.castling_availability = self.castling_availability.replace(&'Q'.to_string(), "");
-        } else if piece == 'R' && source_coords[1] == 7 {
-            self.castling_availability = self.castling_availability.replace(&'K'.to_string(), "");
-        } else if piece == 'r' && source_coords[1] == 0 {
-            self.castling_availability = self.castling_availability.replace(&'q'.to_string(), "");
-        } else if piece == 'r' && source_coords[1] == 7 {
-            self.castling_availability = self.castling_availability.replace(&'k'.to_string(), "");
+        // Special case: Castling, where the king may land on its own rook's square, or not move at all in Chess960
+        if let Some(castling) = CastlingMove::from_move(self, &source_coords, &target_coords) {
+            castling.play(self);
+            return;
         }
 
-        if piece == 'K' {
-            self.castling_availability = self.castling_availability.replace(&'Q'.to_string(), "");
-            self.castling_availability = self.castling_availability.replace(&'K'.to_string(), "");
-        } else if piece == 'k' {
-            self.castling_availability = self.castling_availability.replace(&'q'.to_string(), "");
-            self.castling_availability = self.castling_availability.replace(&'k'.to_string(), "");
-        }
-        if self.castling_availability.is_empty() {
-            self.castling_availability = "-".to_string();
-        }
+        //Castling rights updates: a king moving, or a rook leaving its starting square or being captured on it, disables that castling
+        self.castling_rights.revoke_for_square(&source_coords, piece);
+        self.castling_rights.revoke_for_square(&target_coords, get_piece(self, &target_coords));
 
 
         if get_piece(self, &target_coords) != '*' { // target square isn't empty => Capture
             increment_halfmove_counter = false;
         }
         // OR
+        let is_en_passant = piece.eq_ignore_ascii_case(&'p')
+            && self.en_passant_square != "-"
+            && target_coords == get_board_coords(self.en_passant_square.clone());
         if piece.eq_ignore_ascii_case(&'p')// the piece moved is a pawn
         {
             increment_halfmove_counter = false;
-            //Set en passant counter if it's a first turn advance
-            if target_coords[0] == source_coords[0] + 2 {
-                self.en_passant_square = get_algebraic_notation(vec![source_coords[0]+1, source_coords[1]]);
-            }
-            if target_coords[0] == source_coords[0] - 2 {
-                self.en_passant_square = get_algebraic_notation(vec![source_coords[0]-1, source_coords[1]]);
-            }
+        }
+        //Set en passant square if it's a first turn advance. Any other move clears it, so it only lasts one turn
+        if piece.eq_ignore_ascii_case(&'p') && target_coords[0] == source_coords[0] + 2 {
+            self.en_passant_square = get_algebraic_notation(vec![source_coords[0]+1, source_coords[1]]);
+        } else if piece.eq_ignore_ascii_case(&'p') && target_coords[0] == source_coords[0] - 2 {
+            self.en_passant_square = get_algebraic_notation(vec![source_coords[0]-1, source_coords[1]]);
         } else {
             self.en_passant_square = "-".to_string();
         }
@@ -1069,40 +2055,32 @@ impl Board {
             self.set_piece(&target_coords, piece);
         }
         // Special case: En Passant
-        if self.en_passant_square != "-".to_string() {
-            if target_coords == get_board_coords(self.en_passant_square.clone()) {
-                match self.active_player {
-                    'w' => self.set_piece(&vec![target_coords[0]+1, target_coords[1]], '*'),
-                    'b' => self.set_piece(&vec![target_coords[0]-1, target_coords[1]], '*'),
-                    _ => panic!("ACTIVE PLAYER DOES NOT EXIST")
-                }
+        if is_en_passant {
+            match self.active_player {
+                'w' => self.set_piece(&[target_coords[0]+1, target_coords[1]], '*'),
+                'b' => self.set_piece(&[target_coords[0]-1, target_coords[1]], '*'),
+                _ => panic!("ACTIVE PLAYER DOES NOT EXIST")
             }
         }
+    } // Moves a piece to a target square.
 
-        // Special case: Castling
-        if piece == 'K' && source_coords[1]-target_coords[1] == 2 {
-            self.set_piece(&target_coords, piece);
-            self.set_piece(&vec![7,0], '*');
-            self.set_piece(&vec![target_coords[0], target_coords[1]+1], 'R');
-        }
-        if piece == 'K' && source_coords[1]-target_coords[1] == -2 {
-            self.set_piece(&target_coords, piece);
-            self.set_piece(&vec![7,7], '*');
-            self.set_piece(&vec![target_coords[0], target_coords[1]-1], 'R');
-        }
-        if piece == 'k' && source_coords[1]-target_coords[1] == 2 {
-            self.set_piece(&target_coords, piece);
-            self.set_piece(&vec![0,0], '*');
-            self.set_piece(&vec![target_coords[0], target_coords[1]+1], 'R');
+    fn play_move(&mut self, source: Vec<i32>, target: Vec<i32>, promotion: Option<char>) {
+        let previous_selection = self.promotion_selection;
+        if let Some(piece) = promotion {
+            self.promotion_selection = piece;
         }
-        if piece == 'K' && source_coords[1]-target_coords[1] == -2 {
-            self.set_piece(&target_coords, piece);
-            self.set_piece(&vec![7,7], '*');
-            self.set_piece(&vec![target_coords[0], target_coords[1]-1], 'R');
+        self.move_piece(source, target);
+        self.promotion_selection = previous_selection;
+
+        if self.active_player == 'w' {
+            self.active_player = 'b';
+        } else if self.active_player == 'b' {
+            self.turn_counter += 1;
+            self.active_player = 'w';
         }
-    } // Moves a piece to a target square.
+    } // Moves a piece, then hands the turn to the other player. Promotes to the given piece, or to promotion_selection if None.
 
-    fn set_piece(&mut self, coords: &Vec<i32>, piece: char) {
+    fn set_piece(&mut self, coords: &[i32], piece: char) {
         self.board_state[coords[0] as usize][coords[1] as usize] = piece;
     } // changes the given board coordinate to the given piece. 
 
@@ -1110,7 +2088,7 @@ impl Board {
     /// A function to set the piece that a pawn promotes to.
     ///
     /// ## Arguments
-    /// ```
+    /// ```text
     /// piece: char // A valid FEN chess character.
     /// 
     /// ```
@@ -1120,8 +2098,9 @@ impl Board {
     /// ## Example
     ///
     /// ```
-    /// let example_game = Game::new(); // Create a new game at the starting position
-    /// example_game.board.set_promotion('n') // When a pawn promotes, promote it to a knight.
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.board.set_promotion('n'); // When a pawn promotes, promote it to a knight.
     /// ```
     pub fn set_promotion(&mut self, piece: char) -> bool { //Returns true if attempting to set promotion to valid piece.
         if ['b', 'n', 'r', 'q'].contains(&piece.to_ascii_lowercase()) {
@@ -1130,13 +2109,90 @@ impl Board {
         } false
     }
 }
+#[derive(Clone, PartialEq, Eq, Hash)]
+/// A struct to represent a single move.
+///
+/// ## Attributes
+/// ```text
+/// source: String, // The square where the piece to move stands, in algebraic notation.
+/// target: String, // The square to which to move the piece, in algebraic notation.
+/// promotion: Option<char>, // The piece a pawn promotes to (lowercase 'q', 'r', 'b' or 'n'), or None if the move is not a promotion.
+/// ```
+pub struct Move {
+    pub source: String,
+    pub target: String,
+    pub promotion: Option<char>,
+}
+impl Move {
+    /// A function to parse a move in long algebraic (UCI) notation, e.g. "e2e4" or "e7e8q".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// uci_move: &str, // The move in long algebraic notation.
+    /// 
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Move) if the string is well-formed, None otherwise. It does not check whether the move is legal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Move;
+    /// let example_move = Move::from_uci("e7e8q").unwrap();
+    /// assert_eq!(example_move.target, "e8");
+    /// assert_eq!(example_move.promotion, Some('q'));
+    /// ```
+    pub fn from_uci(uci_move: &str) -> Option<Move> {
+        let chars = uci_move.chars().collect::<Vec<char>>();
+        if chars.len() != 4 && chars.len() != 5 {
+            return None;
+        }
+        for square in [&chars[0..2], &chars[2..4]] {
+            if !('a'..='h').contains(&square[0]) || !('1'..='8').contains(&square[1]) {
+                return None;
+            }
+        }
+        let promotion = match chars.get(4) {
+            Some(piece) if ['q', 'r', 'b', 'n'].contains(&piece.to_ascii_lowercase()) => Some(piece.to_ascii_lowercase()),
+            Some(_) => return None,
+            None => None,
+        };
+        Some(Move {
+            source: chars[0..2].iter().collect(),
+            target: chars[2..4].iter().collect(),
+            promotion,
+        })
+    }
+
+    /// A function to write the move in long algebraic (UCI) notation. Inverse function for from_uci().
+    ///
+    /// ## Returns
+    /// This function returns a String such as "e2e4" or "e7e8q".
+    pub fn to_uci(&self) -> String {
+        match self.promotion {
+            Some(promotion) => format!("{}{}{}", self.source, self.target, promotion),
+            None => format!("{}{}", self.source, self.target),
+        }
+    }
+}
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut output = "Current board (top left corner is a8)".to_string();
         for i in &self.board_state {
             output = format!("{} \n {:?}", output, i);
         } // beautify the printed Vector
-        output = format!("{} \n Active Player: {}, \n Castling Availability: {}, \n En Passant availability: {}, \n Halfmove counter: {}, \n Turn count: {}", output, self.active_player, self.castling_availability, self.en_passant_square, self.halfmove_counter, self.turn_counter);
+        output = format!("{} \n Active Player: {}, \n Castling Availability: {}, \n En Passant availability: {}, \n Halfmove counter: {}, \n Turn count: {}", output, self.active_player, self.castling_rights, self.en_passant_square, self.halfmove_counter, self.turn_counter);
         write!(f, "{}", output)
     }
 }
@@ -1224,11 +2280,80 @@ mod tests {
     #[test]
     fn test_game_cases() {
         let mut test_position = Game::new_from_fen("6k1/5p1p/8/6p1/2P1p1P1/4P2P/1r6/q1K5 w - - 8 47".to_string());
-        let mut test_position_2 = Game::new_from_fen("6k1/5p1p/8/6p1/2P1p1P1/4P2P/1r6/2K5 w - - 100 47".to_string());
+        let test_position_2 = Game::new_from_fen("6k1/5p1p/8/6p1/2P1p1P1/4P2P/1r6/2K5 w - - 100 47".to_string());
         test_position.update_game_status();
         println!("Test position 2 halfmove counter: {}", test_position_2.board.halfmove_counter);
-        debug_assert_eq!(test_position.game_status, 2);
-        debug_assert_eq!(test_position_2.game_status, 4);
+        debug_assert_eq!(test_position.game_status, GameStatus::BlackWinsByCheckmate);
+        debug_assert_eq!(test_position_2.game_status, GameStatus::FiftyMoveDraw);
+    }
+    #[test]
+    fn test_status_predicates() {
+        let stalemate = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string());
+        assert!(stalemate.is_stalemate() && stalemate.is_draw() && stalemate.is_game_over());
+        assert!(!stalemate.is_checkmate());
+        let white_mated = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert_eq!(white_mated.game_status, GameStatus::BlackWinsByCheckmate);
+        assert!(white_mated.is_checkmate() && white_mated.is_game_over());
+        assert!(!white_mated.is_draw() && !white_mated.is_stalemate());
+        let black_mated = Game::new_from_fen("r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23".to_string());
+        assert_eq!(black_mated.game_status, GameStatus::WhiteWinsByCheckmate);
+        assert!(black_mated.is_checkmate() && black_mated.is_game_over());
+        let fifty_moves = Game::new_from_fen("6k1/5p1p/8/6p1/2P1p1P1/4P2P/1r6/2K5 w - - 100 47".to_string());
+        assert!(fifty_moves.is_draw() && fifty_moves.is_game_over());
+        assert!(!fifty_moves.is_checkmate() && !fifty_moves.is_stalemate());
+        let start = Game::new();
+        assert!(!start.is_checkmate() && !start.is_stalemate() && !start.is_draw() && !start.is_game_over());
+    }
+    #[test]
+    fn test_result_strings() {
+        let expected = [
+            (GameStatus::InProgress, None, "*"),
+            (GameStatus::WhiteWinsByCheckmate, Some(Color::White), "1-0"),
+            (GameStatus::BlackWinsByCheckmate, Some(Color::Black), "0-1"),
+            (GameStatus::Stalemate, None, "1/2-1/2"),
+            (GameStatus::FiftyMoveDraw, None, "1/2-1/2"),
+        ];
+        for (status, winner, result) in expected {
+            assert_eq!(status.winner(), winner);
+            assert_eq!(status.result_string(), result);
+        }
+        let mut test_game = Game::new();
+        for uci_move in ["f2f3", "e7e5", "g2g4"] {
+            test_game.make_uci_move(uci_move);
+            assert_eq!(test_game.result_string(), "*");
+        }
+        test_game.make_uci_move("d8h4");
+        assert_eq!(test_game.winner(), Some(Color::Black));
+        assert_eq!(test_game.result_string(), "0-1");
+    }
+    #[test]
+    fn test_move_counters() {
+        let mut test_game = Game::new();
+        assert_eq!((test_game.halfmove_clock(), test_game.fullmove_number(), test_game.ply()), (0, 1, 0));
+        for uci_move in ["g1f3", "g8f6", "b1c3", "b8c6"] {
+            assert!(test_game.make_uci_move(uci_move));
+        }
+        assert_eq!((test_game.halfmove_clock(), test_game.fullmove_number(), test_game.ply()), (4, 3, 4));
+        assert!(test_game.make_uci_move("c3d5"));
+        assert!(test_game.make_uci_move("f6d5")); // The capture resets the clock
+        assert_eq!((test_game.halfmove_clock(), test_game.fullmove_number(), test_game.ply()), (0, 4, 6));
+        assert!(test_game.make_uci_move("f3d4"));
+        assert_eq!((test_game.halfmove_clock(), test_game.fullmove_number(), test_game.ply()), (1, 4, 7));
+        assert_eq!(test_game.history[4].to_uci(), "c3d5");
+
+        let round_trip = Game::new_from_fen(test_game.fen.clone());
+        assert_eq!(round_trip.fen, test_game.fen);
+        assert_eq!(round_trip.halfmove_clock(), 1);
+        assert_eq!(round_trip.fullmove_number(), 4);
+        assert_eq!(round_trip.ply(), 0);
+        assert_eq!(round_trip.start_fen, test_game.fen);
+    }
+    #[test]
+    fn test_history_records_promotions() {
+        let mut test_game = Game::new_from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1".to_string());
+        test_game.board.set_promotion('r');
+        assert!(test_game.make_move("a7".to_string(), "a8".to_string()));
+        assert_eq!(test_game.history, vec![Move::from_uci("a7a8r").unwrap()]);
     }
     #[test]
     fn test_board_coord_conversion() {
@@ -1257,6 +2382,101 @@ mod tests {
         debug_assert!(result);
     }
     #[test]
+    fn test_castling_gestures() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        for (gesture, castling) in [("e1h1", "e1g1"), ("e1a1", "e1c1")] {
+            let mut by_gesture = Game::new_from_fen(fen.to_string());
+            let mut by_king_move = Game::new_from_fen(fen.to_string());
+            assert!(by_gesture.make_uci_move(gesture), "{}", gesture);
+            assert!(by_king_move.make_uci_move(castling));
+            assert_eq!(by_gesture.fen, by_king_move.fen);
+            assert_eq!(by_gesture.history, by_king_move.history); // Recorded as the two-square move
+            for (gesture, castling) in [("e8h8", "e8g8"), ("e8a8", "e8c8")] {
+                let (mut by_gesture, mut by_king_move) = (by_gesture.clone(), by_king_move.clone());
+                assert_eq!(by_gesture.make_uci_move(gesture), by_king_move.make_uci_move(castling), "{}", gesture); // After O-O, f8 is attacked
+                assert_eq!(by_gesture.fen, by_king_move.fen);
+            }
+        }
+        let mut without_rights = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1".to_string());
+        assert!(!without_rights.make_move("e1".to_string(), "h1".to_string()));
+        let mut blocked = Game::new_from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1".to_string());
+        assert!(!blocked.make_move("e1".to_string(), "a1".to_string()));
+        let mut attacked = Game::new_from_fen("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1".to_string());
+        assert!(!attacked.make_move("e1".to_string(), "h1".to_string())); // In check
+        assert!(!attacked.make_move("e8".to_string(), "h8".to_string())); // Not Black's turn
+    }
+    #[test]
+    fn test_pawns_on_back_ranks() {
+        let test_game = Game::new_from_fen("P3k3/8/8/8/8/8/8/4K2p w - - 0 1".to_string());
+        assert!(test_game.legal_moves().iter().all(|legal_move| legal_move.source == "e1"));
+        assert!(!test_game.board.is_square_attacked(Square::from_algebraic("b7").unwrap(), 'w')); // The a8 pawn attacks nothing
+        let mut black_to_move = Game::new_from_fen("P3k3/8/8/8/8/8/8/4K2p b - - 0 1".to_string());
+        assert!(black_to_move.legal_moves().iter().all(|legal_move| legal_move.source == "e8"));
+        assert!(black_to_move.make_uci_move("e8d7"));
+        assert!(Game::try_from_fen("P3k3/8/8/8/8/8/8/4K2p w - - 0 1").is_err());
+    }
+    #[test]
+    fn test_promotion_chooser() {
+        let calls = std::cell::Cell::new(0);
+        let mut test_game = Game::new_from_fen("1r2k3/P7/8/8/8/8/4P3/4K3 w - - 0 1".to_string());
+        let choose_rook = |game: &Game| {
+            calls.set(calls.get() + 1);
+            assert_eq!(game.board.active_player, 'w'); // The position before the move
+            PieceKind::Rook
+        };
+        assert!(test_game.make_move_with("e2".to_string(), "e4".to_string(), choose_rook));
+        assert!(!test_game.make_move_with("a7".to_string(), "a8".to_string(), choose_rook)); // Not White's turn
+        assert!(test_game.make_move_with("b8".to_string(), "b7".to_string(), choose_rook));
+        assert_eq!(calls.get(), 0);
+        assert!(!test_game.make_move_with("a7".to_string(), "b8".to_string(), choose_rook)); // Nothing to capture on b8
+        assert!(!test_game.make_move_with("a7".to_string(), "a8".to_string(), |_game| PieceKind::King));
+        assert_eq!(test_game.history.len(), 2);
+        assert!(test_game.make_move_with("a7".to_string(), "a8".to_string(), choose_rook));
+        assert_eq!(calls.get(), 1);
+        assert_eq!(test_game.history.last(), Move::from_uci("a7a8r").as_ref());
+        assert_eq!(get_piece(&test_game.board, &get_board_coords("a8".to_string())), 'R');
+    }
+    #[test]
+    fn test_captured_pieces() {
+        let mut test_game = Game::new_from_fen("2r1k3/1P6/8/8/5p2/8/4P3/4K3 w - - 0 1".to_string());
+        for uci_move in ["e2e4", "f4e3", "b7c8n", "e8d7", "e1e2", "d7e6", "e2e3"] {
+            assert!(test_game.make_uci_move(uci_move), "{}", uci_move);
+        }
+        let pieces = |fen_chars: &str| fen_chars.chars().map(|fen_char| Piece::from_fen_char(fen_char).unwrap()).collect::<Vec<Piece>>();
+        assert_eq!(test_game.captured_by(Color::White), pieces("rp")); // The rook by promotion, then the pawn that took en passant
+        assert_eq!(test_game.captured_by(Color::Black), pieces("P")); // The pawn taken en passant
+        test_game.undo_move();
+        assert_eq!(test_game.captured_by(Color::White), pieces("r"));
+        test_game.undo_move();
+        test_game.undo_move();
+        test_game.undo_move();
+        test_game.undo_move();
+        assert_eq!(test_game.captured_by(Color::White), pieces(""));
+        assert_eq!(test_game.captured_by(Color::Black), pieces("P"));
+    }
+    #[test]
+    fn test_en_passant_square() {
+        let mut test_game = Game::new_from_fen("4k3/8/8/8/3p4/8/2P1P3/4K3 w - - 0 1".to_string());
+        assert!(test_game.make_uci_move("e2e4")); // Next to the black pawn on d4
+        assert_eq!(test_game.en_passant_square(), Square::from_algebraic("e3"));
+        assert!(test_game.make_uci_move("e8e7"));
+        assert_eq!(test_game.en_passant_square(), None); // Only right after the push
+        assert!(test_game.make_uci_move("c2c4")); // Also next to the pawn on d4
+        assert_eq!(test_game.en_passant_square(), Square::from_algebraic("c3"));
+
+        let mut alone = Game::new_from_fen("4k3/8/8/8/8/8/7P/4K3 w - - 0 1".to_string());
+        assert!(alone.make_uci_move("h2h4"));
+        assert_eq!(alone.en_passant_square(), None);
+        assert_eq!(Game::new_from_fen("4k3/8/8/8/6Pp/8/8/4K3 b - g3 0 1".to_string()).en_passant_square(), Square::from_algebraic("g3"));
+        assert_eq!(Game::new_from_fen("4k3/8/8/8/6P1/8/8/4K3 b - g3 0 1".to_string()).en_passant_square(), None);
+
+        let mut single_push = Game::from_uci_moves("b2b4 a7a6").unwrap(); // A double push followed by a single push
+        assert_eq!(single_push.board.en_passant_square, "-");
+        assert!(single_push.fen.ends_with("w KQkq - 0 2"));
+        assert!(!single_push.legal_moves().iter().any(|legal_move| legal_move.target == "b3"));
+        assert!(!single_push.make_uci_move("a2b3"));
+    }
+    #[test]
     fn test_promotion() {
         let mut test_position = Game::new_from_fen("r5k1/5p1p/p7/5Rp1/2P1p3/4P1PP/1p4NK/2q5 b - - 0 33".to_string());
         println!("{:?}", test_position);
@@ -1283,6 +2503,13 @@ mod tests {
         println!("{:?}", test_position);
         test_position.make_move("e1".to_string(), "c1".to_string());
         println!("{:?}", test_position);
+        assert!(test_position.make_move("e8".to_string(), "c8".to_string())); // Black castles with its own rook
+        assert_eq!(test_position.fen, "2kr3r/pppppppp/8/8/8/8/PPPPPPPP/2KR3R w - - 2 2");
+        let mut kingside = Game::new_from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1".to_string());
+        assert!(kingside.make_move("e8".to_string(), "g8".to_string()));
+        assert_eq!(kingside.fen, "r4rk1/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQ - 1 2");
+        let blocked = Game::new_from_fen("rn2k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 b q - 0 1".to_string());
+        assert!(!blocked.legal_moves().contains(&Move::from_uci("e8c8").unwrap())); // The b8 knight is in the way
     }
     #[test]
     fn test_2_move_mate() {
@@ -1293,7 +2520,7 @@ mod tests {
         debug_assert_eq!(test_game.fen, "rnbqkbnr/pppppppp/8/8/8/5P2/PPPPP1PP/RNBQKBNR b KQkq - 0 1".to_string());
         //println!("x coord: {:?}, y_coord: {:?}", get_board_coords("e7".to_string()), get_board_coords("e5".to_string()));
         //println!("{:?}", get_available_moves_internal(test_game.board.clone(), test_game.board.active_player, false));
-        let testing = test_game.make_move("e7".to_string(), "e5".to_string());
+        test_game.make_move("e7".to_string(), "e5".to_string());
         println!("{}", test_game.fen);
         //println!("{:?}", test_game.board);
         debug_assert_eq!(test_game.fen, "rnbqkbnr/pppp1ppp/8/4p3/8/5P2/PPPPP1PP/RNBQKBNR w KQkq e6 0 2".to_string());
@@ -1303,11 +2530,175 @@ mod tests {
         test_game.make_move("d8".to_string(), "h4".to_string());
         println!("{}", test_game.fen);
         debug_assert_eq!(test_game.fen, "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
-        debug_assert_eq!(test_game.game_status, 2)
+        debug_assert_eq!(test_game.game_status, GameStatus::BlackWinsByCheckmate)
     }
     #[test]
     fn test_castling_through_check() {
-        let mut test_position = Game::new_from_fen("1nbqkbn1/pppppppp/8/8/3r1r2/8/PPP1P1PP/R3K2R w KQ - 0 1".to_string());
+        let test_position = Game::new_from_fen("1nbqkbn1/pppppppp/8/8/3r1r2/8/PPP1P1PP/R3K2R w KQ - 0 1".to_string());
         println!("{:?}", get_available_moves(test_position.board.clone(), test_position.board.active_player, false));
     }
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_events() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        struct MessageVisitor(String);
+        impl Visit for MessageVisitor {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+        struct CollectingSubscriber(Arc<Mutex<Vec<String>>>);
+        impl Subscriber for CollectingSubscriber {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                *metadata.level() == tracing::Level::DEBUG
+            }
+            fn new_span(&self, _span: &Attributes) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _span: &Id, _values: &Record) {}
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, event: &Event) {
+                let mut visitor = MessageVisitor(String::new());
+                event.record(&mut visitor);
+                self.0.lock().unwrap().push(visitor.0);
+            }
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let messages = Arc::new(Mutex::new(vec![]));
+        tracing::subscriber::with_default(CollectingSubscriber(Arc::clone(&messages)), || {
+            let mut test_game = Game::new();
+            assert!(test_game.make_uci_move("f2f3"));
+            assert!(!test_game.make_uci_move("e2e4")); // Not White's turn
+            assert!(!test_game.make_uci_move("nonsense"));
+            for uci_move in ["e7e5", "g2g4", "d8h4"] {
+                assert!(test_game.make_uci_move(uci_move));
+            }
+            assert!(!test_game.make_uci_move("e1f2")); // The game is over
+        });
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec![
+                "move applied", "move rejected", "move rejected", "move applied", "move applied", "status changed",
+                "move applied", "move rejected",
+            ]
+        );
+    }
+    #[test]
+    fn test_sorted_legal_moves() {
+        let starting_position = Game::new();
+        let listing: Vec<String> = starting_position.legal_moves_sorted().iter().map(|legal_move| legal_move.to_uci()).collect();
+        assert_eq!(
+            listing,
+            [
+                "b1a3", "b1c3", "g1f3", "g1h3", "a2a3", "a2a4", "b2b3", "b2b4", "c2c3", "c2c4", "d2d3", "d2d4", "e2e3", "e2e4",
+                "f2f3", "f2f4", "g2g3", "g2g4", "h2h3", "h2h4",
+            ]
+        );
+        let promotion = Game::new_from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let promotions: Vec<String> = promotion.legal_moves_sorted().iter().take(8).map(|legal_move| legal_move.to_uci()).collect();
+        assert_eq!(promotions[..4], ["e1d1", "e1f1", "e1d2", "e1e2"]);
+        assert_eq!(promotions[4..], ["e1f2", "a7a8q", "a7a8r", "a7a8b"]);
+        assert_eq!(starting_position.legal_moves(), starting_position.legal_moves()); // The unsorted order is stable too
+    }
+    #[test]
+    fn test_board_fen() {
+        let start = Board::from_board_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", Color::White).unwrap();
+        assert_eq!(generate_fen(&start), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(start.board_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+
+        let moved_kings = Board::from_board_fen("r4rk1/8/8/8/8/8/8/R4RK1", Color::Black).unwrap();
+        assert_eq!(moved_kings.castling_rights.to_fen(), "-");
+        assert_eq!(generate_fen(&moved_kings), "r4rk1/8/8/8/8/8/8/R4RK1 b - - 0 1");
+        let one_rook_moved = Board::from_board_fen("r3k3/8/8/8/8/8/8/4K2R", Color::White).unwrap();
+        assert_eq!(one_rook_moved.castling_rights.to_fen(), "Kq");
+
+        assert_eq!(Board::from_board_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP", Color::White), Err(FenError::WrongRankCount(7)));
+        assert_eq!(Board::from_board_fen("8/8/8/8/8/8/8/9", Color::White), Err(FenError::InvalidRank("9".to_string())));
+        assert_eq!(Board::from_board_fen("8/8/8/8/8/8/8/8 w", Color::White), Err(FenError::WrongFieldCount(2)));
+    }
+    #[test]
+    fn test_lenient_fen() {
+        let cases = [
+            ("8/8/8/8/8/8/8/K6k w - -", "8/8/8/8/8/8/8/K6k w - - 0 1"),
+            ("8/8/8/8/8/8/8/K6k w", "8/8/8/8/8/8/8/K6k w - - 0 1"),
+            ("8/8/8/8/8/8/8/K6k", "8/8/8/8/8/8/8/K6k w - - 0 1"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+            ("  r3k2r/8/8/8/8/8/8/R3K2R \t w   Kk ", "r3k2r/8/8/8/8/8/8/R3K2R w Kk - 0 1"),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR   b  KQkq   e3 0   1", "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        ];
+        for (fen, normalized) in cases {
+            assert_eq!(generate_fen(&Board::from_fen_lenient(fen).unwrap()), normalized, "{}", fen);
+        }
+        assert_eq!(try_parse_fen("8/8/8/8/8/8/8/K6k w - -"), Err(FenError::WrongFieldCount(4))); // Strict parsing still rejects them
+        assert_eq!(try_parse_fen("8/8/8/8/8/8/8/K6k w"), Err(FenError::WrongFieldCount(2)));
+        for fen in ["rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", "8/8/4k3/8/8/4K3/4R3/8 w - - 99 80", "r3k2r/8/8/8/8/8/8/R3K2R w - - 3 12"] {
+            assert_eq!(Board::from_fen_lenient(fen), try_parse_fen(fen)); // Complete FENs are read the same way
+        }
+        assert_eq!(Board::from_fen_lenient(""), Err(FenError::WrongFieldCount(0)));
+        assert_eq!(Board::from_fen_lenient("8/8/8/8/8/8/8/K6k x"), Err(FenError::InvalidSideToMove("x".to_string())));
+        assert_eq!(Board::from_fen_lenient("8/8/8/8/8/8/8/K6k w - - 0 1 2"), Err(FenError::WrongFieldCount(7)));
+    }
+    #[test]
+    fn test_parse_any_input() {
+        let fen: Game = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".parse().unwrap();
+        assert_eq!(fen, Game::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string()));
+        let board_fen: Game = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR".parse().unwrap();
+        assert_eq!(board_fen, Game::new());
+        let tagged: Game = "[Event \"Casual game\"]\n\n1. f3 e5 2. g4 Qh4# 0-1".parse().unwrap();
+        assert!(tagged.is_checkmate());
+        let movetext: Game = "  e4 e5 Nf3 Nc6 ".parse().unwrap();
+        assert_eq!(movetext.history.len(), 4);
+        assert!(matches!("O-O".parse::<Game>(), Err(ParseGameError::Pgn(pgn::PgnError::IllegalMove(error))) if error.ply == 0)); // Read as movetext, but illegal
+
+        assert_eq!("Hello, world!".parse::<Game>(), Err(ParseGameError::Unrecognized("Hello, world!".to_string())));
+        assert_eq!("".parse::<Game>(), Err(ParseGameError::Unrecognized(String::new())));
+        assert_eq!("8/8/8/8/8/8/8/K6k w - - 0 x".parse::<Game>(), Err(ParseGameError::Fen(FenError::InvalidCounter("x".to_string()))));
+        assert!(matches!("8/8/8/8/8/8/8/8".parse::<Game>(), Err(ParseGameError::Fen(FenError::IllegalPosition(_))))); // No kings
+        assert!(matches!("[Event \"Broken".parse::<Game>(), Err(ParseGameError::Pgn(_))));
+    }
+    #[test]
+    fn test_status_stops_at_the_first_legal_move() {
+        use crate::undo::SIMULATED_MOVES;
+        let simulated = |action: &mut dyn FnMut()| {
+            SIMULATED_MOVES.with(|count| count.set(0));
+            action();
+            SIMULATED_MOVES.with(|count| count.get())
+        };
+        let mut game = Game::new();
+        assert_eq!(simulated(&mut || { get_legal_move_list(&game.board); }), 20); // Every move is tried
+        assert_eq!(simulated(&mut || { get_game_status(&game.board); }), 1); // Only until one is legal
+        assert_eq!(simulated(&mut || { game.make_move("e2".to_string(), "e4".to_string()); }), 20 + 1); // Was 20 + 20
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", // Stalemate
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", // Checkmate
+            "4k3/8/8/8/8/8/3r1r2/R3K2R w KQ - 0 1", // Castling through check both ways
+            "6k1/8/8/8/8/8/4r3/R3K2R w KQ - 0 1", // In check, so no castling
+        ] {
+            let board = parse_fen(fen);
+            assert_eq!(has_legal_move(&board), !get_legal_move_list(&board).is_empty(), "{}", fen);
+        }
+    }
+    #[test]
+    fn test_one_king_safety_pass() {
+        use crate::undo::SIMULATED_MOVES;
+        let in_check = parse_fen("6k1/8/8/8/8/8/4r3/R3K2R w KQ - 0 1");
+        let candidates: usize = get_available_moves_internal(in_check.clone(), 'w', true).values().map(Vec::len).sum();
+        SIMULATED_MOVES.with(|count| count.set(0));
+        let legal_moves = get_legal_move_list(&in_check);
+        assert_eq!(SIMULATED_MOVES.with(|count| count.get()), candidates as u64 - 2); // Each move is tried once, and castling out of check not at all
+        assert_eq!(legal_moves.len(), 3); // Kd1, Kf1 and Kxe2
+
+        let pinned = parse_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1"); // Not in check, but the knight is pinned
+        assert!(get_legal_move_list(&pinned).iter().all(|(source, _target, _promotion)| *source == vec![7, 4]));
+    }
 }
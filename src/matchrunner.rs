@@ -0,0 +1,333 @@
+/*****************************
+*  MATCH RUNNER              *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Self-play matches between two move-selection strategies, for tuning the engine.
+
+A strategy is anything that implements MovePicker. The random mover (with the `rand` feature) and the alpha-beta search
+both come with one, see random::RandomPicker and search::SearchPicker.
+
+With the `tablebase` feature, run_match_with_tablebase() ends games as soon as the tablebase knows their result.
+*/
+
+use std::collections::HashMap;
+
+#[cfg(feature = "tablebase")]
+use crate::tablebase::Tablebase;
+use crate::{Color, Game, GameStatus, Move};
+
+/// The number of half-moves after which a game is stopped and scored as a draw.
+pub const PLY_CAP: u32 = 300;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+/// A trait for anything that can choose a move, such as an engine or a random mover.
+pub trait MovePicker {
+    /// A function to choose a move for the active player. It is only called when the game is in progress,
+    /// and should return one of game.legal_moves().
+    fn pick(&mut self, game: &Game) -> Move;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent why a game of a match ended.
+///
+/// ```text
+/// Status(GameStatus), // The game ended by the rules (see docs for GameStatus).
+/// PlyCap, // The game reached PLY_CAP half-moves, and is scored as a draw.
+/// IllegalMove(Color), // The picker playing this color returned an illegal move, and loses.
+/// ```
+pub enum Termination {
+    Status(GameStatus),
+    PlyCap,
+    IllegalMove(Color),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// A struct to represent the result of a match. Wins, draws and losses are counted from the first picker's perspective.
+///
+/// ## Attributes
+/// ```text
+/// wins: usize, // Games won by the first picker.
+/// draws: usize,
+/// losses: usize, // Games won by the second picker.
+/// total_plies: u64, // The number of half-moves played over all games.
+/// terminations: HashMap<Termination, usize>, // How many games ended for each reason (see docs for Termination).
+/// ```
+pub struct MatchReport {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub total_plies: u64,
+    pub terminations: HashMap<Termination, usize>,
+}
+impl MatchReport {
+    /// A function to return the number of games played.
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// A function to return the average length of the games, in half-moves. Returns 0 if no games were played.
+    pub fn average_length(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        self.total_plies as f64 / self.games() as f64
+    }
+
+    /// A function to return the first picker's score, counting wins as 1 and draws as 0.5. Returns 0.5 if no games were played.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// A function to estimate how much stronger the first picker is than the second, in Elo points.
+    ///
+    /// ## Returns
+    /// This function returns the Elo difference that matches the score. It is infinite if one picker won every game.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::matchrunner::MatchReport;
+    /// let report = MatchReport { wins: 3, draws: 0, losses: 1, ..Default::default() };
+    /// assert_eq!(report.elo_difference().round(), 191.0); // A score of 75%
+    /// ```
+    pub fn elo_difference(&self) -> f64 {
+        elo_from_score(self.score())
+    }
+
+    /// A function to return the margin of error of elo_difference(), at 95% confidence.
+    ///
+    /// ## Returns
+    /// This function returns half the width of the confidence interval in Elo points. The interval is usually lopsided,
+    /// so this is an average of the two sides. It is infinite if the interval reaches a score of 0% or 100%.
+    pub fn elo_error_margin(&self) -> f64 {
+        if self.games() == 0 {
+            return f64::INFINITY;
+        }
+        let games = self.games() as f64;
+        let score = self.score();
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+        let margin = 1.96 * (variance / games).sqrt();
+        (elo_from_score(score + margin) - elo_from_score(score - margin)) / 2.0
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn elo_from_score(score: f64) -> f64 {
+    if score <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if score >= 1.0 {
+        return f64::INFINITY;
+    }
+    -400.0 * (1.0 / score - 1.0).log10()
+} // The logistic Elo model: the expected score of a player rated d points higher is 1 / (1 + 10^(-d/400))
+
+fn play_game(
+    white: &mut dyn MovePicker,
+    black: &mut dyn MovePicker,
+    opening_fen: &str,
+    adjudicate: &dyn Fn(&mut Game) -> bool,
+) -> (Termination, u32) {
+    let mut game = Game::new_from_fen(opening_fen.to_string());
+    let mut plies = 0;
+    while !game.is_game_over() && !adjudicate(&mut game) {
+        if plies >= PLY_CAP {
+            return (Termination::PlyCap, plies);
+        }
+        let (picked_move, color) = if game.board.active_player == 'w' {
+            (white.pick(&game), Color::White)
+        } else {
+            (black.pick(&game), Color::Black)
+        };
+        if !game.make_uci_move(&picked_move.to_uci()) {
+            return (Termination::IllegalMove(color), plies);
+        }
+        plies += 1;
+    }
+    (Termination::Status(game.game_status), plies)
+} // Plays one game and returns why it ended and how many half-moves were played. adjudicate may end the game before every move
+
+fn play_match(
+    white: &mut dyn MovePicker,
+    black: &mut dyn MovePicker,
+    games: usize,
+    opening_fens: &[String],
+    adjudicate: &dyn Fn(&mut Game) -> bool,
+) -> MatchReport {
+    let start_position = [Game::new().fen];
+    let opening_fens = if opening_fens.is_empty() { &start_position[..] } else { opening_fens };
+    let mut report = MatchReport::default();
+    for game_index in 0..games {
+        let opening_fen = &opening_fens[(game_index / 2) % opening_fens.len()];
+        let first_picker_color = if game_index % 2 == 0 { Color::White } else { Color::Black };
+        let (termination, plies) = match first_picker_color {
+            Color::White => play_game(white, black, opening_fen, adjudicate),
+            Color::Black => play_game(black, white, opening_fen, adjudicate),
+        };
+        let winner = match termination {
+            Termination::Status(status) => status.winner(),
+            Termination::PlyCap => None,
+            Termination::IllegalMove(color) => Some(color.opposite()),
+        };
+        match winner {
+            Some(color) if color == first_picker_color => report.wins += 1,
+            Some(_) => report.losses += 1,
+            None => report.draws += 1,
+        }
+        report.total_plies += plies as u64;
+        *report.terminations.entry(termination).or_insert(0) += 1;
+    }
+    report
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to play a match between two move pickers.
+///
+/// ## Arguments
+/// ```text
+/// white: &mut dyn MovePicker, // The first picker. It plays White in the first game.
+/// black: &mut dyn MovePicker, // The second picker. It plays Black in the first game.
+/// games: usize, // The number of games to play. The pickers swap colors after every game.
+/// opening_fens: &[String], // The positions to start from. Each one is played twice, once with each picker as White.
+///                          // The normal starting position is used if the slice is empty.
+///
+/// ```
+/// ## Returns
+/// This function returns a MatchReport (see docs for MatchReport). Games that reach PLY_CAP half-moves are scored as draws.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::matchrunner::run_match;
+/// # use alviny_task_3::search::SearchPicker;
+/// let mut engine = SearchPicker { depth: 1 };
+/// let mut other_engine = SearchPicker { depth: 1 };
+/// let mated = vec!["rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string()]; // White is already mated
+/// let report = run_match(&mut engine, &mut other_engine, 2, &mated);
+/// assert_eq!((report.wins, report.losses), (1, 1)); // Each picker was Black once
+/// ```
+pub fn run_match(white: &mut dyn MovePicker, black: &mut dyn MovePicker, games: usize, opening_fens: &[String]) -> MatchReport {
+    play_match(white, black, games, opening_fens, &|_game| false)
+}
+
+/// A function to play a match between two move pickers, ending every game whose result is known to the tablebase
+/// (see docs for Game::adjudicate_with_tablebase). Only available with the `tablebase` feature.
+///
+/// ## Arguments
+/// ```text
+/// white: &mut dyn MovePicker, // The first picker. It plays White in the first game.
+/// black: &mut dyn MovePicker, // The second picker. It plays Black in the first game.
+/// games: usize, // The number of games to play. The pickers swap colors after every game.
+/// opening_fens: &[String], // The positions to start from, as for run_match().
+/// tablebase: &Tablebase, // The tables to adjudicate with.
+///
+/// ```
+/// ## Returns
+/// This function returns a MatchReport (see docs for MatchReport). Adjudicated games are counted under Termination::Status.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::GameStatus;
+/// # use alviny_task_3::matchrunner::{run_match_with_tablebase, Termination};
+/// # use alviny_task_3::search::SearchPicker;
+/// # use alviny_task_3::tablebase::Tablebase;
+/// let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/syzygy")).unwrap();
+/// let mut engine = SearchPicker { depth: 1 };
+/// let mut other_engine = SearchPicker { depth: 1 };
+/// let ending = vec!["8/8/8/3k4/8/8/8/4KQ2 w - - 0 1".to_string()];
+/// let report = run_match_with_tablebase(&mut engine, &mut other_engine, 2, &ending, &tablebase);
+/// assert_eq!((report.wins, report.losses, report.total_plies), (1, 1, 0)); // Both games were over before the first move
+/// assert_eq!(report.terminations[&Termination::Status(GameStatus::WhiteWinsByAdjudication)], 2);
+/// ```
+#[cfg(feature = "tablebase")]
+pub fn run_match_with_tablebase(
+    white: &mut dyn MovePicker,
+    black: &mut dyn MovePicker,
+    games: usize,
+    opening_fens: &[String],
+    tablebase: &Tablebase,
+) -> MatchReport {
+    play_match(white, black, games, opening_fens, &|game| game.adjudicate_with_tablebase(tablebase))
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FirstMovePicker;
+    impl MovePicker for FirstMovePicker {
+        fn pick(&mut self, game: &Game) -> Move {
+            let mut moves = game.legal_moves();
+            moves.sort_by_key(|legal_move| legal_move.to_uci());
+            moves.remove(0)
+        }
+    }
+
+    struct IllegalPicker;
+    impl MovePicker for IllegalPicker {
+        fn pick(&mut self, _game: &Game) -> Move {
+            Move::from_uci("a1a8").unwrap()
+        }
+    }
+
+    #[test]
+    fn test_report_adds_up() {
+        let report = run_match(&mut FirstMovePicker, &mut IllegalPicker, 4, &[]);
+        assert_eq!((report.wins, report.draws, report.losses), (4, 0, 0));
+        assert_eq!(report.terminations[&Termination::IllegalMove(Color::White)], 2);
+        assert_eq!(report.terminations[&Termination::IllegalMove(Color::Black)], 2);
+        assert_eq!(report.total_plies, 2); // The illegal picker loses on its first move, which is the second ply when it is Black
+        assert_eq!(report.elo_difference(), f64::INFINITY);
+    }
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_match() {
+        use crate::random::RandomPicker;
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut first = RandomPicker { rng: StdRng::seed_from_u64(1) };
+        let mut second = RandomPicker { rng: StdRng::seed_from_u64(2) };
+        let report = run_match(&mut first, &mut second, 4, &[]);
+        assert_eq!(report.games(), 4);
+        assert_eq!(report.terminations.values().sum::<usize>(), 4);
+        assert!(report.average_length() > 0.0 && report.average_length() <= PLY_CAP as f64);
+        assert!(!report.terminations.keys().any(|termination| matches!(termination, Termination::IllegalMove(_))));
+    }
+    #[test]
+    fn test_elo_estimate() {
+        let even = MatchReport { wins: 10, draws: 20, losses: 10, ..Default::default() };
+        assert_eq!(even.elo_difference(), 0.0);
+        assert!(even.elo_error_margin() > 0.0 && even.elo_error_margin() < 100.0);
+        let more_games = MatchReport { wins: 100, draws: 200, losses: 100, ..Default::default() };
+        assert!(more_games.elo_error_margin() < even.elo_error_margin());
+        let losing = MatchReport { wins: 1, draws: 0, losses: 3, ..Default::default() };
+        assert_eq!(losing.elo_difference().round(), -191.0);
+    }
+}
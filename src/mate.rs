@@ -0,0 +1,205 @@
+/*****************************
+*  MATE SOLVER               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A solver for forced mates, e.g. for checking "mate in N" puzzles.
+
+Unlike the general search (see the search module), the solver never estimates: a line is only returned if every
+defence has been tried and all of them are mated within the bound. It looks for a mate in 1 first, then a mate in 2,
+and so on, so the mate it finds is always the shortest one. At each depth, lines that can't mate within the plies
+left are cut off without being searched further (mate distance pruning).
+
+In a variant, the solver plays by the variant's rules (see docs for Rules), and every win counts as a mate: blowing up
+the king in Atomic, reaching the hill in King of the Hill, or winning the race in Racing Kings.
+*/
+
+use crate::rules::Rules;
+use crate::{get_algebraic_notation, get_board_coords, get_legal_move_list, has_legal_move, player_is_in_check, Board, Color, Game, Move};
+
+type InternalMove = (Vec<i32>, Vec<i32>, Option<char>);
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn children(board: &Board, rules: Option<&dyn Rules>) -> Vec<(InternalMove, Board, bool)> {
+    let mut children = match rules {
+        Some(rules) => rules
+            .legal_moves(board)
+            .into_iter()
+            .map(|legal_move| {
+                let mut child = board.clone();
+                rules.play_move(&mut child, &legal_move);
+                let gives_check = rules.is_in_check(&child, Color::from_char(child.active_player).unwrap_or(Color::White));
+                ((get_board_coords(legal_move.source), get_board_coords(legal_move.target), legal_move.promotion), child, gives_check)
+            })
+            .collect::<Vec<(InternalMove, Board, bool)>>(),
+        None => get_legal_move_list(board)
+            .into_iter()
+            .map(|legal_move| {
+                let mut child = board.clone();
+                child.play_move(legal_move.0.clone(), legal_move.1.clone(), legal_move.2);
+                let gives_check = player_is_in_check(&child, child.active_player);
+                (legal_move, child, gives_check)
+            })
+            .collect::<Vec<(InternalMove, Board, bool)>>(),
+    };
+    children.sort_by_key(|(_legal_move, _child, gives_check)| !gives_check);
+    children
+} // Plays every legal move, by the variant's rules if there are any, listing the checks first since they are the likeliest to mate.
+
+fn is_won(child: &Board, gives_check: bool, rules: Option<&dyn Rules>) -> bool {
+    match rules {
+        Some(rules) => rules.game_status(child).winner().is_some_and(|winner| winner.to_char() != child.active_player),
+        None => gives_check && !has_legal_move(child),
+    }
+} // Checks whether the move that led to child won the game, i.e. mated in standard chess
+
+fn attack(board: &Board, plies: u32, rules: Option<&dyn Rules>) -> Option<Vec<InternalMove>> {
+    let children = children(board, rules);
+    for (legal_move, child, gives_check) in &children {
+        if is_won(child, *gives_check, rules) {
+            return Some(vec![legal_move.clone()]);
+        }
+    } // Mate in one
+    if plies < 3 {
+        return None;
+    }
+    for (legal_move, child, _gives_check) in children {
+        if let Some(mut line) = defend(&child, plies - 1, rules) {
+            line.insert(0, legal_move);
+            return Some(line);
+        }
+    }
+    None
+} // Finds a move for the side to move that mates within the given number of plies, and the line that follows.
+
+fn defend(board: &Board, plies: u32, rules: Option<&dyn Rules>) -> Option<Vec<InternalMove>> {
+    let children = children(board, rules);
+    if children.is_empty() {
+        return None; // Stalemate. Checkmate was already spotted by attack()
+    }
+    let mut longest_line: Option<Vec<InternalMove>> = None;
+    for (legal_move, child, _gives_check) in children {
+        let mut line = shortest_mate(&child, plies - 1, rules)?; // This defence holds
+        line.insert(0, legal_move);
+        if longest_line.as_ref().is_none_or(|longest| line.len() > longest.len()) {
+            longest_line = Some(line);
+        }
+    }
+    longest_line
+} // Checks that every defence is mated within the given number of plies, and returns the one that lasts the longest.
+
+fn shortest_mate(board: &Board, max_plies: u32, rules: Option<&dyn Rules>) -> Option<Vec<InternalMove>> {
+    (1..=max_plies).step_by(2).find_map(|plies| attack(board, plies, rules))
+} // Tries a mate in one, then in two, and so on, so that the first mate found is the shortest.
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to find a forced checkmate for the active player, or a forced win of any kind in a variant (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// max_plies: u32, // The longest mate to look for, in half-moves. A mate in N is 2 * N - 1 plies, so a mate in 2 is 3 plies.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(line) with the moves of the shortest forced mate, starting with the active player's move
+    /// and ending with the mating move, or None if there is no mate within max_plies. The defending moves in the line are
+    /// the ones that hold out the longest.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string());
+    /// let line = example_game.find_forced_mate(1).unwrap();
+    /// assert_eq!(line[0].to_uci(), "a1a8"); // A back rank mate
+    /// ```
+    pub fn find_forced_mate(&self, max_plies: u32) -> Option<Vec<Move>> {
+        if self.is_game_over() {
+            return None;
+        }
+        let line = shortest_mate(&self.board, max_plies, self.variant.non_standard_rules())?;
+        Some(line
+            .into_iter()
+            .map(|(source, target, promotion)| Move {
+                source: get_algebraic_notation(source),
+                target: get_algebraic_notation(target),
+                promotion,
+            })
+            .collect())
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, GameStatus, Variant};
+
+    fn uci_line(line: &[crate::Move]) -> Vec<String> {
+        line.iter().map(|line_move| line_move.to_uci()).collect()
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let test_game = Game::new_from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2".to_string());
+        assert_eq!(uci_line(&test_game.find_forced_mate(5).unwrap()), ["d8h4"]);
+    }
+    #[test]
+    fn test_smothered_mate() {
+        // Philidor's legacy: 1. Qg8+ Rxg8 2. Nf7#. The knight on h6 keeps the king from taking the queen
+        let test_game = Game::new_from_fen("4r2k/6pp/7N/3Q4/8/8/8/6K1 w - - 0 1".to_string());
+        assert!(test_game.find_forced_mate(1).is_none());
+        let line = test_game.find_forced_mate(3).unwrap();
+        assert_eq!(uci_line(&line), ["d5g8", "e8g8", "h6f7"]);
+        let mut replay = test_game.clone();
+        for line_move in &line {
+            assert!(replay.make_uci_move(&line_move.to_uci()));
+        }
+        assert_eq!(replay.game_status, GameStatus::WhiteWinsByCheckmate);
+        assert_eq!(test_game.find_forced_mate(5).unwrap().len(), 3); // More room doesn't make the mate longer
+    }
+    #[test]
+    fn test_escape_square_means_no_mate() {
+        // Ra8+ looks like a back rank mate, but the h6 pawn gave the king an escape square
+        let test_game = Game::new_from_fen("6k1/5pp1/7p/8/8/8/8/R5K1 w - - 0 1".to_string());
+        assert!(test_game.find_forced_mate(3).is_none());
+        let checkmate = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert!(checkmate.find_forced_mate(3).is_none());
+    }
+    #[test]
+    fn test_variant_wins_count_as_mates() {
+        // Rxe7 blows up the black king in Atomic, while in standard chess the king just takes the rook back
+        let fen = "4k3/4p3/8/8/8/8/8/4R1K1 w - - 0 1";
+        assert!(Game::new_from_fen(fen.to_string()).find_forced_mate(3).is_none());
+        let atomic = Game::new_from_fen_with_variant(fen.to_string(), Variant::Atomic);
+        let line = atomic.find_forced_mate(1).unwrap();
+        assert_eq!(uci_line(&line), ["e1e7"]);
+        let mut replay = atomic.clone();
+        assert!(replay.make_uci_move("e1e7"));
+        assert_eq!(replay.game_status, GameStatus::WhiteWinsByExplosion);
+
+        // The king reaches the hill in two moves, and Black can't stop it
+        let hill = Game::new_from_fen_with_variant("7k/8/8/8/8/8/1K6/8 w - - 0 1".to_string(), Variant::KingOfTheHill);
+        assert!(hill.find_forced_mate(1).is_none());
+        let line = hill.find_forced_mate(3).unwrap();
+        assert_eq!(line.len(), 3);
+        let mut replay = hill.clone();
+        for line_move in &line {
+            assert!(replay.make_uci_move(&line_move.to_uci()));
+        }
+        assert_eq!(replay.game_status, GameStatus::WhiteWinsByKingOfTheHill);
+    }
+}
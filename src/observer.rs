@@ -0,0 +1,206 @@
+/*****************************
+*  OBSERVER                  *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Callbacks for reacting to everything that happens in a Game, e.g. to broadcast moves to spectators or store them.
+
+An observer is registered with Game::set_observer, and is called with a MoveEvent after every move, undo, resignation
+and draw agreement. It is called with the Game borrowed immutably, and only gets to see the MoveEvent, so it can't
+change the Game it observes. Cloning a Game doesn't clone its observer: the clone starts without one.
+*/
+
+use std::fmt;
+
+use crate::{Color, Game, GameStatus, Move, get_board_coords, get_captured_piece};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent what happened in a MoveEvent.
+///
+/// ```text
+/// Move, // A move was made.
+/// Undo, // The last move was taken back.
+/// Resignation(Color), // The player of this color resigned.
+/// DrawAgreement, // The players agreed to a draw.
+/// ```
+pub enum MoveEventKind {
+    Move,
+    Undo,
+    Resignation(Color),
+    DrawAgreement,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent something that happened in a Game, as passed to its observer.
+///
+/// ## Attributes
+/// ```text
+/// kind: MoveEventKind, // What happened (see docs for MoveEventKind).
+/// chess_move: Option<Move>, // The move that was made or taken back. None for resignations and draw agreements.
+/// fen: String, // The FEN of the position after the event.
+/// is_check: bool, // Whether the player to move is in check after the event.
+/// is_capture: bool, // Whether chess_move captured a piece.
+/// status: GameStatus, // The game status after the event (see docs for GameStatus).
+/// ```
+pub struct MoveEvent {
+    pub kind: MoveEventKind,
+    pub chess_move: Option<Move>,
+    pub fen: String,
+    pub is_check: bool,
+    pub is_capture: bool,
+    pub status: GameStatus,
+}
+
+type Callback = Box<dyn Fn(&MoveEvent) + Send>;
+
+#[derive(Default)]
+pub(crate) struct Observer(Option<Callback>);
+impl Clone for Observer {
+    fn clone(&self) -> Self {
+        Observer(None)
+    }
+} // Clones start without an observer, so that e.g. searching on a copy of the Game doesn't report the searched moves
+impl PartialEq for Observer {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+} // Two Games are equal regardless of who observes them
+impl Observer {
+    pub(crate) fn call(&self, event: &MoveEvent) {
+        if let Some(observer) = &self.0 {
+            observer(event);
+        }
+    } // Calls the callback, if there is one
+}
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.0.is_some() { "Observer" } else { "No observer" })
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+pub(crate) fn is_capture(game: &Game, chess_move: &Move) -> bool {
+    let source = get_board_coords(chess_move.source.clone());
+    let target = get_board_coords(chess_move.target.clone());
+    get_captured_piece(&game.board, &source, &target).is_some()
+} // Whether a move captures in the Game's current position, including en passant. Chess960 castling onto the own rook doesn't
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to register an observer, which is called after every move, undo, resignation and draw agreement.
+    /// Only one observer can be registered at a time: a new one replaces the old one.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// observer: Box<dyn Fn(&MoveEvent) + Send>, // The callback (see docs for MoveEvent).
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::sync::mpsc;
+    /// # use alviny_task_3::Game;
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.set_observer(Box::new(move |event| sender.send(event.fen.clone()).unwrap()));
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert_eq!(receiver.recv().unwrap(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    /// ```
+    pub fn set_observer(&mut self, observer: Box<dyn Fn(&MoveEvent) + Send>) {
+        self.observer = Observer(Some(observer));
+    }
+
+    /// A function to remove the observer, if there is one.
+    pub fn clear_observer(&mut self) {
+        self.observer = Observer(None);
+    }
+
+    pub(crate) fn notify(&self, kind: MoveEventKind, chess_move: Option<Move>, is_capture: bool) {
+        if self.observer.0.is_some() {
+            self.observer.call(&self.event(kind, chess_move, is_capture));
+        }
+    } // Takes &self, so the observer is called while nothing can mutate the Game
+
+    pub(crate) fn event(&self, kind: MoveEventKind, chess_move: Option<Move>, is_capture: bool) -> MoveEvent {
+        MoveEvent {
+            kind,
+            chess_move,
+            fen: self.fen.clone(),
+            is_check: self.checks[if self.board.active_player == 'w' { 0 } else { 1 }],
+            is_capture,
+            status: self.game_status,
+        }
+    } // The MoveEvent for something that just happened in the Game
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn observed_game() -> (Game, Arc<Mutex<Vec<MoveEvent>>>) {
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut game = Game::new();
+        let sink = Arc::clone(&events);
+        game.set_observer(Box::new(move |event| sink.lock().unwrap().push(event.clone())));
+        (game, events)
+    }
+
+    #[test]
+    fn test_event_sequence() {
+        let (mut test_game, events) = observed_game();
+        for uci_move in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5e5"] {
+            assert!(test_game.make_uci_move(uci_move));
+        }
+        assert!(!test_game.make_uci_move("e1e2")); // Illegal moves aren't reported
+        assert_eq!(test_game.undo_move(), Some(Move::from_uci("d5e5").unwrap()));
+        assert!(test_game.resign(Color::White));
+        assert!(!test_game.make_uci_move("d5e5")); // No moves after a resignation
+
+        let events = events.lock().unwrap();
+        let kinds: Vec<MoveEventKind> = events.iter().map(|event| event.kind).collect();
+        let mut expected = vec![MoveEventKind::Move; 6];
+        expected.extend([MoveEventKind::Undo, MoveEventKind::Resignation(Color::White)]);
+        assert_eq!(kinds, expected);
+        let captures: Vec<bool> = events.iter().map(|event| event.is_capture).collect();
+        assert_eq!(captures, vec![false, false, true, true, false, false, false, false]);
+        let checks: Vec<bool> = events.iter().map(|event| event.is_check).collect();
+        assert_eq!(checks, vec![false, false, false, false, false, true, false, false]); // 3... Qe5+
+        assert_eq!(events[5].chess_move, Some(Move::from_uci("d5e5").unwrap()));
+        assert_eq!(events[6].fen, events[4].fen); // The undo goes back to the position after 3. Nc3
+        assert_eq!(events[7].chess_move, None);
+        assert_eq!(events[7].status, GameStatus::BlackWinsByResignation);
+    }
+    #[test]
+    fn test_draw_agreement_and_clones() {
+        let (mut test_game, events) = observed_game();
+        let mut copy = test_game.clone();
+        assert!(copy.make_uci_move("e2e4")); // The copy has no observer
+        assert!(test_game.agree_draw());
+        assert!(!test_game.agree_draw()); // The game is already over
+        assert!(!test_game.resign(Color::Black));
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, MoveEventKind::DrawAgreement);
+        assert_eq!(events[0].status, GameStatus::DrawByAgreement);
+    }
+}
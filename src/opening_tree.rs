@@ -0,0 +1,239 @@
+/*****************************
+*  OPENING TREE              *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+An opening explorer: what was played in a position across a collection of games, and how it scored.
+
+The tree is keyed by position (the Zobrist key, see docs for Board::zobrist_key) rather than by the moves that led
+there, so transpositions share a node: 1. e4 e5 2. Nf3 and 1. Nf3 e5 2. e4 count towards the same statistics. The
+result of a game is its GameStatus, which for a game read from PGN includes a result the moves don't show, e.g. a
+resignation (see docs for Game::from_pgn). Games that are still in progress count as played, but not as a win, a draw
+or a loss. Every game is replayed by the rules of its variant.
+
+```
+# use alviny_task_3::Game;
+# use alviny_task_3::opening_tree::OpeningTree;
+let games = ["e2e4 e7e5", "e2e4 c7c5", "d2d4 d7d5"].map(|moves| Game::from_uci_moves(moves).unwrap());
+let tree = OpeningTree::build(games, 10);
+let stats = tree.lookup(&Game::new().board);
+assert_eq!((stats[0].chess_move.to_uci(), stats[0].count), ("e2e4".to_string(), 2));
+```
+*/
+
+use std::collections::HashMap;
+
+use crate::successor::find_legal_move;
+use crate::{Board, Color, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent how often a move was played from a position, and how the games went.
+///
+/// ## Attributes
+/// ```text
+/// chess_move: Move, // The move.
+/// count: u32, // The number of games in which it was played from the position.
+/// white_wins: u32, // How many of those games White won.
+/// draws: u32, // How many of those games were drawn.
+/// black_wins: u32, // How many of those games Black won. Games without a result are counted in none of the three.
+/// ```
+pub struct MoveStats {
+    pub chess_move: Move,
+    pub count: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+impl MoveStats {
+    /// A function to return the score of the move for White, counting a win as 1 and a draw as 1/2.
+    ///
+    /// ## Returns
+    /// This function returns the average score in the games that have a result, from 0.0 to 1.0, or None if none of
+    /// them has.
+    pub fn white_score(&self) -> Option<f64> {
+        let decided = self.white_wins + self.draws + self.black_wins;
+        (decided > 0).then(|| (self.white_wins as f64 + self.draws as f64 / 2.0) / decided as f64)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// A struct to represent the moves played from every position of a collection of games (see module docs).
+pub struct OpeningTree {
+    nodes: HashMap<u64, Vec<MoveStats>>,
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl OpeningTree {
+    /// A function to build the tree from a collection of games, e.g. the games read from a PGN file.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// games: impl IntoIterator<Item = Game>, // The games, each replayed from its start_fen.
+    /// max_plies: usize, // How many moves of each game to add, e.g. 20 for the first ten moves.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the OpeningTree.
+    pub fn build(games: impl IntoIterator<Item = Game>, max_plies: usize) -> OpeningTree {
+        let mut tree = OpeningTree::default();
+        for game in games {
+            tree.add_game(&game, max_plies);
+        }
+        tree
+    }
+
+    /// A function to add the first moves of one more game to the tree.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: &Game, // The game, replayed from its start_fen by the rules of its variant. Its result is its game_status.
+    /// max_plies: usize, // How many of its moves to add.
+    ///
+    /// ```
+    pub fn add_game(&mut self, game: &Game, max_plies: usize) {
+        let rules = game.variant.rules();
+        let mut board = Game::new_from_fen_with_variant(game.start_fen.clone(), game.variant.clone()).board;
+        let winner = game.game_status.winner();
+        let drawn = game.game_status.is_game_over() && winner.is_none();
+        for played in game.history.iter().take(max_plies) {
+            let moves = self.nodes.entry(board.zobrist_key()).or_default();
+            let index = match moves.iter().position(|stats| stats.chess_move == *played) {
+                Some(index) => index,
+                None => {
+                    moves.push(MoveStats { chess_move: played.clone(), count: 0, white_wins: 0, draws: 0, black_wins: 0 });
+                    moves.len() - 1
+                }
+            };
+            let stats = &mut moves[index];
+            stats.count += 1;
+            match winner {
+                Some(Color::White) => stats.white_wins += 1,
+                Some(Color::Black) => stats.black_wins += 1,
+                None if drawn => stats.draws += 1,
+                None => {}
+            }
+            let Ok(legal_move) = find_legal_move(&rules.legal_moves(&board), played, board.promotion_selection) else {
+                break;
+            };
+            rules.play_move(&mut board, &legal_move);
+        }
+    }
+
+    /// A function to look up what was played in a position.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The position, however it was reached.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the statistics of every move played from the position, the most frequent first, and
+    /// moves played equally often in UCI order. Empty if the position isn't in the tree.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::opening_tree::OpeningTree;
+    /// let tree = OpeningTree::build([Game::from_uci_moves("f2f3 e7e5 g2g4 d8h4").unwrap()], 4); // The fool's mate
+    /// let stats = tree.lookup(&Game::from_uci_moves("f2f3 e7e5").unwrap().board);
+    /// assert_eq!((stats[0].chess_move.to_uci(), stats[0].black_wins), ("g2g4".to_string(), 1));
+    /// assert!(tree.lookup(&Game::from_uci_moves("e2e4").unwrap().board).is_empty());
+    /// ```
+    pub fn lookup(&self, board: &Board) -> Vec<MoveStats> {
+        let mut moves = self.nodes.get(&board.zobrist_key()).cloned().unwrap_or_default();
+        moves.sort_by(|first, second| second.count.cmp(&first.count).then_with(|| first.chess_move.to_uci().cmp(&second.chess_move.to_uci())));
+        moves
+    }
+
+    /// A function to return the number of positions in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// A function to check whether the tree has no positions, i.e. no moves have been added.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    fn five_games() -> Vec<Game> {
+        let mut games = vec![
+            Game::from_uci_moves("e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7").unwrap(), // The scholar's mate, 1-0
+            Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6 f1c4").unwrap(),
+            Game::from_uci_moves("g1f3 b8c6 e2e4 e7e5 f1b5").unwrap(), // Transposes to the game before after 2... e5
+            Game::from_uci_moves("e2e4 c7c5").unwrap(),
+            Game::from_uci_moves("f2f3 e7e5 g2g4 d8h4").unwrap(), // The fool's mate, 0-1
+        ];
+        games[1].agree_draw();
+        games[2].resign(Color::White);
+        games
+    }
+
+    #[test]
+    fn test_root_and_after_e4() {
+        let tree = OpeningTree::build(five_games(), 10);
+        let root = tree.lookup(&Game::new().board);
+        let summary = root.iter().map(|stats| (stats.chess_move.to_uci(), stats.count, stats.white_wins, stats.draws, stats.black_wins)).collect::<Vec<_>>();
+        assert_eq!(summary, [("e2e4".to_string(), 3, 1, 1, 0), ("f2f3".to_string(), 1, 0, 0, 1), ("g1f3".to_string(), 1, 0, 0, 1)]);
+        assert_eq!(root[0].white_score(), Some(0.75));
+
+        let after_e4 = tree.lookup(&Game::from_uci_moves("e2e4").unwrap().board);
+        let summary = after_e4.iter().map(|stats| (stats.chess_move.to_uci(), stats.count)).collect::<Vec<_>>();
+        assert_eq!(summary, [("e7e5".to_string(), 2), ("c7c5".to_string(), 1)]);
+        assert_eq!(after_e4[1].white_score(), None); // The Sicilian game has no result
+    }
+    #[test]
+    fn test_results_and_variants() {
+        let resigned = Game::from_pgn("[Result \"0-1\"]\n\n1. e4 e5 2. Nf3 0-1").unwrap();
+        let tree = OpeningTree::build([resigned], 10);
+        let root = tree.lookup(&Game::new().board);
+        assert_eq!((root[0].count, root[0].black_wins), (1, 1));
+
+        let mut atomic = Game::new_with_variant(Variant::Atomic);
+        for uci in ["g1f3", "e7e6", "f3g5", "d8g5", "e2e3"] {
+            atomic.make_uci_move(uci);
+        }
+        let tree = OpeningTree::build([atomic.clone()], 10);
+        atomic.undo_move();
+        let after_explosion = tree.lookup(&atomic.board); // The queen blew up with the knight on g5
+        assert_eq!(after_explosion.len(), 1);
+        assert_eq!(after_explosion[0].chess_move.to_uci(), "e2e3");
+    }
+    #[test]
+    fn test_transpositions_merge() {
+        let tree = OpeningTree::build(five_games(), 10);
+        let transposed = tree.lookup(&Game::from_uci_moves("g1f3 b8c6 e2e4").unwrap().board);
+        assert_eq!(transposed.len(), 1);
+        assert_eq!((transposed[0].chess_move.to_uci(), transposed[0].count), ("e7e5".to_string(), 1));
+        let merged = tree.lookup(&Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6").unwrap().board);
+        let summary = merged.iter().map(|stats| (stats.chess_move.to_uci(), stats.draws, stats.black_wins)).collect::<Vec<_>>();
+        assert_eq!(summary, [("f1b5".to_string(), 0, 1), ("f1c4".to_string(), 1, 0)]);
+        let after_nf3 = tree.lookup(&Game::from_uci_moves("e2e4 e7e5 g1f3").unwrap().board);
+        assert_eq!((after_nf3[0].count, after_nf3[0].draws), (1, 1));
+
+        let shallow = OpeningTree::build(five_games(), 1);
+        assert_eq!(shallow.len(), 1);
+        assert!(OpeningTree::default().is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+/*****************************
+*  PAWN STRUCTURE            *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The weaknesses and strengths of one side's pawns, e.g. for an evaluation term or for a GUI that highlights them.
+
+Every pawn is classified by its square, so the results can be drawn straight onto the board:
+```text
+doubled, // Shares its file with another pawn of its own color. All pawns on the file count, not just the extra ones.
+isolated, // No pawn of its own color on either adjacent file. An a- or h-pawn only has one adjacent file.
+passed, // No enemy pawn ahead of it on its own or an adjacent file, and no pawn of its own color ahead of it on its file.
+        // Enemy pawns it has already gone past don't count.
+backward, // Has pawns of its own color on an adjacent file, but all of them further ahead, so none can protect it
+          // when it advances, and the square in front of it is attacked or blocked by an enemy pawn.
+```
+Of two doubled pawns with the way clear, only the front one is passed: the one behind it can't run until it has gone.
+
+```
+# use alviny_task_3::{Game, Square};
+let example_game = Game::new_from_fen("4k3/8/8/3P4/8/3P4/P7/4K3 w - - 0 1".to_string());
+let structure = example_game.board.pawn_structure('w');
+assert_eq!(structure.passed, [Square::from_algebraic("a2").unwrap(), Square::from_algebraic("d5").unwrap()]);
+assert_eq!(structure.isolated.len(), 3);
+```
+*/
+
+use crate::square::File;
+use crate::{Board, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A struct to represent the pawn structure of one side (see docs for Board::pawn_structure). Every list of squares is
+/// ordered from a1 to h8.
+///
+/// ## Attributes
+/// ```text
+/// pawns: Vec<Square>, // Every pawn of the side.
+/// pawns_per_file: [u8; 8], // The number of pawns on each file, from the a-file to the h-file.
+/// doubled: Vec<Square>, // The pawns that share their file with another pawn of the side.
+/// isolated: Vec<Square>, // The pawns without a pawn of the side on an adjacent file.
+/// passed: Vec<Square>, // The pawns that no enemy pawn can stop, or capture, on their way to promotion.
+/// backward: Vec<Square>, // The pawns left behind by the pawns on the adjacent files, that can't advance safely.
+/// ```
+pub struct PawnStructure {
+    pub pawns: Vec<Square>,
+    pub pawns_per_file: [u8; 8],
+    pub doubled: Vec<Square>,
+    pub isolated: Vec<Square>,
+    pub passed: Vec<Square>,
+    pub backward: Vec<Square>,
+}
+impl PawnStructure {
+    /// A function to return the files with more than one pawn, from the a-file to the h-file.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::square::File;
+    /// let example_game = Game::new_from_fen("4k3/8/8/8/8/2P5/2P4P/4K2R w - - 0 1".to_string());
+    /// assert_eq!(example_game.board.pawn_structure('w').doubled_files(), [File::C]);
+    /// ```
+    pub fn doubled_files(&self) -> Vec<File> {
+        (0..8).filter(|&file| self.pawns_per_file[file as usize] > 1).filter_map(File::from_index).collect()
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn pawn_squares(board: &Board, color: char) -> Vec<(i8, i8)> {
+    let pawn = if color == 'w' { 'P' } else { 'p' };
+    board
+        .pieces_of(color)
+        .filter(|(_square, piece)| piece.to_fen_char() == pawn)
+        .map(|(square, _piece)| (square.file().index() as i8, square.rank().index() as i8))
+        .collect()
+} // The (file, rank) of every pawn of one color, 0 to 7 each
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to analyse the pawn structure of one side (see module docs for the definitions).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // The side whose pawns are analysed, 'w' or 'b'. The other side's pawns only matter as blockers.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a PawnStructure with the doubled, isolated, passed and backward pawns of the side.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new_from_fen("4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1".to_string());
+    /// let structure = example_game.board.pawn_structure('w');
+    /// assert_eq!(structure.backward, [Square::from_algebraic("d3").unwrap()]); // d4 is covered by the c5 pawn
+    /// ```
+    pub fn pawn_structure(&self, color: char) -> PawnStructure {
+        let (own, enemy) = (pawn_squares(self, color), pawn_squares(self, if color == 'w' { 'b' } else { 'w' }));
+        let forward: i8 = if color == 'w' { 1 } else { -1 };
+        let ahead = |rank: i8, of: i8| (rank - of) * forward > 0; // Whether `rank` is further up the board than `of`, for `color`
+        let mut structure = PawnStructure::default();
+        for &(file, _rank) in &own {
+            structure.pawns_per_file[file as usize] += 1;
+        }
+        for square in Square::all() {
+            let (file, rank) = (square.file().index() as i8, square.rank().index() as i8);
+            if !own.contains(&(file, rank)) {
+                continue;
+            }
+            let neighbours = own.iter().filter(|&&(other_file, _)| other_file.abs_diff(file) == 1).collect::<Vec<_>>();
+            let isolated = neighbours.is_empty();
+            let passed = !enemy.iter().any(|&(enemy_file, enemy_rank)| enemy_file.abs_diff(file) <= 1 && ahead(enemy_rank, rank))
+                && !own.iter().any(|&(own_file, own_rank)| own_file == file && ahead(own_rank, rank));
+            let stop_rank = rank + forward;
+            let stop_contested = enemy.contains(&(file, stop_rank))
+                || enemy.iter().any(|&(enemy_file, enemy_rank)| enemy_file.abs_diff(file) == 1 && enemy_rank == stop_rank + forward);
+            let backward = !isolated && neighbours.iter().all(|&&(_, neighbour_rank)| ahead(neighbour_rank, rank)) && stop_contested;
+
+            structure.pawns.push(square);
+            if structure.pawns_per_file[file as usize] > 1 {
+                structure.doubled.push(square);
+            }
+            if isolated {
+                structure.isolated.push(square);
+            }
+            if passed {
+                structure.passed.push(square);
+            }
+            if backward {
+                structure.backward.push(square);
+            }
+        }
+        structure
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::Game;
+
+    fn classified(fen: &str, color: char) -> [Vec<String>; 4] {
+        let structure = Game::new_from_fen(fen.to_string()).board.pawn_structure(color);
+        [structure.doubled, structure.isolated, structure.passed, structure.backward].map(|squares| squares.iter().map(|square| square.to_algebraic()).collect())
+    } // The doubled, isolated, passed and backward pawns, as algebraic squares
+
+    #[test]
+    fn test_starting_position() {
+        let structure = Game::new().board.pawn_structure('b');
+        assert_eq!(structure.pawns.len(), 8);
+        assert_eq!(structure.pawns_per_file, [1; 8]);
+        assert_eq!(classified("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 'w'), [vec![], vec![], vec![], vec![]] as [Vec<String>; 4]);
+    }
+    #[test]
+    fn test_edge_files() {
+        let [_, isolated, _, _] = classified("4k3/8/8/8/8/8/P1P4P/4K3 w - - 0 1", 'w');
+        assert_eq!(isolated, ["a2", "c2", "h2"]); // The a-pawn has no b-pawn, and the c-pawn has neither neighbour
+        let [_, isolated, _, _] = classified("4k3/8/8/8/8/1P6/P6P/4K3 w - - 0 1", 'w');
+        assert_eq!(isolated, ["h2"]);
+        let [_, isolated, _, _] = classified("4k3/8/8/8/8/8/P6P/4K3 w - - 0 1", 'w');
+        assert_eq!(isolated, ["a2", "h2"]); // The board doesn't wrap around from the h-file to the a-file
+    }
+    #[test]
+    fn test_passed_pawns() {
+        // The d5 pawn has gone past the c4 and e4 pawns, which don't stop it any more
+        let [_, _, passed, _] = classified("4k3/8/8/3P4/2p1p3/8/8/4K3 w - - 0 1", 'w');
+        assert_eq!(passed, ["d5"]);
+        let [_, _, passed, _] = classified("4k3/8/8/3P4/2p1p3/8/8/4K3 w - - 0 1", 'b');
+        assert_eq!(passed, ["c4", "e4"]);
+        // A pawn on an adjacent file ahead stops it, even if it's blocked itself, and so does one straight ahead
+        assert!(classified("4k3/8/4p3/3P4/8/8/8/4K3 w - - 0 1", 'w')[2].is_empty());
+        assert!(classified("4k3/3p4/8/3P4/8/8/8/4K3 w - - 0 1", 'w')[2].is_empty());
+        assert_eq!(classified("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", 'w')[2], ["d5"]);
+        // One file further away doesn't
+        assert_eq!(classified("4k3/5p2/8/3P4/8/8/8/4K3 w - - 0 1", 'w')[2], ["d5"]);
+        // Doubled passers: only the front one is passed
+        let [doubled, _, passed, _] = classified("4k3/8/8/3P4/8/3P4/8/4K3 w - - 0 1", 'w');
+        assert_eq!((doubled, passed), (vec!["d3".to_string(), "d5".to_string()], vec!["d5".to_string()]));
+        // For Black, ahead is down the board
+        assert_eq!(classified("4k3/8/8/8/2p5/8/3P4/4K3 b - - 0 1", 'b')[2], [] as [String; 0]);
+        assert_eq!(classified("4k3/8/8/8/2p5/3P4/8/4K3 b - - 0 1", 'b')[2], [] as [String; 0]);
+        assert_eq!(classified("4k3/8/8/8/3P4/2p5/8/4K3 b - - 0 1", 'b')[2], ["c3"]);
+    }
+    #[test]
+    fn test_backward_pawns() {
+        assert_eq!(classified("4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1", 'w')[3], ["d3"]);
+        assert!(classified("4k3/8/8/8/2P1P3/3P4/8/4K3 w - - 0 1", 'w')[3].is_empty()); // Nothing stops d4
+        assert!(classified("4k3/8/8/2p5/2P5/3PP3/8/4K3 w - - 0 1", 'w')[3].is_empty()); // e3 will protect it on d4
+        assert_eq!(classified("4k3/8/8/8/2P1P3/3Pp3/8/4K3 w - - 0 1", 'w')[3], [] as [String; 0]); // The e3 pawn doesn't cover d4
+        assert_eq!(classified("4k3/8/8/8/2PpP3/3P4/8/4K3 w - - 0 1", 'w')[3], ["d3"]); // Blocked
+        assert!(classified("4k3/8/8/2p5/8/3P4/8/4K3 w - - 0 1", 'w')[3].is_empty()); // Isolated, not backward
+        // The same structure for Black, flipped
+        assert_eq!(classified("4k3/8/3p4/2p1p3/2P5/8/8/4K3 b - - 0 1", 'b')[3], ["d6"]);
+    }
+    #[test]
+    fn test_colors_are_symmetric() {
+        for fen in ["4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1", "4k3/p7/1p6/8/3P4/3P2pP/P5P1/4K3 w - - 0 1"] {
+            let board = Game::new_from_fen(fen.to_string()).board;
+            let (white, black) = (board.pawn_structure('w'), board.flip_colors().pawn_structure('b'));
+            assert_eq!(white.pawns.len(), black.pawns.len());
+            assert_eq!(white.pawns_per_file, black.pawns_per_file);
+            for (white_squares, black_squares) in [(&white.doubled, &black.doubled), (&white.isolated, &black.isolated), (&white.passed, &black.passed), (&white.backward, &black.backward)] {
+                let flipped = |square: &crate::Square| (square.file().index(), 7 - square.rank().index());
+                let mut white_squares = white_squares.iter().map(flipped).collect::<Vec<_>>();
+                let mut black_squares = black_squares.iter().map(|square| (square.file().index(), square.rank().index())).collect::<Vec<_>>();
+                white_squares.sort();
+                black_squares.sort();
+                assert_eq!(white_squares, black_squares, "{}", fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,801 @@
+/*****************************
+*  PGN IMPORT                *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Reading and writing games in Portable Game Notation (PGN), the format chess databases and websites export games in.
+Read more about the format here:
+<https://www.saremo.de/pgn_standard.txt>
+
+A PGN game is a list of tag pairs, e.g. `[White "Morphy, Paul"]`, followed by the movetext: the moves in standard
+algebraic notation with move numbers, ending with the result. Games that don't start from the starting position give
+it in a FEN tag, and games of a variant name it in a Variant tag, e.g. `[Variant "Atomic"]`. The other tags, e.g. the
+players and the event, are kept on the Game (see Game::tag), and a result the final position doesn't show, e.g. after
+a resignation, becomes its status. A Game is a single line of play, so variations (`(...)`) and numeric annotation
+glyphs (`$1`) are not part of it and are skipped; Study::from_pgn() keeps them (see the study module). Files with many
+games are read one game at a time with read_games().
+
+The comments after a move (`{...}` and `; ...`) are kept as the move's MoveAnnotation. The clock and evaluation
+commands that e.g. lichess exports, `{ [%clk 0:02:45] [%eval -0.35] }`, are read into its clock and eval, and
+everything else, including commands that can't be read, is kept as comment text. to_pgn() writes them back.
+
+```
+# use alviny_task_3::Game;
+let pgn = "[Event \"Casual game\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 {Scholar's mate} Nc6 3. Bc4 Nf6?? 4. Qxf7# 1-0";
+let example_game = Game::from_pgn(pgn).unwrap();
+assert!(example_game.is_checkmate());
+```
+*/
+
+use std::fmt;
+use std::io::BufRead;
+use std::time::Duration;
+
+use crate::replay::ReplayError;
+use crate::search::Score;
+use crate::{try_parse_fen, FenError, Game, GameStatus, Variant};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why a PGN game can't be read.
+///
+/// ```text
+/// InvalidTag(String), // A line starting with '[' isn't a tag pair like [Name "value"].
+/// InvalidFen(FenError), // The FEN tag can't be read (see docs for FenError).
+/// UnknownVariant(String), // The Variant tag names rules that aren't built in (see docs for Variant::from_name). Holds the name.
+/// UnclosedComment, // A '{' comment never ends.
+/// UnclosedVariation, // A '(' variation never ends, or a ')' has no '('.
+/// IllegalMove(ReplayError), // A move of the movetext can't be played (see docs for ReplayError).
+/// InGame(u64, Box<PgnError>), // A game of a file can't be read (see docs for read_games). Holds the byte offset of its first line and why.
+/// Io(String), // The file can't be read. Holds the message of the I/O error.
+/// ```
+pub enum PgnError {
+    InvalidTag(String),
+    InvalidFen(FenError),
+    UnknownVariant(String),
+    UnclosedComment,
+    UnclosedVariation,
+    IllegalMove(ReplayError),
+    InGame(u64, Box<PgnError>),
+    Io(String),
+}
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(line) => write!(f, "invalid tag pair: {}", line),
+            PgnError::InvalidFen(fen_error) => write!(f, "invalid FEN tag: {}", fen_error),
+            PgnError::UnknownVariant(name) => write!(f, "unknown variant: {}", name),
+            PgnError::UnclosedComment => write!(f, "a comment is never closed"),
+            PgnError::UnclosedVariation => write!(f, "the parentheses of the variations don't match"),
+            PgnError::IllegalMove(replay_error) => write!(f, "{}", replay_error),
+            PgnError::InGame(offset, pgn_error) => write!(f, "game at byte {}: {}", offset, pgn_error),
+            PgnError::Io(message) => write!(f, "can't read the file: {}", message),
+        }
+    }
+}
+impl std::error::Error for PgnError {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A struct to represent what a PGN comment says about a move. Every move of a Game has one (see docs for Game::annotation).
+///
+/// ## Attributes
+/// ```text
+/// clock: Option<Duration>, // The time left on the mover's clock after the move, from a [%clk h:mm:ss] command.
+/// eval: Option<Score>, // The evaluation after the move from White's point of view, from a [%eval 0.35] or [%eval #-3] command.
+/// comment: Option<String>, // The rest of the comments, including commands that aren't clk or eval or can't be read.
+/// ```
+pub struct MoveAnnotation {
+    pub clock: Option<Duration>,
+    pub eval: Option<Score>,
+    pub comment: Option<String>,
+}
+
+/// A struct to represent the games of a PGN file, read one at a time (see docs for read_games).
+pub struct PgnGames<R> {
+    reader: R,
+    offset: u64,
+    pending_line: Option<(u64, String)>,
+    failed: bool,
+}
+impl<R: BufRead> Iterator for PgnGames<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Result<Game, PgnError>> {
+        let mut pgn = String::new();
+        let mut start = None;
+        let mut in_movetext = false;
+        let mut in_comment = false;
+        loop {
+            let (line_offset, line) = match self.pending_line.take() {
+                Some(line) => line,
+                None => match self.read_line() {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(pgn_error) => return Some(Err(pgn_error)),
+                },
+            };
+            let trimmed = line.trim();
+            if start.is_none() && trimmed.is_empty() {
+                continue;
+            }
+            if in_movetext && !in_comment && trimmed.starts_with('[') {
+                self.pending_line = Some((line_offset, line));
+                break; // The tags of the next game, after a game without a result
+            }
+            start.get_or_insert(line_offset);
+            pgn.push_str(&line);
+            if in_comment || (!trimmed.is_empty() && !trimmed.starts_with('[')) {
+                in_movetext = true;
+                if ends_with_result(&line, &mut in_comment) {
+                    break;
+                }
+            }
+        }
+        let start = start?;
+        Some(Game::from_pgn(&pgn).map_err(|pgn_error| PgnError::InGame(start, Box::new(pgn_error))))
+    }
+}
+impl<R: BufRead> PgnGames<R> {
+    fn read_line(&mut self) -> Result<Option<(u64, String)>, PgnError> {
+        if self.failed {
+            return Ok(None);
+        }
+        let mut bytes = vec![];
+        let length = self.reader.read_until(b'\n', &mut bytes).map_err(|io_error| {
+            self.failed = true;
+            PgnError::Io(io_error.to_string())
+        })?;
+        if length == 0 {
+            return Ok(None);
+        }
+        let line_offset = self.offset;
+        self.offset += length as u64;
+        let mut line = String::from_utf8_lossy(&bytes).into_owned();
+        if line_offset == 0 && line.starts_with('\u{feff}') {
+            line.remove(0); // A byte order mark
+        }
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        Ok(Some((line_offset, line)))
+    } // Reads the next line and the byte offset it starts at, or None at the end of the file. Bytes that aren't UTF-8 are
+    // replaced rather than failing the whole file, and the reading stops after an I/O error
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+pub(crate) const ROSTER: [(&str, &str); 6] =
+    [("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"), ("White", "?"), ("Black", "?")]; // The seven tag roster but Result, with the values for unknown
+
+const GAME_TAGS: [&str; 4] = ["Result", "SetUp", "FEN", "Variant"]; // The tags that come from the game rather than its tags
+
+pub(crate) fn start_game(tags: &[(String, String)]) -> Result<Game, PgnError> {
+    let tag = |tag_name: &str| tags.iter().find(|(name, _value)| name == tag_name).map(|(_name, value)| value.as_str());
+    let variant = match tag("Variant") {
+        None => Variant::Standard,
+        Some(name) if ["Chess960", "From Position"].iter().any(|standard| standard.eq_ignore_ascii_case(name)) => Variant::Standard,
+        Some(name) => Variant::from_name(name).ok_or_else(|| PgnError::UnknownVariant(name.to_string()))?,
+    };
+    match tag("FEN") {
+        Some(fen) => {
+            try_parse_fen(fen).map_err(PgnError::InvalidFen)?.validate().map_err(FenError::IllegalPosition).map_err(PgnError::InvalidFen)?;
+            Ok(Game::new_from_fen_with_variant(fen.to_string(), variant))
+        }
+        None => Ok(Game::new_with_variant(variant)),
+    }
+} // The position before the first move, by the rules of the Variant tag. lichess writes "Chess960" and "From Position"
+// for games that are played by the standard rules from the FEN tag
+
+pub(crate) fn format_tag(name: &str, value: &str) -> String {
+    format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+} // Writes a tag pair on its own line, escaping the value
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+} // Reads a tag pair line like [Name "value"], unescaping the value.
+
+pub(crate) enum MovetextToken {
+    Move(String),
+    Comment(String),
+    Nag(u8),
+    StartVariation,
+    EndVariation,
+}
+
+const SUFFIX_NAGS: [(&str, u8); 6] = [("!!", 3), ("??", 4), ("!?", 5), ("?!", 6), ("!", 1), ("?", 2)];
+
+pub(crate) fn split_pgn(pgn: &str) -> Result<(Vec<(String, String)>, String), PgnError> {
+    let mut tags = vec![];
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        if movetext.trim().is_empty() && line.trim_start().starts_with('[') {
+            tags.push(parse_tag(line).ok_or_else(|| PgnError::InvalidTag(line.trim().to_string()))?);
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+    Ok((tags, movetext))
+} // Splits a game into its tag pairs and its movetext
+
+pub(crate) fn tokenize_movetext(movetext: &str) -> Result<Vec<MovetextToken>, PgnError> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let finish_token = |tokens: &mut Vec<MovetextToken>, token: &mut String| {
+        let text = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.'); // Move numbers, also "12.e4"
+        if let Some(nag) = text.strip_prefix('$') {
+            tokens.extend(nag.parse::<u8>().ok().map(MovetextToken::Nag));
+        } else if !text.is_empty() && !RESULTS.contains(&token.as_str()) {
+            match SUFFIX_NAGS.iter().find(|(suffix, _nag)| text.len() > suffix.len() && text.ends_with(suffix)) {
+                Some((suffix, nag)) => {
+                    tokens.push(MovetextToken::Move(text[..text.len() - suffix.len()].to_string()));
+                    tokens.push(MovetextToken::Nag(*nag));
+                }
+                None => tokens.push(MovetextToken::Move(text.to_string())),
+            }
+        }
+        token.clear();
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                finish_token(&mut tokens, &mut token);
+                let mut comment = String::new();
+                loop {
+                    match chars.next().ok_or(PgnError::UnclosedComment)? {
+                        '}' => break,
+                        c => comment.push(c),
+                    }
+                }
+                tokens.push(MovetextToken::Comment(comment));
+            }
+            ';' => {
+                finish_token(&mut tokens, &mut token);
+                tokens.push(MovetextToken::Comment(chars.by_ref().take_while(|c| *c != '\n').collect()));
+            }
+            '(' | ')' => {
+                finish_token(&mut tokens, &mut token);
+                tokens.push(if c == '(' { MovetextToken::StartVariation } else { MovetextToken::EndVariation });
+            }
+            c if c.is_whitespace() => finish_token(&mut tokens, &mut token),
+            c => token.push(c),
+        }
+    }
+    finish_token(&mut tokens, &mut token);
+    Ok(tokens)
+} // Splits the movetext into moves, comments, NAGs and the parentheses around variations, leaving out move numbers
+// and the result. The suffixes "!", "?", "!!", "??", "!?" and "?!" become the NAGs $1 to $6
+
+fn movetext_moves(movetext: &str) -> Result<Vec<(String, Vec<String>)>, PgnError> {
+    let mut moves: Vec<(String, Vec<String>)> = vec![];
+    let mut variation_depth = 0;
+    for token in tokenize_movetext(movetext)? {
+        match token {
+            MovetextToken::StartVariation => variation_depth += 1,
+            MovetextToken::EndVariation if variation_depth == 0 => return Err(PgnError::UnclosedVariation),
+            MovetextToken::EndVariation => variation_depth -= 1,
+            _ if variation_depth > 0 => {}
+            MovetextToken::Move(san) => moves.push((san, vec![])),
+            MovetextToken::Comment(comment) => {
+                if let Some((_san, comments)) = moves.last_mut() {
+                    comments.push(comment);
+                } // Comments before the first move are about the game rather than a move, and are skipped
+            }
+            MovetextToken::Nag(_) => {}
+        }
+    }
+    if variation_depth != 0 {
+        return Err(PgnError::UnclosedVariation);
+    }
+    Ok(moves)
+} // Picks the moves of the main line and the comments after each of them out of the movetext, leaving out variations
+// and NAGs
+
+fn parse_clock(text: &str) -> Option<Duration> {
+    let (hours_and_minutes, seconds) = text.rsplit_once(':')?;
+    let (hours, minutes) = hours_and_minutes.split_once(':').unwrap_or(("0", hours_and_minutes));
+    let (whole_seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    if fraction.len() > 3 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let milliseconds = format!("{:0<3}", fraction).parse::<u64>().ok()?;
+    let (hours, minutes, whole_seconds) = (hours.parse::<u64>().ok()?, minutes.parse::<u64>().ok()?, whole_seconds.parse::<u64>().ok()?);
+    if minutes >= 60 || whole_seconds >= 60 {
+        return None;
+    }
+    Some(Duration::from_millis(((hours * 60 + minutes) * 60 + whole_seconds) * 1000 + milliseconds))
+} // Reads the argument of [%clk], e.g. "0:02:45" or "1:05:00.5"
+
+fn parse_eval(text: &str) -> Option<Score> {
+    let text = text.split(',').next()?; // Some sites add the depth, e.g. "0.35,24"
+    if let Some(moves) = text.strip_prefix('#') {
+        return moves.parse::<i32>().ok().map(Score::Mate);
+    }
+    let pawns = text.parse::<f64>().ok().filter(|pawns| pawns.is_finite() && pawns.abs() < 1000.0)?;
+    Some(Score::Centipawns((pawns * 100.0).round() as i32))
+} // Reads the argument of [%eval], e.g. "-0.35" or "#-3"
+
+pub(crate) fn parse_annotation(comments: &[String]) -> MoveAnnotation {
+    let mut annotation = MoveAnnotation::default();
+    let mut text = vec![];
+    for comment in comments {
+        let mut rest = comment.as_str();
+        while let Some(start) = rest.find("[%") {
+            text.push(rest[..start].trim().to_string());
+            let Some(length) = rest[start..].find(']') else {
+                rest = &rest[start..];
+                break;
+            };
+            let command = &rest[start..start + length + 1];
+            let mut words = command[2..command.len() - 1].split_whitespace();
+            match (words.next(), words.next(), words.next()) {
+                (Some("clk"), Some(clock), None) if let Some(clock) = parse_clock(clock) => annotation.clock = Some(clock),
+                (Some("eval"), Some(eval), None) if let Some(eval) = parse_eval(eval) => annotation.eval = Some(eval),
+                _ => text.push(command.to_string()),
+            }
+            rest = &rest[start + length + 1..];
+        }
+        text.push(rest.trim().to_string());
+    }
+    text.retain(|part| !part.is_empty());
+    annotation.comment = (!text.is_empty()).then(|| text.join(" "));
+    annotation
+} // Reads the clock and evaluation out of the comments after a move, and keeps the rest as the comment
+
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let mut text = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if clock.subsec_millis() != 0 {
+        text.push_str(format!(".{:03}", clock.subsec_millis()).trim_end_matches('0'));
+    }
+    text
+} // Writes the argument of [%clk]. Inverse function for parse_clock()
+
+fn format_eval(eval: Score) -> String {
+    match eval {
+        Score::Centipawns(centipawns) => {
+            let sign = if centipawns < 0 { "-" } else { "" };
+            format!("{}{}.{:02}", sign, centipawns.unsigned_abs() / 100, centipawns.unsigned_abs() % 100)
+        }
+        Score::Mate(moves) => format!("#{}", moves),
+    }
+} // Writes the argument of [%eval]. Inverse function for parse_eval()
+
+pub(crate) fn format_annotation(annotation: &MoveAnnotation) -> Option<String> {
+    let mut parts = vec![];
+    if let Some(eval) = annotation.eval {
+        parts.push(format!("[%eval {}]", format_eval(eval)));
+    }
+    if let Some(clock) = annotation.clock {
+        parts.push(format!("[%clk {}]", format_clock(clock)));
+    }
+    if let Some(comment) = &annotation.comment {
+        parts.push(comment.replace('}', ")"));
+    }
+    (!parts.is_empty()).then(|| format!("{{ {} }}", parts.join(" ")))
+} // Writes the comment of a move, or None if there is nothing to say. A '}' would end the comment early, so it is replaced
+
+fn ends_with_result(line: &str, in_comment: &mut bool) -> bool {
+    let mut outside_comments = String::new();
+    for c in line.chars() {
+        match (c, *in_comment) {
+            ('}', true) => *in_comment = false,
+            (_, true) => {}
+            ('{', false) => *in_comment = true,
+            (';', false) => break,
+            (c, false) => outside_comments.push(c),
+        }
+    }
+    outside_comments.split_whitespace().last().is_some_and(|token| RESULTS.contains(&token))
+} // Checks whether a line of movetext ends with a result, which ends the game, keeping track of '{' comments that go on
+// over several lines. Those can contain anything, including empty lines, tags and results
+
+pub(crate) fn wrap_movetext(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    text.push_str(&line);
+    text.push('\n');
+    text
+} // Joins the movetext with spaces into lines of at most 80 characters, unless a token is longer
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to read the games of a PGN file one at a time, e.g. a database with thousands of games. Only the game
+/// being read is kept in memory.
+///
+/// ## Arguments
+/// ```text
+/// reader: impl BufRead, // The file, e.g. a BufReader around a File.
+///
+/// ```
+/// ## Returns
+/// This function returns an iterator over the games, each read like Game::from_pgn() with its own tags and result. A
+/// game ends with its result, or where the tags of the next game start. A game that can't be read is a PgnError::InGame
+/// with the byte offset of its first line, and the games after it are read as usual. An I/O error is a PgnError::Io,
+/// and ends the iteration.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::pgn::{self, PgnError};
+/// let file = "[Event \"A\"]\n\n1. e4 e5 1-0\n\n[Event \"B\"]\n\n1. e4 e4 0-1\n\n1. d4 *\n";
+/// let games = pgn::read_games(file.as_bytes()).collect::<Vec<_>>();
+/// assert_eq!(games.len(), 3);
+/// assert!(matches!(games[1], Err(PgnError::InGame(27, _))));
+/// assert_eq!(games[2].as_ref().unwrap().history.len(), 1);
+/// assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("A"));
+/// assert_eq!(games[0].as_ref().unwrap().result_string(), "1-0");
+/// ```
+pub fn read_games<R: BufRead>(reader: R) -> PgnGames<R> {
+    PgnGames { reader, offset: 0, pending_line: None, failed: false }
+}
+
+impl Game {
+    /// A function to create a game by reading a single game in PGN.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// pgn: &str, // The tag pairs and the movetext of one game.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game after the last move of the main line, or a PgnError (see docs for PgnError). The
+    /// Variant tag gives the rules (see docs for Variant::from_name), and the other tag pairs are kept on the game (see
+    /// docs for tag). Variations and NAGs are skipped, since a Game is a single line: use Study::from_pgn() to keep them. A result the final position doesn't show, taken from the end
+    /// of the movetext or else the Result tag, becomes the game status: a resignation or a draw by agreement, or a time
+    /// forfeit if the Termination tag is "time forfeit".
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, GameStatus};
+    /// let example_game = Game::from_pgn("[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 (1. e3 Kd7) 1... Kd7 *").unwrap();
+    /// assert_eq!(example_game.fen, "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+    /// let example_game = Game::from_pgn("[White \"Anderssen\"]\n\n1. e4 e5 2. Bc4 0-1").unwrap();
+    /// assert_eq!(example_game.tag("White"), Some("Anderssen"));
+    /// assert_eq!(example_game.game_status, GameStatus::BlackWinsByResignation);
+    /// ```
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        let (tags, movetext) = split_pgn(pgn)?;
+        let mut game = start_game(&tags)?;
+        let moves = movetext_moves(&movetext)?;
+        let first_ply = game.history.len();
+        game.play_moves(&moves.iter().map(|(san, _comments)| san.as_str()).collect::<Vec<&str>>()).map_err(PgnError::IllegalMove)?;
+        for (ply, (_san, comments)) in moves.iter().enumerate() {
+            game.set_annotation(first_ply + ply, parse_annotation(comments));
+        }
+        let tag = |tag_name: &str| tags.iter().find(|(name, _value)| name == tag_name).map(|(_name, value)| value.as_str());
+        let result = movetext.split_whitespace().last().filter(|token| RESULTS.contains(token)).or(tag("Result"));
+        if !game.game_status.is_game_over() {
+            let time_forfeit = tag("Termination").is_some_and(|termination| termination.eq_ignore_ascii_case("time forfeit"));
+            game.game_status = match (result, time_forfeit) {
+                (Some("1-0"), false) => GameStatus::WhiteWinsByResignation,
+                (Some("1-0"), true) => GameStatus::WhiteWinsByTimeForfeit,
+                (Some("0-1"), false) => GameStatus::BlackWinsByResignation,
+                (Some("0-1"), true) => GameStatus::BlackWinsByTimeForfeit,
+                (Some("1/2-1/2"), false) => GameStatus::DrawByAgreement,
+                (Some("1/2-1/2"), true) => GameStatus::TimeForfeitDraw,
+                _ => GameStatus::InProgress,
+            };
+        }
+        game.tags = tags
+            .into_iter()
+            .filter(|(name, value)| !GAME_TAGS.contains(&name.as_str()) && !ROSTER.contains(&(name.as_str(), value.as_str())))
+            .collect();
+        Ok(game)
+    }
+
+    /// A function to write the game in PGN.
+    ///
+    /// ## Returns
+    /// This function returns the game as the seven standard tag pairs, then the Variant tag if the game isn't standard
+    /// chess, the SetUp and FEN tags if it didn't start from the variant's starting position, then the game's other tags, and the movetext with the annotation of every move.
+    /// Tags of the seven the game doesn't have are "?" (see docs for set_tag). Lines are at most 80 characters long,
+    /// unless a comment is longer.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::from_pgn("1. f3 e5 2. g4 { [%clk 0:00:57] } 2... Qh4# 0-1").unwrap();
+    /// assert!(example_game.to_pgn().ends_with("[Result \"0-1\"]\n\n1. f3 e5 2. g4 { [%clk 0:00:57] } 2... Qh4# 0-1\n"));
+    /// ```
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in ROSTER {
+            pgn.push_str(&format_tag(name, self.tag(name).unwrap_or(value)));
+        }
+        pgn.push_str(&format_tag("Result", self.result_string()));
+        if self.variant != Variant::Standard {
+            pgn.push_str(&format_tag("Variant", self.variant.rules().name()));
+        }
+        if self.start_fen != Game::new_with_variant(self.variant.clone()).fen {
+            pgn.push_str(&format_tag("SetUp", "1"));
+            pgn.push_str(&format_tag("FEN", &self.start_fen));
+        }
+        for (name, value) in self.tags.iter().filter(|(name, _value)| !ROSTER.iter().any(|(roster_name, _)| roster_name == name)) {
+            pgn.push_str(&format_tag(name, value));
+        }
+        if self.tag("Termination").is_none() && self.game_status.is_time_forfeit() {
+            pgn.push_str(&format_tag("Termination", "time forfeit"));
+        } // So that from_pgn() reads the result back the same way
+        pgn.push('\n');
+
+        let mut tokens = vec![];
+        let mut replay = Game::new_from_fen_with_variant(self.start_fen.clone(), self.variant.clone());
+        let mut after_comment = true;
+        for (ply, chess_move) in self.history.iter().enumerate() {
+            let san = replay.to_san(chess_move).unwrap_or_else(|| chess_move.to_uci());
+            tokens.push(match (replay.board.active_player, after_comment) {
+                ('w', _) => format!("{}. {}", replay.fullmove_number(), san),
+                (_, true) => format!("{}... {}", replay.fullmove_number(), san),
+                _ => san,
+            }); // Kept together with its move number, so that a line never ends in a move number
+            let comment = self.annotation(ply).and_then(format_annotation);
+            after_comment = comment.is_some();
+            tokens.extend(comment);
+            replay.make_uci_move(&chess_move.to_uci());
+        }
+        tokens.push(self.result_string().to_string());
+
+        pgn.push_str(&wrap_movetext(&tokens));
+        pgn
+    }
+
+    /// A function to return the value of one of the game's PGN tag pairs, e.g. the name of the white player.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// name: &str, // The name of the tag, e.g. "White". Case sensitive, as in PGN.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the value, or None if the game doesn't have the tag. Result, SetUp, FEN and Variant are
+    /// never tags of the game, since they come from its status, start_fen and variant, and a tag of the seven tag roster
+    /// that from_pgn() reads as unknown, e.g. [White "?"], isn't kept.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag_name, _value)| tag_name == name).map(|(_name, value)| value.as_str())
+    }
+
+    /// A function to return every PGN tag pair of the game, in the order they were read or set.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// A function to set one of the game's PGN tag pairs, which to_pgn() writes, e.g. the players or the event.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// name: &str, // The name of the tag, e.g. "Event".
+    /// value: &str, // The new value. A tag the game already has keeps its place.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the tag was set, or false if it is Result, SetUp, FEN or Variant (see docs for tag).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.set_tag("White", "Morphy, Paul"));
+    /// assert!(!example_game.set_tag("Result", "1-0"));
+    /// assert!(example_game.to_pgn().contains("[White \"Morphy, Paul\"]"));
+    /// ```
+    pub fn set_tag(&mut self, name: &str, value: &str) -> bool {
+        if GAME_TAGS.contains(&name) {
+            return false;
+        }
+        match self.tags.iter_mut().find(|(tag_name, _value)| tag_name == name) {
+            Some((_name, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        true
+    }
+
+    /// A function to return the annotation of a move: its clock, evaluation and comment (see docs for MoveAnnotation).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// ply: usize, // The index of the move in history, starting from 0.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the annotation, or None if there is no such move. Moves made with make_move() and the like
+    /// have an empty annotation.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::search::Score;
+    /// let example_game = Game::from_pgn("1. e4 { [%eval 0.3] Best by test } 1... e5").unwrap();
+    /// assert_eq!(example_game.annotation(0).unwrap().eval, Some(Score::Centipawns(30)));
+    /// assert_eq!(example_game.annotation(0).unwrap().comment.as_deref(), Some("Best by test"));
+    /// assert_eq!(example_game.annotation(1).unwrap().eval, None);
+    /// ```
+    pub fn annotation(&self, ply: usize) -> Option<&MoveAnnotation> {
+        self.annotations.get(ply)
+    }
+
+    /// A function to change the annotation of a move, e.g. to record the clock after a move in a timed game.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// ply: usize, // The index of the move in history, starting from 0.
+    /// annotation: MoveAnnotation, // The new annotation (see docs for MoveAnnotation).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the annotation was changed, or false if there is no such move.
+    pub fn set_annotation(&mut self, ply: usize, annotation: MoveAnnotation) -> bool {
+        if ply >= self.history.len() {
+            return false;
+        }
+        self.annotations.resize(self.history.len(), MoveAnnotation::default()); // In case history was edited directly
+        self.annotations[ply] = annotation;
+        true
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotated_game() {
+        let pgn = "[Event \"Paris\"]\n[White \"Morphy, Paul \\\"the pride\\\"\"]\n[Result \"1-0\"]\n\n\
+                   1. e4 e5 2. Nf3 d6 3. d4 Bg4 $6 {This is a weak move\nalready.} 4. dxe5 Bxf3 5. Qxf3 dxe5\n\
+                   6. Bc4 Nf6 7. Qb3 (7. Qg3 ; sharper\n 7... Qd7) 7... Qe7 8. Nc3 c6 9.Bg5 1-0";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game.history.len(), 17);
+        assert_eq!(game.fen, "rn2kb1r/pp2qppp/2p2n2/4p1B1/2B1P3/1QN5/PPP2PPP/R3K2R b KQkq - 1 9");
+        assert_eq!(parse_tag("[White \"Morphy, Paul \\\"the pride\\\"\"]").unwrap().1, "Morphy, Paul \"the pride\"");
+    }
+    #[test]
+    fn test_broken_pgn() {
+        assert_eq!(Game::from_pgn("[Event Paris]\n\n1. e4 *"), Err(PgnError::InvalidTag("[Event Paris]".to_string())));
+        assert!(matches!(Game::from_pgn("[FEN \"8/8/8 w - - 0 1\"]\n\n*"), Err(PgnError::InvalidFen(_))));
+        assert_eq!(Game::from_pgn("1. e4 {unfinished e5"), Err(PgnError::UnclosedComment));
+        assert_eq!(Game::from_pgn("1. e4 (1. d4 e5"), Err(PgnError::UnclosedVariation));
+        assert_eq!(Game::from_pgn("1. e4 ) e5"), Err(PgnError::UnclosedVariation));
+        let replay_error = match Game::from_pgn("1. e4 e5 2. Ke3 *") {
+            Err(PgnError::IllegalMove(replay_error)) => replay_error,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!((replay_error.ply, replay_error.move_text.as_str()), (2, "Ke3"));
+        assert_eq!(replay_error.fen_before, "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+    }
+    #[test]
+    fn test_clock_and_eval_round_trip() {
+        // The Immortal Game, with made-up clocks and evaluations in the style of a lichess export
+        let pgn = "[Event \"Casual game\"]\n[Site \"https://lichess.org/xxxxxxxx\"]\n[Result \"1-0\"]\n\n\
+            1. e4 { [%eval 0.35] [%clk 0:04:56] } 1... e5 { [%eval 0.14] [%clk 0:04:57] } 2. f4 { [%eval 0.1] [%clk 0:04:46] }\n\
+            2... exf4 { [%eval -0.6] [%clk 0:04:47] } 3. Bc4 { [%eval 0.06] [%clk 0:04:45] } 3... Qh4+ { [%eval -0.33] [%clk 0:04:39] }\n\
+            4. Kf1 { [%eval -0.82] [%clk 0:04:41] } 4... b5 { [%eval -0.87] [%clk 0:04:27] } 5. Bxb5 { [%eval -0.91] [%clk 0:04:32] }\n\
+            5... Nf6 { [%eval -0.33] [%clk later] } 6. Nf3 { [%eval -0.11] [%clk 0:04:28] } 6... Qh6 { [%eval -0.29] [%clk 0:04:07] }\n\
+            7. d3 { [%eval -0.01] [%clk 0:04:27] } 7... Nh5 { [%eval -0.56] [%clk 0:04:05] } 8. Nh4 { [%eval -1.29] [%clk 0:04:17] }\n\
+            8... Qg5 { [%eval -0.77] [%clk 0:04:04] } 9. Nf5 { [%eval -0.82] [%clk 0:04:12] } 9... c6 { [%eval -0.14] [%clk 0:03:52] }\n\
+            10. g4 { [%eval 0.20] [%clk 0:04:05] } 10... Nf6 { [%eval 0.03] [%clk 0:03:45] } 11. Rg1 { [%eval -0.06] [%clk 0:04:06.5] }\n\
+            11... cxb5 { [%eval 0.55] [%clk 0:03:42] } 12. h4 { [%eval -0.20] [%clk 0:03:53] } 12... Qg6 { [%eval -0.65] [%clk 0:03:34] }\n\
+            13. h5 { [%eval -0.75] [%clk 0:03:42] } 13... Qg5 { [%eval -0.18] [%clk 0:03:23] } 14. Qf3 { [%eval -0.17] [%clk 0:03:35] }\n\
+            14... Ng8 { [%eval -0.05] [%clk 0:03:16] } 15. Bxf4 { [%eval 0.08] [%clk 0:03:26] } 15... Qf6 { [%eval -0.34] [%clk 0:03:06] }\n\
+            16. Nc3 { [%eval -0.05] [%clk 0:03:20] } 16... Bc5 { [%eval 0.52] [%clk 0:03:05] } 17. Nd5 { [%csl Gd5] [%eval 0.79] [%clk 0:03:10] }\n\
+            17... Qxb2 { [%eval 1.11] [%clk 0:03:02] } 18. Bd6 { A rook sacrifice follows. [%eval 1.85] [%clk 0:03:04] }\n\
+            18... Bxg1 { [%eval 1.96] [%clk 0:02:52] } 19. e5 { [%eval 2.21] [%clk 0:02:52] } 19... Qxa1+ { [%eval 2.74] [%clk 0:02:41] }\n\
+            20. Ke2 { [%eval 2.37] [%clk 0:02:42] } 20... Na6 { [%eval #5] [%clk 0:02:39] } 21. Nxg7+ { [%eval #3] [%clk 0:02:31] }\n\
+            21... Kd8 { [%eval #2] [%clk 0:02:37] } 22. Qf6+ { [%eval #1] [%clk 0:02:29] } 22... Nxf6 { [%eval #1] [%clk 0:02:34] }\n\
+            23. Be7# { [%clk 0:02:22] } 1-0";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert!(game.is_checkmate());
+        assert_eq!(game.annotation(26).unwrap().clock, Some(Duration::from_secs(3 * 60 + 35))); // 14. Qf3
+        assert_eq!(game.annotation(38).unwrap().eval, Some(Score::Centipawns(237))); // 20. Ke2
+        assert_eq!(game.annotation(39).unwrap().eval, Some(Score::Mate(5)));
+        assert_eq!(game.annotation(20).unwrap().clock, Some(Duration::from_millis(246_500)));
+        let malformed = game.annotation(9).unwrap();
+        assert_eq!((malformed.clock, malformed.comment.as_deref()), (None, Some("[%clk later]")));
+        assert_eq!(game.annotation(32).unwrap().comment.as_deref(), Some("[%csl Gd5]"));
+        assert_eq!(game.annotation(34).unwrap().comment.as_deref(), Some("A rook sacrifice follows."));
+        assert_eq!(game.annotation(44).unwrap().eval, None);
+
+        let exported = game.to_pgn();
+        assert!(exported.lines().all(|line| line.len() <= 80));
+        let movetext = exported.split_whitespace().collect::<Vec<&str>>().join(" ");
+        assert!(movetext.contains(" 20. Ke2 { [%eval 2.37] [%clk 0:02:42] } 20... Na6 { [%eval #5] [%clk 0:02:39] } 21. Nxg7+ "));
+        assert!(movetext.contains(" 17. Nd5 { [%eval 0.79] [%clk 0:03:10] [%csl Gd5] } 17... Qxb2 "));
+        let reimported = Game::from_pgn(&exported).unwrap();
+        assert_eq!(reimported.history, game.history);
+        assert_eq!(reimported.annotations, game.annotations);
+        assert_eq!(reimported.to_pgn(), exported);
+
+        let mut undone = game.clone();
+        undone.undo_move();
+        assert_eq!(undone.annotation(44), None);
+        assert!(!undone.set_annotation(44, MoveAnnotation::default()));
+    }
+    #[test]
+    fn test_tags_and_result_round_trip() {
+        let pgn = "[Event \"Casual \\\"Game\\\"\"]\n[Site \"London\"]\n[Date \"1851.06.21\"]\n[Round \"?\"]\n[White \"Anderssen\"]\n\
+            [Black \"Kieseritzky\"]\n[Result \"0-1\"]\n[ECO \"C33\"]\n\n1. e4 e5 2. f4 exf4 0-1";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game.game_status, GameStatus::BlackWinsByResignation);
+        assert_eq!(game.tag("Event"), Some("Casual \"Game\""));
+        assert_eq!(game.tag("ECO"), Some("C33"));
+        assert_eq!((game.tag("Result"), game.tag("Round")), (None, None));
+        assert_eq!(game.to_pgn(), pgn.replace("exf4 0-1", "exf4 0-1\n"));
+        assert_eq!(Game::from_pgn(&game.to_pgn()).unwrap(), game);
+
+        let flagged = Game::from_pgn("[Result \"1/2-1/2\"]\n[Termination \"time forfeit\"]\n\n1. e4 e5 1/2-1/2").unwrap();
+        assert_eq!(flagged.game_status, GameStatus::TimeForfeitDraw);
+        let mut unfinished = Game::from_pgn("[Result \"*\"]\n\n1. e4 *").unwrap();
+        assert_eq!(unfinished.game_status, GameStatus::InProgress);
+        assert!(unfinished.set_tag("Round", "3"));
+        assert!(unfinished.set_tag("Annotator", "alviny"));
+        assert!(unfinished.to_pgn().starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"3\"]\n"));
+        assert!(unfinished.to_pgn().contains("[Result \"*\"]\n[Annotator \"alviny\"]\n\n1. e4 *"));
+        let mated = Game::from_pgn("1. f3 e5 2. g4 Qh4# 1-0").unwrap(); // A wrong result doesn't change what the board shows
+        assert_eq!(mated.game_status, GameStatus::BlackWinsByCheckmate);
+    }
+    #[test]
+    fn test_read_games() {
+        let first = "[Event \"First\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 {The threat:\n\n[%csl Rf7] 1-0\n} 3... Nf6?? 4. Qxf7# 1-0\n\n";
+        let corrupt = "[Event \"Second\"]\n[Result \"0-1\"]\n\n1. e4 e5 2. Ke3 Nc6 0-1\n\n";
+        let third = "[Event \"Third\"]\n\n1. d4 d5 2. c4 {; not a comment end} dxc4\n*\n";
+        let file = format!("\u{feff}{}{}{}", first, corrupt, third);
+        let games = read_games(file.as_bytes()).collect::<Vec<Result<Game, PgnError>>>();
+        assert_eq!(games.len(), 3);
+        assert!(games[0].as_ref().unwrap().is_checkmate());
+        assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("First"));
+        assert_eq!(games[0].as_ref().unwrap().result_string(), "1-0");
+        let offset = ("\u{feff}".len() + first.len()) as u64;
+        assert!(matches!(&games[1], Err(PgnError::InGame(start, pgn_error)) if *start == offset && matches!(**pgn_error, PgnError::IllegalMove(_))));
+        assert_eq!(games[2].as_ref().unwrap().history.len(), 4);
+        assert_eq!(games[2].as_ref().unwrap().tag("Event"), Some("Third"));
+        assert_eq!(games[2].as_ref().unwrap().result_string(), "*");
+
+        let resigned = "[Event \"Fourth\"]\n[Result \"0-1\"]\n\n1. e4 e5 0-1\n\n[Event \"Fifth\"]\n[Result \"1/2-1/2\"]\n\n1. d4 d5 1/2-1/2\n";
+        let results = read_games(resigned.as_bytes())
+            .map(|game| game.map(|game| (game.tag("Event").unwrap().to_string(), game.result_string())))
+            .collect::<Result<Vec<(String, &str)>, PgnError>>()
+            .unwrap();
+        assert_eq!(results, [("Fourth".to_string(), "0-1"), ("Fifth".to_string(), "1/2-1/2")]);
+
+        let without_results = "[Event \"A\"]\n1. e4\n[Event \"B\"]\n1. d4 d5\n";
+        let histories = read_games(without_results.as_bytes()).map(|game| game.unwrap().history.len()).collect::<Vec<usize>>();
+        assert_eq!(histories, [1, 2]);
+        assert_eq!(read_games("\n\n".as_bytes()).count(), 0);
+    }
+}
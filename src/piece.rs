@@ -0,0 +1,365 @@
+/*****************************
+*  PIECES                    *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Types for the pieces on the board, and iterators over the occupied squares of a Board.
+
+Internally the board stores pieces by their FEN letter (capital for white, lowercase for black) and empty squares as `'*'`.
+These types let downstream code work with pieces without knowing about that representation.
+*/
+
+use std::fmt;
+
+use crate::{Board, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent one of the two sides.
+pub enum Color {
+    White,
+    Black,
+}
+impl Color {
+    /// A function to create a color from the character used by Board.active_player.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Color), or None if the character is neither 'w' nor 'b'.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Color;
+    /// assert_eq!(Color::from_char('b'), Some(Color::Black));
+    /// ```
+    pub fn from_char(color: char) -> Option<Color> {
+        match color {
+            'w' => Some(Color::White),
+            'b' => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    /// A function to return the character used by Board.active_player, 'w' or 'b'. Inverse function for from_char().
+    pub fn to_char(&self) -> char {
+        match self {
+            Color::White => 'w',
+            Color::Black => 'b',
+        }
+    }
+
+    /// A function to return the other side.
+    pub fn opposite(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent the type of a piece, regardless of its color.
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// A struct to represent a piece.
+///
+/// ## Attributes
+/// ```text
+/// kind: PieceKind, // The type of the piece.
+/// color: char, // 'w' or 'b'.
+/// ```
+pub struct Piece {
+    pub kind: PieceKind,
+    pub color: char,
+}
+impl Piece {
+    /// A function to create a piece from its letter in FEN notation.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// fen_char: char, // Capital for white, lowercase for black, e.g. 'N' or 'q'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Piece), or None if the character isn't a piece (such as the empty square '*').
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Piece, PieceKind};
+    /// let knight = Piece::from_fen_char('n').unwrap();
+    /// assert_eq!(knight.kind, PieceKind::Knight);
+    /// assert_eq!(knight.color, 'b');
+    /// ```
+    pub fn from_fen_char(fen_char: char) -> Option<Piece> {
+        let kind = match fen_char.to_ascii_lowercase() {
+            'p' => PieceKind::Pawn,
+            'n' => PieceKind::Knight,
+            'b' => PieceKind::Bishop,
+            'r' => PieceKind::Rook,
+            'q' => PieceKind::Queen,
+            'k' => PieceKind::King,
+            _ => return None,
+        };
+        let color = if fen_char.is_ascii_uppercase() { 'w' } else { 'b' };
+        Some(Piece { kind, color })
+    }
+
+    /// A function to return the piece's letter in FEN notation. Inverse function for from_fen_char().
+    ///
+    /// ## Returns
+    /// This function returns a capital letter for white pieces and a lowercase letter for black pieces.
+    pub fn to_fen_char(&self) -> char {
+        let fen_char = match self.kind {
+            PieceKind::Pawn => 'p',
+            PieceKind::Knight => 'n',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::Queen => 'q',
+            PieceKind::King => 'k',
+        };
+        if self.color == 'w' { fen_char.to_ascii_uppercase() } else { fen_char }
+    }
+}
+impl fmt::Debug for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen_char())
+    }
+}
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen_char())
+    }
+}
+
+impl Board {
+    /// A function to iterate over every occupied square of the board.
+    ///
+    /// ## Returns
+    /// This function returns an iterator of (Square, Piece) pairs. Squares are visited in the same order as a FEN string:
+    /// a8, b8, ..., h8, a7, ..., h1.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let (square, piece) = example_game.board.pieces().next().unwrap();
+    /// assert_eq!(square.to_algebraic(), "a8");
+    /// assert_eq!(piece.to_fen_char(), 'r');
+    /// ```
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.board_state.iter().enumerate().flat_map(|(row, pieces)| {
+            pieces.iter().enumerate().filter_map(move |(col, fen_char)| {
+                Piece::from_fen_char(*fen_char).map(|piece| (Square::from_coords(&[row as i32, col as i32]), piece))
+            })
+        })
+    }
+
+    /// A function to iterate over the pieces of one color. See docs for pieces() for the order.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns an iterator of (Square, Piece) pairs.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.pieces_of('w').count(), 16);
+    /// ```
+    pub fn pieces_of(&self, color: char) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.pieces().filter(move |(_square, piece)| piece.color == color)
+    }
+
+    /// A function to find the king of one side.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Square) with the king's location, or None if that side doesn't have exactly one king.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.king_square('b').unwrap().to_algebraic(), "e8");
+    /// ```
+    pub fn king_square(&self, color: char) -> Option<Square> {
+        let mut kings = self
+            .pieces_of(color)
+            .filter(|(_square, piece)| piece.kind == PieceKind::King)
+            .map(|(square, _piece)| square);
+        match (kings.next(), kings.next()) {
+            (Some(square), None) => Some(square),
+            _ => None,
+        }
+    } // Scans the board instead of caching the location, since board_state is public and may be edited directly.
+
+    /// A function to check whether a square is empty.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert!(example_game.board.is_empty(Square::from_algebraic("e4").unwrap()));
+    /// assert!(!example_game.board.is_empty(Square::from_algebraic("e2").unwrap()));
+    /// ```
+    pub fn is_empty(&self, square: Square) -> bool {
+        self.piece_on(square) == '*'
+    }
+
+    /// A function to find the color of the piece on a square.
+    ///
+    /// ## Returns
+    /// This function returns the color of the piece, or None if the square is empty.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game, Square};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.color_on(Square::from_algebraic("d8").unwrap()), Some(Color::Black));
+    /// ```
+    pub fn color_on(&self, square: Square) -> Option<Color> {
+        Piece::from_fen_char(self.piece_on(square)).and_then(|piece| Color::from_char(piece.color))
+    }
+
+    /// A function to count the pieces of one kind and color, e.g. the white knights.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Piece};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.board.count(Piece::from_fen_char('p').unwrap()), 8);
+    /// ```
+    pub fn count(&self, piece: Piece) -> usize {
+        let fen_char = piece.to_fen_char();
+        self.board_state.iter().flatten().filter(|square| **square == fen_char).count()
+    }
+
+    /// A function to count the pieces of one color, the king included.
+    pub fn count_color(&self, color: Color) -> usize {
+        self.pieces_of(color.to_char()).count()
+    }
+
+    /// A function to iterate over the squares with a piece of one color. See docs for pieces() for the order.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game};
+    /// let example_game = Game::new_from_fen("7k/8/8/3q4/8/8/8/K6Q w - - 0 1".to_string());
+    /// let squares = example_game.board.occupied_squares(Color::White).map(|square| square.to_algebraic()).collect::<Vec<String>>();
+    /// assert_eq!(squares, ["a1", "h1"]);
+    /// ```
+    pub fn occupied_squares(&self, color: Color) -> impl Iterator<Item = Square> + '_ {
+        self.pieces_of(color.to_char()).map(|(square, _piece)| square)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    #[test]
+    fn test_color_conversions() {
+        for color in [Color::White, Color::Black] {
+            assert_eq!(Color::from_char(color.to_char()), Some(color));
+            assert_ne!(color.opposite(), color);
+            assert_eq!(color.opposite().opposite(), color);
+        }
+        assert_eq!(Color::from_char('x'), None);
+    }
+    #[test]
+    fn test_fen_char_round_trip() {
+        for fen_char in "PNBRQKpnbrqk".chars() {
+            assert_eq!(Piece::from_fen_char(fen_char).unwrap().to_fen_char(), fen_char);
+        }
+        assert!(Piece::from_fen_char('*').is_none());
+        assert!(Piece::from_fen_char('x').is_none());
+    }
+    #[test]
+    fn test_pieces_at_start() {
+        let test_game = Game::new();
+        assert_eq!(test_game.board.pieces().count(), 32);
+        assert_eq!(test_game.board.pieces_of('w').count(), 16);
+        assert_eq!(test_game.board.pieces_of('b').count(), 16);
+        let e1 = Square::from_algebraic("e1").unwrap();
+        assert!(test_game.board.pieces().any(|pair| pair == (e1, Piece { kind: PieceKind::King, color: 'w' })));
+    }
+    #[test]
+    fn test_king_square() {
+        let start = Game::new();
+        assert_eq!(start.board.king_square('w'), Square::from_algebraic("e1"));
+        assert_eq!(start.board.king_square('b'), Square::from_algebraic("e8"));
+        let castled = Game::new_from_fen("r4rk1/ppp2ppp/8/8/8/8/PPP2PPP/2KR3R w - - 4 12".to_string());
+        assert_eq!(castled.board.king_square('w'), Square::from_algebraic("c1"));
+        assert_eq!(castled.board.king_square('b'), Square::from_algebraic("g8"));
+        let study = crate::parse_fen("8/8/8/4p3/8/3P4/8/8 w - - 0 1"); // A kingless pawn study
+        assert_eq!(study.king_square('w'), None);
+        assert_eq!(study.king_square('b'), None);
+    }
+    #[test]
+    fn test_pieces_order() {
+        let test_game = Game::new_from_fen("7k/8/8/3q4/8/8/8/K7 w - - 0 1".to_string());
+        let squares = test_game.board.pieces().map(|(square, _piece)| square.to_algebraic()).collect::<Vec<String>>();
+        assert_eq!(squares, ["h8", "d5", "a1"]);
+    }
+    #[test]
+    fn test_census() {
+        let start = Game::new().board;
+        let e4 = Square::from_algebraic("e4").unwrap();
+        let e1 = Square::from_algebraic("e1").unwrap();
+        assert!(start.is_empty(e4) && !start.is_empty(e1));
+        assert_eq!((start.color_on(e1), start.color_on(e4)), (Some(Color::White), None));
+        assert_eq!((start.count_color(Color::White), start.count_color(Color::Black)), (16, 16));
+        assert_eq!(start.count(Piece { kind: PieceKind::Knight, color: 'w' }), 2);
+        assert_eq!(start.count(Piece { kind: PieceKind::Queen, color: 'b' }), 1);
+        assert_eq!(start.occupied_squares(Color::Black).count(), 16);
+        assert_eq!((0..64).filter_map(Square::from_index).filter(|square| start.is_empty(*square)).count(), 32);
+
+        let endgame = crate::parse_fen("8/5k2/8/3p4/8/2N5/1P4K1/8 b - - 0 50");
+        assert_eq!((endgame.count_color(Color::White), endgame.count_color(Color::Black)), (3, 2));
+        assert_eq!(endgame.count(Piece { kind: PieceKind::Pawn, color: 'w' }), 1);
+        assert_eq!(endgame.count(Piece { kind: PieceKind::Bishop, color: 'w' }), 0);
+        let white = endgame.occupied_squares(Color::White).map(|square| square.to_algebraic()).collect::<Vec<String>>();
+        assert_eq!(white, ["c3", "b2", "g2"]);
+        assert_eq!(endgame.color_on(Square::from_algebraic("d5").unwrap()), Some(Color::Black));
+    }
+}
@@ -0,0 +1,506 @@
+/*****************************
+*  POLYGLOT OPENING BOOKS    *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Support for opening books in the Polyglot (.bin) format. Only available with the `book` feature.
+Read more about the format here:
+<http://hgm.nubati.net/book_format.html>
+
+A book is a list of 16 byte entries sorted by position key. Positions are keyed with the Polyglot Zobrist hash,
+which uses a fixed, published table of random numbers (see the zobrist module), so books made by other tools can be read by this library.
+
+Books can also be built from a collection of games (see docs for build_from_pgn), and saved for other tools to read.
+*/
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use rand::Rng;
+
+use crate::{get_board_coords, get_piece, Board, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to represent a single book move.
+///
+/// ## Attributes
+/// ```text
+/// key: u64, // The Polyglot key of the position the move is played from.
+/// book_move: Move, // The move, converted to this library's notation (castling moves the king 2 squares).
+/// weight: u16, // How often the move should be played, relative to the other moves in the position.
+/// learn: u32, // Learning data written by some engines. Not used by this library.
+/// ```
+pub struct BookEntry {
+    pub key: u64,
+    pub book_move: Move,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+/// A struct to represent a Polyglot opening book loaded into memory.
+pub struct PolyglotBook {
+    entries: Vec<RawEntry>,
+}
+impl PolyglotBook {
+    /// A function to read a Polyglot book from a file.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// path: impl AsRef<Path>, // The path to the .bin file.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the book, or an io::Error if the file can't be read or its length isn't a multiple of 16 bytes.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # use alviny_task_3::polyglot::PolyglotBook;
+    /// let book = PolyglotBook::open("performance.bin").unwrap();
+    /// ```
+    pub fn open(path: impl AsRef<Path>) -> io::Result<PolyglotBook> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Polyglot book length is not a multiple of 16 bytes"));
+        }
+        Ok(PolyglotBook::from_bytes(&bytes))
+    }
+
+    /// A function to read a Polyglot book that is already in memory. Trailing bytes that don't make up a whole entry are ignored.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// bytes: &[u8], // The contents of a .bin file.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the book.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::polyglot::PolyglotBook;
+    /// let book = PolyglotBook::from_bytes(&[]);
+    /// assert!(book.is_empty());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> PolyglotBook {
+        let mut entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| RawEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect::<Vec<RawEntry>>();
+        entries.sort_by_key(|entry| entry.key); // Books should already be sorted, but lookups rely on it
+        PolyglotBook { entries }
+    }
+
+    /// A function to find every book move for a position.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The position to look up.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a Vec of BookEntries, in the order they appear in the book. The Vec is empty if the position isn't in the book.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::polyglot::PolyglotBook;
+    /// let book = PolyglotBook::from_bytes(&[]);
+    /// assert!(book.lookup(&Game::new().board).is_empty());
+    /// ```
+    pub fn lookup(&self, board: &Board) -> Vec<BookEntry> {
+        let key = polyglot_key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .map(|entry| BookEntry {
+                key,
+                book_move: decode_move(board, entry.raw_move),
+                weight: entry.weight,
+                learn: entry.learn,
+            })
+            .collect()
+    }
+
+    /// A function to write the book in the Polyglot (.bin) format. Inverse function for from_bytes().
+    ///
+    /// ## Returns
+    /// This function returns the entries as 16 bytes each, sorted by key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.key.to_be_bytes());
+            bytes.extend_from_slice(&entry.raw_move.to_be_bytes());
+            bytes.extend_from_slice(&entry.weight.to_be_bytes());
+            bytes.extend_from_slice(&entry.learn.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// A function to write the book to a file in the Polyglot (.bin) format, e.g. for a GUI to use. Inverse function for open().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// path: impl AsRef<Path>, // The path to the .bin file. An existing file is overwritten.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns an io::Error if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// A function to return the number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// A function to check whether the book has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Game {
+    /// A function to pick a move from an opening book. Moves are picked at random, in proportion to their weights.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// book: &PolyglotBook, // The opening book to play from.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Move), or None if the position isn't in the book or none of its book moves are legal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::polyglot::PolyglotBook;
+    /// let book = PolyglotBook::from_bytes(&[]);
+    /// assert!(Game::new().book_move(&book).is_none());
+    /// ```
+    pub fn book_move(&self, book: &PolyglotBook) -> Option<Move> {
+        let legal_moves = self.legal_moves();
+        let entries = book
+            .lookup(&self.board)
+            .into_iter()
+            .filter(|entry| legal_moves.contains(&entry.book_move))
+            .collect::<Vec<BookEntry>>(); // Guards against key collisions and broken books
+        let total_weight = entries.iter().map(|entry| entry.weight as u32).sum::<u32>();
+        if total_weight == 0 {
+            return None;
+        }
+        pick_weighted(entries, rand::rng().random_range(0..total_weight))
+    }
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to calculate the Polyglot key of a position. Alias to
+/// ```text
+/// board.zobrist_key()
+/// ```
+///
+/// ## Arguments
+/// ```text
+/// board: &Board, // The position to hash.
+///
+/// ```
+/// ## Returns
+/// This function returns the 64 bit key used to look positions up in a book.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::polyglot::polyglot_key;
+/// assert_eq!(polyglot_key(&Game::new().board), 0x463B96181691FC9C);
+/// ```
+pub fn polyglot_key(board: &Board) -> u64 {
+    board.zobrist_key()
+}
+
+/// A function to convert a move in Polyglot's 16 bit encoding into a Move.
+///
+/// ## Arguments
+/// ```text
+/// board: &Board, // The position the move is played from. Needed because Polyglot writes castling as "king takes rook".
+/// raw_move: u16, // The encoded move.
+///
+/// ```
+/// ## Returns
+/// This function returns the Move. Castling is converted to the king moving 2 squares.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::polyglot::decode_move;
+/// let example_game = Game::new();
+/// assert_eq!(decode_move(&example_game.board, 0x031C).to_uci(), "e2e4");
+/// ```
+pub fn decode_move(board: &Board, raw_move: u16) -> Move {
+    let square = |file: u16, rank: u16| format!("{}{}", (b'a' + file as u8) as char, rank + 1);
+    let to_file = raw_move & 7;
+    let to_rank = (raw_move >> 3) & 7;
+    let from_file = (raw_move >> 6) & 7;
+    let from_rank = (raw_move >> 9) & 7;
+    let promotion = match (raw_move >> 12) & 7 {
+        1 => Some('n'),
+        2 => Some('b'),
+        3 => Some('r'),
+        4 => Some('q'),
+        _ => None,
+    };
+
+    let moving_piece = board.board_state[7 - from_rank as usize][from_file as usize];
+    let is_castling = moving_piece.eq_ignore_ascii_case(&'k') && from_file == 4 && from_rank == to_rank && (to_file == 0 || to_file == 7);
+    let to_file = match (is_castling, to_file) {
+        (true, 7) => 6,
+        (true, _) => 2,
+        (false, to_file) => to_file,
+    };
+    Move {
+        source: square(from_file, from_rank),
+        target: square(to_file, to_rank),
+        promotion,
+    }
+}
+
+/// A function to convert a Move into Polyglot's 16 bit encoding. Inverse function for decode_move().
+///
+/// ## Arguments
+/// ```text
+/// board: &Board, // The position the move is played from. Needed because Polyglot writes castling as "king takes rook".
+/// book_move: &Move, // The move. Castling is the king moving 2 squares.
+///
+/// ```
+/// ## Returns
+/// This function returns the encoded move.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::{Game, Move};
+/// # use alviny_task_3::polyglot::encode_move;
+/// let example_game = Game::new();
+/// assert_eq!(encode_move(&example_game.board, &Move::from_uci("e2e4").unwrap()), 0x031C);
+/// ```
+pub fn encode_move(board: &Board, book_move: &Move) -> u16 {
+    let square = |algebraic: &str| {
+        let bytes = algebraic.as_bytes();
+        ((bytes[0] - b'a') as u16, (bytes[1] - b'1') as u16)
+    };
+    let (from_file, from_rank) = square(&book_move.source);
+    let (to_file, to_rank) = square(&book_move.target);
+    let moving_piece = get_piece(board, &get_board_coords(book_move.source.clone()));
+    let to_file = match to_file {
+        6 if moving_piece.eq_ignore_ascii_case(&'k') && from_file == 4 => 7,
+        2 if moving_piece.eq_ignore_ascii_case(&'k') && from_file == 4 => 0,
+        to_file => to_file,
+    };
+    let promotion = match book_move.promotion {
+        Some('n') => 1,
+        Some('b') => 2,
+        Some('r') => 3,
+        Some('q') => 4,
+        _ => 0,
+    };
+    (promotion << 12) | (from_rank << 9) | (from_file << 6) | (to_rank << 3) | to_file
+}
+
+/// A function to build an opening book from a collection of games in PGN.
+///
+/// Every game is replayed, and each of its moves up to max_depth_plies is recorded under the position it was played from.
+/// The weight of a book move is the number of times it was played in that position. Moves played fewer than
+/// min_games times are left out, so that a single odd game doesn't make it into the book.
+///
+/// ## Arguments
+/// ```text
+/// readers: impl IntoIterator<Item = impl Read>, // The games, one per reader (see docs for Game::from_pgn), e.g. files.
+/// max_depth_plies: u32, // How many half-moves of each game to record.
+/// min_games: u32, // How often a move has to be played in a position to make it into the book.
+///
+/// ```
+/// ## Returns
+/// This function returns the book, or an io::Error if a reader fails or a game isn't valid PGN (with the PgnError inside).
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::polyglot::build_from_pgn;
+/// let games = ["1. e4 e5 2. Nf3 *", "1. e4 c5 *", "1. d4 d5 *"];
+/// let book = build_from_pgn(games.iter().map(|pgn| pgn.as_bytes()), 2, 2).unwrap();
+/// let entries = book.lookup(&Game::new().board);
+/// assert_eq!((entries.len(), entries[0].book_move.to_uci().as_str(), entries[0].weight), (1, "e2e4", 2));
+/// ```
+pub fn build_from_pgn(readers: impl IntoIterator<Item = impl Read>, max_depth_plies: u32, min_games: u32) -> io::Result<PolyglotBook> {
+    let mut counts: BTreeMap<(u64, u16), u32> = BTreeMap::new();
+    for mut reader in readers {
+        let mut pgn = String::new();
+        reader.read_to_string(&mut pgn)?;
+        let game = Game::from_pgn(&pgn).map_err(|pgn_error| io::Error::new(io::ErrorKind::InvalidData, pgn_error))?;
+        for (board, played_move) in game.positions().zip(&game.history).take(max_depth_plies as usize) {
+            *counts.entry((polyglot_key(&board), encode_move(&board, played_move))).or_default() += 1;
+        }
+    }
+    let mut entries = counts
+        .into_iter()
+        .filter(|(_entry, count)| *count >= min_games)
+        .map(|((key, raw_move), count)| RawEntry { key, raw_move, weight: count.min(u16::MAX as u32) as u16, learn: 0 })
+        .collect::<Vec<RawEntry>>();
+    entries.sort_by_key(|entry| (entry.key, Reverse(entry.weight))); // The most played move first
+    Ok(PolyglotBook { entries })
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Clone, Debug)]
+struct RawEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+    learn: u32,
+}
+
+fn pick_weighted(entries: Vec<BookEntry>, mut roll: u32) -> Option<Move> {
+    for entry in entries {
+        if roll < entry.weight as u32 {
+            return Some(entry.book_move);
+        }
+        roll -= entry.weight as u32;
+    }
+    None
+} // Walks the entries until the roll (0..total weight) falls inside one of them.
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two entries for the starting position: 1. e4 (weight 2) and 1. d4 (weight 1)
+    const TEST_BOOK: [u8; 32] = [
+        0x46, 0x3B, 0x96, 0x18, 0x16, 0x91, 0xFC, 0x9C, 0x03, 0x1C, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        0x46, 0x3B, 0x96, 0x18, 0x16, 0x91, 0xFC, 0x9C, 0x02, 0xDB, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_polyglot_key() {
+        let after_e4 = Game::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string());
+        assert_eq!(polyglot_key(&after_e4.board), 0x823C9B50FD114196);
+    }
+    #[test]
+    fn test_lookup_start_position() {
+        let book = PolyglotBook::from_bytes(&TEST_BOOK);
+        let entries = book.lookup(&Game::new().board);
+        let moves = entries.iter().map(|entry| entry.book_move.to_uci()).collect::<Vec<String>>();
+        assert_eq!(moves, ["e2e4", "d2d4"]);
+        assert_eq!(entries[0].weight, 2);
+        let mut after_e4 = Game::new();
+        after_e4.make_uci_move("e2e4");
+        assert!(book.lookup(&after_e4.board).is_empty());
+    }
+    #[test]
+    fn test_book_move() {
+        let book = PolyglotBook::from_bytes(&TEST_BOOK);
+        let start = Game::new();
+        for _ in 0..20 {
+            let book_move = start.book_move(&book).unwrap().to_uci();
+            assert!(book_move == "e2e4" || book_move == "d2d4");
+        }
+        let entries = book.lookup(&start.board);
+        assert_eq!(pick_weighted(entries.clone(), 0).unwrap().to_uci(), "e2e4");
+        assert_eq!(pick_weighted(entries.clone(), 1).unwrap().to_uci(), "e2e4");
+        assert_eq!(pick_weighted(entries, 2).unwrap().to_uci(), "d2d4");
+    }
+    #[test]
+    fn test_open_book_file() {
+        let path = std::env::temp_dir().join("alviny_task_3_test_book.bin");
+        fs::write(&path, TEST_BOOK).unwrap();
+        let book = PolyglotBook::open(&path).unwrap();
+        assert_eq!(book.len(), 2);
+        fs::write(&path, &TEST_BOOK[0..20]).unwrap();
+        assert!(PolyglotBook::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn test_build_from_pgn() {
+        let games = [
+            "[Event \"Scholar's mate\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0",
+            "[Event \"Italian\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. O-O Nf6 5. d3 O-O *",
+            "[Event \"Queen's gambit\"]\n\n1. d4 d5 2. c4 dxc4 3. e4 {Central} e5 *",
+        ];
+        let book = build_from_pgn(games.iter().map(|pgn| pgn.as_bytes()), 10, 1).unwrap();
+        let entries = book.lookup(&Game::new().board);
+        let weights = entries.iter().map(|entry| (entry.book_move.to_uci(), entry.weight)).collect::<Vec<(String, u16)>>();
+        assert_eq!(weights, [("e2e4".to_string(), 2), ("d2d4".to_string(), 1)]);
+        let after_e4 = Game::from_moves(&["e4"]).unwrap();
+        let entries = book.lookup(&after_e4.board);
+        assert_eq!((entries.len(), entries[0].book_move.to_uci().as_str(), entries[0].weight), (1, "e7e5", 2));
+        let castled = Game::from_moves(&["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"]).unwrap();
+        assert_eq!(book.lookup(&castled.board)[0].book_move.to_uci(), "e1g1"); // Stored as e1h1
+
+        let round_trip = PolyglotBook::from_bytes(&book.to_bytes());
+        assert_eq!(round_trip.len(), book.len());
+        assert_eq!(round_trip.lookup(&Game::new().board), book.lookup(&Game::new().board));
+        assert_eq!(book.to_bytes()[0..8], book.entries.iter().map(|entry| entry.key).min().unwrap().to_be_bytes());
+
+        let common = build_from_pgn(games.iter().map(|pgn| pgn.as_bytes()), 10, 2).unwrap();
+        assert_eq!(common.len(), 2); // 1. e4 and 1... e5
+        let shallow = build_from_pgn(games.iter().map(|pgn| pgn.as_bytes()), 1, 1).unwrap();
+        assert_eq!(shallow.len(), 2); // 1. e4 and 1. d4
+        assert!(build_from_pgn(["1. e4 e5 2. Ke3".as_bytes()], 10, 1).is_err());
+    }
+    #[test]
+    fn test_encode_round_trips() {
+        let castling = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        for uci_move in ["e1g1", "e1c1", "a1a8", "h1h2", "e1f1"] {
+            let book_move = Move::from_uci(uci_move).unwrap();
+            assert_eq!(decode_move(&castling.board, encode_move(&castling.board, &book_move)), book_move);
+        }
+        assert_eq!(encode_move(&castling.board, &Move::from_uci("e1g1").unwrap()), (4 << 6) | 7);
+        let promotion = Game::new_from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1".to_string());
+        assert_eq!(encode_move(&promotion.board, &Move::from_uci("a7a8n").unwrap()), (1 << 12) | (48 << 6) | 56);
+    }
+    #[test]
+    fn test_decode_castling_and_promotion() {
+        let castling = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string());
+        assert_eq!(decode_move(&castling.board, (4 << 6) | 7).to_uci(), "e1g1"); // e1h1
+        assert_eq!(decode_move(&castling.board, 4 << 6).to_uci(), "e1c1"); // e1a1
+        assert_eq!(decode_move(&castling.board, (60 << 6) | 63).to_uci(), "e8g8"); // e8h8
+        let promotion = Game::new_from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1".to_string());
+        assert_eq!(decode_move(&promotion.board, (1 << 12) | (48 << 6) | 56).to_uci(), "a7a8n");
+    }
+}
@@ -0,0 +1,839 @@
+/*****************************
+*  POSITION EDITING          *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Free editing of positions, e.g. for analysis boards where pieces are dragged around before the analysis starts,
+and a builder for setting up positions in code without writing FEN strings by hand.
+Positions can be checked at two levels: validate() for positions the move generator can play from,
+and is_legal_position() for positions that could actually occur in a game.
+
+Edits are made on a copy of the board and only handed back to the Game once the position has been validated,
+so an edited Game never reaches the move generator in a state it can't handle (such as a side without a king).
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::castling::{CastledStatus, CastlingMove};
+use crate::{check_for_checks, generate_fen, player_is_in_check, Board, CastlingRights, Color, Game, GameStatus, Piece, PieceKind, Square, Variant};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent the reasons a position can't be played.
+pub enum PositionError {
+    InvalidSquare(String),
+    InvalidPiece(char),
+    MissingKing(Color),
+    TooManyKings(Color),
+    PawnOnBackRank(Square),
+    OpponentInCheck, // The side that is not to move is in check, so the side to move could capture the king
+    InvalidCastlingRights(char),
+    InvalidEnPassant(Square),
+    InvalidCell { row: usize, column: usize, character: char }, // A cell of a grid isn't a piece or empty (see Board::try_from)
+    InvalidChess960Index(u16), // Chess960 starting positions are numbered 0 to 959 (see Board::chess960_start)
+}
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::InvalidSquare(square) => write!(f, "{} is not a square", square),
+            PositionError::InvalidPiece(piece) => write!(f, "{} is not a piece", piece),
+            PositionError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            PositionError::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            PositionError::PawnOnBackRank(square) => write!(f, "there is a pawn on {}", square),
+            PositionError::OpponentInCheck => write!(f, "the side that is not to move is in check"),
+            PositionError::InvalidCastlingRights(right) => write!(f, "castling right {} doesn't match the king and rook placement", right),
+            PositionError::InvalidEnPassant(square) => write!(f, "en passant on {} isn't possible in this position", square),
+            PositionError::InvalidCell { row, column, character } => write!(f, "{:?} in row {}, column {} is not a piece or an empty square", character, row, column),
+            PositionError::InvalidChess960Index(index) => write!(f, "{} is not a Chess960 starting position, they are numbered 0 to 959", index),
+        }
+    }
+}
+impl std::error::Error for PositionError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An enum to represent the reasons a position can't have been reached from the starting position in a normal game.
+/// See docs for Board::is_legal_position().
+pub enum IllegalPosition {
+    MissingKing(Color),
+    TooManyKings(Color),
+    KingsAdjacent,
+    OpponentInCheck, // The side that is not to move is in check, so the side to move could capture the king
+    TooManyPawns(Color),
+    TooManyPromotedPieces(Color), // More extra queens, rooks, bishops and knights than missing pawns
+    PawnOnBackRank(Square),
+    InvalidEnPassant(Square),
+}
+impl fmt::Display for IllegalPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalPosition::MissingKing(color) => write!(f, "{:?} has no king", color),
+            IllegalPosition::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            IllegalPosition::KingsAdjacent => write!(f, "the kings are next to each other"),
+            IllegalPosition::OpponentInCheck => write!(f, "the side that is not to move is in check"),
+            IllegalPosition::TooManyPawns(color) => write!(f, "{:?} has more than 8 pawns", color),
+            IllegalPosition::TooManyPromotedPieces(color) => write!(f, "{:?} has more promoted pieces than missing pawns", color),
+            IllegalPosition::PawnOnBackRank(square) => write!(f, "there is a pawn on {}", square),
+            IllegalPosition::InvalidEnPassant(square) => write!(f, "en passant on {} isn't possible in this position", square),
+        }
+    }
+}
+impl std::error::Error for IllegalPosition {}
+
+/// A struct to edit the position of a Game. Create one with Game::edit().
+///
+/// Every editing function returns the editor, so edits can be chained. Nothing happens to the Game until finish() succeeds.
+pub struct PositionEditor<'a> {
+    game: &'a mut Game,
+    board: Board,
+}
+impl PositionEditor<'_> {
+    /// A function to put a piece on a square, replacing whatever was there.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square to put the piece on.
+    /// piece: Piece, // The piece to put there.
+    ///
+    /// ```
+    pub fn put_piece(&mut self, square: Square, piece: Piece) -> &mut Self {
+        self.board.set_piece(&square.coords(), piece.to_fen_char());
+        self
+    }
+
+    /// A function to empty a square.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Square, // The square to empty.
+    ///
+    /// ```
+    pub fn remove_piece(&mut self, square: Square) -> &mut Self {
+        self.board.set_piece(&square.coords(), '*');
+        self
+    }
+
+    /// A function to set whose turn it is.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The side to move.
+    ///
+    /// ```
+    pub fn set_side_to_move(&mut self, color: Color) -> &mut Self {
+        self.board.active_player = color.to_char();
+        self
+    }
+
+    /// A function to set the castling rights.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// rights: &str, // The castling rights in FEN notation, e.g. "KQkq", "Kq" or "-". Unknown characters are ignored.
+    ///
+    /// ```
+    pub fn set_castling_rights(&mut self, rights: &str) -> &mut Self {
+        self.board.castling_rights = CastlingRights::default();
+        for right in rights.chars() {
+            self.board.castling_rights.set(right, true);
+        }
+        self
+    }
+
+    /// A function to set the en passant square, i.e. the square a pawn skipped over with a double push on the last move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: Option<Square>, // The skipped square, or None if the last move wasn't a double pawn push.
+    ///
+    /// ```
+    pub fn set_en_passant(&mut self, square: Option<Square>) -> &mut Self {
+        self.board.en_passant_square = square.map_or("-".to_string(), |square| square.to_algebraic());
+        self
+    }
+
+    /// A function to remove every piece, castling right and en passant square. The side to move is kept.
+    pub fn clear(&mut self) -> &mut Self {
+        for row in self.board.board_state.iter_mut() {
+            row.fill('*');
+        }
+        self.board.castling_rights = CastlingRights::default();
+        self.board.en_passant_square = "-".to_string();
+        self
+    }
+
+    /// A function to validate the edited position and, if it is valid, make it the Game's new starting position.
+    ///
+    /// ## Returns
+    /// This function returns Ok(()) if the Game was updated, or the first problem found with the position.
+    /// On an error the Game is left untouched, and the editor can be used to fix the position and try again.
+    /// The move history and the halfmove clock of an updated Game are reset, since the edited position has no history.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Square};
+    /// # use alviny_task_3::position::PositionError;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// let mut editor = example_game.edit();
+    /// editor.remove_piece(Square::from_algebraic("e1").unwrap());
+    /// assert!(matches!(editor.finish(), Err(PositionError::MissingKing(_))));
+    /// ```
+    pub fn finish(&mut self) -> Result<(), PositionError> {
+        self.board.validate()?;
+        self.board.halfmove_counter = 0;
+        self.game.board = self.board.clone();
+        self.game.checks = self.game.variant.checks(&self.game.board);
+        self.game.fen = generate_fen(&self.game.board);
+        self.game.start_fen = self.game.fen.clone();
+        self.game.history.clear();
+        self.game.has_castled = CastledStatus::from_rights(&self.game.board.castling_rights);
+        self.game.position_occurrences = HashMap::from([(self.game.board.zobrist_key(), 1)]);
+        self.game.update_game_status();
+        Ok(())
+    }
+}
+
+/// A struct to set up a Board piece by piece. Create one with BoardBuilder::empty().
+///
+/// Every setting function returns the builder, so settings can be chained. Squares and pieces that can't be parsed
+/// are reported by build(), along with any other problem with the position.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::position::BoardBuilder;
+/// let board = BoardBuilder::empty()
+///     .piece("g1", 'K')
+///     .piece("a1", 'R')
+///     .piece("g8", 'k')
+///     .piece("f7", 'p')
+///     .piece("g7", 'p')
+///     .piece("h7", 'p')
+///     .side_to_move('w')
+///     .build()
+///     .unwrap();
+/// let mut example_game = Game::from_board(board);
+/// example_game.make_uci_move("a1a8"); // Ra8# is a back rank mate
+/// assert!(example_game.is_checkmate());
+/// ```
+#[derive(Clone, Debug)]
+pub struct BoardBuilder {
+    board: Board,
+    error: Option<PositionError>,
+}
+impl BoardBuilder {
+    /// A function to start building from an empty board, with White to move, no castling rights, no en passant square,
+    /// a halfmove clock of 0 and a fullmove number of 1.
+    pub fn empty() -> BoardBuilder {
+        BoardBuilder {
+            board: crate::parse_fen("8/8/8/8/8/8/8/8 w - - 0 1"),
+            error: None,
+        }
+    }
+
+    /// A function to put a piece on a square, replacing whatever was there.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: &str, // The square in algebraic notation, e.g. "e1".
+    /// piece: char, // The piece in FEN notation, e.g. 'K' or 'q'.
+    ///
+    /// ```
+    pub fn piece(&mut self, square: &str, piece: char) -> &mut Self {
+        match (Square::from_algebraic(square), Piece::from_fen_char(piece)) {
+            (Some(square), Some(piece)) => self.board.set_piece(&square.coords(), piece.to_fen_char()),
+            (None, _) => self.record_error(PositionError::InvalidSquare(square.to_string())),
+            (_, None) => self.record_error(PositionError::InvalidPiece(piece)),
+        }
+        self
+    }
+
+    /// A function to set whose turn it is.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: char, // 'w' or 'b'.
+    ///
+    /// ```
+    pub fn side_to_move(&mut self, color: char) -> &mut Self {
+        self.board.active_player = color;
+        self
+    }
+
+    /// A function to set the castling rights.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// rights: &str, // The castling rights in FEN notation, e.g. "KQkq", "Kq" or "-". Unknown characters are ignored.
+    ///
+    /// ```
+    pub fn castling(&mut self, rights: &str) -> &mut Self {
+        self.board.castling_rights = CastlingRights::default();
+        for right in rights.chars() {
+            self.board.castling_rights.set(right, true);
+        }
+        self
+    }
+
+    /// A function to set the en passant square, i.e. the square a pawn skipped over with a double push on the last move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// square: &str, // The skipped square in algebraic notation, e.g. "e3".
+    ///
+    /// ```
+    pub fn en_passant(&mut self, square: &str) -> &mut Self {
+        match Square::from_algebraic(square) {
+            Some(square) => self.board.en_passant_square = square.to_algebraic(),
+            None => self.record_error(PositionError::InvalidSquare(square.to_string())),
+        }
+        self
+    }
+
+    /// A function to set the halfmove clock and the fullmove number.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// halfmove_clock: u32, // The number of halfmoves since the last capture or pawn move.
+    /// fullmove_number: u64, // The move number, starting at 1.
+    ///
+    /// ```
+    pub fn counters(&mut self, halfmove_clock: u32, fullmove_number: u64) -> &mut Self {
+        self.board.halfmove_counter = halfmove_clock;
+        self.board.turn_counter = fullmove_number;
+        self
+    }
+
+    /// A function to finish building.
+    ///
+    /// ## Returns
+    /// This function returns the Board, or the first problem found: a square or piece that couldn't be parsed,
+    /// a side to move other than 'w' and 'b', or any problem found by Board::validate().
+    pub fn build(&self) -> Result<Board, PositionError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        if Color::from_char(self.board.active_player).is_none() {
+            return Err(PositionError::InvalidPiece(self.board.active_player));
+        }
+        self.board.validate()?;
+        Ok(self.board.clone())
+    }
+
+    fn record_error(&mut self, error: PositionError) {
+        self.error.get_or_insert(error);
+    } // Only the first error is kept, since later ones are often caused by it.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to represent everything about a position except where the pieces stand, e.g. to go with
+/// Board::to_array(). The Default is White to move, no castling rights, no en passant square and counters 0 and 1.
+///
+/// ## Attributes
+/// ```text
+/// side_to_move: Color, // The player to move.
+/// castling_rights: CastlingRights, // Who may still castle, and to which side.
+/// en_passant: Option<Square>, // The square a pawn skipped over with a double push on the last move, if any.
+/// halfmove_clock: u32, // The number of halfmoves since the last capture or pawn move.
+/// fullmove_number: u64, // The move number, starting at 1.
+/// ```
+pub struct BoardMeta {
+    pub side_to_move: Color,
+    pub castling_rights: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u64,
+}
+impl Default for BoardMeta {
+    fn default() -> BoardMeta {
+        BoardMeta { side_to_move: Color::White, castling_rights: CastlingRights::default(), en_passant: None, halfmove_clock: 0, fullmove_number: 1 }
+    }
+}
+
+impl Game {
+    /// A function to create a new Game from a Board, e.g. one made with BoardBuilder.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: Board, // The position to start from. It isn't validated, so use Board::validate() first if it might be broken.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a Game object, which starts from the given Board.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, parse_fen};
+    /// let example_game = Game::from_board(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    /// assert_eq!(example_game, Game::new());
+    /// ```
+    pub fn from_board(board: Board) -> Game {
+        let fen = generate_fen(&board);
+        let checks = check_for_checks(&board);
+        let has_castled = CastledStatus::from_rights(&board.castling_rights);
+        let position_occurrences = HashMap::from([(board.zobrist_key(), 1)]);
+        let mut game = Game { start_fen: fen.clone(), fen, board, checks, game_status: GameStatus::InProgress, history: vec![], variant: Variant::Standard, clock: None, captured: Default::default(), annotations: vec![], observer: Default::default(), has_castled, position_occurrences, tags: vec![], draw_offer: None };
+        game.update_game_status();
+        game
+    }
+
+    /// A function to start editing the position (see docs for PositionEditor).
+    ///
+    /// ## Returns
+    /// This function returns a PositionEditor that starts from the current position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Piece, PieceKind, Square};
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.edit()
+    ///     .clear()
+    ///     .put_piece(Square::from_algebraic("e1").unwrap(), Piece { kind: PieceKind::King, color: 'w' })
+    ///     .put_piece(Square::from_algebraic("e8").unwrap(), Piece { kind: PieceKind::King, color: 'b' })
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(example_game.fen, "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    /// ```
+    pub fn edit(&mut self) -> PositionEditor<'_> {
+        let board = self.board.clone();
+        PositionEditor { game: self, board }
+    }
+}
+
+impl Board {
+    /// A function to return the pieces on the board as a plain 8x8 array, e.g. for a renderer. This is the stable
+    /// shape to exchange positions in, whatever the board looks like inside.
+    ///
+    /// ## Returns
+    /// This function returns the array by rank, then file, from White's side at the bottom of a diagram: array[0] is
+    /// the eighth rank and array[7] the first, and array[rank][0] is on the a-file. So array[0][0] is a8, array[7][0]
+    /// is a1, and a square is at array[7 - square.rank().index()][square.file().index()]. Empty squares are None.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Piece};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let array = example_game.board.to_array();
+    /// assert_eq!(array[0][0], Piece::from_fen_char('r')); // a8
+    /// assert_eq!(array[7][4], Piece::from_fen_char('K')); // e1
+    /// assert_eq!(array[4][4], None); // e4
+    /// ```
+    pub fn to_array(&self) -> [[Option<Piece>; 8]; 8] {
+        let mut array = [[None; 8]; 8];
+        for square in Square::all() {
+            array[7 - square.rank().index() as usize][square.file().index() as usize] = Piece::from_fen_char(self.piece_on(square));
+        }
+        array
+    }
+
+    /// A function to create a Board from a plain 8x8 array of pieces (see docs for Board::to_array) and the rest of the
+    /// position. Inverse function for to_array() and meta().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// array: [[Option<Piece>; 8]; 8], // The pieces, with array[0][0] on a8 and array[7][7] on h1.
+    /// meta: BoardMeta, // The side to move, castling rights, en passant square and counters.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Board. It isn't validated, so use Board::validate() if it might be broken.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Board, Piece};
+    /// # use alviny_task_3::position::BoardMeta;
+    /// let mut array = [[None; 8]; 8];
+    /// array[7][4] = Piece::from_fen_char('K'); // e1
+    /// array[0][4] = Piece::from_fen_char('k'); // e8
+    /// assert_eq!(generate_fen(&Board::from_array(array, BoardMeta::default())), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    /// ```
+    pub fn from_array(array: [[Option<Piece>; 8]; 8], meta: BoardMeta) -> Board {
+        let mut board = crate::parse_fen("8/8/8/8/8/8/8/8 w - - 0 1");
+        for (row, pieces) in array.iter().enumerate() {
+            for (column, piece) in pieces.iter().enumerate() {
+                board.board_state[row][column] = piece.map_or('*', |piece| piece.to_fen_char());
+            }
+        }
+        board.active_player = meta.side_to_move.to_char();
+        board.castling_rights = meta.castling_rights;
+        board.en_passant_square = meta.en_passant.map_or("-".to_string(), |square| square.to_algebraic());
+        board.halfmove_counter = meta.halfmove_clock;
+        board.turn_counter = meta.fullmove_number;
+        board
+    }
+
+    /// A function to return everything about the position except where the pieces stand (see docs for BoardMeta).
+    ///
+    /// ## Returns
+    /// This function returns the BoardMeta of the board. A side to move other than 'b' is read as White.
+    pub fn meta(&self) -> BoardMeta {
+        BoardMeta {
+            side_to_move: if self.active_player == 'b' { Color::Black } else { Color::White },
+            castling_rights: self.castling_rights,
+            en_passant: Square::from_algebraic(&self.en_passant_square),
+            halfmove_clock: self.halfmove_counter,
+            fullmove_number: self.turn_counter,
+        }
+    }
+
+    /// A function to check that a position can be played: each side has one king, there are no pawns on the first or last rank,
+    /// the side that is not to move isn't in check, and the castling rights and en passant square fit the position.
+    ///
+    /// ## Returns
+    /// This function returns Ok(()), or the first problem found.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::parse_fen;
+    /// # use alviny_task_3::position::PositionError;
+    /// assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").validate().is_ok());
+    /// assert!(matches!(parse_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").validate(), Err(PositionError::MissingKing(_))));
+    /// ```
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for color in [Color::White, Color::Black] {
+            match self.count_pieces(color, PieceKind::King) {
+                0 => return Err(PositionError::MissingKing(color)),
+                1 => {}
+                _ => return Err(PositionError::TooManyKings(color)),
+            }
+        }
+        if let Some((square, _piece)) = self.pieces().find(|(square, piece)| {
+            piece.kind == PieceKind::Pawn && (square.index() < 8 || square.index() >= 56)
+        }) {
+            return Err(PositionError::PawnOnBackRank(square));
+        }
+        let opponent = if self.active_player == 'w' { 'b' } else { 'w' };
+        if player_is_in_check(self, opponent) {
+            return Err(PositionError::OpponentInCheck);
+        }
+        for right in ['K', 'Q', 'k', 'q'] {
+            if self.castling_rights.has(right) && CastlingMove::for_right(self, right).is_none() {
+                return Err(PositionError::InvalidCastlingRights(right)); // The king must be on its home rank, with the rook on that side of it
+            }
+        }
+        if let Some(square) = Square::from_algebraic(&self.en_passant_square) && !self.en_passant_fits(square) {
+            return Err(PositionError::InvalidEnPassant(square));
+        }
+        Ok(())
+    }
+
+    /// A function to check that a position could have been reached in a normal game. This is stricter than validate(),
+    /// and meant for guarding analysis tools against corrupted input. The rules checked, in order, are:
+    /// - each side has exactly one king, and the kings aren't next to each other,
+    /// - no side has more than 8 pawns, or more extra queens, rooks, bishops and knights than it has missing pawns,
+    /// - there are no pawns on the first or last rank,
+    /// - the side that is not to move isn't in check,
+    /// - the en passant square, if any, is behind a pawn that could just have made a double push.
+    ///
+    /// Castling rights aren't checked, see validate() for that.
+    ///
+    /// ## Returns
+    /// This function returns Ok(()), or the first rule that is broken.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, parse_fen};
+    /// # use alviny_task_3::position::IllegalPosition;
+    /// assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_legal_position().is_ok());
+    /// let ten_knights = parse_fen("4k3/8/8/8/8/NNNNNNNN/PPPPPPPP/NN2K3 w - - 0 1");
+    /// assert_eq!(ten_knights.is_legal_position(), Err(IllegalPosition::TooManyPromotedPieces(Color::White)));
+    /// ```
+    pub fn is_legal_position(&self) -> Result<(), IllegalPosition> {
+        for color in [Color::White, Color::Black] {
+            match self.count_pieces(color, PieceKind::King) {
+                0 => return Err(IllegalPosition::MissingKing(color)),
+                1 => {}
+                _ => return Err(IllegalPosition::TooManyKings(color)),
+            }
+        }
+        if let (Some(white_king), Some(black_king)) = (self.king_square('w'), self.king_square('b'))
+            && white_king.distance(black_king) == 1 {
+            return Err(IllegalPosition::KingsAdjacent);
+        }
+        for color in [Color::White, Color::Black] {
+            let pawns = self.count_pieces(color, PieceKind::Pawn);
+            if pawns > 8 {
+                return Err(IllegalPosition::TooManyPawns(color));
+            }
+            let promoted = [(PieceKind::Queen, 1), (PieceKind::Rook, 2), (PieceKind::Bishop, 2), (PieceKind::Knight, 2)]
+                .into_iter()
+                .map(|(kind, starting_count)| self.count_pieces(color, kind).saturating_sub(starting_count))
+                .sum::<usize>();
+            if promoted > 8 - pawns {
+                return Err(IllegalPosition::TooManyPromotedPieces(color));
+            }
+        }
+        if let Some((square, _piece)) = self.pieces().find(|(square, piece)| {
+            piece.kind == PieceKind::Pawn && (square.index() < 8 || square.index() >= 56)
+        }) {
+            return Err(IllegalPosition::PawnOnBackRank(square));
+        }
+        let opponent = if self.active_player == 'w' { 'b' } else { 'w' };
+        if player_is_in_check(self, opponent) {
+            return Err(IllegalPosition::OpponentInCheck);
+        }
+        if let Some(square) = Square::from_algebraic(&self.en_passant_square) && !self.en_passant_fits(square) {
+            return Err(IllegalPosition::InvalidEnPassant(square));
+        }
+        Ok(())
+    }
+
+    fn count_pieces(&self, color: Color, kind: PieceKind) -> usize {
+        self.pieces_of(color.to_char()).filter(|(_square, piece)| piece.kind == kind).count()
+    }
+
+    fn en_passant_fits(&self, square: Square) -> bool {
+        let (skipped_rank, pawn, pushed_from, pushed_to) = match self.active_player {
+            'w' => ('6', 'p', 8, -8),
+            _ => ('3', 'P', -8, 8),
+        }; // The pawn that just moved was pushed from one rank behind the skipped square to one rank ahead of it
+        let offset = |delta: i8| Square::from_index((square.index() as i8 + delta) as u8);
+        square.rank().to_char() == skipped_rank
+            && self.piece_on(square) == '*'
+            && offset(pushed_from).is_some_and(|from| self.piece_on(from) == '*')
+            && offset(pushed_to).is_some_and(|to| self.piece_on(to) == pawn)
+    }
+
+    pub(crate) fn piece_on(&self, square: Square) -> char {
+        let coords = square.coords();
+        self.board_state[coords[0] as usize][coords[1] as usize]
+    } // Returns the content of a square.
+}
+
+impl TryFrom<[[char; 8]; 8]> for Board {
+    type Error = PositionError;
+
+    /// A function to create a Board from a grid of characters, e.g. a diagram in a test.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// grid: [[char; 8]; 8], // The pieces in FEN notation, in the same order as Board::to_array(): grid[0][0] is a8 and grid[7][7] is h1.
+    ///                       // Empty squares can be '.', ' ' or '*'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Board, or PositionError::InvalidCell with the row and column of the first cell
+    /// (in reading order) that is neither. Like Board::from_fen_lenient(), White is to move, castling rights are
+    /// inferred from the pieces (see docs for Board::from_board_fen), and there is no en passant square; use
+    /// Board::from_array() to set them. The position isn't validated.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Board};
+    /// let grid = [
+    ///     ['r', '.', '.', '.', 'k', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', '.', '.', '.', '.'],
+    ///     ['.', '.', '.', '.', 'K', '.', '.', 'R'],
+    /// ];
+    /// assert_eq!(generate_fen(&Board::try_from(grid).unwrap()), "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1");
+    /// ```
+    fn try_from(grid: [[char; 8]; 8]) -> Result<Board, PositionError> {
+        let mut array = [[None; 8]; 8];
+        for (row, characters) in grid.iter().enumerate() {
+            for (column, &character) in characters.iter().enumerate() {
+                array[row][column] = match character {
+                    '.' | ' ' | '*' => None,
+                    _ => Some(Piece::from_fen_char(character).ok_or(PositionError::InvalidCell { row, column, character })?),
+                };
+            }
+        }
+        let mut board = Board::from_array(array, BoardMeta::default());
+        board.castling_rights = inferred_castling_rights(&board);
+        Ok(board)
+    }
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+/// Each castling right, with the indices of the squares its king and rook must stand on (see docs for Square).
+pub(crate) const CASTLING_SQUARES: [(char, u8, u8, char, char); 4] = [
+    ('K', 4, 7, 'K', 'R'), // e1, h1
+    ('Q', 4, 0, 'K', 'R'), // e1, a1
+    ('k', 60, 63, 'k', 'r'), // e8, h8
+    ('q', 60, 56, 'k', 'r'), // e8, a8
+];
+
+pub(crate) fn inferred_castling_rights(board: &Board) -> CastlingRights {
+    let mut rights = CastlingRights::default();
+    for (right, king_index, rook_index, king, rook) in CASTLING_SQUARES {
+        let piece_on = |index: u8| Square::from_index(index).map_or('*', |square| board.piece_on(square));
+        rights.set(right, piece_on(king_index) == king && piece_on(rook_index) == rook);
+    }
+    rights
+} // Every castling right whose king and rook are still on their starting squares, since they might not have moved
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fen, GameStatus};
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+    fn piece(fen_char: char) -> Piece {
+        Piece::from_fen_char(fen_char).unwrap()
+    }
+
+    #[test]
+    fn test_build_and_play_endgame() {
+        let mut test_game = Game::new();
+        test_game.edit()
+            .clear()
+            .put_piece(square("g6"), piece('K'))
+            .put_piece(square("a7"), piece('Q'))
+            .put_piece(square("h8"), piece('k'))
+            .set_side_to_move(Color::White)
+            .finish()
+            .unwrap();
+        assert_eq!(test_game.fen, "7k/Q7/6K1/8/8/8/8/8 w - - 0 1");
+        assert_eq!(test_game.start_fen, test_game.fen);
+        assert_eq!(test_game.game_status, GameStatus::InProgress);
+        assert!(test_game.make_uci_move("a7g7")); // Qg7#
+        assert_eq!(test_game.game_status, GameStatus::WhiteWinsByCheckmate);
+    }
+    #[test]
+    fn test_finish_rejects_illegal_edits() {
+        let mut test_game = Game::new();
+        let original_fen = test_game.fen.clone();
+        let mut editor = test_game.edit();
+        editor.put_piece(square("d4"), piece('K'));
+        assert_eq!(editor.finish(), Err(PositionError::TooManyKings(Color::White)));
+        editor.remove_piece(square("d4")).put_piece(square("d1"), piece('P'));
+        assert_eq!(editor.finish(), Err(PositionError::PawnOnBackRank(square("d1"))));
+        editor.put_piece(square("d1"), piece('Q')).remove_piece(square("h1"));
+        assert_eq!(editor.finish(), Err(PositionError::InvalidCastlingRights('K')));
+        editor.set_castling_rights("Qkq");
+        assert!(editor.finish().is_ok());
+        assert_ne!(test_game.fen, original_fen);
+        assert_eq!(test_game.board.castling_rights.to_fen(), "Qkq");
+    }
+    #[test]
+    fn test_game_is_untouched_until_finish() {
+        let mut test_game = Game::new();
+        test_game.edit().clear();
+        assert_eq!(test_game, Game::new());
+    }
+    #[test]
+    fn test_board_builder() {
+        let board = BoardBuilder::empty()
+            .piece("e1", 'K')
+            .piece("e8", 'k')
+            .piece("a1", 'R')
+            .side_to_move('w')
+            .castling("Q")
+            .build()
+            .unwrap();
+        assert_eq!(crate::generate_fen(&board), "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        let test_game = Game::from_board(board);
+        assert!(test_game.legal_moves().contains(&crate::Move::from_uci("e1c1").unwrap())); // Castling queenside
+    }
+    #[test]
+    fn test_board_builder_errors() {
+        assert_eq!(BoardBuilder::empty().piece("e9", 'K').build(), Err(PositionError::InvalidSquare("e9".to_string())));
+        assert_eq!(BoardBuilder::empty().piece("e1", 'X').build(), Err(PositionError::InvalidPiece('X')));
+        assert_eq!(BoardBuilder::empty().piece("e1", 'K').build(), Err(PositionError::MissingKing(Color::Black)));
+        assert_eq!(
+            BoardBuilder::empty().piece("e1", 'K').piece("e8", 'k').castling("K").build(),
+            Err(PositionError::InvalidCastlingRights('K'))
+        );
+        let mut builder = BoardBuilder::empty();
+        builder.piece("e1", 'K').piece("e8", 'k').counters(12, 30).side_to_move('b');
+        assert_eq!(crate::generate_fen(&builder.build().unwrap()), "4k3/8/8/8/8/8/8/4K3 b - - 12 30");
+    }
+    #[test]
+    fn test_validate() {
+        assert_eq!(parse_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").validate(), Ok(()));
+        assert_eq!(parse_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").validate(), Err(PositionError::MissingKing(Color::White)));
+        assert_eq!(parse_fen("4k2R/8/8/8/8/8/8/4K3 w - - 0 1").validate(), Err(PositionError::OpponentInCheck));
+        assert_eq!(parse_fen("4k2R/8/8/8/8/8/8/4K3 b - - 0 1").validate(), Ok(()));
+        assert_eq!(parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").validate(), Ok(()));
+        assert_eq!(parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - e6 0 1").validate(), Err(PositionError::InvalidEnPassant(square("e6"))));
+        assert_eq!(parse_fen("4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1").validate(), Err(PositionError::InvalidEnPassant(square("d6"))));
+        assert_eq!(parse_fen("4k3/8/8/8/4Pp2/8/8/4K3 b - e3 0 1").validate(), Ok(()));
+    }
+    #[test]
+    fn test_is_legal_position() {
+        let legal = |fen: &str| parse_fen(fen).is_legal_position();
+        assert_eq!(legal("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), Ok(()));
+        assert_eq!(legal("4k3/8/8/8/8/8/8/8 w - - 0 1"), Err(IllegalPosition::MissingKing(Color::White)));
+        assert_eq!(legal("4k3/8/8/8/8/8/8/3kK3 w - - 0 1"), Err(IllegalPosition::TooManyKings(Color::Black)));
+        assert_eq!(legal("8/8/8/8/8/8/8/3kK3 w - - 0 1"), Err(IllegalPosition::KingsAdjacent));
+        assert_eq!(legal("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"), Err(IllegalPosition::TooManyPawns(Color::Black)));
+        assert_eq!(legal("4k3/8/8/8/8/QQQ5/PPPPPPP1/4K3 w - - 0 1"), Err(IllegalPosition::TooManyPromotedPieces(Color::White)));
+        assert_eq!(legal("4k3/8/8/8/8/QQQ5/PPPPPP2/4K3 w - - 0 1"), Ok(())); // Two pawns promoted to queens
+        assert_eq!(legal("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"), Err(IllegalPosition::PawnOnBackRank(square("a8"))));
+        assert_eq!(legal("4k2R/8/8/8/8/8/8/4K3 w - - 0 1"), Err(IllegalPosition::OpponentInCheck));
+        assert_eq!(legal("4k3/8/8/3pP3/8/8/8/4K3 w - e6 0 1"), Err(IllegalPosition::InvalidEnPassant(square("e6"))));
+        assert_eq!(legal("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"), Ok(()));
+    }
+    #[test]
+    fn test_array_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/1pp2p1p/p1n5/3pP3/8/5N2/PPP2PPP/R3K1R1 w Qkq d6 0 14",
+        ] {
+            let board = parse_fen(fen);
+            let array = board.to_array();
+            assert_eq!(Board::from_array(array, board.meta()), board, "{}", fen);
+        }
+        let array = parse_fen("r3k2r/1pp2p1p/p1n5/3pP3/8/5N2/PPP2PPP/R3K1R1 w Qkq d6 0 14").to_array();
+        assert_eq!(array[0][0], Some(piece('r'))); // a8
+        assert_eq!(array[0][7], Some(piece('r'))); // h8
+        assert_eq!(array[2][2], Some(piece('n'))); // c6
+        assert_eq!(array[3][4], Some(piece('P'))); // e5
+        assert_eq!(array[5][5], Some(piece('N'))); // f3
+        assert_eq!(array[7][6], Some(piece('R'))); // g1
+        assert_eq!(array[7][7], None); // h1
+        assert_eq!(array.iter().flatten().flatten().count(), 21);
+    }
+    #[test]
+    fn test_board_from_char_grid() {
+        let mut grid = [
+            ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'],
+            ['p', 'p', 'p', 'p', 'p', 'p', 'p', 'p'],
+            ['.', '.', '.', '.', '.', '.', '.', '.'],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['*', '*', '*', '*', 'P', '*', '*', '*'],
+            ['.', ' ', '*', '.', ' ', '*', '.', ' '],
+            ['P', 'P', 'P', 'P', '.', 'P', 'P', 'P'],
+            ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'],
+        ];
+        let board = Board::try_from(grid).unwrap();
+        assert_eq!(generate_fen(&board), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(board.to_array().map(|row| row.map(|cell| cell.map_or('.', |piece| piece.to_fen_char()))), grid.map(|row| row.map(|cell| if cell == ' ' || cell == '*' { '.' } else { cell })));
+
+        grid[3][2] = 'x'; // c5
+        let error = Board::try_from(grid).unwrap_err();
+        assert_eq!(error, PositionError::InvalidCell { row: 3, column: 2, character: 'x' });
+        assert_eq!(error.to_string(), "'x' in row 3, column 2 is not a piece or an empty square");
+        grid[1][7] = '0'; // h7 comes first in reading order
+        assert_eq!(Board::try_from(grid), Err(PositionError::InvalidCell { row: 1, column: 7, character: '0' }));
+    }
+}
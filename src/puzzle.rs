@@ -0,0 +1,319 @@
+/*****************************
+*  PUZZLES                   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Verification of tactics puzzles, e.g. for validating the entries of a puzzle database.
+
+A puzzle is a starting position and a solution line. The player to move in the starting position is the solver, and
+every other move in the line is an opponent reply. A puzzle is sound if every solver move meets the PuzzleCriteria and
+is the only move that does, since a puzzle with two solutions (a "dual") can't tell a right answer from a wrong one.
+
+The module can also mine puzzles from games: mates_in_one() lists the mating moves of a position, and
+find_mate_in_one_positions() finds every moment of a game where one was on the board, played or missed.
+
+```
+# use alviny_task_3::puzzle::{verify, PuzzleCriteria};
+// Philidor's legacy: 1. Qg8+ Rxg8 2. Nf7#
+assert_eq!(verify("4r2k/6pp/7N/3Q4/8/8/8/6K1 w - - 0 1", &["Qg8+", "Rxg8", "Nf7#"], PuzzleCriteria::Mate), Ok(()));
+```
+*/
+
+use std::fmt;
+
+use crate::checks::scratch_board;
+use crate::san::SanError;
+use crate::{has_legal_move, FenError, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An enum to represent what a solver move has to achieve.
+///
+/// ```text
+/// Mate, // Force checkmate within the rest of the line.
+/// Advantage(i32), // Leave the solver at least this many centipawns ahead, e.g. 800 for "win the queen". The score comes from
+///                 // a search (see docs for Game::best_move) as deep as the rest of the line. Checkmate always counts.
+/// ```
+pub enum PuzzleCriteria {
+    Mate,
+    Advantage(i32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why a puzzle isn't sound. ply is the index of the offending move in the line, starting from 0.
+///
+/// ```text
+/// InvalidFen(FenError), // The starting position can't be read (see docs for FenError).
+/// IllegalMove { ply, move_text, reason }, // A move of the line can't be played (see docs for SanError).
+/// NotWinning { ply, move_text }, // A solver move doesn't meet the criteria.
+/// Dual { ply, move_text, alternative }, // Another move meets the criteria as well, so the solution isn't unique.
+/// ```
+pub enum PuzzleError {
+    InvalidFen(FenError),
+    IllegalMove { ply: usize, move_text: String, reason: SanError },
+    NotWinning { ply: usize, move_text: String },
+    Dual { ply: usize, move_text: String, alternative: Move },
+}
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::InvalidFen(fen_error) => write!(f, "invalid starting position: {}", fen_error),
+            PuzzleError::IllegalMove { ply, move_text, reason } => write!(f, "ply {} (\"{}\"): {}", ply + 1, move_text, reason),
+            PuzzleError::NotWinning { ply, move_text } => write!(f, "ply {} (\"{}\"): doesn't meet the criteria", ply + 1, move_text),
+            PuzzleError::Dual { ply, move_text, alternative } => {
+                write!(f, "ply {} (\"{}\"): {} works as well", ply + 1, move_text, alternative.to_uci())
+            }
+        }
+    }
+}
+impl std::error::Error for PuzzleError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent a moment of a game where the side to move could mate in one (see docs for
+/// find_mate_in_one_positions).
+///
+/// ## Attributes
+/// ```text
+/// ply: usize, // The index in history of the move that was played from the position, starting from 0.
+/// fen: String, // The position.
+/// mating_moves: Vec<Move>, // Every move that mates, see docs for mates_in_one.
+/// played: Move, // The move that was played instead, or one of the mating moves.
+/// ```
+pub struct MateInOnePosition {
+    pub ply: usize,
+    pub fen: String,
+    pub mating_moves: Vec<Move>,
+    pub played: Move,
+}
+impl MateInOnePosition {
+    /// A function to check whether the mate was missed, i.e. the move played doesn't mate.
+    pub fn missed(&self) -> bool {
+        !self.mating_moves.contains(&self.played)
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn read_move(game: &Game, move_text: &str) -> Result<Move, SanError> {
+    let parsed_move = match Move::from_uci(move_text) {
+        Some(uci_move) => uci_move,
+        None => game.parse_san(move_text)?,
+    };
+    if !game.legal_moves().contains(&parsed_move) {
+        return Err(SanError::Illegal);
+    }
+    Ok(parsed_move)
+} // Reads a move in UCI or SAN, and checks that it is legal.
+
+fn meets_criteria(game: &Game, candidate: &Move, plies_left: u32, criteria: PuzzleCriteria) -> bool {
+    let mut after = game.clone();
+    if !after.make_uci_move(&candidate.to_uci()) {
+        return false;
+    }
+    if after.is_checkmate() {
+        return true;
+    }
+    if after.is_game_over() {
+        return false;
+    }
+    match criteria {
+        PuzzleCriteria::Mate => {
+            plies_left >= 3
+                && after.legal_moves().iter().all(|reply| {
+                    let mut after_reply = after.clone();
+                    after_reply.make_uci_move(&reply.to_uci());
+                    after_reply.find_forced_mate(plies_left - 2).is_some()
+                })
+        }
+        PuzzleCriteria::Advantage(threshold) => {
+            let depth = (plies_left - 1).max(1);
+            after.best_move(depth).is_some_and(|(_reply, score)| -score >= threshold)
+        }
+    }
+} // Checks whether a solver move meets the criteria, with plies_left plies of the line left, counting the move itself.
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to list every legal move that checkmates at once. Only moves that give check are played out, each on a
+/// scratch board rather than a copy of the Game, so this is cheap enough to run on every position of a game.
+///
+/// ## Arguments
+/// ```text
+/// game: &Game, // The position. Mates are judged by the standard rules, whatever the variant.
+///
+/// ```
+/// ## Returns
+/// This function returns the mating moves, sorted as by Game::legal_moves_sorted(). Empty if there are none or the
+/// game is over.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::puzzle::mates_in_one;
+/// let example_game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string());
+/// assert_eq!(mates_in_one(&example_game)[0].to_uci(), "a1a8"); // A back rank mate
+/// assert!(mates_in_one(&Game::new()).is_empty());
+/// ```
+pub fn mates_in_one(game: &Game) -> Vec<Move> {
+    if game.is_game_over() {
+        return vec![];
+    }
+    game.legal_moves_sorted()
+        .into_iter()
+        .filter(|candidate| {
+            game.gives_check(candidate)
+                && scratch_board(&game.board, candidate).is_some_and(|next_board| !has_legal_move(&next_board))
+        })
+        .collect()
+}
+
+/// A function to replay a game and find every position where the side to move could mate in one, whether the mate was
+/// played or missed, e.g. for "you missed a mate" reports.
+///
+/// ## Arguments
+/// ```text
+/// game: &Game, // The game, replayed from its start_fen through its history.
+///
+/// ```
+/// ## Returns
+/// This function returns the positions in the order they occurred (see docs for MateInOnePosition).
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::puzzle::find_mate_in_one_positions;
+/// let example_game = Game::from_uci_moves("e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7").unwrap(); // The scholar's mate
+/// let positions = find_mate_in_one_positions(&example_game);
+/// assert_eq!(positions.len(), 1);
+/// assert_eq!((positions[0].ply, positions[0].missed()), (6, false));
+/// ```
+pub fn find_mate_in_one_positions(game: &Game) -> Vec<MateInOnePosition> {
+    let Ok(mut replay) = Game::try_from_fen(&game.start_fen) else {
+        return vec![];
+    };
+    let mut positions = vec![];
+    for (ply, played) in game.history.iter().enumerate() {
+        let mating_moves = mates_in_one(&replay);
+        if !mating_moves.is_empty() {
+            positions.push(MateInOnePosition { ply, fen: replay.fen.clone(), mating_moves, played: played.clone() });
+        }
+        if !replay.make_uci_move(&played.to_uci()) {
+            break;
+        }
+    }
+    positions
+}
+
+/// A function to verify a puzzle: that every move of the solution line is legal, and that every solver move meets the
+/// criteria while no other move does.
+///
+/// ## Arguments
+/// ```text
+/// fen: &str, // The starting position. The side to move is the solver.
+/// line: &[&str], // The solution line in UCI or SAN, solver moves and opponent replies taking turns, e.g. ["Qg8+", "Rxg8", "Nf7#"].
+/// criteria: PuzzleCriteria, // What the solver moves have to achieve (see docs for PuzzleCriteria).
+///
+/// ```
+/// ## Returns
+/// This function returns Ok(()) if the puzzle is sound, or a PuzzleError (see docs for PuzzleError) for the first ply
+/// where it isn't.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Move;
+/// # use alviny_task_3::puzzle::{verify, PuzzleCriteria, PuzzleError};
+/// // Both rooks mate on the back rank
+/// let dual = verify("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["e1e8"], PuzzleCriteria::Mate);
+/// assert_eq!(dual, Err(PuzzleError::Dual { ply: 0, move_text: "e1e8".to_string(), alternative: Move::from_uci("a1a8").unwrap() }));
+/// ```
+pub fn verify(fen: &str, line: &[&str], criteria: PuzzleCriteria) -> Result<(), PuzzleError> {
+    let mut game = Game::try_from_fen(fen).map_err(PuzzleError::InvalidFen)?;
+    for (ply, move_text) in line.iter().enumerate() {
+        let played = read_move(&game, move_text)
+            .map_err(|reason| PuzzleError::IllegalMove { ply, move_text: move_text.to_string(), reason })?;
+        if ply % 2 == 0 {
+            let plies_left = (line.len() - ply) as u32;
+            if !meets_criteria(&game, &played, plies_left, criteria) {
+                return Err(PuzzleError::NotWinning { ply, move_text: move_text.to_string() });
+            }
+            let alternatives = game.legal_moves_sorted().into_iter().filter(|alternative| *alternative != played);
+            for alternative in alternatives {
+                if meets_criteria(&game, &alternative, plies_left, criteria) {
+                    return Err(PuzzleError::Dual { ply, move_text: move_text.to_string(), alternative });
+                }
+            }
+        }
+        game.make_uci_move(&played.to_uci());
+    }
+    Ok(())
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMOTHERED_MATE: &str = "4r2k/6pp/7N/3Q4/8/8/8/6K1 w - - 0 1";
+
+    #[test]
+    fn test_sound_puzzles() {
+        assert_eq!(verify(SMOTHERED_MATE, &["Qg8+", "Rxg8", "Nf7#"], PuzzleCriteria::Mate), Ok(()));
+        assert_eq!(verify(SMOTHERED_MATE, &["d5g8", "e8g8", "h6f7"], PuzzleCriteria::Mate), Ok(()));
+        let hanging_queen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        assert_eq!(verify(hanging_queen, &["Rxd5"], PuzzleCriteria::Advantage(400)), Ok(()));
+    }
+    #[test]
+    fn test_dual_solution() {
+        let puzzle_error = verify("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["Ra8#"], PuzzleCriteria::Mate).unwrap_err();
+        assert_eq!(
+            puzzle_error,
+            PuzzleError::Dual { ply: 0, move_text: "Ra8#".to_string(), alternative: Move::from_uci("e1e8").unwrap() }
+        );
+        assert_eq!(puzzle_error.to_string(), "ply 1 (\"Ra8#\"): e1e8 works as well");
+    }
+    #[test]
+    fn test_broken_lines() {
+        let puzzle_error = verify(SMOTHERED_MATE, &["Nf7+", "Kg8", "Nh6+"], PuzzleCriteria::Mate).unwrap_err();
+        assert_eq!(puzzle_error, PuzzleError::NotWinning { ply: 0, move_text: "Nf7+".to_string() });
+        let puzzle_error = verify(SMOTHERED_MATE, &["Qg8+", "Kxg8", "Nf7#"], PuzzleCriteria::Mate).unwrap_err();
+        assert_eq!(puzzle_error, PuzzleError::IllegalMove { ply: 1, move_text: "Kxg8".to_string(), reason: SanError::Illegal });
+        assert!(matches!(verify("8/8/8", &[], PuzzleCriteria::Mate), Err(PuzzleError::InvalidFen(_))));
+    }
+    #[test]
+    fn test_mates_in_one() {
+        let two_rooks = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1".to_string());
+        assert_eq!(mates_in_one(&two_rooks), [Move::from_uci("a1a8").unwrap(), Move::from_uci("e1e8").unwrap()]);
+        let promotion = Game::new_from_fen("7k/1P4pp/8/8/8/8/8/6K1 w - - 0 1".to_string());
+        assert_eq!(mates_in_one(&promotion).iter().map(Move::to_uci).collect::<Vec<_>>(), ["b7b8q", "b7b8r"]);
+        assert!(mates_in_one(&Game::new_from_fen(SMOTHERED_MATE.to_string())).is_empty()); // Mate in two
+    }
+    #[test]
+    fn test_missed_mate() {
+        // 3. Qh5 Nf6?? allows Qxf7#, but White plays 4. Qf3 and only mates after 4... Ng4?
+        let missed = Game::from_uci_moves("e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f3 f6g4 f3f7").unwrap();
+        assert!(missed.is_checkmate());
+        let positions = find_mate_in_one_positions(&missed);
+        assert_eq!(positions.len(), 2);
+        assert_eq!((positions[0].ply, positions[0].mating_moves.clone(), positions[0].missed()), (6, vec![Move::from_uci("h5f7").unwrap()], true));
+        assert_eq!(positions[0].fen, "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4");
+        assert_eq!((positions[1].ply, positions[1].played.to_uci(), positions[1].missed()), (8, "f3f7".to_string(), false));
+    }
+}
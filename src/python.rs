@@ -0,0 +1,208 @@
+/*****************************
+*  PYTHON BINDINGS           *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Python bindings, built with PyO3 when the `python` feature is enabled, so that games can be played and positions
+generated from Python without python-chess.
+
+The module is built with maturin (see pyproject.toml), e.g. `maturin develop` in a virtualenv, and imported as
+`alviny_task_3`:
+
+```python
+import alviny_task_3
+
+game = alviny_task_3.Game()
+game.make_move("e2e4")
+assert game.legal_moves()["e7"] == ["e5", "e6"]
+assert game.board.piece_at("e4") == "P"
+```
+
+Every method goes through the fallible APIs of the library (try_from_fen, make_uci_move, ...), and errors are raised
+as Python exceptions: InvalidFenError and IllegalMoveError, which are both ValueErrors.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{Board, Color, Game, Move, Square};
+
+create_exception!(alviny_task_3, InvalidFenError, PyValueError, "Raised when a FEN string can't be read.");
+create_exception!(alviny_task_3, IllegalMoveError, PyValueError, "Raised when a move is malformed or illegal.");
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[pyclass(name = "Game", module = "alviny_task_3")]
+/// A struct to represent a game, as the Python class Game. The game is behind a Mutex, since Python objects can be
+/// shared between threads.
+pub struct PyGame {
+    game: Mutex<Game>,
+}
+#[pymethods]
+impl PyGame {
+    /// A function to create a new game at the starting position. Game() in Python.
+    #[new]
+    pub fn new() -> PyGame {
+        PyGame { game: Mutex::new(Game::new()) }
+    }
+
+    /// A function to create a game from a FEN string. Game.from_fen(fen) in Python.
+    ///
+    /// ## Returns
+    /// This function returns the game, or raises InvalidFenError if the FEN can't be read.
+    #[staticmethod]
+    pub fn from_fen(fen: &str) -> PyResult<PyGame> {
+        Game::try_from_fen(fen).map(|game| PyGame { game: Mutex::new(game) }).map_err(|fen_error| InvalidFenError::new_err(fen_error.to_string()))
+    }
+
+    /// A function to make a move in long algebraic (UCI) notation, e.g. "e2e4" or "e7e8n". Promotions without a
+    /// piece promote to a queen.
+    ///
+    /// ## Returns
+    /// This function returns None, or raises IllegalMoveError if the move is malformed or not legal. The game is
+    /// unchanged if the move was refused.
+    pub fn make_move(&self, uci_move: &str) -> PyResult<()> {
+        let mut game = self.game();
+        if game.is_game_over() {
+            return Err(IllegalMoveError::new_err(format!("{}: the game is over", uci_move)));
+        }
+        let requested = Move::from_uci(uci_move).ok_or_else(|| IllegalMoveError::new_err(format!("{}: not a move", uci_move)))?;
+        if !game.make_uci_move(&requested.to_uci()) {
+            return Err(IllegalMoveError::new_err(format!("{}: illegal in this position", uci_move)));
+        }
+        Ok(())
+    }
+
+    /// A function to list the legal moves of the side to move.
+    ///
+    /// ## Returns
+    /// This function returns a dict from every square with a piece that can move, to the list of squares it can move
+    /// to in order, e.g. {"g1": ["f3", "h3"], ...}. A promotion is listed once, for its square.
+    pub fn legal_moves(&self) -> HashMap<String, Vec<String>> {
+        let mut moves: HashMap<String, Vec<String>> = HashMap::new();
+        for legal_move in self.game().legal_moves() {
+            let targets = moves.entry(legal_move.source).or_default();
+            if !targets.contains(&legal_move.target) {
+                targets.push(legal_move.target);
+            }
+        }
+        moves.values_mut().for_each(|targets| targets.sort());
+        moves
+    }
+
+    /// A function to return the FEN of the current position. game.fen in Python.
+    #[getter]
+    pub fn fen(&self) -> String {
+        self.game().fen.clone()
+    }
+
+    /// A function to return how the game stands, as the name of its GameStatus, e.g. "InProgress" or
+    /// "WhiteWinsByCheckmate". game.status in Python.
+    #[getter]
+    pub fn status(&self) -> String {
+        format!("{:?}", self.game().game_status)
+    }
+
+    /// A function to check whether the side to move is in check.
+    pub fn is_check(&self) -> bool {
+        let game = self.game();
+        let side_to_move = Color::from_char(game.board.active_player).unwrap_or(Color::White);
+        game.checks.get(if side_to_move == Color::White { 0 } else { 1 }).copied().unwrap_or(false)
+    }
+
+    /// A function to return a copy of the current board. game.board in Python.
+    #[getter]
+    pub fn board(&self) -> PyBoard {
+        PyBoard { board: self.game().board.clone() }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game(\"{}\")", self.game().fen)
+    }
+}
+impl PyGame {
+    fn game(&self) -> MutexGuard<'_, Game> {
+        self.game.lock().unwrap_or_else(PoisonError::into_inner)
+    } // Locks the game. Moves are checked before they are made, so a poisoned lock is used as it is.
+}
+impl Default for PyGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[pyclass(name = "Board", module = "alviny_task_3")]
+/// A struct to represent a board, as the Python class Board. Boards are copies, so changing the game afterwards
+/// doesn't change them.
+pub struct PyBoard {
+    board: Board,
+}
+#[pymethods]
+impl PyBoard {
+    /// A function to find the piece on a square.
+    ///
+    /// ## Returns
+    /// This function returns the piece's FEN letter (capital for white, lowercase for black), or None if the square is
+    /// empty. Raises ValueError if the string isn't a square.
+    pub fn piece_at(&self, square: &str) -> PyResult<Option<String>> {
+        let square = Square::from_algebraic(square).ok_or_else(|| PyValueError::new_err(format!("{} is not a square", square)))?;
+        Ok(self.board.pieces().find(|(occupied, _piece)| *occupied == square).map(|(_square, piece)| piece.to_fen_char().to_string()))
+    }
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+#[pymodule]
+/// The Python module alviny_task_3.
+pub fn alviny_task_3(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyBoard>()?;
+    module.add("InvalidFenError", module.py().get_type::<InvalidFenError>())?;
+    module.add("IllegalMoveError", module.py().get_type::<IllegalMoveError>())?;
+    Ok(())
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrappers() {
+        let game = PyGame::new();
+        assert_eq!(game.legal_moves().values().map(Vec::len).sum::<usize>(), 20);
+        assert_eq!(game.legal_moves()["g1"], vec!["f3", "h3"]);
+        for uci_move in ["f2f3", "e7e5", "g2g4"] {
+            game.make_move(uci_move).unwrap();
+        }
+        assert!(game.make_move("e2e5").is_err());
+        assert!(game.make_move("nonsense").is_err());
+        game.make_move("d8h4").unwrap();
+        assert!(game.is_check());
+        assert_eq!(game.status(), "BlackWinsByCheckmate");
+        assert!(game.legal_moves().is_empty());
+        assert!(game.make_move("e2e4").is_err());
+        assert_eq!(game.board().piece_at("h4").unwrap(), Some("q".to_string()));
+        assert_eq!(game.board().piece_at("e4").unwrap(), None);
+        assert!(game.board().piece_at("z9").is_err());
+
+        let promotion = PyGame::from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(promotion.legal_moves()["a7"], vec!["a8"]);
+        assert!(PyGame::from_fen("8/8/8 w - - 0 1").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+/*****************************
+*  RANDOM MOVES              *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Random move selection, for fuzzing and for "beginner bot" opponents. Only available with the `rand` feature.
+
+The random number generator is always passed in by the caller, so a game can be reproduced by seeding it the same way.
+*/
+
+use rand::Rng;
+
+use crate::matchrunner::MovePicker;
+use crate::{Game, GameStatus, Move};
+
+/// A struct to play uniformly random legal moves, e.g. in a match (see docs for run_match).
+///
+/// ## Attributes
+/// ```text
+/// rng: R, // The random number generator to draw from.
+/// ```
+pub struct RandomPicker<R: Rng> {
+    pub rng: R,
+}
+impl<R: Rng> MovePicker for RandomPicker<R> {
+    fn pick(&mut self, game: &Game) -> Move {
+        game.random_move(&mut self.rng).expect("Good heavens! Asked to pick a move in a finished game!")
+    }
+}
+
+impl Game {
+    /// A function to pick a uniformly random legal move for the active player.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// rng: &mut impl Rng, // The random number generator to draw from.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Move), or None if the active player has no legal moves.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use rand::SeedableRng;
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let random_move = example_game.random_move(&mut rng).unwrap();
+    /// assert!(example_game.legal_moves().contains(&random_move));
+    /// ```
+    pub fn random_move(&self, rng: &mut impl Rng) -> Option<Move> {
+        let mut moves = self.legal_moves();
+        if moves.is_empty() {
+            return None;
+        }
+        moves.sort_by_key(|legal_move| legal_move.to_uci()); // Rules::legal_moves() doesn't promise an order (custom variants may list moves any way), so seeded runs draw from UCI order
+        let index = rng.random_range(0..moves.len());
+        Some(moves[index].clone())
+    }
+
+    /// A function to play random moves from the current position until the game ends or the ply cap is hit.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// rng: &mut impl Rng, // The random number generator to draw from.
+    /// max_plies: u32, // The maximum number of half-moves to play.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the final game status (see docs for GameStatus) and the moves that were played.
+    /// A status of GameStatus::InProgress means the ply cap was hit before the game ended.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use rand::SeedableRng;
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// let (status, moves) = example_game.play_random_game(&mut rng, 10);
+    /// assert!(moves.len() <= 10);
+    /// ```
+    pub fn play_random_game(&mut self, rng: &mut impl Rng, max_plies: u32) -> (GameStatus, Vec<Move>) {
+        let mut moves = vec![];
+        while !self.is_game_over() && moves.len() < max_plies as usize {
+            let random_move = match self.random_move(rng) {
+                Some(random_move) => random_move,
+                None => break,
+            };
+            if !self.make_uci_move(&random_move.to_uci()) {
+                break;
+            }
+            moves.push(random_move);
+        }
+        (self.game_status, moves)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_random_games() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut test_game = Game::new();
+            let (status, moves) = test_game.play_random_game(&mut rng, 300);
+            assert_eq!(status, test_game.game_status);
+            assert!(status.is_game_over() || moves.len() == 300);
+        }
+    }
+    #[test]
+    fn test_random_games_are_reproducible() {
+        let mut first_game = Game::new();
+        let mut second_game = Game::new();
+        let (_, first_moves) = first_game.play_random_game(&mut StdRng::seed_from_u64(7), 40);
+        let (_, second_moves) = second_game.play_random_game(&mut StdRng::seed_from_u64(7), 40);
+        assert_eq!(first_moves, second_moves);
+        assert_eq!(first_game.fen, second_game.fen);
+    }
+    #[test]
+    fn test_no_random_move_when_game_is_over() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let checkmate = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert!(checkmate.random_move(&mut rng).is_none());
+    }
+}
@@ -0,0 +1,296 @@
+/*****************************
+*  BOARD RENDERING           *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Drawing the board as text, for terminals and logs.
+
+The plain rendering uses FEN letters (or chess symbols, with the unicode flag) and dots for empty squares. With a
+Palette, the squares are drawn with ANSI escape sequences in 256-color mode: light and dark backgrounds, white and black
+pieces in their own colors, and optionally the last move and a king in check highlighted. Whether the terminal can
+show colors is up to the caller; RenderOptions::for_terminal() also honours the NO_COLOR convention
+(<https://no-color.org>).
+
+```
+# use alviny_task_3::Game;
+# use alviny_task_3::render::RenderOptions;
+let example_game = Game::new();
+let rendering = example_game.render(&RenderOptions::default());
+assert!(rendering.starts_with("8 r n b q k b n r\n7 p p p p p p p p\n6 . . . . . . . .\n"));
+assert!(rendering.ends_with("1 R N B Q K B N R\n  a b c d e f g h\n"));
+```
+*/
+
+use std::env;
+use std::ffi::OsString;
+
+use crate::{Board, Game, Piece, PieceKind, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent the colors of a colored rendering, as codes of the 256-color ANSI palette (0 to 255).
+///
+/// ## Attributes
+/// ```text
+/// light_square: u8, // The background of the light squares.
+/// dark_square: u8, // The background of the dark squares.
+/// white_piece: u8, // The color of the white pieces.
+/// black_piece: u8, // The color of the black pieces.
+/// last_move: u8, // The background of the two squares of the last move.
+/// check: u8, // The background of a king in check.
+/// ```
+pub struct Palette {
+    pub light_square: u8,
+    pub dark_square: u8,
+    pub white_piece: u8,
+    pub black_piece: u8,
+    pub last_move: u8,
+    pub check: u8,
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Palette { light_square: 180, dark_square: 137, white_piece: 231, black_piece: 16, last_move: 143, check: 160 }
+    } // Brown squares, bright white and black pieces, an olive last move and a red check
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent how a board is drawn. The Default is the plain rendering, from White's side, with coordinates.
+///
+/// ## Attributes
+/// ```text
+/// unicode: bool, // Draw the pieces as chess symbols (♔, ♟, ...) instead of FEN letters.
+/// coordinates: bool, // Label the ranks on the left and the files below.
+/// flipped: bool, // Draw the board from Black's side.
+/// colors: Option<Palette>, // Draw with ANSI escape sequences in these colors. None draws plain text.
+/// highlight_last_move: bool, // With colors, highlight the squares the last move went from and to.
+/// highlight_check: bool, // With colors, highlight a king in check.
+/// ```
+pub struct RenderOptions {
+    pub unicode: bool,
+    pub coordinates: bool,
+    pub flipped: bool,
+    pub colors: Option<Palette>,
+    pub highlight_last_move: bool,
+    pub highlight_check: bool,
+}
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            unicode: false,
+            coordinates: true,
+            flipped: false,
+            colors: None,
+            highlight_last_move: true,
+            highlight_check: true,
+        }
+    }
+}
+impl RenderOptions {
+    /// A function to create options for drawing in a terminal.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// supports_color: bool, // Whether the terminal can show 256 colors, as decided by the caller.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the default options with the default Palette, or the plain default options if the
+    /// terminal doesn't support colors or the NO_COLOR environment variable is set to anything but "".
+    pub fn for_terminal(supports_color: bool) -> RenderOptions {
+        RenderOptions { colors: terminal_colors(supports_color, env::var_os("NO_COLOR")), ..RenderOptions::default() }
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const RESET: &str = "\x1b[0m";
+
+fn terminal_colors(supports_color: bool, no_color: Option<OsString>) -> Option<Palette> {
+    let colors_disabled = no_color.is_some_and(|value| !value.is_empty());
+    (supports_color && !colors_disabled).then(Palette::default)
+} // The palette for_terminal() uses, given the value of NO_COLOR.
+
+fn glyph(piece: Option<Piece>, unicode: bool) -> char {
+    let Some(piece) = piece else {
+        return if unicode { '·' } else { '.' };
+    };
+    if !unicode {
+        return piece.to_fen_char();
+    }
+    let symbols = if piece.color == 'w' { ['♙', '♘', '♗', '♖', '♕', '♔'] } else { ['♟', '♞', '♝', '♜', '♛', '♚'] };
+    match piece.kind {
+        PieceKind::Pawn => symbols[0],
+        PieceKind::Knight => symbols[1],
+        PieceKind::Bishop => symbols[2],
+        PieceKind::Rook => symbols[3],
+        PieceKind::Queen => symbols[4],
+        PieceKind::King => symbols[5],
+    }
+} // The character a square is drawn with.
+
+fn render_board(board: &Board, options: &RenderOptions, last_move: &[Square], checked_kings: &[Square]) -> String {
+    let order = if options.flipped { [7, 6, 5, 4, 3, 2, 1, 0] } else { [0, 1, 2, 3, 4, 5, 6, 7] };
+    let mut output = String::new();
+    for row in order {
+        if options.coordinates {
+            output.push_str(&format!("{} ", 8 - row));
+        }
+        let squares = order.map(|col| {
+            let square = Square::from_coords(&[row, col]);
+            let piece = Piece::from_fen_char(board.board_state[row as usize][col as usize]);
+            let Some(palette) = options.colors else {
+                return glyph(piece, options.unicode).to_string();
+            };
+            let background = if options.highlight_check && checked_kings.contains(&square) {
+                palette.check
+            } else if options.highlight_last_move && last_move.contains(&square) {
+                palette.last_move
+            } else if (square.file().index() + square.rank().index()) % 2 == 1 {
+                palette.light_square
+            } else {
+                palette.dark_square
+            };
+            match piece {
+                None => format!("\x1b[48;5;{}m   ", background),
+                Some(piece) => {
+                    let foreground = if piece.color == 'w' { palette.white_piece } else { palette.black_piece };
+                    format!("\x1b[48;5;{};38;5;{}m {} ", background, foreground, glyph(Some(piece), options.unicode))
+                }
+            }
+        });
+        match options.colors {
+            None => output.push_str(&squares.join(" ")),
+            Some(_) => output.push_str(&(squares.concat() + RESET)),
+        }
+        output.push('\n');
+    }
+    if options.coordinates {
+        let files = order.map(|col| char::from(b'a' + col as u8));
+        match options.colors {
+            None => output.push_str(&format!("  {}\n", files.map(String::from).join(" "))),
+            Some(_) => output.push_str(&format!("  {}\n", files.map(|file| format!(" {} ", file)).concat())),
+        }
+    }
+    output
+} // Draws the board rank by rank, highlighting the given squares. Colored ranks end with a reset, so that the colors
+// never leak into whatever is printed next.
+
+fn checked_kings(board: &Board) -> Vec<Square> {
+    ['w', 'b']
+        .into_iter()
+        .filter_map(|color| board.king_square(color).filter(|king| board.is_square_attacked(*king, if color == 'w' { 'b' } else { 'w' })))
+        .collect()
+} // The squares of the kings that are in check.
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to draw the board as text. A board doesn't know its last move, so only kings in check are
+    /// highlighted (see docs for Game::render).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &RenderOptions, // How to draw the board (see docs for RenderOptions).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the drawing, one line per rank and a line of file labels, each ending in a newline.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::render::RenderOptions;
+    /// let example_game = Game::new_from_fen("8/8/8/8/8/8/8/K6k w - - 0 1".to_string());
+    /// let options = RenderOptions { unicode: true, coordinates: false, ..RenderOptions::default() };
+    /// assert!(example_game.board.render(&options).ends_with("♔ · · · · · · ♚\n"));
+    /// ```
+    pub fn render(&self, options: &RenderOptions) -> String {
+        render_board(self, options, &[], &checked_kings(self))
+    }
+}
+
+impl Game {
+    /// A function to draw the current position as text, with the last move and a king in check highlighted if the
+    /// options have colors.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &RenderOptions, // How to draw the board (see docs for RenderOptions).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the drawing (see docs for Board::render).
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let last_move = match self.history.last() {
+            Some(last_move) => [&last_move.source, &last_move.target].into_iter().filter_map(|square| Square::from_algebraic(square)).collect(),
+            None => vec![],
+        };
+        render_board(&self.board, options, &last_move, &checked_kings(&self.board))
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_rendering() {
+        let game = Game::from_uci_moves("e2e4").unwrap();
+        let flipped = RenderOptions { flipped: true, ..RenderOptions::default() };
+        let rendering = game.render(&flipped);
+        assert!(rendering.starts_with("1 R N B K Q B N R\n2 P P P . P P P P\n3 . . . . . . . .\n4 . . . P . . . .\n"));
+        assert!(rendering.ends_with("8 r n b k q b n r\n  h g f e d c b a\n"));
+        let unicode = RenderOptions { unicode: true, coordinates: false, ..RenderOptions::default() };
+        assert_eq!(game.board.render(&unicode).lines().next(), Some("♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜"));
+        assert!(!game.render(&RenderOptions::default()).contains('\x1b'));
+    }
+    #[test]
+    fn test_colored_snapshot() {
+        let palette = Palette { light_square: 1, dark_square: 2, white_piece: 3, black_piece: 4, last_move: 5, check: 6 };
+        let options = RenderOptions { colors: Some(palette), ..RenderOptions::default() };
+        let mut game = Game::new_from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1".to_string());
+        assert!(game.make_uci_move("h1h8"));
+        let (light, dark) = ("\x1b[48;5;1m   ", "\x1b[48;5;2m   ");
+        let dark_first = dark.to_string() + light + dark + light + dark + light + dark + light + RESET;
+        let light_first = light.to_string() + dark + light + dark + light + dark + light + dark + RESET;
+        let expected = [
+            "8 \x1b[48;5;6;38;5;4m k ".to_string() + dark + light + dark + light + dark + light + "\x1b[48;5;5;38;5;3m R " + RESET,
+            "7 ".to_string() + &dark_first,
+            "6 ".to_string() + light + "\x1b[48;5;2;38;5;3m K " + light + dark + light + dark + light + dark + RESET,
+            "5 ".to_string() + &dark_first,
+            "4 ".to_string() + &light_first,
+            "3 ".to_string() + &dark_first,
+            "2 ".to_string() + &light_first,
+            "1 ".to_string() + dark + light + dark + light + dark + light + dark + "\x1b[48;5;5m   " + RESET,
+            "   a  b  c  d  e  f  g  h ".to_string(),
+        ];
+        assert_eq!(game.render(&options), expected.join("\n") + "\n");
+
+        let no_highlights = RenderOptions { highlight_last_move: false, highlight_check: false, ..options };
+        assert!(!game.render(&no_highlights).contains("48;5;5") && !game.render(&no_highlights).contains("48;5;6"));
+    }
+    #[test]
+    fn test_no_color() {
+        assert_eq!(terminal_colors(true, None), Some(Palette::default()));
+        assert_eq!(terminal_colors(true, Some(OsString::new())), Some(Palette::default()));
+        assert_eq!(terminal_colors(true, Some(OsString::from("1"))), None);
+        assert_eq!(terminal_colors(false, None), None);
+    }
+}
@@ -0,0 +1,315 @@
+/*****************************
+*  REPLAY                    *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Games built by replaying a list of moves, which is how game records usually arrive, e.g. from a web frontend
+or a database.
+
+Every move is checked for legality in the position it is played in. The first move that can't be played stops
+the replay, and the ReplayError says which one it was, the position it was played in and why, e.g. "illegal move
+3. Ke3 in position rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2: a king can't move like that".
+
+```
+# use alviny_task_3::Game;
+let from_san = Game::from_moves(&["e4", "e5", "Nf3", "Nc6"]).unwrap();
+let from_uci = Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6").unwrap();
+assert_eq!(from_san.fen, from_uci.fen);
+
+let mut from_fen = Game::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".to_string());
+from_fen.play_moves(&["e4", "Kd7", "e5"]).unwrap(); // Replaying from a custom position
+```
+*/
+
+use std::fmt;
+use std::iter;
+
+use crate::castling::CastleSide;
+use crate::explain::{MoveDiagnosis, MoveError};
+use crate::san::{self, SanError, SanPattern, SanStyle};
+use crate::{Board, Game, Move, Piece};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent the first move of a replay that couldn't be played.
+///
+/// ## Attributes
+/// ```text
+/// ply: usize, // The index the move would have had in the game's history, starting from 0.
+/// move_text: String, // The move, as given.
+/// fen_before: String, // The FEN of the position the move was played in.
+/// reason: MoveError, // Why it couldn't be played (see docs for MoveError). UCI moves are never Ambiguous.
+/// ```
+pub struct ReplayError {
+    pub ply: usize,
+    pub move_text: String,
+    pub fen_before: String,
+    pub reason: MoveError,
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut fields = self.fen_before.split_whitespace().skip(1);
+        let dots = if fields.next() == Some("b") { "..." } else { ". " };
+        let move_number = fields.nth(3).unwrap_or("1");
+        write!(f, "illegal move {}{}{} in position {}: {}", move_number, dots, self.move_text, self.fen_before, self.reason)
+    }
+}
+impl std::error::Error for ReplayError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn illegal_san_reason(game: &Game, san: &str) -> MoveError {
+    let pattern = match san::read_san(san, &SanStyle::ENGLISH) {
+        Ok(pattern) => pattern,
+        Err(_) => return MoveError::Unreadable,
+    };
+    let (piece, sources, target) = match pattern {
+        SanPattern::Castling { side } => {
+            let Some(king) = game.board.king_square(game.board.active_player) else {
+                return MoveError::Illegal(MoveDiagnosis::Forbidden);
+            };
+            let file = if side == CastleSide::Kingside { 'g' } else { 'c' };
+            let target = game.board.castling_move(side).map_or(format!("{}{}", file, king.rank().to_char()), |castling| castling.target);
+            ('K', vec![king.to_string()], target)
+        }
+        SanPattern::PieceMove { piece, target, file, rank, .. } => {
+            let sources = game
+                .board
+                .pieces()
+                .filter(|(_square, candidate)| candidate.color == game.board.active_player && candidate.to_fen_char().to_ascii_uppercase() == piece)
+                .map(|(square, _candidate)| square.to_string())
+                .filter(|square| file.is_none_or(|file| square.starts_with(file)) && rank.is_none_or(|rank| square.ends_with(rank)))
+                .collect::<Vec<String>>();
+            (piece, sources, target)
+        }
+    };
+    let diagnoses = sources.iter().map(|source| game.explain_move(source, &target)).collect::<Vec<MoveDiagnosis>>();
+    let diagnosis = diagnoses
+        .iter()
+        .find(|diagnosis| !matches!(diagnosis, MoveDiagnosis::ImpossibleForPiece { .. }))
+        .or(diagnoses.first())
+        .cloned()
+        .or_else(|| Piece::from_fen_char(piece).map(|piece| MoveDiagnosis::ImpossibleForPiece { piece: piece.kind }));
+    match diagnosis {
+        Some(MoveDiagnosis::Legal) | None => MoveError::Illegal(MoveDiagnosis::Forbidden), // E.g. a promotion without the piece
+        Some(diagnosis) => MoveError::Illegal(diagnosis),
+    }
+} // Explains why no legal move fits a SAN move, with the pieces that could have been meant. The explanation for a piece
+// that can reach the target is more useful than "a rook can't move like that" for the rook on the other side
+
+impl Game {
+    fn replay_error(&self, move_text: &str, reason: MoveError) -> ReplayError {
+        ReplayError { ply: self.history.len(), move_text: move_text.to_string(), fen_before: self.fen.clone(), reason }
+    } // The ReplayError for a move that can't be played in the current position
+
+    pub(crate) fn play_san(&mut self, san: &str) -> Result<Move, ReplayError> {
+        let reason = match self.parse_san(san) {
+            Ok(parsed_move) if self.make_uci_move(&parsed_move.to_uci()) => return Ok(parsed_move),
+            Ok(_parsed_move) => MoveError::Illegal(MoveDiagnosis::GameOver),
+            Err(SanError::Unreadable) => MoveError::Unreadable,
+            Err(SanError::Ambiguous) => MoveError::Ambiguous,
+            Err(SanError::Illegal) => illegal_san_reason(self, san),
+        };
+        Err(self.replay_error(san, reason))
+    } // Makes a move in SAN, or explains why it can't be made. A legal move is only refused once the game is over
+
+    fn play_uci(&mut self, uci_move: &str) -> Result<(), ReplayError> {
+        let Some(parsed_move) = Move::from_uci(uci_move) else {
+            return Err(self.replay_error(uci_move, MoveError::Unreadable));
+        };
+        if self.make_uci_move(&parsed_move.to_uci()) {
+            return Ok(());
+        }
+        let reason = self.with_move(&parsed_move).err().unwrap_or(MoveError::Illegal(MoveDiagnosis::Forbidden));
+        Err(self.replay_error(uci_move, reason))
+    } // Makes a move in UCI notation, or explains why it can't be made
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to create a game from the starting position by replaying moves in standard algebraic notation.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// moves: &[&str], // The moves in SAN (see docs for Game::parse_san), e.g. ["e4", "e5", "Nf3"].
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game after the last move, or a ReplayError for the first move that couldn't be played.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::from_moves(&["f3", "e5", "g4", "Qh4#"]).unwrap();
+    /// assert!(example_game.is_checkmate());
+    /// let replay_error = Game::from_moves(&["e4", "e5", "Ke3"]).unwrap_err();
+    /// assert_eq!(replay_error.ply, 2);
+    /// assert_eq!(replay_error.fen_before, "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+    /// assert!(replay_error.to_string().starts_with("illegal move 2. Ke3 in position rnbqkbnr/"));
+    /// ```
+    pub fn from_moves(moves: &[&str]) -> Result<Game, ReplayError> {
+        let mut game = Game::new();
+        game.play_moves(moves)?;
+        Ok(game)
+    }
+
+    /// A function to create a game from the starting position by replaying moves in long algebraic (UCI) notation.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// moves: &str, // The moves separated by whitespace, e.g. "e2e4 e7e5 g1f3".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game after the last move, or a ReplayError for the first move that couldn't be played.
+    pub fn from_uci_moves(moves: &str) -> Result<Game, ReplayError> {
+        let mut game = Game::new();
+        game.play_uci_moves(moves)?;
+        Ok(game)
+    }
+
+    /// A function to make several moves in standard algebraic notation, in order.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// moves: &[&str], // The moves in SAN (see docs for Game::parse_san).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a ReplayError for the first move that couldn't be played. The moves before it have been made.
+    pub fn play_moves(&mut self, moves: &[&str]) -> Result<(), ReplayError> {
+        for move_text in moves {
+            self.play_san(move_text)?;
+        }
+        Ok(())
+    }
+
+    /// A function to make several moves in long algebraic (UCI) notation, in order.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// moves: &str, // The moves separated by whitespace, e.g. "e2e4 e7e5 g1f3".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a ReplayError for the first move that couldn't be played. The moves before it have been made.
+    pub fn play_uci_moves(&mut self, moves: &str) -> Result<(), ReplayError> {
+        for move_text in moves.split_whitespace() {
+            self.play_uci(move_text)?;
+        }
+        Ok(())
+    }
+
+    /// A function to walk through every position the game has been through, e.g. to draw a diagram for each move.
+    /// The positions are rebuilt by replaying the history from start_fen, one move per step, so the iterator always
+    /// agrees with the history, also after undo_move().
+    ///
+    /// ## Returns
+    /// This function returns an iterator over the Boards: the starting position first, then the position after each
+    /// move, ending with the current position. It yields history.len() + 1 Boards.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Game};
+    /// let example_game = Game::from_moves(&["e4", "e5"]).unwrap();
+    /// let fens = example_game.positions().map(|board| generate_fen(&board)).collect::<Vec<String>>();
+    /// assert_eq!(fens.len(), 3);
+    /// assert_eq!(fens[1], "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    /// assert_eq!(fens[2], example_game.fen);
+    /// ```
+    pub fn positions(&self) -> impl Iterator<Item = Board> + '_ {
+        let start = Game::new_from_fen_with_variant(self.start_fen.clone(), self.variant.clone());
+        iter::once(start.board.clone()).chain(self.history.iter().scan(start, |replay, played_move| {
+            replay.make_uci_move(&played_move.to_uci());
+            Some(replay.board.clone())
+        }))
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_illegal_fifth_move() {
+        let replay_error = Game::from_moves(&["e4", "e5", "Nf3", "Nc6", "Bb6", "Nf6"]).unwrap_err();
+        assert_eq!((replay_error.ply, replay_error.move_text.as_str()), (4, "Bb6"));
+        assert_eq!(replay_error.fen_before, "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        assert_eq!(replay_error.reason, MoveError::Illegal(MoveDiagnosis::ImpossibleForPiece { piece: crate::PieceKind::Bishop }));
+
+        let replay_error = Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6 f1b6 g8f6").unwrap_err();
+        assert_eq!((replay_error.ply, replay_error.move_text.as_str()), (4, "f1b6"));
+        assert_eq!(replay_error.fen_before, "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+
+        let replay_error = Game::from_uci_moves("e2e4 e7e5 g1f3 b8c6 Bb5").unwrap_err();
+        assert_eq!((replay_error.ply, replay_error.reason), (4, MoveError::Unreadable));
+    }
+    #[test]
+    fn test_broken_game_record() {
+        // 1. e4 e5 2. Nf3 Nc6 3. Bb5 Nd4 4. Nxd4 exd4 5. O-O c6 6. Re1, and Black's bishop can't leave the pin
+        let moves = ["e4", "e5", "Nf3", "Nc6", "Bb5", "Nd4", "Nxd4", "exd4", "O-O", "c6", "Re1", "d5", "exd5+", "Be7", "dxc6", "Bb4"];
+        let replay_error = Game::from_moves(&moves).unwrap_err();
+        assert_eq!(replay_error.ply, 15);
+        assert_eq!(replay_error.fen_before, "r1bqk1nr/pp2bppp/2P5/1B6/3p4/8/PPPP1PPP/RNBQR1K1 b kq - 0 8");
+        let pinned = MoveDiagnosis::Pinned { piece: crate::PieceKind::Bishop, attacker: crate::Square::from_algebraic("e1").unwrap(), attacker_kind: crate::PieceKind::Rook };
+        assert_eq!(replay_error.reason, MoveError::Illegal(pinned));
+        assert_eq!(
+            replay_error.to_string(),
+            "illegal move 8...Bb4 in position r1bqk1nr/pp2bppp/2P5/1B6/3p4/8/PPPP1PPP/RNBQR1K1 b kq - 0 8: that bishop is pinned by the rook on e1"
+        );
+
+        let mut in_check = Game::new_from_fen("4r1k1/8/8/8/8/8/3P4/3RK3 w - - 0 1".to_string());
+        let replay_error = in_check.play_moves(&["d4", "Kh8", "Rd2"]).unwrap_err();
+        assert_eq!(replay_error.ply, 0); // d4 doesn't address the check
+        assert!(matches!(replay_error.reason, MoveError::Illegal(MoveDiagnosis::DoesNotAddressCheck { .. })));
+        let replay_error = Game::new_from_fen("r3k3/8/8/8/8/8/8/3RK3 b q - 0 1".to_string()).play_moves(&["O-O-O"]).unwrap_err();
+        assert!(matches!(replay_error.reason, MoveError::Illegal(MoveDiagnosis::CastlingThroughCheck { .. })), "{}", replay_error);
+    }
+    #[test]
+    fn test_replays_agree() {
+        let from_san = Game::from_moves(&["e4", "d5", "exd5", "Nf6", "c4", "c6", "dxc6", "Nxc6", "Nc3", "e5", "Nf3", "Bc5", "Be2", "O-O", "O-O"]).unwrap();
+        let from_uci = Game::from_uci_moves("e2e4 d7d5 e4d5 g8f6 c2c4 c7c6 d5c6 b8c6 b1c3 e7e5 g1f3 f8c5 f1e2 e8g8 e1g1").unwrap();
+        assert_eq!(from_san, from_uci);
+
+        let mut ambiguous = Game::new_from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1".to_string());
+        let replay_error = ambiguous.play_moves(&["Kf2", "Kd7", "Nd2"]).unwrap_err();
+        assert_eq!((replay_error.ply, replay_error.reason), (2, MoveError::Ambiguous));
+        assert_eq!(ambiguous.history.len(), 2); // The moves before the failure were made
+    }
+    #[test]
+    fn test_positions() {
+        let mut game = Game::from_moves(&["e4", "c5", "Nf3", "d6", "d4", "cxd4"]).unwrap();
+        let fens = game.positions().map(|board| crate::generate_fen(&board)).collect::<Vec<String>>();
+        assert_eq!(fens.len(), 7);
+        assert_eq!(fens[0], Game::new().fen);
+        assert_eq!(fens[3], "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        assert_eq!(fens[6], game.fen);
+
+        game.undo_move();
+        game.undo_move();
+        let after_undo = game.positions().map(|board| crate::generate_fen(&board)).collect::<Vec<String>>();
+        assert_eq!(after_undo, fens[0..5]);
+        game.make_uci_move("b1c3");
+        assert_eq!(game.positions().last().unwrap(), game.board);
+        assert_eq!(game.positions().nth(3).unwrap(), Game::new_from_fen(fens[3].clone()).board);
+    }
+}
@@ -0,0 +1,226 @@
+/*****************************
+*  RULES                     *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The rules a Game is played by. Game never decides on its own which moves are legal, what a move does to the board,
+or when the game is over: it asks the Rules of its Variant (see docs for Variant).
+
+Every method of the Rules trait defaults to standard chess, so a variant only overrides the parts that differ.
+
+## Writing your own variant
+A downstream crate can add a fairy variant by implementing Rules and wrapping it in Variant::Custom.
+Moves are given as Move structs in long algebraic notation, and the standard behaviour is available from
+StandardRules for rules that only adjust it:
+
+```
+# use std::sync::Arc;
+# use alviny_task_3::{Board, Game, Move, Variant};
+# use alviny_task_3::rules::{Rules, StandardRules};
+#[derive(Debug)]
+struct NoCastling;
+impl Rules for NoCastling {
+    fn name(&self) -> &str {
+        "No castling"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        StandardRules
+            .legal_moves(board)
+            .into_iter()
+            .filter(|legal_move| {
+                let is_king = board.pieces().any(|(square, piece)| {
+                    square.to_algebraic() == legal_move.source && piece.to_fen_char().eq_ignore_ascii_case(&'k')
+                });
+                !(is_king && legal_move.source.as_bytes()[0].abs_diff(legal_move.target.as_bytes()[0]) == 2)
+            })
+            .collect()
+    }
+}
+
+let mut example_game = Game::new_from_fen_with_variant(
+    "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string(),
+    Variant::Custom(Arc::new(NoCastling)),
+);
+assert!(!example_game.make_uci_move("e1g1"));
+assert!(example_game.make_uci_move("e1f1"));
+```
+*/
+
+use std::fmt;
+
+use crate::{
+    get_algebraic_notation, get_board_coords, get_game_status, get_legal_move_list, player_is_in_check, Board, Color,
+    GameStatus, Move,
+};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+pub(crate) fn move_list(triples: Vec<(Vec<i32>, Vec<i32>, Option<char>)>) -> Vec<Move> {
+    triples
+        .into_iter()
+        .map(|(source, target, promotion)| Move {
+            source: get_algebraic_notation(source),
+            target: get_algebraic_notation(target),
+            promotion,
+        })
+        .collect()
+} // Converts (source, target, promotion) triples from the move generator into Moves
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+/// A trait for the rules of a chess variant. Every method except name() defaults to standard chess.
+pub trait Rules: fmt::Debug + Send + Sync {
+    /// A function to return the name of the variant, e.g. "King of the Hill".
+    fn name(&self) -> &str;
+
+    /// A function to return the FEN of the position the variant starts from.
+    fn start_fen(&self) -> &str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    }
+
+    /// A function to list every legal move for the active player.
+    ///
+    /// ## Returns
+    /// This function returns a Vec of Moves. Pawn moves to the last rank should be listed once per promotion piece (q, r, b, n).
+    /// Once the game is over there should be no legal moves, unless the game ended by a draw rule that still allows play
+    /// (such as the fifty-move rule).
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        move_list(get_legal_move_list(board))
+    }
+
+    /// A function to play a move that legal_moves() returned, and hand the turn to the other player.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &mut Board, // The Board to play the move on.
+    /// chess_move: &Move, // The move. A promotion of None promotes to board.promotion_selection.
+    ///
+    /// ```
+    fn play_move(&self, board: &mut Board, chess_move: &Move) {
+        board.play_move(
+            get_board_coords(chess_move.source.clone()),
+            get_board_coords(chess_move.target.clone()),
+            chess_move.promotion,
+        );
+    }
+
+    /// A function to check whether one side is in check. Only used for reporting, through Game.checks.
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        player_is_in_check(board, color.to_char())
+    }
+
+    /// A function to return the game status (see docs for GameStatus) of a Board.
+    fn game_status(&self, board: &Board) -> GameStatus {
+        get_game_status(board)
+    }
+
+    /// A function to add variant state that FEN can't express to the FEN written to Game.fen, such as the
+    /// number of checks given in Three-check. The FEN is returned unchanged by default.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The Board the FEN was generated from.
+    /// fen: String, // The standard FEN of the Board.
+    ///
+    /// ```
+    fn extend_fen(&self, _board: &Board, fen: String) -> String {
+        fen
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct for the rules of standard chess, i.e. every default of the Rules trait.
+pub struct StandardRules;
+impl Rules for StandardRules {
+    fn name(&self) -> &str {
+        "Standard"
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Variant};
+
+    fn perft(game: &Game, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        game.legal_moves()
+            .iter()
+            .map(|legal_move| {
+                let mut next_game = game.clone();
+                assert!(next_game.make_uci_move(&legal_move.to_uci()));
+                perft(&next_game, depth - 1)
+            })
+            .sum()
+    } // Counts the leaf nodes of the game tree through Game, i.e. through the Rules of the Variant
+
+    fn direct_perft(board: &Board, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        get_legal_move_list(board)
+            .into_iter()
+            .map(|(source, target, promotion)| {
+                let mut next_board = board.clone();
+                next_board.play_move(source, target, promotion);
+                direct_perft(&next_board, depth - 1)
+            })
+            .sum()
+    } // Counts the leaf nodes of the game tree straight from the move generator
+
+    #[test]
+    fn test_standard_rules_match_the_move_generator() {
+        let positions = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 2),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+        ];
+        for (fen, depth) in positions {
+            let game = Game::new_from_fen(fen.to_string());
+            assert_eq!(perft(&game, depth), direct_perft(&game.board, depth), "{}", fen);
+        }
+        assert_eq!(perft(&Game::new_from_fen(positions[2].0.to_string()), 3), 2812);
+    }
+    #[test]
+    fn test_perft_known_values() {
+        let positions: [(&str, &[u64]); 8] = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]), // Kiwipete
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812]),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+            ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189]), // Chess960, in Shredder-FEN
+            ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002]),
+            ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471]),
+        ];
+        for (fen, counts) in positions {
+            let board = Game::new_from_fen(fen.to_string()).board;
+            for (depth, count) in (1..).zip(counts) {
+                assert_eq!(direct_perft(&board, depth), *count, "{} at depth {}", fen, depth);
+            }
+        }
+    } // The published counts from the Chess Programming Wiki, and for Chess960 from its perft suite
+    #[test]
+    fn test_variant_rules() {
+        assert_eq!(Variant::Standard.rules().name(), "Standard");
+        assert_eq!(Variant::Atomic.rules().name(), "Atomic");
+        assert_eq!(Variant::RacingKings.rules().start_fen(), Variant::RacingKings.start_fen());
+        assert_eq!(Variant::KingOfTheHill, Variant::KingOfTheHill);
+        assert_ne!(Variant::KingOfTheHill, Variant::Standard);
+    }
+}
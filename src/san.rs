@@ -0,0 +1,409 @@
+/*****************************
+*  STANDARD ALGEBRAIC        *
+*  NOTATION                  *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Moves in standard algebraic notation (SAN), the notation of scoresheets, books and PGN files, e.g. "Nf3", "exd5",
+"O-O" or "e8=Q+".
+
+SAN only names the target square, so a move can only be read or written knowing the position it is played in.
+Both directions are therefore methods on Game, and work on the current position.
+
+Reading is forgiving about the details people get wrong: check and mate markers, annotations ("!", "?!"), the capture
+sign, "0-0" for castling and a promotion without "=" are all accepted. A piece letter must be uppercase, since
+a lowercase 'b' is the b-file.
+
+Moves can also be written and read with the piece letters of other languages, or with figurines, by passing a
+SanStyle to to_san_with() and parse_san_with(), e.g. "Sf3" for Nf3 in German. The letters of a style can be anything
+that can't be mistaken for the rest of the move: not a file ('a' to 'h') or rank ('1' to '8'), and not 'x', ':', '=',
+'O' or '0'. That is why the files being lowercase matters: the Spanish and French 'C' for the knight is never the c-file.
+*/
+
+use std::fmt;
+
+use crate::castling::{CastleSide, CastledStatus};
+use crate::{get_board_coords, get_piece, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// An enum to represent why a SAN move couldn't be read.
+///
+/// ```text
+/// Unreadable, // The text isn't SAN.
+/// Illegal, // No legal move fits the text.
+/// Ambiguous, // More than one legal move fits the text, e.g. "Nd2" when both knights can go there.
+/// ```
+pub enum SanError {
+    Unreadable,
+    Illegal,
+    Ambiguous,
+}
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            SanError::Unreadable => "not a move in standard algebraic notation",
+            SanError::Illegal => "no legal move fits",
+            SanError::Ambiguous => "more than one legal move fits",
+        };
+        write!(f, "{}", description)
+    }
+}
+impl std::error::Error for SanError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent the piece letters SAN is written with (see the module docs for which letters work).
+///
+/// ## Attributes
+/// ```text
+/// piece_letters: [char; 5], // The letters of the king, queen, rook, bishop and knight, in that order.
+/// ```
+pub struct SanStyle {
+    pub piece_letters: [char; 5],
+}
+impl SanStyle {
+    /// The English letters, which are the ones PGN files use: K, Q, R, B, N.
+    pub const ENGLISH: SanStyle = SanStyle { piece_letters: ['K', 'Q', 'R', 'B', 'N'] };
+    /// The German letters: K (König), D (Dame), T (Turm), L (Läufer), S (Springer).
+    pub const GERMAN: SanStyle = SanStyle { piece_letters: ['K', 'D', 'T', 'L', 'S'] };
+    /// The Spanish letters: R (rey), D (dama), T (torre), A (alfil), C (caballo).
+    pub const SPANISH: SanStyle = SanStyle { piece_letters: ['R', 'D', 'T', 'A', 'C'] };
+    /// The French letters: R (roi), D (dame), T (tour), F (fou), C (cavalier).
+    pub const FRENCH: SanStyle = SanStyle { piece_letters: ['R', 'D', 'T', 'F', 'C'] };
+    /// Figurines, which read the same in every language: ♔, ♕, ♖, ♗, ♘.
+    pub const FIGURINE: SanStyle = SanStyle { piece_letters: ['♔', '♕', '♖', '♗', '♘'] };
+
+    fn letter(&self, english_letter: char) -> char {
+        ENGLISH_LETTERS.iter().position(|letter| *letter == english_letter).map_or(english_letter, |index| self.piece_letters[index])
+    } // The style's letter for an English piece letter
+
+    fn english_letter(&self, letter: char) -> Option<char> {
+        self.piece_letters.iter().position(|piece_letter| *piece_letter == letter).map(|index| ENGLISH_LETTERS[index])
+    } // The English piece letter for one of the style's letters, or None if it isn't one
+}
+impl Default for SanStyle {
+    fn default() -> Self {
+        SanStyle::ENGLISH
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+const ENGLISH_LETTERS: [char; 5] = ['K', 'Q', 'R', 'B', 'N'];
+
+fn piece_on(game: &Game, square: &str) -> char {
+    get_piece(&game.board, &get_board_coords(square.to_string())).to_ascii_uppercase()
+} // The piece on a square as an uppercase letter, or '*' if it is empty
+
+fn castle_side(game: &Game, chess_move: &Move) -> Option<CastleSide> {
+    CastledStatus::castle_side(&game.board, chess_move)
+} // The side a move castles to, if it castles. In Chess960 the king may move onto its own rook (see the castling module)
+
+fn disambiguation(game: &Game, chess_move: &Move, legal_moves: &[Move]) -> String {
+    let piece = piece_on(game, &chess_move.source);
+    let rivals: Vec<&Move> = legal_moves
+        .iter()
+        .filter(|rival| rival.target == chess_move.target && rival.source != chess_move.source && piece_on(game, &rival.source) == piece)
+        .collect();
+    let (file, rank) = (&chess_move.source[0..1], &chess_move.source[1..2]);
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|rival| &rival.source[0..1] != file) {
+        file.to_string()
+    } else if rivals.iter().all(|rival| &rival.source[1..2] != rank) {
+        rank.to_string()
+    } else {
+        chess_move.source.clone()
+    }
+} // The file, rank or square of the source that tells a piece apart from the others of its kind that can reach the target
+
+fn split_square(text: &[char]) -> Option<(&[char], String)> {
+    match text {
+        [rest @ .., file, rank] if ('a'..='h').contains(file) && ('1'..='8').contains(rank) => Some((rest, format!("{}{}", file, rank))),
+        _ => None,
+    }
+} // Splits the square off the end of some text
+
+pub(crate) enum SanPattern {
+    Castling { side: CastleSide },
+    PieceMove { piece: char, target: String, promotion: Option<char>, file: Option<char>, rank: Option<char> },
+} // What a SAN move says about the move it stands for: the piece (as an English letter), the target, and the file and
+// rank of the source if they are given
+
+impl SanPattern {
+    pub(crate) fn fits(&self, game: &Game, chess_move: &Move) -> bool {
+        match self {
+            SanPattern::Castling { side } => castle_side(game, chess_move) == Some(*side),
+            SanPattern::PieceMove { piece, target, promotion, file, rank } => {
+                let mut source = chess_move.source.chars();
+                let (source_file, source_rank) = (source.next(), source.next());
+                chess_move.target == *target
+                    && chess_move.promotion == *promotion
+                    && piece_on(game, &chess_move.source) == *piece
+                    && file.is_none_or(|file| source_file == Some(file))
+                    && rank.is_none_or(|rank| source_rank == Some(rank))
+            }
+        }
+    } // Checks whether a move is one the text can stand for, leaving out whether it is legal
+}
+
+pub(crate) fn read_san(san: &str, style: &SanStyle) -> Result<SanPattern, SanError> {
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    match text.replace('0', "O").as_str() {
+        "O-O" => return Ok(SanPattern::Castling { side: CastleSide::Kingside }),
+        "O-O-O" => return Ok(SanPattern::Castling { side: CastleSide::Queenside }),
+        _ => {}
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let (piece, rest) = match chars.split_first() {
+        Some((piece, rest)) if let Some(piece) = style.english_letter(*piece) => (piece, rest),
+        _ => ('P', &chars[..]),
+    };
+    let (rest, promotion) = match rest {
+        [rest @ .., '=', promotion] | [rest @ .., promotion]
+            if piece == 'P' && let Some(promotion) = style.english_letter(*promotion) && promotion != 'K' =>
+        {
+            (rest, Some(promotion.to_ascii_lowercase()))
+        }
+        _ => (rest, None),
+    };
+    let (rest, target) = split_square(rest).ok_or(SanError::Unreadable)?;
+    let rest = rest.strip_suffix(&['x']).or(rest.strip_suffix(&[':'])).unwrap_or(rest);
+    let (file, rank) = match rest {
+        [] => (None, None),
+        [file] if ('a'..='h').contains(file) => (Some(*file), None),
+        [rank] if ('1'..='8').contains(rank) => (None, Some(*rank)),
+        [file, rank] if ('a'..='h').contains(file) && ('1'..='8').contains(rank) => (Some(*file), Some(*rank)),
+        _ => return Err(SanError::Unreadable),
+    };
+    Ok(SanPattern::PieceMove { piece, target, promotion, file, rank })
+} // Reads a SAN move into a SanPattern, without looking at the position
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to write a move in standard algebraic notation, in the current position.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the move in SAN, e.g. "Nbd2", "exd6", "O-O-O" or "e8=Q#", or None if the move is illegal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::new_from_fen("4k3/P7/8/8/8/8/8/R3K1NR w K - 0 1".to_string());
+    /// assert_eq!(example_game.to_san(&Move::from_uci("g1f3").unwrap()), Some("Nf3".to_string()));
+    /// assert_eq!(example_game.to_san(&Move::from_uci("e1g1").unwrap()), None); // The knight is in the way
+    /// assert_eq!(example_game.to_san(&Move::from_uci("a7a8q").unwrap()), Some("a8=Q+".to_string()));
+    /// ```
+    pub fn to_san(&self, chess_move: &Move) -> Option<String> {
+        self.to_san_with(chess_move, &SanStyle::ENGLISH)
+    }
+
+    /// A function to write a move in standard algebraic notation with the piece letters of a SanStyle.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    /// style: &SanStyle, // The piece letters to use (see docs for SanStyle).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the move in SAN, or None if the move is illegal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// # use alviny_task_3::san::SanStyle;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let knight_move = Move::from_uci("g1f3").unwrap();
+    /// assert_eq!(example_game.to_san_with(&knight_move, &SanStyle::GERMAN), Some("Sf3".to_string()));
+    /// assert_eq!(example_game.to_san_with(&knight_move, &SanStyle::FIGURINE), Some("♘f3".to_string()));
+    /// ```
+    pub fn to_san_with(&self, chess_move: &Move, style: &SanStyle) -> Option<String> {
+        let legal_moves = self.legal_moves();
+        let selection = self.board.promotion_selection.to_ascii_lowercase();
+        let chess_move = legal_moves.iter().find(|legal_move| {
+            legal_move.source == chess_move.source
+                && legal_move.target == chess_move.target
+                && (legal_move.promotion == chess_move.promotion || (chess_move.promotion.is_none() && legal_move.promotion == Some(selection)))
+        })?;
+        let mut san = if let Some(side) = castle_side(self, chess_move) {
+            if side == CastleSide::Kingside { "O-O".to_string() } else { "O-O-O".to_string() }
+        } else {
+            let piece = piece_on(self, &chess_move.source);
+            let is_capture = crate::observer::is_capture(self, chess_move);
+            let mut san = match piece {
+                'P' if is_capture => chess_move.source[0..1].to_string(),
+                'P' => String::new(),
+                _ => format!("{}{}", style.letter(piece), disambiguation(self, chess_move, &legal_moves)),
+            };
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&chess_move.target);
+            if let Some(promotion) = chess_move.promotion {
+                san.push('=');
+                san.push(style.letter(promotion.to_ascii_uppercase()));
+            }
+            san
+        };
+        let mut next_game = self.clone();
+        next_game.make_uci_move(&chess_move.to_uci());
+        if next_game.game_status.is_checkmate() {
+            san.push('#');
+        } else if next_game.checks.contains(&true) {
+            san.push('+');
+        }
+        Some(san)
+    }
+
+    /// A function to read a move in standard algebraic notation, in the current position. Inverse function for to_san().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// san: &str, // The move in SAN, e.g. "Nf3", "exd5", "O-O" or "e8=Q+" (see the module docs for what else is accepted).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the legal Move the text describes, or a SanError (see docs for SanError). The move is not made.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// # use alviny_task_3::san::SanError;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.parse_san("Nf3"), Ok(Move::from_uci("g1f3").unwrap()));
+    /// assert_eq!(example_game.parse_san("e4!"), Ok(Move::from_uci("e2e4").unwrap()));
+    /// assert_eq!(example_game.parse_san("Nd4"), Err(SanError::Illegal));
+    /// assert_eq!(example_game.parse_san("Knight to f3"), Err(SanError::Unreadable));
+    /// ```
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        self.parse_san_with(san, &SanStyle::ENGLISH)
+    }
+
+    /// A function to read a move in standard algebraic notation written with the piece letters of a SanStyle.
+    /// Inverse function for to_san_with().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// san: &str, // The move in SAN, with the style's piece letters, e.g. "Sf3" or "e8=D" in German.
+    /// style: &SanStyle, // The piece letters the move is written with (see docs for SanStyle).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the legal Move the text describes, or a SanError (see docs for SanError). The move is not made.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// # use alviny_task_3::san::{SanError, SanStyle};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// assert_eq!(example_game.parse_san_with("Cc3", &SanStyle::SPANISH), Ok(Move::from_uci("b1c3").unwrap()));
+    /// assert_eq!(example_game.parse_san_with("Nc3", &SanStyle::SPANISH), Err(SanError::Unreadable));
+    /// ```
+    pub fn parse_san_with(&self, san: &str, style: &SanStyle) -> Result<Move, SanError> {
+        let pattern = read_san(san, style)?;
+        let legal_moves = self.legal_moves();
+        let candidates: Vec<&Move> = legal_moves.iter().filter(|legal_move| pattern.fits(self, legal_move)).collect();
+        match candidates[..] {
+            [chess_move] => Ok(chess_move.clone()),
+            [] => Err(SanError::Illegal),
+            _ => Err(SanError::Ambiguous),
+        }
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_san_round_trip() {
+        let positions = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1",
+        ];
+        for fen in positions {
+            let test_game = Game::new_from_fen(fen.to_string());
+            for legal_move in test_game.legal_moves() {
+                let san = test_game.to_san(&legal_move).unwrap();
+                assert_eq!(test_game.parse_san(&san), Ok(legal_move), "{} in {}", san, fen);
+            }
+        }
+    }
+    #[test]
+    fn test_san_details() {
+        let test_game = Game::new_from_fen("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1".to_string());
+        let san = |uci_move: &str| test_game.to_san(&Move::from_uci(uci_move).unwrap()).unwrap();
+        assert_eq!(san("a8d8"), "Rad8");
+        assert_eq!(san("d7d5"), "Q7d5"); // The queen on d2 shares the file
+        assert_eq!(san("f3d5"), "Qfd5");
+        assert_eq!(san("d2d4"), "Q2d4");
+        assert_eq!(test_game.parse_san("Qd5"), Err(SanError::Ambiguous));
+        assert_eq!(test_game.parse_san("Rd8"), Err(SanError::Ambiguous));
+        let three_queens = Game::new_from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1".to_string());
+        assert_eq!(three_queens.to_san(&Move::from_uci("a1b2").unwrap()), Some("Qa1b2".to_string()));
+        assert_eq!(three_queens.parse_san("Qa1b2"), Ok(Move::from_uci("a1b2").unwrap()));
+
+        let castling = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1".to_string());
+        assert_eq!(castling.parse_san("0-0-0"), Ok(Move::from_uci("e8c8").unwrap()));
+        assert_eq!(castling.to_san(&Move::from_uci("e8g8").unwrap()), Some("O-O".to_string()));
+        assert_eq!(castling.to_san(&Move::from_uci("a8a1").unwrap()), Some("Rxa1+".to_string()));
+
+        let promotion = Game::new_from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        assert_eq!(promotion.parse_san("axb8=N"), Ok(Move::from_uci("a7b8n").unwrap()));
+        assert_eq!(promotion.parse_san("axb8Q+"), Ok(Move::from_uci("a7b8q").unwrap()));
+        assert_eq!(promotion.parse_san("a8"), Err(SanError::Illegal)); // The promotion piece is required
+        assert_eq!(promotion.parse_san("ab8=Q"), Ok(Move::from_uci("a7b8q").unwrap()));
+        assert_eq!(promotion.parse_san("a7b8=Q"), Ok(Move::from_uci("a7b8q").unwrap()));
+        assert_eq!(promotion.parse_san("Ka8=Q"), Err(SanError::Unreadable));
+    }    #[test]
+    fn test_localized_round_trip() {
+        let german_game = ["e4", "e5", "Sf3", "Sc6", "Lb5", "a6", "La4", "Sf6", "O-O", "Le7", "Te1", "b5", "Lb3", "d6", "c3", "O-O", "h3", "Sa5", "Lc2", "c5", "d4", "Dc7", "Sbd2", "Ld7", "Sf1", "Tfe8"];
+        let mut test_game = Game::new();
+        let mut english_game = vec![];
+        for san in german_game {
+            let chess_move = test_game.parse_san_with(san, &SanStyle::GERMAN).unwrap();
+            assert_eq!(test_game.to_san_with(&chess_move, &SanStyle::GERMAN).as_deref(), Some(san));
+            english_game.push(test_game.to_san(&chess_move).unwrap());
+            assert!(test_game.make_uci_move(&chess_move.to_uci()));
+        }
+        assert_eq!(english_game[20..], ["d4", "Qc7", "Nbd2", "Bd7", "Nf1", "Rfe8"]);
+        assert_eq!(test_game.parse_san_with("Sf3", &SanStyle::ENGLISH), Err(SanError::Unreadable));
+
+        let promotion = Game::new_from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let queening = Move::from_uci("a7b8q").unwrap();
+        assert_eq!(promotion.to_san_with(&queening, &SanStyle::FRENCH), Some("axb8=D+".to_string()));
+        assert_eq!(promotion.to_san_with(&queening, &SanStyle::FIGURINE), Some("axb8=♕+".to_string()));
+        assert_eq!(promotion.parse_san_with("axb8=♕", &SanStyle::FIGURINE), Ok(queening));
+        assert_eq!(promotion.parse_san_with("axb8=R", &SanStyle::SPANISH), Err(SanError::Unreadable)); // R is the king
+        assert_eq!(promotion.parse_san_with("axb8T", &SanStyle::SPANISH), Ok(Move::from_uci("a7b8r").unwrap()));
+    }
+}
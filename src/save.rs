@@ -0,0 +1,480 @@
+/*****************************
+*  SAVED GAMES               *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Saving a game to a file and resuming it later, e.g. for a desktop app.
+
+A save file holds everything needed to carry on where the game was left: the variant, the starting position and the
+moves (in the binary format, see the binary module), the annotation of every move, the clock, how the game ended if
+that can't be seen from the moves, e.g. a resignation, the PGN tags and a pending draw offer. The rest of the Game,
+like the captured pieces and the castling status, follows from the moves. The observer isn't saved, and games of a
+custom variant can't be saved, since their rules are code rather than data.
+
+Nothing in the file is trusted: loading replays every move, and rejects the file if the annotations or the result
+don't fit the moves. The file starts with SAVE_MAGIC and a version byte. Files of older versions keep loading when
+the format changes: version 1 files, which end after the clock, load without tags or a draw offer.
+
+```text
+SAVE_MAGIC, SAVE_VERSION, // 4 + 1 bytes.
+game, // The length of the binary game (4 bytes), then the game (see docs for Game::to_bytes).
+result, // 1 byte: 0 if the moves decide it, otherwise 1 + the index of the status in STORED_STATUSES.
+annotations, // For every move: which parts are present (1 byte: 1 clock, 2 eval, 4 comment), then the present parts:
+             // the clock in milliseconds (8 bytes), the eval as its kind (1 byte: 0 centipawns, 1 mate) and value
+             // (4 bytes), and the comment as its length (4 bytes) and UTF-8 text.
+clock, // 1 byte: 0 without a clock. Otherwise 1, the time control as a PGN tag (length in 2 bytes, then the text),
+       // the remaining time of White and Black in milliseconds (8 bytes each), the moves made by White and Black
+       // (4 bytes each), and the side whose time runs (1 byte: 0 neither, 1 White, 2 Black).
+tags, // The number of tags (4 bytes), then for every tag its name and its value, each as its length (4 bytes) and UTF-8 text.
+draw offer, // 1 byte: 0 without a pending offer, 1 if White offered a draw, 2 if Black did.
+```
+All numbers are little-endian.
+*/
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::binary::BinaryError;
+use crate::clock::{Clock, TimeControl};
+use crate::pgn::MoveAnnotation;
+use crate::search::Score;
+use crate::{Color, Game, GameStatus, Variant};
+
+/// The bytes every save file starts with.
+pub const SAVE_MAGIC: [u8; 4] = *b"AVSG";
+/// The version of the format written by Game::save().
+pub const SAVE_VERSION: u8 = 2;
+/// The results that are saved, since the moves alone don't show them.
+pub const STORED_STATUSES: [GameStatus; 10] = [
+    GameStatus::WhiteWinsByResignation,
+    GameStatus::BlackWinsByResignation,
+    GameStatus::DrawByAgreement,
+    GameStatus::DrawByRepetition,
+    GameStatus::WhiteWinsByTimeForfeit,
+    GameStatus::BlackWinsByTimeForfeit,
+    GameStatus::TimeForfeitDraw,
+    GameStatus::WhiteWinsByAdjudication,
+    GameStatus::BlackWinsByAdjudication,
+    GameStatus::DrawByAdjudication,
+];
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An enum to represent why a game can't be saved or loaded.
+///
+/// ```text
+/// Io(String), // The file can't be written or read. Holds the message of the I/O error.
+/// NotASaveFile, // The file doesn't start with SAVE_MAGIC.
+/// UnsupportedVersion(u8), // The file was written by a newer version of the format.
+/// Truncated, // The file ends too early.
+/// CustomVariant(String), // The game is played by custom rules, which can't be saved. Holds the name of the rules.
+/// InvalidGame(BinaryError), // The moves can't be read or played (see docs for BinaryError).
+/// Inconsistent(String), // The file can be read, but doesn't fit the moves, e.g. a result that can't follow them. Holds what is wrong.
+/// ```
+pub enum SaveError {
+    Io(String),
+    NotASaveFile,
+    UnsupportedVersion(u8),
+    Truncated,
+    CustomVariant(String),
+    InvalidGame(BinaryError),
+    Inconsistent(String),
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(message) => write!(f, "can't access the file: {}", message),
+            SaveError::NotASaveFile => write!(f, "not a saved game"),
+            SaveError::UnsupportedVersion(version) => write!(f, "unsupported version {} of the save format", version),
+            SaveError::Truncated => write!(f, "the file ends too early"),
+            SaveError::CustomVariant(name) => write!(f, "can't save a game of the custom variant {}", name),
+            SaveError::InvalidGame(binary_error) => write!(f, "invalid game: {}", binary_error),
+            SaveError::Inconsistent(problem) => write!(f, "the file doesn't fit its moves: {}", problem),
+        }
+    }
+}
+impl std::error::Error for SaveError {}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+struct SaveReader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> SaveReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], SaveError> {
+        let (taken, rest) = self.bytes.split_at_checked(count).ok_or(SaveError::Truncated)?;
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveError> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn byte(&mut self) -> Result<u8, SaveError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn millis(&mut self) -> Result<Duration, SaveError> {
+        Ok(Duration::from_millis(u64::from_le_bytes(self.array()?)))
+    }
+
+    fn text(&mut self, length: usize) -> Result<String, SaveError> {
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| SaveError::Inconsistent("text isn't UTF-8".to_string()))
+    }
+
+    fn long_text(&mut self) -> Result<String, SaveError> {
+        let length = u32::from_le_bytes(self.array()?) as usize;
+        self.text(length)
+    }
+} // Reads the parts of a save file in order, failing with Truncated at the end of the data
+
+fn encode_annotation(bytes: &mut Vec<u8>, annotation: &MoveAnnotation) {
+    let parts = annotation.clock.is_some() as u8 | (annotation.eval.is_some() as u8) << 1 | (annotation.comment.is_some() as u8) << 2;
+    bytes.push(parts);
+    if let Some(clock) = annotation.clock {
+        bytes.extend((clock.as_millis() as u64).to_le_bytes());
+    }
+    if let Some(eval) = &annotation.eval {
+        let (kind, value) = match eval {
+            Score::Centipawns(centipawns) => (0, *centipawns),
+            Score::Mate(moves) => (1, *moves),
+        };
+        bytes.push(kind);
+        bytes.extend(value.to_le_bytes());
+    }
+    if let Some(comment) = &annotation.comment {
+        encode_long_text(bytes, comment);
+    }
+}
+
+fn encode_long_text(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend((text.len() as u32).to_le_bytes());
+    bytes.extend(text.as_bytes());
+} // A text as its length (4 bytes) and UTF-8 bytes, read back by SaveReader::long_text
+
+fn decode_annotation(reader: &mut SaveReader) -> Result<MoveAnnotation, SaveError> {
+    let parts = reader.byte()?;
+    if parts > 7 {
+        return Err(SaveError::Inconsistent(format!("unknown annotation parts {}", parts)));
+    }
+    let mut annotation = MoveAnnotation::default();
+    if parts & 1 != 0 {
+        annotation.clock = Some(reader.millis()?);
+    }
+    if parts & 2 != 0 {
+        let kind = reader.byte()?;
+        let value = i32::from_le_bytes(reader.array()?);
+        annotation.eval = Some(match kind {
+            0 => Score::Centipawns(value),
+            1 => Score::Mate(value),
+            _ => return Err(SaveError::Inconsistent(format!("unknown eval kind {}", kind))),
+        });
+    }
+    if parts & 4 != 0 {
+        annotation.comment = Some(reader.long_text()?);
+    }
+    Ok(annotation)
+}
+
+fn encode_clock(bytes: &mut Vec<u8>, clock: &Clock) {
+    bytes.push(1);
+    let time_control = clock.time_control().to_pgn_tag();
+    bytes.extend((time_control.len() as u16).to_le_bytes());
+    bytes.extend(time_control.as_bytes());
+    for color in [Color::White, Color::Black] {
+        bytes.extend((clock.remaining(color).as_millis() as u64).to_le_bytes());
+    }
+    for moves in clock.moves_made() {
+        bytes.extend(moves.to_le_bytes());
+    }
+    bytes.push(match clock.running() {
+        None => 0,
+        Some(Color::White) => 1,
+        Some(Color::Black) => 2,
+    });
+}
+
+fn decode_clock(reader: &mut SaveReader) -> Result<Clock, SaveError> {
+    let length = u16::from_le_bytes(reader.array()?) as usize;
+    let tag = reader.text(length)?;
+    let time_control = TimeControl::from_pgn_tag(&tag).ok_or_else(|| SaveError::Inconsistent(format!("invalid time control {}", tag)))?;
+    let remaining = [reader.millis()?, reader.millis()?];
+    let moves_made = [u32::from_le_bytes(reader.array()?), u32::from_le_bytes(reader.array()?)];
+    let running = match reader.byte()? {
+        0 => None,
+        1 => Some(Color::White),
+        2 => Some(Color::Black),
+        side => return Err(SaveError::Inconsistent(format!("unknown running side {}", side))),
+    };
+    Ok(Clock::restore(time_control, remaining, moves_made, running))
+}
+
+fn encode_game(game: &Game) -> Result<Vec<u8>, SaveError> {
+    if let Variant::Custom(rules) = &game.variant {
+        return Err(SaveError::CustomVariant(rules.name().to_string()));
+    }
+    let mut bytes = SAVE_MAGIC.to_vec();
+    bytes.push(SAVE_VERSION);
+    let moves = game.to_bytes();
+    bytes.extend((moves.len() as u32).to_le_bytes());
+    bytes.extend(moves);
+    bytes.push(STORED_STATUSES.iter().position(|status| *status == game.game_status).map_or(0, |index| index as u8 + 1));
+    for ply in 0..game.history.len() {
+        encode_annotation(&mut bytes, game.annotation(ply).unwrap_or(&MoveAnnotation::default()));
+    }
+    match &game.clock {
+        Some(clock) => encode_clock(&mut bytes, clock),
+        None => bytes.push(0),
+    }
+    bytes.extend((game.tags().len() as u32).to_le_bytes());
+    for (name, value) in game.tags() {
+        encode_long_text(&mut bytes, name);
+        encode_long_text(&mut bytes, value);
+    }
+    bytes.push(match game.draw_offer() {
+        None => 0,
+        Some(Color::White) => 1,
+        Some(Color::Black) => 2,
+    });
+    Ok(bytes)
+} // The contents of a save file (see module docs)
+
+fn decode_game(bytes: &[u8]) -> Result<Game, SaveError> {
+    let mut reader = SaveReader { bytes };
+    if reader.take(SAVE_MAGIC.len()).ok() != Some(&SAVE_MAGIC[..]) {
+        return Err(SaveError::NotASaveFile);
+    }
+    let version = reader.byte()?;
+    if version == 0 || version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    } // Every version adds parts at the end, and older files stop before them
+    let length = u32::from_le_bytes(reader.array()?) as usize;
+    let mut game = Game::from_bytes(reader.take(length)?).map_err(SaveError::InvalidGame)?;
+
+    let stored_status = match reader.byte()? {
+        0 => None,
+        index => Some(*STORED_STATUSES.get(index as usize - 1).ok_or_else(|| SaveError::Inconsistent(format!("unknown result {}", index)))?),
+    };
+    if let Some(status) = stored_status {
+        if game.game_status.is_game_over() && game.game_status != status {
+            return Err(SaveError::Inconsistent(format!("{:?} after a game that ended with {:?}", status, game.game_status)));
+        }
+        if status == GameStatus::DrawByRepetition && game.repetition_count() < 3 {
+            return Err(SaveError::Inconsistent("a repetition draw without a repetition".to_string()));
+        }
+        game.game_status = status;
+    }
+    for ply in 0..game.history.len() {
+        let annotation = decode_annotation(&mut reader)?;
+        game.set_annotation(ply, annotation);
+    }
+    if reader.byte()? != 0 {
+        game.set_clock(decode_clock(&mut reader)?);
+    }
+    if version >= 2 {
+        decode_tags(&mut reader, &mut game)?;
+        let offered_by = match reader.byte()? {
+            0 => None,
+            1 => Some(Color::White),
+            2 => Some(Color::Black),
+            side => return Err(SaveError::Inconsistent(format!("unknown draw offer {}", side))),
+        };
+        if let Some(color) = offered_by && !game.offer_draw(color) {
+            return Err(SaveError::Inconsistent(format!("a draw offer by {:?} after the game ended with {:?}", color, game.game_status)));
+        }
+    }
+    if !reader.bytes.is_empty() {
+        return Err(SaveError::Inconsistent(format!("{} bytes after the end", reader.bytes.len())));
+    }
+    Ok(game)
+} // Reads and checks the contents of a save file
+
+fn decode_tags(reader: &mut SaveReader, game: &mut Game) -> Result<(), SaveError> {
+    let count = u32::from_le_bytes(reader.array()?);
+    for _ in 0..count {
+        let name = reader.long_text()?;
+        let value = reader.long_text()?;
+        if name.is_empty() || name.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+            return Err(SaveError::Inconsistent(format!("invalid tag name {:?}", name)));
+        }
+        if game.tag(&name).is_some() || !game.set_tag(&name, &value) {
+            return Err(SaveError::Inconsistent(format!("the tag {} can't be stored", name)));
+        }
+    }
+    Ok(())
+} // Reads the PGN tags. Tags that come from the game itself, like Result, and repeated tags are rejected
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to save the game to a file, to resume it later with Game::load() (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// path: impl AsRef<Path>, // The file to write. It is replaced if it exists.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Ok(()), SaveError::CustomVariant if the game is played by custom rules, or SaveError::Io
+    /// if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        fs::write(path, encode_game(self)?).map_err(|io_error| SaveError::Io(io_error.to_string()))
+    }
+
+    /// A function to resume a game saved with Game::save(), checking everything in the file.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// path: impl AsRef<Path>, // The file to read.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game as it was saved, or a SaveError (see docs for SaveError) if the file can't be
+    /// read, was written by a newer version, or doesn't hold a valid game.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let path = std::env::temp_dir().join("alviny_task_3_load_example.sav");
+    /// let example_game = Game::from_moves(&["e4", "e5", "Nf3"]).unwrap();
+    /// example_game.save(&path).unwrap();
+    /// assert_eq!(Game::load(&path).unwrap(), example_game);
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> Result<Game, SaveError> {
+        let bytes = fs::read(path).map_err(|io_error| SaveError::Io(io_error.to_string()))?;
+        decode_game(&bytes)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("alviny_task_3_{}_{}.sav", name, std::process::id()))
+    }
+
+    fn assert_resumes(game: &Game, name: &str) -> Game {
+        let path = temp_file(name);
+        game.save(&path).unwrap();
+        let loaded = Game::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.fen, game.fen);
+        assert_eq!(loaded.legal_moves(), game.legal_moves());
+        assert_eq!(loaded, *game);
+        loaded
+    }
+
+    #[test]
+    fn test_save_mid_game() {
+        let mut test_game = Game::new();
+        test_game.set_clock(Clock::from_time_control(TimeControl::from_pgn_tag("40/5400+30:1800+30").unwrap()));
+        for (uci_move, seconds) in [("e2e4", 0), ("e7e5", 12), ("g1f3", 7), ("b8c6", 30), ("f1b5", 3)] {
+            assert!(test_game.make_timed_move(uci_move, Duration::from_secs(seconds)));
+        }
+        test_game.set_annotation(1, MoveAnnotation { clock: Some(Duration::from_millis(5_399_500)), eval: Some(Score::Centipawns(-21)), comment: Some("The open game ✓".to_string()) });
+        test_game.set_annotation(4, MoveAnnotation { eval: Some(Score::Mate(-12)), ..MoveAnnotation::default() });
+        test_game.undo_move();
+        assert!(test_game.make_uci_move("f1c4"));
+        let loaded = assert_resumes(&test_game, "mid_game");
+        assert_eq!(loaded.remaining_time(Color::Black), Some(Duration::from_secs(5400 - 12 + 30 - 30 + 30)));
+        assert_eq!(loaded.clock.as_ref().unwrap().running(), Some(Color::Black));
+        assert_eq!(loaded.annotation(1).unwrap().comment.as_deref(), Some("The open game ✓"));
+        assert_eq!(loaded.captured_by(Color::White), test_game.captured_by(Color::White));
+
+        let mut resigned = Game::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".to_string());
+        resigned.make_uci_move("e2e4");
+        resigned.resign(Color::Black);
+        assert_eq!(assert_resumes(&resigned, "resigned").game_status, GameStatus::WhiteWinsByResignation);
+        assert_resumes(&Game::from_uci_moves("f2f3 e7e5 g2g4 d8h4").unwrap(), "checkmate");
+        let mut adjudicated = Game::new_from_fen("8/8/8/3k4/8/8/8/4KQ2 w - - 0 1".to_string());
+        adjudicated.game_status = GameStatus::WhiteWinsByAdjudication;
+        assert_eq!(assert_resumes(&adjudicated, "adjudicated").game_status, GameStatus::WhiteWinsByAdjudication);
+    }
+    #[test]
+    fn test_save_variants_tags_and_draw_offers() {
+        let mut atomic = Game::new_with_variant(Variant::Atomic);
+        atomic.play_uci_moves("g1f3 e7e6 f3g5 d8g5").unwrap();
+        let loaded = assert_resumes(&atomic, "atomic");
+        assert_eq!(loaded.variant, Variant::Atomic);
+        assert!(loaded.fen.starts_with("rnb1kbnr/pppp1ppp/4p3/8/8/8/")); // The queen blew up with the knight on g5
+        let mut hill = Game::new_with_variant(Variant::KingOfTheHill);
+        hill.play_uci_moves("e2e4 e7e5 e1e2").unwrap();
+        assert_eq!(assert_resumes(&hill, "hill").variant, Variant::KingOfTheHill);
+
+        let mut tagged = Game::from_pgn("[Event \"Club championship\"]\n[White \"Anderssen\"]\n[ECO \"C33\"]\n\n1. e4 e5 2. f4 *").unwrap();
+        assert!(tagged.offer_draw(Color::White));
+        let loaded = assert_resumes(&tagged, "tagged");
+        assert_eq!((loaded.tag("White"), loaded.tag("ECO")), (Some("Anderssen"), Some("C33")));
+        assert_eq!(loaded.draw_offer(), Some(Color::White));
+
+        #[derive(Debug)]
+        struct Renamed;
+        impl Rules for Renamed {
+            fn name(&self) -> &str {
+                "Renamed chess"
+            }
+        }
+        let custom = Game::new_with_variant(Variant::Custom(Arc::new(Renamed)));
+        assert_eq!(custom.save(temp_file("custom")), Err(SaveError::CustomVariant("Renamed chess".to_string())));
+        assert!(!temp_file("custom").exists());
+
+        let version_1 = [&SAVE_MAGIC[..], &[1, 4, 0, 0, 0, 1, 1, 12, 7, 0, 0, 0]].concat(); // 1. e4, saved before tags and draw offers
+        assert_eq!(decode_game(&version_1).unwrap(), Game::from_uci_moves("e2e4").unwrap());
+        let mut bytes = encode_game(&tagged).unwrap();
+        *bytes.last_mut().unwrap() = 3;
+        assert!(matches!(decode_game(&bytes), Err(SaveError::Inconsistent(_))));
+        tagged.resign(Color::Black);
+        let mut bytes = encode_game(&tagged).unwrap();
+        *bytes.last_mut().unwrap() = 1; // An offer after the game ended
+        assert!(matches!(decode_game(&bytes), Err(SaveError::Inconsistent(_))));
+        let mut result_tag = Game::new();
+        result_tag.tags.push(("Result".to_string(), "1-0".to_string()));
+        assert!(matches!(decode_game(&encode_game(&result_tag).unwrap()), Err(SaveError::Inconsistent(_))));
+    }
+    #[test]
+    fn test_load_checks_the_file() {
+        let mut bytes = encode_game(&Game::from_uci_moves("e2e4 e7e5").unwrap()).unwrap();
+        assert!(decode_game(&bytes).is_ok());
+        assert_eq!(decode_game(b"PGN?"), Err(SaveError::NotASaveFile));
+        assert_eq!(decode_game(&bytes[..bytes.len() - 1]), Err(SaveError::Truncated));
+
+        let status_index = 4 + 1 + 4 + 7;
+        bytes[status_index] = 1; // White wins by resignation
+        assert_eq!(decode_game(&bytes).unwrap().game_status, GameStatus::WhiteWinsByResignation);
+        bytes[status_index] = 4; // A repetition draw, but nothing has been repeated
+        assert!(matches!(decode_game(&bytes), Err(SaveError::Inconsistent(_))));
+        bytes[status_index] = 0;
+
+        bytes[4 + 1 + 4 + 5] = 0; // 2... a1e5
+        assert!(matches!(decode_game(&bytes), Err(SaveError::InvalidGame(BinaryError::IllegalMove(_)))));
+        bytes[4] = SAVE_VERSION + 1;
+        assert_eq!(decode_game(&bytes), Err(SaveError::UnsupportedVersion(SAVE_VERSION + 1)));
+        assert!(matches!(Game::load(temp_file("missing")), Err(SaveError::Io(_))));
+    }
+}
@@ -0,0 +1,1357 @@
+/*****************************
+*  SEARCH                    *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A negamax search with alpha-beta pruning over the legal move generator and the static evaluation.
+
+Moves are searched in a promising order (see docs for order_moves), since alpha-beta prunes the most when the best
+move is tried first.
+
+Positions that were already searched, e.g. in an earlier iteration or through another move order, are looked up in a
+transposition table (see docs for TranspositionTable). The best move found there is tried first, and if the earlier
+search went deep enough, its score can be used without searching the position again.
+
+At the end of each line the search doesn't stop dead, since it might stop right before a recapture: it keeps trying
+captures (a quiescence search) until the position is quiet. Captures that the static exchange evaluation says lose
+material are skipped, and the side to move may always "stand pat" on the static evaluation instead of capturing.
+
+Scores returned by the search are in centipawns from the perspective of the side to move (like the UCI "score cp"),
+so a positive score is good for the player whose turn it is. Checkmates are scored as `MATE_SCORE - ply`, where ply
+is the number of half-moves until the mate, so that a quicker mate is always preferred over a slower one.
+
+In a variant, the search plays by the Game's Rules (see docs for Rules) at every node, and any win, e.g. reaching the
+hill in King of the Hill, is scored like a checkmate. The static exchange evaluation is skipped there, since a variant
+may change what a capture does.
+*/
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::evaluation::{piece_value, Evaluator, StandardEvaluator, MATE_SCORE};
+use crate::matchrunner::MovePicker;
+use crate::rules::Rules;
+use crate::undo::Undo;
+use crate::{get_algebraic_notation, get_board_coords, get_captured_piece, get_game_status, get_legal_capture_list, get_legal_move_list, player_is_in_check, Board, Game, GameStatus, Move, Square};
+
+const INFINITY: i32 = MATE_SCORE + 1;
+/// The deepest iteration an iterative deepening search (see docs for Game::search) will start.
+pub const MAX_DEPTH: u32 = 64;
+/// How many nodes are searched between each look at the clock and the stop flag.
+const NODES_PER_CLOCK_CHECK: u64 = 16;
+/// How often an InfoSink hears about a long iteration that hasn't completed yet.
+pub const INFO_INTERVAL: Duration = Duration::from_secs(1);
+/// The size of the transposition table in megabytes, unless SearchOptions says otherwise.
+pub const DEFAULT_HASH_SIZE: usize = 16;
+
+type InternalMove = (Vec<i32>, Vec<i32>, Option<char>);
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone)]
+/// A struct to set the limits and the output of an iterative deepening search (see docs for Game::search).
+/// The Default searches one line to MAX_DEPTH without a time limit, so set at least one of the limits.
+///
+/// ## Attributes
+/// ```text
+/// depth: u32, // The deepest iteration to search. It is capped at MAX_DEPTH, and 0 is treated as 1.
+/// time_limit: Option<Duration>, // How long the search may take, or None to search until depth is reached.
+/// multipv: usize, // How many lines to return, each starting with a different move. 0 is treated as 1.
+/// hash_size: usize, // The size of the transposition table in megabytes (see docs for TranspositionTable). Defaults to DEFAULT_HASH_SIZE.
+/// evaluator: Arc<dyn Evaluator + Send + Sync>, // How to score the positions at the end of the lines (see docs for Evaluator).
+///                                              // Defaults to StandardEvaluator.
+/// ```
+pub struct SearchOptions {
+    pub depth: u32,
+    pub time_limit: Option<Duration>,
+    pub multipv: usize,
+    pub hash_size: usize,
+    pub evaluator: Arc<dyn Evaluator + Send + Sync>,
+}
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            depth: MAX_DEPTH,
+            time_limit: None,
+            multipv: 1,
+            hash_size: DEFAULT_HASH_SIZE,
+            evaluator: Arc::new(StandardEvaluator),
+        }
+    }
+}
+impl fmt::Debug for SearchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SearchOptions")
+            .field("depth", &self.depth)
+            .field("time_limit", &self.time_limit)
+            .field("multipv", &self.multipv)
+            .field("hash_size", &self.hash_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to represent one line found by a search.
+///
+/// ## Attributes
+/// ```text
+/// score: i32, // The score of the line from the perspective of the side to move.
+/// principal_variation: Vec<Move>, // The moves the search expects to be played.
+/// ```
+pub struct SearchLine {
+    pub score: i32,
+    pub principal_variation: Vec<Move>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to represent the result of an iterative deepening search.
+///
+/// ## Attributes
+/// ```text
+/// best_move: Option<Move>, // The best move found in the last completed iteration. None only if the game is over.
+/// score: i32, // The score of best_move from the perspective of the side to move.
+/// depth: u32, // The depth of the last completed iteration. 0 if not even depth 1 could be completed in time.
+/// nodes: u64, // The number of positions visited, including the ones in an aborted iteration.
+/// principal_variation: Vec<Move>, // The line the search expects to be played, starting with best_move.
+/// lines: Vec<SearchLine>, // The best lines, best first and each starting with a different move (see docs for SearchOptions).
+///                         // The first line is the same as best_move, score and principal_variation.
+/// ```
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub principal_variation: Vec<Move>,
+    pub lines: Vec<SearchLine>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An enum to represent a search score the way the UCI protocol reports it.
+///
+/// ```text
+/// Centipawns(i32), // "score cp": centipawns from the perspective of the side to move.
+/// Mate(i32), // "score mate": the side to move mates in this many moves, or is mated in this many moves if negative.
+/// ```
+pub enum Score {
+    Centipawns(i32),
+    Mate(i32),
+}
+impl Score {
+    /// A function to convert a score returned by the search (see docs for SearchResult) into a Score.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::evaluation::MATE_SCORE;
+    /// # use alviny_task_3::search::Score;
+    /// assert_eq!(Score::from_search_score(34), Score::Centipawns(34));
+    /// assert_eq!(Score::from_search_score(MATE_SCORE - 3), Score::Mate(2)); // Mate on the third ply is mate in 2
+    /// assert_eq!(Score::from_search_score(-(MATE_SCORE - 2)), Score::Mate(-1));
+    /// ```
+    pub fn from_search_score(score: i32) -> Score {
+        let plies = MATE_SCORE - score.abs();
+        if plies > MATE_PLY_LIMIT {
+            return Score::Centipawns(score);
+        }
+        let moves = (plies + 1) / 2;
+        Score::Mate(if score > 0 { moves } else { -moves })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to represent the progress of a search, handed to an InfoSink.
+///
+/// ## Attributes
+/// ```text
+/// depth: u32, // The depth of the iteration.
+/// selective_depth: u32, // The deepest ply reached in the iteration so far, including captures searched past the depth.
+/// multipv: usize, // Which line this is, starting from 1 for the best one (see docs for SearchOptions).
+/// score: Option<Score>, // The score of the line, or None if the iteration hasn't completed yet.
+/// nodes: u64, // The number of positions visited since the search started.
+/// elapsed: Duration, // The time since the search started.
+/// nodes_per_second: u64, // The search speed.
+/// principal_variation: Vec<Move>, // The line, or empty if the iteration hasn't completed yet.
+/// ```
+pub struct SearchInfo {
+    pub depth: u32,
+    pub selective_depth: u32,
+    pub multipv: usize,
+    pub score: Option<Score>,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub nodes_per_second: u64,
+    pub principal_variation: Vec<Move>,
+}
+
+/// A trait for receiving the progress of a search, e.g. to stream UCI "info" lines to a GUI (see docs for uci::format_info).
+/// Any closure taking a &SearchInfo is an InfoSink.
+///
+/// The sink hears about every line of every completed iteration, and every INFO_INTERVAL during an iteration that takes longer.
+pub trait InfoSink {
+    fn info(&mut self, info: &SearchInfo);
+}
+impl<F: FnMut(&SearchInfo)> InfoSink for F {
+    fn info(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TableEntry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<(Square, Square, Option<char>)>,
+}
+
+#[derive(Clone, Debug)]
+/// A struct to remember the results of searched positions, keyed by their Zobrist key (see docs for Board::zobrist_key).
+/// The table has a fixed size, and a new result replaces whatever was stored in its slot.
+///
+/// A table can be kept between searches, e.g. by a UCI session between "go" commands, so that a search of the next
+/// position starts out knowing what the last one found. Game::search() uses a fresh table of SearchOptions::hash_size.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::search::TranspositionTable;
+/// let mut table = TranspositionTable::new(16);
+/// assert_eq!(table.size_mb(), 16);
+/// table.resize(64);
+/// assert_eq!(table.size_mb(), 64);
+/// ```
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    megabytes: usize,
+}
+impl TranspositionTable {
+    /// A function to create an empty table.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// megabytes: usize, // How much memory the table may use. 0 is treated as 1.
+    ///
+    /// ```
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let megabytes = megabytes.max(1);
+        let capacity = megabytes * 1024 * 1024 / size_of::<Option<TableEntry>>();
+        TranspositionTable { entries: vec![None; capacity], megabytes }
+    }
+
+    /// A function to change the size of the table. Everything stored in it is forgotten.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// megabytes: usize, // How much memory the table may use. 0 is treated as 1.
+    ///
+    /// ```
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = TranspositionTable::new(megabytes);
+    }
+
+    /// A function to forget everything stored in the table, e.g. when a new game starts.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// A function to return the size of the table in megabytes, as given to new() or resize().
+    pub fn size_mb(&self) -> usize {
+        self.megabytes
+    }
+
+    pub(crate) fn placeholder() -> TranspositionTable {
+        TranspositionTable { entries: vec![None], megabytes: 0 }
+    } // A table with room for one position, to stand in for a table that was lent to a search on another thread
+
+    /// A function to return how many positions the table has room for.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn probe(&self, key: u64) -> Option<TableEntry> {
+        self.entries[(key % self.entries.len() as u64) as usize].filter(|entry| entry.key == key)
+    }
+
+    fn store(&mut self, entry: TableEntry) {
+        let index = (entry.key % self.entries.len() as u64) as usize;
+        self.entries[index] = Some(entry);
+    }
+}
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_HASH_SIZE)
+    }
+}
+
+/// A struct to start searches on a worker thread, so that the caller stays free to e.g. read a UCI "stop" command.
+pub struct Search;
+impl Search {
+    /// A function to start a search (see docs for Game::search) on a new thread.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: Game, // The position to search.
+    /// options: SearchOptions, // The limits and the number of lines (see docs for SearchOptions). Without a time limit,
+    ///                         // a deep search only ends when stopped.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a SearchHandle to stop the search and collect its result.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::search::{Search, SearchOptions};
+    /// let handle = Search::spawn(Game::new(), SearchOptions::default()); // Searches until stopped
+    /// handle.stop();
+    /// assert!(handle.join().best_move.is_some());
+    /// ```
+    pub fn spawn(game: Game, options: SearchOptions) -> SearchHandle {
+        Search::spawn_with_info(game, options, |_info: &SearchInfo| {})
+    }
+
+    /// A function to start a search on a new thread, reporting its progress to an InfoSink (see docs for InfoSink).
+    /// The sink is called from the worker thread.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: Game, // The position to search.
+    /// options: SearchOptions, // The limits and the number of lines (see docs for SearchOptions).
+    /// sink: impl InfoSink + Send + 'static, // Where to report the progress.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a SearchHandle to stop the search and collect its result.
+    pub fn spawn_with_info(game: Game, options: SearchOptions, sink: impl InfoSink + Send + 'static) -> SearchHandle {
+        let table = TranspositionTable::new(options.hash_size);
+        Search::spawn_with_table(game, options, table, sink)
+    }
+
+    pub(crate) fn spawn_with_table(game: Game, options: SearchOptions, mut table: TranspositionTable, mut sink: impl InfoSink + Send + 'static) -> SearchHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            let result = run_search(&game, &options, Some(&mut table), Some(worker_stop), Some(&mut sink));
+            (result, table)
+        });
+        SearchHandle { stop, thread }
+    } // Like spawn_with_info(), with a table that is handed back by SearchHandle::join_with_table(), e.g. to keep it between moves
+}
+
+/// A struct to control a search running on a worker thread (see docs for Search::spawn).
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<(SearchResult, TranspositionTable)>,
+}
+impl SearchHandle {
+    /// A function to ask the search to stop. The search notices within a few nodes, and keeps the result of the last
+    /// completed iteration. Stopping a finished search does nothing.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// A function to check whether the search has finished, either by reaching a limit or by being stopped.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// A function to wait for the search to finish and return its result.
+    ///
+    /// ## Returns
+    /// This function returns the SearchResult (see docs for SearchResult). If any legal move exists, best_move is always Some.
+    pub fn join(self) -> SearchResult {
+        self.join_with_table().0
+    }
+
+    pub(crate) fn join_with_table(self) -> (SearchResult, TranspositionTable) {
+        self.thread.join().expect("Good heavens! The search thread panicked!")
+    } // Waits for the search, and hands back its transposition table too
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to play the moves found by a fixed depth search (see docs for Game::best_move), e.g. in a match
+/// (see docs for run_match).
+pub struct SearchPicker {
+    pub depth: u32,
+}
+impl MovePicker for SearchPicker {
+    fn pick(&mut self, game: &Game) -> Move {
+        let (best_move, _score) = game.best_move(self.depth).expect("Good heavens! Asked to pick a move in a finished game!");
+        best_move
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A struct to pass what is already known about a position to order_moves().
+///
+/// ## Attributes
+/// ```text
+/// best_move: Option<Move>, // A move to try before all others, e.g. the best move of the previous iteration (the PV move).
+/// ```
+pub struct OrderingHints {
+    pub best_move: Option<Move>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to remember what AnalysisBoard::make_null_move() changed, so that AnalysisBoard::unmake_null_move() can restore it.
+pub struct NullMove {
+    en_passant_square: String,
+    halfmove_counter: u32,
+    turn_counter: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct for a copy of a Board that analysis code may change in ways a game never could, such as passing the turn
+/// (see make_null_move()). It owns its Board, so nothing done to it reaches the Game it was copied from.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Game;
+/// # use alviny_task_3::search::AnalysisBoard;
+/// let example_game = Game::new(); // Create a new game at the starting position
+/// let mut analysis = AnalysisBoard::new(&example_game.board);
+/// let null_move = analysis.make_null_move().unwrap();
+/// assert_eq!(analysis.board().active_player, 'b');
+/// assert_eq!(example_game.board.active_player, 'w'); // The game is untouched
+/// analysis.unmake_null_move(null_move);
+/// assert_eq!(analysis.board(), &example_game.board);
+/// ```
+pub struct AnalysisBoard {
+    board: Board,
+}
+impl AnalysisBoard {
+    /// A function to start analysing a copy of a Board.
+    pub fn new(board: &Board) -> AnalysisBoard {
+        AnalysisBoard { board: board.clone() }
+    }
+
+    /// A function to return the Board as it stands after the changes made so far.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// A function to pass the turn without moving, for search techniques like null move pruning and threat detection.
+    /// Passing is not a legal chess move, so this is only available here and never through Game::make_move.
+    ///
+    /// ## Returns
+    /// This function returns Some(NullMove) to hand to unmake_null_move(), or None (leaving the board untouched)
+    /// if the active player is in check, since passing would leave the king in check.
+    pub fn make_null_move(&mut self) -> Option<NullMove> {
+        self.board.make_null_move()
+    }
+
+    /// A function to take back a null move made with make_null_move().
+    ///
+    /// ## Arguments
+    /// ```text
+    /// null_move: NullMove, // The value returned by the make_null_move() call to take back.
+    ///
+    /// ```
+    pub fn unmake_null_move(&mut self, null_move: NullMove) {
+        self.board.unmake_null_move(null_move);
+    }
+}
+
+impl Board {
+    pub(crate) fn make_null_move(&mut self) -> Option<NullMove> {
+        if player_is_in_check(self, self.active_player) {
+            return None;
+        }
+        let null_move = NullMove {
+            en_passant_square: self.en_passant_square.clone(),
+            halfmove_counter: self.halfmove_counter,
+            turn_counter: self.turn_counter,
+        };
+        self.en_passant_square = "-".to_string(); // The chance to capture en passant is gone after any move
+        self.halfmove_counter += 1;
+        if self.active_player == 'w' {
+            self.active_player = 'b';
+        } else {
+            self.turn_counter += 1;
+            self.active_player = 'w';
+        }
+        Some(null_move)
+    } // Passes the turn (see docs for AnalysisBoard::make_null_move). Not public, since Game would no longer match its board
+
+    pub(crate) fn unmake_null_move(&mut self, null_move: NullMove) {
+        self.en_passant_square = null_move.en_passant_square;
+        self.halfmove_counter = null_move.halfmove_counter;
+        self.turn_counter = null_move.turn_counter;
+        self.active_player = if self.active_player == 'w' { 'b' } else { 'w' };
+    } // Takes back a null move made with make_null_move()
+}
+
+/// A function to sort moves so that the ones most likely to be good come first, which is the order the search tries them in.
+///
+/// ## Arguments
+/// ```text
+/// board: &Board, // The position the moves are played in.
+/// moves: &mut [Move], // The moves to sort, in place.
+/// hints: &OrderingHints, // What is already known about the position (see docs for OrderingHints).
+///
+/// ```
+/// ## Returns
+/// This function sorts the moves in stages: first hints.best_move, then captures with the most valuable victim first and,
+/// among captures of the same piece, the least valuable attacker first (MVV-LVA), then promotions, best piece first,
+/// and finally the quiet moves. Moves within a stage keep their order.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::{Game, Move};
+/// # use alviny_task_3::search::{order_moves, OrderingHints};
+/// let example_game = Game::new_from_fen("4k3/8/8/3r4/2P1p3/3Q4/8/4K1N1 w - - 0 1".to_string());
+/// let mut moves = ["g1f3", "c4d5", "d3e4", "d3d5"].map(|uci_move| Move::from_uci(uci_move).unwrap()).to_vec();
+/// order_moves(&example_game.board, &mut moves, &OrderingHints::default());
+/// let ordered: Vec<String> = moves.iter().map(|ordered_move| ordered_move.to_uci()).collect();
+/// assert_eq!(ordered, ["c4d5", "d3d5", "d3e4", "g1f3"]); // PxR, QxR, QxP, then the quiet knight move
+/// ```
+pub fn order_moves(board: &Board, moves: &mut [Move], hints: &OrderingHints) {
+    moves.sort_by_cached_key(|chess_move| {
+        if hints.best_move.as_ref() == Some(chess_move) {
+            return Reverse(i32::MAX);
+        }
+        let source = get_board_coords(chess_move.source.clone());
+        let target = get_board_coords(chess_move.target.clone());
+        Reverse(move_order_key(board, &source, &target, chess_move.promotion))
+    });
+}
+
+impl Game {
+    /// A function to find the best move for the active player with a fixed depth search.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// depth: u32, // How many half-moves to look ahead. A depth of 0 is treated as 1.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some((best move, score)), where the score is from the perspective of the side to move.
+    /// It returns None only if the game is over.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let example_game = Game::new_from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2".to_string());
+    /// let (best_move, _score) = example_game.best_move(2).unwrap();
+    /// assert_eq!(best_move.to_uci(), "d8h4"); // 2... Qh4#
+    /// ```
+    pub fn best_move(&self, depth: u32) -> Option<(Move, i32)> {
+        if self.is_game_over() {
+            return None;
+        }
+        let mut searcher = Searcher::new(None);
+        searcher.rules = self.variant.non_standard_rules();
+        let (score, principal_variation) = searcher.search_root(&self.board, depth.max(1), None, &[])?;
+        let best_move = principal_variation.into_iter().next()?;
+        Some((to_move(best_move), score))
+    }
+
+    /// A function to find the best move for the active player within a time limit, using iterative deepening.
+    /// Short for search() with only a time limit set.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// limit: Duration, // How long the search may take. The clock is checked every few nodes, so expect it to overshoot slightly.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a SearchResult (see docs for SearchResult). If any legal move exists, best_move is always Some,
+    /// even if the limit is too short to complete a single iteration.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use std::time::Duration;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let result = example_game.best_move_timed(Duration::from_millis(100));
+    /// assert!(example_game.legal_moves().contains(&result.best_move.unwrap()));
+    /// ```
+    pub fn best_move_timed(&self, limit: Duration) -> SearchResult {
+        self.search(&SearchOptions { time_limit: Some(limit), ..SearchOptions::default() })
+    }
+
+    /// A function to search the position with iterative deepening: depth 1, then depth 2, and so on, until a limit is reached.
+    /// With options.multipv above 1, every iteration searches the root once per line, leaving out the first moves of
+    /// the lines already found, so that e.g. an analysis board can show the best few candidate moves.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &SearchOptions, // The limits and the number of lines (see docs for SearchOptions).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a SearchResult (see docs for SearchResult) from the last completed iteration. The search stops
+    /// early once every line ends in a forced mate. If any legal move exists, best_move is always Some, even if the time
+    /// limit is too short to complete a single iteration.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::search::SearchOptions;
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let result = example_game.search(&SearchOptions { depth: 2, multipv: 3, ..SearchOptions::default() });
+    /// assert_eq!(result.lines.len(), 3);
+    /// assert!(result.lines[0].score >= result.lines[1].score);
+    /// ```
+    pub fn search(&self, options: &SearchOptions) -> SearchResult {
+        run_search(self, options, None, None, None)
+    }
+
+    /// A function to search like search(), while reporting the progress to an InfoSink (see docs for InfoSink).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &SearchOptions, // The limits and the number of lines (see docs for SearchOptions).
+    /// sink: &mut dyn InfoSink, // Where to report the progress, e.g. a closure.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the same SearchResult as search().
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::search::{SearchInfo, SearchOptions};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let mut depths = vec![];
+    /// example_game.search_with_info(&SearchOptions { depth: 2, ..SearchOptions::default() }, &mut |info: &SearchInfo| depths.push(info.depth));
+    /// assert_eq!(depths, [1, 2]);
+    /// ```
+    pub fn search_with_info(&self, options: &SearchOptions, sink: &mut dyn InfoSink) -> SearchResult {
+        run_search(self, options, None, None, Some(sink))
+    }
+
+    /// A function to search like search_with_info(), with a transposition table that outlives the search.
+    /// options.hash_size is ignored, the table keeps its own size.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// options: &SearchOptions, // The limits and the number of lines (see docs for SearchOptions).
+    /// table: &mut TranspositionTable, // The table to use. What it knows from earlier searches is used, and kept up to date.
+    /// sink: &mut dyn InfoSink, // Where to report the progress, e.g. a closure.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the same SearchResult as search().
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::search::{SearchInfo, SearchOptions, TranspositionTable};
+    /// let mut table = TranspositionTable::new(1);
+    /// let options = SearchOptions { depth: 3, ..SearchOptions::default() };
+    /// let first = Game::new().search_with_table(&options, &mut table, &mut |_info: &SearchInfo| {});
+    /// let again = Game::new().search_with_table(&options, &mut table, &mut |_info: &SearchInfo| {});
+    /// assert!(again.nodes < first.nodes); // The second search finds the first one's results in the table
+    /// ```
+    pub fn search_with_table(&self, options: &SearchOptions, table: &mut TranspositionTable, sink: &mut dyn InfoSink) -> SearchResult {
+        run_search(self, options, Some(table), None, Some(sink))
+    }
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+/// Scores within this many plies of MATE_SCORE are mates.
+const MATE_PLY_LIMIT: i32 = 1000;
+
+struct Searcher<'a> {
+    rules: Option<&'a dyn Rules>,
+    evaluator: &'a dyn Evaluator,
+    table: Option<&'a mut TranspositionTable>,
+    deadline: Option<Instant>,
+    stop: Option<Arc<AtomicBool>>,
+    sink: Option<&'a mut dyn InfoSink>,
+    started: Instant,
+    last_report: Instant,
+    depth: u32,
+    selective_depth: u32,
+    nodes: u64,
+    aborted: bool,
+    quiescence: bool,
+    ordering: bool,
+}
+impl<'a> Searcher<'a> {
+    fn new(deadline: Option<Instant>) -> Searcher<'a> {
+        let started = Instant::now();
+        Searcher {
+            rules: None,
+            evaluator: &StandardEvaluator,
+            table: None,
+            deadline,
+            stop: None,
+            sink: None,
+            started,
+            last_report: started,
+            depth: 0,
+            selective_depth: 0,
+            nodes: 0,
+            aborted: false,
+            quiescence: true,
+            ordering: true,
+        }
+    }
+
+    fn visit_node(&mut self, ply: u32) -> bool {
+        self.nodes += 1;
+        self.selective_depth = self.selective_depth.max(ply);
+        if self.nodes.is_multiple_of(NODES_PER_CLOCK_CHECK) {
+            let out_of_time = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            let stopped = self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed));
+            self.aborted |= out_of_time || stopped;
+            if self.sink.is_some() && self.last_report.elapsed() >= INFO_INTERVAL {
+                self.report(1, None, vec![]);
+            }
+        }
+        self.aborted
+    } // Counts a node and looks at the clock and the stop flag every few nodes. Returns true if the search has to stop.
+
+    fn report(&mut self, multipv: usize, score: Option<i32>, principal_variation: Vec<InternalMove>) {
+        let elapsed = self.started.elapsed();
+        let info = SearchInfo {
+            depth: self.depth,
+            selective_depth: self.selective_depth,
+            multipv,
+            score: score.map(Score::from_search_score),
+            nodes: self.nodes,
+            elapsed,
+            nodes_per_second: (self.nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64,
+            principal_variation: principal_variation.into_iter().map(to_move).collect(),
+        };
+        if let Some(sink) = self.sink.as_mut() {
+            sink.info(&info);
+        }
+        self.last_report = Instant::now();
+    } // Hands the progress to the InfoSink, if there is one. Without a score and a line, it reports an iteration in progress.
+
+    fn search_lines(&mut self, board: &Board, depth: u32, count: usize, previous: &[(i32, Vec<InternalMove>)]) -> Option<Vec<(i32, Vec<InternalMove>)>> {
+        let mut lines: Vec<(i32, Vec<InternalMove>)> = vec![];
+        for index in 0..count {
+            let excluded = lines.iter().map(|(_score, line)| line[0].clone()).collect::<Vec<InternalMove>>();
+            let previous_best = previous.get(index).and_then(|(_score, line)| line.first());
+            match self.search_root(board, depth, previous_best, &excluded) {
+                Some(line) => lines.push(line),
+                None if self.aborted => return None,
+                None => break, // Every legal move already starts a line
+            }
+        }
+        lines.sort_by_key(|(score, _line)| Reverse(*score));
+        Some(lines)
+    } // Searches the root once per line, each time leaving out the first moves of the lines found so far (MultiPV).
+
+    fn search_root(&mut self, board: &Board, depth: u32, previous_best: Option<&InternalMove>, excluded: &[InternalMove]) -> Option<(i32, Vec<InternalMove>)> {
+        let mut moves = self.legal_moves(board);
+        moves.retain(|legal_move| !excluded.contains(legal_move));
+        self.order(board, &mut moves, previous_best); // Search the best move of the previous iteration first
+
+        let mut best = None;
+        let mut alpha = -INFINITY;
+        let mut child = board.clone(); // Every move is made and unmade on this one copy
+        for legal_move in moves {
+            let undo = self.make(board, &mut child, &legal_move);
+            let mut line = vec![];
+            let score = -self.negamax(&child, depth - 1, 1, -INFINITY, -alpha, &mut line);
+            unmake(&mut child, undo);
+            if self.aborted {
+                return None;
+            }
+            if best.is_none() || score > alpha {
+                alpha = score;
+                line.insert(0, legal_move);
+                best = Some((score, line));
+            }
+        }
+        best
+    } // Returns the score and principal variation, or None if there are no moves or the search ran out of time.
+
+    fn negamax(&mut self, board: &Board, depth: u32, ply: u32, mut alpha: i32, beta: i32, line: &mut Vec<InternalMove>) -> i32 {
+        if self.visit_node(ply) {
+            return 0; // The result is thrown away by search_root
+        }
+        if depth == 0 {
+            return if self.quiescence { self.quiescence_search(board, ply, alpha, beta) } else { self.leaf_score(board, ply) };
+        }
+        let mut moves = self.legal_moves(board);
+        if let Some(rules) = self.rules {
+            let status = rules.game_status(board);
+            if status.is_game_over() {
+                return status_score(status, board, ply);
+            }
+        } else if moves.is_empty() {
+            if player_is_in_check(board, board.active_player) {
+                return -(MATE_SCORE - ply as i32);
+            }
+            return 0; // Stalemate
+        } else if board.halfmove_counter >= 100 {
+            return 0;
+        }
+        let key = board.zobrist_key();
+        let entry = self.table.as_ref().and_then(|table| table.probe(key));
+        if let Some(entry) = entry
+            && entry.depth >= depth
+        {
+            let score = from_table_score(entry.score, ply);
+            if score >= beta && entry.bound != Bound::Upper {
+                return beta;
+            }
+            if score <= alpha && entry.bound != Bound::Lower {
+                return alpha;
+            }
+        } // Only cutoffs are taken from the table, since a score inside the window would need its line too
+        let hash_move = entry.and_then(|entry| entry.best_move).map(|(source, target, promotion)| (source.coords(), target.coords(), promotion));
+        self.order(board, &mut moves, hash_move.as_ref());
+
+        let original_alpha = alpha;
+        let mut child = board.clone();
+        for legal_move in moves {
+            let undo = self.make(board, &mut child, &legal_move);
+            let mut child_line = vec![];
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut child_line);
+            unmake(&mut child, undo);
+            if score >= beta {
+                self.remember(key, depth, ply, beta, Bound::Lower, Some(&legal_move));
+                return beta; // The opponent will never allow this line
+            }
+            if score > alpha {
+                alpha = score;
+                line.clear();
+                line.push(legal_move);
+                line.append(&mut child_line);
+            }
+        }
+        if alpha > original_alpha {
+            self.remember(key, depth, ply, alpha, Bound::Exact, line.first());
+        } else {
+            self.remember(key, depth, ply, alpha, Bound::Upper, None);
+        }
+        alpha
+    } // Returns the score of the board from the perspective of the side to move, and fills in the best line found.
+
+    fn remember(&mut self, key: u64, depth: u32, ply: u32, score: i32, bound: Bound, best_move: Option<&InternalMove>) {
+        if self.aborted {
+            return; // The score of an aborted search is meaningless
+        }
+        if let Some(table) = self.table.as_mut() {
+            let best_move = best_move.map(|(source, target, promotion)| (Square::from_coords(source), Square::from_coords(target), *promotion));
+            table.store(TableEntry { key, depth, score: to_table_score(score, ply), bound, best_move });
+        }
+    } // Stores the result of a node in the transposition table, if there is one.
+
+    fn leaf_score(&self, board: &Board, ply: u32) -> i32 {
+        let status = self.rules.map_or_else(|| get_game_status(board), |rules| rules.game_status(board));
+        if status.is_game_over() {
+            return status_score(status, board, ply);
+        }
+        let limit = MATE_SCORE - MATE_PLY_LIMIT - 1;
+        let score = self.evaluator.evaluate(board).clamp(-limit, limit);
+        if board.active_player == 'w' { score } else { -score }
+    } // Static evaluation at the leaves, flipped to the side to move. Finished games are scored here and never by the evaluator.
+
+    fn order(&self, board: &Board, moves: &mut [InternalMove], best_move: Option<&InternalMove>) {
+        if !self.ordering {
+            return;
+        }
+        moves.sort_by_cached_key(|legal_move| {
+            if best_move == Some(legal_move) {
+                return Reverse(i32::MAX);
+            }
+            Reverse(move_order_key(board, &legal_move.0, &legal_move.1, legal_move.2))
+        });
+    } // Sorts moves like order_moves(), for the search's own move representation.
+
+    fn quiescence_search(&mut self, board: &Board, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        if self.visit_node(ply) {
+            return 0;
+        }
+        let stand_pat = self.leaf_score(board, ply);
+        if stand_pat.abs() >= MATE_SCORE - ply as i32 {
+            return stand_pat; // Checkmate, there is nothing left to capture
+        }
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+        let mut captures = match self.rules {
+            Some(_) => self.legal_moves(board).into_iter().filter(|legal_move| get_captured_piece(board, &legal_move.0, &legal_move.1).is_some()).collect(),
+            None => get_legal_capture_list(board),
+        };
+        self.order(board, &mut captures, None);
+        let mut child = board.clone();
+        for capture in captures {
+            let (source, target) = (Square::from_coords(&capture.0), Square::from_coords(&capture.1));
+            if self.rules.is_none() && board.static_exchange(source, target) < 0 {
+                continue; // Obviously loses material. Variants may change what a capture does, so they try every capture
+            }
+            let undo = self.make(board, &mut child, &capture);
+            let score = -self.quiescence_search(&child, ply + 1, -beta, -alpha);
+            unmake(&mut child, undo);
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    } // Searches only captures until the position is quiet, so that the static evaluation isn't trusted in the middle of an exchange.
+
+    fn legal_moves(&self, board: &Board) -> Vec<InternalMove> {
+        match self.rules {
+            Some(rules) => rules
+                .legal_moves(board)
+                .into_iter()
+                .map(|legal_move| (get_board_coords(legal_move.source), get_board_coords(legal_move.target), legal_move.promotion))
+                .collect(),
+            None => get_legal_move_list(board),
+        }
+    } // The legal moves by the rules of the variant being searched (see docs for Variant::non_standard_rules).
+
+    fn make(&self, board: &Board, child: &mut Board, legal_move: &InternalMove) -> Option<Undo> {
+        match self.rules {
+            Some(rules) => {
+                child.clone_from(board);
+                rules.play_move(child, &to_move(legal_move.clone()));
+                None
+            }
+            None => Some(child.play_move_undoable(&legal_move.0, &legal_move.1, legal_move.2)),
+        }
+    } // Plays a move on child, a copy of board. Standard moves are made in place and unmade by unmake(), while a variant's
+    // rules can do anything to the board, so they play on a fresh copy instead.
+}
+
+fn unmake(child: &mut Board, undo: Option<Undo>) {
+    if let Some(undo) = undo {
+        child.unmake(undo);
+    }
+} // Takes back a move played by Searcher::make(). A variant's move needs no undoing, since the next one starts from a fresh copy.
+
+fn status_score(status: GameStatus, board: &Board, ply: u32) -> i32 {
+    match status.winner() {
+        Some(winner) if winner.to_char() == board.active_player => MATE_SCORE - ply as i32,
+        Some(_winner) => -(MATE_SCORE - ply as i32),
+        None => 0,
+    }
+} // Scores a finished game from the perspective of the side to move. Every win counts as a mate, however the variant decides it.
+
+fn run_search(
+    game: &Game,
+    options: &SearchOptions,
+    table: Option<&mut TranspositionTable>,
+    stop: Option<Arc<AtomicBool>>,
+    sink: Option<&mut dyn InfoSink>,
+) -> SearchResult {
+    let mut own_table;
+    let table = match table {
+        Some(table) => table,
+        None => {
+            own_table = TranspositionTable::new(options.hash_size);
+            &mut own_table
+        }
+    };
+    let mut searcher = Searcher::new(options.time_limit.map(|limit| Instant::now() + limit));
+    searcher.rules = game.variant.non_standard_rules();
+    searcher.evaluator = options.evaluator.as_ref();
+    searcher.table = Some(table);
+    searcher.stop = stop;
+    searcher.sink = sink.map(|sink| sink as &mut dyn InfoSink);
+    let mut result = SearchResult {
+        best_move: None,
+        score: 0,
+        depth: 0,
+        nodes: 0,
+        principal_variation: vec![],
+        lines: vec![],
+    };
+    if game.is_game_over() {
+        return result;
+    }
+    let mut lines = match searcher.legal_moves(&game.board).into_iter().next() {
+        Some(first_move) => vec![(0, vec![first_move])],
+        None => return result,
+    }; // Fallback in case not even depth 1 can be completed
+
+    for depth in 1..=options.depth.clamp(1, MAX_DEPTH) {
+        searcher.depth = depth;
+        searcher.selective_depth = 0;
+        match searcher.search_lines(&game.board, depth, options.multipv.max(1), &lines) {
+            Some(completed) => {
+                result.depth = depth;
+                lines = completed;
+                for (index, (score, line)) in lines.iter().enumerate() {
+                    searcher.report(index + 1, Some(*score), line.clone());
+                }
+            }
+            None => break, // Out of time or stopped, keep the last completed iteration
+        }
+        if lines.iter().all(|(score, _line)| score.abs() >= MATE_SCORE - depth as i32) {
+            break; // Forced mates have been found, searching deeper won't find quicker ones
+        }
+    }
+    result.nodes = searcher.nodes;
+    result.lines = lines
+        .into_iter()
+        .map(|(score, line)| SearchLine { score, principal_variation: line.into_iter().map(to_move).collect() })
+        .collect();
+    result.score = result.lines[0].score;
+    result.principal_variation = result.lines[0].principal_variation.clone();
+    result.best_move = result.principal_variation.first().cloned();
+    result
+} // Runs the iterative deepening of Game::search(). Setting stop aborts the search like running out of time does,
+// and the sink hears about the progress. Without a table, a fresh one of options.hash_size is used.
+
+fn to_table_score(score: i32, ply: u32) -> i32 {
+    if score >= MATE_SCORE - MATE_PLY_LIMIT {
+        score + ply as i32
+    } else if score <= -(MATE_SCORE - MATE_PLY_LIMIT) {
+        score - ply as i32
+    } else {
+        score
+    }
+} // Mate scores count plies from the root, so they are stored counting from the position itself instead.
+
+fn from_table_score(score: i32, ply: u32) -> i32 {
+    if score >= MATE_SCORE - MATE_PLY_LIMIT {
+        score - ply as i32
+    } else if score <= -(MATE_SCORE - MATE_PLY_LIMIT) {
+        score + ply as i32
+    } else {
+        score
+    }
+} // Inverse function for to_table_score().
+
+
+fn move_order_key(board: &Board, source: &[i32], target: &[i32], promotion: Option<char>) -> i32 {
+    let promotion_bonus = promotion.map_or(0, piece_value);
+    match get_captured_piece(board, source, target) {
+        Some(victim) => {
+            let attacker = board.board_state[source[0] as usize][source[1] as usize];
+            let attacker_value = if attacker.eq_ignore_ascii_case(&'k') { 1000 } else { piece_value(attacker) };
+            20_000 + 10 * piece_value(victim.to_fen_char()) - attacker_value + promotion_bonus
+        }
+        None if promotion.is_some() => 10_000 + promotion_bonus,
+        None => 0,
+    }
+} // A higher key means the move is tried earlier. The king counts as the most valuable attacker, since it can only take undefended pieces.
+
+fn to_move((source, target, promotion): InternalMove) -> Move {
+    Move {
+        source: get_algebraic_notation(source),
+        target: get_algebraic_notation(target),
+        promotion,
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    #[test]
+    fn test_null_moves() {
+        let mut test_board = Game::new_from_fen("r3k2r/ppp2ppp/2n5/3pP3/8/2N5/PPP2PPP/R3K2R w KQkq d6 0 9".to_string()).board;
+        let original_board = test_board.clone();
+        let original_key = test_board.zobrist_key();
+        let first = test_board.make_null_move().unwrap();
+        assert_eq!(test_board.active_player, 'b');
+        assert_eq!(test_board.en_passant_square, "-");
+        assert_ne!(test_board.zobrist_key(), original_key);
+        let second = test_board.make_null_move().unwrap();
+        assert_eq!(test_board.turn_counter, 10);
+        let after_two = test_board.zobrist_key();
+        test_board.unmake_null_move(second);
+        test_board.unmake_null_move(first);
+        assert_eq!(test_board, original_board);
+        assert_eq!(test_board.zobrist_key(), original_key);
+        // The en passant capture is gone, so only the en passant part of the key differs
+        let without_en_passant = Game::new_from_fen("r3k2r/ppp2ppp/2n5/3pP3/8/2N5/PPP2PPP/R3K2R w KQkq - 0 9".to_string());
+        assert_eq!(after_two, without_en_passant.board.zobrist_key());
+    }
+    #[test]
+    fn test_two_null_moves_restore_the_key() {
+        let mut test_board = Game::new().board;
+        let original_key = test_board.zobrist_key();
+        test_board.make_null_move().unwrap();
+        test_board.make_null_move().unwrap();
+        assert_eq!(test_board.zobrist_key(), original_key);
+    }
+    #[test]
+    fn test_no_null_move_in_check() {
+        let test_game = Game::new_from_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1".to_string());
+        let mut analysis = AnalysisBoard::new(&test_game.board);
+        assert!(analysis.make_null_move().is_none());
+        assert_eq!(analysis.board(), &test_game.board);
+    }
+
+    #[test]
+    fn test_fools_mate() {
+        let test_position = Game::new_from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2".to_string());
+        let (best_move, score) = test_position.best_move(2).unwrap();
+        println!("{:?} {}", best_move, score);
+        assert_eq!(best_move.to_uci(), "d8h4");
+        assert_eq!(score, MATE_SCORE - 1);
+    }
+    #[test]
+    fn test_mate_in_two() {
+        // 1. Kf7 (or Kg6) Kh7 2. Rh1#, while 1. Ra8+ lets the king out through h7
+        let test_position = Game::new_from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1".to_string());
+        let (best_move, score) = test_position.best_move(4).unwrap();
+        println!("{:?} {}", best_move, score);
+        assert_eq!(score, MATE_SCORE - 3);
+        let mut test_game = test_position.clone();
+        assert!(test_game.make_uci_move(&best_move.to_uci()));
+    }
+    #[test]
+    fn test_game_over_returns_none() {
+        let checkmate = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        let stalemate = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string());
+        assert!(checkmate.best_move(3).is_none());
+        assert!(stalemate.best_move(3).is_none());
+    }
+    #[test]
+    fn test_timed_search_respects_limit() {
+        let test_position = Game::new();
+        let start = Instant::now();
+        let result = test_position.best_move_timed(Duration::from_millis(200));
+        let elapsed = start.elapsed();
+        println!("{:?} in {:?}", result, elapsed);
+        assert!(elapsed < Duration::from_millis(400));
+        assert!(test_position.legal_moves().contains(&result.best_move.unwrap()));
+        assert!(result.nodes > 0);
+    }
+    #[test]
+    fn test_timed_search_tiny_budget() {
+        let test_position = Game::new();
+        let result = test_position.best_move_timed(Duration::from_millis(1));
+        assert!(test_position.legal_moves().contains(&result.best_move.unwrap()));
+    }
+    #[test]
+    fn test_timed_search_forced_mate() {
+        let test_position = Game::new_from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1".to_string());
+        let mut found_mate = false;
+        for limit in [1, 50, 2000] {
+            let result = test_position.best_move_timed(Duration::from_millis(limit));
+            println!("{}ms: {:?}", limit, result);
+            assert!(test_position.legal_moves().contains(&result.best_move.clone().unwrap()));
+            assert_eq!(result.principal_variation.first(), result.best_move.as_ref());
+            if found_mate {
+                assert_eq!(result.score, MATE_SCORE - 3); // A longer search never forgets the mate
+            }
+            found_mate = result.score == MATE_SCORE - 3;
+        }
+        assert!(found_mate);
+    }
+    #[test]
+    fn test_principal_variation_is_playable() {
+        let test_position = Game::new_from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1".to_string());
+        let result = test_position.best_move_timed(Duration::from_secs(5));
+        assert_eq!(result.principal_variation.len(), 3);
+        let mut test_game = test_position.clone();
+        for pv_move in &result.principal_variation {
+            assert!(test_game.make_uci_move(&pv_move.to_uci()));
+        }
+        assert_eq!(test_game.game_status, GameStatus::WhiteWinsByCheckmate);
+    }
+    #[test]
+    fn test_game_over_timed_search() {
+        let checkmate = Game::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string());
+        assert!(checkmate.best_move_timed(Duration::from_millis(10)).best_move.is_none());
+    }
+    #[test]
+    fn test_quiescence_sees_the_recapture() {
+        // Qxd5 wins a pawn at the horizon, but exd5 wins the queen right back
+        let test_board = Game::new_from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1".to_string()).board;
+        let mut without_quiescence = Searcher::new(None);
+        without_quiescence.quiescence = false;
+        let (_score, line) = without_quiescence.search_root(&test_board, 1, None, &[]).unwrap();
+        assert_eq!(to_move(line[0].clone()).to_uci(), "d1d5");
+
+        let (_score, line) = Searcher::new(None).search_root(&test_board, 1, None, &[]).unwrap();
+        assert_ne!(to_move(line[0].clone()).to_uci(), "d1d5");
+        let (best_move, score) = Game { board: test_board, ..Game::new() }.best_move(2).unwrap();
+        println!("{:?} {}", best_move, score);
+        assert_ne!(best_move.to_uci(), "d1d5");
+        assert!(score > 0);
+    }
+    #[test]
+    fn test_move_ordering() {
+        let test_game = Game::new_from_fen("4k3/8/8/2r5/1P1Q4/5N2/1p6/R3K3 w - - 0 1".to_string());
+        let mut moves = test_game.legal_moves();
+        order_moves(&test_game.board, &mut moves, &OrderingHints::default());
+        let position = |uci_move: &str| moves.iter().position(|legal_move| legal_move.to_uci() == uci_move).unwrap();
+        assert_eq!(moves[0].to_uci(), "b4c5"); // PxR: the most valuable victim, taken by the least valuable attacker
+        assert!(position("b4c5") < position("d4c5")); // QxR
+        assert!(position("d4c5") < position("d4b2")); // QxP
+        assert!(position("d4b2") < position("f3g5")); // A quiet knight move
+
+        let hints = OrderingHints { best_move: Some(Move::from_uci("f3g5").unwrap()) };
+        order_moves(&test_game.board, &mut moves, &hints);
+        assert_eq!(moves[0].to_uci(), "f3g5");
+    }
+    #[test]
+    fn test_ordering_searches_fewer_nodes() {
+        // A middlegame position with captures on both sides
+        let test_board = Game::new_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4".to_string()).board;
+        let mut unordered = Searcher::new(None);
+        unordered.ordering = false;
+        let (unordered_score, _line) = unordered.search_root(&test_board, 2, None, &[]).unwrap();
+        let mut ordered = Searcher::new(None);
+        let (ordered_score, line) = ordered.search_root(&test_board, 2, None, &[]).unwrap();
+        println!("unordered: {} nodes, ordered: {} nodes", unordered.nodes, ordered.nodes);
+        assert_eq!(ordered_score, unordered_score);
+        assert_eq!(to_move(line[0].clone()).to_uci(), "h5f7"); // Scholar's mate
+        assert!(ordered.nodes < unordered.nodes);
+    }
+    #[test]
+    fn test_multipv() {
+        // Rxd5 wins the queen, everything else leaves the rook to be taken or loses time
+        let test_position = Game::new_from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1".to_string());
+        let result = test_position.search(&SearchOptions { depth: 2, multipv: 4, ..SearchOptions::default() });
+        assert_eq!(result.lines.len(), 4);
+        assert_eq!(result.best_move.unwrap().to_uci(), "d1d5");
+        assert_eq!(result.lines[0].principal_variation, result.principal_variation);
+        assert!(result.lines[0].score > result.lines[1].score + 500);
+        assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        let mut first_moves = result.lines.iter().map(|line| line.principal_variation[0].to_uci()).collect::<Vec<String>>();
+        first_moves.sort();
+        first_moves.dedup();
+        assert_eq!(first_moves.len(), 4);
+
+        let single_move = Game::new_from_fen("k7/8/8/8/8/8/1r6/K7 w - - 0 1".to_string());
+        let result = single_move.search(&SearchOptions { depth: 2, multipv: 5, ..SearchOptions::default() });
+        assert_eq!(result.lines.len(), 1); // Kxb2 is the only legal move
+    }
+    #[test]
+    fn test_stop_a_spawned_search() {
+        let test_position = Game::new_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4".to_string());
+        let handle = Search::spawn(test_position.clone(), SearchOptions::default());
+        thread::sleep(Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        let stopped_at = Instant::now();
+        handle.stop();
+        let result = handle.join();
+        println!("{:?} after {:?}", result, stopped_at.elapsed());
+        assert!(stopped_at.elapsed() < Duration::from_millis(500));
+        assert!(test_position.legal_moves().contains(&result.best_move.unwrap()));
+
+        let finished = Search::spawn(test_position, SearchOptions { depth: 1, ..SearchOptions::default() });
+        while !finished.is_finished() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(finished.join().depth, 1);
+    }
+    #[test]
+    fn test_info_sink() {
+        let test_position = Game::new_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4".to_string());
+        let mut reports = vec![];
+        let result = test_position.search_with_info(&SearchOptions { depth: 3, ..SearchOptions::default() }, &mut |info: &SearchInfo| {
+            reports.push(info.clone())
+        });
+        let completed = reports.iter().filter(|info| info.score.is_some()).collect::<Vec<&SearchInfo>>();
+        assert_eq!(completed.iter().map(|info| info.depth).collect::<Vec<u32>>(), [1, 2, 3]);
+        assert!(reports.windows(2).all(|pair| pair[0].depth <= pair[1].depth && pair[0].nodes <= pair[1].nodes));
+        let last = completed.last().unwrap();
+        assert_eq!(last.score, Some(Score::Centipawns(result.score)));
+        assert_eq!(last.principal_variation, result.principal_variation);
+        assert!(last.selective_depth >= 3);
+        assert_eq!(last.nodes, result.nodes);
+    }
+    #[test]
+    fn test_custom_evaluator_still_finds_mates() {
+        struct Indifferent;
+        impl Evaluator for Indifferent {
+            fn evaluate(&self, _board: &Board) -> i32 {
+                0
+            }
+        }
+        struct Liar;
+        impl Evaluator for Liar {
+            fn evaluate(&self, _board: &Board) -> i32 {
+                MATE_SCORE // Claims White has mated in every position
+            }
+        }
+        let test_position = Game::new_from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1".to_string());
+        let options = SearchOptions { depth: 4, evaluator: Arc::new(Indifferent), ..SearchOptions::default() };
+        let result = test_position.search(&options);
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert_eq!(Score::from_search_score(result.score), Score::Mate(2));
+
+        let options = SearchOptions { depth: 2, evaluator: Arc::new(Liar), ..SearchOptions::default() };
+        let result = test_position.search(&options);
+        assert!(result.score < MATE_SCORE - MATE_PLY_LIMIT); // A large score, but never a mate
+        let stalemate = Game::new_from_fen("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1".to_string());
+        let result = stalemate.search(&SearchOptions { depth: 1, evaluator: Arc::new(Liar), ..SearchOptions::default() });
+        assert_ne!(result.best_move.unwrap().to_uci(), "f1f7"); // Stalemate is 0, however good the evaluator says it is
+    }
+    #[test]
+    fn test_table_keeps_mate_distances() {
+        // Mate in two with the rook: the table must not turn it into a mate in one, or lose it
+        let test_position = Game::new_from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1".to_string());
+        let options = SearchOptions { depth: 5, ..SearchOptions::default() };
+        let mut table = TranspositionTable::new(1);
+        let first = test_position.search_with_table(&options, &mut table, &mut |_info: &SearchInfo| {});
+        let again = test_position.search_with_table(&options, &mut table, &mut |_info: &SearchInfo| {});
+        assert_eq!(first.score, MATE_SCORE - 3);
+        assert_eq!(again.score, MATE_SCORE - 3);
+        assert!(again.nodes < first.nodes);
+        assert_eq!(test_position.search(&options).score, MATE_SCORE - 3);
+        table.clear();
+        assert_eq!(table.capacity(), TranspositionTable::new(1).capacity());
+    }
+    #[test]
+    fn test_best_move_is_legal() {
+        let test_position = Game::new();
+        let (best_move, _score) = test_position.best_move(1).unwrap();
+        assert!(test_position.legal_moves().contains(&best_move));
+    }
+    #[test]
+    fn test_search_follows_the_variant() {
+        // Kings never capture in Atomic, since the king would blow up with the knight
+        let atomic = Game::new_from_fen_with_variant("4k3/8/8/8/8/8/3n4/4K3 w - - 0 1".to_string(), Variant::Atomic);
+        for depth in 1..=3 {
+            let (best_move, _score) = atomic.best_move(depth).unwrap();
+            assert!(atomic.legal_moves().contains(&best_move));
+        }
+        let result = atomic.search(&SearchOptions { depth: 3, multipv: 10, ..SearchOptions::default() });
+        assert!(result.lines.iter().all(|line| line.principal_variation[0].to_uci() != "e1d2"));
+        assert!(result.lines.iter().all(|line| atomic.legal_moves().contains(&line.principal_variation[0])));
+
+        // Reaching the hill wins, in time for the search to score it as a mate
+        let hill = Game::new_from_fen_with_variant("7k/8/8/8/8/2K5/8/8 w - - 0 1".to_string(), Variant::KingOfTheHill);
+        let (best_move, score) = hill.best_move(2).unwrap();
+        assert_eq!((best_move.to_uci().as_str(), score), ("c3d4", MATE_SCORE - 1));
+        assert_eq!(hill.search(&SearchOptions { depth: 3, ..SearchOptions::default() }).score, MATE_SCORE - 1);
+
+        let race = Game::new_from_fen_with_variant("8/6K1/8/8/8/8/8/k7 w - - 0 1".to_string(), Variant::RacingKings);
+        let (best_move, score) = race.best_move(2).unwrap();
+        assert_eq!((best_move.target.as_bytes()[1], score), (b'8', MATE_SCORE - 1));
+    }
+}
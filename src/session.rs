@@ -0,0 +1,353 @@
+/*****************************
+*  GAME SESSIONS             *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A Game that can be shared between threads, for servers where several connections act on the same game.
+
+Every method takes &self and locks the game only for as long as it runs, so a GameSession can be put in an Arc and
+handed to every connection. Moves are checked and made under a single lock, so two players racing to move can never
+both succeed. Every move raises the session's version by one, which lets clients tell whether their view of the game
+is up to date, and try_move_at() only makes a move if the game hasn't changed since the client last looked.
+
+The observer of the Game (see docs for Game::set_observer) is taken over by the session, and is never called while the
+game is locked, so it can read the session, e.g. session.fen(), and even make moves on it. Events are queued under the
+lock and passed to the observer in the order they happened, one at a time, by whichever thread finds the observer
+free. So try_move() may return before its event has been passed on, if another thread is still busy with an earlier
+one.
+
+```
+# use alviny_task_3::{Color, Game};
+# use alviny_task_3::session::{GameSession, SessionError};
+# use std::sync::Arc;
+let session = Arc::new(GameSession::new(Game::new()));
+let for_black = Arc::clone(&session);
+assert_eq!(for_black.try_move(Color::Black, "e7", "e5", None), Err(SessionError::WrongPlayer));
+assert_eq!(session.try_move(Color::White, "e2", "e4", None), Ok(1));
+assert_eq!(for_black.version(), 1);
+```
+*/
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
+
+use crate::observer::{MoveEvent, MoveEventKind, Observer};
+use crate::{Color, Game, GameStatus, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An enum to represent why a session refused a move.
+///
+/// ```text
+/// WrongPlayer, // It isn't the player's turn.
+/// GameOver, // The game has already ended.
+/// IllegalMove, // The move is malformed or not legal in the position.
+/// StaleVersion(u64), // The game has changed since the version the client gave (see docs for try_move_at). Holds the current version.
+/// ```
+pub enum SessionError {
+    WrongPlayer,
+    GameOver,
+    IllegalMove,
+    StaleVersion(u64),
+}
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::WrongPlayer => write!(f, "it is not your turn"),
+            SessionError::GameOver => write!(f, "the game is over"),
+            SessionError::IllegalMove => write!(f, "the move is illegal"),
+            SessionError::StaleVersion(current) => write!(f, "the game has changed, it is now at version {}", current),
+        }
+    }
+}
+impl std::error::Error for SessionError {}
+
+/// A struct to represent a game shared between threads (see the module docs).
+pub struct GameSession {
+    state: Mutex<SessionState>,
+    observer: Mutex<Observer>,
+}
+impl GameSession {
+    /// A function to start a session for a game, at version 0.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: Game, // The game to share. It may already have moves in it. Its observer, if any, is called by the session (see module docs).
+    ///
+    /// ```
+    pub fn new(mut game: Game) -> GameSession {
+        let observer = std::mem::take(&mut game.observer);
+        GameSession { state: Mutex::new(SessionState { game, version: 0, pending: VecDeque::new() }), observer: Mutex::new(observer) }
+    }
+
+    /// A function for a player to make a move, if it is their turn and the move is legal.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// player: Color, // The player making the move, e.g. the one the connection belongs to.
+    /// from: &str, // The square the piece moves from, e.g. "e7".
+    /// to: &str, // The square the piece moves to, e.g. "e8".
+    /// promotion: Option<char>, // The piece a pawn promotes to ('q', 'r', 'b' or 'n'). None promotes to a queen.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the new version, or a SessionError (see docs for SessionError) if the move was refused,
+    /// in which case the game is unchanged.
+    pub fn try_move(&self, player: Color, from: &str, to: &str, promotion: Option<char>) -> Result<u64, SessionError> {
+        let result = self.state().try_move(player, from, to, promotion);
+        self.dispatch_events();
+        result
+    }
+
+    /// A function to make a move like try_move(), but only if the game is still at the version the client last saw.
+    /// This keeps a client from moving in a position it hasn't seen yet, e.g. after the opponent took back a move.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// version: u64, // The version the client's view of the game is at.
+    /// player: Color, // The player making the move.
+    /// from: &str, // The square the piece moves from.
+    /// to: &str, // The square the piece moves to.
+    /// promotion: Option<char>, // The piece a pawn promotes to. None promotes to a queen.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the new version, or a SessionError. SessionError::StaleVersion holds the current version.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game};
+    /// # use alviny_task_3::session::{GameSession, SessionError};
+    /// let session = GameSession::new(Game::new());
+    /// assert_eq!(session.try_move_at(0, Color::White, "e2", "e4", None), Ok(1));
+    /// assert_eq!(session.try_move_at(0, Color::Black, "e7", "e5", None), Err(SessionError::StaleVersion(1)));
+    /// ```
+    pub fn try_move_at(&self, version: u64, player: Color, from: &str, to: &str, promotion: Option<char>) -> Result<u64, SessionError> {
+        let result = {
+            let mut state = self.state();
+            if state.version != version {
+                return Err(SessionError::StaleVersion(state.version));
+            }
+            state.try_move(player, from, to, promotion)
+        };
+        self.dispatch_events();
+        result
+    }
+
+    /// A function to take back the last move, e.g. after both players agreed to it.
+    ///
+    /// ## Returns
+    /// This function returns the move that was taken back and the new version, or None if there are no moves. Taking
+    /// back a move raises the version too, since the game has changed.
+    pub fn undo_move(&self) -> Option<(Move, u64)> {
+        let result = {
+            let mut state = self.state();
+            let captures_before = state.captures();
+            let undone_move = state.game.undo_move()?;
+            state.version += 1;
+            let event = state.game.event(MoveEventKind::Undo, Some(undone_move.clone()), state.captures() < captures_before);
+            state.pending.push_back(event);
+            (undone_move, state.version)
+        };
+        self.dispatch_events();
+        Some(result)
+    }
+
+    /// A function to return the version, which starts at 0 and goes up by one with every change to the game.
+    pub fn version(&self) -> u64 {
+        self.state().version
+    }
+
+    /// A function to return the FEN of the current position.
+    pub fn fen(&self) -> String {
+        self.state().game.fen.clone()
+    }
+
+    /// A function to return whether the game is in progress, and if not, how it ended (see docs for GameStatus).
+    pub fn status(&self) -> GameStatus {
+        self.state().game.game_status
+    }
+
+    /// A function to list the legal moves of a player.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The player.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the legal moves, or an empty Vec if it isn't the player's turn or the game is over.
+    pub fn legal_moves_for(&self, color: Color) -> Vec<Move> {
+        let state = self.state();
+        if state.side_to_move() != color || state.game.is_game_over() {
+            return vec![];
+        }
+        state.game.legal_moves()
+    }
+
+    /// A function to return a copy of the game and the version it is at, e.g. to send the whole game to a client that
+    /// just connected. The copy is taken under a single lock, so the version always matches the game.
+    pub fn snapshot(&self) -> (Game, u64) {
+        let state = self.state();
+        (state.game.clone(), state.version)
+    }
+
+    fn state(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    } // Locks the game. A thread that panicked while holding the lock can't have left it half-changed, since
+    // moves are checked before they are made, so a poisoned lock is used as it is.
+
+    fn dispatch_events(&self) {
+        loop {
+            let observer = match self.observer.try_lock() {
+                Ok(observer) => observer,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => return, // The thread holding it passes our events on too
+            };
+            loop {
+                let Some(event) = self.state().pending.pop_front() else { break }; // The game is unlocked again before the call
+                observer.call(&event);
+            }
+            drop(observer);
+            if self.state().pending.is_empty() {
+                return;
+            }
+        } // An event queued while the observer was being released would be left behind, so check again
+    } // Passes the queued events to the observer without holding the game's lock (see module docs).
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+struct SessionState {
+    game: Game,
+    version: u64,
+    pending: VecDeque<MoveEvent>,
+}
+impl SessionState {
+    fn side_to_move(&self) -> Color {
+        Color::from_char(self.game.board.active_player).unwrap_or(Color::White)
+    } // The color whose turn it is.
+
+    fn captures(&self) -> usize {
+        self.game.captured.iter().map(Vec::len).sum()
+    } // The number of pieces captured so far, to tell whether a move captured one.
+
+    fn try_move(&mut self, player: Color, from: &str, to: &str, promotion: Option<char>) -> Result<u64, SessionError> {
+        if self.game.is_game_over() {
+            return Err(SessionError::GameOver);
+        }
+        if self.side_to_move() != player {
+            return Err(SessionError::WrongPlayer);
+        }
+        let uci_move = format!("{}{}{}", from, to, promotion.map(String::from).unwrap_or_default());
+        let requested = Move::from_uci(&uci_move).ok_or(SessionError::IllegalMove)?;
+        let captures_before = self.captures();
+        if !self.game.make_uci_move(&requested.to_uci()) {
+            return Err(SessionError::IllegalMove);
+        }
+        self.version += 1;
+        let event = self.game.event(MoveEventKind::Move, self.game.history.last().cloned(), self.captures() > captures_before);
+        self.pending.push_back(event);
+        Ok(self.version)
+    } // Checks and makes a move while the lock is held, and queues its event for the observer.
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    // No move of this line lands on a square a piece of the same color left earlier, so a move that lost a race
+    // can never become legal again later on
+    const LINE: [&str; 10] = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "d2d3", "f8c5", "c2c3", "d7d6"];
+
+    #[test]
+    fn test_racing_threads() {
+        let session = Arc::new(GameSession::new(Game::new()));
+        let threads = (0..8)
+            .map(|thread_index| {
+                let session = Arc::clone(&session);
+                thread::spawn(move || {
+                    let player = if thread_index % 2 == 0 { Color::White } else { Color::Black };
+                    let mut moves_made = 0;
+                    loop {
+                        let version = session.version() as usize;
+                        if version == LINE.len() {
+                            return moves_made;
+                        }
+                        let (from, to) = LINE[version].split_at(2);
+                        if session.try_move(player, from, to, None).is_ok() {
+                            moves_made += 1;
+                        }
+                        assert!(session.try_move(player, "a1", "a5", None).is_err()); // Never legal
+                        assert!(session.try_move(player, "x9", "e4", None).is_err());
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let moves_made = threads.into_iter().map(|thread| thread.join().unwrap()).sum::<usize>();
+        assert_eq!(moves_made, LINE.len()); // Every move of the line was made exactly once
+        assert_eq!(session.version(), LINE.len() as u64);
+        let sequential = Game::from_uci_moves(&LINE.join(" ")).unwrap();
+        assert_eq!(session.fen(), sequential.fen);
+        assert_eq!(session.snapshot().0.history, sequential.history);
+    }
+    #[test]
+    fn test_turns_and_versions() {
+        let session = GameSession::new(Game::new_from_fen("7k/P7/8/8/8/8/8/K7 w - - 0 1".to_string()));
+        assert!(session.legal_moves_for(Color::Black).is_empty());
+        assert_eq!(session.legal_moves_for(Color::White).len(), 7);
+        assert_eq!(session.try_move(Color::White, "a7", "a6", None), Err(SessionError::IllegalMove));
+        assert_eq!(session.try_move(Color::White, "a7", "a8", Some('n')), Ok(1));
+        assert!(session.fen().starts_with("N6k/"));
+        assert_eq!(session.undo_move().unwrap().1, 2);
+        assert_eq!(session.try_move_at(1, Color::White, "a7", "a8", None), Err(SessionError::StaleVersion(2)));
+        assert_eq!(session.try_move_at(2, Color::White, "a7", "a8", None), Ok(3));
+
+        let checkmated = GameSession::new(Game::from_moves(&["f3", "e5", "g4", "Qh4#"]).unwrap());
+        assert_eq!(checkmated.status(), GameStatus::BlackWinsByCheckmate);
+        assert_eq!(checkmated.try_move(Color::White, "e2", "e4", None), Err(SessionError::GameOver));
+        assert!(checkmated.legal_moves_for(Color::White).is_empty());
+    }
+    #[test]
+    fn test_observer_can_use_the_session() {
+        let handle: Arc<std::sync::OnceLock<std::sync::Weak<GameSession>>> = Arc::default();
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut game = Game::new();
+        let (observer_handle, sink) = (Arc::clone(&handle), Arc::clone(&seen));
+        game.set_observer(Box::new(move |event| {
+            let session = observer_handle.get().unwrap().upgrade().unwrap();
+            sink.lock().unwrap().push((event.kind, event.fen == session.fen(), event.is_capture)); // Would deadlock under the lock
+            if session.version() == 1 {
+                assert_eq!(session.try_move(Color::Black, "d7", "d5", None), Ok(2)); // Passed on after this call returns
+            }
+        }));
+        let session = Arc::new(GameSession::new(game));
+        handle.set(Arc::downgrade(&session)).unwrap();
+
+        assert_eq!(session.try_move(Color::White, "e2", "e4", None), Ok(1));
+        assert_eq!(session.try_move(Color::White, "e4", "d5", None), Ok(3));
+        assert_eq!(session.undo_move().map(|(_undone, version)| version), Some(4));
+        assert_eq!(session.try_move(Color::White, "a2", "a5", None), Err(SessionError::IllegalMove)); // Not reported
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.iter().map(|(kind, ..)| *kind).collect::<Vec<_>>(), [MoveEventKind::Move, MoveEventKind::Move, MoveEventKind::Move, MoveEventKind::Undo]);
+        assert_eq!(seen.iter().map(|(_kind, _fen, capture)| *capture).collect::<Vec<_>>(), [false, false, true, true]);
+        assert!(seen.iter().all(|(_kind, current, _capture)| *current)); // Each event was passed on before the next change
+    }
+}
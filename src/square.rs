@@ -0,0 +1,430 @@
+/*****************************
+*  SQUARES                   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Types for the 64 squares of the board and its files and ranks, so that downstream code doesn't have to juggle algebraic notation strings.
+
+Squares are numbered from a1 (0) to h8 (63), rank by rank, which is the numbering used by most chess software
+(including the Polyglot book format).
+*/
+
+use std::fmt;
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An enum to represent a file (column) of the board, from A to H.
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+impl File {
+    /// A function to create a file from its index.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// index: u8, // 0 is the a-file, 7 is the h-file.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(File), or None if the index is 8 or higher.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::square::File;
+    /// assert_eq!(File::from_index(4), Some(File::E));
+    /// ```
+    pub fn from_index(index: u8) -> Option<File> {
+        FILES.get(index as usize).copied()
+    }
+
+    /// A function to create a file from its letter.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// letter: char, // 'a' to 'h'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(File), or None if the letter isn't a file.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::square::File;
+    /// assert_eq!(File::from_char('c'), Some(File::C));
+    /// assert_eq!(File::from_char('i'), None);
+    /// ```
+    pub fn from_char(letter: char) -> Option<File> {
+        ('a'..='h').contains(&letter).then(|| FILES[(letter as u8 - b'a') as usize])
+    }
+
+    /// A function to return the index of the file, from 0 (a-file) to 7 (h-file).
+    pub fn index(&self) -> u8 {
+        *self as u8
+    }
+
+    /// A function to return the letter of the file, from 'a' to 'h'.
+    pub fn to_char(&self) -> char {
+        (b'a' + self.index()) as char
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An enum to represent a rank (row) of the board, from One to Eight.
+pub enum Rank {
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+impl Rank {
+    /// A function to create a rank from its index.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// index: u8, // 0 is the first rank, 7 is the eighth rank.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Rank), or None if the index is 8 or higher.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::square::Rank;
+    /// assert_eq!(Rank::from_index(3), Some(Rank::Four));
+    /// ```
+    pub fn from_index(index: u8) -> Option<Rank> {
+        RANKS.get(index as usize).copied()
+    }
+
+    /// A function to create a rank from its digit.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// digit: char, // '1' to '8'.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Rank), or None if the digit isn't a rank.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::square::Rank;
+    /// assert_eq!(Rank::from_char('8'), Some(Rank::Eight));
+    /// assert_eq!(Rank::from_char('9'), None);
+    /// ```
+    pub fn from_char(digit: char) -> Option<Rank> {
+        ('1'..='8').contains(&digit).then(|| RANKS[(digit as u8 - b'1') as usize])
+    }
+
+    /// A function to return the index of the rank, from 0 (first rank) to 7 (eighth rank).
+    pub fn index(&self) -> u8 {
+        *self as u8
+    }
+
+    /// A function to return the digit of the rank, from '1' to '8'.
+    pub fn to_char(&self) -> char {
+        (b'1' + self.index()) as char
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A struct to represent a single square on the board.
+pub struct Square(u8);
+impl Square {
+    /// A function to create a square from its file and rank.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// file: File, // The file of the square.
+    /// rank: Rank, // The rank of the square.
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// # use alviny_task_3::square::{File, Rank};
+    /// assert_eq!(Square::new(File::E, Rank::Four).to_algebraic(), "e4");
+    /// ```
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square(rank.index() * 8 + file.index())
+    }
+
+    /// A function to iterate over all 64 squares, from a1 to h8 (a1, b1, ..., h1, a2, ..., h8).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// assert_eq!(Square::all().count(), 64);
+    /// assert_eq!(Square::all().last().unwrap().to_algebraic(), "h8");
+    /// ```
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    /// A function to create a square from its index.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// index: u8, // 0 is a1, 7 is h1, 56 is a8 and 63 is h8.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Square), or None if the index is 64 or higher.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// assert_eq!(Square::from_index(4).unwrap().to_algebraic(), "e1");
+    /// assert!(Square::from_index(64).is_none());
+    /// ```
+    pub fn from_index(index: u8) -> Option<Square> {
+        (index < 64).then_some(Square(index))
+    }
+
+    /// A function to parse a square in algebraic notation.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// algebraic_notation: &str, // e.g. "e4".
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Square), or None if the string isn't a square.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// assert_eq!(Square::from_algebraic("h8").unwrap().index(), 63);
+    /// assert!(Square::from_algebraic("i9").is_none());
+    /// ```
+    pub fn from_algebraic(algebraic_notation: &str) -> Option<Square> {
+        let chars = algebraic_notation.chars().collect::<Vec<char>>();
+        if chars.len() != 2 {
+            return None;
+        }
+        Some(Square::new(File::from_char(chars[0])?, Rank::from_char(chars[1])?))
+    }
+
+    /// A function to write the square in algebraic notation. Inverse function for from_algebraic().
+    ///
+    /// ## Returns
+    /// This function returns a String such as "e4".
+    pub fn to_algebraic(&self) -> String {
+        format!("{}{}", self.file().to_char(), self.rank().to_char())
+    }
+
+    /// A function to return the index of the square, from a1 (0) to h8 (63).
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    /// A function to return the file of the square.
+    pub fn file(&self) -> File {
+        FILES[(self.0 % 8) as usize]
+    }
+
+    /// A function to return the rank of the square.
+    pub fn rank(&self) -> Rank {
+        RANKS[(self.0 / 8) as usize]
+    }
+
+    /// A function to calculate the distance to another square, counted in king moves (the Chebyshev distance).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// other: Square, // The square to measure to.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the distance, from 0 (the same square) to 7.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// let a1 = Square::from_algebraic("a1").unwrap();
+    /// let c6 = Square::from_algebraic("c6").unwrap();
+    /// assert_eq!(a1.distance(c6), 5);
+    /// ```
+    pub fn distance(&self, other: Square) -> u8 {
+        let file_distance = self.file().index().abs_diff(other.file().index());
+        let rank_distance = self.rank().index().abs_diff(other.rank().index());
+        file_distance.max(rank_distance)
+    }
+
+    /// A function to check whether another square is on the same diagonal or anti-diagonal as this one.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// other: Square, // The square to compare to.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if a bishop could move between the squares on an empty board, or if the squares are the same.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Square;
+    /// let a1 = Square::from_algebraic("a1").unwrap();
+    /// assert!(a1.same_diagonal(Square::from_algebraic("h8").unwrap()));
+    /// assert!(!a1.same_diagonal(Square::from_algebraic("h7").unwrap()));
+    /// ```
+    pub fn same_diagonal(&self, other: Square) -> bool {
+        let file_distance = self.file().index().abs_diff(other.file().index());
+        let rank_distance = self.rank().index().abs_diff(other.rank().index());
+        file_distance == rank_distance
+    }
+
+    pub(crate) fn from_coords(coords: &[i32]) -> Square {
+        Square(((7 - coords[0]) * 8 + coords[1]) as u8)
+    } // Converts Board.board_state coords ([0, 0] is a8) into a Square.
+
+    pub(crate) fn coords(&self) -> Vec<i32> {
+        vec![7 - (self.0 / 8) as i32, (self.0 % 8) as i32]
+    } // Converts a Square into Board.board_state coords. Inverse function for from_coords().
+}
+impl fmt::Debug for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_algebraic())
+    }
+}
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_algebraic())
+    }
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+const FILES: [File; 8] = [File::A, File::B, File::C, File::D, File::E, File::F, File::G, File::H];
+const RANKS: [Rank; 8] = [Rank::One, Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight];
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algebraic_round_trip() {
+        for index in 0..64 {
+            let square = Square::from_index(index).unwrap();
+            assert_eq!(Square::from_algebraic(&square.to_algebraic()), Some(square));
+        }
+        assert_eq!(Square::from_algebraic("a1").unwrap().index(), 0);
+        assert_eq!(Square::from_algebraic("e4").unwrap().index(), 28);
+        assert!(Square::from_algebraic("e").is_none());
+        assert!(Square::from_algebraic("e44").is_none());
+        assert!(Square::from_algebraic("E4").is_none());
+    }
+    #[test]
+    fn test_file_and_rank_conversions() {
+        for index in 0..8 {
+            let file = File::from_index(index).unwrap();
+            let rank = Rank::from_index(index).unwrap();
+            assert_eq!(file.index(), index);
+            assert_eq!(rank.index(), index);
+            assert_eq!(File::from_char(file.to_char()), Some(file));
+            assert_eq!(Rank::from_char(rank.to_char()), Some(rank));
+        }
+        assert_eq!(File::from_index(8), None);
+        assert_eq!(Rank::from_index(8), None);
+        assert_eq!(File::A.to_char(), 'a');
+        assert_eq!(File::H.to_char(), 'h');
+        assert_eq!(Rank::One.to_char(), '1');
+        assert_eq!(Rank::Eight.to_char(), '8');
+        assert_eq!(File::from_char('`'), None); // the character before 'a'
+        assert_eq!(File::from_char('A'), None);
+        assert_eq!(Rank::from_char('0'), None);
+    }
+    #[test]
+    fn test_square_file_and_rank() {
+        let corners = [("a1", File::A, Rank::One), ("h1", File::H, Rank::One), ("a8", File::A, Rank::Eight), ("h8", File::H, Rank::Eight)];
+        for (name, file, rank) in corners {
+            let square = Square::from_algebraic(name).unwrap();
+            assert_eq!(square.file(), file);
+            assert_eq!(square.rank(), rank);
+            assert_eq!(Square::new(file, rank), square);
+        }
+    }
+    #[test]
+    fn test_all_squares() {
+        let squares = Square::all().collect::<Vec<Square>>();
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[0].to_algebraic(), "a1");
+        assert_eq!(squares[7].to_algebraic(), "h1");
+        assert_eq!(squares[8].to_algebraic(), "a2");
+        assert_eq!(squares[63].to_algebraic(), "h8");
+        for (index, square) in squares.iter().enumerate() {
+            assert_eq!(square.index() as usize, index);
+        }
+    }
+    #[test]
+    fn test_distance() {
+        let square = |name: &str| Square::from_algebraic(name).unwrap();
+        assert_eq!(square("a1").distance(square("a1")), 0);
+        assert_eq!(square("a1").distance(square("h8")), 7);
+        assert_eq!(square("h1").distance(square("a8")), 7);
+        assert_eq!(square("e4").distance(square("f5")), 1);
+        assert_eq!(square("e4").distance(square("e8")), 4);
+        assert_eq!(square("b2").distance(square("g4")), 5);
+        assert_eq!(square("h1").distance(square("a2")), 7); // Adjacent indices, but opposite edges of the board
+        assert_eq!(square("h4").distance(square("a5")), 7);
+        assert_eq!(square("c3").distance(square("f7")), square("f7").distance(square("c3")));
+    }
+    #[test]
+    fn test_same_diagonal() {
+        let square = |name: &str| Square::from_algebraic(name).unwrap();
+        assert!(square("a1").same_diagonal(square("h8")));
+        assert!(square("h1").same_diagonal(square("a8")));
+        assert!(square("c1").same_diagonal(square("h6")));
+        assert!(square("e4").same_diagonal(square("b7")));
+        assert!(square("e4").same_diagonal(square("e4")));
+        assert!(!square("a1").same_diagonal(square("a8")));
+        assert!(!square("a1").same_diagonal(square("b3")));
+        assert!(!square("h1").same_diagonal(square("a1")));
+        assert!(!square("h2").same_diagonal(square("a2"))); // Index difference of 7, like a real anti-diagonal step
+        assert!(!square("h3").same_diagonal(square("a5"))); // Index difference of 9 across the board edge
+        assert!(!square("a4").same_diagonal(square("h5")));
+    }
+    #[test]
+    fn test_from_coords() {
+        assert_eq!(Square::from_coords(&[0, 0]).to_algebraic(), "a8");
+        assert_eq!(Square::from_coords(&[7, 7]).to_algebraic(), "h1");
+        assert_eq!(Square::from_coords(&[3, 4]).to_algebraic(), "e5");
+        for square in Square::all() {
+            assert_eq!(Square::from_coords(&square.coords()), square);
+        }
+    }
+}
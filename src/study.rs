@@ -0,0 +1,543 @@
+/*****************************
+*  STUDIES                   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Games with variations, as annotated games and opening studies are written: a tree of moves rather than a single line.
+
+A Game is a single line of play: the moves that were played, each with its comment, clock and evaluation, but no
+variations or NAGs (numeric annotation glyphs, e.g. `$1` or `!` for a good move), which keeps it cheap to replay, undo
+and search. Game::from_pgn() keeps the main line and skips the rest; a Study keeps everything the movetext says, with
+the same Variant tag. Every move is a MoveNode with its comments and NAGs, and the moves that can follow it. The first
+of those is the main line, the others are variations, in the order they were written. to_pgn() writes the tree back
+with the same structure, and from_game() and game() convert between the two.
+
+A study has a cursor, which starts before the first move. It can go forward along the main line, enter a variation,
+go back, and the line it is on can be promoted to the main line. game() replays the moves up to the cursor.
+
+```
+# use alviny_task_3::study::Study;
+let mut example_study = Study::from_pgn("1. e4 e5 (1... c5 {The Sicilian}) 2. Nf3 *").unwrap();
+assert!(example_study.go_forward()); // 1. e4
+assert_eq!(example_study.next_moves().len(), 2); // 1... e5 and 1... c5
+assert!(example_study.enter_variation(1));
+assert_eq!(example_study.current().unwrap().annotation.comment.as_deref(), Some("The Sicilian"));
+example_study.promote_to_mainline();
+assert_eq!(example_study.to_pgn().lines().last(), Some("1. e4 c5 { The Sicilian } (1... e5 2. Nf3) *"));
+```
+*/
+
+use std::slice;
+use std::vec;
+
+use crate::pgn::{self, MovetextToken, MoveAnnotation, PgnError};
+use crate::{Game, Move, Variant};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent a move of a study, and the moves that can follow it.
+///
+/// ## Attributes
+/// ```text
+/// chess_move: Move, // The move.
+/// annotation: MoveAnnotation, // The comments after the move (see docs for MoveAnnotation).
+/// starting_comment: Option<String>, // A comment before the move, e.g. at the start of a variation or of the game.
+/// nags: Vec<u8>, // The numeric annotation glyphs of the move, e.g. 1 for "!" and 4 for "??", in the order they were written.
+/// children: Vec<MoveNode>, // The moves that can follow. The first is the main line, the others are variations.
+/// ```
+pub struct MoveNode {
+    pub chess_move: Move,
+    pub annotation: MoveAnnotation,
+    pub starting_comment: Option<String>,
+    pub nags: Vec<u8>,
+    pub children: Vec<MoveNode>,
+}
+impl MoveNode {
+    /// A function to create a move without comments, NAGs or moves after it.
+    pub fn new(chess_move: Move) -> MoveNode {
+        MoveNode { chess_move, annotation: MoveAnnotation::default(), starting_comment: None, nags: vec![], children: vec![] }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A struct to represent a game with variations (see the module docs).
+///
+/// ## Attributes
+/// ```text
+/// tags: Vec<(String, String)>, // The tag pairs, in order, e.g. ("White", "Morphy, Paul").
+/// start_fen: String, // The position before the first move.
+/// variant: Variant, // The rules the moves are played by.
+/// moves: Vec<MoveNode>, // The first moves. The first is the main line, the others are variations.
+/// ```
+pub struct Study {
+    pub tags: Vec<(String, String)>,
+    pub start_fen: String,
+    pub variant: Variant,
+    pub moves: Vec<MoveNode>,
+    cursor: Vec<usize>,
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn read_line(tokens: &mut vec::IntoIter<MovetextToken>, start: &Game, in_variation: bool) -> Result<Vec<MoveNode>, PgnError> {
+    let mut line: Vec<(MoveNode, Vec<String>, Vec<MoveNode>)> = vec![]; // Each move, its comments and its variations
+    let mut leading_variations = vec![];
+    let mut starting_comments: Vec<String> = vec![];
+    let mut before = start.clone();
+    let mut game = start.clone();
+    loop {
+        match tokens.next() {
+            Some(MovetextToken::Move(san)) => {
+                before = game.clone();
+                let chess_move = game.play_san(&san).map_err(PgnError::IllegalMove)?;
+                let mut node = MoveNode::new(chess_move);
+                node.starting_comment = (!starting_comments.is_empty()).then(|| starting_comments.join(" "));
+                starting_comments.clear();
+                line.push((node, vec![], vec![]));
+            }
+            Some(MovetextToken::Comment(comment)) => match line.last_mut() {
+                Some((_node, comments, _variations)) => comments.push(comment),
+                None => starting_comments.push(comment.trim().to_string()),
+            },
+            Some(MovetextToken::Nag(nag)) => {
+                if let Some((node, _comments, _variations)) = line.last_mut() {
+                    node.nags.push(nag);
+                }
+            }
+            Some(MovetextToken::StartVariation) => match line.last_mut() {
+                Some((_node, _comments, variations)) => variations.extend(read_line(tokens, &before, true)?),
+                None => leading_variations.extend(read_line(tokens, start, true)?),
+            },
+            Some(MovetextToken::EndVariation) if in_variation => break,
+            None if !in_variation => break,
+            Some(MovetextToken::EndVariation) | None => return Err(PgnError::UnclosedVariation),
+        }
+    }
+    let mut moves: Vec<MoveNode> = vec![];
+    for (mut node, comments, variations) in line.into_iter().rev() {
+        node.annotation = pgn::parse_annotation(&comments);
+        node.children = moves;
+        moves = [node].into_iter().chain(variations).collect();
+    }
+    moves.extend(leading_variations);
+    Ok(moves)
+} // Reads a line of moves from the position at its start, up to the ')' that ends it or the end of the movetext. A
+// variation is read from the position before the move it replaces. Returns the line's first move followed by its
+// variations, each with the rest of its line as children
+
+fn write_line(moves: &[MoveNode], start: &Game, tokens: &mut Vec<String>) {
+    let mut moves = moves;
+    let mut game = start.clone();
+    let mut show_number = true;
+    while let Some((node, variations)) = moves.split_first() {
+        if let Some(comment) = &node.starting_comment {
+            tokens.push(format!("{{ {} }}", comment.replace('}', ")")));
+        }
+        let san = game.to_san(&node.chess_move).unwrap_or_else(|| node.chess_move.to_uci());
+        tokens.push(match (game.board.active_player, show_number || node.starting_comment.is_some()) {
+            ('w', _) => format!("{}. {}", game.fullmove_number(), san),
+            (_, true) => format!("{}... {}", game.fullmove_number(), san),
+            _ => san,
+        });
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        let comment = pgn::format_annotation(&node.annotation);
+        show_number = comment.is_some() || !variations.is_empty();
+        tokens.extend(comment);
+        for variation in variations {
+            let mut variation_tokens = vec![];
+            write_line(slice::from_ref(variation), &game, &mut variation_tokens);
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+            tokens.extend(variation_tokens);
+        }
+        game.make_uci_move(&node.chess_move.to_uci());
+        moves = &node.children;
+    }
+} // Writes a line and its variations in movetext, with the move number before every white move and before a black move
+// that follows a comment or a variation
+
+impl Study {
+    fn start_game(&self) -> Game {
+        Game::new_from_fen_with_variant(self.start_fen.clone(), self.variant.clone())
+    } // The position before the first move
+
+    fn children_at(&mut self, depth: usize) -> &mut Vec<MoveNode> {
+        let mut moves = &mut self.moves;
+        for index in &self.cursor[..depth] {
+            moves = &mut moves[*index].children;
+        }
+        moves
+    } // The moves that can follow the first depth moves of the cursor's line
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Study {
+    /// A function to create a study with the moves of a game as its main line, and no variations.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: &Game, // The game. Its annotations and tags are kept, and its result is written in the Result tag.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the study, with the seven standard tag pairs (see docs for Game::to_pgn) and the cursor
+    /// before the first move.
+    pub fn from_game(game: &Game) -> Study {
+        let mut tags: Vec<(String, String)> =
+            pgn::ROSTER.iter().map(|(name, value)| (name.to_string(), game.tag(name).unwrap_or(value).to_string())).collect();
+        tags.push(("Result".to_string(), game.result_string().to_string()));
+        if game.variant != Variant::Standard {
+            tags.push(("Variant".to_string(), game.variant.rules().name().to_string()));
+        }
+        if game.start_fen != Game::new_with_variant(game.variant.clone()).fen {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), game.start_fen.clone()));
+        }
+        tags.extend(game.tags().iter().filter(|(name, _value)| !pgn::ROSTER.iter().any(|(roster_name, _)| roster_name == name)).cloned());
+        let mut moves = vec![];
+        for (ply, chess_move) in game.history.iter().enumerate().rev() {
+            let mut node = MoveNode::new(chess_move.clone());
+            node.annotation = game.annotation(ply).cloned().unwrap_or_default();
+            node.children = moves;
+            moves = vec![node];
+        }
+        Study { tags, start_fen: game.start_fen.clone(), variant: game.variant.clone(), moves, cursor: vec![] }
+    }
+
+    /// A function to create a study by reading a single game in PGN, with its variations, comments and NAGs.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// pgn: &str, // The tag pairs and the movetext of one game.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the study with the cursor before the first move, or a PgnError (see docs for PgnError).
+    /// The Variant tag gives the rules, as in Game::from_pgn(). The ply of a ReplayError counts the moves from the start of the game along the line the move is in.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::study::Study;
+    /// let example_study = Study::from_pgn("[White \"Anderssen\"]\n\n1. e4 $1 (1. d4 d5) 1... e5 *").unwrap();
+    /// assert_eq!(example_study.moves.len(), 2);
+    /// assert_eq!(example_study.moves[0].nags, vec![1]);
+    /// assert_eq!(example_study.moves[1].children[0].chess_move.to_uci(), "d7d5");
+    /// ```
+    pub fn from_pgn(pgn: &str) -> Result<Study, PgnError> {
+        let (tags, movetext) = pgn::split_pgn(pgn)?;
+        let start = pgn::start_game(&tags)?;
+        let moves = read_line(&mut pgn::tokenize_movetext(&movetext)?.into_iter(), &start, false)?;
+        Ok(Study { tags, start_fen: start.fen.clone(), variant: start.variant.clone(), moves, cursor: vec![] })
+    }
+
+    /// A function to write the study in PGN, with its variations, comments and NAGs.
+    ///
+    /// ## Returns
+    /// This function returns the tag pairs and the movetext, ending in the value of the Result tag ("*" if there is
+    /// none). Lines are at most 80 characters long, unless a comment is longer.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            pgn.push_str(&pgn::format_tag(name, value));
+        }
+        if !self.tags.is_empty() {
+            pgn.push('\n');
+        }
+        let mut tokens = vec![];
+        write_line(&self.moves, &self.start_game(), &mut tokens);
+        tokens.push(self.tags.iter().find(|(name, _value)| name == "Result").map_or("*".to_string(), |(_name, result)| result.clone()));
+        pgn.push_str(&pgn::wrap_movetext(&tokens));
+        pgn
+    }
+
+    /// A function to return the moves of the main line, from the first move to the end, ignoring the cursor.
+    pub fn mainline(&self) -> Vec<Move> {
+        let mut mainline = vec![];
+        let mut moves = &self.moves;
+        while let Some(node) = moves.first() {
+            mainline.push(node.chess_move.clone());
+            moves = &node.children;
+        }
+        mainline
+    }
+
+    /// A function to replay the moves up to the cursor.
+    ///
+    /// ## Returns
+    /// This function returns the Game after the move the cursor is on, with the annotation of every move.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::study::Study;
+    /// let mut example_study = Study::from_pgn("1. f3 e5 2. g4 (2. e4) 2... Qh4# 0-1").unwrap();
+    /// while example_study.go_forward() {}
+    /// assert!(example_study.game().is_checkmate());
+    /// ```
+    pub fn game(&self) -> Game {
+        let mut game = self.start_game();
+        let mut moves = &self.moves;
+        for index in &self.cursor {
+            let node = &moves[*index];
+            game.make_uci_move(&node.chess_move.to_uci());
+            game.set_annotation(game.history.len() - 1, node.annotation.clone());
+            moves = &node.children;
+        }
+        game
+    }
+
+    /// A function to return the move the cursor is on, i.e. the last move made in game().
+    ///
+    /// ## Returns
+    /// This function returns the move, or None if the cursor is before the first move.
+    pub fn current(&self) -> Option<&MoveNode> {
+        let (last, path) = self.cursor.split_last()?;
+        let mut moves = &self.moves;
+        for index in path {
+            moves = &moves[*index].children;
+        }
+        moves.get(*last)
+    }
+
+    /// A function to return the move the cursor is on, to change its comments or NAGs (see docs for current).
+    pub fn current_mut(&mut self) -> Option<&mut MoveNode> {
+        let (&last, _path) = self.cursor.split_last()?;
+        self.children_at(self.cursor.len() - 1).get_mut(last)
+    }
+
+    /// A function to return the moves that can follow the cursor.
+    ///
+    /// ## Returns
+    /// This function returns the moves, the main line first and then its variations, or an empty slice at the end of
+    /// a line.
+    pub fn next_moves(&self) -> &[MoveNode] {
+        self.current().map_or(&self.moves, |node| &node.children)
+    }
+
+    /// A function to move the cursor one move forward along the main line of where it is.
+    ///
+    /// ## Returns
+    /// This function returns true if the cursor moved, or false at the end of a line.
+    pub fn go_forward(&mut self) -> bool {
+        self.enter_variation(0)
+    }
+
+    /// A function to move the cursor one move back.
+    ///
+    /// ## Returns
+    /// This function returns true if the cursor moved, or false if it was before the first move.
+    pub fn go_back(&mut self) -> bool {
+        self.cursor.pop().is_some()
+    }
+
+    /// A function to move the cursor back to before the first move.
+    pub fn go_to_start(&mut self) {
+        self.cursor.clear();
+    }
+
+    /// A function to move the cursor forward into one of the moves that can follow it (see docs for next_moves).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// index: usize, // The index of the move in next_moves(). 0 is the main line, 1 the first variation, and so on.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the cursor moved, or false if there is no such move.
+    pub fn enter_variation(&mut self, index: usize) -> bool {
+        if index >= self.next_moves().len() {
+            return false;
+        }
+        self.cursor.push(index);
+        true
+    }
+
+    /// A function to make the line the cursor is on the main line: at every move on the way to the cursor, the move
+    /// taken becomes the first of its siblings, and the other moves keep their order. The cursor stays on the same move.
+    pub fn promote_to_mainline(&mut self) {
+        for depth in 0..self.cursor.len() {
+            let index = self.cursor[depth];
+            let siblings = self.children_at(depth);
+            let promoted = siblings.remove(index);
+            siblings.insert(0, promoted);
+            self.cursor[depth] = 0;
+        }
+    }
+
+    /// A function to add a move after the cursor and move the cursor to it. A move that is already there is entered
+    /// rather than added twice, and a new move becomes the main line only if there are no moves after the cursor yet.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to a queen.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the cursor moved, or false if the move is illegal after the cursor.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Move;
+    /// # use alviny_task_3::study::Study;
+    /// let mut example_study = Study::from_pgn("1. e4 e5 *").unwrap();
+    /// assert!(example_study.add_move(&Move::from_uci("d2d4").unwrap()));
+    /// assert!(example_study.to_pgn().starts_with("1. e4 (1. d4) 1... e5"));
+    /// ```
+    pub fn add_move(&mut self, chess_move: &Move) -> bool {
+        let game = self.game();
+        let promotion = chess_move.promotion.map(|promotion| promotion.to_ascii_lowercase());
+        let Some(legal_move) = game.legal_moves().into_iter().find(|legal_move| {
+            legal_move.source == chess_move.source && legal_move.target == chess_move.target && legal_move.promotion.is_none_or(|legal| Some(legal) == promotion.or(Some('q')))
+        }) else {
+            return false;
+        };
+        let siblings = self.children_at(self.cursor.len());
+        let index = match siblings.iter().position(|node| node.chess_move == legal_move) {
+            Some(index) => index,
+            None => {
+                siblings.push(MoveNode::new(legal_move));
+                siblings.len() - 1
+            }
+        };
+        self.cursor.push(index);
+        true
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STUDY: &str = "[Event \"Opening study\"]\n[White \"?\"]\n[Result \"*\"]\n\n\
+        {The Italian Game} 1. e4 e5 2. Nf3 Nc6 3. Bc4 $1 {Aiming at f7.} (3. Bb5 {The Ruy Lopez} 3... a6 (3... Nf6 $5\n\
+        {The Berlin}) 4. Ba4) 3... Bc5 (3... Nf6 {The Two Knights} 4. Ng5 d5! (4... Bc5!? {The Traxler}) 5. exd5)\n\
+        4. c3 ; Preparing d4\n4... Nf6?! *";
+
+    #[test]
+    fn test_study_tree() {
+        let study = Study::from_pgn(STUDY).unwrap();
+        assert_eq!(study.tags[0], ("Event".to_string(), "Opening study".to_string()));
+        assert_eq!(study.moves.len(), 1);
+        assert_eq!(study.moves[0].starting_comment.as_deref(), Some("The Italian Game"));
+        let mainline = study.mainline().iter().map(Move::to_uci).collect::<Vec<String>>();
+        assert_eq!(mainline, ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "c2c3", "g8f6"]);
+
+        let after_nc6 = &study.moves[0].children[0].children[0].children[0];
+        let third_moves = &after_nc6.children;
+        assert_eq!(third_moves.iter().map(|node| node.chess_move.to_uci()).collect::<Vec<String>>(), ["f1c4", "f1b5"]);
+        assert_eq!((third_moves[0].nags.as_slice(), third_moves[0].annotation.comment.as_deref()), (&[1][..], Some("Aiming at f7.")));
+        let ruy_lopez = &third_moves[1];
+        assert_eq!(ruy_lopez.annotation.comment.as_deref(), Some("The Ruy Lopez"));
+        assert_eq!(ruy_lopez.children.len(), 2); // 3... a6 and 3... Nf6
+        assert_eq!(ruy_lopez.children[0].children[0].chess_move.to_uci(), "b5a4");
+        let berlin = &ruy_lopez.children[1];
+        assert_eq!((berlin.nags.as_slice(), berlin.annotation.comment.as_deref(), berlin.children.len()), (&[5][..], Some("The Berlin"), 0));
+
+        let black_third_moves = &third_moves[0].children;
+        assert_eq!(black_third_moves.len(), 2); // 3... Bc5 and 3... Nf6
+        let two_knights = &black_third_moves[1];
+        let fourth_black_moves = &two_knights.children[0].children;
+        assert_eq!((fourth_black_moves.len(), fourth_black_moves[0].nags.as_slice()), (2, &[1][..]));
+        assert_eq!(fourth_black_moves[1].annotation.comment.as_deref(), Some("The Traxler"));
+        assert_eq!(black_third_moves[0].children[0].annotation.comment.as_deref(), Some("Preparing d4"));
+        assert_eq!(black_third_moves[0].children[0].children[0].nags, vec![6]);
+    }
+    #[test]
+    fn test_study_round_trip() {
+        let study = Study::from_pgn(STUDY).unwrap();
+        let exported = study.to_pgn();
+        assert!(exported.lines().all(|line| line.len() <= 80));
+        let movetext = exported.split_whitespace().collect::<Vec<&str>>().join(" ");
+        assert!(movetext.starts_with("[Event \"Opening study\"] [White \"?\"] [Result \"*\"] { The Italian Game } 1. e4 e5"));
+        assert!(movetext.contains(" 3. Bc4 $1 { Aiming at f7. } (3. Bb5 { The Ruy Lopez } 3... a6 (3... Nf6 $5 { The Berlin }) 4. Ba4) 3... Bc5 "));
+        assert!(movetext.contains(" 4. Ng5 d5 $1 (4... Bc5 $5 { The Traxler }) 5. exd5) 4. c3 "));
+        let reimported = Study::from_pgn(&exported).unwrap();
+        assert_eq!(reimported.moves, study.moves);
+        assert_eq!(reimported.to_pgn(), exported);
+        assert_eq!(Game::from_pgn(&exported).unwrap().history, study.mainline());
+
+        let game = Game::from_pgn("1. e4 { [%clk 0:05:00] } 1... e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0").unwrap();
+        let from_game = Study::from_game(&game);
+        assert_eq!(from_game.mainline(), game.history);
+        assert!(from_game.to_pgn().ends_with("[Result \"1-0\"]\n\n1. e4 { [%clk 0:05:00] } 1... e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"));
+    }
+    #[test]
+    fn test_variant_studies() {
+        let pgn = "[Variant \"Atomic\"]\n[Result \"*\"]\n\n1. Nf3 e6 (1... d5) 2. Ng5 Qxg5 *\n";
+        let mut study = Study::from_pgn(pgn).unwrap();
+        assert_eq!(study.variant, Variant::Atomic);
+        while study.go_forward() {}
+        assert!(study.game().fen.starts_with("rnb1kbnr/pppp1ppp/4p3/8/8/8/")); // The queen blew up with the knight
+        assert_eq!(study.to_pgn(), pgn);
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!((game.variant.clone(), game.fen.clone()), (Variant::Atomic, study.game().fen));
+        assert!(Study::from_game(&game).to_pgn().contains("[Result \"*\"]\n[Variant \"Atomic\"]\n\n1. Nf3 e6 2. Ng5 Qxg5 *"));
+        assert_eq!(Game::from_pgn(&game.to_pgn()).unwrap(), game);
+        assert_eq!(Study::from_pgn("[Variant \"Crazyhouse\"]\n\n1. e4 *").unwrap_err(), PgnError::UnknownVariant("Crazyhouse".to_string()));
+    }
+    #[test]
+    fn test_navigation() {
+        let mut study = Study::from_pgn(STUDY).unwrap();
+        assert!(study.current().is_none() && !study.go_back());
+        for _ in 0..4 {
+            assert!(study.go_forward());
+        }
+        assert_eq!(study.next_moves().len(), 2);
+        assert!(!study.enter_variation(2));
+        assert!(study.enter_variation(1)); // 3. Bb5
+        assert!(study.enter_variation(1)); // 3... Nf6
+        assert!(!study.go_forward());
+        assert_eq!(study.game().fen, "r1bqkb1r/pppp1ppp/2n2n2/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        study.current_mut().unwrap().annotation.comment = Some("The Berlin Wall".to_string());
+        assert_eq!(study.game().annotation(5).unwrap().comment.as_deref(), Some("The Berlin Wall"));
+
+        study.promote_to_mainline();
+        assert_eq!(study.current().unwrap().annotation.comment.as_deref(), Some("The Berlin Wall"));
+        let mainline = study.mainline().iter().map(Move::to_uci).collect::<Vec<String>>();
+        assert_eq!(mainline, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "g8f6"]);
+        let movetext = study.to_pgn().split_whitespace().collect::<Vec<&str>>().join(" ");
+        assert!(movetext.contains(" 3. Bb5 { The Ruy Lopez } (3. Bc4 $1 { Aiming at f7. } 3... Bc5 "));
+        assert!(movetext.contains(" 4. c3 { Preparing d4 } 4... Nf6 $6) 3... Nf6 $5 { The Berlin Wall } (3... a6 4. Ba4) *"));
+
+        assert!(!study.add_move(&Move::from_uci("e1e3").unwrap()));
+        assert!(study.add_move(&Move::from_uci("e1g1").unwrap()));
+        assert!(study.go_back());
+        assert!(study.add_move(&Move::from_uci("e1g1").unwrap())); // Entered again, not added twice
+        assert_eq!(study.to_pgn().matches("O-O").count(), 1);
+        study.go_to_start();
+        assert_eq!(study.game().fen, Game::new().fen);
+    }
+    #[test]
+    fn test_broken_studies() {
+        assert_eq!(Study::from_pgn("1. e4 (1. d4 e5").unwrap_err(), PgnError::UnclosedVariation);
+        assert_eq!(Study::from_pgn("1. e4 e5) 2. Nf3").unwrap_err(), PgnError::UnclosedVariation);
+        let replay_error = match Study::from_pgn("1. e4 e5 (1... Nf3) *") {
+            Err(PgnError::IllegalMove(replay_error)) => replay_error,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!((replay_error.ply, replay_error.move_text.as_str()), (1, "Nf3"));
+    }
+}
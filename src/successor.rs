@@ -0,0 +1,214 @@
+/*****************************
+*  SUCCESSORS                *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+The position after a move, without changing the current one. This is what analysis asks all the time:
+"what would the position be if I played this?"
+
+Game::with_move returns the next Game with the same bookkeeping as make_move(), and Game::with_move_using can skip
+the parts the caller doesn't need. Board::after_move is cheaper still, since it only plays the move on the board.
+*/
+
+use std::collections::HashMap;
+
+use crate::explain::{MoveDiagnosis, MoveError};
+use crate::{get_algebraic_notation, get_board_coords, get_legal_move_list, Board, Game, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A struct to represent which bookkeeping Game::with_move_using does. The Default does all of it, like make_move().
+///
+/// ## Attributes
+/// ```text
+/// update_status: bool, // Recompute checks and game_status. If false, they are copied from the current position.
+/// keep_history: bool, // Copy start_fen, history, the captured pieces and the annotations, and add the move to them. If false, the
+/// // successor is a new game starting from its own position, with an empty history.
+/// ```
+pub struct SuccessorOptions {
+    pub update_status: bool,
+    pub keep_history: bool,
+}
+impl Default for SuccessorOptions {
+    fn default() -> Self {
+        SuccessorOptions { update_status: true, keep_history: true }
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+pub(crate) fn find_legal_move(legal_moves: &[Move], chess_move: &Move, promotion_selection: char) -> Result<Move, Option<char>> {
+    if let Some(promotion) = chess_move.promotion && !"qrbn".contains(promotion.to_ascii_lowercase()) {
+        return Err(Some(promotion));
+    }
+    let promotion = chess_move.promotion.unwrap_or(promotion_selection).to_ascii_lowercase();
+    legal_moves
+        .iter()
+        .find(|legal_move| {
+            legal_move.source == chess_move.source
+                && legal_move.target == chess_move.target
+                && legal_move.promotion.is_none_or(|legal_promotion| legal_promotion == promotion)
+        })
+        .cloned()
+        .ok_or(None)
+} // Finds the legal move the given move means, as make_uci_move() reads it: a missing promotion piece is the promotion
+// selection, and a promotion piece on a move that isn't a promotion is ignored. Err(Some) is a piece pawns can't promote to
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to return the game after a move, leaving this game as it is.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Game that make_move() would produce (without an observer), or a MoveError (see docs for
+    /// MoveError) if the move is illegal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let after_e4 = example_game.with_move(&Move::from_uci("e2e4").unwrap()).unwrap();
+    /// assert_eq!(after_e4.fen, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    /// assert_eq!(example_game.history.len(), 0); // Unchanged
+    /// assert_eq!(example_game.with_move(&Move::from_uci("e2e5").unwrap()).unwrap_err().to_string(), "a pawn can't move like that");
+    /// ```
+    pub fn with_move(&self, chess_move: &Move) -> Result<Game, MoveError> {
+        self.with_move_using(chess_move, SuccessorOptions::default())
+    }
+
+    /// A function to return the game after a move, leaving this game as it is, with only some of the bookkeeping.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to Board.promotion_selection, as in make_move().
+    /// options: SuccessorOptions, // Which bookkeeping to do (see docs for SuccessorOptions).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the next Game, or a MoveError (see docs for MoveError) if the move is illegal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, GameStatus, Move};
+    /// # use alviny_task_3::successor::SuccessorOptions;
+    /// let example_game = Game::new_from_fen("7k/8/6K1/8/8/8/8/R7 w - - 0 1".to_string());
+    /// let fast = SuccessorOptions { update_status: false, keep_history: false };
+    /// let mate = example_game.with_move_using(&Move::from_uci("a1a8").unwrap(), fast).unwrap();
+    /// assert_eq!(mate.game_status, GameStatus::InProgress); // Not recomputed
+    /// assert_eq!(mate.start_fen, mate.fen);
+    /// ```
+    pub fn with_move_using(&self, chess_move: &Move, options: SuccessorOptions) -> Result<Game, MoveError> {
+        let legal_move = find_legal_move(&self.legal_moves(), chess_move, self.board.promotion_selection).map_err(|error| {
+            match error {
+                Some(promotion) => MoveError::InvalidPromotion(promotion),
+                None => match self.explain_move(&chess_move.source, &chess_move.target) {
+                    MoveDiagnosis::Legal => MoveError::Illegal(MoveDiagnosis::Forbidden),
+                    diagnosis => MoveError::Illegal(diagnosis),
+                },
+            }
+        })?;
+        let mut next_game = self.clone();
+        next_game.apply_move(legal_move, options.update_status);
+        if !options.keep_history {
+            next_game.start_fen = next_game.fen.clone();
+            next_game.history.clear();
+            next_game.position_occurrences = HashMap::from([(next_game.board.zobrist_key(), 1)]);
+            next_game.captured = Default::default();
+            next_game.annotations.clear();
+        }
+        Ok(next_game)
+    }
+}
+
+impl Board {
+    /// A function to return the board after a move in standard chess, leaving this board as it is. This is cheaper than
+    /// Game::with_move, since there is no FEN, history or game status to update.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move. A promotion of None promotes to promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the next Board, or None if the move is illegal. Variants are not taken into account.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, Game, Move};
+    /// let example_game = Game::new(); // Create a new game at the starting position
+    /// let board = example_game.board.after_move(&Move::from_uci("g1f3").unwrap()).unwrap();
+    /// assert_eq!(generate_fen(&board), "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1");
+    /// ```
+    pub fn after_move(&self, chess_move: &Move) -> Option<Board> {
+        let legal_moves = get_legal_move_list(self)
+            .into_iter()
+            .map(|(source, target, promotion)| Move {
+                source: get_algebraic_notation(source),
+                target: get_algebraic_notation(target),
+                promotion,
+            })
+            .collect::<Vec<Move>>();
+        let legal_move = find_legal_move(&legal_moves, chess_move, self.promotion_selection).ok()?;
+        let mut next_board = self.clone();
+        next_board.play_move(get_board_coords(legal_move.source), get_board_coords(legal_move.target), legal_move.promotion);
+        Some(next_board)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, GameStatus};
+
+    #[test]
+    fn test_successors_match_make_move() {
+        let mut game = Game::new_from_fen("r1n1k2r/1P6/8/8/5p2/8/4P3/R3K2R w KQkq - 0 1".to_string());
+        for uci_move in ["e2e4", "f4e3", "b7c8n", "e8g8", "e1c1", "e3e2"] {
+            let before = game.clone();
+            let chess_move = Move::from_uci(uci_move).unwrap();
+            let successor = game.with_move(&chess_move).unwrap();
+            let board = game.board.after_move(&chess_move).unwrap();
+            assert_eq!(game, before, "{}", uci_move); // Untouched
+            assert!(game.make_uci_move(uci_move), "{}", uci_move);
+            assert_eq!(successor, game, "{}", uci_move);
+            assert_eq!(board, game.board, "{}", uci_move);
+        }
+        assert_eq!((game.captured_by(Color::White).len(), game.captured_by(Color::Black).len()), (1, 1));
+    }
+    #[test]
+    fn test_rejected_successors() {
+        let game = Game::new_from_fen("4r1k1/8/8/8/8/8/4B3/4K2P w - - 0 1".to_string());
+        let error = game.with_move(&Move::from_uci("e2d3").unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "that bishop is pinned by the rook on e8");
+        assert!(game.board.after_move(&Move::from_uci("e2d3").unwrap()).is_none());
+        let bad_promotion = Move { source: "e1".to_string(), target: "d1".to_string(), promotion: Some('k') };
+        assert_eq!(game.with_move(&bad_promotion), Err(MoveError::InvalidPromotion('k')));
+        let checkmated = Game::new_from_fen("7k/8/6K1/8/8/8/8/R7 w - - 0 1".to_string()).with_move(&Move::from_uci("a1a8").unwrap()).unwrap();
+        assert_eq!(checkmated.game_status, GameStatus::WhiteWinsByCheckmate);
+        assert_eq!(checkmated.with_move(&Move::from_uci("h8h7").unwrap()), Err(MoveError::Illegal(MoveDiagnosis::GameOver)));
+    }
+}
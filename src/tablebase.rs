@@ -0,0 +1,1402 @@
+/*****************************
+*  SYZYGY TABLEBASES         *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Endgame tablebase probing in the Syzygy format. Only available with the `tablebase` feature.
+Read more about the format here:
+<https://www.chessprogramming.org/Syzygy_Bases>
+
+A tablebase is a directory with two files per material balance, named after the pieces of each side, e.g. "KQvKR.rtbw"
+and "KQvKR.rtbz". The .rtbw file holds whether each position is won, drawn or lost (see docs for Wdl), and the .rtbz
+file how many half-moves it takes to reach the next capture, pawn move or mate (the DTZ). Files are read into memory
+the first time a position needs them. Tables with and without pawns are supported, laid out as Stockfish's prober reads them.
+
+KvK has no file, since it is always a draw. Positions where castling is still allowed are not in the tables.
+
+The results assume perfect play, and don't count the moves already made towards the fifty-move rule.
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::rules::{Rules, StandardRules};
+use crate::{Board, Color, Game, GameStatus, Move, Piece, PieceKind, Square, Variant};
+
+/// The bytes every .rtbw file starts with.
+pub const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+/// The bytes every .rtbz file starts with.
+pub const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An enum to represent the result of a position with perfect play, for the side to move.
+///
+/// ```text
+/// Loss,
+/// BlessedLoss, // Lost, but the fifty-move rule comes first, so it is a draw.
+/// Draw,
+/// CursedWin, // Won, but the fifty-move rule comes first, so it is a draw.
+/// Win,
+/// ```
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+impl Wdl {
+    /// A function to return the result for the other side.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::tablebase::Wdl;
+    /// assert_eq!(Wdl::CursedWin.opposite(), Wdl::BlessedLoss);
+    /// ```
+    pub fn opposite(&self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    fn from_value(value: u32) -> Option<Wdl> {
+        [Wdl::Loss, Wdl::BlessedLoss, Wdl::Draw, Wdl::CursedWin, Wdl::Win].get(value as usize).copied()
+    } // Tables store the result as 0 (loss) to 4 (win)
+
+    fn signum(&self) -> i32 {
+        match self {
+            Wdl::Loss | Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+            Wdl::CursedWin | Wdl::Win => 1,
+        }
+    }
+}
+
+/// A struct to represent a directory of Syzygy tables.
+pub struct Tablebase {
+    tables: HashMap<String, TableFiles>,
+    max_pieces: usize,
+}
+impl Tablebase {
+    /// A function to find the tables in a directory. The files are only read once a position needs them.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// directory: impl AsRef<Path>, // The directory with the .rtbw and .rtbz files. Other files are ignored.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the tablebase, or an io::Error if the directory can't be read.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::tablebase::Tablebase;
+    /// let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/syzygy")).unwrap();
+    /// assert_eq!(tablebase.max_pieces(), 3); // KQvK
+    /// ```
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<Tablebase> {
+        let mut tables = HashMap::<String, TableFiles>::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let (Some(name), Some(extension)) = (path.file_stem(), path.extension()) else {
+                continue;
+            };
+            let Some(material) = name.to_str().and_then(Material::from_name) else {
+                continue;
+            };
+            let files = tables.entry(material.name()).or_default();
+            match extension.to_str() {
+                Some("rtbw") => files.wdl = Some(LazyTable { path, table: OnceLock::new() }),
+                Some("rtbz") => files.dtz = Some(LazyTable { path, table: OnceLock::new() }),
+                _ => {}
+            }
+        }
+        tables.retain(|_name, files| files.wdl.is_some() || files.dtz.is_some());
+        let max_pieces = tables
+            .keys()
+            .filter_map(|name| Material::from_name(name))
+            .map(|material| material.piece_count())
+            .fold(2, usize::max);
+        Ok(Tablebase { tables, max_pieces })
+    }
+
+    /// A function to return the most pieces a position can have and still be looked up, kings included.
+    /// This is 2 (KvK) if the directory has no tables.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// A function to look up whether a position is won, drawn or lost.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The position, by the rules of standard chess.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(Wdl) for the side to move (see docs for Wdl), or None if the position has castling
+    /// rights or more than max_pieces() pieces, or its table (or the table of a capture from it) is missing or broken.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::tablebase::{Tablebase, Wdl};
+    /// let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/syzygy")).unwrap();
+    /// let example_game = Game::new_from_fen("8/8/8/3k4/8/8/8/4KQ2 b - - 0 1".to_string());
+    /// assert_eq!(tablebase.probe_wdl(&example_game.board), Some(Wdl::Loss));
+    /// let example_game = Game::new_from_fen("8/8/8/3k4/8/8/8/4K3 w - - 0 1".to_string());
+    /// assert_eq!(tablebase.probe_wdl(&example_game.board), Some(Wdl::Draw)); // KvK needs no file
+    /// ```
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        self.search(board, false).map(|(wdl, _zeroing_is_best)| wdl)
+    }
+
+    /// A function to look up the distance to zeroing (DTZ) of a position: how many half-moves it takes, with best
+    /// play, until the winning side captures, moves a pawn or mates.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// board: &Board, // The position, by the rules of standard chess.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns Some(i32): positive if the side to move wins, negative if it loses, and 0 for draws.
+    /// A position that is already mated gives -1. Some tables store the distance in moves, so the value can be one
+    /// half-move too high. Returns None when probe_wdl() does, or the .rtbz file is missing or broken.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// # use alviny_task_3::tablebase::Tablebase;
+    /// let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/syzygy")).unwrap();
+    /// let example_game = Game::new_from_fen("3k4/8/3K4/8/8/8/8/7Q w - - 0 1".to_string());
+    /// assert_eq!(tablebase.probe_dtz(&example_game.board), Some(1)); // Qh8#
+    /// ```
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        self.dtz(board)
+    }
+
+    fn can_probe(&self, board: &Board) -> bool {
+        board.castling_rights.is_empty()
+            && board.pieces().count() <= self.max_pieces
+            && board.pieces_of('w').filter(|(_square, piece)| piece.kind == PieceKind::King).count() == 1
+            && board.pieces_of('b').filter(|(_square, piece)| piece.kind == PieceKind::King).count() == 1
+    } // Tables only have positions without castling rights, with one king per side
+
+    fn search(&self, board: &Board, check_zeroing_moves: bool) -> Option<(Wdl, bool)> {
+        let legal_moves = StandardRules.legal_moves(board);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for legal_move in &legal_moves {
+            if !(is_capture(board, legal_move) || (check_zeroing_moves && is_pawn_move(board, legal_move))) {
+                continue;
+            }
+            searched += 1;
+            let (value, _zeroing_is_best) = self.search(&board_after(board, legal_move), false)?;
+            let value = value.opposite();
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+        let no_more_moves = searched > 0 && searched == legal_moves.len();
+        let value = if no_more_moves { best } else { self.wdl_table(board)? };
+        if best >= value {
+            return Some((best, best > Wdl::Draw || no_more_moves));
+        }
+        Some((value, false))
+    } // Returns the result and whether a capture (or pawn move) is the best move. Captures are searched instead of trusting
+    // the table, which may hold any value for positions where the best move captures, and has no en passant rights
+
+    fn dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, zeroing_is_best) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0); // The tables don't store draws
+        }
+        if zeroing_is_best {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let Some(dtz) = self.dtz_table(board, wdl)? {
+            let fifty_move_offset = if matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss) { 100 } else { 0 };
+            return Some((dtz + fifty_move_offset) * wdl.signum());
+        }
+        let mut min_dtz = i32::MAX;
+        for legal_move in StandardRules.legal_moves(board) {
+            let is_zeroing = is_capture(board, &legal_move) || is_pawn_move(board, &legal_move);
+            let next_board = board_after(board, &legal_move);
+            let mut dtz = if is_zeroing {
+                -dtz_before_zeroing(self.search(&next_board, false)?.0)
+            } else {
+                -self.dtz(&next_board)?
+            };
+            if dtz == 1 && is_mated(&next_board) {
+                min_dtz = 1;
+            }
+            if !is_zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        Some(if min_dtz == i32::MAX { -1 } else { min_dtz })
+    } // Tables only store one side to move, so the other side is found by looking one move ahead
+
+    fn wdl_table(&self, board: &Board) -> Option<Wdl> {
+        if board.pieces().count() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let (table, black_is_stronger) = self.table(board, TableKind::Wdl)?;
+        let (file, side_to_move, index) = table.locate(&board_pieces(board), board.active_player == 'b', black_is_stronger)?;
+        Wdl::from_value(table.pairs_for(file, side_to_move)?.decompress(&table.bytes, index)?)
+    } // Looks the position up in its .rtbw file
+
+    fn dtz_table(&self, board: &Board, wdl: Wdl) -> Option<Option<i32>> {
+        let (table, black_is_stronger) = self.table(board, TableKind::Dtz)?;
+        let (file, side_to_move, index) = table.locate(&board_pieces(board), board.active_player == 'b', black_is_stronger)?;
+        let pairs = table.pairs_for(file, side_to_move)?;
+        let stores_both_sides = table.material.is_symmetric() && !table.material.has_pawns();
+        if (pairs.flags & FLAG_SIDE_TO_MOVE) as usize != side_to_move && !stores_both_sides {
+            return Some(None);
+        }
+        let value = pairs.decompress(&table.bytes, index)?;
+        table.dtz_plies(file, value, wdl).map(Some)
+    } // Looks the position up in its .rtbz file. Returns Some(None) if the file only has the other side to move
+
+    fn table(&self, board: &Board, kind: TableKind) -> Option<(&Table, bool)> {
+        let material = Material::of_board(board);
+        let (files, black_is_stronger) = match self.tables.get(&material.name()) {
+            Some(files) => (files, false),
+            None => (self.tables.get(&material.swapped().name())?, true),
+        };
+        let lazy_table = match kind {
+            TableKind::Wdl => files.wdl.as_ref()?,
+            TableKind::Dtz => files.dtz.as_ref()?,
+        };
+        let table = lazy_table.table.get_or_init(|| {
+            let material = if black_is_stronger { material.swapped() } else { material };
+            Table::read(fs::read(&lazy_table.path).ok()?, material, kind)
+        });
+        table.as_ref().map(|table| (table, black_is_stronger))
+    } // Finds (and reads, the first time) the table of the position's material. Files are named with the stronger side
+    // first, so true is returned if the position has to be looked up with the colors swapped
+}
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = self.tables.keys().collect::<Vec<&String>>();
+        names.sort();
+        f.debug_struct("Tablebase").field("tables", &names).field("max_pieces", &self.max_pieces).finish()
+    }
+}
+
+impl Game {
+    /// A function to end the game with its tablebase result, e.g. in engine matches, where playing out a decided ending
+    /// only costs time. Only standard chess games are adjudicated.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// tablebase: &Tablebase, // The tables to look the position up in.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if the game was ended, with WhiteWinsByAdjudication, BlackWinsByAdjudication or
+    /// DrawByAdjudication. Cursed wins and blessed losses are draws. Wins are only adjudicated if the DTZ is found and the
+    /// fifty-move rule can't interrupt them, so a game can stay in progress even though the position is in the tables.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, GameStatus};
+    /// # use alviny_task_3::tablebase::Tablebase;
+    /// let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/syzygy")).unwrap();
+    /// let mut example_game = Game::new_from_fen("8/8/8/3k4/8/8/8/4KQ2 w - - 0 1".to_string());
+    /// assert!(example_game.adjudicate_with_tablebase(&tablebase));
+    /// assert_eq!(example_game.game_status, GameStatus::WhiteWinsByAdjudication);
+    /// ```
+    pub fn adjudicate_with_tablebase(&mut self, tablebase: &Tablebase) -> bool {
+        if self.game_status.is_game_over() || self.variant != Variant::Standard {
+            return false;
+        }
+        let Some(wdl) = tablebase.probe_wdl(&self.board) else {
+            return false;
+        };
+        let side_to_move = if self.board.active_player == 'w' { Color::White } else { Color::Black };
+        let winner = match wdl {
+            Wdl::Win | Wdl::Loss => {
+                let Some(dtz) = tablebase.probe_dtz(&self.board) else {
+                    return false;
+                };
+                if dtz.unsigned_abs() + self.board.halfmove_counter > 100 {
+                    return false; // The game might still end in a fifty-move draw
+                }
+                Some(if wdl == Wdl::Win { side_to_move } else { side_to_move.opposite() })
+            }
+            Wdl::BlessedLoss | Wdl::Draw | Wdl::CursedWin => None,
+        };
+        self.game_status = match winner {
+            Some(Color::White) => GameStatus::WhiteWinsByAdjudication,
+            Some(Color::Black) => GameStatus::BlackWinsByAdjudication,
+            None => GameStatus::DrawByAdjudication,
+        };
+        true
+    }
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+const FLAG_SIDE_TO_MOVE: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+#[derive(Debug, Default)]
+struct TableFiles {
+    wdl: Option<LazyTable>,
+    dtz: Option<LazyTable>,
+}
+
+#[derive(Debug)]
+struct LazyTable {
+    path: PathBuf,
+    table: OnceLock<Option<Table>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Material {
+    counts: [[usize; 6]; 2],
+} // How many of each piece (pawn, knight, bishop, rook, queen, king) the two sides have. The first side is White in the
+// file name, which is the stronger side
+
+impl Material {
+    fn from_name(name: &str) -> Option<Material> {
+        let (first, second) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+        for (side, letters) in [first, second].into_iter().enumerate() {
+            for letter in letters.chars() {
+                counts[side]["PNBRQK".find(letter)?] += 1;
+            }
+        }
+        let material = Material { counts };
+        (counts[0][5] == 1 && counts[1][5] == 1 && material.piece_count() <= 7).then_some(material)
+    } // Reads a name such as "KRPvKR". Syzygy tables go up to 7 pieces
+
+    fn of_board(board: &Board) -> Material {
+        let mut counts = [[0; 6]; 2];
+        for (_square, piece) in board.pieces() {
+            counts[(piece.color == 'b') as usize][(piece_code(piece) & 7) as usize - 1] += 1;
+        }
+        Material { counts }
+    }
+
+    fn name(&self) -> String {
+        let side_name = |counts: &[usize; 6]| {
+            [(5, 'K'), (4, 'Q'), (3, 'R'), (2, 'B'), (1, 'N'), (0, 'P')]
+                .iter()
+                .map(|(kind, letter)| letter.to_string().repeat(counts[*kind]))
+                .collect::<String>()
+        };
+        format!("{}v{}", side_name(&self.counts[0]), side_name(&self.counts[1]))
+    }
+
+    fn swapped(&self) -> Material {
+        Material { counts: [self.counts[1], self.counts[0]] }
+    }
+
+    fn piece_count(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    fn is_symmetric(&self) -> bool {
+        self.counts[0] == self.counts[1]
+    }
+
+    fn has_pawns(&self) -> bool {
+        self.counts[0][0] + self.counts[1][0] > 0
+    }
+
+    fn pawn_counts(&self) -> [usize; 2] {
+        let (white_pawns, black_pawns) = (self.counts[0][0], self.counts[1][0]);
+        if black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns) {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        }
+    } // The pawns of the leading side (the one with fewer pawns, but some) and of the other side
+
+    fn has_unique_pieces(&self) -> bool {
+        self.counts.iter().any(|side| side[..5].contains(&1))
+    } // Whether a side has exactly one of some piece other than the king
+}
+
+struct Encoding {
+    binomial: [[u64; 64]; 6],
+    map_a1d1d4: [usize; 64],
+    map_b1h1h7: [usize; 64],
+    map_kk: [[u64; 64]; 10],
+    map_pawns: [usize; 64],
+    lead_pawn_index: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+} // The tables that turn a position into its index in a table file
+
+impl Encoding {
+    fn new() -> Encoding {
+        let mut binomial = [[0; 64]; 6];
+        binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                binomial[k][n] = if k > 0 { binomial[k - 1][n - 1] } else { 0 } + if k < n { binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut map_b1h1h7 = [0; 64];
+        for (code, square) in (0..64).filter(|square| diagonal_offset(*square) < 0).enumerate() {
+            map_b1h1h7[square] = code;
+        }
+        let mut map_a1d1d4 = [0; 64];
+        let in_triangle = |square: usize| square % 8 <= 3 && square <= 27;
+        let below = (0..64).filter(|square| in_triangle(*square) && diagonal_offset(*square) < 0);
+        let on_diagonal = (0..64).filter(|square| in_triangle(*square) && diagonal_offset(*square) == 0);
+        for (code, square) in below.chain(on_diagonal).enumerate() {
+            map_a1d1d4[square] = code;
+        }
+
+        let mut map_kk = [[0; 64]; 10];
+        let mut both_on_diagonal = vec![];
+        let mut code = 0;
+        for (first_index, first) in (0..10).map(|index| (index, (0..28).find(|square| in_triangle(*square) && map_a1d1d4[*square] == index).unwrap())) {
+            for (second, entry) in map_kk[first_index].iter_mut().enumerate() {
+                if square_distance(first, second) <= 1 || (diagonal_offset(first) == 0 && diagonal_offset(second) > 0) {
+                    continue; // Touching kings, or a position that is mirrored in the a1-h8 diagonal
+                }
+                if diagonal_offset(first) == 0 && diagonal_offset(second) == 0 {
+                    both_on_diagonal.push((first_index, second));
+                } else {
+                    *entry = code;
+                    code += 1;
+                }
+            }
+        }
+        for (first_index, second) in both_on_diagonal {
+            map_kk[first_index][second] = code;
+            code += 1;
+        }
+
+        let mut map_pawns = [0; 64];
+        let mut lead_pawn_index = [[0; 64]; 6];
+        let mut lead_pawns_size = [[0; 4]; 6];
+        let mut available_squares = 48;
+        for lead_pawns in 1..6 {
+            for (file, size) in lead_pawns_size[lead_pawns].iter_mut().enumerate() {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let square = rank * 8 + file;
+                    if lead_pawns == 1 {
+                        map_pawns[square] = available_squares - 1;
+                        map_pawns[square ^ 7] = available_squares - 2;
+                        available_squares -= 2;
+                    }
+                    lead_pawn_index[lead_pawns][square] = index;
+                    index += binomial[lead_pawns - 1][map_pawns[square]];
+                }
+                *size = index;
+            }
+        }
+        Encoding { binomial, map_a1d1d4, map_b1h1h7, map_kk, map_pawns, lead_pawn_index, lead_pawns_size }
+    }
+}
+
+fn encoding() -> &'static Encoding {
+    static ENCODING: OnceLock<Encoding> = OnceLock::new();
+    ENCODING.get_or_init(Encoding::new)
+}
+
+fn diagonal_offset(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+} // Negative below the a1-h8 diagonal, 0 on it and positive above it
+
+fn square_distance(first: usize, second: usize) -> usize {
+    (first % 8).abs_diff(second % 8).max((first / 8).abs_diff(second / 8))
+}
+
+#[derive(Clone, Debug, Default)]
+struct PairsData {
+    flags: u8,
+    pieces: Vec<u8>,
+    group_len: Vec<usize>,
+    group_index: Vec<u64>,
+    single_value: Option<u32>,
+    block_size: usize,
+    span: u64,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    block_lengths: usize,
+    block_lengths_size: usize,
+    blocks: usize,
+    blocks_num: usize,
+    min_sym_len: usize,
+    lowest_sym: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u32>,
+    btree: usize,
+    map_index: [usize; 4],
+} // One compressed table of values (for one side to move and lead pawn file). The fields holding positions in the file
+// are byte offsets. Values are Huffman coded symbols, and every symbol is a pair of symbols or a value (see decompress())
+
+impl PairsData {
+    fn set_groups(&mut self, material: &Material, order: [u8; 2], file: usize) -> Option<()> {
+        let encoding = encoding();
+        let mut first_len: i32 = if material.has_pawns() { 0 } else if material.has_unique_pieces() { 3 } else { 2 };
+        self.group_len = vec![1];
+        for piece in 1..self.pieces.len() {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[piece] == self.pieces[piece - 1] {
+                *self.group_len.last_mut()? += 1;
+            } else {
+                self.group_len.push(1);
+            }
+        }
+        let groups = self.group_len.len();
+        let has_other_pawns = material.has_pawns() && material.pawn_counts()[1] > 0;
+        let mut next = if has_other_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - self.group_len[0] - if has_other_pawns { *self.group_len.get(1)? } else { 0 };
+        let mut index: u64 = 1;
+        self.group_index = vec![0; groups + 1];
+        let mut position = 0;
+        while next < groups || position == order[0] || position == order[1] {
+            let combinations = if position == order[0] {
+                self.group_index[0] = index;
+                if material.has_pawns() {
+                    *encoding.lead_pawns_size.get(self.group_len[0])?.get(file)?
+                } else if material.has_unique_pieces() {
+                    31332
+                } else {
+                    462
+                }
+            } else if position == order[1] {
+                self.group_index[1] = index;
+                *encoding.binomial.get(self.group_len[1])?.get(48 - self.group_len[0])?
+            } else {
+                self.group_index[next] = index;
+                let combinations = *encoding.binomial.get(self.group_len[next])?.get(free_squares)?;
+                free_squares = free_squares.checked_sub(self.group_len[next])?;
+                next += 1;
+                combinations
+            };
+            index = index.checked_mul(combinations)?;
+            position += 1;
+        }
+        self.group_index[groups] = index;
+        Some(())
+    } // Splits the pieces into groups of pieces encoded together: the leading pieces (or pawns), then the other side's
+    // pawns, then each run of equal pieces. The file decides in which order the groups make up the index
+
+    fn set_sizes(&mut self, bytes: &[u8], offset: usize) -> Option<usize> {
+        self.flags = *bytes.get(offset)?;
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            self.single_value = Some(*bytes.get(offset + 1)? as u32);
+            return Some(offset + 2);
+        }
+        let table_size = *self.group_index.last()?;
+        self.block_size = 1usize.checked_shl(*bytes.get(offset + 1)? as u32)?;
+        self.span = 1u64.checked_shl(*bytes.get(offset + 2)? as u32)?;
+        self.sparse_index_size = table_size.div_ceil(self.span) as usize;
+        let padding = *bytes.get(offset + 3)? as usize;
+        self.blocks_num = read_u32_le(bytes, offset + 4)? as usize;
+        self.block_lengths_size = self.blocks_num + padding;
+        let max_sym_len = *bytes.get(offset + 8)? as usize;
+        self.min_sym_len = *bytes.get(offset + 9)? as usize;
+        self.lowest_sym = offset + 10;
+        let lengths = (max_sym_len + 1).checked_sub(self.min_sym_len).filter(|lengths| *lengths > 0)?;
+        self.base64 = vec![0; lengths];
+        for length in (0..lengths - 1).rev() {
+            let lowest = read_u16_le(bytes, self.lowest_sym + 2 * length)? as u64;
+            let next_lowest = read_u16_le(bytes, self.lowest_sym + 2 * (length + 1))? as u64;
+            self.base64[length] = self.base64[length + 1].wrapping_add(lowest).wrapping_sub(next_lowest) / 2;
+        }
+        for (length, base) in self.base64.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - length - self.min_sym_len) as u32).unwrap_or(0);
+        }
+        let symbols_offset = self.lowest_sym + 2 * lengths;
+        let symbols = read_u16_le(bytes, symbols_offset)? as usize;
+        self.btree = symbols_offset + 2;
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                self.symlen[symbol] = self.set_symlen(bytes, symbol, &mut visited)?;
+            }
+        }
+        Some(self.btree + 3 * symbols + (symbols & 1))
+    } // Reads the sizes and the Huffman code of the table, and returns the offset after them
+
+    fn set_symlen(&mut self, bytes: &[u8], symbol: usize, visited: &mut [bool]) -> Option<u32> {
+        visited[symbol] = true;
+        let (left, right) = self.children(bytes, symbol)?;
+        if right == 0xFFF {
+            return Some(0);
+        }
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symlen[child] = self.set_symlen(bytes, child, visited)?;
+            }
+        }
+        Some(self.symlen[left] + self.symlen[right] + 1)
+    } // Counts how many values a symbol stands for, minus one
+
+    fn children(&self, bytes: &[u8], symbol: usize) -> Option<(usize, usize)> {
+        let node = bytes.get(self.btree + 3 * symbol..self.btree + 3 * symbol + 3)?;
+        let left = (((node[1] & 0xF) as usize) << 8) | node[0] as usize;
+        let right = ((node[2] as usize) << 4) | (node[1] >> 4) as usize;
+        Some((left, right))
+    } // Returns the pair a symbol stands for. A symbol that stands for a value has 0xFFF on the right and the value on the left
+
+    fn decompress(&self, bytes: &[u8], index: u64) -> Option<u32> {
+        if let Some(value) = self.single_value {
+            return Some(value);
+        }
+        let sparse_entry = self.sparse_index + 6 * (index / self.span) as usize;
+        let mut block = read_u32_le(bytes, sparse_entry)? as usize;
+        let mut offset = read_u16_le(bytes, sparse_entry + 4)? as i64 + (index % self.span) as i64 - (self.span / 2) as i64;
+        let block_length = |block: usize| -> Option<i64> {
+            (block < self.block_lengths_size).then_some(())?;
+            Some(read_u16_le(bytes, self.block_lengths + 2 * block)? as i64)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        let mut position = self.blocks + block * self.block_size;
+        let mut buffer = ((read_u32_be(bytes, position) as u64) << 32) | read_u32_be(bytes, position + 4) as u64;
+        position += 8;
+        let mut buffer_bits = 64;
+        let mut symbol = loop {
+            let mut length = 0;
+            while buffer < self.base64[length] {
+                length += 1;
+                if length == self.base64.len() {
+                    return None;
+                }
+            }
+            let code = (buffer - self.base64[length]).checked_shr((64 - length - self.min_sym_len) as u32).unwrap_or(0);
+            let symbol = code as usize + read_u16_le(bytes, self.lowest_sym + 2 * length)? as usize;
+            let values = *self.symlen.get(symbol)? as i64 + 1;
+            if offset < values {
+                break symbol;
+            }
+            offset -= values;
+            let bits = length + self.min_sym_len;
+            buffer = buffer.checked_shl(bits as u32).unwrap_or(0);
+            buffer_bits -= bits as i32;
+            if buffer_bits <= 32 {
+                buffer_bits += 32;
+                buffer |= (read_u32_be(bytes, position) as u64) << (64 - buffer_bits);
+                position += 4;
+            }
+        };
+        while self.symlen[symbol] != 0 {
+            let (left, right) = self.children(bytes, symbol)?;
+            let left_values = *self.symlen.get(left)? as i64 + 1;
+            if offset < left_values {
+                symbol = left;
+            } else {
+                offset -= left_values;
+                symbol = right;
+            }
+        }
+        Some(self.children(bytes, symbol)?.0 as u32)
+    } // Finds the block with the value through the sparse index, then reads symbols until the one that covers the value,
+    // and splits it into its pair until the value is reached
+}
+
+#[derive(Debug)]
+struct Table {
+    bytes: Vec<u8>,
+    material: Material,
+    pairs: Vec<Vec<PairsData>>,
+    dtz_map: usize,
+} // A table file read into memory. pairs has one entry per lead pawn file (just one without pawns), each with one
+// entry per side to move (just one for DTZ files and symmetric material)
+
+impl Table {
+    fn read(bytes: Vec<u8>, material: Material, kind: TableKind) -> Option<Table> {
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+        if bytes.get(0..4)? != magic || (*bytes.get(4)? & 2 != 0) != material.has_pawns() {
+            return None;
+        }
+        let sides = if kind == TableKind::Wdl && !material.is_symmetric() { 2 } else { 1 };
+        let files = if material.has_pawns() { 4 } else { 1 };
+        let has_other_pawns = material.has_pawns() && material.pawn_counts()[1] > 0;
+        let mut pairs = vec![];
+        let mut offset = 5;
+        for file in 0..files {
+            let first = *bytes.get(offset)?;
+            let second = if has_other_pawns { *bytes.get(offset + 1)? } else { 0xFF };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            offset += 1 + has_other_pawns as usize;
+            let mut file_pairs = vec![PairsData::default(); sides];
+            for _ in 0..material.piece_count() {
+                let piece = *bytes.get(offset)?;
+                for (side, side_pairs) in file_pairs.iter_mut().enumerate() {
+                    side_pairs.pieces.push(if side == 1 { piece >> 4 } else { piece & 0xF });
+                }
+                offset += 1;
+            }
+            for (side, side_pairs) in file_pairs.iter_mut().enumerate() {
+                side_pairs.set_groups(&material, orders[side], file)?;
+            }
+            pairs.push(file_pairs);
+        }
+        offset += offset & 1;
+        for side_pairs in pairs.iter_mut().flatten() {
+            offset = side_pairs.set_sizes(&bytes, offset)?;
+        }
+        let dtz_map = offset;
+        if kind == TableKind::Dtz {
+            for file_pairs in &mut pairs {
+                let side_pairs = &mut file_pairs[0];
+                if side_pairs.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                if side_pairs.flags & FLAG_WIDE != 0 {
+                    offset += offset & 1;
+                    for map_index in &mut side_pairs.map_index {
+                        *map_index = (offset - dtz_map) / 2 + 1;
+                        offset += 2 * read_u16_le(&bytes, offset)? as usize + 2;
+                    }
+                } else {
+                    for map_index in &mut side_pairs.map_index {
+                        *map_index = offset - dtz_map + 1;
+                        offset += *bytes.get(offset)? as usize + 1;
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+        for side_pairs in pairs.iter_mut().flatten() {
+            side_pairs.sparse_index = offset;
+            offset += 6 * side_pairs.sparse_index_size;
+        }
+        for side_pairs in pairs.iter_mut().flatten() {
+            side_pairs.block_lengths = offset;
+            offset += 2 * side_pairs.block_lengths_size;
+        }
+        for side_pairs in pairs.iter_mut().flatten() {
+            offset = offset.div_ceil(64) * 64;
+            side_pairs.blocks = offset;
+            offset += side_pairs.blocks_num * side_pairs.block_size;
+        }
+        (offset <= bytes.len()).then_some(Table { bytes, material, pairs, dtz_map })
+    } // The header has the piece order and groups, then come the Huffman codes, the DTZ value maps, the sparse indexes,
+    // the block lengths and the blocks, for every file and side in turn
+
+    fn pairs_for(&self, file: usize, side_to_move: usize) -> Option<&PairsData> {
+        let file_pairs = self.pairs.get(file)?;
+        file_pairs.get(if file_pairs.len() == 2 { side_to_move } else { 0 })
+    }
+
+    fn locate(&self, pieces: &[(usize, u8)], black_to_move: bool, black_is_stronger: bool) -> Option<(usize, usize, u64)> {
+        let encoding = encoding();
+        let flip = (self.material.is_symmetric() && black_to_move) || black_is_stronger;
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let side_to_move = (flip ^ black_to_move) as usize;
+
+        let mut squares = vec![];
+        let mut codes = vec![];
+        let mut file = 0;
+        let lead_pawn = self.material.has_pawns().then(|| self.pairs[0][0].pieces[0] ^ flip_color);
+        if let Some(lead_pawn) = lead_pawn {
+            squares = pieces.iter().filter(|(_square, code)| *code == lead_pawn).map(|(square, _code)| square ^ flip_squares).collect();
+            let leading = (0..squares.len()).max_by_key(|pawn| encoding.map_pawns[squares[*pawn]])?;
+            squares.swap(0, leading);
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+            codes = vec![lead_pawn ^ flip_color; squares.len()];
+        }
+        let lead_pawns = squares.len();
+        for (square, code) in pieces.iter().filter(|(_square, code)| Some(*code) != lead_pawn) {
+            squares.push(square ^ flip_squares);
+            codes.push(code ^ flip_color);
+        }
+        let pairs = self.pairs_for(file, side_to_move)?;
+        if codes.len() != pairs.pieces.len() {
+            return None;
+        }
+        for piece in lead_pawns..squares.len().saturating_sub(1) {
+            if let Some(other) = (piece + 1..squares.len()).find(|other| codes[*other] == pairs.pieces[piece]) {
+                codes.swap(piece, other);
+                squares.swap(piece, other);
+            }
+        } // Puts the pieces in the order of the table
+
+        if squares[0] % 8 > 3 {
+            squares.iter_mut().for_each(|square| *square ^= 7);
+        }
+        let mut index = if lead_pawns > 0 {
+            squares[1..lead_pawns].sort_by_key(|square| encoding.map_pawns[*square]);
+            let others = squares[1..lead_pawns].iter().enumerate();
+            encoding.lead_pawn_index[lead_pawns][squares[0]] + others.map(|(pawn, square)| encoding.binomial[pawn + 1][encoding.map_pawns[*square]]).sum::<u64>()
+        } else {
+            if squares[0] / 8 > 3 {
+                squares.iter_mut().for_each(|square| *square ^= 56);
+            }
+            if let Some(first_off) = squares[..pairs.group_len[0]].iter().position(|square| diagonal_offset(*square) != 0)
+                && diagonal_offset(squares[first_off]) > 0
+            {
+                squares.iter_mut().for_each(|square| *square = ((*square >> 3) | (*square << 3)) & 63);
+            }
+            leading_group_index(&squares, self.material.has_unique_pieces())
+        };
+
+        index = index.checked_mul(pairs.group_index[0])?;
+        let mut group_start = pairs.group_len[0];
+        let mut other_pawns = self.material.has_pawns() && self.material.pawn_counts()[1] > 0;
+        for group in 1..pairs.group_len.len() {
+            let group_end = group_start + pairs.group_len[group];
+            squares[group_start..group_end].sort();
+            let mut combination = 0;
+            for (position, square) in squares[group_start..group_end].iter().enumerate() {
+                let lower = squares[..group_start].iter().filter(|earlier| *earlier < square).count();
+                combination += encoding.binomial[position + 1][square - lower - if other_pawns { 8 } else { 0 }];
+            }
+            other_pawns = false;
+            index += combination * pairs.group_index[group];
+            group_start = group_end;
+        }
+        Some((file, side_to_move, index))
+    } // Returns the lead pawn file, the side to move and the index of a position, given as (square, piece code) pairs.
+    // The position is mirrored so that the stronger side is White and the leading piece is in the a1-d1-d4 triangle
+    // (or the leading pawn on files a-d), then every group of pieces is encoded as a combination of squares
+
+    fn dtz_plies(&self, file: usize, value: u32, wdl: Wdl) -> Option<i32> {
+        let pairs = self.pairs_for(file, 0)?;
+        let mut value = value as usize;
+        if pairs.flags & FLAG_MAPPED != 0 {
+            let map_index = pairs.map_index[[1, 3, 0, 2, 0][wdl as usize]];
+            value = if pairs.flags & FLAG_WIDE != 0 {
+                read_u16_le(&self.bytes, self.dtz_map + 2 * (map_index + value))? as usize
+            } else {
+                *self.bytes.get(self.dtz_map + map_index + value)? as usize
+            };
+        }
+        let in_moves = match wdl {
+            Wdl::Win => pairs.flags & FLAG_WIN_PLIES == 0,
+            Wdl::Loss => pairs.flags & FLAG_LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        let plies = if in_moves { 2 * value } else { value };
+        Some(plies as i32 + 1)
+    } // Turns a stored DTZ value into half-moves, through the table's value map if it has one
+}
+
+fn leading_group_index(squares: &[usize], has_unique_pieces: bool) -> u64 {
+    let encoding = encoding();
+    let rank = |square: usize| (square / 8) as u64;
+    if !has_unique_pieces {
+        return encoding.map_kk[encoding.map_a1d1d4[squares[0]]][squares[1]];
+    }
+    let (first, second, third) = (squares[0], squares[1], squares[2]);
+    let adjust_second = (second > first) as u64;
+    let adjust_third = (third > first) as u64 + (third > second) as u64;
+    if diagonal_offset(first) != 0 {
+        (encoding.map_a1d1d4[first] as u64 * 63 + second as u64 - adjust_second) * 62 + third as u64 - adjust_third
+    } else if diagonal_offset(second) != 0 {
+        (6 * 63 + rank(first) * 28 + encoding.map_b1h1h7[second] as u64) * 62 + third as u64 - adjust_third
+    } else if diagonal_offset(third) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + rank(first) * 7 * 28 + (rank(second) - adjust_second) * 28 + encoding.map_b1h1h7[third] as u64
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(first) * 7 * 6 + (rank(second) - adjust_second) * 6 + rank(third) - adjust_third
+    }
+} // Encodes the two kings (462 ways), or the first three pieces if a side has a piece it has only one of (31332 ways).
+// The first piece is below the a1-h8 diagonal or on it, and the later pieces are counted by whether they are too
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> u32 {
+    (0..4).fold(0, |word, byte| (word << 8) | *bytes.get(offset + byte).unwrap_or(&0) as u32)
+} // Huffman codes are read in big-endian words. Past the end of the file, the bits are 0
+
+fn piece_code(piece: Piece) -> u8 {
+    let kind = match piece.kind {
+        PieceKind::Pawn => 1,
+        PieceKind::Knight => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Rook => 4,
+        PieceKind::Queen => 5,
+        PieceKind::King => 6,
+    };
+    if piece.color == 'w' { kind } else { kind + 8 }
+} // The piece numbers used in table files
+
+fn board_pieces(board: &Board) -> Vec<(usize, u8)> {
+    let mut pieces = board.pieces().map(|(square, piece)| (square.index() as usize, piece_code(piece))).collect::<Vec<(usize, u8)>>();
+    pieces.sort();
+    pieces
+} // The pieces as (square, piece code) pairs, from a1 to h8
+
+fn moved_piece(board: &Board, chess_move: &Move) -> char {
+    Square::from_algebraic(&chess_move.source).map_or('*', |square| board.piece_on(square))
+}
+
+fn is_pawn_move(board: &Board, chess_move: &Move) -> bool {
+    moved_piece(board, chess_move).eq_ignore_ascii_case(&'p')
+}
+
+fn is_capture(board: &Board, chess_move: &Move) -> bool {
+    let target = Square::from_algebraic(&chess_move.target).map_or('*', |square| board.piece_on(square));
+    target != '*' || (is_pawn_move(board, chess_move) && chess_move.source.as_bytes()[0] != chess_move.target.as_bytes()[0])
+} // A pawn that changes file without landing on a piece captures en passant
+
+fn board_after(board: &Board, chess_move: &Move) -> Board {
+    let mut next_board = board.clone();
+    StandardRules.play_move(&mut next_board, chess_move);
+    next_board
+}
+
+fn is_mated(board: &Board) -> bool {
+    let side_to_move = if board.active_player == 'w' { Color::White } else { Color::Black };
+    StandardRules.is_in_check(board, side_to_move) && StandardRules.legal_moves(board).is_empty()
+}
+
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Loss => -1,
+        Wdl::BlessedLoss => -101,
+        Wdl::Draw => 0,
+        Wdl::CursedWin => 101,
+        Wdl::Win => 1,
+    }
+} // The DTZ of a position whose best move captures or moves a pawn
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    // The shipped KQvK tables are generated by the solver and writer below. Run the tests with WRITE_SYZYGY_FIXTURES=1
+    // to write them again.
+    const KQVK_PIECES: [u8; 3] = [6, 5, 14]; // The order the tables store the pieces in: K, Q and k
+    const BLOCK_SIZE_BITS: u8 = 10;
+    const SPAN_BITS: u8 = 10;
+
+    fn fixture_directory() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join("syzygy")
+    }
+
+    fn tablebase() -> Tablebase {
+        Tablebase::open(fixture_directory()).unwrap()
+    }
+
+    fn board(fen: &str) -> Board {
+        Game::new_from_fen(fen.to_string()).board
+    }
+
+    fn king_steps(square: usize) -> impl Iterator<Item = usize> {
+        (0..64).filter(move |other| square_distance(square, *other) == 1)
+    }
+
+    fn queen_sees(queen: usize, target: usize, blocker: usize) -> bool {
+        let (file_step, rank_step) = ((target % 8) as i32 - (queen % 8) as i32, (target / 8) as i32 - (queen / 8) as i32);
+        if queen == target || !(file_step == 0 || rank_step == 0 || file_step.abs() == rank_step.abs()) {
+            return false;
+        }
+        let step = file_step.signum() + 8 * rank_step.signum();
+        let mut square = queen as i32 + step;
+        while square != target as i32 {
+            if square == blocker as i32 {
+                return false;
+            }
+            square += step;
+        }
+        true
+    } // Whether a queen attacks a square, with one piece that might stand in the way
+
+    fn queen_squares(queen: usize, blockers: [usize; 2]) -> Vec<usize> {
+        (0..64).filter(|square| !blockers.contains(square) && blockers.iter().all(|blocker| queen_sees(queen, *square, *blocker))).collect()
+    }
+
+    fn kqvk_index(king: usize, queen: usize, enemy_king: usize) -> usize {
+        (king * 64 + queen) * 64 + enemy_king
+    }
+
+    fn kqvk_is_legal(king: usize, queen: usize, enemy_king: usize, black_to_move: bool) -> bool {
+        king != queen && queen != enemy_king && square_distance(king, enemy_king) > 1 && (black_to_move || !queen_sees(queen, enemy_king, king))
+    }
+
+    fn black_moves(king: usize, queen: usize, enemy_king: usize) -> (Vec<usize>, bool) {
+        let mut moves = vec![];
+        let mut takes_queen = false;
+        for square in king_steps(enemy_king).filter(|square| square_distance(*square, king) > 1) {
+            if square == queen {
+                takes_queen = takes_queen || square_distance(queen, king) > 1;
+            } else if !queen_sees(queen, square, king) {
+                moves.push(kqvk_index(king, queen, square));
+            }
+        }
+        (moves, takes_queen)
+    } // The White to move positions Black can reach, and whether Black can take the queen (a draw)
+
+    fn solve_kqvk() -> (Vec<i16>, Vec<i16>) {
+        let mut white_to_move = vec![-1; 64 * 64 * 64];
+        let mut black_to_move = vec![-1; 64 * 64 * 64];
+        let mut lost = vec![];
+        for (king, queen, enemy_king) in (0..64 * 64 * 64).map(|index| (index / 4096, index / 64 % 64, index % 64)) {
+            let (moves, takes_queen) = black_moves(king, queen, enemy_king);
+            if kqvk_is_legal(king, queen, enemy_king, true) && moves.is_empty() && !takes_queen && queen_sees(queen, enemy_king, king) {
+                black_to_move[kqvk_index(king, queen, enemy_king)] = 0;
+                lost.push((king, queen, enemy_king));
+            }
+        }
+        let mut plies = 0;
+        while !lost.is_empty() {
+            let mut won = vec![];
+            for (king, queen, enemy_king) in lost.drain(..) {
+                let king_moves = king_steps(king).filter(|square| *square != queen).map(|square| (square, queen));
+                let queen_moves = queen_squares(queen, [king, enemy_king]).into_iter().map(|square| (king, square));
+                for (king, queen) in king_moves.chain(queen_moves) {
+                    let index = kqvk_index(king, queen, enemy_king);
+                    if kqvk_is_legal(king, queen, enemy_king, false) && white_to_move[index] < 0 {
+                        white_to_move[index] = plies + 1;
+                        won.push((king, queen, enemy_king));
+                    }
+                }
+            } // White moves into a lost position
+            for (king, queen, enemy_king) in won {
+                for square in king_steps(enemy_king).filter(|square| *square != king && *square != queen) {
+                    let index = kqvk_index(king, queen, square);
+                    let (moves, takes_queen) = black_moves(king, queen, square);
+                    let all_lose = !moves.is_empty() && !takes_queen && moves.iter().all(|next| white_to_move[*next] >= 0);
+                    if kqvk_is_legal(king, queen, square, true) && black_to_move[index] < 0 && all_lose {
+                        black_to_move[index] = plies + 2;
+                        lost.push((king, queen, square));
+                    }
+                }
+            } // Black moves from a position where every move loses
+            plies += 2;
+        }
+        (white_to_move, black_to_move)
+    } // Half-moves to mate for every won White to move and lost Black to move position, or -1. White = KQ
+
+    fn compress(flags: u8, values: &[u32]) -> (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut counts = BTreeMap::<u32, usize>::new();
+        for value in values {
+            *counts.entry(*value).or_insert(0) += 1;
+        }
+        if counts.len() == 1 {
+            return (vec![flags | FLAG_SINGLE_VALUE, values[0] as u8], vec![], vec![], vec![]);
+        }
+        let mut code_lengths = counts.keys().map(|value| (*value, 0)).collect::<BTreeMap<u32, usize>>();
+        let mut nodes = counts.iter().map(|(value, count)| (*count, vec![*value])).collect::<Vec<(usize, Vec<u32>)>>();
+        while nodes.len() > 1 {
+            nodes.sort_by_key(|(count, _values)| *count);
+            let (first_count, mut first_values) = nodes.remove(0);
+            let (second_count, second_values) = nodes.remove(0);
+            first_values.extend(second_values);
+            first_values.iter().for_each(|value| *code_lengths.get_mut(value).unwrap() += 1);
+            nodes.push((first_count + second_count, first_values));
+        } // Huffman code lengths
+
+        let mut symbols = code_lengths.iter().map(|(value, length)| (*length, *value)).collect::<Vec<(usize, u32)>>();
+        symbols.sort_by_key(|(length, value)| (usize::MAX - length, *value)); // Longest codes get the lowest symbols
+        let (max_length, min_length) = (symbols[0].0, symbols[symbols.len() - 1].0);
+        let count_of = |length: usize| symbols.iter().filter(|(symbol_length, _value)| *symbol_length == length).count();
+        let mut lowest = vec![0; max_length - min_length + 1];
+        let mut base = vec![0; max_length - min_length + 1];
+        for length in (min_length..max_length).rev() {
+            lowest[length - min_length] = lowest[length + 1 - min_length] + count_of(length + 1);
+            base[length - min_length] = (base[length + 1 - min_length] + count_of(length + 1)) / 2;
+        }
+        let code_of = |value: u32| {
+            let symbol = symbols.iter().position(|(_length, symbol_value)| *symbol_value == value).unwrap();
+            let length = symbols[symbol].0;
+            (base[length - min_length] + symbol - lowest[length - min_length], length)
+        };
+        let codes = counts.keys().map(|value| (*value, code_of(*value))).collect::<BTreeMap<u32, (usize, usize)>>();
+
+        let block_bits = 8usize << BLOCK_SIZE_BITS;
+        let mut blocks: Vec<(usize, Vec<bool>)> = vec![(0, vec![])];
+        for value in values {
+            let (code, length) = codes[value];
+            let (block_values, bits) = blocks.last_mut().unwrap();
+            if bits.len() + length > block_bits || *block_values == 65536 {
+                blocks.push((0, vec![]));
+            }
+            let (block_values, bits) = blocks.last_mut().unwrap();
+            bits.extend((0..length).rev().map(|bit| code >> bit & 1 == 1));
+            *block_values += 1;
+        }
+        let mut block_bytes = vec![];
+        for (_block_values, bits) in &blocks {
+            let mut bytes = vec![0u8; 1 << BLOCK_SIZE_BITS];
+            for (bit, is_set) in bits.iter().enumerate() {
+                bytes[bit / 8] |= (*is_set as u8) << (7 - bit % 8);
+            }
+            block_bytes.extend(bytes);
+        }
+
+        let starts = blocks.iter().scan(0, |start, (block_values, _bits)| Some(std::mem::replace(start, *start + block_values)));
+        let starts = starts.collect::<Vec<usize>>();
+        let span = 1usize << SPAN_BITS;
+        let mut sparse_index = vec![];
+        for entry in 0..values.len().div_ceil(span) {
+            let middle = entry * span + span / 2;
+            let block = starts.iter().rposition(|start| *start <= middle.min(values.len() - 1)).unwrap();
+            sparse_index.extend((block as u32).to_le_bytes());
+            sparse_index.extend(((middle - starts[block]) as u16).to_le_bytes());
+        }
+        let block_lengths = blocks.iter().flat_map(|(block_values, _bits)| ((block_values - 1) as u16).to_le_bytes()).collect();
+
+        let mut header = vec![flags, BLOCK_SIZE_BITS, SPAN_BITS, 0];
+        header.extend((blocks.len() as u32).to_le_bytes());
+        header.extend([max_length as u8, min_length as u8]);
+        header.extend(lowest.iter().flat_map(|lowest| (*lowest as u16).to_le_bytes()));
+        header.extend((symbols.len() as u16).to_le_bytes());
+        for (_length, value) in &symbols {
+            header.extend([*value as u8, ((*value >> 8) as u8 & 0xF) | 0xF0, 0xFF]); // A value, with 0xFFF on the right
+        }
+        if symbols.len() % 2 == 1 {
+            header.push(0);
+        }
+        (header, sparse_index, block_lengths, block_bytes)
+    } // Compresses one side of a table with a plain Huffman code, without pairing symbols
+
+    fn write_table(magic: [u8; 4], sides: &[(u8, Vec<u32>)]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend([1, 0]); // Not symmetric, no pawns. The leading group comes first, for both sides
+        bytes.extend(KQVK_PIECES.iter().map(|piece| piece | piece << 4));
+        bytes.resize(bytes.len().div_ceil(2) * 2, 0);
+        let parts = sides.iter().map(|(flags, values)| compress(*flags, values)).collect::<Vec<_>>();
+        parts.iter().for_each(|(header, _sparse_index, _block_lengths, _blocks)| bytes.extend(header));
+        if magic == DTZ_MAGIC {
+            bytes.resize(bytes.len().div_ceil(2) * 2, 0); // No value maps
+        }
+        parts.iter().for_each(|(_header, sparse_index, _block_lengths, _blocks)| bytes.extend(sparse_index));
+        parts.iter().for_each(|(_header, _sparse_index, block_lengths, _blocks)| bytes.extend(block_lengths));
+        for (_header, _sparse_index, _block_lengths, blocks) in &parts {
+            bytes.resize(bytes.len().div_ceil(64) * 64, 0);
+            bytes.extend(blocks);
+        }
+        bytes
+    }
+
+    fn kqvk_tables() -> (Vec<u8>, Vec<u8>) {
+        let (white_to_move, black_to_move) = solve_kqvk();
+        let material = Material::from_name("KQvK").unwrap();
+        let mut pairs = PairsData { pieces: KQVK_PIECES.to_vec(), ..Default::default() };
+        pairs.set_groups(&material, [0, 0xF], 0).unwrap();
+        let table = Table { bytes: vec![], material, pairs: vec![vec![pairs.clone(), pairs]], dtz_map: 0 };
+
+        let mut values = [vec![None; 31332], vec![None; 31332], vec![None; 31332]]; // WDL White and Black to move, DTZ
+        for (king, queen, enemy_king) in (0..64 * 64 * 64).map(|index| (index / 4096, index / 64 % 64, index % 64)) {
+            let solved = [white_to_move[kqvk_index(king, queen, enemy_king)], black_to_move[kqvk_index(king, queen, enemy_king)]];
+            for (black_to_move, plies) in [false, true].into_iter().zip(solved) {
+                if !kqvk_is_legal(king, queen, enemy_king, black_to_move) {
+                    continue;
+                }
+                let (_file, _side, index) = table.locate(&[(king, 6), (queen, 5), (enemy_king, 14)], black_to_move, false).unwrap();
+                let wdl = match (plies >= 0, black_to_move) {
+                    (false, _) => 2,
+                    (true, false) => 4,
+                    (true, true) => 0,
+                };
+                let stored = if black_to_move { vec![(1, wdl)] } else { vec![(0, wdl), (2, plies.max(1) as u32 - 1)] };
+                for (table_values, value) in stored {
+                    let old_value = values[table_values][index as usize].replace(value);
+                    assert!(old_value.is_none_or(|old_value| old_value == value), "{} {} {}", king, queen, enemy_king);
+                }
+            }
+        } // Mirrored positions share an index, so they must have the same value
+        let [white_wdl, black_wdl, white_dtz] = values.map(|values| values.into_iter().map(|value| value.unwrap_or(0)).collect::<Vec<u32>>());
+        let wdl = write_table(WDL_MAGIC, &[(0, white_wdl), (0, black_wdl)]);
+        let dtz = write_table(DTZ_MAGIC, &[(FLAG_WIN_PLIES | FLAG_LOSS_PLIES, white_dtz)]);
+        (wdl, dtz)
+    } // The values of positions that can't happen are 0
+
+    #[test]
+    fn test_fixtures_come_from_the_solver() {
+        let (wdl, dtz) = kqvk_tables();
+        if std::env::var_os("WRITE_SYZYGY_FIXTURES").is_some() {
+            fs::create_dir_all(fixture_directory()).unwrap();
+            fs::write(fixture_directory().join("KQvK.rtbw"), &wdl).unwrap();
+            fs::write(fixture_directory().join("KQvK.rtbz"), &dtz).unwrap();
+        }
+        assert!(fs::read(fixture_directory().join("KQvK.rtbw")).unwrap() == wdl);
+        assert!(fs::read(fixture_directory().join("KQvK.rtbz")).unwrap() == dtz);
+    }
+    #[test]
+    fn test_encoding_tables() {
+        let encoding = encoding();
+        assert_eq!(encoding.map_kk.iter().flatten().max(), Some(&461)); // 462 ways to place the kings
+        assert_eq!(encoding.map_a1d1d4[1], 0); // b1
+        assert_eq!(encoding.map_a1d1d4[27], 9); // d4
+        assert_eq!((encoding.map_pawns[8], encoding.map_pawns[15], encoding.map_pawns[52]), (47, 46, 0)); // a2, h2, e7
+        assert_eq!(encoding.lead_pawns_size[1], [6, 6, 6, 6]);
+        assert_eq!(encoding.binomial[2][5], 10);
+    }
+    #[test]
+    fn test_material_names() {
+        assert_eq!(Material::from_name("KPRvKR").unwrap().name(), "KRPvKR");
+        assert_eq!(Material::from_name("KQvK").unwrap().swapped().name(), "KvKQ");
+        assert!(Material::from_name("KQvK").unwrap().has_unique_pieces());
+        assert!(!Material::from_name("KRRvK").unwrap().has_unique_pieces());
+        assert_eq!(Material::from_name("KPvKPP").unwrap().pawn_counts(), [1, 2]);
+        assert_eq!(Material::from_name("KPPvKP").unwrap().pawn_counts(), [1, 2]); // Black leads, having fewer pawns
+        for name in ["KQK", "KvQ", "KXvK", "KQQQQQvKQ"] {
+            assert!(Material::from_name(name).is_none(), "{}", name);
+        }
+        assert_eq!(Material::of_board(&board("8/8/8/3k4/8/8/8/4KQ2 w - - 0 1")).name(), "KQvK");
+    }
+    #[test]
+    fn test_open() {
+        let tablebase = tablebase();
+        assert_eq!(tablebase.max_pieces(), 3);
+        assert_eq!(format!("{:?}", tablebase), "Tablebase { tables: [\"KQvK\"], max_pieces: 3 }");
+        assert!(Tablebase::open(fixture_directory().join("missing")).is_err());
+    }
+    #[test]
+    fn test_probe_wdl() {
+        let tablebase = tablebase();
+        let cases = [
+            ("8/8/8/3k4/8/8/8/4KQ2 w - - 0 1", Some(Wdl::Win)),
+            ("8/8/8/3k4/8/8/8/4KQ2 b - - 0 1", Some(Wdl::Loss)),
+            ("3k4/3Q4/3K4/8/8/8/8/8 b - - 0 1", Some(Wdl::Loss)), // Mated
+            ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Some(Wdl::Draw)), // Stalemated
+            ("8/8/8/8/8/3k4/3Q4/7K b - - 0 1", Some(Wdl::Draw)), // Black takes the queen
+            ("8/8/8/8/8/3k4/3Q4/3K4 b - - 0 1", Some(Wdl::Loss)), // The queen is protected
+            ("4kq2/8/8/8/3K4/8/8/8 w - - 0 1", Some(Wdl::Loss)), // Black has the queen
+            ("4kq2/8/8/8/3K4/8/8/8 b - - 0 1", Some(Wdl::Win)),
+            ("8/8/8/3k4/8/8/8/4K3 b - - 0 1", Some(Wdl::Draw)), // KvK
+            ("8/8/8/3k4/8/8/8/4KR2 w - - 0 1", None), // No KRvK table
+            ("8/8/8/3k4/8/8/8/3qKQ2 w - - 0 1", None), // More pieces than any table
+        ];
+        for (fen, wdl) in cases {
+            assert_eq!(tablebase.probe_wdl(&board(fen)), wdl, "{}", fen);
+        }
+    }
+    #[test]
+    fn test_probes_agree_with_the_moves() {
+        let tablebase = tablebase();
+        for (king, queen, enemy_king) in [(4, 5, 35), (0, 62, 36), (18, 10, 0), (62, 11, 60), (27, 45, 7), (33, 3, 58)] {
+            for active_player in ['w', 'b'] {
+                let mut board = board("8/8/8/8/8/8/8/8 w - - 0 1");
+                for (square, piece) in [(king, 'K'), (queen, 'Q'), (enemy_king, 'k')] {
+                    let coords = Square::from_index(square).unwrap().coords();
+                    board.board_state[coords[0] as usize][coords[1] as usize] = piece;
+                }
+                board.active_player = active_player;
+                let wdl = tablebase.probe_wdl(&board).unwrap();
+                let dtz = tablebase.probe_dtz(&board).unwrap();
+                let children = StandardRules
+                    .legal_moves(&board)
+                    .iter()
+                    .map(|legal_move| {
+                        let next_board = board_after(&board, legal_move);
+                        (tablebase.probe_wdl(&next_board).unwrap().opposite(), -tablebase.probe_dtz(&next_board).unwrap(), is_mated(&next_board))
+                    })
+                    .collect::<Vec<(Wdl, i32, bool)>>();
+                let no_moves = if is_mated(&board) { Wdl::Loss } else { Wdl::Draw };
+                assert_eq!(wdl, children.iter().map(|(wdl, _dtz, _mates)| *wdl).max().unwrap_or(no_moves), "{:?}", board);
+                let expected_dtz = match wdl {
+                    Wdl::Win if children.iter().any(|(_wdl, _dtz, mates)| *mates) => 1,
+                    Wdl::Win => children.iter().filter(|(wdl, _dtz, _mates)| *wdl == Wdl::Win).map(|(_wdl, dtz, _mates)| dtz + 1).min().unwrap(),
+                    Wdl::Loss if children.is_empty() => -1,
+                    Wdl::Loss => children.iter().map(|(_wdl, dtz, _mates)| dtz - 1).min().unwrap(),
+                    _ => 0,
+                };
+                assert_eq!(dtz, expected_dtz, "{:?}", board);
+            }
+        }
+    }
+    #[test]
+    fn test_probe_dtz() {
+        let tablebase = tablebase();
+        assert_eq!(tablebase.probe_dtz(&board("3k4/8/3K4/8/8/8/8/7Q w - - 0 1")), Some(1));
+        assert_eq!(tablebase.probe_dtz(&board("3k4/3Q4/3K4/8/8/8/8/8 b - - 0 1")), Some(-1));
+        assert_eq!(tablebase.probe_dtz(&board("8/8/8/8/8/3k4/3Q4/7K b - - 0 1")), Some(0));
+        assert_eq!(tablebase.probe_dtz(&board("8/8/8/3k4/8/8/8/4K3 b - - 0 1")), Some(0));
+        let longest = (0..64)
+            .filter_map(|square| {
+                let fen = format!("{}/8/8/8/8/8/8/KQ6 b - - 0 1", ["k7", "1k6", "2k5", "3k4", "4k3", "5k2", "6k1", "7k"][square % 8]);
+                tablebase.probe_dtz(&board(&fen))
+            })
+            .min();
+        assert!(longest.is_some_and(|dtz| dtz < -1 && dtz % 2 == 0)); // Black loses after an even number of half-moves
+    }
+    #[test]
+    fn test_adjudicate_with_tablebase() {
+        let tablebase = tablebase();
+        let cases = [
+            ("8/8/8/3k4/8/8/8/4KQ2 b - - 0 1", GameStatus::WhiteWinsByAdjudication),
+            ("4kq2/8/8/8/3K4/8/8/8 w - - 0 1", GameStatus::BlackWinsByAdjudication),
+            ("8/8/8/8/8/3k4/3Q4/7K b - - 0 1", GameStatus::DrawByAdjudication),
+            ("8/8/8/3k4/8/8/8/4K3 w - - 0 1", GameStatus::DrawByAdjudication),
+        ];
+        for (fen, status) in cases {
+            let mut game = Game::new_from_fen(fen.to_string());
+            assert!(game.adjudicate_with_tablebase(&tablebase), "{}", fen);
+            assert_eq!(game.game_status, status, "{}", fen);
+            assert!(!game.adjudicate_with_tablebase(&tablebase), "{}", fen); // Already over
+            assert!(game.legal_moves().is_empty(), "{}", fen);
+        }
+        let mut late = Game::new_from_fen("8/8/8/3k4/8/8/8/4KQ2 w - - 99 80".to_string());
+        assert!(!late.adjudicate_with_tablebase(&tablebase)); // The fifty-move rule comes first
+        assert_eq!(late.game_status, GameStatus::InProgress);
+        let mut too_many_pieces = Game::new();
+        assert!(!too_many_pieces.adjudicate_with_tablebase(&tablebase));
+    }
+}
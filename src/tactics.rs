@@ -0,0 +1,259 @@
+/*****************************
+*  TACTICS                   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Simple tactical patterns for a training mode, e.g. warning a beginner about a piece they are about to lose, or
+pointing out the fork they just played.
+
+Everything here is worked out from the attack tables and static exchange evaluation (see docs for
+Board::static_exchange), not from a search, so it is a quick hint and not a proof: a hanging piece can be saved by a
+counterattack, and a check elsewhere on the board can change everything.
+
+```
+# use alviny_task_3::{Color, Game, Square};
+let example_game = Game::from_uci_moves("e2e4 e7e5 d1h5 b8c6 h5e5").unwrap(); // The queen takes the e5 pawn, but...
+assert_eq!(example_game.hanging_pieces(Color::White), [Square::from_algebraic("e5").unwrap()]); // ...Nxe5 wins it
+```
+*/
+
+use crate::checks::scratch_board;
+use crate::{Board, Color, Game, Move, Piece, PieceKind, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A struct to represent a threat: a piece that attacks an enemy piece (see docs for Game::threats_created).
+///
+/// ## Attributes
+/// ```text
+/// attacker: Square, // The square of the attacking piece.
+/// piece: Piece, // The attacking piece.
+/// target: Square, // The square of the attacked piece.
+/// victim: Piece, // The attacked piece.
+/// ```
+pub struct Threat {
+    pub attacker: Square,
+    pub piece: Piece,
+    pub target: Square,
+    pub victim: Piece,
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn is_hanging(board: &Board, square: Square, color: char) -> bool {
+    let enemy = if color == 'w' { 'b' } else { 'w' };
+    let attackers = board.attackers_of(square, enemy);
+    if attackers.is_empty() {
+        return false;
+    }
+    let (defenders, pinned): (Vec<Square>, Vec<Square>) = board.attackers_of(square, color).into_iter().partition(|&defender| board.pinned_by(defender).is_none());
+    if defenders.is_empty() {
+        return true;
+    }
+    let mut unpinned_board = board.clone();
+    for defender in pinned {
+        unpinned_board.set_piece(&defender.coords(), '*');
+    } // A pinned defender can't recapture, so it plays no part in the exchange
+    let Some((first_attacker, _piece)) = unpinned_board.least_valuable_attacker(square, enemy) else {
+        return false;
+    };
+    attackers.len() > defenders.len() && unpinned_board.static_exchange(first_attacker, square) > 0
+} // Whether the piece on the square is attacked and either undefended, or outnumbered so that the exchange wins material
+
+fn fork_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 1,
+        PieceKind::Knight | PieceKind::Bishop => 3,
+        PieceKind::Rook => 5,
+        PieceKind::Queen => 9,
+        PieceKind::King => i32::MAX,
+    }
+} // The traditional piece values, so that a bishop forking two knights counts. The king is worth more than anything
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to list the pieces of one player that the opponent can win by capturing them.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// color: Color, // The player whose pieces are looked at. It doesn't have to be their turn.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the squares of the pieces, ordered from a1 to h8, that are attacked and either not defended
+    /// at all, or attacked by more pieces than defend them so that the exchange on the square wins material for the
+    /// opponent. Defenders that are pinned to their king don't count. A piece that is attacked by a cheaper piece but
+    /// defended, e.g. a knight attacked by a pawn, is threatened but not hanging. The king is never hanging.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Color, Game, Square};
+    /// let example_game = Game::new_from_fen("4k3/8/4p3/3N4/8/8/8/4K3 w - - 0 1".to_string());
+    /// assert_eq!(example_game.hanging_pieces(Color::White), [Square::from_algebraic("d5").unwrap()]);
+    /// assert!(example_game.hanging_pieces(Color::Black).is_empty()); // Nothing of Black's is attacked
+    /// ```
+    pub fn hanging_pieces(&self, color: Color) -> Vec<Square> {
+        let color = color.to_char();
+        Square::all()
+            .filter(|&square| {
+                let piece = self.board.piece_on(square);
+                piece != '*' && !piece.eq_ignore_ascii_case(&'k') && piece.is_ascii_uppercase() == (color == 'w')
+            })
+            .filter(|&square| is_hanging(&self.board, square, color))
+            .collect()
+    }
+
+    /// A function to list the enemy pieces that the moved piece attacks after a move, without making it.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move, assumed to be legal (see docs for gives_check).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a Threat for every enemy piece, king included, that the piece on the target square
+    /// attacks after the move, ordered from a1 to h8. If the opponent wins material by capturing the moved piece (see
+    /// docs for Board::static_exchange), the threats are idle and none are returned. Returns an empty list if
+    /// the source square has no piece of the side to move.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::new_from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1".to_string());
+    /// let threats = example_game.threats_created(&Move::from_uci("b5c7").unwrap());
+    /// assert_eq!(threats.iter().map(|threat| threat.target.to_string()).collect::<Vec<String>>(), ["a8", "e8"]);
+    /// ```
+    pub fn threats_created(&self, chess_move: &Move) -> Vec<Threat> {
+        let Some(next_board) = scratch_board(&self.board, chess_move) else {
+            return vec![];
+        };
+        let (mover, opponent) = if self.board.active_player == 'w' { ('w', 'b') } else { ('b', 'w') };
+        let Some(attacker) = Square::from_algebraic(&chess_move.target) else {
+            return vec![];
+        };
+        let Some(piece) = Piece::from_fen_char(next_board.piece_on(attacker)) else {
+            return vec![];
+        };
+        if let Some((capturer, _piece)) = next_board.least_valuable_attacker(attacker, opponent)
+            && next_board.static_exchange(capturer, attacker) > 0 {
+            return vec![];
+        } // The moved piece is lost before it can carry out any threat
+        let mut threats = next_board
+            .pieces_of(opponent)
+            .filter(|&(target, _victim)| next_board.attackers_of(target, mover).contains(&attacker))
+            .map(|(target, victim)| Threat { attacker, piece, target, victim })
+            .collect::<Vec<Threat>>();
+        threats.sort_by_key(|threat| threat.target.index());
+        threats
+    }
+
+    /// A function to check whether a move is a fork: the moved piece attacks two or more enemy pieces at once that
+    /// are worth at least as much as itself, or the king.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move, assumed to be legal (see docs for gives_check).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns true if at least two of threats_created() are against the king or a piece of equal or
+    /// higher value, using the traditional values where knights and bishops are worth the same. A piece that can
+    /// simply be captured doesn't fork anything.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let example_game = Game::new_from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1".to_string());
+    /// assert!(example_game.is_fork(&Move::from_uci("b5c7").unwrap())); // Check, and the rook falls
+    /// assert!(!example_game.is_fork(&Move::from_uci("b5d6").unwrap())); // Only check
+    /// ```
+    pub fn is_fork(&self, chess_move: &Move) -> bool {
+        let threats = self.threats_created(chess_move);
+        threats.iter().filter(|threat| fork_value(threat.victim.kind) >= fork_value(threat.piece.kind)).count() >= 2
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hanging(fen: &str, color: Color) -> Vec<String> {
+        Game::new_from_fen(fen.to_string()).hanging_pieces(color).iter().map(|square| square.to_algebraic()).collect()
+    } // The hanging pieces of one player, as algebraic squares
+
+    #[test]
+    fn test_knight_attacked_by_a_pawn() {
+        assert_eq!(hanging("4k3/8/4p3/3N4/8/8/8/4K3 w - - 0 1", Color::White), ["d5"]);
+        assert!(hanging("4k3/8/4p3/3N4/2P5/8/8/4K3 w - - 0 1", Color::White).is_empty()); // Defended by the c4 pawn
+        assert_eq!(hanging("2r1k3/8/4p3/3N4/2P5/8/8/2K5 w - - 0 1", Color::White), ["c4", "d5"]); // The c4 pawn is pinned, and hangs to the pinner itself
+        assert!(hanging("4k3/8/8/3N4/8/8/8/4K3 w - - 0 1", Color::White).is_empty()); // Not attacked at all
+    }
+    #[test]
+    fn test_outnumbered_defenders() {
+        // The d5 pawn is attacked by the queen and the rook and defended by the e6 pawn: taking it loses a rook for two pawns
+        assert!(hanging("4k3/8/4p3/3p3R/8/8/8/3QK3 b - - 0 1", Color::Black).is_empty());
+        // The b5 rook is attacked by a knight and a bishop and defended once: Nxb5 axb5 Bxb5 wins the exchange
+        assert_eq!(hanging("4k3/8/p7/1r6/8/2N5/4B3/4K3 b - - 0 1", Color::Black), ["b5"]);
+        // One attacker and one defender: not hanging, whatever the exchange is worth
+        assert!(hanging("4k3/8/p7/1r6/8/2N5/8/4K3 b - - 0 1", Color::Black).is_empty());
+    }
+    #[test]
+    fn test_kings_and_both_colors() {
+        assert!(hanging("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", Color::White).is_empty()); // The king is attacked, but kings don't hang
+        assert_eq!(hanging("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", Color::Black), ["d2"]); // The queen can be taken by the king
+        assert!(Game::new().hanging_pieces(Color::White).is_empty());
+        assert!(Game::new().hanging_pieces(Color::Black).is_empty());
+    }
+    #[test]
+    fn test_knight_fork() {
+        let game = Game::new_from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1".to_string());
+        let fork = Move::from_uci("b5c7").unwrap();
+        let threats = game.threats_created(&fork);
+        let targets = threats.iter().map(|threat| (threat.target.to_algebraic(), threat.victim.to_fen_char())).collect::<Vec<(String, char)>>();
+        assert_eq!(targets, [("a8".to_string(), 'r'), ("e8".to_string(), 'k')]);
+        assert!(threats.iter().all(|threat| threat.attacker.to_algebraic() == "c7" && threat.piece.to_fen_char() == 'N'));
+        assert!(game.is_fork(&fork));
+
+        let defended = Game::new_from_fen("r2qk3/8/8/1N6/8/8/8/4K3 w - - 0 1".to_string()); // The queen takes the knight for free
+        assert!(defended.threats_created(&fork).is_empty());
+        assert!(!defended.is_fork(&fork));
+        let supported = Game::new_from_fen("r2qk3/8/1P6/1N6/8/8/8/4K3 w - - 0 1".to_string()); // Qxc7 bxc7 loses the queen
+        assert!(supported.is_fork(&fork));
+    }
+    #[test]
+    fn test_forks_need_valuable_targets() {
+        let pawn_fork = Game::new_from_fen("4k3/8/8/1n1b4/8/1PP5/8/4K3 w - - 0 1".to_string());
+        let push = Move::from_uci("c3c4").unwrap();
+        assert_eq!(pawn_fork.threats_created(&push).len(), 2);
+        assert!(pawn_fork.is_fork(&push)); // A pawn forking a knight and a bishop
+        let knight_on_pawns = Game::new_from_fen("4k3/8/8/8/p3p3/8/8/1N2K3 w - - 0 1".to_string());
+        let jump = Move::from_uci("b1c3").unwrap();
+        assert_eq!(knight_on_pawns.threats_created(&jump).len(), 2);
+        assert!(!knight_on_pawns.is_fork(&jump)); // Pawns aren't worth a knight
+
+        let black = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K1n1 b - - 0 1".to_string());
+        let threats = black.threats_created(&Move::from_uci("g1f3").unwrap());
+        assert_eq!(threats.iter().map(|threat| threat.victim.kind).collect::<Vec<PieceKind>>(), [PieceKind::King]);
+        assert!(black.threats_created(&Move::from_uci("d4d5").unwrap()).is_empty()); // No piece on d4
+    }
+}
@@ -0,0 +1,214 @@
+/*****************************
+*  TRANSFORMS                *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Geometric transforms of positions, for building symmetric test suites and for evaluation code that wants to see
+every position from one side's point of view.
+
+A transform moves every piece to its image square. The parts of the position that aren't pieces follow as closely as
+the rules allow, which is documented on each function: castling only exists from the original king and rook squares,
+so it rarely survives a transform.
+*/
+
+use crate::{Board, CastlingRights, Square};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn transform_square(square: &str, transform: fn(Square) -> Square) -> String {
+    match Square::from_algebraic(square) {
+        Some(square) => transform(square).to_algebraic(),
+        None => "-".to_string(),
+    }
+} // Applies a square transform to a square in algebraic notation, keeping "-" as "-"
+
+fn mirror_square(square: Square) -> Square {
+    Square::from_index(square.index() ^ 7).unwrap()
+} // a-file <-> h-file
+
+fn flip_square(square: Square) -> Square {
+    Square::from_index(square.index() ^ 56).unwrap()
+} // rank 1 <-> rank 8
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to mirror the board left to right, so that the a-file and the h-file swap places.
+    ///
+    /// Castling rights are dropped, since the kings and rooks are no longer on the squares castling starts from.
+    /// The en passant square is mirrored along with the pawns, e.g. e3 becomes d3.
+    ///
+    /// ## Returns
+    /// This function returns the mirrored Board. Its legal moves are the mirror images of the original's legal moves,
+    /// apart from castling.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, parse_fen};
+    /// let board = parse_fen("4k3/8/8/8/4P3/8/8/R3K3 b Q e3 0 1");
+    /// assert_eq!(generate_fen(&board.mirrored_horizontal()), "3k4/8/8/8/3P4/8/8/3K3R b - d3 0 1");
+    /// ```
+    pub fn mirrored_horizontal(&self) -> Board {
+        let mut mirrored = self.clone();
+        for row in mirrored.board_state.iter_mut() {
+            row.reverse();
+        }
+        mirrored.castling_rights = CastlingRights::default();
+        mirrored.en_passant_square = transform_square(&self.en_passant_square, mirror_square);
+        mirrored
+    }
+
+    /// A function to flip the board upside down, so that rank 1 and rank 8 swap places. Pieces keep their colors.
+    ///
+    /// Castling rights are dropped, since the kings and rooks are no longer on the squares castling starts from.
+    /// The en passant square is cleared, since pawns still move in the direction of their own color
+    /// and the flipped pawn can't have just made a double push.
+    ///
+    /// ## Returns
+    /// This function returns the flipped Board. Pawns keep moving towards their own promotion rank, so only the
+    /// legal moves of the other pieces are the mirror images of the original's. See flip_colors() for a flip that
+    /// also swaps the sides.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, parse_fen};
+    /// let board = parse_fen("4k3/8/8/8/8/8/1N6/4K3 w - - 0 1");
+    /// assert_eq!(generate_fen(&board.flipped_vertical()), "4K3/1N6/8/8/8/8/8/4k3 w - - 0 1");
+    /// ```
+    pub fn flipped_vertical(&self) -> Board {
+        let mut flipped = self.clone();
+        flipped.board_state.reverse();
+        flipped.castling_rights = CastlingRights::default();
+        flipped.en_passant_square = "-".to_string();
+        flipped
+    }
+
+    /// A function to create the same position from the other side's point of view: the board is flipped upside down,
+    /// every piece changes color, and the other side is to move.
+    ///
+    /// Castling rights swap sides (K becomes k and so on) and the en passant square is flipped, e.g. e3 becomes e6,
+    /// so the flipped position is exactly as playable as the original. The move counters are kept.
+    ///
+    /// ## Returns
+    /// This function returns the flipped Board. Its legal moves are the flipped images of the original's legal moves,
+    /// one to one. It is a different position with a different Zobrist key, unless you flip it back.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{generate_fen, parse_fen};
+    /// let board = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 1");
+    /// assert_eq!(generate_fen(&board.flip_colors()), "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w Qk e6 0 1");
+    /// ```
+    pub fn flip_colors(&self) -> Board {
+        let mut flipped = self.clone();
+        flipped.board_state.reverse();
+        for piece in flipped.board_state.iter_mut().flatten() {
+            *piece = if piece.is_ascii_uppercase() { piece.to_ascii_lowercase() } else { piece.to_ascii_uppercase() };
+        }
+        flipped.active_player = if self.active_player == 'w' { 'b' } else { 'w' };
+        flipped.castling_rights = self.castling_rights.swapped();
+        flipped.en_passant_square = transform_square(&self.en_passant_square, flip_square);
+        flipped
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_fen, parse_fen, Game, Move};
+
+    fn transformed_moves(board: &Board, transform: fn(Square) -> Square) -> Vec<String> {
+        let mut moves = Game::from_board(board.clone())
+            .legal_moves()
+            .into_iter()
+            .map(|legal_move| {
+                Move {
+                    source: transform_square(&legal_move.source, transform),
+                    target: transform_square(&legal_move.target, transform),
+                    promotion: legal_move.promotion,
+                }
+                .to_uci()
+            })
+            .collect::<Vec<String>>();
+        moves.sort();
+        moves
+    }
+
+    fn sorted_moves(board: &Board) -> Vec<String> {
+        transformed_moves(board, |square| square)
+    }
+
+    #[test]
+    fn test_mirrored_moves_match() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w - - 4 4",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", // En passant
+            "8/P6k/8/8/8/8/6Kp/8 b - - 0 1", // Promotions
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        ];
+        for fen in fens {
+            let board = parse_fen(fen);
+            let mirrored = board.mirrored_horizontal();
+            assert_eq!(mirrored.validate(), Ok(()), "{}", fen);
+            assert_eq!(sorted_moves(&mirrored), transformed_moves(&board, mirror_square), "{}", fen);
+            assert_eq!(generate_fen(&mirrored.mirrored_horizontal()), fen);
+        }
+    }
+    #[test]
+    fn test_flipped_moves_match() {
+        let fens = [
+            "4k3/8/8/8/8/8/1N6/4K3 w - - 0 1",
+            "r3k3/8/3b4/8/4Q3/8/8/4K2R b - - 0 1",
+            "7k/8/8/8/8/8/1q6/K7 w - - 0 1", // Pawnless, since pawns don't flip their direction
+        ];
+        for fen in fens {
+            let board = parse_fen(fen);
+            let flipped = board.flipped_vertical();
+            assert_eq!(flipped.validate(), Ok(()), "{}", fen);
+            assert_eq!(sorted_moves(&flipped), transformed_moves(&board, flip_square), "{}", fen);
+        }
+    }
+    #[test]
+    fn test_flipped_colors_moves_match() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3", // En passant
+            "8/P6k/8/8/8/8/6Kp/8 b - - 0 1", // Promotions
+        ];
+        for fen in fens {
+            let board = parse_fen(fen);
+            let flipped = board.flip_colors();
+            assert_eq!(flipped.validate(), Ok(()), "{}", fen);
+            assert_eq!(sorted_moves(&flipped), transformed_moves(&board, flip_square), "{}", fen);
+            assert_ne!(flipped.zobrist_key(), board.zobrist_key(), "{}", fen);
+            assert_eq!(generate_fen(&flipped.flip_colors()), fen);
+        }
+    }
+    #[test]
+    fn test_transforms_drop_castling() {
+        let board = Game::new().board;
+        assert!(board.mirrored_horizontal().castling_rights.is_empty());
+        assert!(board.flipped_vertical().castling_rights.is_empty());
+        assert_eq!(generate_fen(&board.flipped_vertical().flipped_vertical()), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+    }
+}
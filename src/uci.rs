@@ -0,0 +1,916 @@
+/*****************************
+*  UCI ENGINE FRONT-END      *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A front-end for the Universal Chess Interface (UCI), so that this library can be used as an engine
+by GUIs such as Arena or Cute Chess. Read more about the protocol here:
+<https://backscattering.de/chess/uci/>
+
+All moves are read and written in long algebraic notation ("e2e4", "e7e8q"). Castling is the king's two-square move,
+unless the GUI turns on the UCI_Chess960 option, in which case castling is written as the king taking its own rook ("e1h1").
+Chess960 positions that don't start like standard chess castle by the king taking its own rook either way (see the
+castling module), and FENs with their castling rights are read in X-FEN or Shredder-FEN.
+
+The engine declares these options in its response to "uci", and the GUI may change them with "setoption":
+
+```text
+Hash, // The size of the transposition table in megabytes (see docs for TranspositionTable).
+MultiPV, // How many lines to report (see docs for SearchOptions).
+Move Overhead, // How many milliseconds to keep in reserve for the GUI and the connection on every "go movetime".
+UCI_Chess960, // Whether castling is written as the king taking its own rook.
+```
+
+run() starts a "go" on a worker thread (see docs for Search::spawn and UciEngine::start_command), so the engine keeps
+reading commands while it thinks: "stop" and "quit" cut the search short, and "isready" is answered at once. Commands that change the
+position or the options ("position", "ucinewgame", "setoption" and another "go") first let the search finish, or stop
+it if it is a "go infinite". The "bestmove" is written as soon as the search finishes, except after "go infinite", where
+it waits for "stop" as the protocol requires.
+*/
+
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::engine::{EngineSession, DEFAULT_MOVETIME, DEFAULT_MOVE_OVERHEAD, MAX_HASH_SIZE, MAX_MOVE_OVERHEAD, MAX_MULTIPV};
+use crate::search::{InfoSink, Score, SearchHandle, SearchInfo, SearchOptions, DEFAULT_HASH_SIZE, MAX_DEPTH};
+use crate::castling::CastlingMove;
+use crate::{generate_fen, get_board_coords, parse_fen, Game, Move, Square};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// A struct to represent the search limits of a `go` command.
+///
+/// ## Attributes
+/// ```text
+/// depth: Option<u32>, // The depth given by "go depth N", if any.
+/// movetime: Option<u64>, // The time in milliseconds given by "go movetime T", if any.
+/// infinite: bool, // Whether the command was "go infinite", i.e. search until "stop".
+/// ```
+pub struct GoCommand {
+    pub depth: Option<u32>,
+    pub movetime: Option<u64>,
+    pub infinite: bool,
+}
+
+/// A struct to represent a UCI engine session.
+///
+/// ## Attributes
+/// ```text
+/// session: EngineSession, // The position set by the last "position" command, and the options set by "setoption"
+///                         // (see docs for EngineSession).
+/// chess960: bool, // Set by the "UCI_Chess960" option. Whether castling is written as the king taking its own rook.
+/// ```
+pub struct UciEngine {
+    pub session: EngineSession,
+    pub chess960: bool,
+    search: Option<RunningSearch>,
+    waker: Option<Sender<Event>>,
+}
+impl UciEngine {
+    /// A function to create a new engine session at the starting position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::UciEngine;
+    /// let engine = UciEngine::new();
+    /// ```
+    pub fn new() -> UciEngine {
+        UciEngine { session: EngineSession::new(), chess960: false, search: None, waker: None }
+    }
+
+    /// A function to handle a single line of UCI input, waiting for the "bestmove" of a "go".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// command: &str, // One line sent by the GUI, e.g. "position startpos moves e2e4".
+    /// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns false if the command was "quit", true otherwise. Unknown commands are ignored, as the protocol requires.
+    /// A "go" returns once the search has written its "bestmove", except "go infinite", which returns at once and searches
+    /// until "stop". Use start_command() to keep handling commands while any search runs.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::UciEngine;
+    /// let mut engine = UciEngine::new();
+    /// let mut output = vec![];
+    /// engine.handle_command("isready", &mut output);
+    /// engine.handle_command("go depth 2", &mut output);
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert!(output.starts_with("readyok\n"));
+    /// assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    /// ```
+    pub fn handle_command(&mut self, command: &str, output: &mut impl Write) -> bool {
+        let keep_running = self.start_command(command, output);
+        if self.search.as_ref().is_some_and(|search| !search.infinite) {
+            self.wait(output);
+        }
+        keep_running
+    }
+
+    /// A function to handle a single line of UCI input like handle_command(), without waiting for a "go": the search
+    /// runs on a worker thread while more commands are handled (see module docs). This is what run() uses.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// command: &str, // One line sent by the GUI, e.g. "go movetime 1000".
+    /// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns false if the command was "quit", true otherwise. The output of a search is written by
+    /// poll(), wait(), or the command that ends it, such as "stop".
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::UciEngine;
+    /// let mut engine = UciEngine::new();
+    /// let mut output = vec![];
+    /// engine.start_command("go infinite", &mut output);
+    /// assert!(engine.is_searching());
+    /// engine.start_command("stop", &mut output);
+    /// assert!(String::from_utf8(output).unwrap().lines().last().unwrap().starts_with("bestmove "));
+    /// ```
+    pub fn start_command(&mut self, command: &str, output: &mut impl Write) -> bool {
+        let mut tokens = command.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                writeln!(output, "id name alviny-task-3").ok();
+                writeln!(output, "id author alviny").ok();
+                writeln!(output, "option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_SIZE, MAX_HASH_SIZE).ok();
+                writeln!(output, "option name MultiPV type spin default 1 min 1 max {}", MAX_MULTIPV).ok();
+                let (default_overhead, max_overhead) = (DEFAULT_MOVE_OVERHEAD.as_millis(), MAX_MOVE_OVERHEAD.as_millis());
+                writeln!(output, "option name Move Overhead type spin default {} min 0 max {}", default_overhead, max_overhead).ok();
+                writeln!(output, "option name UCI_Chess960 type check default false").ok();
+                writeln!(output, "uciok").ok();
+            }
+            Some("isready") => {
+                writeln!(output, "readyok").ok();
+            }
+            Some("ucinewgame") => {
+                self.end_search(output, false);
+                self.session.new_game();
+            }
+            Some("setoption") => {
+                self.end_search(output, false);
+                if let Some((name, value)) = parse_setoption(command) {
+                    self.set_option(&name, &value);
+                }
+            }
+            Some("position") => {
+                self.end_search(output, false);
+                if let Some(game) = parse_position(command) {
+                    self.session.game = game;
+                }
+            }
+            Some("go") => {
+                self.end_search(output, false);
+                let go_command = parse_go(command);
+                let (sender, infos) = mpsc::channel();
+                let forwarder = InfoForwarder { infos: sender, waker: self.waker.clone() };
+                let handle = self.session.spawn_think(self.options_for(&go_command), forwarder);
+                let (game, chess960, infinite) = (self.session.game.clone(), self.chess960, go_command.infinite);
+                self.search = Some(RunningSearch { handle, infos, game, chess960, infinite });
+            }
+            Some("stop") => {
+                self.end_search(output, true);
+            }
+            Some("quit") => {
+                self.end_search(output, true);
+                return false;
+            }
+            _ => {} // "debug", "ponderhit", etc. are not supported and silently ignored
+        }
+        output.flush().ok();
+        true
+    }
+
+    /// A function to write the output of the running search that has come in so far, including the "bestmove" if the
+    /// search has finished (see module docs). Does nothing if no search is running.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+    ///
+    /// ```
+    pub fn poll(&mut self, output: &mut impl Write) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        for info in search.infos.try_iter() {
+            writeln!(output, "{}", search.format(&info)).ok();
+        }
+        if search.handle.is_finished() && !search.infinite {
+            self.end_search(output, false);
+        }
+        output.flush().ok();
+    }
+
+    /// A function to wait for the running search to finish, writing its output as it comes in. A "go infinite" is
+    /// stopped instead, since it would never finish. Does nothing if no search is running.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::UciEngine;
+    /// let mut engine = UciEngine::new();
+    /// let mut output = vec![];
+    /// engine.start_command("go depth 2", &mut output);
+    /// engine.wait(&mut output);
+    /// assert!(String::from_utf8(output).unwrap().lines().last().unwrap().starts_with("bestmove "));
+    /// ```
+    pub fn wait(&mut self, output: &mut impl Write) {
+        self.end_search(output, false);
+    }
+
+    /// A function to check whether a search started by "go" hasn't written its "bestmove" yet.
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// A function to change one of the options the engine declares (see the module docs), like "setoption" does.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// name: &str, // The name of the option, in any case, e.g. "Hash" or "move overhead".
+    /// value: &str, // The new value, e.g. "128" or "true". Numbers are clamped to the declared range (see docs for EngineSession).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns false if the option is unknown or the value can't be read, in which case nothing changes.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::UciEngine;
+    /// let mut engine = UciEngine::new();
+    /// assert!(engine.set_option("MultiPV", "3"));
+    /// assert_eq!(engine.session.search_options.multipv, 3);
+    /// assert!(!engine.set_option("Threads", "4"));
+    /// ```
+    pub fn set_option(&mut self, name: &str, value: &str) -> bool {
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => self.session.set_hash_size(megabytes),
+                Err(_) => return false,
+            },
+            "multipv" => match value.parse::<usize>() {
+                Ok(lines) => self.session.set_multipv(lines),
+                Err(_) => return false,
+            },
+            "move overhead" => match value.parse::<u64>() {
+                Ok(milliseconds) => self.session.set_move_overhead(Duration::from_millis(milliseconds)),
+                Err(_) => return false,
+            },
+            "uci_chess960" => match value {
+                "true" => self.chess960 = true,
+                "false" => self.chess960 = false,
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// A function to turn the limits of a "go" command into the options for the search, starting from the session's search_options.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// go_command: &GoCommand, // The limits (see docs for GoCommand). "infinite" wins over a depth, which wins over a movetime.
+    ///                         // Without any, the engine thinks for DEFAULT_MOVETIME. The move overhead is taken off the time.
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::uci::{parse_go, UciEngine};
+    /// # use std::time::Duration;
+    /// let mut engine = UciEngine::new();
+    /// engine.set_option("Move Overhead", "50");
+    /// let options = engine.options_for(&parse_go("go movetime 300"));
+    /// assert_eq!(options.time_limit, Some(Duration::from_millis(250)));
+    /// ```
+    pub fn options_for(&self, go_command: &GoCommand) -> SearchOptions {
+        let session = &self.session;
+        if go_command.infinite {
+            return SearchOptions { depth: MAX_DEPTH, time_limit: None, ..session.search_options.clone() };
+        }
+        match (go_command.depth, go_command.movetime) {
+            (Some(depth), _) => SearchOptions { depth, ..session.search_options.clone() },
+            (None, Some(movetime)) => {
+                SearchOptions { time_limit: Some(session.time_limit(Duration::from_millis(movetime))), ..session.search_options.clone() }
+            }
+            (None, None) => SearchOptions { time_limit: Some(session.time_limit(DEFAULT_MOVETIME)), ..session.search_options.clone() },
+        }
+    }
+
+    fn end_search(&mut self, output: &mut impl Write, stop: bool) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        if stop || search.infinite {
+            search.handle.stop();
+        }
+        for info in search.infos.iter() {
+            writeln!(output, "{}", search.format(&info)).ok();
+        } // Ends when the worker drops its InfoForwarder, i.e. when the search is over
+        let result = self.session.finish_think(search.handle);
+        match result.best_move {
+            Some(best_move) if search.chess960 => writeln!(output, "bestmove {}", to_chess960_line(&search.game, &[best_move])[0]).ok(),
+            Some(best_move) => writeln!(output, "bestmove {}", best_move).ok(),
+            None => writeln!(output, "bestmove 0000").ok(),
+        };
+    } // Waits for the running search (stopping it first if asked to, or if it is infinite) and writes the rest of its output
+}
+impl Default for UciEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to run the UCI protocol loop until "quit" is received or the input ends.
+///
+/// ## Arguments
+/// ```text
+/// input: impl BufRead + Send + 'static, // The commands from the GUI, e.g. BufReader::new(stdin()). Read on a thread of its
+///                                       // own, so that the search output is written while the engine waits for a command.
+/// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+///
+/// ```
+/// When the input ends, a running search is finished as if the next command had been "position" (see module docs).
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::uci;
+/// let mut output = vec![];
+/// uci::run("uci\nisready\nquit\n".as_bytes(), &mut output);
+/// assert!(String::from_utf8(output).unwrap().ends_with("uciok\nreadyok\n"));
+/// ```
+pub fn run(input: impl BufRead + Send + 'static, output: &mut impl Write) {
+    let (sender, events) = mpsc::channel();
+    let mut engine = UciEngine::new();
+    engine.waker = Some(sender.clone());
+    thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else { break };
+            if sender.send(Event::Command(line)).is_err() {
+                return; // The engine has quit
+            }
+        }
+        sender.send(Event::EndOfInput).ok();
+    });
+    loop {
+        match events.recv() {
+            Ok(Event::Command(line)) => {
+                if !engine.start_command(&line, output) {
+                    break;
+                }
+            }
+            Ok(Event::SearchProgress) => engine.poll(output),
+            Ok(Event::EndOfInput) | Err(_) => {
+                engine.wait(output);
+                break;
+            }
+        }
+    }
+}
+
+/// A function to parse a UCI "position" command into a Game.
+///
+/// ## Arguments
+/// ```text
+/// command: &str, // "position startpos [moves ...]" or "position fen <FEN> [moves ...]"
+///
+/// ```
+/// ## Returns
+/// This function returns Some(Game) with all moves applied, or None if the command is malformed or any of the moves is illegal.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::uci::parse_position;
+/// let example_game = parse_position("position startpos moves e2e4 e7e5").unwrap();
+/// assert_eq!(example_game.fen, "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+/// ```
+pub fn parse_position(command: &str) -> Option<Game> {
+    let tokens = command.split_whitespace().collect::<Vec<&str>>();
+    if tokens.first() != Some(&"position") {
+        return None;
+    }
+    let moves_index = tokens.iter().position(|token| *token == "moves").unwrap_or(tokens.len());
+    let mut game = match tokens.get(1) {
+        Some(&"startpos") if moves_index == 2 => Game::new(),
+        Some(&"fen") if moves_index == 8 => Game::new_from_fen(tokens[2..8].join(" ")),
+        _ => return None,
+    };
+    game.play_uci_moves(&tokens[(moves_index + 1).min(tokens.len())..].join(" ")).ok()?;
+    Some(game)
+}
+
+impl Game {
+    /// A function to write the game as a UCI "position" command, e.g. to hand it to an external engine for analysis.
+    /// Inverse function for parse_position().
+    ///
+    /// ## Returns
+    /// This function returns "position startpos moves ..." if the game started from the standard starting position,
+    /// and "position fen <start_fen> moves ..." otherwise. The moves are the history in long algebraic notation, so
+    /// castling is the king's two-square move and promotions end with the piece letter. "moves" is left out if there are none.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Game;
+    /// let mut example_game = Game::new(); // Create a new game at the starting position
+    /// example_game.make_uci_move("e2e4"); // 1. e4
+    /// assert_eq!(example_game.to_uci_position(), "position startpos moves e2e4");
+    /// ```
+    pub fn to_uci_position(&self) -> String {
+        let start_fen = generate_fen(&parse_fen(&self.start_fen));
+        let mut command = if start_fen == Game::new().fen {
+            "position startpos".to_string()
+        } else {
+            format!("position fen {}", start_fen)
+        };
+        if !self.history.is_empty() {
+            let moves = self.history.iter().map(|played_move| played_move.to_uci()).collect::<Vec<String>>();
+            command.push_str(" moves ");
+            command.push_str(&moves.join(" "));
+        }
+        command
+    }
+}
+
+/// A function to parse a UCI "go" command.
+///
+/// ## Arguments
+/// ```text
+/// command: &str, // e.g. "go depth 4" or "go movetime 1000"
+///
+/// ```
+/// ## Returns
+/// This function returns a GoCommand. Limits that are missing or can't be parsed are None.
+pub fn parse_go(command: &str) -> GoCommand {
+    let tokens = command.split_whitespace().collect::<Vec<&str>>();
+    let mut go_command = GoCommand::default();
+    for (index, token) in tokens.iter().enumerate() {
+        match *token {
+            "depth" => go_command.depth = tokens.get(index + 1).and_then(|value| value.parse().ok()),
+            "movetime" => go_command.movetime = tokens.get(index + 1).and_then(|value| value.parse().ok()),
+            "infinite" => go_command.infinite = true,
+            _ => {}
+        }
+    }
+    go_command
+}
+
+/// A function to write the progress of a search as a UCI "info" line.
+///
+/// ## Arguments
+/// ```text
+/// info: &SearchInfo, // The progress reported by the search (see docs for InfoSink).
+///
+/// ```
+/// ## Returns
+/// This function returns the line without a trailing newline. The score and the principal variation are left out
+/// while the iteration hasn't completed.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::Move;
+/// # use alviny_task_3::search::{Score, SearchInfo};
+/// # use alviny_task_3::uci::format_info;
+/// # use std::time::Duration;
+/// let info = SearchInfo {
+///     depth: 2,
+///     selective_depth: 4,
+///     multipv: 1,
+///     score: Some(Score::Centipawns(34)),
+///     nodes: 1200,
+///     elapsed: Duration::from_millis(250),
+///     nodes_per_second: 4800,
+///     principal_variation: vec![Move::from_uci("e2e4").unwrap(), Move::from_uci("e7e5").unwrap()],
+/// };
+/// assert_eq!(format_info(&info), "info depth 2 seldepth 4 multipv 1 score cp 34 nodes 1200 nps 4800 time 250 pv e2e4 e7e5");
+/// ```
+pub fn format_info(info: &SearchInfo) -> String {
+    let mut line = format!("info depth {} seldepth {}", info.depth, info.selective_depth);
+    if let Some(score) = info.score {
+        line.push_str(&format!(" multipv {}", info.multipv));
+        match score {
+            Score::Centipawns(centipawns) => line.push_str(&format!(" score cp {}", centipawns)),
+            Score::Mate(moves) => line.push_str(&format!(" score mate {}", moves)),
+        }
+    }
+    line.push_str(&format!(" nodes {} nps {} time {}", info.nodes, info.nodes_per_second, info.elapsed.as_millis()));
+    if !info.principal_variation.is_empty() {
+        let moves = info.principal_variation.iter().map(|pv_move| pv_move.to_uci()).collect::<Vec<String>>();
+        line.push_str(&format!(" pv {}", moves.join(" ")));
+    }
+    line
+}
+
+/// A function to parse a UCI "setoption" command.
+///
+/// ## Arguments
+/// ```text
+/// command: &str, // "setoption name <id> [value <x>]". The id and the value may contain spaces.
+///
+/// ```
+/// ## Returns
+/// This function returns Some((name, value)), where value is empty for options without one (buttons),
+/// or None if the command is malformed.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::uci::parse_setoption;
+/// let (name, value) = parse_setoption("setoption name Move Overhead value 30").unwrap();
+/// assert_eq!((name.as_str(), value.as_str()), ("Move Overhead", "30"));
+/// ```
+pub fn parse_setoption(command: &str) -> Option<(String, String)> {
+    let tokens = command.split_whitespace().collect::<Vec<&str>>();
+    if tokens.first() != Some(&"setoption") || tokens.get(1) != Some(&"name") {
+        return None;
+    }
+    let value_index = tokens.iter().position(|token| *token == "value").unwrap_or(tokens.len());
+    if value_index <= 2 {
+        return None; // No name
+    }
+    Some((tokens[2..value_index].join(" "), tokens.get(value_index + 1..).unwrap_or_default().join(" ")))
+}
+
+/// A function to read a UCI "info" line, e.g. from an external engine. Inverse function for format_info().
+///
+/// ## Arguments
+/// ```text
+/// line: &str, // One line written by an engine, e.g. "info depth 12 score cp 31 nodes 80000 pv e2e4 e7e5".
+///
+/// ```
+/// ## Returns
+/// This function returns Some(SearchInfo), or None if the line isn't an "info" line or only carries a "string".
+/// Fields the line leaves out are 0 (multipv is 1), and so is anything that can't be read. A score given as a bound
+/// ("lowerbound", "upperbound") is read like an exact one.
+///
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::search::Score;
+/// # use alviny_task_3::uci::parse_info;
+/// let info = parse_info("info depth 20 seldepth 31 multipv 1 score mate -3 nodes 123456 nps 1000000 time 123 pv e1d1 a2a1q").unwrap();
+/// assert_eq!((info.depth, info.score, info.nodes), (20, Some(Score::Mate(-3)), 123456));
+/// assert_eq!(info.principal_variation[1].to_uci(), "a2a1q");
+/// assert!(parse_info("info string NNUE evaluation enabled").is_none());
+/// ```
+pub fn parse_info(line: &str) -> Option<SearchInfo> {
+    let tokens = line.split_whitespace().collect::<Vec<&str>>();
+    if tokens.first() != Some(&"info") || tokens.get(1) == Some(&"string") {
+        return None;
+    }
+    let mut info = SearchInfo {
+        depth: 0,
+        selective_depth: 0,
+        multipv: 1,
+        score: None,
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        nodes_per_second: 0,
+        principal_variation: vec![],
+    };
+    let number = |index: usize| tokens.get(index + 1).and_then(|value| value.parse::<u64>().ok()).unwrap_or_default();
+    for (index, token) in tokens.iter().enumerate() {
+        match *token {
+            "depth" => info.depth = number(index) as u32,
+            "seldepth" => info.selective_depth = number(index) as u32,
+            "multipv" => info.multipv = number(index).max(1) as usize,
+            "nodes" => info.nodes = number(index),
+            "nps" => info.nodes_per_second = number(index),
+            "time" => info.elapsed = Duration::from_millis(number(index)),
+            "score" => {
+                let value = tokens.get(index + 2).and_then(|value| value.parse::<i32>().ok());
+                info.score = match (tokens.get(index + 1), value) {
+                    (Some(&"cp"), Some(centipawns)) => Some(Score::Centipawns(centipawns)),
+                    (Some(&"mate"), Some(moves)) => Some(Score::Mate(moves)),
+                    _ => None,
+                };
+            }
+            "pv" => {
+                info.principal_variation = tokens[index + 1..].iter().map_while(|pv_move| Move::from_uci(pv_move)).collect();
+                break; // The line ends with the moves
+            }
+            "string" => break, // Free text until the end of the line
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+enum Event {
+    Command(String),
+    SearchProgress,
+    EndOfInput,
+} // What run() waits for: a line from the GUI, or news from the search
+
+struct RunningSearch {
+    handle: SearchHandle,
+    infos: Receiver<SearchInfo>,
+    game: Game,
+    chess960: bool,
+    infinite: bool,
+}
+impl RunningSearch {
+    fn format(&self, info: &SearchInfo) -> String {
+        let mut info = info.clone();
+        if self.chess960 {
+            info.principal_variation = to_chess960_line(&self.game, &info.principal_variation);
+        }
+        format_info(&info)
+    } // An info line of this search, with castling written the way the GUI asked for
+} // A search started by "go" whose "bestmove" hasn't been written yet
+
+struct InfoForwarder {
+    infos: Sender<SearchInfo>,
+    waker: Option<Sender<Event>>,
+}
+impl InfoSink for InfoForwarder {
+    fn info(&mut self, info: &SearchInfo) {
+        self.infos.send(info.clone()).ok();
+        if let Some(waker) = &self.waker {
+            waker.send(Event::SearchProgress).ok();
+        }
+    }
+}
+impl Drop for InfoForwarder {
+    fn drop(&mut self) {
+        if let Some(waker) = &self.waker {
+            waker.send(Event::SearchProgress).ok();
+        }
+    }
+} // Passes the progress of a search from the worker thread to the engine, and lets run() know when the search is over
+
+fn to_chess960_line(game: &Game, line: &[Move]) -> Vec<Move> {
+    let mut position = game.clone();
+    let mut encoded = vec![];
+    for line_move in line {
+        let (source, target) = (get_board_coords(line_move.source.clone()), get_board_coords(line_move.target.clone()));
+        let mut written = line_move.clone();
+        if let Some(castling) = CastlingMove::from_move(&position.board, &source, &target) {
+            written.target = Square::from_coords(&castling.rook).to_algebraic();
+        }
+        position.make_uci_move(&line_move.to_uci());
+        encoded.push(written);
+    }
+    encoded
+} // Rewrites castling moves as the king taking its own rook, replaying the line to tell the king's moves apart.
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor, Read};
+    use std::time::Instant;
+
+    fn run_transcript(transcript: &str) -> String {
+        let mut output = vec![];
+        run(Cursor::new(transcript.to_string()), &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_position_export_round_trips() {
+        let games = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4 d7d5 e4d5 g8f6 f1b5 c7c6 d5c6 d8a5 c6b7 e8d8 b7c8q d8c8 g1f3 e7e5 e1g1"),
+            ("r3k2r/6P1/8/8/8/8/8/R3K2R b KQkq - 3 40", "e8c8 g7g8r c8b7 e1g1"),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", ""),
+        ];
+        for (fen, moves) in games {
+            let mut game = Game::new_from_fen(fen.to_string());
+            for uci_move in moves.split_whitespace() {
+                assert!(game.make_uci_move(uci_move), "{}", uci_move);
+            }
+            let command = game.to_uci_position();
+            assert_eq!(parse_position(&command).unwrap().fen, game.fen, "{}", command);
+        }
+        assert_eq!(Game::new().to_uci_position(), "position startpos");
+        let mut castled = Game::new_from_fen("r3k2r/6P1/8/8/8/8/8/R3K2R b KQkq - 3 40".to_string());
+        castled.make_move_str("O-O-O").unwrap();
+        castled.make_move_str("g7g8=R").unwrap();
+        assert_eq!(castled.to_uci_position(), "position fen r3k2r/6P1/8/8/8/8/8/R3K2R b KQkq - 3 40 moves e8c8 g7g8r");
+    }
+    #[test]
+    fn test_handshake() {
+        let output = run_transcript("uci\nisready\nquit\n");
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0..2], ["id name alviny-task-3", "id author alviny"]);
+        assert_eq!(lines[2], "option name Hash type spin default 16 min 1 max 1024");
+        assert_eq!(lines[5], "option name UCI_Chess960 type check default false");
+        assert_eq!(lines[6..], ["uciok", "readyok"]);
+    }
+    #[test]
+    fn test_quit_stops_the_loop() {
+        let output = run_transcript("quit\nisready\n");
+        assert_eq!(output, "");
+    }
+    #[test]
+    fn test_position_startpos_moves() {
+        let game = parse_position("position startpos moves e2e4 e7e5 g1f3").unwrap();
+        assert_eq!(game.fen, "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        let game = parse_position("position startpos").unwrap();
+        assert_eq!(game.fen, Game::new().fen);
+    }
+    #[test]
+    fn test_position_fen_moves() {
+        let game = parse_position("position fen 7k/P7/8/8/8/8/8/K7 w - - 0 1 moves a7a8n").unwrap();
+        assert_eq!(game.fen, "N6k/8/8/8/8/8/8/K7 b - - 0 1");
+        assert_eq!(game.board.promotion_selection, 'q');
+    }
+    #[test]
+    fn test_position_rejects_bad_input() {
+        assert!(parse_position("position startpos moves e2e5").is_none()); // illegal move
+        assert!(parse_position("position startpos moves e2").is_none()); // malformed move
+        assert!(parse_position("position fen 8/8/8/8/8/8/8/K6k w").is_none()); // truncated FEN
+        assert!(parse_position("position").is_none());
+    }
+    #[test]
+    fn test_parse_go() {
+        assert_eq!(parse_go("go depth 4"), GoCommand { depth: Some(4), movetime: None, infinite: false });
+        assert_eq!(parse_go("go movetime 250"), GoCommand { depth: None, movetime: Some(250), infinite: false });
+        assert_eq!(parse_go("go infinite"), GoCommand { depth: None, movetime: None, infinite: true });
+        assert_eq!(parse_go("go ponder"), GoCommand::default());
+    }
+    #[test]
+    fn test_setoption() {
+        let mut engine = UciEngine::new();
+        let script = [
+            "setoption name Hash value 64",
+            "setoption name MultiPV value 3",
+            "setoption name Move Overhead value 50",
+            "setoption name UCI_Chess960 value true",
+            "setoption name Threads value 4", // Unknown options are ignored
+            "setoption name Clear Hash",
+            "setoption name MultiPV value many",
+            "setoption value 5",
+        ];
+        for command in script {
+            let mut output = vec![];
+            assert!(engine.handle_command(command, &mut output));
+            assert!(output.is_empty());
+        }
+        assert_eq!((engine.session.search_options.hash_size, engine.session.table.size_mb()), (64, 64));
+        assert_eq!(engine.session.search_options.multipv, 3);
+        assert_eq!(engine.session.move_overhead, Duration::from_millis(50));
+        assert!(engine.chess960);
+
+        let options = engine.options_for(&parse_go("go movetime 1000"));
+        assert_eq!((options.multipv, options.hash_size), (3, 64));
+        assert_eq!(options.time_limit, Some(Duration::from_millis(950)));
+        assert_eq!(engine.options_for(&parse_go("go movetime 20")).time_limit, Some(Duration::from_millis(1)));
+        assert_eq!(engine.options_for(&parse_go("go depth 3")).depth, 3);
+        engine.set_option("hash", "0");
+        assert_eq!(engine.session.table.size_mb(), 1); // Clamped to the declared range
+    }
+    #[test]
+    fn test_chess960_castling() {
+        // Castling is the only way to mate
+        let fen = "4rkr1/4p1p1/8/8/8/8/8/4K2R w K - 0 1";
+        let output = run_transcript(&format!("position fen {}\ngo depth 1\n", fen));
+        assert!(output.ends_with("bestmove e1g1\n"));
+        let output = run_transcript(&format!("setoption name UCI_Chess960 value true\nposition fen {}\ngo depth 1\n", fen));
+        assert!(output.contains(" pv e1h1\n"));
+        assert!(output.ends_with("bestmove e1h1\n"));
+        let game = parse_position(&format!("position fen {} moves e1h1", fen)).unwrap();
+        assert!(game.is_checkmate());
+    }
+    #[test]
+    fn test_info_round_trips() {
+        let output = run_transcript("position startpos moves e2e4\ngo depth 2\n");
+        for line in output.lines().filter(|line| line.starts_with("info")) {
+            assert_eq!(format_info(&parse_info(line).unwrap()), line);
+        }
+        let info = parse_info("info depth 7 score cp 15 lowerbound hashfull 10").unwrap();
+        assert_eq!((info.depth, info.score, info.multipv), (7, Some(Score::Centipawns(15)), 1));
+        assert!(info.principal_variation.is_empty());
+        assert!(parse_info("bestmove e2e4").is_none());
+    }
+    #[test]
+    fn test_go_only_move() {
+        // The white king on a1 can only escape to a2
+        let output = run_transcript("position fen 1r6/8/8/8/8/2k5/8/K7 w - - 0 1\ngo depth 1\n");
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("info depth 1 seldepth 1 multipv 1 score cp "));
+        assert!(lines[0].ends_with(" pv a1a2"));
+        assert_eq!(lines[1], "bestmove a1a2");
+    }
+    #[test]
+    fn test_go_checkmated() {
+        let output = run_transcript("position startpos moves f2f3 e7e5 g2g4 d8h4\ngo movetime 10\n");
+        assert_eq!(output, "bestmove 0000\n");
+    }
+    #[test]
+    fn test_game_transcript() {
+        let output = run_transcript("uci\nucinewgame\nisready\nposition startpos moves e2e4\ngo movetime 100\n");
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[6..8], ["uciok", "readyok"]);
+        assert!(lines[8..lines.len() - 1].iter().all(|line| line.starts_with("info depth ")));
+        let best_move = lines.last().unwrap().strip_prefix("bestmove ").unwrap();
+        let mut game = parse_position("position startpos moves e2e4").unwrap();
+        assert!(game.make_uci_move(best_move));
+    }
+    #[test]
+    fn test_go_then_stop() {
+        struct SlowStop(bool);
+        impl Read for SlowStop {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0 {
+                    return Ok(0);
+                }
+                thread::sleep(Duration::from_millis(200));
+                self.0 = true;
+                buf[..5].copy_from_slice(b"stop\n");
+                Ok(5)
+            }
+        } // Sends "stop" once the engine has had time to start thinking
+        let input = BufReader::new(Cursor::new("position startpos\ngo infinite\n").chain(SlowStop(false)));
+        let (sender, receiver) = mpsc::channel();
+        let start = Instant::now();
+        thread::spawn(move || {
+            let mut output = vec![];
+            run(input, &mut output);
+            sender.send(String::from_utf8(output).unwrap()).ok();
+        });
+        let output = receiver.recv_timeout(Duration::from_secs(30)).expect("the search didn't stop");
+        assert!(start.elapsed() < Duration::from_secs(30));
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert!(lines.len() > 1);
+        assert!(lines[..lines.len() - 1].iter().all(|line| line.starts_with("info depth ")));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("bestmove ")).count(), 1);
+        let best_move = lines.last().unwrap().strip_prefix("bestmove ").unwrap();
+        assert!(Game::new().make_uci_move(best_move));
+    }
+    #[test]
+    fn test_one_bestmove_per_go() {
+        let mut engine = UciEngine::new();
+        let mut output = vec![];
+        assert!(engine.handle_command("go infinite", &mut output)); // Doesn't wait, or it would never return
+        thread::sleep(Duration::from_millis(50));
+        for command in ["stop", "stop", "isready"] {
+            engine.handle_command(command, &mut output);
+        } // The second "stop" has no search left to stop
+        engine.handle_command("go depth 1", &mut output); // Waits for its "bestmove"
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        let best_moves = lines.iter().enumerate().filter(|(_index, line)| line.starts_with("bestmove ")).map(|(index, _line)| index).collect::<Vec<usize>>();
+        assert_eq!(best_moves.len(), 2);
+        assert_eq!(lines[best_moves[0] + 1], "readyok");
+        assert_eq!(best_moves[1], lines.len() - 1);
+        assert!(!engine.is_searching());
+    }
+    #[test]
+    fn test_commands_are_answered_while_searching() {
+        let mut engine = UciEngine::new();
+        let mut output = vec![];
+        engine.start_command("go infinite", &mut output);
+        assert!(engine.is_searching());
+        engine.start_command("isready", &mut output);
+        engine.poll(&mut output);
+        assert!(engine.is_searching()); // An infinite search waits for "stop"
+        assert!(!engine.start_command("quit", &mut output));
+        assert!(!engine.is_searching());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("readyok\n"));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+}
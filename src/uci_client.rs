@@ -0,0 +1,316 @@
+/*****************************
+*  UCI ENGINE CLIENT         *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A client for external UCI engines, e.g. to ask Stockfish what it thinks of a position. Only available with the
+`uci-client` feature. This is the other side of the uci module: here this library is the GUI.
+
+The engine runs as a child process. Its output is read on a separate thread, so that every wait for an answer can
+time out instead of hanging on an engine that crashed or never answers. The engine is asked to quit when the
+ExternalEngine is dropped, and killed if it doesn't.
+
+```no_run
+# use alviny_task_3::Game;
+# use alviny_task_3::uci::GoCommand;
+# use alviny_task_3::uci_client::ExternalEngine;
+let mut stockfish = ExternalEngine::spawn("stockfish", &[]).unwrap();
+let analysis = stockfish.analyze(&Game::new(), &GoCommand { depth: Some(20), movetime: None, infinite: false }).unwrap();
+println!("{:?} {:?}", analysis.best_move, analysis.score);
+```
+*/
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::engine::DEFAULT_MOVETIME;
+use crate::search::Score;
+use crate::uci::{parse_info, GoCommand};
+use crate::{Game, Move};
+
+/// How long to wait for an answer the engine should give right away, and for a search beyond its time limit.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Debug)]
+/// An enum to represent why talking to an external engine failed.
+///
+/// ```text
+/// Io(io::Error), // The engine couldn't be started, or writing to it failed.
+/// Timeout(String), // The engine didn't send the expected answer in time, e.g. "uciok".
+/// Exited, // The engine closed its output, usually because it crashed or quit.
+/// ```
+pub enum EngineError {
+    Io(io::Error),
+    Timeout(String),
+    Exited,
+}
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::Io(io_error) => write!(f, "couldn't talk to the engine: {}", io_error),
+            EngineError::Timeout(expected) => write!(f, "the engine didn't answer \"{}\" in time", expected),
+            EngineError::Exited => write!(f, "the engine exited"),
+        }
+    }
+}
+impl std::error::Error for EngineError {}
+impl From<io::Error> for EngineError {
+    fn from(io_error: io::Error) -> Self {
+        EngineError::Io(io_error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A struct to represent what an external engine thinks of a position.
+///
+/// ## Attributes
+/// ```text
+/// best_move: Option<Move>, // The "bestmove". None if the engine had no move to give, e.g. in a checkmate.
+/// ponder: Option<Move>, // The reply the engine expects, if it gave one.
+/// score: Option<Score>, // The score of the best line from the perspective of the side to move, if the engine gave one.
+/// depth: u32, // The depth of the last "info" line with a score.
+/// principal_variation: Vec<Move>, // The best line from the last "info" line with a score.
+/// ```
+pub struct AnalysisResult {
+    pub best_move: Option<Move>,
+    pub ponder: Option<Move>,
+    pub score: Option<Score>,
+    pub depth: u32,
+    pub principal_variation: Vec<Move>,
+}
+
+/// A struct to represent an external UCI engine running as a child process.
+///
+/// ## Attributes
+/// ```text
+/// name: String, // The name the engine gave in its "id name" line, or "" if it didn't.
+/// timeout: Duration, // How long to wait for answers. Searches get this long on top of their movetime. Defaults to DEFAULT_TIMEOUT.
+/// ```
+pub struct ExternalEngine {
+    pub name: String,
+    pub timeout: Duration,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+impl ExternalEngine {
+    /// A function to start an engine and perform the UCI handshake ("uci", then "isready").
+    ///
+    /// ## Arguments
+    /// ```text
+    /// program: impl AsRef<OsStr>, // The engine binary, e.g. "stockfish" or a path to it.
+    /// args: &[&str], // Command line arguments for the engine. Usually none.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the engine, ready for analysis, or an EngineError if it can't be started or doesn't
+    /// answer the handshake within DEFAULT_TIMEOUT.
+    pub fn spawn(program: impl AsRef<OsStr>, args: &[&str]) -> Result<ExternalEngine, EngineError> {
+        let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+        let stdin = child.stdin.take().ok_or(EngineError::Exited)?;
+        let stdout = child.stdout.take().ok_or(EngineError::Exited)?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break; // The ExternalEngine is gone
+                }
+            }
+        });
+        let mut engine = ExternalEngine { name: String::new(), timeout: DEFAULT_TIMEOUT, child, stdin, lines };
+        engine.send("uci")?;
+        let deadline = Instant::now() + engine.timeout;
+        loop {
+            let line = engine.read_line(deadline, "uciok")?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.wait_until_ready()?;
+        Ok(engine)
+    }
+
+    /// A function to set one of the engine's options, e.g. "Threads" or "Hash".
+    ///
+    /// ## Arguments
+    /// ```text
+    /// name: &str, // The name of the option, as the engine declared it.
+    /// value: &str, // The new value.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns an EngineError if the engine doesn't answer "isready" afterwards.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.wait_until_ready()
+    }
+
+    /// A function to have the engine analyze a position.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// game: &Game, // The position, sent with its move history (see docs for Game::to_uci_position).
+    /// limits: &GoCommand, // How deep or how long to search (see docs for GoCommand). Without limits, the engine
+    ///                     // searches for DEFAULT_MOVETIME. "infinite" is ignored, since analyze() waits for the result.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the AnalysisResult, or an EngineError. If the engine doesn't finish within its movetime
+    /// and the timeout, it is sent "stop" and gets one more timeout to answer.
+    pub fn analyze(&mut self, game: &Game, limits: &GoCommand) -> Result<AnalysisResult, EngineError> {
+        self.send(&game.to_uci_position())?;
+        let mut go_command = "go".to_string();
+        if let Some(depth) = limits.depth {
+            go_command.push_str(&format!(" depth {}", depth));
+        }
+        let movetime = match (limits.depth, limits.movetime) {
+            (_, Some(movetime)) => Some(Duration::from_millis(movetime)),
+            (None, None) => Some(DEFAULT_MOVETIME),
+            (Some(_), None) => None,
+        };
+        if let Some(movetime) = movetime {
+            go_command.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        self.send(&go_command)?;
+
+        let mut result = AnalysisResult { best_move: None, ponder: None, score: None, depth: 0, principal_variation: vec![] };
+        let mut deadline = Instant::now() + movetime.unwrap_or_default() + self.timeout;
+        let mut stopped = false;
+        loop {
+            let line = match self.read_line(deadline, "bestmove") {
+                Err(EngineError::Timeout(_)) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    deadline = Instant::now() + self.timeout;
+                    continue;
+                }
+                line => line?,
+            };
+            if let Some(info) = parse_info(&line) {
+                if info.multipv == 1 && info.score.is_some() {
+                    (result.score, result.depth, result.principal_variation) = (info.score, info.depth, info.principal_variation);
+                }
+            } else if let Some(best_move) = line.strip_prefix("bestmove") {
+                let mut tokens = best_move.split_whitespace();
+                result.best_move = tokens.next().and_then(Move::from_uci); // "0000" and "(none)" aren't moves
+                result.ponder = match (tokens.next(), tokens.next()) {
+                    (Some("ponder"), Some(ponder)) => Move::from_uci(ponder),
+                    _ => None,
+                };
+                return Ok(result);
+            }
+        }
+    }
+
+    /// A function to ask the engine to quit, and wait for it to exit. The engine is killed if it doesn't exit within the timeout.
+    ///
+    /// ## Returns
+    /// This function returns an EngineError if the engine couldn't be waited for or killed.
+    pub fn quit(mut self) -> Result<(), EngineError> {
+        self.shut_down()
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        Ok(())
+    } // Writes one command to the engine.
+
+    fn read_line(&self, deadline: Instant, expected: &str) -> Result<String, EngineError> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(EngineError::Timeout(expected.to_string())),
+            Err(RecvTimeoutError::Disconnected) => Err(EngineError::Exited),
+        }
+    } // Waits for the next line of the engine's output, until the deadline.
+
+    fn wait_until_ready(&mut self) -> Result<(), EngineError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + self.timeout;
+        while self.read_line(deadline, "readyok")?.trim() != "readyok" {}
+        Ok(())
+    } // Sends "isready" and waits for "readyok", skipping anything else the engine writes.
+
+    fn shut_down(&mut self) -> Result<(), EngineError> {
+        if self.child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        self.send("quit").ok(); // The engine may already be on its way out
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            if self.child.try_wait()?.is_some() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.child.kill()?;
+        self.child.wait()?;
+        Ok(())
+    } // Sends "quit" and waits for the engine to exit, killing it if it doesn't.
+}
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        self.shut_down().ok();
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn mock_engine() -> ExternalEngine {
+        let script = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/mock_uci_engine.sh");
+        ExternalEngine::spawn("sh", &[script]).unwrap()
+    }
+
+    #[test]
+    fn test_handshake_and_analysis() {
+        let mut engine = mock_engine();
+        assert_eq!(engine.name, "MockFish 1.0");
+        engine.set_option("Hash", "32").unwrap();
+        let analysis = engine.analyze(&Game::new(), &GoCommand { depth: Some(2), movetime: None, infinite: false }).unwrap();
+        assert_eq!(analysis.best_move, Move::from_uci("e2e4"));
+        assert_eq!(analysis.ponder, Move::from_uci("e7e5"));
+        assert_eq!((analysis.score, analysis.depth), (Some(Score::Centipawns(35)), 2));
+        assert_eq!(analysis.principal_variation, [Move::from_uci("e2e4").unwrap(), Move::from_uci("e7e5").unwrap()]);
+        engine.quit().unwrap();
+    }
+    #[test]
+    fn test_mate_and_no_move() {
+        let mut engine = mock_engine();
+        let checkmated = Game::from_moves(&["f3", "e5", "g4", "Qh4#"]).unwrap();
+        let analysis = engine.analyze(&checkmated, &GoCommand::default()).unwrap();
+        assert_eq!((analysis.best_move, analysis.score), (None, Some(Score::Mate(0))));
+    }
+    #[test]
+    fn test_timeout() {
+        let mut engine = mock_engine();
+        engine.timeout = Duration::from_millis(200);
+        let started = Instant::now();
+        let analysis = engine.analyze(&Game::new(), &GoCommand { depth: Some(99), movetime: None, infinite: false });
+        assert!(matches!(analysis, Err(EngineError::Timeout(expected)) if expected == "bestmove"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let started = Instant::now();
+        drop(engine); // The mock ignores "quit" while it "searches", so it has to be killed
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(matches!(ExternalEngine::spawn("/nonexistent/engine", &[]), Err(EngineError::Io(_))));
+    }
+}
@@ -0,0 +1,221 @@
+/*****************************
+*  MAKE AND UNMAKE           *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Playing a move on a board and taking it back, without copying the board. Trying out a move is what the legality
+filter and the search do for every move they look at, and a copy of the board costs nine allocations, while an Undo
+records only the few squares the move changes.
+
+Board::make_move_undoable plays a move and returns an Undo, and Board::unmake puts everything back: the squares, the
+castling rights, the en passant square, the counters and the side to move. A Board keeps no incremental hash, since
+its Zobrist key is computed from the position (see docs for Board::zobrist_key), so the key is restored with the board.
+Undos must be unmade in the reverse order of the moves.
+
+```
+# use alviny_task_3::{Game, Move};
+let mut board = Game::new().board;
+let original = board.clone();
+let undo = board.make_move_undoable(&Move::from_uci("e2e4").unwrap());
+assert_eq!(board.active_player, 'b');
+board.unmake(undo);
+assert_eq!(board, original);
+```
+*/
+
+use crate::castling::CastlingMove;
+use crate::{get_piece, Board, CastlingRights, Move, Piece, Square};
+
+#[cfg(test)]
+thread_local! {
+    pub(crate) static SIMULATED_MOVES: std::cell::Cell<u64> = const { std::cell::Cell::new(0) }; // Counts move_piece_undoable() calls, to test how much work move generation does
+}
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A struct to represent what a move changed on a board, so that Board::unmake can take it back (see module docs).
+/// The squares are private, since an Undo only fits the board and position it was made on.
+pub struct Undo {
+    squares: [Option<(usize, usize, char)>; 4],
+    captured: Option<Piece>,
+    castling_rights: CastlingRights,
+    en_passant_square: String,
+    halfmove_counter: u32,
+    turn_counter: u64,
+    active_player: char,
+}
+impl Undo {
+    /// A function to return the piece the move captured, if any. A pawn captured en passant counts.
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+}
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    pub(crate) fn move_piece_undoable(&mut self, source: &[i32], target: &[i32]) -> Undo {
+        let piece = get_piece(self, source);
+        let mut changed = vec![[source[0], source[1]], [target[0], target[1]]];
+        if piece.eq_ignore_ascii_case(&'p') && Square::from_coords(target).to_algebraic() == self.en_passant_square {
+            changed.extend([[target[0] - 1, target[1]], [target[0] + 1, target[1]]]); // The pawn taken en passant
+        } else if let Some(castling) = CastlingMove::from_move(self, source, target) {
+            changed = vec![castling.king, castling.rook, castling.king_target, castling.rook_target]; // The castling rook too
+        }
+        let mut squares = [None; 4];
+        for (slot, [row, column]) in squares.iter_mut().zip(changed) {
+            if (0..8).contains(&row) && (0..8).contains(&column) {
+                *slot = Some((row as usize, column as usize, self.board_state[row as usize][column as usize]));
+            }
+        }
+        let undo = Undo {
+            squares,
+            captured: crate::get_captured_piece(self, source, target),
+            castling_rights: self.castling_rights,
+            en_passant_square: self.en_passant_square.clone(),
+            halfmove_counter: self.halfmove_counter,
+            turn_counter: self.turn_counter,
+            active_player: self.active_player,
+        };
+        #[cfg(test)]
+        SIMULATED_MOVES.with(|count| count.set(count.get() + 1));
+        self.move_piece(source.to_vec(), target.to_vec());
+        undo
+    } // Moves a piece like move_piece, without handing over the turn, and records what changed.
+
+    pub(crate) fn play_move_undoable(&mut self, source: &[i32], target: &[i32], promotion: Option<char>) -> Undo {
+        let previous_selection = self.promotion_selection;
+        if let Some(piece) = promotion {
+            self.promotion_selection = piece;
+        }
+        let undo = self.move_piece_undoable(source, target);
+        self.promotion_selection = previous_selection;
+        if self.active_player == 'w' {
+            self.active_player = 'b';
+        } else if self.active_player == 'b' {
+            self.turn_counter += 1;
+            self.active_player = 'w';
+        }
+        undo
+    } // Plays a move like play_move, and records what changed.
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Board {
+    /// A function to play a move on the board in place, so that it can be taken back with unmake() (see module docs).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// chess_move: &Move, // The move, assumed to be legal. A promotion of None promotes to promotion_selection, as in make_move().
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the Undo for unmake(). If a square of the move isn't on the board, nothing is played, and
+    /// the Undo changes nothing either.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Move};
+    /// let mut board = Game::from_uci_moves("e2e4 d7d5").unwrap().board;
+    /// let capture = Move::from_uci("e4d5").unwrap();
+    /// let expected = board.after_move(&capture).unwrap();
+    /// let undo = board.make_move_undoable(&capture);
+    /// assert_eq!(undo.captured().map(|piece| piece.to_fen_char()), Some('p'));
+    /// assert_eq!(board, expected);
+    /// ```
+    pub fn make_move_undoable(&mut self, chess_move: &Move) -> Undo {
+        let (Some(source), Some(target)) = (Square::from_algebraic(&chess_move.source), Square::from_algebraic(&chess_move.target)) else {
+            return Undo {
+                squares: [None; 4],
+                captured: None,
+                castling_rights: self.castling_rights,
+                en_passant_square: self.en_passant_square.clone(),
+                halfmove_counter: self.halfmove_counter,
+                turn_counter: self.turn_counter,
+                active_player: self.active_player,
+            };
+        };
+        self.play_move_undoable(&source.coords(), &target.coords(), chess_move.promotion)
+    }
+
+    /// A function to take back the last move played with make_move_undoable(), restoring the board exactly.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// undo: Undo, // The Undo returned for the last move that hasn't been taken back yet.
+    ///
+    /// ```
+    pub fn unmake(&mut self, undo: Undo) {
+        for (row, column, piece) in undo.squares.into_iter().rev().flatten() {
+            self.board_state[row][column] = piece;
+        }
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_square = undo.en_passant_square;
+        self.halfmove_counter = undo.halfmove_counter;
+        self.turn_counter = undo.turn_counter;
+        self.active_player = undo.active_player;
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_fen, Game};
+
+    const TRICKY_FENS: [&str; 6] = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", // Castling both ways, pins
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1", // En passant on the a-file
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", // En passant next to another capture
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", // Promotions with and without captures
+        "r3k2r/1P6/8/8/8/8/6p1/R3K2R w KQkq - 0 1", // Promotions onto the castling rooks
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+
+    #[test]
+    fn test_make_and_unmake_every_move() {
+        for fen in TRICKY_FENS {
+            let game = Game::new_from_fen(fen.to_string());
+            let original = game.board.clone();
+            let mut board = game.board.clone();
+            for legal_move in game.legal_moves() {
+                let undo = board.make_move_undoable(&legal_move);
+                assert_eq!(board, original.after_move(&legal_move).unwrap(), "{} {}", fen, legal_move.to_uci());
+                for reply in Game::from_board(board.clone()).legal_moves() {
+                    let before_reply = board.clone();
+                    let reply_undo = board.make_move_undoable(&reply);
+                    board.unmake(reply_undo);
+                    assert_eq!(board, before_reply, "{} {} {}", fen, legal_move.to_uci(), reply.to_uci());
+                }
+                board.unmake(undo);
+                assert_eq!(board, original, "{} {}", fen, legal_move.to_uci());
+                assert_eq!(board.zobrist_key(), original.zobrist_key());
+            }
+        }
+    }
+    #[test]
+    fn test_captured_piece() {
+        let mut board = parse_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+        let undo = board.make_move_undoable(&crate::Move::from_uci("e5f6").unwrap());
+        assert_eq!(undo.captured().map(|piece| piece.to_fen_char()), Some('p')); // En passant
+        board.unmake(undo);
+        let undo = board.make_move_undoable(&crate::Move::from_uci("g1f3").unwrap());
+        assert_eq!(undo.captured(), None);
+    }
+}
@@ -0,0 +1,479 @@
+/*****************************
+*  VARIANTS                  *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+Chess variants, i.e. games played with the normal pieces but with changed rules.
+
+The variant is stored on the Game rather than in the FEN, so a FEN string describes the same position in every variant,
+and the variant survives every move and every regeneration of Game.fen.
+
+Every variant shares the move geometry of get_piece_movements(). What differs is which of those moves are legal,
+what happens on the board when a move is played, and when the game ends, and Game asks the Rules of the Variant for each
+of these (see docs for Rules).
+*/
+
+use std::sync::Arc;
+
+use crate::castling::CastlingMove;
+use crate::rules::{move_list, Rules, StandardRules};
+use crate::{
+    flatten_move_map, get_available_moves_internal, get_board_coords, get_captured_piece, get_legal_move_list, get_piece,
+    player_is_in_check, Board, CastlingRights, Color, Game, GameStatus, Move,
+};
+
+/*****************************
+*  PRIVATE HELPER FUNCTIONS  *
+*  BEGIN HERE                *
+*****************************/
+
+fn hill_winner(board: &Board) -> Option<GameStatus> {
+    let on_hill = |color: char| {
+        board.king_square(color).is_some_and(|square| HILL.contains(&square.to_algebraic().as_str()))
+    };
+    if on_hill('w') {
+        Some(GameStatus::WhiteWinsByKingOfTheHill)
+    } else if on_hill('b') {
+        Some(GameStatus::BlackWinsByKingOfTheHill)
+    } else {
+        None
+    }
+} // Returns the winning status if a king stands on the hill
+
+fn atomic_play_move(board: &mut Board, source: Vec<i32>, target: Vec<i32>, promotion: Option<char>) {
+    let is_pawn_capture = get_piece(board, &source).eq_ignore_ascii_case(&'p') && source[1] != target[1]; // Includes en passant
+    let is_capture = get_captured_piece(board, &source, &target).is_some() || is_pawn_capture;
+    board.play_move(source, target.clone(), promotion);
+    if !is_capture {
+        return;
+    }
+    board.set_piece(&target, '*'); // The capturing piece explodes too
+    for y_step in -1..=1 {
+        for x_step in -1..=1 {
+            let (y_pos, x_pos) = (target[0] + y_step, target[1] + x_step);
+            if (0..8).contains(&y_pos) && (0..8).contains(&x_pos) && !get_piece(board, &[y_pos, x_pos]).eq_ignore_ascii_case(&'p') {
+                board.set_piece(&[y_pos, x_pos], '*');
+            }
+        }
+    }
+    CastlingRights::revoke_unplaced(board);
+} // Plays a move, then blows up the capturing piece and every piece but pawns around the target square if it was a capture
+
+fn atomic_in_check(board: &Board, color: char) -> bool {
+    let enemy = if color == 'w' { 'b' } else { 'w' };
+    match (board.king_square(color), board.king_square(enemy)) {
+        (Some(king), Some(enemy_king)) => king.distance(enemy_king) > 1 && board.is_square_attacked(king, enemy),
+        _ => false,
+    }
+} // Kings next to each other can't be checked, since capturing one would blow up the other
+
+fn atomic_legal_moves(board: &Board) -> Vec<(Vec<i32>, Vec<i32>, Option<char>)> {
+    let color = board.active_player;
+    let enemy = if color == 'w' { 'b' } else { 'w' };
+    if board.king_square(color).is_none() || board.king_square(enemy).is_none() {
+        return vec![];
+    } // The game is already over
+    let pseudo_legal_moves = flatten_move_map(board, get_available_moves_internal(board.clone(), color, true));
+    pseudo_legal_moves
+        .into_iter()
+        .filter(|(source, target, promotion)| {
+            let is_king = get_piece(board, source).eq_ignore_ascii_case(&'k');
+            if is_king && get_captured_piece(board, source, target).is_some() {
+                return false;
+            } // Kings never capture
+            if let Some(castling) = CastlingMove::from_move(board, source, target)
+                && (atomic_in_check(board, color) || castling.passes_attacked_square(&mut board.clone(), |passing| atomic_in_check(passing, color)))
+            {
+                return false;
+            } // No castling out of or through check
+            let mut test_board = board.clone();
+            atomic_play_move(&mut test_board, source.clone(), target.clone(), *promotion);
+            test_board.king_square(color).is_some()
+                && (test_board.king_square(enemy).is_none() || !atomic_in_check(&test_board, color))
+        })
+        .collect()
+} // A move is legal if it doesn't blow up your own king, and either blows up the enemy king or doesn't leave yours in check
+
+fn atomic_game_status(board: &Board) -> GameStatus {
+    let player = board.active_player;
+    if board.king_square(player).is_none() {
+        return if player == 'w' { GameStatus::BlackWinsByExplosion } else { GameStatus::WhiteWinsByExplosion };
+    }
+    if atomic_legal_moves(board).is_empty() {
+        if !atomic_in_check(board, player) {
+            return GameStatus::Stalemate;
+        } else if player == 'w' {
+            return GameStatus::BlackWinsByCheckmate;
+        } else {
+            return GameStatus::WhiteWinsByCheckmate;
+        }
+    }
+    if board.halfmove_counter >= 100 {
+        return GameStatus::FiftyMoveDraw;
+    }
+    GameStatus::InProgress
+} // Same as get_game_status(), with atomic check, plus the win by blowing up the enemy king
+
+fn racing_legal_moves(board: &Board) -> Vec<(Vec<i32>, Vec<i32>, Option<char>)> {
+    let enemy = if board.active_player == 'w' { 'b' } else { 'w' };
+    get_legal_move_list(board)
+        .into_iter()
+        .filter(|(source, target, promotion)| {
+            let mut test_board = board.clone();
+            test_board.play_move(source.clone(), target.clone(), *promotion);
+            !player_is_in_check(&test_board, enemy)
+        })
+        .collect()
+} // The normal legal moves, minus every move that gives check
+
+fn racing_game_status(board: &Board) -> GameStatus {
+    let on_last_rank = |color: char| board.king_square(color).is_some_and(|square| square.rank().to_char() == '8');
+    match (on_last_rank('w'), on_last_rank('b')) {
+        (true, true) => return GameStatus::RaceDraw,
+        (false, true) => return GameStatus::BlackWinsByRace,
+        (true, false) => {
+            let black_can_equalize = board.active_player == 'b'
+                && racing_legal_moves(board).iter().any(|(source, target, _)| {
+                    get_piece(board, source) == 'k' && target[0] == 0
+                });
+            if !black_can_equalize {
+                return GameStatus::WhiteWinsByRace;
+            }
+        } // White moves first, so Black gets one move to reach the last rank as well
+        (false, false) => {}
+    }
+    if racing_legal_moves(board).is_empty() {
+        return GameStatus::Stalemate; // Nobody can be in check, so there is no checkmate
+    }
+    if board.halfmove_counter >= 100 {
+        return GameStatus::FiftyMoveDraw;
+    }
+    GameStatus::InProgress
+} // Returns the game status (see docs for GameStatus) of a Racing Kings board
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+#[derive(Clone, Debug, Default)]
+/// An enum to represent the rules a Game is played by.
+///
+/// ```text
+/// Standard, // Normal chess.
+/// KingOfTheHill, // Normal chess, but moving your king to d4, d5, e4 or e5 wins the game immediately.
+/// Atomic, // Captures blow up the capturing piece and every piece but pawns around the target square. Blowing up the enemy king wins.
+///         // Kings can't capture, and kings standing next to each other can't be checked.
+/// RacingKings, // No checks are allowed, and the first king to reach the 8th rank wins. If White gets there first,
+///              // Black gets one more move, and reaching the 8th rank with it makes the game a draw.
+/// Custom(Arc<dyn Rules>), // Any other rules (see docs for Rules). Two custom variants are equal if they share the same Arc.
+/// ```
+pub enum Variant {
+    #[default]
+    Standard,
+    KingOfTheHill,
+    Atomic,
+    RacingKings,
+    Custom(Arc<dyn Rules>),
+}
+impl Variant {
+    /// A function to return the rules of the variant (see docs for Rules).
+    pub fn rules(&self) -> &dyn Rules {
+        match self {
+            Variant::Standard => &StandardRules,
+            Variant::KingOfTheHill => &KingOfTheHillRules,
+            Variant::Atomic => &AtomicRules,
+            Variant::RacingKings => &RacingKingsRules,
+            Variant::Custom(rules) => rules.as_ref(),
+        }
+    }
+
+    /// A function to return the FEN of the position the variant starts from.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Variant;
+    /// assert_eq!(Variant::RacingKings.start_fen(), "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+    /// ```
+    pub fn start_fen(&self) -> &str {
+        self.rules().start_fen()
+    }
+
+    /// A function to find a built-in variant by the name its rules give (see docs for Rules::name).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// name: &str, // The name, e.g. "King of the Hill". Case insensitive.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns the variant, or None if no built-in variant has the name. Custom variants are never found.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::Variant;
+    /// assert_eq!(Variant::from_name("atomic"), Some(Variant::Atomic));
+    /// assert_eq!(Variant::from_name(Variant::RacingKings.rules().name()), Some(Variant::RacingKings));
+    /// assert_eq!(Variant::from_name("Crazyhouse"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Variant> {
+        [Variant::Standard, Variant::KingOfTheHill, Variant::Atomic, Variant::RacingKings]
+            .into_iter()
+            .find(|variant| variant.rules().name().eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn checks(&self, board: &Board) -> Vec<bool> {
+        vec![self.rules().is_in_check(board, Color::White), self.rules().is_in_check(board, Color::Black)]
+    } // Same format as check_for_checks()
+
+    pub(crate) fn non_standard_rules(&self) -> Option<&dyn Rules> {
+        match self {
+            Variant::Standard => None,
+            _ => Some(self.rules()),
+        }
+    } // The search and the mate solver use the move generator directly in standard chess, which is much faster than Rules
+}
+impl PartialEq for Variant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Variant::Custom(rules), Variant::Custom(other_rules)) => Arc::ptr_eq(rules, other_rules),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+impl Eq for Variant {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct for the rules of King of the Hill (see docs for Variant).
+pub struct KingOfTheHillRules;
+impl Rules for KingOfTheHillRules {
+    fn name(&self) -> &str {
+        "King of the Hill"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        if hill_winner(board).is_some() {
+            return vec![];
+        }
+        StandardRules.legal_moves(board)
+    }
+
+    fn game_status(&self, board: &Board) -> GameStatus {
+        hill_winner(board).unwrap_or_else(|| StandardRules.game_status(board))
+    } // Reaching the hill is checked before the normal rules, so it wins even if it would otherwise be stalemate or a draw
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct for the rules of Atomic chess (see docs for Variant).
+pub struct AtomicRules;
+impl Rules for AtomicRules {
+    fn name(&self) -> &str {
+        "Atomic"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        move_list(atomic_legal_moves(board))
+    }
+
+    fn play_move(&self, board: &mut Board, chess_move: &Move) {
+        atomic_play_move(
+            board,
+            get_board_coords(chess_move.source.clone()),
+            get_board_coords(chess_move.target.clone()),
+            chess_move.promotion,
+        );
+    }
+
+    fn is_in_check(&self, board: &Board, color: Color) -> bool {
+        atomic_in_check(board, color.to_char())
+    }
+
+    fn game_status(&self, board: &Board) -> GameStatus {
+        atomic_game_status(board)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A struct for the rules of Racing Kings (see docs for Variant).
+pub struct RacingKingsRules;
+impl Rules for RacingKingsRules {
+    fn name(&self) -> &str {
+        "Racing Kings"
+    }
+
+    fn start_fen(&self) -> &str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        if racing_game_status(board).is_game_over() {
+            return vec![];
+        }
+        move_list(racing_legal_moves(board))
+    }
+
+    fn game_status(&self, board: &Board) -> GameStatus {
+        racing_game_status(board)
+    }
+}
+
+/// The four centre squares a king has to reach to win King of the Hill.
+const HILL: [&str; 4] = ["d4", "d5", "e4", "e5"];
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+impl Game {
+    /// A function to create a new Game of a variant from a given FEN. See docs for Game::new_from_fen.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// fen: String, // A valid FEN string.
+    /// variant: Variant, // The rules to play by (see docs for Variant).
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns a Game object. A position that a variant rule has already decided gets the matching status.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, GameStatus, Variant};
+    /// let example_game = Game::new_from_fen_with_variant("4k3/8/8/8/4K3/8/8/8 b - - 0 1".to_string(), Variant::KingOfTheHill);
+    /// assert_eq!(example_game.game_status, GameStatus::WhiteWinsByKingOfTheHill);
+    /// ```
+    pub fn new_from_fen_with_variant(fen: String, variant: Variant) -> Game {
+        let mut game = Game::new_from_fen(fen);
+        game.checks = variant.checks(&game.board);
+        game.fen = variant.rules().extend_fen(&game.board, game.fen);
+        game.start_fen = game.fen.clone();
+        game.variant = variant;
+        game.update_game_status();
+        game
+    }
+
+    /// A function to create a new Game of a variant at the variant's starting position (see docs for Variant::start_fen).
+    ///
+    /// ## Arguments
+    /// ```text
+    /// variant: Variant, // The rules to play by (see docs for Variant).
+    ///
+    /// ```
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::{Game, Variant};
+    /// let example_game = Game::new_with_variant(Variant::KingOfTheHill);
+    /// assert_eq!(example_game.variant, Variant::KingOfTheHill);
+    /// ```
+    pub fn new_with_variant(variant: Variant) -> Game {
+        let fen = variant.start_fen().to_string();
+        Self::new_from_fen_with_variant(fen, variant)
+    }
+}
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Move};
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let fen = "8/8/4k3/b6q/6n1/3K4/8/8 w - - 0 1"; // Black would answer Kd4 with Qd5#
+        let mut standard = Game::new_from_fen(fen.to_string());
+        assert!(standard.make_uci_move("d3d4"));
+        assert!(standard.make_uci_move("h5d5"));
+        assert_eq!(standard.game_status, GameStatus::BlackWinsByCheckmate);
+
+        let mut hill = Game::new_from_fen_with_variant(fen.to_string(), Variant::KingOfTheHill);
+        assert_eq!(hill.game_status, GameStatus::InProgress);
+        assert!(hill.make_uci_move("d3d4"));
+        assert_eq!(hill.game_status, GameStatus::WhiteWinsByKingOfTheHill);
+        assert_eq!(hill.winner(), Some(Color::White));
+        assert_eq!(hill.result_string(), "1-0");
+        assert!(hill.legal_moves().is_empty());
+        assert!(!hill.make_uci_move("h5d5")); // The game is over
+        assert_eq!(hill.variant, Variant::KingOfTheHill);
+    }
+    #[test]
+    fn test_black_reaches_the_hill() {
+        let mut hill = Game::new_from_fen_with_variant("8/8/4k3/8/8/8/8/K7 b - - 0 1".to_string(), Variant::KingOfTheHill);
+        assert!(hill.make_uci_move("e6e5"));
+        assert_eq!(hill.game_status, GameStatus::BlackWinsByKingOfTheHill);
+        assert_eq!(Game::new_with_variant(Variant::KingOfTheHill).legal_moves().len(), 20);
+    }
+    #[test]
+    fn test_atomic_knight_trap() {
+        let mut atomic = Game::new_with_variant(Variant::Atomic);
+        for uci_move in ["g1f3", "a7a6", "f3g5", "a6a5"] {
+            assert!(atomic.make_uci_move(uci_move));
+        }
+        assert!(atomic.make_uci_move("g5f7")); // 3. Nxf7 blows up the king on e8
+        assert_eq!(atomic.fen, "rnbq3r/1pppp1pp/8/p7/8/8/PPPPPPPP/RNBQKB1R b KQ - 0 3");
+        assert_eq!(atomic.game_status, GameStatus::WhiteWinsByExplosion);
+        assert_eq!(atomic.result_string(), "1-0");
+        assert!(atomic.legal_moves().is_empty());
+    }
+    #[test]
+    fn test_atomic_defending_the_trap() {
+        let mut atomic = Game::new_with_variant(Variant::Atomic);
+        for uci_move in ["g1f3", "e7e6", "f3g5"] {
+            assert!(atomic.make_uci_move(uci_move));
+        }
+        assert!(atomic.make_uci_move("d8g5")); // Taking the knight blows up the queen too, but saves the king
+        assert_eq!(atomic.fen, "rnb1kbnr/pppp1ppp/4p3/8/8/8/PPPPPPPP/RNBQKB1R w KQkq - 0 3");
+        assert_eq!(atomic.game_status, GameStatus::InProgress);
+    }
+    #[test]
+    fn test_atomic_king_rules() {
+        let atomic = |fen: &str| Game::new_from_fen_with_variant(fen.to_string(), Variant::Atomic);
+        let no_king_captures = atomic("4k3/8/8/8/8/8/3n4/4K3 w - - 0 1");
+        assert!(!no_king_captures.legal_moves().contains(&Move::from_uci("e1d2").unwrap()));
+        let adjacent_kings = atomic("8/8/8/8/8/8/3kK3/7r w - - 0 1"); // The rook can't take the king without blowing up its own
+        assert_eq!(adjacent_kings.checks, vec![false, false]);
+        assert_eq!(adjacent_kings.game_status, GameStatus::InProgress);
+        let own_king_nearby = atomic("4k3/8/8/8/8/8/3p4/2Q1K3 w - - 0 1"); // Qxd2 would blow up the white king
+        assert!(!own_king_nearby.legal_moves().contains(&Move::from_uci("c1d2").unwrap()));
+        assert_eq!(own_king_nearby.legal_moves().len(), 4); // Kd1, Ke2, Kf1 and Kf2, since Kxd2 is a king capture
+    }
+    #[test]
+    fn test_racing_kings_equalizing_draw() {
+        let racing = |fen: &str| Game::new_from_fen_with_variant(fen.to_string(), Variant::RacingKings);
+        let mut equalized = racing("8/1k4K1/8/8/8/8/8/8 w - - 0 1");
+        assert!(equalized.make_uci_move("g7g8"));
+        assert_eq!(equalized.game_status, GameStatus::InProgress); // Black may still equalize
+        assert!(equalized.make_uci_move("b7b8"));
+        assert_eq!(equalized.game_status, GameStatus::RaceDraw);
+        assert_eq!(equalized.result_string(), "1/2-1/2");
+
+        let mut missed = racing("8/1k4K1/8/8/8/8/8/8 w - - 0 1");
+        assert!(missed.make_uci_move("g7g8"));
+        assert!(missed.make_uci_move("b7b6"));
+        assert_eq!(missed.game_status, GameStatus::WhiteWinsByRace);
+
+        let mut too_far = racing("8/6K1/1k6/8/8/8/8/8 w - - 0 1");
+        assert!(too_far.make_uci_move("g7g8"));
+        assert_eq!(too_far.game_status, GameStatus::WhiteWinsByRace); // Black can't reach the 8th rank in one move
+        assert!(too_far.legal_moves().is_empty());
+    }
+    #[test]
+    fn test_racing_kings_no_checks() {
+        let racing = Game::new_from_fen_with_variant("8/8/8/8/k7/8/8/4Q2K w - - 0 1".to_string(), Variant::RacingKings);
+        let standard = Game::new_from_fen("8/8/8/8/k7/8/8/4Q2K w - - 0 1".to_string());
+        let checking_move = Move::from_uci("e1e4").unwrap(); // Qe4+ is illegal, since it gives check
+        assert!(standard.legal_moves().contains(&checking_move));
+        assert!(!racing.legal_moves().contains(&checking_move));
+        assert!(racing.legal_moves().contains(&Move::from_uci("e1e2").unwrap()));
+        assert_eq!(Game::new_with_variant(Variant::RacingKings).legal_moves().len(), 21);
+    }
+}
@@ -0,0 +1,282 @@
+/*****************************
+*  XBOARD ENGINE FRONT-END   *
+*  AUTHOR: alviny            *
+*****************************/
+
+/*!
+A front-end for the Chess Engine Communication Protocol (CECP, or "xboard"), for older GUIs and tournament managers
+that don't speak UCI. Read more about the protocol here:
+<https://www.gnu.org/software/xboard/engine-intf.html>
+
+Unlike UCI, where the GUI sends the whole position before every search, an xboard engine follows the game move by
+move and decides for itself when to think: it plays one side, and answers every move of the other side with a move
+of its own, unless it has been put in force mode. The search, the options and the transposition table are the same
+as for UCI (see docs for EngineSession).
+
+All moves are read and written in coordinate notation ("e2e4", "e7e8q"). Castling is the king's two-square move.
+*/
+
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+use crate::engine::{EngineSession, DEFAULT_MOVETIME};
+use crate::search::{SearchInfo, SearchOptions};
+use crate::{Color, Game, GameStatus, Move};
+
+/*****************************
+*  PUBLIC STRUCTS            *
+*  BEGIN HERE                *
+*****************************/
+
+/// A struct to represent an xboard engine session.
+///
+/// ## Attributes
+/// ```text
+/// session: EngineSession, // The game and the search settings (see docs for EngineSession).
+/// engine_color: Color, // The side the engine plays. Black after "new", the side to move after "go".
+/// force_mode: bool, // Set by "force" and "result". The engine only records the moves, and plays neither side.
+/// time_left: Option<Duration>, // The engine's clock, as given by the last "time" command.
+/// opponent_time_left: Option<Duration>, // The opponent's clock, as given by the last "otim" command.
+/// ```
+pub struct XBoardEngine {
+    pub session: EngineSession,
+    pub engine_color: Color,
+    pub force_mode: bool,
+    pub time_left: Option<Duration>,
+    pub opponent_time_left: Option<Duration>,
+}
+impl XBoardEngine {
+    /// A function to create a new engine session at the starting position, with the engine playing Black.
+    pub fn new() -> XBoardEngine {
+        XBoardEngine {
+            session: EngineSession::new(),
+            engine_color: Color::Black,
+            force_mode: false,
+            time_left: None,
+            opponent_time_left: None,
+        }
+    }
+
+    /// A function to handle a single line of xboard input.
+    ///
+    /// ## Arguments
+    /// ```text
+    /// command: &str, // One line sent by the GUI, e.g. "usermove e2e4" or "time 6000".
+    /// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+    ///
+    /// ```
+    /// ## Returns
+    /// This function returns false if the command was "quit", true otherwise. Unknown commands are answered with an
+    /// error, as the protocol asks.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use alviny_task_3::xboard::XBoardEngine;
+    /// let mut engine = XBoardEngine::new();
+    /// let mut output = vec![];
+    /// engine.handle_command("ping 7", &mut output);
+    /// assert_eq!(String::from_utf8(output).unwrap(), "pong 7\n");
+    /// ```
+    pub fn handle_command(&mut self, command: &str, output: &mut impl Write) -> bool {
+        let tokens = command.split_whitespace().collect::<Vec<&str>>();
+        let argument = tokens.get(1).copied().unwrap_or_default();
+        match tokens.first().copied() {
+            None | Some("xboard") | Some("accepted") | Some("rejected") | Some("random") | Some("post") | Some("nopost")
+            | Some("hard") | Some("easy") | Some("computer") => {}
+            Some("protover") => {
+                if argument.parse::<u32>().is_ok_and(|version| version >= 2) {
+                    writeln!(
+                        output,
+                        "feature myname=\"alviny-task-3\" ping=1 setboard=1 usermove=1 memory=1 sigint=0 sigterm=0 colors=0 analyze=0 done=1"
+                    )
+                    .ok();
+                }
+            }
+            Some("new") => {
+                self.session.new_game();
+                self.engine_color = Color::Black;
+                self.force_mode = false;
+                self.time_left = None;
+                self.opponent_time_left = None;
+            }
+            Some("setboard") => match Game::try_from_fen(&tokens[1..].join(" ")) {
+                Ok(game) => self.session.game = game,
+                Err(fen_error) => {
+                    writeln!(output, "tellusererror Illegal position: {}", fen_error).ok();
+                }
+            },
+            Some("force") | Some("result") => self.force_mode = true,
+            Some("go") => {
+                self.force_mode = false;
+                self.engine_color = side_to_move(&self.session.game);
+                self.play(output);
+            }
+            Some("time") => self.time_left = parse_centiseconds(argument),
+            Some("otim") => self.opponent_time_left = parse_centiseconds(argument),
+            Some("memory") => {
+                if let Ok(megabytes) = argument.parse::<usize>() {
+                    self.session.set_hash_size(megabytes);
+                }
+            }
+            Some("ping") => {
+                writeln!(output, "pong {}", argument).ok();
+            }
+            Some("usermove") => self.user_move(argument, output),
+            Some("quit") => return false,
+            Some(move_text) if Move::from_uci(move_text).is_some() => self.user_move(move_text, output),
+            Some(_) => {
+                writeln!(output, "Error (unknown command): {}", command.trim()).ok();
+            }
+        }
+        output.flush().ok();
+        true
+    }
+
+    fn user_move(&mut self, move_text: &str, output: &mut impl Write) {
+        if !self.session.game.make_uci_move(move_text) {
+            writeln!(output, "Illegal move: {}", move_text).ok();
+            return;
+        }
+        if !self.force_mode && side_to_move(&self.session.game) == self.engine_color {
+            self.play(output);
+        }
+    } // Records the opponent's move, and answers it unless the engine is in force mode.
+
+    fn play(&mut self, output: &mut impl Write) {
+        if !self.session.game.is_game_over() {
+            let time_limit = match self.time_left {
+                Some(time_left) => self.session.time_for_move(time_left),
+                None => self.session.time_limit(DEFAULT_MOVETIME),
+            };
+            let options = SearchOptions { time_limit: Some(time_limit), multipv: 1, ..self.session.search_options.clone() };
+            let result = self.session.think(&options, &mut |_info: &SearchInfo| {});
+            if let Some(best_move) = result.best_move {
+                self.session.game.make_uci_move(&best_move.to_uci());
+                writeln!(output, "move {}", best_move).ok();
+            }
+        }
+        if self.session.game.is_game_over() {
+            writeln!(output, "{} {{{}}}", self.session.game.result_string(), result_comment(self.session.game.game_status)).ok();
+        }
+    } // Thinks about the position and plays the best move. Tells the GUI the result if the game is over.
+}
+impl Default for XBoardEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*****************************
+*  PUBLIC FUNCTIONS          *
+*  BEGIN HERE                *
+*****************************/
+
+/// A function to run the xboard protocol loop until "quit" is received or the input ends.
+///
+/// ## Arguments
+/// ```text
+/// input: impl BufRead, // The commands from the GUI. Usually stdin.
+/// output: &mut impl Write, // Where to write the engine's responses. Usually stdout.
+///
+/// ```
+/// ## Example
+///
+/// ```
+/// # use alviny_task_3::xboard;
+/// let mut output = vec![];
+/// xboard::run("xboard\nprotover 2\nquit\n".as_bytes(), &mut output);
+/// assert!(String::from_utf8(output).unwrap().ends_with("done=1\n"));
+/// ```
+pub fn run(input: impl BufRead, output: &mut impl Write) {
+    let mut engine = XBoardEngine::new();
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if !engine.handle_command(&line, output) {
+            break;
+        }
+    }
+}
+
+/*****************************
+*   PRIVATE HELPER FUNCTIONS *
+*   BEGIN HERE               *
+*****************************/
+
+fn side_to_move(game: &Game) -> Color {
+    Color::from_char(game.board.active_player).unwrap_or(Color::White)
+} // The color whose turn it is.
+
+fn parse_centiseconds(argument: &str) -> Option<Duration> {
+    argument.parse::<u64>().ok().map(|centiseconds| Duration::from_millis(centiseconds * 10))
+} // Reads the clock of a "time" or "otim" command, which counts hundredths of a second.
+
+fn result_comment(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::WhiteWinsByCheckmate => "White mates",
+        GameStatus::BlackWinsByCheckmate => "Black mates",
+        GameStatus::Stalemate => "Stalemate",
+        GameStatus::FiftyMoveDraw => "50 move rule",
+        _ => "Game over",
+    }
+} // The reason given after the result, e.g. "1-0 {White mates}".
+
+/*****************************
+*         UNIT TESTS         *
+*         BEGIN HERE         *
+*****************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_transcript(transcript: &str) -> String {
+        let mut output = vec![];
+        run(transcript.as_bytes(), &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_force_mode_then_go() {
+        let output = run_transcript("xboard\nprotover 2\nnew\nforce\ne2e4\nusermove e7e5\ng1f3\ntime 3000\notim 2900\ngo\nquit\n");
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2); // No replies while in force mode
+        assert!(lines[0].starts_with("feature myname=\"alviny-task-3\" "));
+        let engine_move = lines[1].strip_prefix("move ").unwrap();
+        let mut game = Game::from_uci_moves("e2e4 e7e5 g1f3").unwrap();
+        assert!(game.make_uci_move(engine_move)); // The engine played Black's move
+    }
+    #[test]
+    fn test_engine_answers_moves() {
+        let mut engine = XBoardEngine::new();
+        let mut output = vec![];
+        for command in ["new", "time 500", "otim 500", "usermove e2e4"] {
+            engine.handle_command(command, &mut output);
+        }
+        assert_eq!(engine.time_left, Some(Duration::from_secs(5)));
+        assert_eq!(engine.opponent_time_left, Some(Duration::from_secs(5)));
+        assert_eq!(engine.session.game.history.len(), 2); // e2e4 and the engine's reply
+        assert!(String::from_utf8(output).unwrap().starts_with("move "));
+
+        let mut output = vec![];
+        engine.handle_command("usermove e2e4", &mut output);
+        assert_eq!(String::from_utf8(output).unwrap(), "Illegal move: e2e4\n");
+        let mut output = vec![];
+        engine.handle_command("result 1/2-1/2 {Draw by agreement}", &mut output);
+        assert!(engine.force_mode);
+        engine.handle_command("d2d3", &mut output); // Recorded without a reply
+        assert_eq!(engine.session.game.history.len(), 3);
+        engine.handle_command("frobnicate", &mut output);
+        assert_eq!(String::from_utf8(output).unwrap(), "Error (unknown command): frobnicate\n");
+    }
+    #[test]
+    fn test_setboard_and_mate() {
+        let output = run_transcript("new\nforce\nsetboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo\nquit\n");
+        assert_eq!(output, "move a1a8\n1-0 {White mates}\n");
+        let output = run_transcript("setboard 8/8/8\nquit\n");
+        assert!(output.starts_with("tellusererror Illegal position: "));
+    }
+}
@@ -0,0 +1,37 @@
+#!/bin/sh
+# A stand-in for a real UCI engine, used by the tests of the uci_client module so that they don't need Stockfish.
+# It gives the same canned analysis for every position, except:
+#   - a position with moves ending in d8h4 (fool's mate) is checkmate, so there is no best move,
+#   - "go depth 99" never finishes, and from then on the engine ignores everything, even "stop" and "quit".
+position=""
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name MockFish 1.0"
+            echo "id author alviny"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "uciok"
+            ;;
+        isready) echo "readyok" ;;
+        position*) position="$line" ;;
+        "go depth 99"*)
+            while true; do sleep 1; done
+            ;;
+        go*)
+            case "$position" in
+                *d8h4)
+                    echo "info depth 0 score mate 0"
+                    echo "bestmove (none)"
+                    ;;
+                *)
+                    echo "info string canned analysis"
+                    echo "info depth 1 seldepth 1 multipv 1 score cp 20 nodes 20 nps 20000 time 1 pv e2e4"
+                    echo "info depth 2 seldepth 3 multipv 1 score cp 35 nodes 80 nps 40000 time 2 pv e2e4 e7e5"
+                    echo "info depth 2 seldepth 3 multipv 2 score cp 30 nodes 80 nps 40000 time 2 pv d2d4 d7d5"
+                    echo "bestmove e2e4 ponder e7e5"
+                    ;;
+            esac
+            ;;
+        quit) exit 0 ;;
+    esac
+done